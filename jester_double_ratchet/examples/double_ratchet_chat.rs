@@ -0,0 +1,316 @@
+//! Two parties chatting in-process through the double ratchet.
+//!
+//! ```text
+//! cargo run --example double_ratchet_chat --features hash_kdf
+//! ```
+//!
+//! Messages travel as encoded bytes over a channel that holds some of them back until the end of the conversation.
+//! Later messages still decrypt, because the receiver skips the message keys of the missing messages, and the
+//! held-back messages are decrypted from the skipped keys when they finally arrive.
+
+use std::fmt;
+
+use jester_double_ratchet::chain_key::ChainKey;
+use jester_double_ratchet::cipher_suite::{
+    AeadAlgorithm, CipherSuite, DhAlgorithm, HashAlgorithm, KdfAlgorithm,
+};
+use jester_double_ratchet::hash_kdf::DhRootKdf;
+use jester_double_ratchet::message_encryption::GcmMessageEncryption;
+use jester_double_ratchet::padding::PaddingScheme;
+use jester_double_ratchet::state::{Established, Initiator};
+use jester_double_ratchet::{
+    DecryptionException, DoubleRatchetAlgorithmMessage, DoubleRatchetProtocol,
+};
+use jester_encryption::aes::Aes256;
+use jester_encryption::diffie_hellman::DhGroup;
+use jester_hashes::blake::blake2b::Blake2b;
+use jester_hashes::kdf::HashKdfChain;
+use jester_maths::prime::IetfGroup3;
+use rand::{thread_rng, CryptoRng, RngCore};
+
+/// The primitives of the chat, which are exactly the primitives the suite names
+const SUITE: CipherSuite = CipherSuite::new(
+    DhAlgorithm::IetfGroup3,
+    AeadAlgorithm::Aes256Gcm,
+    KdfAlgorithm::HmacChain,
+    HashAlgorithm::Blake2b,
+);
+
+/// Hides the exact length of the lines, and makes tampered messages fail to unpad
+const PADDING: PaddingScheme = PaddingScheme::PadToMultiple(32);
+
+/// The root chain key both parties agreed on before the chat, e.g. with an authenticated key exchange
+const SHARED_SECRET: &[u8] = b"agreed upon out of band";
+
+type Chat<State> = DoubleRatchetProtocol<
+    IetfGroup3,
+    GcmMessageEncryption<Blake2b, Aes256>,
+    DhRootKdf<Blake2b, IetfGroup3>,
+    HashKdfChain<Blake2b>,
+    IetfGroup3,
+    IetfGroup3,
+    IetfGroup3,
+    ChainKey,
+    ChainKey,
+    ChainKey,
+    State,
+>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Party {
+    Alice,
+    Bob,
+}
+
+impl fmt::Display for Party {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Party::Alice => write!(f, "alice"),
+            Party::Bob => write!(f, "bob"),
+        }
+    }
+}
+
+/// A line typed by one of the parties
+struct Line {
+    from: Party,
+    text: &'static str,
+    /// Whether the channel holds the message back until the end of the conversation
+    delayed: bool,
+}
+
+#[rustfmt::skip]
+const SCRIPT: &[Line] = &[
+    Line { from: Party::Alice, text: "hi bob, got a minute?", delayed: false },
+    Line { from: Party::Bob, text: "sure, what's up?", delayed: false },
+    Line { from: Party::Alice, text: "the meeting moved to thursday", delayed: true },
+    Line { from: Party::Alice, text: "same room as last time", delayed: true },
+    Line { from: Party::Alice, text: "can you bring the slides?", delayed: false },
+    Line { from: Party::Bob, text: "which meeting?", delayed: false },
+    Line { from: Party::Bob, text: "ah, the review. yes, I'll bring them", delayed: true },
+    Line { from: Party::Alice, text: "great, thanks!", delayed: false },
+    Line { from: Party::Bob, text: "see you on thursday", delayed: false },
+];
+
+/// A line as it was received
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Delivery {
+    to: Party,
+    text: String,
+    /// Whether the line was decrypted from a skipped message key, because later lines arrived before it
+    late: bool,
+}
+
+/// Start a chat. Alice initiates the session, and Bob's greeting completes it.
+///
+/// # Returns
+/// The sessions of Alice and Bob and Bob's greeting as received by Alice
+fn establish<R>(rng: &mut R) -> (Chat<Established>, Chat<Established>, String)
+where
+    R: RngCore + CryptoRng,
+{
+    let generator = IetfGroup3::subgroup_generator().unwrap();
+
+    let (mut alice, hello) = Chat::<Initiator>::initialize_sending(
+        rng,
+        generator.clone(),
+        ChainKey::new(SHARED_SECRET.to_vec()),
+        SUITE,
+    );
+    // the first message of the initiator only carries its public key
+    let hello =
+        DoubleRatchetAlgorithmMessage::decode::<IetfGroup3>(&hello.encode::<IetfGroup3>()).unwrap();
+    let mut bob = Chat::<Established>::initialize_receiving(
+        rng,
+        generator,
+        hello.public_key().clone(),
+        ChainKey::new(SHARED_SECRET.to_vec()),
+        SUITE,
+    )
+    .unwrap();
+    alice.set_padding_scheme(PADDING);
+    bob.set_padding_scheme(PADDING);
+
+    let greeting = bob
+        .encrypt_message(b"bob joined the chat")
+        .encode::<IetfGroup3>();
+    let message = DoubleRatchetAlgorithmMessage::decode::<IetfGroup3>(&greeting).unwrap();
+    let (alice, greeting) = alice.decrypt_first_message(message).unwrap();
+
+    (alice, bob, String::from_utf8(greeting).unwrap())
+}
+
+/// Decode and decrypt a message received by `party`.
+///
+/// # Returns
+/// The line and whether it was decrypted from a skipped message key, or a description of why it was rejected
+fn receive(party: &mut Chat<Established>, bytes: &[u8]) -> Result<(String, bool), String> {
+    let message = DoubleRatchetAlgorithmMessage::decode::<IetfGroup3>(bytes)
+        .map_err(|exception| format!("malformed message: {:?}", exception))?;
+
+    let (clear_text, late) = match party.decrypt_message(message) {
+        Ok(clear_text) => (clear_text, false),
+        Err(DecryptionException::OutOfOrderMessage { decrypted_message }) => {
+            (decrypted_message, true)
+        }
+        Err(exception) => return Err(format!("rejected message: {:?}", exception)),
+    };
+
+    String::from_utf8(clear_text)
+        .map(|text| (text, late))
+        .map_err(|_| "the line is no valid UTF-8".to_string())
+}
+
+/// Run the chat of `script` over a channel that holds back the delayed lines until all other lines were delivered.
+///
+/// # Returns
+/// The lines in the order they were received
+fn run_chat<R>(rng: &mut R, script: &[Line]) -> Vec<Delivery>
+where
+    R: RngCore + CryptoRng,
+{
+    let (mut alice, mut bob, _) = establish(rng);
+
+    let mut deliveries = Vec::with_capacity(script.len());
+    let mut held_back = Vec::new();
+    for line in script {
+        let (sender, receiver, to) = match line.from {
+            Party::Alice => (&mut alice, &mut bob, Party::Bob),
+            Party::Bob => (&mut bob, &mut alice, Party::Alice),
+        };
+
+        let bytes = sender
+            .encrypt_message(line.text.as_bytes())
+            .encode::<IetfGroup3>();
+        if line.delayed {
+            held_back.push((to, bytes));
+            continue;
+        }
+
+        let (text, late) = receive(receiver, &bytes).unwrap();
+        deliveries.push(Delivery { to, text, late });
+    }
+
+    for (to, bytes) in held_back {
+        let receiver = match to {
+            Party::Alice => &mut alice,
+            Party::Bob => &mut bob,
+        };
+        let (text, late) = receive(receiver, &bytes).unwrap();
+        deliveries.push(Delivery { to, text, late });
+    }
+
+    deliveries
+}
+
+fn main() {
+    let mut rng = thread_rng();
+
+    for line in SCRIPT {
+        let channel = if line.delayed {
+            " (held back by the channel)"
+        } else {
+            ""
+        };
+        println!("{} types: {}{}", line.from, line.text, channel);
+    }
+    println!();
+
+    for delivery in run_chat(&mut rng, SCRIPT) {
+        let late = if delivery.late {
+            " (late, decrypted from a skipped key)"
+        } else {
+            ""
+        };
+        println!("{} reads: {}{}", delivery.to, delivery.text, late);
+    }
+    println!();
+
+    // a tampered message fails authentication and is rejected
+    let (mut alice, mut bob, _) = establish(&mut rng);
+    let mut bytes = alice
+        .encrypt_message(b"transfer 10 coins")
+        .encode::<IetfGroup3>();
+    *bytes.last_mut().unwrap() ^= 1;
+    match receive(&mut bob, &bytes) {
+        Ok((text, _)) => println!("bob reads a tampered message: {}", text),
+        Err(reason) => println!("bob drops a tampered message: {}", reason),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_establish() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let (mut alice, mut bob, greeting) = establish(&mut rng);
+        assert_eq!(greeting, "bob joined the chat");
+
+        let bytes = alice.encrypt_message(b"hello").encode::<IetfGroup3>();
+        assert_eq!(receive(&mut bob, &bytes), Ok(("hello".to_string(), false)));
+    }
+
+    #[test]
+    fn test_every_line_delivered_once() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let deliveries = run_chat(&mut rng, SCRIPT);
+        assert_eq!(deliveries.len(), SCRIPT.len());
+
+        for line in SCRIPT {
+            let to = if line.from == Party::Alice {
+                Party::Bob
+            } else {
+                Party::Alice
+            };
+            let delivery = Delivery {
+                to,
+                text: line.text.to_string(),
+                late: line.delayed,
+            };
+            assert_eq!(
+                deliveries
+                    .iter()
+                    .filter(|received| **received == delivery)
+                    .count(),
+                1
+            );
+        }
+
+        // the held back lines are received last, in the order they were sent
+        let late = deliveries
+            .iter()
+            .skip_while(|delivery| !delivery.late)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            late.len(),
+            SCRIPT.iter().filter(|line| line.delayed).count()
+        );
+        assert_eq!(late[0].text, "the meeting moved to thursday");
+    }
+
+    #[test]
+    fn test_tampered_and_replayed_messages() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let (mut alice, mut bob, _) = establish(&mut rng);
+
+        let bytes = alice
+            .encrypt_message(b"transfer 10 coins")
+            .encode::<IetfGroup3>();
+        assert_eq!(
+            receive(&mut bob, &bytes),
+            Ok(("transfer 10 coins".to_string(), false))
+        );
+        assert!(receive(&mut bob, &bytes).is_err());
+
+        let mut tampered = alice
+            .encrypt_message(b"transfer 99 coins")
+            .encode::<IetfGroup3>();
+        assert!(receive(&mut bob, &tampered[..tampered.len() / 2]).is_err());
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(receive(&mut bob, &tampered).is_err());
+    }
+}
@@ -0,0 +1,109 @@
+//! A container for the secret keys of the key derivation chains. Chain keys are consumed by every derivation step, so
+//! the container cannot be cloned implicitly, and its memory is overwritten with zeros when it is dropped.
+
+use jester_util::Redacted;
+use zeroize::Zeroize;
+
+/// The secret bytes of a chain key or a key derived from it. It does not implement `Clone`, so an old chain key
+/// cannot accidentally outlive the derivation step that consumes it, and its `Debug` output does not contain the
+/// key. The bytes are zeroed when the key is dropped.
+#[derive(Debug)]
+pub struct ChainKey(Redacted<Vec<u8>>);
+
+impl ChainKey {
+    /// Take ownership of the secret `bytes`.
+    pub fn new(bytes: Vec<u8>) -> Self {
+        ChainKey(Redacted::new(bytes))
+    }
+
+    /// The secret bytes of the key.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Create a copy of the key. Every copy of a chain key weakens the forward secrecy of the protocol, so this is
+    /// only meant for storing the key in a backup of the session state, which must be protected like the key itself.
+    pub fn dangerous_clone_for_backup(&self) -> Self {
+        ChainKey(self.0.clone())
+    }
+}
+
+/// Keys whose secret bytes can be used as input keying material, for example to export keys bound to the root chain
+/// of a protocol with `key_export`.
+pub trait KeyMaterial {
+    /// The secret bytes of the key.
+    fn key_material(&self) -> &[u8];
+}
+
+impl KeyMaterial for ChainKey {
+    fn key_material(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+/// Keys that can be created from secret bytes, for example to start a root chain from a resumption secret with
+/// `resumption`.
+pub trait FromKeyMaterial {
+    /// Create a key from the secret `bytes`.
+    fn from_key_material(bytes: Vec<u8>) -> Self;
+}
+
+impl FromKeyMaterial for ChainKey {
+    fn from_key_material(bytes: Vec<u8>) -> Self {
+        ChainKey::new(bytes)
+    }
+}
+
+/// Keys of which the protocol keeps a copy while it decrypts a message, so a message that fails to decrypt leaves the
+/// chains unchanged. The copy is dropped as soon as the message is decrypted or rejected. Keys implementing `Clone`
+/// are copied with `clone`.
+pub trait RollbackKey {
+    /// A copy of the key, which replaces the key if the derivation steps of a message are rolled back.
+    fn rollback_copy(&self) -> Self;
+}
+
+impl<K> RollbackKey for K
+where
+    K: Clone,
+{
+    fn rollback_copy(&self) -> Self {
+        self.clone()
+    }
+}
+
+impl RollbackKey for ChainKey {
+    fn rollback_copy(&self) -> Self {
+        self.dangerous_clone_for_backup()
+    }
+}
+
+impl Drop for ChainKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_redacted() {
+        let key = ChainKey::new(b"secret chain key".to_vec());
+
+        assert_eq!(format!("{:?}", key), "ChainKey(<redacted>)");
+        assert_eq!(
+            format!("{:#?}", Some(key)),
+            "Some(\n    ChainKey(\n        <redacted>,\n    ),\n)"
+        );
+    }
+
+    #[test]
+    fn test_backup_clone() {
+        let key = ChainKey::new(b"secret chain key".to_vec());
+        let backup = key.dangerous_clone_for_backup();
+        drop(key);
+
+        assert_eq!(backup.as_bytes(), b"secret chain key");
+    }
+}
@@ -0,0 +1,370 @@
+//! Identifiers of the primitives a session of the `DoubleRatchetProtocol` is run with. Both parties must agree on the
+//! Diffie-Hellman group, the encryption scheme, the key derivation function and the hash function before a session is
+//! initialized. Every message carries the id of the session's `CipherSuite`, so a message of a session with different
+//! primitives is rejected instead of being decrypted with the wrong keys.
+//!
+//! The suite is only a label: the protocol cannot check that its type parameters are the primitives named by the
+//! suite, so applications must pass the suite that matches the primitives they instantiate the protocol with.
+//!
+//! Parties agree on a suite with `CipherSuite::negotiate`, which only selects suites whose primitives are compiled
+//! into this build, see `crate::capabilities`.
+
+use crate::{capabilities, Capabilities};
+
+/// The Diffie-Hellman groups of the ratchet. The groups are the prime field groups defined in RFC 5114.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DhAlgorithm {
+    /// `IetfGroup1`, the 1024 bit group with a 160 bit prime order subgroup
+    IetfGroup1 = 1,
+
+    /// `IetfGroup2`, the 2048 bit group with a 224 bit prime order subgroup
+    IetfGroup2 = 2,
+
+    /// `IetfGroup3`, the 2048 bit group with a 256 bit prime order subgroup
+    IetfGroup3 = 3,
+}
+
+/// The encryption schemes for messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AeadAlgorithm {
+    /// AES-128 in Galois/Counter Mode
+    Aes128Gcm = 1,
+
+    /// AES-256 in Galois/Counter Mode
+    Aes256Gcm = 2,
+}
+
+impl AeadAlgorithm {
+    /// Whether the encryption scheme is compiled into `jester_encryption`
+    fn is_available(self) -> bool {
+        let capability = match self {
+            AeadAlgorithm::Aes128Gcm => jester_encryption::Capabilities::AES128_GCM,
+            AeadAlgorithm::Aes256Gcm => jester_encryption::Capabilities::AES256_GCM,
+        };
+        jester_encryption::capabilities().contains(capability)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            AeadAlgorithm::Aes128Gcm => "aes128-gcm",
+            AeadAlgorithm::Aes256Gcm => "aes256-gcm",
+        }
+    }
+}
+
+/// The key derivation functions of the root chain and the message chains
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KdfAlgorithm {
+    /// The HMAC based `HashKdfChain` of `jester_hashes`
+    HmacChain = 1,
+}
+
+impl KdfAlgorithm {
+    /// Whether the key derivation function is compiled into this crate
+    fn is_available(self) -> bool {
+        match self {
+            KdfAlgorithm::HmacChain => capabilities().contains(Capabilities::HASH_KDF),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            KdfAlgorithm::HmacChain => "hmac-chain",
+        }
+    }
+}
+
+/// The hash functions used by the key derivation function
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashAlgorithm {
+    Md5 = 1,
+    Sha1 = 2,
+    Sm3 = 3,
+    Blake2b = 4,
+    Blake2s = 5,
+    Whirlpool = 6,
+}
+
+impl HashAlgorithm {
+    /// Whether the hash function is compiled into `jester_hashes`. The key derivation functions only use
+    /// `jester_hashes` with the `hash_kdf` feature, so without it, no hash function is available.
+    #[cfg(feature = "hash_kdf")]
+    fn is_available(self) -> bool {
+        use jester_hashes::Capabilities as HashCapabilities;
+
+        let capability = match self {
+            HashAlgorithm::Md5 => HashCapabilities::MD5,
+            HashAlgorithm::Sha1 => HashCapabilities::SHA1,
+            HashAlgorithm::Sm3 => HashCapabilities::SM3,
+            HashAlgorithm::Blake2b => HashCapabilities::BLAKE2B,
+            HashAlgorithm::Blake2s => HashCapabilities::BLAKE2S,
+            HashAlgorithm::Whirlpool => HashCapabilities::WHIRLPOOL,
+        };
+        jester_hashes::capabilities().contains(capability)
+    }
+
+    #[cfg(not(feature = "hash_kdf"))]
+    fn is_available(self) -> bool {
+        false
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Sha1 => "sha1",
+            HashAlgorithm::Sm3 => "sm3",
+            HashAlgorithm::Blake2b => "blake2b",
+            HashAlgorithm::Blake2s => "blake2s",
+            HashAlgorithm::Whirlpool => "whirlpool",
+        }
+    }
+}
+
+/// The primitives of a session. A suite is identified by a two byte id, which holds the ids of the Diffie-Hellman
+/// group, the encryption scheme, the key derivation function and the hash function in its four nibbles, starting
+/// with the most significant one. No primitive has the id zero, so zero is never a valid suite id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CipherSuite {
+    pub dh: DhAlgorithm,
+    pub aead: AeadAlgorithm,
+    pub kdf: KdfAlgorithm,
+    pub hash: HashAlgorithm,
+}
+
+impl CipherSuite {
+    /// Describe a suite of the given primitives.
+    pub const fn new(
+        dh: DhAlgorithm,
+        aead: AeadAlgorithm,
+        kdf: KdfAlgorithm,
+        hash: HashAlgorithm,
+    ) -> Self {
+        CipherSuite {
+            dh,
+            aead,
+            kdf,
+            hash,
+        }
+    }
+
+    /// The two byte id of the suite, which is sent with every message.
+    pub fn to_id(&self) -> u16 {
+        (self.dh as u16) << 12 | (self.aead as u16) << 8 | (self.kdf as u16) << 4 | self.hash as u16
+    }
+
+    /// Look up the suite of an id created by `to_id`.
+    ///
+    /// # Returns
+    /// The suite or `None` if any of the nibbles of `id` is not the id of a known primitive.
+    pub fn from_id(id: u16) -> Option<Self> {
+        let nibble = |index: u16| (id >> (4 * index)) & 0xF;
+
+        let dh = match nibble(3) {
+            1 => DhAlgorithm::IetfGroup1,
+            2 => DhAlgorithm::IetfGroup2,
+            3 => DhAlgorithm::IetfGroup3,
+            _ => return None,
+        };
+        let aead = match nibble(2) {
+            1 => AeadAlgorithm::Aes128Gcm,
+            2 => AeadAlgorithm::Aes256Gcm,
+            _ => return None,
+        };
+        let kdf = match nibble(1) {
+            1 => KdfAlgorithm::HmacChain,
+            _ => return None,
+        };
+        let hash = match nibble(0) {
+            1 => HashAlgorithm::Md5,
+            2 => HashAlgorithm::Sha1,
+            3 => HashAlgorithm::Sm3,
+            4 => HashAlgorithm::Blake2b,
+            5 => HashAlgorithm::Blake2s,
+            6 => HashAlgorithm::Whirlpool,
+            _ => return None,
+        };
+
+        Some(CipherSuite::new(dh, aead, kdf, hash))
+    }
+
+    /// Check that all primitives of the suite are compiled into this build. All Diffie-Hellman groups are always
+    /// available, the other primitives are looked up in the capabilities of this crate, `jester_encryption` and
+    /// `jester_hashes`.
+    ///
+    /// # Returns
+    /// `NegotiationException::UnavailablePrimitive` naming the first primitive that is not available
+    pub fn check_available(&self) -> Result<(), NegotiationException> {
+        let unavailable = |primitive| {
+            Err(NegotiationException::UnavailablePrimitive {
+                suite: *self,
+                primitive,
+            })
+        };
+
+        if !self.aead.is_available() {
+            return unavailable(self.aead.name());
+        }
+        if !self.kdf.is_available() {
+            return unavailable(self.kdf.name());
+        }
+        if !self.hash.is_available() {
+            return unavailable(self.hash.name());
+        }
+        Ok(())
+    }
+
+    /// The suites of `suites` whose primitives are all available in this build, which are the suites a party may
+    /// offer to its peer.
+    pub fn available(suites: &[CipherSuite]) -> Vec<CipherSuite> {
+        suites
+            .iter()
+            .copied()
+            .filter(|suite| suite.check_available().is_ok())
+            .collect()
+    }
+
+    /// Select the suite of a session from the suite ids a peer offers. The first suite of `preferred` that the peer
+    /// offers and whose primitives are all available in this build is selected.
+    ///
+    /// # Returns
+    /// The selected suite, `NegotiationException::UnavailablePrimitive` for the first of the offered suites if none
+    /// of them is available, or `NegotiationException::NoCommonSuite` if the peer offers none of `preferred`.
+    pub fn negotiate(
+        preferred: &[CipherSuite],
+        offered: &[u16],
+    ) -> Result<CipherSuite, NegotiationException> {
+        let mut unavailable = None;
+        for suite in preferred
+            .iter()
+            .filter(|suite| offered.contains(&suite.to_id()))
+        {
+            match suite.check_available() {
+                Ok(()) => return Ok(*suite),
+                Err(exception) => {
+                    unavailable.get_or_insert(exception);
+                }
+            }
+        }
+
+        Err(unavailable.unwrap_or(NegotiationException::NoCommonSuite {}))
+    }
+}
+
+/// Exceptions that can arise when negotiating the `CipherSuite` of a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NegotiationException {
+    /// A primitive of the suite is not compiled into this build, e.g. the `HmacChain` without the `hash_kdf` feature
+    UnavailablePrimitive {
+        suite: CipherSuite,
+        primitive: &'static str,
+    },
+
+    /// The peer offers none of the preferred suites
+    NoCommonSuite {},
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_id_round_trip() {
+        let mut suites = 0;
+        for id in 0..=u16::MAX {
+            if let Some(suite) = CipherSuite::from_id(id) {
+                assert_eq!(suite.to_id(), id);
+                suites += 1;
+            }
+        }
+
+        // every combination of the known primitives has an id
+        assert_eq!(suites, 3 * 2 * 6);
+    }
+
+    #[test]
+    fn test_id_layout() {
+        let suite = CipherSuite::new(
+            DhAlgorithm::IetfGroup3,
+            AeadAlgorithm::Aes256Gcm,
+            KdfAlgorithm::HmacChain,
+            HashAlgorithm::Blake2b,
+        );
+        assert_eq!(suite.to_id(), 0x3214);
+    }
+
+    #[test]
+    fn test_unknown_id_rejected() {
+        assert_eq!(CipherSuite::from_id(0), None);
+        assert_eq!(CipherSuite::from_id(0x4214), None);
+        assert_eq!(CipherSuite::from_id(0x3314), None);
+        assert_eq!(CipherSuite::from_id(0x3224), None);
+        assert_eq!(CipherSuite::from_id(0x3217), None);
+    }
+
+    const PREFERRED: [CipherSuite; 2] = [
+        CipherSuite::new(
+            DhAlgorithm::IetfGroup3,
+            AeadAlgorithm::Aes256Gcm,
+            KdfAlgorithm::HmacChain,
+            HashAlgorithm::Blake2b,
+        ),
+        CipherSuite::new(
+            DhAlgorithm::IetfGroup2,
+            AeadAlgorithm::Aes128Gcm,
+            KdfAlgorithm::HmacChain,
+            HashAlgorithm::Sha1,
+        ),
+    ];
+
+    #[test]
+    fn test_negotiate_no_common_suite() {
+        assert_eq!(
+            CipherSuite::negotiate(&PREFERRED, &[]),
+            Err(NegotiationException::NoCommonSuite {})
+        );
+        assert_eq!(
+            CipherSuite::negotiate(&PREFERRED, &[0x3111, 0x1213]),
+            Err(NegotiationException::NoCommonSuite {})
+        );
+        assert_eq!(
+            CipherSuite::negotiate(&[], &[PREFERRED[0].to_id()]),
+            Err(NegotiationException::NoCommonSuite {})
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "hash_kdf")]
+    fn test_negotiate() {
+        let offered = [PREFERRED[1].to_id(), 0x1111, PREFERRED[0].to_id()];
+        assert_eq!(
+            CipherSuite::negotiate(&PREFERRED, &offered),
+            Ok(PREFERRED[0])
+        );
+        assert_eq!(
+            CipherSuite::negotiate(&PREFERRED, &offered[..2]),
+            Ok(PREFERRED[1])
+        );
+        assert_eq!(CipherSuite::available(&PREFERRED), PREFERRED.to_vec());
+    }
+
+    #[test]
+    #[cfg(not(feature = "hash_kdf"))]
+    fn test_negotiate_unavailable_suite() {
+        let offered = [PREFERRED[1].to_id(), PREFERRED[0].to_id()];
+        assert_eq!(
+            CipherSuite::negotiate(&PREFERRED, &offered),
+            Err(NegotiationException::UnavailablePrimitive {
+                suite: PREFERRED[0],
+                primitive: "hmac-chain",
+            })
+        );
+        assert_eq!(
+            PREFERRED[1].check_available(),
+            Err(NegotiationException::UnavailablePrimitive {
+                suite: PREFERRED[1],
+                primitive: "hmac-chain",
+            })
+        );
+        assert!(CipherSuite::available(&PREFERRED).is_empty());
+    }
+}
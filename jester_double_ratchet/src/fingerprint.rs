@@ -0,0 +1,171 @@
+//! Session fingerprints ("safety numbers") that two parties can compare out of band to detect a man in the middle.
+//! Each public key is hashed iteratively and the truncated hashes of both keys are combined in a canonical order, so
+//! both parties compute the same fingerprint. This module is only available with the `fingerprint` feature.
+
+use std::fmt;
+
+use jester_hashes::tuple_hash::hash_tuple;
+use jester_hashes::{BlockHashFunction, HashValue};
+
+/// The number of hash iterations per key recommended for session fingerprints.
+pub const DEFAULT_FINGERPRINT_ITERATIONS: usize = 5200;
+
+/// Version of the fingerprint format. It is hashed into each key's fingerprint and prepended to the QR encoding.
+const FINGERPRINT_VERSION: u16 = 1;
+
+/// Domain-separation tag of the hashed key tuples
+const FINGERPRINT_DOMAIN: &[u8] = b"jester session fingerprint";
+
+/// Number of hash bytes used per key. Every five bytes are turned into five decimal digits.
+const SIDE_HASH_LENGTH: usize = 30;
+
+/// Number of decimal digits displayed per key
+const SIDE_DIGITS: usize = 30;
+
+/// A session fingerprint derived from the public keys of both parties.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fingerprint {
+    /// The truncated iterated hashes of both keys, ordered by the keys' encodings
+    sides: [Vec<u8>; 2],
+}
+
+impl Fingerprint {
+    /// The fingerprint as 60 decimal digits, grouped in blocks of five digits separated by spaces.
+    pub fn to_numeric_string(&self) -> String {
+        let digits = self.digits();
+        digits
+            .as_bytes()
+            .chunks(5)
+            .map(|group| std::str::from_utf8(group).unwrap())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// A binary encoding of the fingerprint for QR codes. It consists of the two byte big endian format version
+    /// followed by the truncated hashes of both keys.
+    pub fn to_qr_bytes(&self) -> Vec<u8> {
+        [&FINGERPRINT_VERSION.to_be_bytes()[..], &self.sides[0], &self.sides[1]].concat()
+    }
+
+    /// All 60 digits without grouping
+    fn digits(&self) -> String {
+        self.sides
+            .iter()
+            .map(|side| side_digits(side))
+            .collect()
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_numeric_string())
+    }
+}
+
+/// Compute the fingerprint of a session between two parties from their public keys. The order of the keys does not
+/// matter, both parties compute the same fingerprint.
+///
+/// # Parameters
+/// - `ctx` the context of the hash function `H`
+/// - `local_key` the encoded public key of one party
+/// - `remote_key` the encoded public key of the other party
+/// - `iterations` the number of hash iterations per key, see `DEFAULT_FINGERPRINT_ITERATIONS`
+///
+/// # Panics
+/// If the hash function produces less than 30 bytes of output.
+pub fn session_fingerprint<H, K>(ctx: &H::Context, local_key: &K, remote_key: &K, iterations: usize) -> Fingerprint
+where
+    H: BlockHashFunction,
+    K: AsRef<[u8]>,
+{
+    assert!(
+        H::output_size(ctx) >= SIDE_HASH_LENGTH,
+        "session fingerprints require a hash function with at least 30 bytes of output"
+    );
+
+    let (first, second) = if local_key.as_ref() <= remote_key.as_ref() {
+        (local_key.as_ref(), remote_key.as_ref())
+    } else {
+        (remote_key.as_ref(), local_key.as_ref())
+    };
+
+    Fingerprint {
+        sides: [
+            iterated_hash::<H>(ctx, first, iterations),
+            iterated_hash::<H>(ctx, second, iterations),
+        ],
+    }
+}
+
+/// Hash the key `iterations` times, each time as a tuple of the previous hash and the key, and truncate the result.
+/// The first hash is taken of the format version and the key.
+fn iterated_hash<H>(ctx: &H::Context, key: &[u8], iterations: usize) -> Vec<u8>
+where
+    H: BlockHashFunction,
+{
+    let mut hash = FINGERPRINT_VERSION.to_be_bytes().to_vec();
+    for _ in 0..iterations {
+        hash = hash_tuple::<H>(ctx, FINGERPRINT_DOMAIN, &[&hash, key]).raw();
+    }
+
+    hash.truncate(SIDE_HASH_LENGTH);
+    hash
+}
+
+/// Convert a truncated hash into 30 digits. Each five byte chunk is read as a big endian integer reduced modulo 10^5.
+fn side_digits(side: &[u8]) -> String {
+    let digits: String = side
+        .chunks(5)
+        .map(|chunk| {
+            let value = chunk.iter().fold(0u64, |acc, byte| (acc << 8) | *byte as u64);
+            format!("{:05}", value % 100_000)
+        })
+        .collect();
+
+    debug_assert_eq!(digits.len(), SIDE_DIGITS);
+    digits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jester_hashes::blake::blake2b::{Blake2b, Blake2bContext};
+
+    fn fingerprint(local_key: &[u8], remote_key: &[u8]) -> Fingerprint {
+        let ctx = Blake2bContext::new(64).unwrap();
+        session_fingerprint::<Blake2b, _>(&ctx, &local_key, &remote_key, DEFAULT_FINGERPRINT_ITERATIONS)
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let fingerprint_a = fingerprint(b"alice public key", b"bob public key");
+        let fingerprint_b = fingerprint(b"alice public key", b"bob public key");
+
+        assert_eq!(fingerprint_a, fingerprint_b);
+        assert_eq!(fingerprint_a.to_qr_bytes().len(), 62);
+
+        let numeric = fingerprint_a.to_numeric_string();
+        assert_eq!(numeric.len(), 60 + 11);
+        assert!(numeric.split(' ').all(|group| group.len() == 5 && group.bytes().all(|b| b.is_ascii_digit())));
+        assert_eq!(numeric, fingerprint_a.to_string());
+    }
+
+    #[test]
+    fn test_symmetric() {
+        let alice_view = fingerprint(b"alice public key", b"bob public key");
+        let bob_view = fingerprint(b"bob public key", b"alice public key");
+
+        assert_eq!(alice_view, bob_view);
+        assert_eq!(alice_view.to_numeric_string(), bob_view.to_numeric_string());
+        assert_eq!(alice_view.to_qr_bytes(), bob_view.to_qr_bytes());
+    }
+
+    #[test]
+    fn test_key_sensitivity() {
+        let original = fingerprint(b"alice public key", b"bob public key");
+        let changed = fingerprint(b"alice public kez", b"bob public key");
+
+        assert_ne!(original.to_numeric_string(), changed.to_numeric_string());
+        assert_ne!(original.to_qr_bytes(), changed.to_qr_bytes());
+    }
+}
@@ -0,0 +1,461 @@
+//! Group messaging with sender keys. Every member of a group owns a sender chain, a symmetric key ratchet whose
+//! chain key is distributed to the other members over their pairwise `DoubleRatchetProtocol`s. A group message is
+//! encrypted once with the next message key of the sender chain, independent of the number of members, and each
+//! recipient advances its copy of the sender's chain to decrypt it.
+//!
+//! Sender chains only provide forward secrecy: a member that learns a chain key can derive all later message keys of
+//! the chain, but none of the earlier ones. When a member leaves the group, the remaining members must rotate their
+//! sender keys, so the former member cannot read their future messages.
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::hash::Hash;
+use std::marker::PhantomData;
+
+use rand::{CryptoRng, RngCore};
+
+use crate::chain_key::{ChainKey, RollbackKey};
+use crate::replay_window::ReceivedMessages;
+use crate::one_time_key::{derive_message_key, OneTimeKey};
+use crate::skipped_keys::{skip_message_keys, HashMapKeyStore, SkippedKeyStore, StagedKeys};
+use crate::symmetric_channel::MAX_SKIPPED_MESSAGES;
+use crate::DecryptionException::{
+    AuthenticationFailed, OutOfOrderMessage, ReplayedMessage, UnknownMessageHeader,
+};
+use crate::{ConstantInputKeyRatchet, DecryptionException};
+use jester_encryption::SymmetricalEncryptionScheme;
+use jester_util::Redacted;
+
+/// The number of bytes of the header of a sender key distribution: the big endian key id and chain index.
+const DISTRIBUTION_HEADER_LENGTH: usize = 12;
+
+/// The length of chain keys generated for `ChainKey` sender chains.
+const CHAIN_KEY_LENGTH: usize = 32;
+
+/// A chain key that can be used for a sender chain. Sender chain keys are generated randomly and must be encoded as
+/// bytes, so they can be sent over the pairwise protocols.
+pub trait SenderChainKey: Sized {
+    /// Generate a random chain key.
+    fn generate<R>(rng: &mut R) -> Self
+    where
+        R: RngCore + CryptoRng;
+
+    /// Encode the key as bytes.
+    fn to_bytes(&self) -> Vec<u8>;
+
+    /// Decode a key encoded by `to_bytes`.
+    ///
+    /// # Returns
+    /// The decoded key, or `None` if `bytes` is no valid encoding.
+    fn from_bytes(bytes: &[u8]) -> Option<Self>;
+}
+
+impl SenderChainKey for ChainKey {
+    fn generate<R>(rng: &mut R) -> Self
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut bytes = vec![0u8; CHAIN_KEY_LENGTH];
+        rng.fill_bytes(&mut bytes);
+        ChainKey::new(bytes)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.is_empty() {
+            None
+        } else {
+            Some(ChainKey::new(bytes.to_vec()))
+        }
+    }
+}
+
+/// A message encrypted for all members of a group. The key id identifies the sender chain, which changes whenever the
+/// sender rotates its sender key, and the message number is the position of the message within that chain.
+/// # Type Parameters
+/// - `MemberId` the type identifying group members
+/// - `C` the cipher text type
+#[derive(Clone)]
+pub struct GroupMessage<MemberId, C> {
+    sender: MemberId,
+    key_id: u32,
+    pub(crate) message_number: usize,
+    pub(crate) message: C,
+}
+
+impl<MemberId, C> GroupMessage<MemberId, C> {
+    /// The member that sent the message
+    pub fn sender(&self) -> &MemberId {
+        &self.sender
+    }
+}
+
+/// The state of the own sender chain of a member, which must be sent to `recipient` over the pairwise protocol with
+/// that member. The recipient passes the decrypted payload to `GroupSession::process_distribution`.
+#[derive(Debug, Clone)]
+pub struct SenderKeyDistribution<MemberId> {
+    pub recipient: MemberId,
+    pub payload: Redacted<Vec<u8>>,
+}
+
+/// Exceptions that can arise when processing a sender key distribution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupException {
+    /// The distribution was sent by a party that is not a member of the group
+    UnknownMember {},
+
+    /// The payload is not a valid sender key distribution
+    InvalidDistribution {},
+
+    /// The distribution does not replace the current sender chain of the member, because it has the same or an older
+    /// key id
+    OutdatedDistribution { key_id: u32 },
+}
+
+/// The copy of the sender chain of another member.
+struct ReceivingChain<MessageChainKey> {
+    key_id: u32,
+    chain_key: Redacted<Option<MessageChainKey>>,
+    chain_length: usize,
+}
+
+/// The group state of a single member. It contains the own sender chain and copies of the sender chains of all other
+/// members.
+///
+/// # Type Parameters
+/// - `EncryptionScheme` symmetrical encryption scheme for message encryption
+/// - `MessageKdf` key derivation function of the sender chains
+/// - `MemberId` the type identifying group members
+/// - `MessageChainKey` the chain key type of the sender chains
+/// - `MessageKey` encryption key type and output key of the sender chains
+/// - `Store` the store for message keys of skipped messages, indexed by the sender and the key id of its chain
+pub struct GroupSession<
+    EncryptionScheme,
+    MessageKdf,
+    MemberId,
+    MessageChainKey,
+    MessageKey,
+    Store = HashMapKeyStore<(MemberId, u32), MessageKey>,
+> where
+    EncryptionScheme: SymmetricalEncryptionScheme<Key = MessageKey>,
+    MessageKdf: ConstantInputKeyRatchet<ChainKey = MessageChainKey, OutputKey = MessageKey>,
+    MemberId: Clone + Eq + Hash,
+    MessageChainKey: SenderChainKey,
+    Store: SkippedKeyStore<(MemberId, u32), MessageKey>,
+{
+    encryption_scheme: PhantomData<EncryptionScheme>,
+    message_chains: PhantomData<MessageKdf>,
+    own_id: MemberId,
+    members: Vec<MemberId>,
+    sending_key_id: u32,
+    sending_chain_key: Redacted<Option<MessageChainKey>>,
+    sending_chain_length: usize,
+    receiving_chains: HashMap<MemberId, ReceivingChain<MessageChainKey>>,
+    missed_messages: Store,
+    received_messages: ReceivedMessages<(MemberId, u32)>,
+}
+
+impl<EncryptionScheme, MessageKdf, MemberId, MessageChainKey, MessageKey>
+    GroupSession<EncryptionScheme, MessageKdf, MemberId, MessageChainKey, MessageKey>
+where
+    EncryptionScheme: SymmetricalEncryptionScheme<Key = MessageKey>,
+    MessageKdf: ConstantInputKeyRatchet<ChainKey = MessageChainKey, OutputKey = MessageKey>,
+    MemberId: Clone + Eq + Hash,
+    MessageChainKey: SenderChainKey,
+{
+    /// Create a group session with an unbounded in-memory store for skipped message keys. See `new_with_store`.
+    pub fn new<R, I>(
+        rng: &mut R,
+        own_id: MemberId,
+        members: I,
+    ) -> (Self, Vec<SenderKeyDistribution<MemberId>>)
+    where
+        R: RngCore + CryptoRng,
+        I: IntoIterator<Item = MemberId>,
+    {
+        Self::new_with_store(rng, own_id, members, HashMapKeyStore::new())
+    }
+}
+
+impl<EncryptionScheme, MessageKdf, MemberId, MessageChainKey, MessageKey, Store>
+    GroupSession<EncryptionScheme, MessageKdf, MemberId, MessageChainKey, MessageKey, Store>
+where
+    EncryptionScheme: SymmetricalEncryptionScheme<Key = MessageKey>,
+    MessageKdf: ConstantInputKeyRatchet<ChainKey = MessageChainKey, OutputKey = MessageKey>,
+    MemberId: Clone + Eq + Hash,
+    MessageChainKey: SenderChainKey,
+    Store: SkippedKeyStore<(MemberId, u32), MessageKey>,
+{
+    /// Create the group session of the member `own_id` with a fresh sender chain.
+    /// # Parameters
+    /// - `rng` a cryptographically secure random number generator
+    /// - `own_id` the identity of the local member
+    /// - `members` the other members of the group
+    /// - `skipped_key_store` the store for message keys of skipped messages
+    ///
+    /// # Returns
+    /// The session and the distributions of the sender chain, which must be sent to the other members.
+    pub fn new_with_store<R, I>(
+        rng: &mut R,
+        own_id: MemberId,
+        members: I,
+        skipped_key_store: Store,
+    ) -> (Self, Vec<SenderKeyDistribution<MemberId>>)
+    where
+        R: RngCore + CryptoRng,
+        I: IntoIterator<Item = MemberId>,
+    {
+        let mut session = GroupSession {
+            encryption_scheme: PhantomData,
+            message_chains: PhantomData,
+            own_id,
+            members: vec![],
+            sending_key_id: 0,
+            sending_chain_key: Redacted::new(Some(MessageChainKey::generate(rng))),
+            sending_chain_length: 0,
+            receiving_chains: HashMap::new(),
+            missed_messages: skipped_key_store,
+            received_messages: ReceivedMessages::new(),
+        };
+
+        for member in members {
+            if member != session.own_id && !session.members.contains(&member) {
+                session.members.push(member);
+            }
+        }
+
+        let distributions = session.distributions();
+        (session, distributions)
+    }
+
+    /// Add a member to the group. The member only receives the current state of the sender chain, so it cannot
+    /// decrypt messages sent before it joined.
+    ///
+    /// # Returns
+    /// The distribution of the sender chain for the new member.
+    pub fn add_member(&mut self, member: MemberId) -> SenderKeyDistribution<MemberId> {
+        if !self.members.contains(&member) {
+            self.members.push(member.clone());
+        }
+
+        self.distribution(member)
+    }
+
+    /// Remove a member from the group and rotate the sender key, so the member cannot decrypt future messages.
+    /// Messages of the member are not decrypted anymore.
+    ///
+    /// # Returns
+    /// The distributions of the new sender chain for the remaining members.
+    pub fn remove_member<R>(
+        &mut self,
+        rng: &mut R,
+        member: &MemberId,
+    ) -> Vec<SenderKeyDistribution<MemberId>>
+    where
+        R: RngCore + CryptoRng,
+    {
+        self.members.retain(|m| m != member);
+        self.receiving_chains.remove(member);
+        self.rotate_sender_key(rng)
+    }
+
+    /// Replace the sender chain with a fresh one. Messages encrypted afterwards can only be decrypted by members that
+    /// received the new chain.
+    ///
+    /// # Returns
+    /// The distributions of the new sender chain, which must be sent to all members.
+    pub fn rotate_sender_key<R>(&mut self, rng: &mut R) -> Vec<SenderKeyDistribution<MemberId>>
+    where
+        R: RngCore + CryptoRng,
+    {
+        self.sending_key_id += 1;
+        *self.sending_chain_key = Some(MessageChainKey::generate(rng));
+        self.sending_chain_length = 0;
+
+        self.distributions()
+    }
+
+    /// Encrypt a message for all members of the group. The sender chain is advanced once, regardless of the number
+    /// of members.
+    pub fn encrypt_to_group(&mut self, message: &[u8]) -> GroupMessage<MemberId, Vec<u8>> {
+        let (updated_sending_chain_key, message_key) =
+            derive_message_key::<MessageKdf>(self.sending_chain_key.take().unwrap());
+        *self.sending_chain_key = Some(updated_sending_chain_key);
+
+        let message_number = self.sending_chain_length;
+        self.sending_chain_length += 1;
+
+        GroupMessage {
+            sender: self.own_id.clone(),
+            key_id: self.sending_key_id,
+            message_number,
+            message: EncryptionScheme::encrypt_message(&message_key.use_once(), message),
+        }
+    }
+
+    /// Process the distribution of a sender chain received from `sender` over the pairwise protocol. It replaces the
+    /// current chain of the sender. Keys of skipped messages of the replaced chain remain in the store, so late
+    /// messages of the old chain can still be decrypted.
+    ///
+    /// # Returns
+    /// `GroupException::UnknownMember` if the sender is not a member of the group,
+    /// `GroupException::InvalidDistribution` if the payload cannot be decoded and
+    /// `GroupException::OutdatedDistribution` if the sender already distributed a chain with the same or a newer
+    /// key id.
+    pub fn process_distribution(
+        &mut self,
+        sender: MemberId,
+        payload: &[u8],
+    ) -> Result<(), GroupException> {
+        if !self.members.contains(&sender) {
+            return Err(GroupException::UnknownMember {});
+        }
+
+        if payload.len() < DISTRIBUTION_HEADER_LENGTH {
+            return Err(GroupException::InvalidDistribution {});
+        }
+        let key_id = u32::from_be_bytes(payload[..4].try_into().unwrap());
+        let chain_length =
+            u64::from_be_bytes(payload[4..DISTRIBUTION_HEADER_LENGTH].try_into().unwrap())
+                .try_into()
+                .map_err(|_| GroupException::InvalidDistribution {})?;
+        let chain_key = MessageChainKey::from_bytes(&payload[DISTRIBUTION_HEADER_LENGTH..])
+            .ok_or(GroupException::InvalidDistribution {})?;
+
+        if let Some(chain) = self.receiving_chains.get(&sender) {
+            if chain.key_id >= key_id {
+                return Err(GroupException::OutdatedDistribution { key_id });
+            }
+        }
+
+        self.receiving_chains.insert(
+            sender,
+            ReceivingChain {
+                key_id,
+                chain_key: Redacted::new(Some(chain_key)),
+                chain_length,
+            },
+        );
+        Ok(())
+    }
+
+    /// Decrypt a message of another member. The copy of the sender's chain is advanced to the message and the keys
+    /// of skipped messages are stored, like in the pairwise protocol. The chain only advances once the message was
+    /// decrypted, so a rejected message leaves the group state unchanged.
+    ///
+    /// # Returns
+    /// The clear text, `DecryptionException::OutOfOrderMessage` if the message was decrypted with a stored key of a
+    /// skipped message, `DecryptionException::ReplayedMessage` if it was decrypted before,
+    /// `DecryptionException::AuthenticationFailed` if the cipher text fails authentication and
+    /// `DecryptionException::UnknownMessageHeader` if no key for the message is known, e.g. because it was sent before
+    /// the sender chain was distributed to this member or it skips more than `MAX_SKIPPED_MESSAGES` messages.
+    pub fn decrypt_group_message(
+        &mut self,
+        message: GroupMessage<MemberId, Vec<u8>>,
+    ) -> Result<Vec<u8>, DecryptionException>
+    where
+        MessageChainKey: RollbackKey,
+    {
+        let chain_id = (message.sender.clone(), message.key_id);
+        if self
+            .received_messages
+            .contains(&chain_id, message.message_number)
+        {
+            return Err(ReplayedMessage {
+                message_number: message.message_number,
+            });
+        }
+
+        let chain = match self.receiving_chains.get_mut(&message.sender) {
+            Some(chain) => chain,
+            None => return Err(UnknownMessageHeader {}),
+        };
+
+        if chain.key_id != message.key_id || message.message_number < chain.chain_length {
+            // the message belongs to a replaced chain or was skipped
+            let message_key = self
+                .missed_messages
+                .take(&chain_id, message.message_number)
+                .ok_or(UnknownMessageHeader {})?
+                .use_once();
+            let decrypted_message =
+                match EncryptionScheme::try_decrypt_message(&message_key, &message.message) {
+                    Ok(decrypted_message) => decrypted_message,
+                    Err(cause) => {
+                        // a forged message must not use up the key of the real message
+                        self.missed_messages.insert(
+                            chain_id,
+                            message.message_number,
+                            OneTimeKey::new(message_key),
+                        );
+                        return Err(AuthenticationFailed { cause });
+                    }
+                };
+            self.received_messages
+                .insert(chain_id, message.message_number);
+            return Err(OutOfOrderMessage { decrypted_message });
+        }
+
+        if (message.message_number - chain.chain_length) as u64 > MAX_SKIPPED_MESSAGES {
+            return Err(UnknownMessageHeader {});
+        }
+
+        // the keys are derived from a copy of the chain key and the keys of skipped messages are staged, so a message
+        // that fails to decrypt leaves the chain unchanged
+        let mut skipped_keys = StagedKeys::new();
+        let chain_key = skip_message_keys::<MessageKdf, _, _>(
+            chain.chain_key.as_ref().unwrap().rollback_copy(),
+            &chain_id,
+            chain.chain_length,
+            message.message_number,
+            &mut skipped_keys,
+        );
+        let (new_chain_key, message_key) = derive_message_key::<MessageKdf>(chain_key);
+
+        let clear_text =
+            EncryptionScheme::try_decrypt_message(&message_key.use_once(), &message.message)
+                .map_err(|cause| AuthenticationFailed { cause })?;
+
+        // the message is authentic, so the derived keys replace the chain key
+        skipped_keys.commit(&mut self.missed_messages);
+        *chain.chain_key = Some(new_chain_key);
+        chain.chain_length = message.message_number + 1;
+        self.received_messages
+            .insert(chain_id, message.message_number);
+        Ok(clear_text)
+    }
+
+    /// The other members of the group
+    pub fn members(&self) -> &[MemberId] {
+        &self.members
+    }
+
+    /// The number of message keys currently stored for skipped messages.
+    pub fn stored_skipped_keys(&self) -> usize {
+        self.missed_messages.len()
+    }
+
+    /// The distributions of the current sender chain for all members.
+    fn distributions(&self) -> Vec<SenderKeyDistribution<MemberId>> {
+        self.members
+            .iter()
+            .map(|member| self.distribution(member.clone()))
+            .collect()
+    }
+
+    /// The distribution of the current sender chain for `recipient`.
+    fn distribution(&self, recipient: MemberId) -> SenderKeyDistribution<MemberId> {
+        let mut payload = Vec::with_capacity(DISTRIBUTION_HEADER_LENGTH + CHAIN_KEY_LENGTH);
+        payload.extend_from_slice(&self.sending_key_id.to_be_bytes());
+        payload.extend_from_slice(&(self.sending_chain_length as u64).to_be_bytes());
+        payload.extend_from_slice(&self.sending_chain_key.as_ref().unwrap().to_bytes());
+
+        SenderKeyDistribution {
+            recipient,
+            payload: Redacted::new(payload),
+        }
+    }
+}
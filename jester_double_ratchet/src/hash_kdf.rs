@@ -0,0 +1,132 @@
+//! Key derivation functions for the double ratchet based on the hash functions of `jester_hashes`. This module is
+//! only available with the `hash_kdf` feature.
+
+use std::marker::PhantomData;
+
+use jester_encryption::diffie_hellman::{DhKeyCodec, DiffieHellmanKeyExchangeScheme};
+use jester_hashes::kdf::HashKdfChain;
+use jester_hashes::BlockHashFunction;
+
+use crate::chain_key::ChainKey;
+use crate::{ConstantInputKeyRatchet, KeyDerivationFunction};
+
+/// Each derivation step computes the next chain key and the output key as two HMAC invocations keyed with the
+/// current chain key. Since `derive_key` does not receive a context, the default context of the hash function is used.
+/// Output keys are `ChainKey`s as well, because the output of the root chain is the key of a message chain, so the
+/// same chain can be used for both.
+impl<H> KeyDerivationFunction for HashKdfChain<H>
+where
+    H: BlockHashFunction,
+    H::Context: Default,
+{
+    type ChainKey = ChainKey;
+    type Input = Vec<u8>;
+    type OutputKey = ChainKey;
+
+    fn derive_key(
+        chain_key: Self::ChainKey,
+        input: Self::Input,
+    ) -> (Self::ChainKey, Self::OutputKey) {
+        let (next_chain_key, output_key) =
+            HashKdfChain::<H>::new(H::Context::default()).step(chain_key.as_bytes(), &input);
+        (ChainKey::new(next_chain_key), ChainKey::new(output_key))
+    }
+}
+
+/// The symmetric key ratchet of the double ratchet uses a single zero byte as constant input.
+impl<H> ConstantInputKeyRatchet for HashKdfChain<H>
+where
+    H: BlockHashFunction,
+    H::Context: Default,
+{
+    fn constant_input() -> Self::Input {
+        vec![0u8]
+    }
+}
+
+/// A root chain KDF for Diffie-Hellman schemes whose shared keys have the type of their public keys, like the
+/// `DhGroup`s of `jester_encryption`. The shared key is encoded with `DhKeyCodec` and used as input of a
+/// `HashKdfChain`, so the output keys are the chain keys of the message chains.
+pub struct DhRootKdf<H, DHScheme> {
+    hash: PhantomData<H>,
+    scheme: PhantomData<DHScheme>,
+}
+
+impl<H, DHScheme, K> KeyDerivationFunction for DhRootKdf<H, DHScheme>
+where
+    H: BlockHashFunction,
+    H::Context: Default,
+    DHScheme: DhKeyCodec + DiffieHellmanKeyExchangeScheme<PublicKey = K, SharedKey = K>,
+{
+    type ChainKey = ChainKey;
+    type Input = K;
+    type OutputKey = ChainKey;
+
+    fn derive_key(chain_key: Self::ChainKey, input: Self::Input) -> (Self::ChainKey, Self::OutputKey) {
+        HashKdfChain::<H>::derive_key(chain_key, DHScheme::encode_public_key(&input))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jester_hashes::sha1::SHA1Hash;
+    use jester_maths::prime::IetfGroup1;
+    use num::FromPrimitive;
+    use std::time::{Duration, Instant};
+
+    type TestRatchet = HashKdfChain<SHA1Hash>;
+
+    #[test]
+    fn test_ratchet_deterministic() {
+        let chain_key = ChainKey::new(b"chain key".to_vec());
+        let (next_chain_key, output_key) =
+            TestRatchet::derive_key_without_input(chain_key.dangerous_clone_for_backup());
+        let (other_chain_key, other_output_key) = TestRatchet::derive_key_without_input(chain_key);
+
+        assert_eq!(next_chain_key.as_bytes(), other_chain_key.as_bytes());
+        assert_eq!(output_key.as_bytes(), other_output_key.as_bytes());
+    }
+
+    #[test]
+    fn test_ratchet_divergence() {
+        let chain_key = ChainKey::new(b"chain key".to_vec());
+        let (next_chain_key, output_key) =
+            TestRatchet::derive_key(chain_key.dangerous_clone_for_backup(), vec![1]);
+        let (other_chain_key, other_output_key) = TestRatchet::derive_key(chain_key, vec![2]);
+
+        assert_ne!(next_chain_key.as_bytes(), other_chain_key.as_bytes());
+        assert_ne!(output_key.as_bytes(), other_output_key.as_bytes());
+    }
+
+    #[test]
+    fn test_long_ratchet() {
+        let start = Instant::now();
+
+        let mut chain_key = ChainKey::new(b"chain key".to_vec());
+        for _ in 0..1000 {
+            chain_key = TestRatchet::derive_key_without_input(chain_key).0;
+        }
+
+        assert_eq!(chain_key.as_bytes().len(), 20);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_dh_root_kdf() {
+        let shared_key = IetfGroup1::from_u64(0xC0FFEE).unwrap();
+        let (chain_key, output_key) = DhRootKdf::<SHA1Hash, IetfGroup1>::derive_key(
+            ChainKey::new(b"root key".to_vec()),
+            shared_key.clone(),
+        );
+
+        // the shared key is encoded with the full length of the group's keys
+        let (expected_chain_key, expected_output_key) = TestRatchet::derive_key(
+            ChainKey::new(b"root key".to_vec()),
+            IetfGroup1::encode_public_key(&shared_key),
+        );
+        assert_eq!(IetfGroup1::encode_public_key(&shared_key).len(), 128);
+        assert_eq!(chain_key.as_bytes(), expected_chain_key.as_bytes());
+        assert_eq!(output_key.as_bytes(), expected_output_key.as_bytes());
+    }
+}
@@ -0,0 +1,87 @@
+//! Export of key material bound to a session, for application keys that must not consume message keys, like keys
+//! encrypting attachments that are sent out of band. The material is derived from the root chain key with HKDF, so
+//! the protocol needs root chain keys implementing `KeyMaterial`. This module is only available with the
+//! `key_export` feature.
+
+use std::hash::Hash;
+
+use jester_encryption::diffie_hellman::DiffieHellmanKeyExchangeScheme;
+use jester_encryption::SymmetricalEncryptionScheme;
+use jester_hashes::kdf::hkdf_derive_key;
+use jester_hashes::BlockHashFunction;
+
+use crate::chain_key::KeyMaterial;
+use crate::skipped_keys::SkippedKeyStore;
+use crate::{state, ConstantInputKeyRatchet, DoubleRatchetProtocol, KeyDerivationFunction};
+
+/// The HKDF salt separating exported material from the keys derived by the protocol.
+const EXPORT_SALT: &[u8] = b"jester double ratchet key export";
+
+impl<
+        DHScheme,
+        EncryptionScheme,
+        RootKdf,
+        MessageKdf,
+        DHPublicKey,
+        DHPrivateKey,
+        DHSharedKey,
+        RootChainKey,
+        MessageChainKey,
+        MessageKey,
+        Store,
+    >
+    DoubleRatchetProtocol<
+        DHScheme,
+        EncryptionScheme,
+        RootKdf,
+        MessageKdf,
+        DHPublicKey,
+        DHPrivateKey,
+        DHSharedKey,
+        RootChainKey,
+        MessageChainKey,
+        MessageKey,
+        state::Established,
+        Store,
+    >
+where
+    DHScheme: DiffieHellmanKeyExchangeScheme<
+        PublicKey = DHPublicKey,
+        PrivateKey = DHPrivateKey,
+        SharedKey = DHSharedKey,
+    >,
+    EncryptionScheme: SymmetricalEncryptionScheme<Key = MessageKey>,
+    RootKdf: KeyDerivationFunction<
+        ChainKey = RootChainKey,
+        Input = DHSharedKey,
+        OutputKey = MessageChainKey,
+    >,
+    MessageKdf: ConstantInputKeyRatchet<ChainKey = MessageChainKey, OutputKey = MessageKey>,
+    DHPublicKey: Clone + Eq + Hash,
+    Store: SkippedKeyStore<DHPublicKey, MessageKey>,
+    RootChainKey: KeyMaterial,
+{
+    /// Derive `length` bytes of key material from the current root chain key, using HKDF over the hash function `H`
+    /// with `label` as info. Different labels result in independent material. The root chain is not advanced, so
+    /// exporting does not change the keys of any message.
+    ///
+    /// The root chain key only changes with Diffie-Hellman ratchet steps, so exported material is only as fresh as
+    /// the last ratchet step, and exporting with the same label twice in between results in the same bytes. Both
+    /// parties export the same material while their root chain keys agree, which is the case whenever all messages
+    /// sent by either party have been received.
+    pub fn export_key_material<H>(&self, label: &[u8], length: usize) -> Vec<u8>
+    where
+        H: BlockHashFunction,
+        H::Context: Default,
+    {
+        let mut material = hkdf_derive_key::<H, H::Context>(
+            &H::Context::default(),
+            EXPORT_SALT,
+            self.root_chain_key.as_ref().unwrap().key_material(),
+            length,
+            label,
+        );
+        material.truncate(length);
+        material
+    }
+}
@@ -0,0 +1,88 @@
+//! Sources of the Diffie-Hellman key pairs of the ratchet. A protocol draws one key pair from its source for every
+//! Diffie-Hellman ratchet step, so key pairs can be generated in advance, e.g. in a background thread, or held by a
+//! hardware security module that never exposes them to the protocol's random number generator.
+
+use std::sync::Arc;
+
+use jester_encryption::diffie_hellman::{DiffieHellmanKeyExchangeScheme, PrecomputedKeyExchangeScheme};
+use jester_maths::rng::DynCryptoRng;
+use rand::{CryptoRng, RngCore};
+
+/// A source of fresh Diffie-Hellman key pairs for the scheme `DHScheme`.
+pub trait KeyPairSource<DHScheme>
+where
+    DHScheme: DiffieHellmanKeyExchangeScheme,
+{
+    /// Obtain the next key pair. Every key pair secures one sending chain, so a source must never hand out a key pair
+    /// twice.
+    fn next_key_pair(&mut self) -> (DHScheme::PrivateKey, DHScheme::PublicKey);
+}
+
+/// A type-erased function generating Diffie-Hellman key pairs from a precomputed generator, so sources with and
+/// without precomputation have the same type.
+type KeyPairGenerator<DHPrivateKey, DHPublicKey> =
+    Arc<dyn Fn(&mut DynCryptoRng) -> (DHPrivateKey, DHPublicKey) + Send + Sync>;
+
+/// A `KeyPairSource` that generates every key pair when it is requested, using a random number generator.
+///
+/// # Type Parameters
+/// - `R` the random number generator
+/// - `DHScheme` the Diffie-Hellman scheme the key pairs are generated for
+pub struct RngKeyPairSource<R, DHScheme>
+where
+    DHScheme: DiffieHellmanKeyExchangeScheme,
+{
+    rng: R,
+    dh_generator: DHScheme::PublicKey,
+    key_pair_generator: Option<KeyPairGenerator<DHScheme::PrivateKey, DHScheme::PublicKey>>,
+}
+
+impl<R, DHScheme> RngKeyPairSource<R, DHScheme>
+where
+    R: RngCore + CryptoRng,
+    DHScheme: DiffieHellmanKeyExchangeScheme,
+{
+    /// Create a source generating key pairs of the generator `dh_generator`.
+    /// # Parameters
+    /// - `rng` a cryptographically secure random number generator
+    /// - `dh_generator` a pre-shared publicly known value of the Diffie-Hellman-Scheme key space used as generator
+    pub fn new(rng: R, dh_generator: DHScheme::PublicKey) -> Self {
+        RngKeyPairSource {
+            rng,
+            dh_generator,
+            key_pair_generator: None,
+        }
+    }
+
+    /// Create a source generating key pairs using a precomputation of the generator. The precomputation can be
+    /// shared between sources using the same generator.
+    /// # Parameters
+    /// - `rng` a cryptographically secure random number generator
+    /// - `precomputation` the precomputed pre-shared generator of the Diffie-Hellman-Scheme
+    pub fn with_precomputation(rng: R, precomputation: Arc<DHScheme::Precomputation>) -> Self
+    where
+        DHScheme: PrecomputedKeyExchangeScheme + 'static,
+        DHScheme::Precomputation: Send + Sync + 'static,
+    {
+        RngKeyPairSource {
+            rng,
+            dh_generator: DHScheme::precomputed_generator(&precomputation),
+            key_pair_generator: Some(Arc::new(move |rng| {
+                DHScheme::generate_asymmetrical_key_pair_with(&precomputation, rng)
+            })),
+        }
+    }
+}
+
+impl<R, DHScheme> KeyPairSource<DHScheme> for RngKeyPairSource<R, DHScheme>
+where
+    R: RngCore + CryptoRng,
+    DHScheme: DiffieHellmanKeyExchangeScheme,
+{
+    fn next_key_pair(&mut self) -> (DHScheme::PrivateKey, DHScheme::PublicKey) {
+        match &self.key_pair_generator {
+            Some(key_pair_generator) => key_pair_generator(&mut DynCryptoRng::new(&mut self.rng)),
+            None => DHScheme::generate_asymmetrical_key_pair(&mut self.rng, &self.dh_generator),
+        }
+    }
+}
@@ -1,16 +1,83 @@
+use std::convert::TryInto;
+use std::fmt;
 use std::marker::PhantomData;
+use std::sync::Arc;
 
-use rand::{CryptoRng, RngCore};
+use rand::rngs::StdRng;
+use rand::{CryptoRng, RngCore, SeedableRng};
 
-use crate::DecryptionException::{OutOfOrderMessage, UnknownMessageHeader};
-use jester_encryption::diffie_hellman::DiffieHellmanKeyExchangeScheme;
-use jester_encryption::SymmetricalEncryptionScheme;
-use std::collections::HashMap;
+use crate::cipher_suite::CipherSuite;
+use crate::key_pair_source::{KeyPairSource, RngKeyPairSource};
+use crate::chain_key::RollbackKey;
+use crate::one_time_key::{derive_message_key, OneTimeKey};
+use crate::padding::PaddingScheme;
+use crate::replay_window::ReceivedMessages;
+use crate::skipped_keys::{skip_message_keys, HashMapKeyStore, SkippedKeyStore, StagedKeys};
+use crate::DecryptionException::{
+    AuthenticationFailed, InvalidMessageHeader, InvalidPadding, OutOfOrderMessage, ReplayedMessage,
+    SuiteMismatch, UnknownMessageHeader,
+};
+use jester_encryption::diffie_hellman::{
+    DhKeyCodec, DiffieHellmanKeyExchangeScheme, KeyDecodeException, KeyValidationException,
+    PrecomputedKeyExchangeScheme,
+};
+use jester_encryption::{AuthenticationException, SymmetricalEncryptionScheme};
+use jester_util::Redacted;
 use std::hash::Hash;
 
+pub mod chain_key;
+pub mod cipher_suite;
+#[cfg(feature = "fingerprint")]
+pub mod fingerprint;
+pub mod group;
+#[cfg(feature = "hash_kdf")]
+pub mod hash_kdf;
+#[cfg(feature = "key_export")]
+pub mod key_export;
+pub mod key_pair_source;
+pub mod one_time_key;
+#[cfg(feature = "hash_kdf")]
+pub mod message_encryption;
+pub mod padding;
+pub mod replay_window;
+#[cfg(feature = "resumption")]
+pub mod resumption;
+pub mod skipped_keys;
+pub mod symmetric_channel;
+#[cfg(feature = "transcript")]
+pub mod transcript;
+#[cfg(feature = "vault")]
+pub mod vault;
+
 #[cfg(test)]
 mod tests;
 
+jester_util::capabilities! {
+    /// The optional features of a build of this crate, see `capabilities`.
+    pub struct Capabilities {
+        /// The `HmacChain` key derivation function and the message encryption built on it
+        const HASH_KDF = 0, "hash_kdf";
+        const FINGERPRINT = 1, "fingerprint";
+        const KEY_EXPORT = 2, "key_export";
+        const VAULT = 3, "vault";
+        const TRANSCRIPT = 4, "transcript";
+        const RESUMPTION = 5, "resumption";
+    }
+}
+
+/// The optional features this crate was compiled with. `CipherSuite::negotiate` consults it, together with the
+/// capabilities of `jester_encryption` and `jester_hashes`, so that no suite is agreed on whose primitives are
+/// compiled out.
+pub fn capabilities() -> Capabilities {
+    Capabilities::empty()
+        .with(Capabilities::HASH_KDF, cfg!(feature = "hash_kdf"))
+        .with(Capabilities::FINGERPRINT, cfg!(feature = "fingerprint"))
+        .with(Capabilities::KEY_EXPORT, cfg!(feature = "key_export"))
+        .with(Capabilities::VAULT, cfg!(feature = "vault"))
+        .with(Capabilities::TRANSCRIPT, cfg!(feature = "transcript"))
+        .with(Capabilities::RESUMPTION, cfg!(feature = "resumption"))
+}
+
 /// A trait modelling a key-derivation-function as defined by the specification of the Double
 /// Ratchet Algorithm by Trevor Perrin and Moxie Marlinspike.
 pub trait KeyDerivationFunction {
@@ -34,31 +101,187 @@ pub trait KeyDerivationFunction {
 /// A symmetric key ratchet is a special case of an `KeyDerivationFunction` where the input is
 /// constant. It is therefore not a parameter to the `derive_key_without_input` function.
 pub trait ConstantInputKeyRatchet: KeyDerivationFunction {
-    /// The constant input to the `KeyDerivationFunction`
-    const INPUT: Self::Input;
+    /// The constant input to the `KeyDerivationFunction`. It is called once per derivation and must return the same
+    /// value every time, but unlike an associated constant it can construct heap allocated inputs like `Vec<u8>`.
+    fn constant_input() -> Self::Input;
 
     /// Consume the current `derivation_key` to generate a new chain key and an output key.
     fn derive_key_without_input(
         derivation_key: Self::ChainKey,
     ) -> (Self::ChainKey, Self::OutputKey) {
-        Self::derive_key(derivation_key, Self::INPUT)
+        Self::derive_key(derivation_key, Self::constant_input())
+    }
+}
+
+/// The former form of `ConstantInputKeyRatchet`, which declares the constant input as an associated constant. All
+/// implementors implement `ConstantInputKeyRatchet` as well, so existing key ratchets keep working with the protocol.
+#[deprecated(note = "implement `ConstantInputKeyRatchet::constant_input` instead")]
+pub trait ConstInputKeyRatchet: KeyDerivationFunction {
+    /// The constant input to the `KeyDerivationFunction`
+    const INPUT: Self::Input;
+}
+
+#[allow(deprecated)]
+impl<T> ConstantInputKeyRatchet for T
+where
+    T: ConstInputKeyRatchet,
+{
+    fn constant_input() -> Self::Input {
+        T::INPUT
     }
 }
 
 /// A message sent between parties within the double-ratchet-algorithm. It contains the cipher, (except in
 /// the very first message of the protocol initiator) and the public key to the diffie-hellman ratchet. For handling
 /// of out-of-order messages the `message_number` and the `previous_chain_length` (both of the sending chain) are
-/// sent within the header. They can be used by the recipient to detect missing messages.
+/// sent within the header. They can be used by the recipient to detect missing messages. The header also carries the
+/// `CipherSuite` of the sending protocol, so messages of sessions with different primitives are rejected.
 /// # Type Parameters
 /// - `K` the diffie-hellman key type
 /// - `C` the cipher text type
+#[derive(Clone)]
 pub struct DoubleRatchetAlgorithmMessage<K, C> {
+    cipher_suite: CipherSuite,
     public_key: K,
     message_number: usize,
     previous_chain_length: usize,
     message: Option<C>,
 }
 
+/// The version of the wire encoding of `DoubleRatchetAlgorithmMessage`s, which is the first byte of every encoded
+/// message.
+pub const MESSAGE_VERSION: u8 = 1;
+
+impl<K, C> DoubleRatchetAlgorithmMessage<K, C> {
+    /// The cipher suite of the protocol that created the message
+    pub fn cipher_suite(&self) -> CipherSuite {
+        self.cipher_suite
+    }
+
+    /// The Diffie-Hellman public key of the sender's current ratchet step. The addressee of a protocol is initialized
+    /// with the public key of the initiator's first message.
+    pub fn public_key(&self) -> &K {
+        &self.public_key
+    }
+
+    /// Encode the message for sending it over the wire. The encoding starts with a header of the `MESSAGE_VERSION`
+    /// byte, the big endian two byte id of the cipher suite, the big endian two byte length of the public key and the
+    /// public key encoded by `DHScheme`. It is followed by the big endian eight byte message number and previous chain
+    /// length, a byte that is `1` if the message has a cipher text and `0` otherwise, and the cipher text.
+    ///
+    /// # Type Parameters
+    /// - `DHScheme` the Diffie-Hellman scheme encoding the public key
+    pub fn encode<DHScheme>(&self) -> Vec<u8>
+    where
+        DHScheme: DhKeyCodec<PublicKey = K>,
+        C: AsRef<[u8]>,
+    {
+        let public_key = DHScheme::encode_public_key(&self.public_key);
+        let public_key_length: u16 = public_key
+            .len()
+            .try_into()
+            .expect("the encoded public key is longer than 65535 bytes");
+
+        let mut encoding = vec![MESSAGE_VERSION];
+        encoding.extend_from_slice(&self.cipher_suite.to_id().to_be_bytes());
+        encoding.extend_from_slice(&public_key_length.to_be_bytes());
+        encoding.extend(public_key);
+        encoding.extend_from_slice(&(self.message_number as u64).to_be_bytes());
+        encoding.extend_from_slice(&(self.previous_chain_length as u64).to_be_bytes());
+        match &self.message {
+            Some(cipher_text) => {
+                encoding.push(1);
+                encoding.extend_from_slice(cipher_text.as_ref());
+            }
+            None => encoding.push(0),
+        }
+        encoding
+    }
+}
+
+impl<K> DoubleRatchetAlgorithmMessage<K, Vec<u8>> {
+    /// Decode a message encoded by `encode`. The version and the cipher suite are validated, but whether the suite
+    /// is the suite of the session is only checked when the message is decrypted.
+    ///
+    /// # Type Parameters
+    /// - `DHScheme` the Diffie-Hellman scheme decoding the public key
+    ///
+    /// # Returns
+    /// The decoded message or a `MessageDecodeException` if `bytes` is no valid encoding of a message.
+    pub fn decode<DHScheme>(bytes: &[u8]) -> Result<Self, MessageDecodeException>
+    where
+        DHScheme: DhKeyCodec<PublicKey = K>,
+    {
+        let mut remaining = bytes;
+        let mut read = |length: usize| {
+            if remaining.len() < length {
+                return Err(MessageDecodeException::Truncated {});
+            }
+            let (field, rest) = remaining.split_at(length);
+            remaining = rest;
+            Ok(field)
+        };
+
+        let version = read(1)?[0];
+        if version != MESSAGE_VERSION {
+            return Err(MessageDecodeException::UnsupportedVersion { version });
+        }
+
+        let suite_id = u16::from_be_bytes(read(2)?.try_into().unwrap());
+        let cipher_suite =
+            CipherSuite::from_id(suite_id).ok_or(MessageDecodeException::UnknownCipherSuite { id: suite_id })?;
+
+        let public_key_length = u16::from_be_bytes(read(2)?.try_into().unwrap());
+        let public_key = DHScheme::decode_public_key(read(public_key_length as usize)?)
+            .map_err(|cause| MessageDecodeException::InvalidPublicKey { cause })?;
+
+        let mut read_number = || -> Result<usize, MessageDecodeException> {
+            u64::from_be_bytes(read(8)?.try_into().unwrap())
+                .try_into()
+                .map_err(|_| MessageDecodeException::NumberOutOfRange {})
+        };
+        let message_number = read_number()?;
+        let previous_chain_length = read_number()?;
+
+        let message = match read(1)?[0] {
+            0 if remaining.is_empty() => None,
+            1 => Some(remaining.to_vec()),
+            flag => return Err(MessageDecodeException::InvalidCipherTextFlag { flag }),
+        };
+
+        Ok(DoubleRatchetAlgorithmMessage {
+            cipher_suite,
+            public_key,
+            message_number,
+            previous_chain_length,
+            message,
+        })
+    }
+}
+
+/// Exceptions that can arise when decoding a `DoubleRatchetAlgorithmMessage` from bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MessageDecodeException {
+    /// The encoding ends before the message does
+    Truncated {},
+
+    /// The message was encoded with a version of the wire encoding other than `MESSAGE_VERSION`
+    UnsupportedVersion { version: u8 },
+
+    /// The id of the cipher suite does not describe a known `CipherSuite`
+    UnknownCipherSuite { id: u16 },
+
+    /// The public key was rejected by the Diffie-Hellman scheme
+    InvalidPublicKey { cause: KeyDecodeException },
+
+    /// The message number or previous chain length does not fit into a `usize` on this platform
+    NumberOutOfRange {},
+
+    /// The byte that marks whether the message has a cipher text is neither `0` nor `1`, or a message without cipher
+    /// text is followed by further bytes
+    InvalidCipherTextFlag { flag: u8 },
+}
+
 /// The two states the double ratchet protocol can be in. The `Initiator` is the party that is trying to establish
 /// a communication. The addressee can establish the protocol instantly, because it does not need an initialized
 /// receiving chain until it gets another message by the `Initiator`, and that will contain any information necessary
@@ -93,6 +316,7 @@ enum ProtocolException<DHPublicKey> {
 
 /// Exceptions that can arise during decryption of messages. Some can be recovered, like simple out of order
 /// handling, some end the protocol exchange.
+#[derive(Debug)]
 pub enum DecryptionException {
     /// The message that was decrypted had an invalid header, rendering its decryption impossible
     InvalidMessageHeader {},
@@ -103,6 +327,37 @@ pub enum DecryptionException {
     /// The message header identified the message as an out-of-order message but no message key for this out-of-order
     /// arrival could be generated, rendering its decryption impossible
     UnknownMessageHeader {},
+
+    /// The decrypted message was not padded correctly according to the padding scheme of the protocol
+    InvalidPadding {},
+
+    /// The cipher text was rejected by the authenticated encryption scheme of the protocol, because it was forged or
+    /// corrupted in transit
+    AuthenticationFailed { cause: AuthenticationException },
+
+    /// A message with this message number was already decrypted in the same receiving chain, so the message was
+    /// replayed. Replays are only recognized within the window described in `replay_window`, older replays are
+    /// rejected as `UnknownMessageHeader`.
+    ReplayedMessage { message_number: usize },
+
+    /// The message was created by a protocol with a different cipher suite than the receiving protocol, so it is
+    /// rejected without changing the protocol state.
+    SuiteMismatch {
+        got: CipherSuite,
+        expected: CipherSuite,
+    },
+}
+
+/// Describes how the protocol state changed when decrypting a message. Applications can use it to track the
+/// forward-secrecy epochs of a conversation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RatchetEvent<DHPublicKey> {
+    /// The message carried a new public key of the other party, so a Diffie-Hellman ratchet step was performed and
+    /// both message chains were replaced.
+    RatchetStepped { new_remote_key: DHPublicKey },
+
+    /// The message belonged to the current receiving chain.
+    SameChain,
 }
 
 /// Double-Ratchet-Algorithm protocol state. It has some phantom markers for the used primitives and keeps track of
@@ -117,6 +372,11 @@ pub enum DecryptionException {
 /// - `RootChainKey` root KDF key type
 /// - `MessageChainKey` root KDF output key type and message KDFs' key type
 /// - `MessageKey` encryption key type and output key of message KDFs
+/// - `Store` the store for message keys of skipped messages
+///
+/// Messages are not padded by default. Use `set_padding_scheme` to hide the exact length of messages. The
+/// Diffie-Hellman key pairs of the ratchet are drawn from a `KeyPairSource`, one for every ratchet step. Both parties
+/// are initialized with the same `CipherSuite`, which is sent with every message and verified on decryption.
 pub struct DoubleRatchetProtocol<
     DHScheme,
     EncryptionScheme,
@@ -129,6 +389,7 @@ pub struct DoubleRatchetProtocol<
     MessageChainKey,
     MessageKey,
     State,
+    Store = HashMapKeyStore<DHPublicKey, MessageKey>,
 > where
     DHScheme: DiffieHellmanKeyExchangeScheme<
         PublicKey = DHPublicKey,
@@ -144,26 +405,35 @@ pub struct DoubleRatchetProtocol<
     MessageKdf: ConstantInputKeyRatchet<ChainKey = MessageChainKey, OutputKey = MessageKey>,
     DHPublicKey: Clone + Eq + Hash,
     State: state::ProtocolState,
+    Store: SkippedKeyStore<DHPublicKey, MessageKey>,
 {
     state: PhantomData<State>,
     diffie_hellman_scheme: PhantomData<DHScheme>,
     encryption_scheme: PhantomData<EncryptionScheme>,
     root_chain: PhantomData<RootKdf>,
     message_chains: PhantomData<MessageKdf>,
-    diffie_hellman_generator: DHPublicKey,
+    key_pair_source: Box<dyn KeyPairSource<DHScheme> + Send>,
     diffie_hellman_public_key: DHPublicKey,
-    diffie_hellman_private_key: Option<DHPrivateKey>,
+    diffie_hellman_private_key: Redacted<Option<DHPrivateKey>>,
     diffie_hellman_received_key: Option<DHPublicKey>,
-    root_chain_key: Option<RootChainKey>,
-    sending_chain_key: Option<MessageChainKey>,
-    receiving_chain_key: Option<MessageChainKey>,
+    root_chain_key: Redacted<Option<RootChainKey>>,
+    sending_chain_key: Redacted<Option<MessageChainKey>>,
+    receiving_chain_key: Redacted<Option<MessageChainKey>>,
     sending_chain_length: usize,
     receiving_chain_length: usize,
     previous_sending_chain_length: usize,
     previous_receiving_chain_length: usize,
-    missed_messages: HashMap<(DHPublicKey, usize), MessageKey>,
+    missed_messages: Store,
+    received_messages: ReceivedMessages<DHPublicKey>,
+    padding: PaddingScheme,
+    cipher_suite: CipherSuite,
+    #[cfg(feature = "transcript")]
+    transcript: Option<transcript::AttachedRecorder<DHPublicKey, MessageKey>>,
+    #[cfg(feature = "resumption")]
+    resumption: Option<resumption::PendingResumption<DHPublicKey, DHPrivateKey>>,
 }
 
+/// Only the public state of the protocol is printed. The private Diffie-Hellman key and the chain keys are redacted.
 impl<
         DHScheme,
         EncryptionScheme,
@@ -175,6 +445,81 @@ impl<
         RootChainKey,
         MessageChainKey,
         MessageKey,
+        State,
+        Store,
+    > fmt::Debug
+    for DoubleRatchetProtocol<
+        DHScheme,
+        EncryptionScheme,
+        RootKdf,
+        MessageKdf,
+        DHPublicKey,
+        DHPrivateKey,
+        DHSharedKey,
+        RootChainKey,
+        MessageChainKey,
+        MessageKey,
+        State,
+        Store,
+    >
+where
+    DHScheme: DiffieHellmanKeyExchangeScheme<
+        PublicKey = DHPublicKey,
+        PrivateKey = DHPrivateKey,
+        SharedKey = DHSharedKey,
+    >,
+    EncryptionScheme: SymmetricalEncryptionScheme<Key = MessageKey>,
+    RootKdf: KeyDerivationFunction<
+        ChainKey = RootChainKey,
+        Input = DHSharedKey,
+        OutputKey = MessageChainKey,
+    >,
+    MessageKdf: ConstantInputKeyRatchet<ChainKey = MessageChainKey, OutputKey = MessageKey>,
+    DHPublicKey: Clone + Eq + Hash + fmt::Debug,
+    State: state::ProtocolState,
+    Store: SkippedKeyStore<DHPublicKey, MessageKey>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DoubleRatchetProtocol")
+            .field("cipher_suite", &self.cipher_suite)
+            .field("diffie_hellman_public_key", &self.diffie_hellman_public_key)
+            .field(
+                "diffie_hellman_private_key",
+                &self.diffie_hellman_private_key,
+            )
+            .field(
+                "diffie_hellman_received_key",
+                &self.diffie_hellman_received_key,
+            )
+            .field("root_chain_key", &self.root_chain_key)
+            .field("sending_chain_key", &self.sending_chain_key)
+            .field("receiving_chain_key", &self.receiving_chain_key)
+            .field("sending_chain_length", &self.sending_chain_length)
+            .field("receiving_chain_length", &self.receiving_chain_length)
+            .field(
+                "previous_sending_chain_length",
+                &self.previous_sending_chain_length,
+            )
+            .field(
+                "previous_receiving_chain_length",
+                &self.previous_receiving_chain_length,
+            )
+            .finish()
+    }
+}
+
+impl<
+        DHScheme,
+        EncryptionScheme,
+        RootKdf,
+        MessageKdf,
+        DHPublicKey,
+        DHPrivateKey,
+        DHSharedKey,
+        RootChainKey,
+        MessageChainKey,
+        MessageKey,
+        Store,
     >
     DoubleRatchetProtocol<
         DHScheme,
@@ -188,6 +533,7 @@ impl<
         MessageChainKey,
         MessageKey,
         state::Initiator,
+        Store,
     >
 where
     DHScheme: DiffieHellmanKeyExchangeScheme<
@@ -203,6 +549,7 @@ where
     >,
     MessageKdf: ConstantInputKeyRatchet<ChainKey = MessageChainKey, OutputKey = MessageKey>,
     DHPublicKey: Clone + Eq + Hash,
+    Store: SkippedKeyStore<DHPublicKey, MessageKey>,
 {
     //noinspection RsFieldInitShorthand
     /// Initialize the double ratchet protocol for the sending side, that starts by sending the other side an empty
@@ -212,17 +559,76 @@ where
     /// - `rng` a cryptographically secure random number generator
     /// - `dh_generator` a pre-shared publicly known value of the Diffie-Hellman-Scheme key space used as generator
     /// - `initial_root_chain_key` the initial common root key of both parties, agreed upon OTR
-    pub fn initialize_sending<R>(
+    /// - `cipher_suite` the primitives of the session, which both parties must agree upon
+    /// - `skipped_key_store` the store for message keys of skipped messages
+    pub fn initialize_sending_with_store<R>(
         rng: &mut R,
         dh_generator: DHPublicKey,
         initial_root_chain_key: RootChainKey,
+        cipher_suite: CipherSuite,
+        skipped_key_store: Store,
+    ) -> (Self, DoubleRatchetAlgorithmMessage<DHPublicKey, Box<[u8]>>)
+    where
+        R: RngCore + CryptoRng,
+        DHScheme: 'static,
+        DHPublicKey: Send,
+    {
+        Self::initialize_sending_with_key_pair_source(
+            RngKeyPairSource::new(seed_key_pair_rng(rng), dh_generator),
+            initial_root_chain_key,
+            cipher_suite,
+            skipped_key_store,
+        )
+    }
+
+    /// Initialize the double ratchet protocol for the sending side like `initialize_sending_with_store`, but
+    /// generate all Diffie-Hellman key pairs using a precomputation of the generator. The precomputation can be
+    /// shared between protocols using the same generator.
+    /// # Parameters
+    /// - `rng` a cryptographically secure random number generator
+    /// - `precomputation` the precomputed pre-shared generator of the Diffie-Hellman-Scheme
+    /// - `initial_root_chain_key` the initial common root key of both parties, agreed upon OTR
+    /// - `cipher_suite` the primitives of the session, which both parties must agree upon
+    /// - `skipped_key_store` the store for message keys of skipped messages
+    pub fn initialize_sending_with_precomputation<R>(
+        rng: &mut R,
+        precomputation: Arc<DHScheme::Precomputation>,
+        initial_root_chain_key: RootChainKey,
+        cipher_suite: CipherSuite,
+        skipped_key_store: Store,
     ) -> (Self, DoubleRatchetAlgorithmMessage<DHPublicKey, Box<[u8]>>)
     where
         R: RngCore + CryptoRng,
+        DHScheme: PrecomputedKeyExchangeScheme + 'static,
+        DHScheme::Precomputation: Send + Sync + 'static,
+        DHPublicKey: Send,
+    {
+        Self::initialize_sending_with_key_pair_source(
+            RngKeyPairSource::with_precomputation(seed_key_pair_rng(rng), precomputation),
+            initial_root_chain_key,
+            cipher_suite,
+            skipped_key_store,
+        )
+    }
+
+    /// Initialize the double ratchet protocol for the sending side like `initialize_sending_with_store`, but draw
+    /// all Diffie-Hellman key pairs, including the initial one, from `key_pair_source`.
+    /// # Parameters
+    /// - `key_pair_source` the source of the Diffie-Hellman key pairs of the ratchet
+    /// - `initial_root_chain_key` the initial common root key of both parties, agreed upon OTR
+    /// - `cipher_suite` the primitives of the session, which both parties must agree upon
+    /// - `skipped_key_store` the store for message keys of skipped messages
+    pub fn initialize_sending_with_key_pair_source<K>(
+        mut key_pair_source: K,
+        initial_root_chain_key: RootChainKey,
+        cipher_suite: CipherSuite,
+        skipped_key_store: Store,
+    ) -> (Self, DoubleRatchetAlgorithmMessage<DHPublicKey, Box<[u8]>>)
+    where
+        K: KeyPairSource<DHScheme> + Send + 'static,
     {
         // generate diffie-hellman public key
-        let (private_dh_key, public_dh_key) =
-            DHScheme::generate_asymmetrical_key_pair(rng, &dh_generator);
+        let (private_dh_key, public_dh_key) = key_pair_source.next_key_pair();
 
         (
             Self {
@@ -231,20 +637,28 @@ where
                 encryption_scheme: PhantomData,
                 root_chain: PhantomData,
                 message_chains: PhantomData,
-                diffie_hellman_generator: dh_generator,
+                key_pair_source: Box::new(key_pair_source),
                 diffie_hellman_public_key: public_dh_key.clone(),
-                diffie_hellman_private_key: Some(private_dh_key),
+                diffie_hellman_private_key: Redacted::new(Some(private_dh_key)),
                 diffie_hellman_received_key: None,
-                root_chain_key: Some(initial_root_chain_key),
-                sending_chain_key: None,
-                receiving_chain_key: None,
+                root_chain_key: Redacted::new(Some(initial_root_chain_key)),
+                sending_chain_key: Redacted::new(None),
+                receiving_chain_key: Redacted::new(None),
                 sending_chain_length: 0,
                 receiving_chain_length: 0,
                 previous_sending_chain_length: 0,
                 previous_receiving_chain_length: 0,
-                missed_messages: HashMap::new(),
+                missed_messages: skipped_key_store,
+                received_messages: ReceivedMessages::new(),
+                padding: PaddingScheme::None,
+                cipher_suite,
+                #[cfg(feature = "transcript")]
+                transcript: None,
+                #[cfg(feature = "resumption")]
+                resumption: None,
             },
             DoubleRatchetAlgorithmMessage {
+                cipher_suite,
                 public_key: public_dh_key,
                 message_number: 0,
                 previous_chain_length: 0,
@@ -257,11 +671,18 @@ where
     /// which is returned, alongside an updated protocol instance containing ready-to-use KDF chains.
     /// # Parameters
     /// - `message` a `DoubleRatchetAlgorithmMessage` that is decrypted and used to advance the protocol state
-    pub fn decrypt_first_message<R>(
+    ///
+    /// # Returns
+    /// Returns `DecryptionException::AuthenticationFailed` if the cipher text fails authentication,
+    /// `DecryptionException::InvalidPadding` if the message is not padded according to the padding scheme,
+    /// `DecryptionException::InvalidMessageHeader` if the public key of the message is rejected by the
+    /// Diffie-Hellman scheme or the message carries no cipher text, and `DecryptionException::SuiteMismatch` if the
+    /// message was created with another cipher suite.
+    #[allow(clippy::type_complexity)]
+    pub fn decrypt_first_message(
         mut self,
-        rng: &mut R,
         message: DoubleRatchetAlgorithmMessage<DHPublicKey, Vec<u8>>,
-    ) -> (
+    ) -> Result<(
         DoubleRatchetProtocol<
             DHScheme,
             EncryptionScheme,
@@ -274,56 +695,165 @@ where
             MessageChainKey,
             MessageKey,
             state::Established,
+            Store,
         >,
         Vec<u8>,
-    )
-    where
-        R: RngCore + CryptoRng,
-    {
+    ), DecryptionException> {
+        check_cipher_suite(self.cipher_suite, &message)?;
+        let cipher_text = cipher_text(&message)?;
+
         // update diffie-hellman-ratchet
-        let generated_dh_shared_key = DHScheme::generate_shared_secret(
-            &self.diffie_hellman_private_key.unwrap(),
+        let generated_dh_shared_key = DHScheme::generate_shared_secret_checked(
+            &self.diffie_hellman_private_key.into_inner().unwrap(),
             &message.public_key,
-        );
+        )
+        .map_err(|_| DecryptionException::InvalidMessageHeader {})?;
 
         // update receiving chain
         let (updated_root_key, receiving_key) =
             RootKdf::derive_key(self.root_chain_key.take().unwrap(), generated_dh_shared_key);
-        let (receiving_chain_key, message_key) =
-            MessageKdf::derive_key_without_input(receiving_key);
+        let (receiving_chain_key, message_key) = derive_message_key::<MessageKdf>(receiving_key);
 
         // decrypt message
-        let clear_text = EncryptionScheme::decrypt_message(&message_key, &message.message.unwrap());
+        let message_key = message_key.use_once();
+        let clear_text = open_message::<EncryptionScheme>(self.padding, &message_key, cipher_text)?;
 
-        // update sending chain
-        let (new_dh_private_key, new_dh_public_key) =
-            DHScheme::generate_asymmetrical_key_pair(rng, &self.diffie_hellman_generator);
-        let new_dh_shared_key =
-            DHScheme::generate_shared_secret(&new_dh_private_key, &message.public_key);
-        let (updated_root_key, sending_key) =
-            RootKdf::derive_key(updated_root_key, new_dh_shared_key);
+        // generate the key pair of the next sending chain, which is derived by the first call of `encrypt_message`
+        let (new_dh_private_key, new_dh_public_key) = self.key_pair_source.next_key_pair();
 
-        (
-            DoubleRatchetProtocol {
-                state: PhantomData,
-                diffie_hellman_scheme: PhantomData,
-                encryption_scheme: PhantomData,
-                root_chain: PhantomData,
-                message_chains: PhantomData,
-                diffie_hellman_generator: self.diffie_hellman_generator,
-                diffie_hellman_public_key: new_dh_public_key,
-                diffie_hellman_private_key: Some(new_dh_private_key),
-                diffie_hellman_received_key: Some(message.public_key),
-                root_chain_key: Some(updated_root_key),
-                sending_chain_key: Some(sending_key),
-                receiving_chain_key: Some(receiving_chain_key),
-                sending_chain_length: 0,
-                receiving_chain_length: 1,
-                previous_sending_chain_length: 0,
-                previous_receiving_chain_length: 0,
-                missed_messages: HashMap::new(),
-            },
-            clear_text,
+        let mut received_messages = ReceivedMessages::new();
+        received_messages.insert(message.public_key.clone(), message.message_number);
+
+        let protocol = DoubleRatchetProtocol {
+            state: PhantomData,
+            diffie_hellman_scheme: PhantomData,
+            encryption_scheme: PhantomData,
+            root_chain: PhantomData,
+            message_chains: PhantomData,
+            key_pair_source: self.key_pair_source,
+            diffie_hellman_public_key: new_dh_public_key,
+            diffie_hellman_private_key: Redacted::new(Some(new_dh_private_key)),
+            diffie_hellman_received_key: Some(message.public_key.clone()),
+            root_chain_key: Redacted::new(Some(updated_root_key)),
+            sending_chain_key: Redacted::new(None),
+            receiving_chain_key: Redacted::new(Some(receiving_chain_key)),
+            sending_chain_length: 0,
+            receiving_chain_length: 1,
+            previous_sending_chain_length: 0,
+            previous_receiving_chain_length: 0,
+            missed_messages: self.missed_messages,
+            received_messages,
+            padding: self.padding,
+            cipher_suite: self.cipher_suite,
+            #[cfg(feature = "transcript")]
+            transcript: self.transcript,
+            #[cfg(feature = "resumption")]
+            resumption: None,
+        };
+
+        #[cfg(feature = "transcript")]
+        protocol.record_transcript(
+            transcript::Direction::Received,
+            &message.public_key,
+            message.message_number,
+            message.previous_chain_length,
+            &message_key,
+        );
+
+        Ok((protocol, clear_text))
+    }
+
+    /// Decrypt the first message received from the addressee like `decrypt_first_message`. The `rng` is not used,
+    /// the next key pair is drawn from the protocol's `KeyPairSource`.
+    #[deprecated(note = "use `decrypt_first_message`, which draws key pairs from the protocol's `KeyPairSource`")]
+    #[allow(clippy::type_complexity)]
+    pub fn decrypt_first_message_using_rng<R>(
+        self,
+        _rng: &mut R,
+        message: DoubleRatchetAlgorithmMessage<DHPublicKey, Vec<u8>>,
+    ) -> Result<(
+        DoubleRatchetProtocol<
+            DHScheme,
+            EncryptionScheme,
+            RootKdf,
+            MessageKdf,
+            DHPublicKey,
+            DHPrivateKey,
+            DHSharedKey,
+            RootChainKey,
+            MessageChainKey,
+            MessageKey,
+            state::Established,
+            Store,
+        >,
+        Vec<u8>,
+    ), DecryptionException>
+    where
+        R: RngCore + CryptoRng,
+    {
+        self.decrypt_first_message(message)
+    }
+}
+
+impl<
+        DHScheme,
+        EncryptionScheme,
+        RootKdf,
+        MessageKdf,
+        DHPublicKey,
+        DHPrivateKey,
+        DHSharedKey,
+        RootChainKey,
+        MessageChainKey,
+        MessageKey,
+    >
+    DoubleRatchetProtocol<
+        DHScheme,
+        EncryptionScheme,
+        RootKdf,
+        MessageKdf,
+        DHPublicKey,
+        DHPrivateKey,
+        DHSharedKey,
+        RootChainKey,
+        MessageChainKey,
+        MessageKey,
+        state::Initiator,
+    >
+where
+    DHScheme: DiffieHellmanKeyExchangeScheme<
+        PublicKey = DHPublicKey,
+        PrivateKey = DHPrivateKey,
+        SharedKey = DHSharedKey,
+    >,
+    EncryptionScheme: SymmetricalEncryptionScheme<Key = MessageKey>,
+    RootKdf: KeyDerivationFunction<
+        ChainKey = RootChainKey,
+        Input = DHSharedKey,
+        OutputKey = MessageChainKey,
+    >,
+    MessageKdf: ConstantInputKeyRatchet<ChainKey = MessageChainKey, OutputKey = MessageKey>,
+    DHPublicKey: Clone + Eq + Hash,
+{
+    /// Initialize the double ratchet protocol for the sending side with an unbounded in-memory store for skipped
+    /// message keys. See `initialize_sending_with_store`.
+    pub fn initialize_sending<R>(
+        rng: &mut R,
+        dh_generator: DHPublicKey,
+        initial_root_chain_key: RootChainKey,
+        cipher_suite: CipherSuite,
+    ) -> (Self, DoubleRatchetAlgorithmMessage<DHPublicKey, Box<[u8]>>)
+    where
+        R: RngCore + CryptoRng,
+        DHScheme: 'static,
+        DHPublicKey: Send,
+    {
+        Self::initialize_sending_with_store(
+            rng,
+            dh_generator,
+            initial_root_chain_key,
+            cipher_suite,
+            HashMapKeyStore::new(),
         )
     }
 }
@@ -339,6 +869,7 @@ impl<
         RootChainKey,
         MessageChainKey,
         MessageKey,
+        Store,
     >
     DoubleRatchetProtocol<
         DHScheme,
@@ -352,6 +883,7 @@ impl<
         MessageChainKey,
         MessageKey,
         state::Established,
+        Store,
     >
 where
     DHScheme: DiffieHellmanKeyExchangeScheme<
@@ -367,6 +899,7 @@ where
     >,
     MessageKdf: ConstantInputKeyRatchet<ChainKey = MessageChainKey, OutputKey = MessageKey>,
     DHPublicKey: Clone + Eq + Hash,
+    Store: SkippedKeyStore<DHPublicKey, MessageKey>,
 {
     //noinspection RsFieldInitShorthand
     /// Initialize the double ratchet protocol for the receiving side, that gets the public key of the other party
@@ -377,69 +910,173 @@ where
     /// - `dh_generator` a pre-shared publicly known value of the Diffie-Hellman-Scheme key space used as generator
     /// - `received_dh_public_key` the other party's Diffie-Hellman public key, that kicks off the DH-Ratchet
     /// - `initial_root_chain_key` the initial common root key of both parties, that was agreed upon off the record.
-    pub fn initialize_receiving<R>(
+    /// - `cipher_suite` the primitives of the session, which both parties must agree upon
+    /// - `skipped_key_store` the store for message keys of skipped messages
+    ///
+    /// # Returns
+    /// The protocol or a `KeyValidationException` if `received_dh_public_key` is rejected by the Diffie-Hellman scheme
+    pub fn initialize_receiving_with_store<R>(
         rng: &mut R,
         dh_generator: DHPublicKey,
         received_dh_public_key: DHPublicKey,
         initial_root_chain_key: RootChainKey,
-    ) -> Self
+        cipher_suite: CipherSuite,
+        skipped_key_store: Store,
+    ) -> Result<Self, KeyValidationException>
+    where
+        R: RngCore + CryptoRng,
+        DHScheme: 'static,
+        DHPublicKey: Send,
+    {
+        Self::initialize_receiving_with_key_pair_source(
+            RngKeyPairSource::new(seed_key_pair_rng(rng), dh_generator),
+            received_dh_public_key,
+            initial_root_chain_key,
+            cipher_suite,
+            skipped_key_store,
+        )
+    }
+
+    /// Initialize the double ratchet protocol for the receiving side like `initialize_receiving_with_store`, but
+    /// generate all Diffie-Hellman key pairs using a precomputation of the generator. The precomputation can be
+    /// shared between protocols using the same generator.
+    /// # Parameters
+    /// - `rng` a cryptographically secure random number generator
+    /// - `precomputation` the precomputed pre-shared generator of the Diffie-Hellman-Scheme
+    /// - `received_dh_public_key` the other party's Diffie-Hellman public key, that kicks off the DH-Ratchet
+    /// - `initial_root_chain_key` the initial common root key of both parties, that was agreed upon off the record.
+    /// - `cipher_suite` the primitives of the session, which both parties must agree upon
+    /// - `skipped_key_store` the store for message keys of skipped messages
+    pub fn initialize_receiving_with_precomputation<R>(
+        rng: &mut R,
+        precomputation: Arc<DHScheme::Precomputation>,
+        received_dh_public_key: DHPublicKey,
+        initial_root_chain_key: RootChainKey,
+        cipher_suite: CipherSuite,
+        skipped_key_store: Store,
+    ) -> Result<Self, KeyValidationException>
     where
         R: RngCore + CryptoRng,
+        DHScheme: PrecomputedKeyExchangeScheme + 'static,
+        DHScheme::Precomputation: Send + Sync + 'static,
+        DHPublicKey: Send,
+    {
+        Self::initialize_receiving_with_key_pair_source(
+            RngKeyPairSource::with_precomputation(seed_key_pair_rng(rng), precomputation),
+            received_dh_public_key,
+            initial_root_chain_key,
+            cipher_suite,
+            skipped_key_store,
+        )
+    }
+
+    /// Initialize the double ratchet protocol for the receiving side like `initialize_receiving_with_store`, but
+    /// draw all Diffie-Hellman key pairs, including the initial one, from `key_pair_source`.
+    /// # Parameters
+    /// - `key_pair_source` the source of the Diffie-Hellman key pairs of the ratchet
+    /// - `received_dh_public_key` the other party's Diffie-Hellman public key, that kicks off the DH-Ratchet
+    /// - `initial_root_chain_key` the initial common root key of both parties, that was agreed upon off the record.
+    /// - `cipher_suite` the primitives of the session, which both parties must agree upon
+    /// - `skipped_key_store` the store for message keys of skipped messages
+    ///
+    /// # Returns
+    /// The protocol or a `KeyValidationException` if `received_dh_public_key` is rejected by the Diffie-Hellman scheme
+    pub fn initialize_receiving_with_key_pair_source<K>(
+        mut key_pair_source: K,
+        received_dh_public_key: DHPublicKey,
+        initial_root_chain_key: RootChainKey,
+        cipher_suite: CipherSuite,
+        skipped_key_store: Store,
+    ) -> Result<Self, KeyValidationException>
+    where
+        K: KeyPairSource<DHScheme> + Send + 'static,
     {
         // diffie hellman key exchange
-        let (generated_dh_private_key, generated_dh_public_key) =
-            DHScheme::generate_asymmetrical_key_pair(rng, &dh_generator);
-        let dh_shared_key =
-            DHScheme::generate_shared_secret(&generated_dh_private_key, &received_dh_public_key);
+        let (generated_dh_private_key, generated_dh_public_key) = key_pair_source.next_key_pair();
+        let dh_shared_key = DHScheme::generate_shared_secret_checked(
+            &generated_dh_private_key,
+            &received_dh_public_key,
+        )?;
 
         // root KDF initialization
         let (new_root_key, sending_key) =
             RootKdf::derive_key(initial_root_chain_key, dh_shared_key);
 
-        Self {
+        Ok(Self {
             state: PhantomData,
             diffie_hellman_scheme: PhantomData,
             encryption_scheme: PhantomData,
             root_chain: PhantomData,
             message_chains: PhantomData,
-            diffie_hellman_generator: dh_generator,
+            key_pair_source: Box::new(key_pair_source),
             diffie_hellman_public_key: generated_dh_public_key,
-            diffie_hellman_private_key: Some(generated_dh_private_key),
+            diffie_hellman_private_key: Redacted::new(Some(generated_dh_private_key)),
             diffie_hellman_received_key: Some(received_dh_public_key),
-            root_chain_key: Some(new_root_key),
-            sending_chain_key: Some(sending_key),
-            receiving_chain_key: None,
+            root_chain_key: Redacted::new(Some(new_root_key)),
+            sending_chain_key: Redacted::new(Some(sending_key)),
+            receiving_chain_key: Redacted::new(None),
             sending_chain_length: 0,
             receiving_chain_length: 0,
             previous_sending_chain_length: 0,
             previous_receiving_chain_length: 0,
-            missed_messages: HashMap::new(),
-        }
+            missed_messages: skipped_key_store,
+            received_messages: ReceivedMessages::new(),
+            padding: PaddingScheme::None,
+            cipher_suite,
+            #[cfg(feature = "transcript")]
+            transcript: None,
+            #[cfg(feature = "resumption")]
+            resumption: None,
+        })
     }
 
     /// Send a message to the other protocol party. This must be done at least once to allow the other party to
-    /// establish their ratchets.
+    /// establish their ratchets. After a Diffie-Hellman ratchet step, the root chain only derives the new sending
+    /// chain when its first message is encrypted, so the root chain keys of both parties agree while no message is
+    /// in flight.
     /// # Parameters
-    /// - `rng` a cryptographically secure random number generator
     /// - `message` the message clear text that gets encrypted and sent
     pub fn encrypt_message(
         &mut self,
         message: &[u8],
     ) -> DoubleRatchetAlgorithmMessage<DHPublicKey, Vec<u8>> {
+        // finish the Diffie-Hellman ratchet step of the last received public key
+        if self.sending_chain_key.is_none() {
+            let dh_shared_key = DHScheme::generate_shared_secret(
+                self.diffie_hellman_private_key.as_ref().unwrap(),
+                self.diffie_hellman_received_key.as_ref().unwrap(),
+            );
+            let (updated_root_key, sending_chain_key) =
+                RootKdf::derive_key(self.root_chain_key.take().unwrap(), dh_shared_key);
+            *self.root_chain_key = Some(updated_root_key);
+            *self.sending_chain_key = Some(sending_chain_key);
+        }
+
         // update sending ratchet
         let (updated_sending_chain_key, message_key) =
-            MessageKdf::derive_key_without_input(self.sending_chain_key.take().unwrap());
-        self.sending_chain_key = Some(updated_sending_chain_key);
+            derive_message_key::<MessageKdf>(self.sending_chain_key.take().unwrap());
+        *self.sending_chain_key = Some(updated_sending_chain_key);
+        let message_key = message_key.use_once();
 
         let current_message_number = self.sending_chain_length;
 
         // update statistics
         self.sending_chain_length += 1;
 
-        // encrypt message
-        let cipher_text = EncryptionScheme::encrypt_message(&message_key, message);
+        #[cfg(feature = "transcript")]
+        self.record_transcript(
+            transcript::Direction::Sent,
+            &self.diffie_hellman_public_key,
+            current_message_number,
+            self.previous_sending_chain_length,
+            &message_key,
+        );
+
+        // pad and encrypt message
+        let cipher_text = EncryptionScheme::encrypt_message(&message_key, &self.padding.pad(message));
 
         DoubleRatchetAlgorithmMessage {
+            cipher_suite: self.cipher_suite,
             public_key: self.diffie_hellman_public_key.clone(),
             message_number: current_message_number,
             previous_chain_length: self.previous_sending_chain_length,
@@ -448,16 +1085,65 @@ where
     }
 
     /// Decrypt a message from the other party that has actual user content. It will fully establish the
-    /// protocol by initializing the receiving chain.
-    pub fn decrypt_message<R>(
+    /// protocol by initializing the receiving chain. If the message causes a Diffie-Hellman ratchet step, the key
+    /// pair of the next sending chain is drawn from the protocol's `KeyPairSource`.
+    pub fn decrypt_message(
         &mut self,
-        rng: &mut R,
+        message: DoubleRatchetAlgorithmMessage<DHPublicKey, Vec<u8>>,
+    ) -> Result<Vec<u8>, DecryptionException>
+    where
+        RootChainKey: RollbackKey,
+        MessageChainKey: RollbackKey,
+    {
+        self.decrypt_message_with_event(message)
+            .map(|(clear_text, _)| clear_text)
+    }
+
+    /// Decrypt a message like `decrypt_message`. The `rng` is not used, key pairs are drawn from the protocol's
+    /// `KeyPairSource`.
+    #[deprecated(note = "use `decrypt_message`, which draws key pairs from the protocol's `KeyPairSource`")]
+    pub fn decrypt_message_using_rng<R>(
+        &mut self,
+        _rng: &mut R,
         message: DoubleRatchetAlgorithmMessage<DHPublicKey, Vec<u8>>,
     ) -> Result<Vec<u8>, DecryptionException>
     where
         R: RngCore + CryptoRng,
+        RootChainKey: RollbackKey,
+        MessageChainKey: RollbackKey,
+    {
+        self.decrypt_message(message)
+    }
+
+    /// Decrypt a message like `decrypt_message`, but additionally report whether the message caused a
+    /// Diffie-Hellman ratchet step. Out-of-order messages never cause a ratchet step and are still reported through
+    /// `DecryptionException::OutOfOrderMessage`. A message that was already decrypted is rejected with
+    /// `DecryptionException::ReplayedMessage`, a message of another cipher suite with
+    /// `DecryptionException::SuiteMismatch` and a message without cipher text with
+    /// `DecryptionException::InvalidMessageHeader`, all without changing the protocol state. The chains only advance
+    /// once the message was decrypted, so a forged message rejected with `DecryptionException::AuthenticationFailed`
+    /// or `DecryptionException::InvalidPadding` leaves the protocol state unchanged as well.
+    pub fn decrypt_message_with_event(
+        &mut self,
+        message: DoubleRatchetAlgorithmMessage<DHPublicKey, Vec<u8>>,
+    ) -> Result<(Vec<u8>, RatchetEvent<DHPublicKey>), DecryptionException>
+    where
+        RootChainKey: RollbackKey,
+        MessageChainKey: RollbackKey,
     {
-        let (mut current_chain_missed_messages, mut next_chain_missed_messages) =
+        check_cipher_suite(self.cipher_suite, &message)?;
+        let cipher_text = cipher_text(&message)?;
+
+        if self
+            .received_messages
+            .contains(&message.public_key, message.message_number)
+        {
+            return Err(ReplayedMessage {
+                message_number: message.message_number,
+            });
+        }
+
+        let (current_chain_missed_messages, next_chain_missed_messages) =
             match detect_missing_messages(self, &message) {
                 Ok(v) => v,
                 Err(ProtocolException::IllegalMessageHeader { message }) => {
@@ -467,112 +1153,359 @@ where
                     public_key,
                     message_number,
                 }) => {
-                    let dictionary_key = (public_key, message_number);
-                    if !self.missed_messages.contains_key(&dictionary_key) {
-                        return Err(UnknownMessageHeader {});
-                    }
+                    let message_key = match self.missed_messages.take(&public_key, message_number) {
+                        Some(message_key) => message_key.use_once(),
+                        None => return Err(UnknownMessageHeader {}),
+                    };
 
-                    let message_key = self.missed_messages.remove(&dictionary_key).unwrap();
                     let decrypted_message =
-                        EncryptionScheme::decrypt_message(&message_key, &message.message.unwrap());
+                        match open_message::<EncryptionScheme>(self.padding, &message_key, cipher_text) {
+                            Ok(decrypted_message) => decrypted_message,
+                            Err(exception) => {
+                                // a forged message must not use up the key of the real message
+                                self.missed_messages.insert(
+                                    public_key,
+                                    message_number,
+                                    OneTimeKey::new(message_key),
+                                );
+                                return Err(exception);
+                            }
+                        };
+
+                    #[cfg(feature = "transcript")]
+                    self.record_transcript(
+                        transcript::Direction::Received,
+                        &public_key,
+                        message_number,
+                        message.previous_chain_length,
+                        &message_key,
+                    );
+
+                    self.received_messages.insert(public_key, message_number);
                     return Err(OutOfOrderMessage { decrypted_message });
                 }
             };
 
-        // insert missing message keys into missed_messages dictionary
-        while current_chain_missed_messages > 0 {
-            let (new_chain_key, output_key) =
-                MessageKdf::derive_key_without_input(self.receiving_chain_key.take().unwrap());
-            self.receiving_chain_key = Some(new_chain_key);
-            self.receiving_chain_length += 1;
-            self.missed_messages.insert(
-                (
-                    self.diffie_hellman_received_key.clone().unwrap(),
-                    self.receiving_chain_length,
-                ),
-                output_key,
-            );
-            current_chain_missed_messages -= 1;
-        }
-
-        // if this message contains a new public key
-        let message_key = if self.diffie_hellman_received_key.is_none()
+        // validate a new public key before the protocol state is changed
+        let is_new_public_key = self.diffie_hellman_received_key.is_none()
             || !message
                 .public_key
-                .eq(self.diffie_hellman_received_key.as_ref().unwrap())
-        {
-            // update diffie-hellman-ratchet
-            let generated_dh_private_key = DHScheme::generate_shared_secret(
-                &self.diffie_hellman_private_key.take().unwrap(),
-                &message.public_key,
-            );
+                .eq(self.diffie_hellman_received_key.as_ref().unwrap());
+        let ratchet_dh_shared_key = if is_new_public_key {
+            Some(
+                DHScheme::generate_shared_secret_checked(
+                    self.diffie_hellman_private_key.as_ref().unwrap(),
+                    &message.public_key,
+                )
+                .map_err(|_| DecryptionException::InvalidMessageHeader {})?,
+            )
+        } else {
+            None
+        };
 
-            // update receiving chain
-            let (updated_root_key, mut receiving_chain_key) = RootKdf::derive_key(
-                self.root_chain_key.take().unwrap(),
-                generated_dh_private_key,
-            );
-            self.receiving_chain_length = 0;
-
-            // if messages of this new chain were missed:
-            while next_chain_missed_messages > 0 {
-                self.receiving_chain_length += 1;
-                let (updated_receiving_chain_key, message_key) =
-                    MessageKdf::derive_key_without_input(receiving_chain_key);
-                receiving_chain_key = updated_receiving_chain_key;
-                self.missed_messages.insert(
-                    (message.public_key.clone(), self.receiving_chain_length),
-                    message_key,
+        // all keys are derived from copies of the chain keys and skipped message keys are staged, so a message that
+        // fails to decrypt leaves the protocol state unchanged
+        let mut skipped_keys = StagedKeys::new();
+        let mut receiving_chain_length = self.receiving_chain_length;
+        let mut receiving_chain_key = self
+            .receiving_chain_key
+            .as_ref()
+            .map(RollbackKey::rollback_copy);
+
+        // skip the missing messages of the current receiving chain
+        if current_chain_missed_messages > 0 {
+            let until = receiving_chain_length + current_chain_missed_messages;
+            receiving_chain_key = Some(skip_message_keys::<MessageKdf, _, _>(
+                receiving_chain_key.unwrap(),
+                self.diffie_hellman_received_key.as_ref().unwrap(),
+                receiving_chain_length,
+                until,
+                &mut skipped_keys,
+            ));
+            receiving_chain_length = until;
+        }
+
+        // if this message contains a new public key, derive the new receiving chain and skip its missing messages
+        let (updated_root_key, receiving_chain_key) =
+            if let Some(generated_dh_shared_key) = ratchet_dh_shared_key {
+                let (updated_root_key, receiving_chain_key) = RootKdf::derive_key(
+                    self.root_chain_key.as_ref().unwrap().rollback_copy(),
+                    generated_dh_shared_key,
                 );
-                next_chain_missed_messages -= 1;
-            }
+                let receiving_chain_key = skip_message_keys::<MessageKdf, _, _>(
+                    receiving_chain_key,
+                    &message.public_key,
+                    0,
+                    next_chain_missed_messages,
+                    &mut skipped_keys,
+                );
+                (Some(updated_root_key), receiving_chain_key)
+            } else {
+                (None, receiving_chain_key.unwrap())
+            };
+        let (updated_receiving_chain_key, message_key) =
+            derive_message_key::<MessageKdf>(receiving_chain_key);
 
-            let (updated_receiving_chain_key, message_key) =
-                MessageKdf::derive_key_without_input(receiving_chain_key);
-            self.receiving_chain_key = Some(updated_receiving_chain_key);
+        // decrypt message and remove its padding
+        let message_key = message_key.use_once();
+        let clear_text = open_message::<EncryptionScheme>(self.padding, &message_key, cipher_text)?;
 
-            // update sending chain
-            let (new_dh_private_key, new_dh_public_key) =
-                DHScheme::generate_asymmetrical_key_pair(rng, &self.diffie_hellman_generator);
-            let new_dh_shared_key =
-                DHScheme::generate_shared_secret(&new_dh_private_key, &message.public_key);
-            let (updated_root_key, sending_chain_key) =
-                RootKdf::derive_key(updated_root_key, new_dh_shared_key);
-            self.sending_chain_key = Some(sending_chain_key);
+        // the message is authentic, so the derived keys replace the chain keys
+        skipped_keys.commit(&mut self.missed_messages);
+        *self.receiving_chain_key = Some(updated_receiving_chain_key);
+
+        let event = if let Some(updated_root_key) = updated_root_key {
+            self.previous_receiving_chain_length = receiving_chain_length;
+
+            // generate the key pair of the next sending chain, which is derived by the next call of
+            // `encrypt_message`
+            let (new_dh_private_key, new_dh_public_key) = self.key_pair_source.next_key_pair();
+            *self.sending_chain_key = None;
 
             // update dh keys
             self.diffie_hellman_public_key = new_dh_public_key;
-            self.diffie_hellman_private_key = Some(new_dh_private_key);
+            *self.diffie_hellman_private_key = Some(new_dh_private_key);
+            self.diffie_hellman_received_key = Some(message.public_key.clone());
 
             // update root chain
-            self.root_chain_key = Some(updated_root_key);
+            *self.root_chain_key = Some(updated_root_key);
 
             // update stats
-            self.previous_receiving_chain_length = self.receiving_chain_length;
             self.previous_sending_chain_length = self.sending_chain_length;
             self.sending_chain_length = 0;
-            self.receiving_chain_length = 1;
+            self.receiving_chain_length = next_chain_missed_messages + 1;
 
-            message_key
+            RatchetEvent::RatchetStepped {
+                new_remote_key: message.public_key.clone(),
+            }
         } else {
-            // if this message does contain a known public key
-            // update receiving chain
-            let (updated_receiving_chain_key, message_key) =
-                MessageKdf::derive_key_without_input(self.receiving_chain_key.take().unwrap());
-            self.receiving_chain_key = Some(updated_receiving_chain_key);
-
-            // update stats
-            self.receiving_chain_length += 1;
-
-            message_key
+            self.receiving_chain_length = receiving_chain_length + 1;
+            RatchetEvent::SameChain
         };
 
-        // decrypt message
-        Ok(EncryptionScheme::decrypt_message(
+        #[cfg(feature = "transcript")]
+        self.record_transcript(
+            transcript::Direction::Received,
+            &message.public_key,
+            message.message_number,
+            message.previous_chain_length,
             &message_key,
-            &message.message.unwrap(),
-        ))
+        );
+
+        self.received_messages
+            .insert(message.public_key, message.message_number);
+        Ok((clear_text, event))
+    }
+
+    /// Decrypt a message like `decrypt_message_with_event`. The `rng` is not used, key pairs are drawn from the
+    /// protocol's `KeyPairSource`.
+    #[deprecated(note = "use `decrypt_message_with_event`, which draws key pairs from the protocol's `KeyPairSource`")]
+    pub fn decrypt_message_with_event_using_rng<R>(
+        &mut self,
+        _rng: &mut R,
+        message: DoubleRatchetAlgorithmMessage<DHPublicKey, Vec<u8>>,
+    ) -> Result<(Vec<u8>, RatchetEvent<DHPublicKey>), DecryptionException>
+    where
+        R: RngCore + CryptoRng,
+        RootChainKey: RollbackKey,
+        MessageChainKey: RollbackKey,
+    {
+        self.decrypt_message_with_event(message)
+    }
+
+    /// The number of messages sent in the current sending chain, i.e. since the last Diffie-Hellman ratchet step.
+    pub fn current_sending_chain_length(&self) -> usize {
+        self.sending_chain_length
     }
+
+    /// The number of messages received in the current receiving chain, including skipped messages.
+    pub fn current_receiving_chain_length(&self) -> usize {
+        self.receiving_chain_length
+    }
+
+    /// The number of message keys currently stored for skipped messages.
+    pub fn stored_skipped_keys(&self) -> usize {
+        self.missed_messages.len()
+    }
+
+    /// The own Diffie-Hellman public key, that is sent with every message of the current sending chain.
+    pub fn current_public_key(&self) -> &DHPublicKey {
+        &self.diffie_hellman_public_key
+    }
+}
+
+impl<
+        DHScheme,
+        EncryptionScheme,
+        RootKdf,
+        MessageKdf,
+        DHPublicKey,
+        DHPrivateKey,
+        DHSharedKey,
+        RootChainKey,
+        MessageChainKey,
+        MessageKey,
+    >
+    DoubleRatchetProtocol<
+        DHScheme,
+        EncryptionScheme,
+        RootKdf,
+        MessageKdf,
+        DHPublicKey,
+        DHPrivateKey,
+        DHSharedKey,
+        RootChainKey,
+        MessageChainKey,
+        MessageKey,
+        state::Established,
+    >
+where
+    DHScheme: DiffieHellmanKeyExchangeScheme<
+        PublicKey = DHPublicKey,
+        PrivateKey = DHPrivateKey,
+        SharedKey = DHSharedKey,
+    >,
+    EncryptionScheme: SymmetricalEncryptionScheme<Key = MessageKey>,
+    RootKdf: KeyDerivationFunction<
+        ChainKey = RootChainKey,
+        Input = DHSharedKey,
+        OutputKey = MessageChainKey,
+    >,
+    MessageKdf: ConstantInputKeyRatchet<ChainKey = MessageChainKey, OutputKey = MessageKey>,
+    DHPublicKey: Clone + Eq + Hash,
+{
+    /// Initialize the double ratchet protocol for the receiving side with an unbounded in-memory store for skipped
+    /// message keys. See `initialize_receiving_with_store`.
+    pub fn initialize_receiving<R>(
+        rng: &mut R,
+        dh_generator: DHPublicKey,
+        received_dh_public_key: DHPublicKey,
+        initial_root_chain_key: RootChainKey,
+        cipher_suite: CipherSuite,
+    ) -> Result<Self, KeyValidationException>
+    where
+        R: RngCore + CryptoRng,
+        DHScheme: 'static,
+        DHPublicKey: Send,
+    {
+        Self::initialize_receiving_with_store(
+            rng,
+            dh_generator,
+            received_dh_public_key,
+            initial_root_chain_key,
+            cipher_suite,
+            HashMapKeyStore::new(),
+        )
+    }
+}
+
+impl<
+        DHScheme,
+        EncryptionScheme,
+        RootKdf,
+        MessageKdf,
+        DHPublicKey,
+        DHPrivateKey,
+        DHSharedKey,
+        RootChainKey,
+        MessageChainKey,
+        MessageKey,
+        State,
+        Store,
+    >
+    DoubleRatchetProtocol<
+        DHScheme,
+        EncryptionScheme,
+        RootKdf,
+        MessageKdf,
+        DHPublicKey,
+        DHPrivateKey,
+        DHSharedKey,
+        RootChainKey,
+        MessageChainKey,
+        MessageKey,
+        State,
+        Store,
+    >
+where
+    DHScheme: DiffieHellmanKeyExchangeScheme<
+        PublicKey = DHPublicKey,
+        PrivateKey = DHPrivateKey,
+        SharedKey = DHSharedKey,
+    >,
+    EncryptionScheme: SymmetricalEncryptionScheme<Key = MessageKey>,
+    RootKdf: KeyDerivationFunction<
+        ChainKey = RootChainKey,
+        Input = DHSharedKey,
+        OutputKey = MessageChainKey,
+    >,
+    MessageKdf: ConstantInputKeyRatchet<ChainKey = MessageChainKey, OutputKey = MessageKey>,
+    DHPublicKey: Clone + Eq + Hash,
+    State: state::ProtocolState,
+    Store: SkippedKeyStore<DHPublicKey, MessageKey>,
+{
+    /// Set the policy for padding messages. It applies to all messages encrypted and decrypted from now on, so both
+    /// parties must use the same policy.
+    pub fn set_padding_scheme(&mut self, padding: PaddingScheme) {
+        self.padding = padding;
+    }
+
+    /// The policy for padding messages
+    pub fn padding_scheme(&self) -> PaddingScheme {
+        self.padding
+    }
+
+    /// The cipher suite of the session, which is sent with every message
+    pub fn cipher_suite(&self) -> CipherSuite {
+        self.cipher_suite
+    }
+}
+
+/// Verify that a received message was created by a protocol with the cipher suite `expected`.
+fn check_cipher_suite<K, C>(
+    expected: CipherSuite,
+    message: &DoubleRatchetAlgorithmMessage<K, C>,
+) -> Result<(), DecryptionException> {
+    if message.cipher_suite != expected {
+        return Err(SuiteMismatch {
+            got: message.cipher_suite,
+            expected,
+        });
+    }
+    Ok(())
+}
+
+/// The cipher text of a received message. Only the first message of the initiator carries none, and it is never
+/// decrypted, so a message without cipher text is rejected with `DecryptionException::InvalidMessageHeader`.
+fn cipher_text<K>(message: &DoubleRatchetAlgorithmMessage<K, Vec<u8>>) -> Result<&[u8], DecryptionException> {
+    message.message.as_deref().ok_or(InvalidMessageHeader {})
+}
+
+/// Decrypt the cipher text of a received message and remove its padding.
+///
+/// # Returns
+/// The clear text, `DecryptionException::AuthenticationFailed` if the encryption scheme rejects the cipher text and
+/// `DecryptionException::InvalidPadding` if the decrypted message is not padded according to `padding`
+fn open_message<EncryptionScheme>(
+    padding: PaddingScheme,
+    key: &EncryptionScheme::Key,
+    cipher_text: &[u8],
+) -> Result<Vec<u8>, DecryptionException>
+where
+    EncryptionScheme: SymmetricalEncryptionScheme,
+{
+    let padded = EncryptionScheme::try_decrypt_message(key, cipher_text)
+        .map_err(|cause| AuthenticationFailed { cause })?;
+    padding.unpad(&padded).ok_or(InvalidPadding {})
+}
+
+/// Seed the random number generator of the `RngKeyPairSource` of a protocol that was initialized with a borrowed
+/// random number generator.
+fn seed_key_pair_rng<R>(rng: &mut R) -> StdRng
+where
+    R: RngCore + CryptoRng,
+{
+    StdRng::from_rng(rng).expect("the random number generator failed to seed the key pair source")
 }
 
 /// Using an incoming message and the current protocol state, detect, whether any messages have been missed. This is
@@ -601,6 +1534,7 @@ fn detect_missing_messages<
     MessageChainKey,
     MessageKey,
     State,
+    Store,
 >(
     protocol: &DoubleRatchetProtocol<
         DHScheme,
@@ -614,6 +1548,7 @@ fn detect_missing_messages<
         MessageChainKey,
         MessageKey,
         State,
+        Store,
     >,
     message: &DoubleRatchetAlgorithmMessage<DHPublicKey, Vec<u8>>,
 ) -> Result<(usize, usize), ProtocolException<DHPublicKey>>
@@ -632,6 +1567,7 @@ where
     MessageKdf: ConstantInputKeyRatchet<ChainKey = MessageChainKey, OutputKey = MessageKey>,
     DHPublicKey: Clone + Eq + Hash,
     State: state::ProtocolState,
+    Store: SkippedKeyStore<DHPublicKey, MessageKey>,
 {
     if protocol.diffie_hellman_received_key.is_none() {
         // this is the first ever message received
@@ -651,6 +1587,12 @@ where
                 message_number: message.message_number,
             })
         }
+    } else if protocol.received_messages.contains_chain(&message.public_key) {
+        // this message belongs to an earlier chain, so it must not start a new one
+        Err(ProtocolException::OutOfOrderMessage {
+            public_key: message.public_key.clone(),
+            message_number: message.message_number,
+        })
     } else {
         if message.previous_chain_length >= protocol.receiving_chain_length {
             // this message starts a new chain
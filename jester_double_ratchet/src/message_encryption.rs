@@ -0,0 +1,149 @@
+//! An authenticated encryption scheme for the message keys of the double ratchet. Every message key is only used for a
+//! single message, so the key and the nonce of the Galois/Counter Mode are both derived from the message key with
+//! HKDF, as recommended by the specification of the Double Ratchet Algorithm. This module is only available with the
+//! `hash_kdf` feature.
+
+use std::marker::PhantomData;
+
+use jester_encryption::aes::gcm::{Gcm, GcmKey, NONCE_LENGTH};
+use jester_encryption::aes::BlockCipher;
+use jester_encryption::{AuthenticationException, SymmetricalEncryptionScheme};
+use jester_hashes::kdf::hkdf_derive_key;
+use jester_hashes::BlockHashFunction;
+use rand::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::chain_key::ChainKey;
+
+/// Separates the keys derived for message encryption from other keys derived from the same message key
+const ENCRYPTION_INFO: &[u8] = b"jester_double_ratchet message encryption";
+
+/// The length of the keys created by `generate_key`
+const GENERATED_KEY_LENGTH: usize = 32;
+
+/// The length of the authentication tags appended to the cipher texts
+const TAG_LENGTH: usize = 16;
+
+/// Encryption of messages with the block cipher `C` in Galois/Counter Mode, keyed with `ChainKey`s. The key and the
+/// nonce of the cipher are derived from the message key with HKDF over the hash function `H` in its default context.
+///
+/// A cipher text that fails authentication is rejected by `try_decrypt_message`, which the protocol reports as
+/// `DecryptionException::AuthenticationFailed`. The infallible `decrypt_message` decrypts it to an empty message
+/// instead.
+pub struct GcmMessageEncryption<H, C> {
+    hash: PhantomData<H>,
+    cipher: PhantomData<C>,
+}
+
+impl<H, C> GcmMessageEncryption<H, C>
+where
+    H: BlockHashFunction,
+    H::Context: Default,
+    C: BlockCipher,
+{
+    /// Derive the key and the nonce of the cipher from a message key.
+    fn expand_key(key: &ChainKey) -> (GcmKey<C>, [u8; NONCE_LENGTH]) {
+        let mut key_material = hkdf_derive_key::<H, H::Context>(
+            &H::Context::default(),
+            &[],
+            key.as_bytes(),
+            C::KEY_LENGTH + NONCE_LENGTH,
+            ENCRYPTION_INFO,
+        );
+
+        let cipher_key = GcmKey::new(&key_material[..C::KEY_LENGTH]);
+        let mut nonce = [0; NONCE_LENGTH];
+        nonce.copy_from_slice(&key_material[C::KEY_LENGTH..C::KEY_LENGTH + NONCE_LENGTH]);
+        key_material.zeroize();
+
+        (cipher_key, nonce)
+    }
+}
+
+impl<H, C> SymmetricalEncryptionScheme for GcmMessageEncryption<H, C>
+where
+    H: BlockHashFunction,
+    H::Context: Default,
+    C: BlockCipher,
+{
+    type Key = ChainKey;
+
+    fn generate_key<R>(rng: &mut R) -> Self::Key
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut key = vec![0; GENERATED_KEY_LENGTH];
+        rng.fill_bytes(&mut key);
+        ChainKey::new(key)
+    }
+
+    fn encrypt_message(key: &Self::Key, message: &[u8]) -> Vec<u8> {
+        let (cipher_key, nonce) = Self::expand_key(key);
+        Gcm::<C, TAG_LENGTH>::encrypt_with_iv(&cipher_key, &nonce, &[], message)
+    }
+
+    /// Decrypt a cipher text, or return an empty message if it fails authentication.
+    fn decrypt_message(key: &Self::Key, message: &[u8]) -> Vec<u8> {
+        Self::try_decrypt_message(key, message).unwrap_or_default()
+    }
+
+    fn try_decrypt_message(key: &Self::Key, message: &[u8]) -> Result<Vec<u8>, AuthenticationException> {
+        let (cipher_key, nonce) = Self::expand_key(key);
+        Gcm::<C, TAG_LENGTH>::decrypt_with_iv(&cipher_key, &nonce, &[], message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jester_encryption::aes::Aes256;
+    use jester_hashes::blake::blake2b::Blake2b;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    type Encryption = GcmMessageEncryption<Blake2b, Aes256>;
+
+    #[test]
+    fn test_round_trip() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let key = Encryption::generate_key(&mut rng);
+        let other_key = Encryption::generate_key(&mut rng);
+
+        let cipher = Encryption::encrypt_message(&key, b"attack at dawn");
+        assert_eq!(cipher.len(), b"attack at dawn".len() + TAG_LENGTH);
+        assert_eq!(
+            Encryption::decrypt_message(&key, &cipher),
+            b"attack at dawn"
+        );
+
+        // the nonce is derived from the key, so encryption is deterministic for a key
+        assert_eq!(Encryption::encrypt_message(&key, b"attack at dawn"), cipher);
+        assert_ne!(
+            Encryption::encrypt_message(&other_key, b"attack at dawn"),
+            cipher
+        );
+    }
+
+    #[test]
+    fn test_tampered_cipher_text() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let key = Encryption::generate_key(&mut rng);
+        let mut cipher = Encryption::encrypt_message(&key, b"attack at dawn");
+        cipher[0] ^= 1;
+
+        assert!(Encryption::decrypt_message(&key, &cipher).is_empty());
+        assert!(Encryption::decrypt_message(&key, &cipher[..TAG_LENGTH - 1]).is_empty());
+        assert!(
+            Encryption::decrypt_message(&Encryption::generate_key(&mut rng), &cipher).is_empty()
+        );
+
+        assert_eq!(
+            Encryption::try_decrypt_message(&key, &cipher),
+            Err(AuthenticationException::InvalidTag {})
+        );
+        assert_eq!(
+            Encryption::try_decrypt_message(&key, &cipher[..TAG_LENGTH - 1]),
+            Err(AuthenticationException::MissingTag {})
+        );
+    }
+}
@@ -0,0 +1,82 @@
+//! A container for message keys, which must be used for a single encryption or decryption only. Encrypting two
+//! messages with the same key breaks the confidentiality of stream cipher based schemes, so the protocol hands
+//! message keys around in a `OneTimeKey`, which releases its key exactly once.
+
+use std::fmt;
+
+use crate::ConstantInputKeyRatchet;
+
+/// A message key that can be used once. It does not implement `Clone`, and `use_once` consumes the container, so the
+/// key cannot be handed to the encryption scheme twice. Keys that are never used are a bug as well, for example a
+/// skipped message key that was taken from the store but not used for decryption. Therefore, dropping a key without
+/// using it panics in debug builds. Keys that are deliberately not used, like evicted skipped message keys, must be
+/// dropped with `discard`.
+pub struct OneTimeKey<K>(Option<K>);
+
+impl<K> OneTimeKey<K> {
+    /// Take ownership of the message `key`.
+    pub fn new(key: K) -> Self {
+        OneTimeKey(Some(key))
+    }
+
+    /// Release the key for its single use.
+    pub fn use_once(mut self) -> K {
+        self.0.take().unwrap()
+    }
+
+    /// Drop the key without using it.
+    pub fn discard(mut self) {
+        self.0.take();
+    }
+}
+
+/// The key is not printed.
+impl<K> fmt::Debug for OneTimeKey<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("OneTimeKey(<redacted>)")
+    }
+}
+
+impl<K> Drop for OneTimeKey<K> {
+    fn drop(&mut self) {
+        // a panic during unwinding would abort the process, so keys dropped by a panicking thread are ignored
+        if cfg!(debug_assertions) && self.0.is_some() && !std::thread::panicking() {
+            panic!("a one time key was dropped without being used");
+        }
+    }
+}
+
+/// Advance a message chain by one step and wrap the derived message key into a `OneTimeKey`.
+///
+/// # Returns
+/// The next chain key and the message key
+pub(crate) fn derive_message_key<MessageKdf>(
+    chain_key: MessageKdf::ChainKey,
+) -> (MessageKdf::ChainKey, OneTimeKey<MessageKdf::OutputKey>)
+where
+    MessageKdf: ConstantInputKeyRatchet,
+{
+    let (chain_key, message_key) = MessageKdf::derive_key_without_input(chain_key);
+    (chain_key, OneTimeKey::new(message_key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_use_once() {
+        let key = OneTimeKey::new(42);
+        assert_eq!(format!("{:?}", key), "OneTimeKey(<redacted>)");
+        assert_eq!(key.use_once(), 42);
+
+        OneTimeKey::new(43).discard();
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "a one time key was dropped without being used")]
+    fn test_drop_unused() {
+        drop(OneTimeKey::new(42));
+    }
+}
@@ -0,0 +1,132 @@
+//! Padding of message clear texts, so the length of a cipher text does not leak the exact length of its clear text.
+//! Messages are padded ISO/IEC 7816-4 style: a single `0x80` byte is appended, followed by zero bytes up to the
+//! padded length.
+
+/// The byte that marks the start of the padding
+const PADDING_MARKER: u8 = 0x80;
+
+/// A policy for padding message clear texts before encryption. Both parties of a protocol exchange must use the same
+/// policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum PaddingScheme {
+    /// Messages are not padded. Compatible with peers that do not pad messages.
+    #[default]
+    None,
+
+    /// Messages are padded to the next multiple of the given block length. The block length must not be zero.
+    PadToMultiple(usize),
+
+    /// Messages are padded to the smallest bucket length they fit in. The bucket lengths must be sorted in ascending
+    /// order. Messages that exceed the largest bucket are padded to a multiple of the largest bucket.
+    PadToBuckets(&'static [usize]),
+}
+
+impl PaddingScheme {
+    /// Pad the `message` according to this policy. At least one byte of padding is always added, unless the policy is
+    /// `PaddingScheme::None`.
+    pub fn pad(&self, message: &[u8]) -> Vec<u8> {
+        if *self == PaddingScheme::None {
+            return message.to_vec();
+        }
+
+        let padded_length = self.padded_length(message.len() + 1);
+        let mut padded = Vec::with_capacity(padded_length);
+        padded.extend_from_slice(message);
+        padded.push(PADDING_MARKER);
+        padded.resize(padded_length, 0);
+        padded
+    }
+
+    /// Remove the padding from a `padded` message. Returns `None` if the padding is invalid, i.e. the message length
+    /// is not a padded length of this policy or the padding is malformed. The padding is examined completely,
+    /// regardless of where it is malformed.
+    pub fn unpad(&self, padded: &[u8]) -> Option<Vec<u8>> {
+        if *self == PaddingScheme::None {
+            return Some(padded.to_vec());
+        }
+
+        // position of the padding marker. While scanning from the back, `in_padding` is one until the first non-zero
+        // byte was found.
+        let mut marker_position = 0;
+        let mut in_padding = 1u8;
+        let mut valid = 1u8;
+        for (index, byte) in padded.iter().enumerate().rev() {
+            let is_zero = (*byte == 0) as u8;
+            let is_marker = (*byte == PADDING_MARKER) as u8;
+            let ends_padding = in_padding & (1 - is_zero);
+
+            valid &= 1 - (ends_padding & (1 - is_marker));
+            marker_position = if ends_padding == 1 {
+                index
+            } else {
+                marker_position
+            };
+            in_padding &= is_zero;
+        }
+        valid &= 1 - in_padding;
+        valid &= (self.padded_length(marker_position + 1) == padded.len()) as u8;
+
+        if valid == 1 {
+            Some(padded[..marker_position].to_vec())
+        } else {
+            None
+        }
+    }
+
+    /// The padded length of a message of length `length`, including the padding marker
+    fn padded_length(&self, length: usize) -> usize {
+        match *self {
+            PaddingScheme::None => length,
+            PaddingScheme::PadToMultiple(block_length) => {
+                assert!(
+                    block_length > 0,
+                    "the padding block length must not be zero"
+                );
+                length.div_ceil(block_length) * block_length
+            }
+            PaddingScheme::PadToBuckets(buckets) => {
+                let largest = *buckets
+                    .last()
+                    .expect("at least one padding bucket is required");
+                buckets
+                    .iter()
+                    .copied()
+                    .find(|bucket| *bucket >= length)
+                    .unwrap_or_else(|| length.div_ceil(largest) * largest)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets() {
+        let scheme = PaddingScheme::PadToBuckets(&[16, 64, 256]);
+
+        assert_eq!(scheme.pad(&[]).len(), 16);
+        assert_eq!(scheme.pad(&[1; 15]).len(), 16);
+        assert_eq!(scheme.pad(&[1; 16]).len(), 64);
+        assert_eq!(scheme.pad(&[1; 256]).len(), 512);
+
+        for length in 0..300 {
+            let message: Vec<u8> = (0..length).map(|i| i as u8).collect();
+            assert_eq!(scheme.unpad(&scheme.pad(&message)), Some(message));
+        }
+    }
+
+    #[test]
+    fn test_invalid_padding() {
+        let scheme = PaddingScheme::PadToMultiple(8);
+
+        assert_eq!(scheme.unpad(&[1, 2, 0x80, 0, 0, 0, 0, 0]), Some(vec![1, 2]));
+        assert_eq!(scheme.unpad(&[1, 2, 0x80, 0, 0, 0, 1, 0]), None);
+        assert_eq!(scheme.unpad(&[1, 2, 0x81, 0, 0, 0, 0, 0]), None);
+        assert_eq!(scheme.unpad(&[0; 8]), None);
+        assert_eq!(scheme.unpad(&[1, 2, 0x80, 0, 0, 0, 0]), None);
+        assert_eq!(scheme.unpad(&[]), None);
+        assert_eq!(PaddingScheme::None.unpad(&[1, 0]), Some(vec![1, 0]));
+    }
+}
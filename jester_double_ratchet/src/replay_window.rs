@@ -0,0 +1,160 @@
+//! Detection of replayed messages. The message numbers already decrypted are recorded per receiving chain, so a
+//! second delivery of a message is rejected as a replay instead of being treated as an unknown out-of-order message.
+//! Only a bounded window of the most recent message numbers of each chain and a bounded number of chains are
+//! tracked, so the memory required does not grow with the length of a conversation.
+
+use std::collections::VecDeque;
+
+/// The number of message numbers below the highest decrypted message number of a chain that are tracked. A replay
+/// of an older message cannot be recognized as such, but its message key was already consumed, so it is still
+/// rejected as `DecryptionException::UnknownMessageHeader`.
+pub const REPLAY_WINDOW_SIZE: usize = 1024;
+
+/// The number of receiving chains whose decrypted messages are tracked. Messages of older chains cannot be
+/// recognized as replays.
+pub const TRACKED_CHAINS: usize = 16;
+
+const WORD_BITS: usize = 64;
+
+/// A bitmap of the message numbers decrypted within one receiving chain, covering the `REPLAY_WINDOW_SIZE` numbers
+/// up to the highest decrypted message number. The bitmap is used as a ring buffer indexed by the message number.
+#[derive(Debug, Clone)]
+pub struct ReplayWindow {
+    highest: usize,
+    bitmap: [u64; REPLAY_WINDOW_SIZE / WORD_BITS],
+}
+
+impl ReplayWindow {
+    /// Create a window containing only `message_number`.
+    pub fn new(message_number: usize) -> Self {
+        let mut window = ReplayWindow {
+            highest: message_number,
+            bitmap: [0; REPLAY_WINDOW_SIZE / WORD_BITS],
+        };
+        window.set(message_number, true);
+        window
+    }
+
+    /// Whether `message_number` was recorded and is still within the window.
+    pub fn contains(&self, message_number: usize) -> bool {
+        message_number <= self.highest
+            && self.highest - message_number < REPLAY_WINDOW_SIZE
+            && self.get(message_number)
+    }
+
+    /// Record `message_number` as decrypted. Numbers above the highest recorded number move the window, forgetting
+    /// the numbers that fall out of it.
+    pub fn insert(&mut self, message_number: usize) {
+        if message_number > self.highest {
+            // clear the bits of the numbers between the old and the new highest number, which wrap around the ring
+            for cleared in (self.highest + 1..message_number).take(REPLAY_WINDOW_SIZE) {
+                self.set(cleared, false);
+            }
+            self.highest = message_number;
+        } else if self.highest - message_number >= REPLAY_WINDOW_SIZE {
+            return;
+        }
+
+        self.set(message_number, true);
+    }
+
+    fn get(&self, message_number: usize) -> bool {
+        let bit = message_number % REPLAY_WINDOW_SIZE;
+        self.bitmap[bit / WORD_BITS] & (1 << (bit % WORD_BITS)) != 0
+    }
+
+    fn set(&mut self, message_number: usize, value: bool) {
+        let bit = message_number % REPLAY_WINDOW_SIZE;
+        if value {
+            self.bitmap[bit / WORD_BITS] |= 1 << (bit % WORD_BITS);
+        } else {
+            self.bitmap[bit / WORD_BITS] &= !(1 << (bit % WORD_BITS));
+        }
+    }
+}
+
+/// The replay windows of the `TRACKED_CHAINS` most recent receiving chains, identified by the Diffie-Hellman public
+/// key of the sending party.
+pub(crate) struct ReceivedMessages<K> {
+    chains: VecDeque<(K, ReplayWindow)>,
+}
+
+impl<K> ReceivedMessages<K>
+where
+    K: Eq,
+{
+    pub(crate) fn new() -> Self {
+        ReceivedMessages {
+            chains: VecDeque::with_capacity(TRACKED_CHAINS),
+        }
+    }
+
+    /// Whether a message of the chain of `public_key` was decrypted before.
+    pub(crate) fn contains_chain(&self, public_key: &K) -> bool {
+        self.chains.iter().any(|(key, _)| key == public_key)
+    }
+
+    /// Whether the message with `message_number` of the chain of `public_key` was decrypted before.
+    pub(crate) fn contains(&self, public_key: &K, message_number: usize) -> bool {
+        self.chains
+            .iter()
+            .any(|(key, window)| key == public_key && window.contains(message_number))
+    }
+
+    /// Record a decrypted message. If its chain is not tracked yet, the oldest chain is forgotten if necessary.
+    pub(crate) fn insert(&mut self, public_key: K, message_number: usize) {
+        if let Some((_, window)) = self.chains.iter_mut().find(|(key, _)| *key == public_key) {
+            window.insert(message_number);
+            return;
+        }
+
+        if self.chains.len() == TRACKED_CHAINS {
+            self.chains.pop_front();
+        }
+        self.chains
+            .push_back((public_key, ReplayWindow::new(message_number)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_moves() {
+        let mut window = ReplayWindow::new(3);
+        assert!(window.contains(3));
+        assert!(!window.contains(2));
+        assert!(!window.contains(4));
+
+        window.insert(1);
+        window.insert(REPLAY_WINDOW_SIZE + 2);
+        assert!(window.contains(3));
+        assert!(!window.contains(1));
+        assert!(!window.contains(2));
+        assert!(window.contains(REPLAY_WINDOW_SIZE + 2));
+
+        // a number below the window is not recorded, and does not overwrite the bit of its ring position
+        window.insert(0);
+        assert!(!window.contains(0));
+        assert!(!window.contains(REPLAY_WINDOW_SIZE));
+
+        window.insert(10 * REPLAY_WINDOW_SIZE);
+        assert!(!window.contains(REPLAY_WINDOW_SIZE + 2));
+        assert!(!window.contains(9 * REPLAY_WINDOW_SIZE));
+        assert!(window.contains(10 * REPLAY_WINDOW_SIZE));
+    }
+
+    #[test]
+    fn test_tracked_chains_bounded() {
+        let mut received = ReceivedMessages::new();
+        for chain in 0..=TRACKED_CHAINS {
+            received.insert(chain, 0);
+        }
+
+        assert!(!received.contains_chain(&0));
+        assert!(!received.contains(&0, 0));
+        assert!(received.contains(&1, 0));
+        assert!(received.contains(&TRACKED_CHAINS, 0));
+    }
+}
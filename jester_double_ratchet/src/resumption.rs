@@ -0,0 +1,370 @@
+//! Resumption of a stale session without a new key agreement. A client that was offline for a long time has old
+//! Diffie-Hellman keys and possibly a large number of skipped message keys. Instead of agreeing on a new initial root
+//! key, both parties re-key their session from a resumption secret, which they exported with
+//! `export_resumption_secret` while their root chain keys agreed, and a fresh Diffie-Hellman exchange:
+//!
+//! 1. one party sends a `ResumptionRequest` created by `initiate_resumption`, containing a new public key
+//! 2. the peer verifies it with `accept_resumption`, re-keys its session and answers with a `ResumptionResponse`
+//!    containing its own new public key
+//! 3. the initiating party verifies the response with `complete_resumption` and re-keys its session
+//!
+//! Both messages are authenticated with an HMAC keyed by the resumption secret. The new root chain key is derived by
+//! the root KDF of the protocol from the resumption secret and the Diffie-Hellman output of the new keys. Afterwards,
+//! the session continues like a new one: the chain lengths are zero and the skipped message keys and the replay
+//! window of the old session are discarded, so messages of the old session still in flight cannot be decrypted
+//! anymore. Either party may send first.
+//!
+//! If a request or response is rejected, the session is not changed and can be used as before. This module is only
+//! available with the `resumption` feature.
+
+use std::hash::Hash;
+
+use jester_encryption::diffie_hellman::{DhKeyCodec, DiffieHellmanKeyExchangeScheme};
+use jester_encryption::SymmetricalEncryptionScheme;
+use jester_hashes::hmac::hmac;
+use jester_hashes::{constant_time_eq, BlockHashFunction};
+use jester_util::Redacted;
+
+use crate::chain_key::{FromKeyMaterial, KeyMaterial};
+use crate::cipher_suite::CipherSuite;
+use crate::replay_window::ReceivedMessages;
+use crate::skipped_keys::SkippedKeyStore;
+use crate::{state, ConstantInputKeyRatchet, DoubleRatchetProtocol, KeyDerivationFunction};
+
+/// The label of the resumption secret exported by `export_resumption_secret`
+pub const RESUMPTION_LABEL: &[u8] = b"jester double ratchet resumption";
+
+/// The length of the resumption secret in bytes
+pub const RESUMPTION_SECRET_LENGTH: usize = 32;
+
+/// Prefix of the authenticated data of a request
+const REQUEST_DOMAIN: &[u8] = b"jester double ratchet resumption request";
+
+/// Prefix of the authenticated data of a response
+const RESPONSE_DOMAIN: &[u8] = b"jester double ratchet resumption response";
+
+/// The request to resume a session, created by `initiate_resumption`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResumptionRequest<K> {
+    pub cipher_suite: CipherSuite,
+
+    /// The new Diffie-Hellman public key of the initiating party
+    pub public_key: K,
+
+    /// The HMAC of the suite and the public key, keyed by the resumption secret
+    pub mac: Vec<u8>,
+}
+
+/// The answer to a `ResumptionRequest`, created by `accept_resumption`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResumptionResponse<K> {
+    pub cipher_suite: CipherSuite,
+
+    /// The new Diffie-Hellman public key of the accepting party
+    pub public_key: K,
+
+    /// The HMAC of the suite and the public keys of request and response, keyed by the resumption secret
+    pub mac: Vec<u8>,
+}
+
+/// Exceptions that can arise when resuming a session. The session is unchanged if any of them is returned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResumptionException {
+    /// The request or response was created by a protocol with a different cipher suite
+    SuiteMismatch {
+        got: CipherSuite,
+        expected: CipherSuite,
+    },
+
+    /// The MAC was not created with the same resumption secret, or the request or response was modified
+    InvalidMac {},
+
+    /// The public key was rejected by the Diffie-Hellman scheme
+    InvalidPublicKey {},
+
+    /// A response was given to a protocol that did not initiate a resumption
+    NoPendingResumption {},
+}
+
+/// The key pair of a request that was sent, but not answered yet
+pub(crate) struct PendingResumption<DHPublicKey, DHPrivateKey> {
+    public_key: DHPublicKey,
+    private_key: Redacted<DHPrivateKey>,
+}
+
+impl<
+        DHScheme,
+        EncryptionScheme,
+        RootKdf,
+        MessageKdf,
+        DHPublicKey,
+        DHPrivateKey,
+        DHSharedKey,
+        RootChainKey,
+        MessageChainKey,
+        MessageKey,
+        Store,
+    >
+    DoubleRatchetProtocol<
+        DHScheme,
+        EncryptionScheme,
+        RootKdf,
+        MessageKdf,
+        DHPublicKey,
+        DHPrivateKey,
+        DHSharedKey,
+        RootChainKey,
+        MessageChainKey,
+        MessageKey,
+        state::Established,
+        Store,
+    >
+where
+    DHScheme: DiffieHellmanKeyExchangeScheme<
+            PublicKey = DHPublicKey,
+            PrivateKey = DHPrivateKey,
+            SharedKey = DHSharedKey,
+        > + DhKeyCodec,
+    EncryptionScheme: SymmetricalEncryptionScheme<Key = MessageKey>,
+    RootKdf: KeyDerivationFunction<
+        ChainKey = RootChainKey,
+        Input = DHSharedKey,
+        OutputKey = MessageChainKey,
+    >,
+    MessageKdf: ConstantInputKeyRatchet<ChainKey = MessageChainKey, OutputKey = MessageKey>,
+    DHPublicKey: Clone + Eq + Hash,
+    Store: SkippedKeyStore<DHPublicKey, MessageKey>,
+    RootChainKey: KeyMaterial + FromKeyMaterial,
+{
+    /// Export the secret a later resumption of the session is authenticated with. It is exported with
+    /// `export_key_material` and `RESUMPTION_LABEL`, so both parties export the same secret while their root chain
+    /// keys agree. The secret must be stored as securely as the session itself.
+    pub fn export_resumption_secret<H>(&self) -> Vec<u8>
+    where
+        H: BlockHashFunction,
+        H::Context: Default,
+    {
+        self.export_key_material::<H>(RESUMPTION_LABEL, RESUMPTION_SECRET_LENGTH)
+    }
+
+    /// Start a resumption of the session. A new key pair is drawn from the protocol's `KeyPairSource` and kept until
+    /// the response is passed to `complete_resumption`. The session is not changed until then, so it can still be
+    /// used if the peer never answers. A later call replaces the pending request.
+    ///
+    /// # Parameters
+    /// - `resumption_secret` the secret exported by `export_resumption_secret`
+    ///
+    /// # Type Parameters
+    /// - `H` the hash function of the HMAC, which both parties must agree upon
+    pub fn initiate_resumption<H>(
+        &mut self,
+        resumption_secret: &[u8],
+    ) -> ResumptionRequest<DHPublicKey>
+    where
+        H: BlockHashFunction,
+        H::Context: Default,
+    {
+        let (private_key, public_key) = self.key_pair_source.next_key_pair();
+        let mac = hmac::<H, H::Context>(
+            &H::Context::default(),
+            resumption_secret,
+            &self.request_data(&public_key),
+        );
+
+        self.resumption = Some(PendingResumption {
+            public_key: public_key.clone(),
+            private_key: Redacted::new(private_key),
+        });
+
+        ResumptionRequest {
+            cipher_suite: self.cipher_suite,
+            public_key,
+            mac,
+        }
+    }
+
+    /// Verify a `ResumptionRequest` of the peer and re-key the session from the resumption secret and a new key
+    /// pair. The sending chain is derived immediately, so this party may send the first message of the resumed
+    /// session. A pending request of this party is discarded.
+    ///
+    /// # Parameters
+    /// - `resumption_secret` the secret exported by `export_resumption_secret`
+    /// - `request` the request of the peer
+    ///
+    /// # Returns
+    /// The response that must be sent to the peer, or a `ResumptionException` if the request is rejected
+    pub fn accept_resumption<H>(
+        &mut self,
+        resumption_secret: &[u8],
+        request: &ResumptionRequest<DHPublicKey>,
+    ) -> Result<ResumptionResponse<DHPublicKey>, ResumptionException>
+    where
+        H: BlockHashFunction,
+        H::Context: Default,
+    {
+        self.check_resumption_suite(request.cipher_suite)?;
+        verify_mac::<H>(
+            resumption_secret,
+            &self.request_data(&request.public_key),
+            &request.mac,
+        )?;
+
+        let (private_key, public_key) = self.key_pair_source.next_key_pair();
+        let dh_shared_key =
+            DHScheme::generate_shared_secret_checked(&private_key, &request.public_key)
+                .map_err(|_| ResumptionException::InvalidPublicKey {})?;
+
+        let (root_chain_key, sending_chain_key) = RootKdf::derive_key(
+            RootChainKey::from_key_material(resumption_secret.to_vec()),
+            dh_shared_key,
+        );
+
+        let mac = hmac::<H, H::Context>(
+            &H::Context::default(),
+            resumption_secret,
+            &self.response_data(&request.public_key, &public_key),
+        );
+
+        self.reset_session(
+            public_key.clone(),
+            private_key,
+            request.public_key.clone(),
+            root_chain_key,
+        );
+        *self.sending_chain_key = Some(sending_chain_key);
+
+        Ok(ResumptionResponse {
+            cipher_suite: self.cipher_suite,
+            public_key,
+            mac,
+        })
+    }
+
+    /// Verify the `ResumptionResponse` to the request of `initiate_resumption` and re-key the session from the
+    /// resumption secret and the new key pairs of both parties.
+    ///
+    /// # Parameters
+    /// - `resumption_secret` the secret exported by `export_resumption_secret`
+    /// - `response` the response of the peer
+    ///
+    /// # Returns
+    /// A `ResumptionException` if no resumption is pending or the response is rejected. The pending request is kept
+    /// in that case, so a valid response can still complete it.
+    pub fn complete_resumption<H>(
+        &mut self,
+        resumption_secret: &[u8],
+        response: &ResumptionResponse<DHPublicKey>,
+    ) -> Result<(), ResumptionException>
+    where
+        H: BlockHashFunction,
+        H::Context: Default,
+    {
+        let pending = self
+            .resumption
+            .as_ref()
+            .ok_or(ResumptionException::NoPendingResumption {})?;
+
+        self.check_resumption_suite(response.cipher_suite)?;
+        verify_mac::<H>(
+            resumption_secret,
+            &self.response_data(&pending.public_key, &response.public_key),
+            &response.mac,
+        )?;
+
+        let dh_shared_key =
+            DHScheme::generate_shared_secret_checked(&pending.private_key, &response.public_key)
+                .map_err(|_| ResumptionException::InvalidPublicKey {})?;
+
+        let (root_chain_key, receiving_chain_key) = RootKdf::derive_key(
+            RootChainKey::from_key_material(resumption_secret.to_vec()),
+            dh_shared_key,
+        );
+
+        // the key pair of the next sending chain, which is derived by the next call of `encrypt_message`
+        let (private_key, public_key) = self.key_pair_source.next_key_pair();
+        self.reset_session(
+            public_key,
+            private_key,
+            response.public_key.clone(),
+            root_chain_key,
+        );
+        *self.receiving_chain_key = Some(receiving_chain_key);
+
+        Ok(())
+    }
+
+    /// Replace the keys of the session, clear both message chains, the skipped message keys and the replay window,
+    /// and discard a pending resumption.
+    fn reset_session(
+        &mut self,
+        public_key: DHPublicKey,
+        private_key: DHPrivateKey,
+        received_key: DHPublicKey,
+        root_chain_key: RootChainKey,
+    ) {
+        self.diffie_hellman_public_key = public_key;
+        *self.diffie_hellman_private_key = Some(private_key);
+        self.diffie_hellman_received_key = Some(received_key);
+        *self.root_chain_key = Some(root_chain_key);
+        *self.sending_chain_key = None;
+        *self.receiving_chain_key = None;
+        self.sending_chain_length = 0;
+        self.receiving_chain_length = 0;
+        self.previous_sending_chain_length = 0;
+        self.previous_receiving_chain_length = 0;
+        while let Some((_, _, key)) = self.missed_messages.evict_oldest() {
+            key.discard();
+        }
+        self.received_messages = ReceivedMessages::new();
+        self.resumption = None;
+    }
+
+    fn check_resumption_suite(&self, got: CipherSuite) -> Result<(), ResumptionException> {
+        if got != self.cipher_suite {
+            return Err(ResumptionException::SuiteMismatch {
+                got,
+                expected: self.cipher_suite,
+            });
+        }
+        Ok(())
+    }
+
+    /// The data authenticated by the MAC of a request
+    fn request_data(&self, request_key: &DHPublicKey) -> Vec<u8> {
+        [
+            REQUEST_DOMAIN,
+            &self.cipher_suite.to_id().to_be_bytes(),
+            &DHScheme::encode_public_key(request_key),
+        ]
+        .concat()
+    }
+
+    /// The data authenticated by the MAC of a response, which binds the response to its request
+    fn response_data(&self, request_key: &DHPublicKey, response_key: &DHPublicKey) -> Vec<u8> {
+        [
+            RESPONSE_DOMAIN,
+            &self.cipher_suite.to_id().to_be_bytes(),
+            &DHScheme::encode_public_key(request_key),
+            &DHScheme::encode_public_key(response_key),
+        ]
+        .concat()
+    }
+}
+
+/// Compare `mac` in constant time with the HMAC of `data` keyed by `resumption_secret`.
+fn verify_mac<H>(
+    resumption_secret: &[u8],
+    data: &[u8],
+    mac: &[u8],
+) -> Result<(), ResumptionException>
+where
+    H: BlockHashFunction,
+    H::Context: Default,
+{
+    let expected = hmac::<H, H::Context>(&H::Context::default(), resumption_secret, data);
+    if constant_time_eq(&expected, mac) {
+        Ok(())
+    } else {
+        Err(ResumptionException::InvalidMac {})
+    }
+}
@@ -0,0 +1,330 @@
+//! Storage for message keys of skipped messages. When messages arrive out of order, the receiving chain is advanced
+//! past the missing messages and their keys are stored, so the messages can still be decrypted once they arrive. The
+//! store is a protocol parameter, so applications can persist skipped keys or bound their number. Keys are stored as
+//! `OneTimeKey`s, so a stored key is used for one decryption at most.
+
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+
+use crate::one_time_key::{derive_message_key, OneTimeKey};
+use crate::ConstantInputKeyRatchet;
+
+/// A store for message keys of skipped messages, indexed by the Diffie-Hellman public key of the sending chain and
+/// the message number within that chain. Stores must `discard` the keys they drop without handing them out, like
+/// keys they evict to bound their size.
+///
+/// # Type Parameters
+/// - `K` the diffie-hellman public key type
+/// - `MK` the message key type
+pub trait SkippedKeyStore<K, MK> {
+    /// Store the message key of a skipped message.
+    fn insert(&mut self, public_key: K, message_number: usize, key: OneTimeKey<MK>);
+
+    /// Remove and return the message key of a skipped message, if it is stored.
+    fn take(&mut self, public_key: &K, message_number: usize) -> Option<OneTimeKey<MK>>;
+
+    /// The number of stored message keys
+    fn len(&self) -> usize;
+
+    /// Whether no message keys are stored
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove the message key that was inserted first and return it along with its public key and message number.
+    fn evict_oldest(&mut self) -> Option<(K, usize, OneTimeKey<MK>)>;
+}
+
+/// An unbounded in-memory store backed by a `HashMap`. This is the default store of the protocol.
+pub struct HashMapKeyStore<K, MK>
+where
+    K: Eq + Hash,
+{
+    keys: HashMap<(K, usize), (u64, OneTimeKey<MK>)>,
+    insertion_counter: u64,
+}
+
+impl<K, MK> HashMapKeyStore<K, MK>
+where
+    K: Eq + Hash,
+{
+    pub fn new() -> Self {
+        HashMapKeyStore {
+            keys: HashMap::new(),
+            insertion_counter: 0,
+        }
+    }
+
+    /// Whether the message key of the given skipped message is stored
+    pub fn contains(&self, public_key: &K, message_number: usize) -> bool
+    where
+        K: Clone,
+    {
+        self.keys
+            .contains_key(&(public_key.clone(), message_number))
+    }
+}
+
+impl<K, MK> Default for HashMapKeyStore<K, MK>
+where
+    K: Eq + Hash,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The keys of messages that never arrived are discarded with the store.
+impl<K, MK> Drop for HashMapKeyStore<K, MK>
+where
+    K: Eq + Hash,
+{
+    fn drop(&mut self) {
+        self.keys.drain().for_each(|(_, (_, key))| key.discard());
+    }
+}
+
+impl<K, MK> SkippedKeyStore<K, MK> for HashMapKeyStore<K, MK>
+where
+    K: Clone + Eq + Hash,
+{
+    fn insert(&mut self, public_key: K, message_number: usize, key: OneTimeKey<MK>) {
+        if let Some((_, replaced)) = self
+            .keys
+            .insert((public_key, message_number), (self.insertion_counter, key))
+        {
+            replaced.discard();
+        }
+        self.insertion_counter += 1;
+    }
+
+    fn take(&mut self, public_key: &K, message_number: usize) -> Option<OneTimeKey<MK>> {
+        self.keys
+            .remove(&(public_key.clone(), message_number))
+            .map(|(_, key)| key)
+    }
+
+    fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    fn evict_oldest(&mut self) -> Option<(K, usize, OneTimeKey<MK>)> {
+        let oldest = self
+            .keys
+            .iter()
+            .min_by_key(|(_, (insertion, _))| *insertion)
+            .map(|(index, _)| index.clone())?;
+
+        let (_, key) = self.keys.remove(&oldest).unwrap();
+        Some((oldest.0, oldest.1, key))
+    }
+}
+
+/// A store that holds at most `capacity` message keys. If a key is inserted into a full store, the oldest key is
+/// evicted and the corresponding message cannot be decrypted anymore.
+pub struct RingBufferKeyStore<K, MK> {
+    capacity: usize,
+    entries: VecDeque<(K, usize, OneTimeKey<MK>)>,
+}
+
+impl<K, MK> RingBufferKeyStore<K, MK> {
+    /// Create a new store for at most `capacity` message keys. A capacity of zero drops all keys.
+    pub fn with_capacity(capacity: usize) -> Self {
+        RingBufferKeyStore {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// The maximum number of message keys held by this store
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Whether the message key of the given skipped message is stored
+    pub fn contains(&self, public_key: &K, message_number: usize) -> bool
+    where
+        K: Eq,
+    {
+        self.entries
+            .iter()
+            .any(|(k, number, _)| k == public_key && *number == message_number)
+    }
+}
+
+impl<K, MK> SkippedKeyStore<K, MK> for RingBufferKeyStore<K, MK>
+where
+    K: Eq,
+{
+    fn insert(&mut self, public_key: K, message_number: usize, key: OneTimeKey<MK>) {
+        if self.capacity == 0 {
+            key.discard();
+            return;
+        }
+
+        while self.entries.len() >= self.capacity {
+            if let Some((_, _, evicted)) = self.evict_oldest() {
+                evicted.discard();
+            }
+        }
+
+        self.entries.push_back((public_key, message_number, key));
+    }
+
+    fn take(&mut self, public_key: &K, message_number: usize) -> Option<OneTimeKey<MK>> {
+        let position = self
+            .entries
+            .iter()
+            .position(|(k, number, _)| k == public_key && *number == message_number)?;
+
+        self.entries.remove(position).map(|(_, _, key)| key)
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn evict_oldest(&mut self) -> Option<(K, usize, OneTimeKey<MK>)> {
+        self.entries.pop_front()
+    }
+}
+
+/// The keys of messages that never arrived are discarded with the store.
+impl<K, MK> Drop for RingBufferKeyStore<K, MK> {
+    fn drop(&mut self) {
+        self.entries.drain(..).for_each(|(_, _, key)| key.discard());
+    }
+}
+
+/// Message keys of skipped messages that are only moved into the store of the protocol once the message that skipped
+/// them was decrypted. Keys that are not committed are discarded.
+pub(crate) struct StagedKeys<K, MK> {
+    entries: Vec<(K, usize, OneTimeKey<MK>)>,
+}
+
+impl<K, MK> StagedKeys<K, MK> {
+    pub(crate) fn new() -> Self {
+        StagedKeys { entries: Vec::new() }
+    }
+
+    /// Move all staged keys into `store`.
+    pub(crate) fn commit<Store>(mut self, store: &mut Store)
+    where
+        Store: SkippedKeyStore<K, MK>,
+    {
+        for (public_key, message_number, key) in self.entries.drain(..) {
+            store.insert(public_key, message_number, key);
+        }
+    }
+}
+
+impl<K, MK> SkippedKeyStore<K, MK> for StagedKeys<K, MK>
+where
+    K: Eq,
+{
+    fn insert(&mut self, public_key: K, message_number: usize, key: OneTimeKey<MK>) {
+        self.entries.push((public_key, message_number, key));
+    }
+
+    fn take(&mut self, public_key: &K, message_number: usize) -> Option<OneTimeKey<MK>> {
+        let position = self
+            .entries
+            .iter()
+            .position(|(k, number, _)| k == public_key && *number == message_number)?;
+
+        Some(self.entries.remove(position).2)
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn evict_oldest(&mut self) -> Option<(K, usize, OneTimeKey<MK>)> {
+        if self.entries.is_empty() {
+            None
+        } else {
+            Some(self.entries.remove(0))
+        }
+    }
+}
+
+/// The keys of a rolled back decryption are discarded.
+impl<K, MK> Drop for StagedKeys<K, MK> {
+    fn drop(&mut self) {
+        self.entries.drain(..).for_each(|(_, _, key)| key.discard());
+    }
+}
+
+/// Advance a receiving chain past skipped messages and store their message keys, so the messages can be decrypted
+/// once they arrive.
+/// # Parameters
+/// - `chain_key` the chain key of the message numbered `from`
+/// - `chain_id` identifies the chain in the store
+/// - `from` the number of the first skipped message
+/// - `until` the number of the next message that is not skipped
+/// - `store` the store receiving the message keys of the skipped messages
+///
+/// # Returns
+/// The chain key of the message numbered `until`
+pub(crate) fn skip_message_keys<MessageKdf, K, Store>(
+    mut chain_key: MessageKdf::ChainKey,
+    chain_id: &K,
+    from: usize,
+    until: usize,
+    store: &mut Store,
+) -> MessageKdf::ChainKey
+where
+    MessageKdf: ConstantInputKeyRatchet,
+    K: Clone,
+    Store: SkippedKeyStore<K, MessageKdf::OutputKey>,
+{
+    for message_number in from..until {
+        let (next_chain_key, message_key) = derive_message_key::<MessageKdf>(chain_key);
+        chain_key = next_chain_key;
+        store.insert(chain_id.clone(), message_number, message_key);
+    }
+
+    chain_key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_map_store() {
+        let mut store = HashMapKeyStore::new();
+        store.insert("a", 1, OneTimeKey::new(10));
+        store.insert("b", 0, OneTimeKey::new(20));
+        store.insert("a", 2, OneTimeKey::new(30));
+
+        assert_eq!(store.len(), 3);
+        assert!(store.contains(&"a", 2));
+        assert_eq!(store.take(&"a", 2).map(OneTimeKey::use_once), Some(30));
+        assert!(!store.contains(&"a", 2));
+        assert!(store.take(&"a", 2).is_none());
+        let mut evict_oldest = || {
+            store
+                .evict_oldest()
+                .map(|(public_key, number, key)| (public_key, number, key.use_once()))
+        };
+        assert_eq!(evict_oldest(), Some(("a", 1, 10)));
+        assert_eq!(evict_oldest(), Some(("b", 0, 20)));
+        assert!(store.is_empty());
+    }
+
+    #[test]
+    fn test_ring_buffer_store() {
+        let mut store = RingBufferKeyStore::with_capacity(2);
+        store.insert("a", 0, OneTimeKey::new(10));
+        store.insert("a", 1, OneTimeKey::new(20));
+        store.insert("a", 2, OneTimeKey::new(30));
+
+        assert_eq!(store.len(), 2);
+        assert!(!store.contains(&"a", 0));
+        assert!(store.take(&"a", 0).is_none());
+        assert_eq!(store.take(&"a", 1).map(OneTimeKey::use_once), Some(20));
+        assert_eq!(store.take(&"a", 2).map(OneTimeKey::use_once), Some(30));
+        assert!(store.is_empty());
+    }
+}
@@ -0,0 +1,193 @@
+//! Unidirectional channels that only use the symmetric key ratchet. Both parties start from a shared chain key, the
+//! sending party advances its chain once per message and the receiving party advances its copy of the chain to
+//! decrypt it. Without Diffie-Hellman ratchet steps, a channel provides forward secrecy but no break-in recovery: a
+//! party that learns a chain key can derive all later message keys, but none of the earlier ones.
+//!
+//! Channels suit applications that only ever send in one direction, like telemetry, where the Diffie-Hellman steps of
+//! the `DoubleRatchetProtocol` would be wasted. A channel either encrypts or decrypts messages, so each party holds
+//! its own instance.
+
+use std::convert::TryInto;
+use std::marker::PhantomData;
+
+use crate::chain_key::RollbackKey;
+use crate::replay_window::ReceivedMessages;
+use crate::one_time_key::{derive_message_key, OneTimeKey};
+use crate::skipped_keys::{skip_message_keys, HashMapKeyStore, SkippedKeyStore, StagedKeys};
+use crate::DecryptionException::{
+    AuthenticationFailed, OutOfOrderMessage, ReplayedMessage, UnknownMessageHeader,
+};
+use crate::{ConstantInputKeyRatchet, DecryptionException, KeyDerivationFunction};
+use jester_encryption::SymmetricalEncryptionScheme;
+use jester_util::Redacted;
+
+/// The largest number of messages a single message may skip. A message further ahead of the receiving chain is
+/// rejected as `DecryptionException::UnknownMessageHeader`, so a forged index cannot make the receiver derive an
+/// unbounded number of message keys.
+pub const MAX_SKIPPED_MESSAGES: u64 = 1 << 16;
+
+/// One end of a unidirectional channel secured by a symmetric key ratchet.
+///
+/// # Type Parameters
+/// - `MessageKdf` the key ratchet of the channel
+/// - `EncryptionScheme` symmetrical encryption scheme for message encryption
+/// - `Store` the store for message keys of skipped messages. The channel has a single chain, so keys are only
+///   indexed by their message number.
+pub struct SymmetricRatchetChannel<
+    MessageKdf,
+    EncryptionScheme,
+    Store = HashMapKeyStore<(), <MessageKdf as KeyDerivationFunction>::OutputKey>,
+> where
+    MessageKdf: ConstantInputKeyRatchet,
+    EncryptionScheme: SymmetricalEncryptionScheme<Key = MessageKdf::OutputKey>,
+    Store: SkippedKeyStore<(), MessageKdf::OutputKey>,
+{
+    encryption_scheme: PhantomData<EncryptionScheme>,
+    message_chain: PhantomData<MessageKdf>,
+    chain_key: Redacted<Option<MessageKdf::ChainKey>>,
+    chain_length: usize,
+    missed_messages: Store,
+    received_messages: ReceivedMessages<()>,
+}
+
+impl<MessageKdf, EncryptionScheme> SymmetricRatchetChannel<MessageKdf, EncryptionScheme>
+where
+    MessageKdf: ConstantInputKeyRatchet,
+    EncryptionScheme: SymmetricalEncryptionScheme<Key = MessageKdf::OutputKey>,
+{
+    /// Create a channel with an unbounded in-memory store for skipped message keys. See `new_with_store`.
+    pub fn new(chain_key: MessageKdf::ChainKey) -> Self {
+        Self::new_with_store(chain_key, HashMapKeyStore::new())
+    }
+}
+
+impl<MessageKdf, EncryptionScheme, Store>
+    SymmetricRatchetChannel<MessageKdf, EncryptionScheme, Store>
+where
+    MessageKdf: ConstantInputKeyRatchet,
+    EncryptionScheme: SymmetricalEncryptionScheme<Key = MessageKdf::OutputKey>,
+    Store: SkippedKeyStore<(), MessageKdf::OutputKey>,
+{
+    /// Create one end of a channel.
+    /// # Parameters
+    /// - `chain_key` the chain key shared by both ends of the channel
+    /// - `skipped_key_store` the store for message keys of skipped messages
+    pub fn new_with_store(chain_key: MessageKdf::ChainKey, skipped_key_store: Store) -> Self {
+        SymmetricRatchetChannel {
+            encryption_scheme: PhantomData,
+            message_chain: PhantomData,
+            chain_key: Redacted::new(Some(chain_key)),
+            chain_length: 0,
+            missed_messages: skipped_key_store,
+            received_messages: ReceivedMessages::new(),
+        }
+    }
+
+    /// Encrypt the next message of the channel. The chain is advanced once and the message key is dropped
+    /// afterwards.
+    ///
+    /// # Returns
+    /// The index of the message within the channel and its cipher text. Both must be sent to the receiving end.
+    pub fn encrypt_next(&mut self, message: &[u8]) -> (u64, Vec<u8>) {
+        let (updated_chain_key, message_key) =
+            derive_message_key::<MessageKdf>(self.chain_key.take().unwrap());
+        *self.chain_key = Some(updated_chain_key);
+
+        let index = self.chain_length as u64;
+        self.chain_length += 1;
+
+        (
+            index,
+            EncryptionScheme::encrypt_message(&message_key.use_once(), message),
+        )
+    }
+
+    /// Decrypt the message with the given `index`. The chain is advanced to the message and the keys of skipped
+    /// messages are stored, like in the `DoubleRatchetProtocol`. The chain only advances once the message was
+    /// decrypted, so a rejected message leaves the channel unchanged.
+    ///
+    /// # Returns
+    /// The clear text, `DecryptionException::OutOfOrderMessage` if the message was decrypted with a stored key of a
+    /// skipped message, `DecryptionException::ReplayedMessage` if it was decrypted before,
+    /// `DecryptionException::AuthenticationFailed` if the cipher text fails authentication and
+    /// `DecryptionException::UnknownMessageHeader` if no key for the message is known, e.g. because it was evicted
+    /// from the store or the message skips more than `MAX_SKIPPED_MESSAGES` messages.
+    pub fn decrypt(
+        &mut self,
+        index: u64,
+        cipher_text: &[u8],
+    ) -> Result<Vec<u8>, DecryptionException>
+    where
+        MessageKdf::ChainKey: RollbackKey,
+    {
+        let message_number: usize = index.try_into().map_err(|_| UnknownMessageHeader {})?;
+        if self.received_messages.contains(&(), message_number) {
+            return Err(ReplayedMessage { message_number });
+        }
+
+        if message_number < self.chain_length {
+            let message_key = self
+                .missed_messages
+                .take(&(), message_number)
+                .ok_or(UnknownMessageHeader {})?
+                .use_once();
+            let decrypted_message =
+                match EncryptionScheme::try_decrypt_message(&message_key, cipher_text) {
+                    Ok(decrypted_message) => decrypted_message,
+                    Err(cause) => {
+                        // a forged message must not use up the key of the real message
+                        self.missed_messages.insert(
+                            (),
+                            message_number,
+                            OneTimeKey::new(message_key),
+                        );
+                        return Err(AuthenticationFailed { cause });
+                    }
+                };
+            self.received_messages.insert((), message_number);
+            return Err(OutOfOrderMessage { decrypted_message });
+        }
+
+        if (message_number - self.chain_length) as u64 > MAX_SKIPPED_MESSAGES {
+            return Err(UnknownMessageHeader {});
+        }
+
+        // the keys are derived from a copy of the chain key and the keys of skipped messages are staged, so a message
+        // that fails to decrypt leaves the chain unchanged
+        let mut skipped_keys = StagedKeys::new();
+        let chain_key = skip_message_keys::<MessageKdf, _, _>(
+            self.chain_key.as_ref().unwrap().rollback_copy(),
+            &(),
+            self.chain_length,
+            message_number,
+            &mut skipped_keys,
+        );
+        let (updated_chain_key, message_key) = derive_message_key::<MessageKdf>(chain_key);
+
+        let clear_text =
+            EncryptionScheme::try_decrypt_message(&message_key.use_once(), cipher_text)
+                .map_err(|cause| AuthenticationFailed { cause })?;
+
+        // the message is authentic, so the derived keys replace the chain key
+        skipped_keys.commit(&mut self.missed_messages);
+        *self.chain_key = Some(updated_chain_key);
+        self.chain_length = message_number + 1;
+        self.received_messages.insert((), message_number);
+        Ok(clear_text)
+    }
+
+    /// The number of messages encrypted or decrypted by this end of the channel, including skipped messages.
+    pub fn chain_length(&self) -> u64 {
+        self.chain_length as u64
+    }
+
+    /// The number of message keys currently stored for skipped messages.
+    pub fn stored_skipped_keys(&self) -> usize {
+        self.missed_messages.len()
+    }
+
+    /// The store for message keys of skipped messages
+    pub fn skipped_key_store(&self) -> &Store {
+        &self.missed_messages
+    }
+}
@@ -1,13 +1,32 @@
-use rand::{CryptoRng, RngCore, thread_rng};
+use rand::{CryptoRng, RngCore, SeedableRng, thread_rng};
+use rand::rngs::StdRng;
 
-use jester_encryption::diffie_hellman::DiffieHellmanKeyExchangeScheme;
-use jester_encryption::SymmetricalEncryptionScheme;
+use jester_encryption::diffie_hellman::{
+    DhKeyCodec, DiffieHellmanKeyExchangeScheme, KeyDecodeException, KeyValidationException,
+    PrecomputedKeyExchangeScheme,
+};
+use jester_encryption::{AuthenticationException, SymmetricalEncryptionScheme};
 use jester_maths::prime::{IetfGroup3, PrimeField};
 use num::Num;
 
-use crate::{DoubleRatchetProtocol, KeyDerivationFunction};
+use crate::{ConstantInputKeyRatchet, DecryptionException, DoubleRatchetAlgorithmMessage, DoubleRatchetProtocol,
+            KeyDerivationFunction, MessageDecodeException, RatchetEvent, MESSAGE_VERSION};
+use crate::cipher_suite::{AeadAlgorithm, CipherSuite, DhAlgorithm, HashAlgorithm, KdfAlgorithm};
+use crate::{capabilities, Capabilities};
+use crate::group::{GroupException, GroupMessage, GroupSession, SenderChainKey, SenderKeyDistribution};
+use crate::key_pair_source::{KeyPairSource, RngKeyPairSource};
+use crate::padding::PaddingScheme;
+use crate::skipped_keys::{HashMapKeyStore, RingBufferKeyStore, SkippedKeyStore};
+use crate::state::{Established, Initiator};
+use crate::symmetric_channel::{SymmetricRatchetChannel, MAX_SKIPPED_MESSAGES};
 use jester_hashes::kdf::hkdf_derive_key;
 use jester_hashes::sha1::SHA1Hash;
+use jester_hashes::siphash::siphash24;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::convert::TryInto;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use jester_util::Redacted;
 
 
 // An encryption scheme for testing, that simply appends the clear text to the password and panics, if the password
@@ -17,11 +36,11 @@ struct TestEncryption {
 }
 
 impl SymmetricalEncryptionScheme for TestEncryption {
-    type Key = &'static str;
+    type Key = Redacted<&'static str>;
 
     fn generate_key<R>(_: &mut R) -> Self::Key where
         R: RngCore + CryptoRng {
-        "super_secure_password"
+        Redacted::new("super_secure_password")
     }
 
     fn encrypt_message(key: &Self::Key, message: &[u8]) -> Vec<u8> {
@@ -30,7 +49,7 @@ impl SymmetricalEncryptionScheme for TestEncryption {
 
     fn decrypt_message(key: &Self::Key, message: &[u8]) -> Vec<u8> {
         if message.starts_with(key.as_bytes()) {
-            message[key.as_bytes().len()..].to_vec()
+            message[key.len()..].to_vec()
         } else {
             panic!("wrong key")
         }
@@ -75,4 +94,1650 @@ fn test_connect() {
     // let (receiver_dh_private, receiver_dh_public) = IetfGroup3::generate_asymmetrical_key_pair(&mut rng, &generator);
     // DoubleRatchetProtocol::initialize_receiving(&mut rng, receiver_dh_public, )
     unimplemented!()
-}
\ No newline at end of file
+}
+/// A toy Diffie-Hellman scheme in the prime field of the Mersenne prime 2^61 - 1. It is not secure, but fast and its
+/// keys are hashable.
+struct ToyDiffieHellman;
+
+const TOY_PRIME: u64 = (1 << 61) - 1;
+
+fn toy_modpow(base: u64, exponent: u64) -> u64 {
+    let mut result: u128 = 1;
+    let mut base = base as u128;
+    let mut exponent = exponent;
+
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result * base % TOY_PRIME as u128;
+        }
+        base = base * base % TOY_PRIME as u128;
+        exponent >>= 1;
+    }
+
+    result as u64
+}
+
+impl DiffieHellmanKeyExchangeScheme for ToyDiffieHellman {
+    type PublicKey = u64;
+    type PrivateKey = u64;
+    type SharedKey = u64;
+
+    fn generate_asymmetrical_key_pair<R>(rng: &mut R, generator: &u64) -> (u64, u64) where
+        R: RngCore + CryptoRng {
+        let private_key = rng.next_u64() % TOY_PRIME;
+        (private_key, toy_modpow(*generator, private_key))
+    }
+
+    fn generate_shared_secret(private_key: &u64, partner_key: &u64) -> u64 {
+        toy_modpow(*partner_key, *private_key)
+    }
+
+    fn validate_public_key(partner_key: &u64) -> Result<(), KeyValidationException> {
+        if *partner_key <= 1 || *partner_key >= TOY_PRIME - 1 {
+            Err(KeyValidationException::DegenerateKey {})
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Toy public keys are encoded as eight big endian bytes.
+impl DhKeyCodec for ToyDiffieHellman {
+    fn encode_public_key(key: &u64) -> Vec<u8> {
+        key.to_be_bytes().to_vec()
+    }
+
+    fn decode_public_key(bytes: &[u8]) -> Result<u64, KeyDecodeException> {
+        let bytes: [u8; 8] = bytes.try_into()
+            .map_err(|_| KeyDecodeException::InvalidLength { expected: 8, given: bytes.len() })?;
+        let key = u64::from_be_bytes(bytes);
+        if key < TOY_PRIME { Ok(key) } else { Err(KeyDecodeException::ValueOutOfRange {}) }
+    }
+}
+
+/// The generator of the `ToyDiffieHellman` scheme, counting the key pairs generated from it.
+struct ToyPrecomputation {
+    generator: u64,
+    generated_key_pairs: AtomicUsize,
+}
+
+impl PrecomputedKeyExchangeScheme for ToyDiffieHellman {
+    type Precomputation = ToyPrecomputation;
+
+    fn precompute_generator(generator: &u64) -> ToyPrecomputation {
+        ToyPrecomputation { generator: *generator, generated_key_pairs: AtomicUsize::new(0) }
+    }
+
+    fn precomputed_generator(precomputation: &ToyPrecomputation) -> u64 {
+        precomputation.generator
+    }
+
+    fn generate_asymmetrical_key_pair_with<R>(precomputation: &ToyPrecomputation, rng: &mut R) -> (u64, u64) where
+        R: RngCore + CryptoRng {
+        precomputation.generated_key_pairs.fetch_add(1, Ordering::SeqCst);
+        Self::generate_asymmetrical_key_pair(rng, &precomputation.generator)
+    }
+}
+
+/// The chain keys and message keys of the toy protocol.
+type ToyKey = Redacted<u64>;
+
+/// A key derivation function that uses SipHash keyed with the chain key as PRF. It serves as root and message KDF.
+struct ToyKdf;
+
+fn toy_prf(key: u64, input: u64, domain: u8) -> u64 {
+    let mut sip_key = [0u8; 16];
+    sip_key[..8].copy_from_slice(&key.to_le_bytes());
+    siphash24(&sip_key, &[&input.to_le_bytes()[..], &[domain]].concat())
+}
+
+impl KeyDerivationFunction for ToyKdf {
+    type ChainKey = ToyKey;
+    type Input = u64;
+    type OutputKey = ToyKey;
+
+    fn derive_key(chain_key: ToyKey, input: u64) -> (ToyKey, ToyKey) {
+        (toy_prf(*chain_key, input, 1).into(), toy_prf(*chain_key, input, 2).into())
+    }
+}
+
+impl ConstantInputKeyRatchet for ToyKdf {
+    fn constant_input() -> u64 {
+        0
+    }
+}
+
+/// An encryption scheme for testing, that prepends the key to the clear text and panics, if the key is wrong in
+/// decryption. `try_decrypt_message` rejects a cipher text with the wrong key prefix like a forged authentication tag.
+struct KeyPrefixEncryption;
+
+impl SymmetricalEncryptionScheme for KeyPrefixEncryption {
+    type Key = ToyKey;
+
+    fn generate_key<R>(rng: &mut R) -> Self::Key where
+        R: RngCore + CryptoRng {
+        Redacted::new(rng.next_u64())
+    }
+
+    fn encrypt_message(key: &Self::Key, message: &[u8]) -> Vec<u8> {
+        [&key.to_le_bytes()[..], message].concat()
+    }
+
+    fn decrypt_message(key: &Self::Key, message: &[u8]) -> Vec<u8> {
+        if message.starts_with(&key.to_le_bytes()) {
+            message[8..].to_vec()
+        } else {
+            panic!("wrong key")
+        }
+    }
+
+    fn try_decrypt_message(key: &Self::Key, message: &[u8]) -> Result<Vec<u8>, AuthenticationException> {
+        if message.starts_with(&key.to_le_bytes()) {
+            Ok(message[8..].to_vec())
+        } else {
+            Err(AuthenticationException::InvalidTag {})
+        }
+    }
+}
+
+fn assert_authentication_failed(result: Result<Vec<u8>, DecryptionException>) {
+    match result {
+        Err(DecryptionException::AuthenticationFailed { cause: AuthenticationException::InvalidTag {} }) => {}
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+type ToyProtocol<State, Store> = DoubleRatchetProtocol<
+    ToyDiffieHellman, KeyPrefixEncryption, ToyKdf, ToyKdf, u64, u64, u64, ToyKey, ToyKey, ToyKey, State, Store>;
+
+const TOY_GENERATOR: u64 = 3;
+
+const TOY_ROOT_KEY: ToyKey = Redacted::new(0x1234_5678);
+
+/// The cipher suite of the toy protocols. The suite is only a label, so any suite can describe the toy primitives.
+const TOY_SUITE: CipherSuite =
+    CipherSuite::new(DhAlgorithm::IetfGroup3, AeadAlgorithm::Aes256Gcm, KdfAlgorithm::HmacChain, HashAlgorithm::Sha1);
+
+/// Establish a protocol between an initiator and an addressee, who sends the first message.
+fn establish<Store>(
+    rng: &mut StdRng,
+    initiator_store: Store,
+    addressee_store: Store,
+) -> (ToyProtocol<Established, Store>, ToyProtocol<Established, Store>)
+    where Store: SkippedKeyStore<u64, ToyKey> {
+    let (initiator, hello) = ToyProtocol::<Initiator, Store>::initialize_sending_with_store(
+        rng, TOY_GENERATOR, TOY_ROOT_KEY, TOY_SUITE, initiator_store);
+    let mut addressee = ToyProtocol::<Established, Store>::initialize_receiving_with_store(
+        rng, TOY_GENERATOR, hello.public_key, TOY_ROOT_KEY, TOY_SUITE, addressee_store).unwrap();
+
+    let reply = addressee.encrypt_message(b"hello");
+    let (initiator, clear_text) = initiator.decrypt_first_message(reply).unwrap();
+    assert_eq!(clear_text, b"hello");
+
+    (initiator, addressee)
+}
+
+#[test]
+fn test_default_store() {
+    let mut rng = StdRng::seed_from_u64(1);
+
+    let (initiator, hello) =
+        ToyProtocol::<Initiator, HashMapKeyStore<u64, ToyKey>>::initialize_sending(
+            &mut rng, TOY_GENERATOR, TOY_ROOT_KEY, TOY_SUITE);
+    let mut addressee = ToyProtocol::<Established, HashMapKeyStore<u64, ToyKey>>::initialize_receiving(
+        &mut rng, TOY_GENERATOR, hello.public_key, TOY_ROOT_KEY, TOY_SUITE).unwrap();
+    let (mut initiator, _) = initiator
+        .decrypt_first_message(addressee.encrypt_message(b"hello"))
+        .unwrap();
+
+    // in order messages in both directions
+    for round in 0..3u8 {
+        let message = initiator.encrypt_message(&[round]);
+        assert_eq!(addressee.decrypt_message(message).unwrap(), vec![round]);
+
+        let message = addressee.encrypt_message(&[round, round]);
+        assert_eq!(initiator.decrypt_message(message).unwrap(), vec![round, round]);
+    }
+
+    // out of order messages
+    let messages: Vec<_> = (0..3u8).map(|i| initiator.encrypt_message(&[i])).collect();
+    let mut messages = messages.into_iter().rev();
+    assert_eq!(addressee.decrypt_message(messages.next().unwrap()).unwrap(), vec![2]);
+    assert_eq!(addressee.missed_messages.len(), 2);
+
+    for expected in &[1u8, 0] {
+        match addressee.decrypt_message(messages.next().unwrap()) {
+            Err(DecryptionException::OutOfOrderMessage { decrypted_message }) =>
+                assert_eq!(decrypted_message, vec![*expected]),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+    assert!(addressee.missed_messages.is_empty());
+
+    let message = addressee.encrypt_message(b"reply");
+    assert_eq!(initiator.decrypt_message(message).unwrap(), b"reply");
+}
+
+#[test]
+fn test_bounded_store_eviction() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let (mut initiator, mut addressee) = establish(
+        &mut rng, RingBufferKeyStore::with_capacity(2), RingBufferKeyStore::with_capacity(2));
+
+    let mut messages: Vec<_> = (0..4u8).map(|i| Some(initiator.encrypt_message(&[i]))).collect();
+
+    // three messages are skipped, but only two keys are kept
+    assert_eq!(addressee.decrypt_message(messages[3].take().unwrap()).unwrap(), vec![3]);
+    assert_eq!(addressee.missed_messages.len(), 2);
+
+    match addressee.decrypt_message(messages[0].take().unwrap()) {
+        Err(DecryptionException::UnknownMessageHeader {}) => {}
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    for (i, message) in messages.iter_mut().enumerate().take(3).skip(1) {
+        match addressee.decrypt_message(message.take().unwrap()) {
+            Err(DecryptionException::OutOfOrderMessage { decrypted_message }) =>
+                assert_eq!(decrypted_message, vec![i as u8]),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+}
+
+#[test]
+fn test_padded_round_trip() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let (mut initiator, mut addressee) = establish(&mut rng, HashMapKeyStore::new(), HashMapKeyStore::new());
+    initiator.set_padding_scheme(PaddingScheme::PadToMultiple(64));
+    addressee.set_padding_scheme(PaddingScheme::PadToMultiple(64));
+
+    let mut cipher_text_lengths = BTreeSet::new();
+    for length in 0..130 {
+        let clear_text: Vec<u8> = (0..length).map(|i| i as u8).collect();
+        let message = initiator.encrypt_message(&clear_text);
+
+        // the encryption prefixes the cipher text with the eight byte key
+        cipher_text_lengths.insert(message.message.as_ref().unwrap().len() - 8);
+        assert_eq!(addressee.decrypt_message(message).unwrap(), clear_text);
+    }
+
+    assert_eq!(cipher_text_lengths.into_iter().collect::<Vec<_>>(), vec![64, 128, 192]);
+}
+
+#[test]
+fn test_tampered_padding() {
+    let mut rng = StdRng::seed_from_u64(4);
+    let (mut initiator, mut addressee) = establish(&mut rng, HashMapKeyStore::new(), HashMapKeyStore::new());
+    initiator.set_padding_scheme(PaddingScheme::PadToMultiple(16));
+    addressee.set_padding_scheme(PaddingScheme::PadToMultiple(16));
+
+    let tampered = |message: &DoubleRatchetAlgorithmMessage<u64, Vec<u8>>| {
+        let mut message = message.clone();
+        *message.message.as_mut().unwrap().last_mut().unwrap() = 1;
+        message
+    };
+    let assert_invalid_padding = |result| match result {
+        Err(DecryptionException::InvalidPadding {}) => {}
+        result => panic!("unexpected result: {:?}", result),
+    };
+
+    // the first message of a new sending chain, after a skipped message, would step the ratchet
+    let skipped = initiator.encrypt_message(b"skipped");
+    let message = initiator.encrypt_message(b"padded");
+    let public_key = *addressee.current_public_key();
+    assert_invalid_padding(addressee.decrypt_message(tampered(&message)));
+
+    // the forged message did not change the protocol state
+    assert_eq!(*addressee.current_public_key(), public_key);
+    assert_eq!(addressee.current_receiving_chain_length(), 0);
+    assert_eq!(addressee.stored_skipped_keys(), 0);
+
+    let (clear_text, event) = addressee.decrypt_message_with_event(message).unwrap();
+    assert_eq!(clear_text, b"padded");
+    assert_eq!(event, RatchetEvent::RatchetStepped { new_remote_key: *initiator.current_public_key() });
+    assert_eq!(addressee.stored_skipped_keys(), 1);
+
+    // a forged out-of-order message does not use up the key of the skipped message
+    assert_invalid_padding(addressee.decrypt_message(tampered(&skipped)));
+    assert_eq!(addressee.stored_skipped_keys(), 1);
+    match addressee.decrypt_message(skipped) {
+        Err(DecryptionException::OutOfOrderMessage { decrypted_message }) => assert_eq!(decrypted_message, b"skipped"),
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    // a forged message of the current chain does not advance the receiving chain
+    let message = initiator.encrypt_message(b"same chain");
+    assert_invalid_padding(addressee.decrypt_message(tampered(&message)));
+    assert_eq!(
+        addressee.decrypt_message_with_event(message).unwrap(),
+        (b"same chain".to_vec(), RatchetEvent::SameChain)
+    );
+}
+
+#[test]
+fn test_ratchet_events() {
+    let mut rng = StdRng::seed_from_u64(5);
+    let (mut initiator, mut addressee) = establish(&mut rng, HashMapKeyStore::new(), HashMapKeyStore::new());
+
+    // the addressee sent one message to establish the protocol
+    assert_eq!(addressee.current_sending_chain_length(), 1);
+    assert_eq!(initiator.current_receiving_chain_length(), 1);
+
+    // each epoch, one party sends a number of messages and the other party answers
+    for epoch in 1..4 {
+        let initiator_key = *initiator.current_public_key();
+        for i in 0..epoch {
+            let (_, event) = addressee
+                .decrypt_message_with_event(initiator.encrypt_message(b"ping"))
+                .unwrap();
+
+            if i == 0 {
+                assert_eq!(event, RatchetEvent::RatchetStepped { new_remote_key: initiator_key });
+            } else {
+                assert_eq!(event, RatchetEvent::SameChain);
+            }
+        }
+        assert_eq!(initiator.current_sending_chain_length(), epoch);
+        assert_eq!(addressee.current_receiving_chain_length(), epoch);
+
+        let addressee_key = *addressee.current_public_key();
+        for i in 0..epoch + 1 {
+            let (_, event) = initiator
+                .decrypt_message_with_event(addressee.encrypt_message(b"pong"))
+                .unwrap();
+
+            if i == 0 {
+                assert_eq!(event, RatchetEvent::RatchetStepped { new_remote_key: addressee_key });
+            } else {
+                assert_eq!(event, RatchetEvent::SameChain);
+            }
+        }
+        assert_eq!(addressee.current_sending_chain_length(), epoch + 1);
+        assert_eq!(initiator.current_receiving_chain_length(), epoch + 1);
+    }
+
+    // skipped messages are counted until they arrive
+    let skipped = initiator.encrypt_message(b"skipped");
+    addressee.decrypt_message(initiator.encrypt_message(b"received")).unwrap();
+    assert_eq!(addressee.stored_skipped_keys(), 1);
+
+    assert!(addressee.decrypt_message_with_event(skipped).is_err());
+    assert_eq!(addressee.stored_skipped_keys(), 0);
+}
+
+#[test]
+fn test_degenerate_public_key_rejected() {
+    let mut rng = StdRng::seed_from_u64(6);
+    let (mut initiator, mut addressee) = establish(&mut rng, HashMapKeyStore::new(), HashMapKeyStore::new());
+
+    for degenerate_key in &[0, 1, TOY_PRIME - 1] {
+        let mut message = initiator.encrypt_message(b"degenerate");
+        message.public_key = *degenerate_key;
+
+        match addressee.decrypt_message(message) {
+            Err(DecryptionException::InvalidMessageHeader {}) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    // the rejected messages did not change the protocol state
+    assert_eq!(addressee.current_receiving_chain_length(), 0);
+    assert_eq!(addressee.stored_skipped_keys(), 0);
+    assert_eq!(addressee.decrypt_message(initiator.encrypt_message(b"valid")).unwrap(), b"valid");
+}
+
+#[test]
+fn test_degenerate_initial_public_key_rejected() {
+    let mut rng = StdRng::seed_from_u64(19);
+
+    let result = ToyProtocol::<Established, _>::initialize_receiving_with_key_pair_source(
+        RngKeyPairSource::new(StdRng::seed_from_u64(20), TOY_GENERATOR), TOY_PRIME - 1, TOY_ROOT_KEY, TOY_SUITE,
+        HashMapKeyStore::new());
+    assert!(matches!(result, Err(KeyValidationException::DegenerateKey {})));
+
+    let result = ToyProtocol::<Established, HashMapKeyStore<u64, ToyKey>>::initialize_receiving(
+        &mut rng, TOY_GENERATOR, TOY_PRIME - 1, TOY_ROOT_KEY, TOY_SUITE);
+    assert!(matches!(result, Err(KeyValidationException::DegenerateKey {})));
+}
+
+#[test]
+fn test_precomputed_generator() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let precomputation = Arc::new(ToyDiffieHellman::precompute_generator(&TOY_GENERATOR));
+
+    let (initiator, hello) = ToyProtocol::<Initiator, _>::initialize_sending_with_precomputation(
+        &mut rng, precomputation.clone(), TOY_ROOT_KEY, TOY_SUITE, HashMapKeyStore::new());
+    let mut addressee = ToyProtocol::<Established, _>::initialize_receiving_with_store(
+        &mut rng, TOY_GENERATOR, hello.public_key, TOY_ROOT_KEY, TOY_SUITE, HashMapKeyStore::new()).unwrap();
+    assert_eq!(precomputation.generated_key_pairs.load(Ordering::SeqCst), 1);
+
+    let (mut initiator, clear_text) = initiator.decrypt_first_message(addressee.encrypt_message(b"hello"))
+        .unwrap();
+    assert_eq!(clear_text, b"hello");
+    assert_eq!(precomputation.generated_key_pairs.load(Ordering::SeqCst), 2);
+
+    // the precomputation is reused for every ratchet step of the initiator
+    for ratchet_step in 3..6 {
+        assert_eq!(addressee.decrypt_message(initiator.encrypt_message(b"ping")).unwrap(), b"ping");
+        assert_eq!(initiator.decrypt_message(addressee.encrypt_message(b"pong")).unwrap(), b"pong");
+        assert_eq!(precomputation.generated_key_pairs.load(Ordering::SeqCst), ratchet_step);
+    }
+}
+
+/// A key pair source counting the key pairs drawn from it
+struct CountingKeyPairSource {
+    source: RngKeyPairSource<StdRng, ToyDiffieHellman>,
+    drawn_key_pairs: Arc<AtomicUsize>,
+}
+
+impl KeyPairSource<ToyDiffieHellman> for CountingKeyPairSource {
+    fn next_key_pair(&mut self) -> (u64, u64) {
+        self.drawn_key_pairs.fetch_add(1, Ordering::SeqCst);
+        self.source.next_key_pair()
+    }
+}
+
+#[test]
+fn test_key_pairs_drawn_per_ratchet_step() {
+    let mut rng = StdRng::seed_from_u64(16);
+    let drawn_key_pairs = Arc::new(AtomicUsize::new(0));
+    let source = CountingKeyPairSource {
+        source: RngKeyPairSource::new(StdRng::seed_from_u64(17), TOY_GENERATOR),
+        drawn_key_pairs: drawn_key_pairs.clone(),
+    };
+
+    let (initiator, hello) = ToyProtocol::<Initiator, _>::initialize_sending_with_key_pair_source(
+        source, TOY_ROOT_KEY, TOY_SUITE, HashMapKeyStore::new());
+    let mut addressee = ToyProtocol::<Established, _>::initialize_receiving_with_store(
+        &mut rng, TOY_GENERATOR, hello.public_key, TOY_ROOT_KEY, TOY_SUITE, HashMapKeyStore::new()).unwrap();
+    assert_eq!(drawn_key_pairs.load(Ordering::SeqCst), 1);
+
+    let (mut initiator, _) = initiator.decrypt_first_message(addressee.encrypt_message(b"hello")).unwrap();
+    assert_eq!(drawn_key_pairs.load(Ordering::SeqCst), 2);
+
+    // messages of the current receiving chain do not draw key pairs
+    let (_, event) = initiator.decrypt_message_with_event(addressee.encrypt_message(b"same chain")).unwrap();
+    assert_eq!(event, RatchetEvent::SameChain);
+    assert_eq!(drawn_key_pairs.load(Ordering::SeqCst), 2);
+
+    for ratchet_step in 3..6 {
+        assert_eq!(addressee.decrypt_message(initiator.encrypt_message(b"ping")).unwrap(), b"ping");
+        let (_, event) = initiator.decrypt_message_with_event(addressee.encrypt_message(b"pong")).unwrap();
+        assert_eq!(event, RatchetEvent::RatchetStepped { new_remote_key: *addressee.current_public_key() });
+        assert_eq!(drawn_key_pairs.load(Ordering::SeqCst), ratchet_step);
+
+        assert_eq!(initiator.decrypt_message(addressee.encrypt_message(b"pong")).unwrap(), b"pong");
+        assert_eq!(drawn_key_pairs.load(Ordering::SeqCst), ratchet_step);
+    }
+}
+
+/// A key pair source handing out key pairs that were generated in advance, like a hardware security module that
+/// holds a limited number of keys
+struct PregeneratedKeyPairSource {
+    key_pairs: VecDeque<(u64, u64)>,
+}
+
+impl PregeneratedKeyPairSource {
+    fn new(rng: &mut StdRng, count: usize) -> Self {
+        PregeneratedKeyPairSource {
+            key_pairs: (0..count)
+                .map(|_| ToyDiffieHellman::generate_asymmetrical_key_pair(rng, &TOY_GENERATOR))
+                .collect(),
+        }
+    }
+}
+
+impl KeyPairSource<ToyDiffieHellman> for PregeneratedKeyPairSource {
+    fn next_key_pair(&mut self) -> (u64, u64) {
+        self.key_pairs.pop_front().expect("all pregenerated key pairs were used")
+    }
+}
+
+#[test]
+fn test_pregenerated_key_pairs() {
+    let mut rng = StdRng::seed_from_u64(18);
+
+    // the initiator needs a key pair for the first message and one for every ratchet step, the addressee only for
+    // the ratchet steps
+    let initiator_source = PregeneratedKeyPairSource::new(&mut rng, 5);
+    let addressee_source = PregeneratedKeyPairSource::new(&mut rng, 4);
+    let last_public_keys = (initiator_source.key_pairs[4].1, addressee_source.key_pairs[3].1);
+
+    let (initiator, hello) = ToyProtocol::<Initiator, _>::initialize_sending_with_key_pair_source(
+        initiator_source, TOY_ROOT_KEY, TOY_SUITE, HashMapKeyStore::new());
+    let mut addressee = ToyProtocol::<Established, _>::initialize_receiving_with_key_pair_source(
+        addressee_source, hello.public_key, TOY_ROOT_KEY, TOY_SUITE, HashMapKeyStore::new()).unwrap();
+    let (mut initiator, clear_text) = initiator.decrypt_first_message(addressee.encrypt_message(b"hello")).unwrap();
+    assert_eq!(clear_text, b"hello");
+
+    for round in 0..3u8 {
+        assert_eq!(addressee.decrypt_message(initiator.encrypt_message(&[round])).unwrap(), vec![round]);
+        assert_eq!(initiator.decrypt_message(addressee.encrypt_message(&[round, round])).unwrap(), vec![round, round]);
+    }
+
+    assert_eq!((*initiator.current_public_key(), *addressee.current_public_key()), last_public_keys);
+}
+
+fn assert_replayed(result: Result<Vec<u8>, DecryptionException>, expected_message_number: usize) {
+    match result {
+        Err(DecryptionException::ReplayedMessage { message_number }) =>
+            assert_eq!(message_number, expected_message_number),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+fn assert_unknown(result: Result<Vec<u8>, DecryptionException>) {
+    match result {
+        Err(DecryptionException::UnknownMessageHeader {}) => {}
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn test_replayed_messages() {
+    let mut rng = StdRng::seed_from_u64(8);
+    let (mut initiator, mut addressee) = establish(&mut rng, HashMapKeyStore::new(), HashMapKeyStore::new());
+
+    // replay of an in-order message
+    let message = initiator.encrypt_message(b"in order");
+    assert_eq!(addressee.decrypt_message(message.clone()).unwrap(), b"in order");
+    assert_replayed(addressee.decrypt_message(message), 0);
+
+    // replay of an out-of-order message after its skipped key was consumed
+    let late = initiator.encrypt_message(b"late");
+    assert_eq!(addressee.decrypt_message(initiator.encrypt_message(b"early")).unwrap(), b"early");
+    match addressee.decrypt_message(late.clone()) {
+        Err(DecryptionException::OutOfOrderMessage { decrypted_message }) => assert_eq!(decrypted_message, b"late"),
+        result => panic!("unexpected result: {:?}", result),
+    }
+    assert_replayed(addressee.decrypt_message(late), 1);
+
+    // the messages of the previous chain are neither replayable nor start a new chain after a ratchet step
+    let skipped = initiator.encrypt_message(b"skipped");
+    let old_chain_message = initiator.encrypt_message(b"old chain");
+    assert_eq!(addressee.decrypt_message(old_chain_message.clone()).unwrap(), b"old chain");
+    assert_eq!(initiator.decrypt_message(addressee.encrypt_message(b"pong")).unwrap(), b"pong");
+    assert_eq!(addressee.decrypt_message(initiator.encrypt_message(b"new chain")).unwrap(), b"new chain");
+
+    assert_replayed(addressee.decrypt_message(old_chain_message), 4);
+    match addressee.decrypt_message(skipped) {
+        Err(DecryptionException::OutOfOrderMessage { decrypted_message }) =>
+            assert_eq!(decrypted_message, b"skipped"),
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    // the first message received by the initiator is tracked as well
+    let reply = addressee.encrypt_message(b"reply");
+    assert_eq!(initiator.decrypt_message(reply.clone()).unwrap(), b"reply");
+    assert_replayed(initiator.decrypt_message(reply), 0);
+
+    // the rejected replays did not change the protocol state
+    assert_eq!(addressee.stored_skipped_keys(), 0);
+    assert_eq!(addressee.decrypt_message(initiator.encrypt_message(b"valid")).unwrap(), b"valid");
+}
+
+#[test]
+fn test_replayed_first_message() {
+    let mut rng = StdRng::seed_from_u64(9);
+    let (initiator, hello) = ToyProtocol::<Initiator, HashMapKeyStore<u64, ToyKey>>::initialize_sending(
+        &mut rng, TOY_GENERATOR, TOY_ROOT_KEY, TOY_SUITE);
+    let mut addressee = ToyProtocol::<Established, HashMapKeyStore<u64, ToyKey>>::initialize_receiving(
+        &mut rng, TOY_GENERATOR, hello.public_key, TOY_ROOT_KEY, TOY_SUITE).unwrap();
+
+    let first = addressee.encrypt_message(b"hello");
+    let (mut initiator, _) = initiator.decrypt_first_message(first.clone()).unwrap();
+    assert_replayed(initiator.decrypt_message(first), 0);
+}
+
+fn assert_suite_mismatch(result: Result<Vec<u8>, DecryptionException>, expected_got: CipherSuite) {
+    match result {
+        Err(DecryptionException::SuiteMismatch { got, expected }) => {
+            assert_eq!(got, expected_got);
+            assert_eq!(expected, TOY_SUITE);
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn test_suite_mismatch_rejected() {
+    let mut rng = StdRng::seed_from_u64(20);
+    let other_suite = CipherSuite { aead: AeadAlgorithm::Aes128Gcm, ..TOY_SUITE };
+
+    // the first message of the addressee
+    let (initiator, hello) = ToyProtocol::<Initiator, HashMapKeyStore<u64, ToyKey>>::initialize_sending(
+        &mut rng, TOY_GENERATOR, TOY_ROOT_KEY, TOY_SUITE);
+    let mut addressee = ToyProtocol::<Established, HashMapKeyStore<u64, ToyKey>>::initialize_receiving(
+        &mut rng, TOY_GENERATOR, hello.public_key, TOY_ROOT_KEY, TOY_SUITE).unwrap();
+    let mut first = addressee.encrypt_message(b"hello");
+    first.cipher_suite = other_suite;
+    match initiator.decrypt_first_message(first) {
+        Err(DecryptionException::SuiteMismatch { got, expected }) => {
+            assert_eq!(got, other_suite);
+            assert_eq!(expected, TOY_SUITE);
+        }
+        Err(e) => panic!("unexpected error: {:?}", e),
+        Ok(_) => panic!("the message of another suite was decrypted"),
+    }
+
+    // messages of the current chain and messages starting a new chain
+    let (mut initiator, mut addressee) = establish(&mut rng, HashMapKeyStore::new(), HashMapKeyStore::new());
+    let mut same_chain = addressee.encrypt_message(b"same chain");
+    let skipping = initiator.encrypt_message(b"skipped");
+    let mut skipping_chain = initiator.encrypt_message(b"new chain");
+    let public_key = *initiator.current_public_key();
+    assert_eq!(initiator.decrypt_message(addressee.encrypt_message(b"ping")).unwrap(), b"ping");
+
+    same_chain.cipher_suite = other_suite;
+    skipping_chain.cipher_suite = other_suite;
+    assert_suite_mismatch(initiator.decrypt_message(same_chain.clone()), other_suite);
+    assert_suite_mismatch(addressee.decrypt_message(skipping_chain.clone()), other_suite);
+
+    // the rejected messages did not change the protocol state
+    assert_eq!(initiator.current_receiving_chain_length(), 3);
+    assert_eq!(initiator.stored_skipped_keys(), 1);
+    assert_eq!(initiator.current_public_key(), &public_key);
+    assert_eq!(addressee.current_receiving_chain_length(), 0);
+    assert_eq!(addressee.stored_skipped_keys(), 0);
+
+    same_chain.cipher_suite = TOY_SUITE;
+    skipping_chain.cipher_suite = TOY_SUITE;
+    match initiator.decrypt_message(same_chain) {
+        Err(DecryptionException::OutOfOrderMessage { decrypted_message }) =>
+            assert_eq!(decrypted_message, b"same chain"),
+        result => panic!("unexpected result: {:?}", result),
+    }
+    assert_eq!(addressee.decrypt_message(skipping_chain).unwrap(), b"new chain");
+    match addressee.decrypt_message(skipping) {
+        Err(DecryptionException::OutOfOrderMessage { decrypted_message }) => assert_eq!(decrypted_message, b"skipped"),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn test_message_encoding_round_trip() {
+    let mut rng = StdRng::seed_from_u64(21);
+    let (initiator, hello) = ToyProtocol::<Initiator, HashMapKeyStore<u64, ToyKey>>::initialize_sending(
+        &mut rng, TOY_GENERATOR, TOY_ROOT_KEY, TOY_SUITE);
+
+    // the first message of the initiator has no cipher text
+    let decoded_hello =
+        DoubleRatchetAlgorithmMessage::<u64, Vec<u8>>::decode::<ToyDiffieHellman>(&hello.encode::<ToyDiffieHellman>())
+            .unwrap();
+    assert_eq!(decoded_hello.cipher_suite(), TOY_SUITE);
+    assert_eq!(decoded_hello.public_key, hello.public_key);
+    assert_eq!((decoded_hello.message_number, decoded_hello.previous_chain_length), (0, 0));
+    assert!(decoded_hello.message.is_none());
+
+    let mut addressee = ToyProtocol::<Established, HashMapKeyStore<u64, ToyKey>>::initialize_receiving(
+        &mut rng, TOY_GENERATOR, decoded_hello.public_key, TOY_ROOT_KEY, TOY_SUITE).unwrap();
+    let transmit = |message: DoubleRatchetAlgorithmMessage<u64, Vec<u8>>| {
+        DoubleRatchetAlgorithmMessage::decode::<ToyDiffieHellman>(&message.encode::<ToyDiffieHellman>()).unwrap()
+    };
+
+    let (mut initiator, clear_text) =
+        initiator.decrypt_first_message(transmit(addressee.encrypt_message(b"hello"))).unwrap();
+    assert_eq!(clear_text, b"hello");
+
+    let empty = initiator.encrypt_message(b"");
+    let decoded_empty = transmit(empty.clone());
+    assert_eq!(decoded_empty.message, empty.message);
+    assert_eq!(addressee.decrypt_message(decoded_empty).unwrap(), b"");
+
+    for round in 0..3u8 {
+        let message = addressee.encrypt_message(&[round]);
+        let decoded = transmit(message.clone());
+        assert_eq!(decoded.public_key, message.public_key);
+        assert_eq!(decoded.message_number, message.message_number);
+        assert_eq!(decoded.previous_chain_length, message.previous_chain_length);
+        assert_eq!(decoded.message, message.message);
+        assert_eq!(initiator.decrypt_message(decoded).unwrap(), vec![round]);
+    }
+}
+
+/// Encode `message` with the cipher text flag `0` and without its cipher text, like the first message of an initiator.
+fn encode_without_cipher_text(message: &DoubleRatchetAlgorithmMessage<u64, Vec<u8>>) -> Vec<u8> {
+    let mut encoding = message.encode::<ToyDiffieHellman>();
+    encoding.truncate(encoding.len() - message.message.as_ref().unwrap().len());
+    *encoding.last_mut().unwrap() = 0;
+    encoding
+}
+
+#[test]
+fn test_missing_cipher_text_rejected() {
+    let mut rng = StdRng::seed_from_u64(22);
+    let (initiator, hello) = ToyProtocol::<Initiator, HashMapKeyStore<u64, ToyKey>>::initialize_sending(
+        &mut rng, TOY_GENERATOR, TOY_ROOT_KEY, TOY_SUITE);
+    let mut addressee = ToyProtocol::<Established, HashMapKeyStore<u64, ToyKey>>::initialize_receiving(
+        &mut rng, TOY_GENERATOR, hello.public_key, TOY_ROOT_KEY, TOY_SUITE).unwrap();
+
+    let reply = addressee.encrypt_message(b"hello");
+    let decoded = DoubleRatchetAlgorithmMessage::decode::<ToyDiffieHellman>(&encode_without_cipher_text(&reply))
+        .unwrap();
+    assert!(decoded.message.is_none());
+    match initiator.decrypt_first_message(decoded) {
+        Err(DecryptionException::InvalidMessageHeader {}) => {}
+        result => panic!("unexpected result: {:?}", result.map(|(_, clear_text)| clear_text)),
+    }
+
+    let (mut initiator, mut addressee) = establish(&mut rng, HashMapKeyStore::new(), HashMapKeyStore::new());
+    let message = initiator.encrypt_message(b"flagged");
+    let decoded = DoubleRatchetAlgorithmMessage::decode::<ToyDiffieHellman>(&encode_without_cipher_text(&message))
+        .unwrap();
+    match addressee.decrypt_message(decoded) {
+        Err(DecryptionException::InvalidMessageHeader {}) => {}
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    // the rejected message did not use up the message key
+    assert_eq!(addressee.current_receiving_chain_length(), 0);
+    assert_eq!(addressee.decrypt_message(message).unwrap(), b"flagged");
+}
+
+#[test]
+fn test_message_header_layout() {
+    let message = DoubleRatchetAlgorithmMessage {
+        cipher_suite: TOY_SUITE,
+        public_key: 0x0102_0304_0506_0708_u64,
+        message_number: 5,
+        previous_chain_length: 0x0100,
+        message: Some(vec![0xAA, 0xBB]),
+    };
+
+    let encoding = message.encode::<ToyDiffieHellman>();
+    assert_eq!(encoding, vec![
+        MESSAGE_VERSION, 0x32, 0x12, // version and suite id
+        0x00, 0x08, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, // public key
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, // message number
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, // previous chain length
+        0x01, 0xAA, 0xBB, // cipher text
+    ]);
+    assert_eq!(MESSAGE_VERSION, 1);
+
+    let decode = |bytes: &[u8]| DoubleRatchetAlgorithmMessage::<u64, Vec<u8>>::decode::<ToyDiffieHellman>(bytes).err();
+    let with_byte = |index: usize, byte: u8| {
+        let mut bytes = encoding.clone();
+        bytes[index] = byte;
+        bytes
+    };
+
+    assert_eq!(decode(&with_byte(0, 2)), Some(MessageDecodeException::UnsupportedVersion { version: 2 }));
+    assert_eq!(decode(&with_byte(1, 0x72)), Some(MessageDecodeException::UnknownCipherSuite { id: 0x7212 }));
+    assert_eq!(decode(&with_byte(4, 0x07)), Some(MessageDecodeException::InvalidPublicKey {
+        cause: KeyDecodeException::InvalidLength { expected: 8, given: 7 },
+    }));
+    assert_eq!(decode(&with_byte(29, 0x02)), Some(MessageDecodeException::InvalidCipherTextFlag { flag: 2 }));
+    assert_eq!(decode(&with_byte(29, 0x00)), Some(MessageDecodeException::InvalidCipherTextFlag { flag: 0 }));
+    assert_eq!(decode(&encoding[..29]), Some(MessageDecodeException::Truncated {}));
+    assert_eq!(decode(&[]), Some(MessageDecodeException::Truncated {}));
+}
+
+#[test]
+fn test_debug_redacted() {
+    let mut rng = StdRng::seed_from_u64(10);
+    let (mut initiator, mut addressee) =
+        establish(&mut rng, HashMapKeyStore::new(), HashMapKeyStore::new());
+    assert_eq!(addressee.decrypt_message(initiator.encrypt_message(b"ping")).unwrap(), b"ping");
+    assert_eq!(initiator.decrypt_message(addressee.encrypt_message(b"pong")).unwrap(), b"pong");
+
+    // the sending chain of the last ratchet step is derived with the first message sent in it
+    initiator.encrypt_message(b"unsent");
+
+    for protocol in [initiator, addressee].iter() {
+        let debug = format!("{:?}", protocol);
+        assert!(debug.contains(&format!("diffie_hellman_public_key: {}", protocol.current_public_key())));
+
+        let secrets = [
+            protocol.diffie_hellman_private_key.unwrap(),
+            *protocol.root_chain_key.unwrap(),
+            *protocol.sending_chain_key.unwrap(),
+            *protocol.receiving_chain_key.unwrap(),
+        ];
+        for secret in secrets.iter() {
+            assert!(!debug.contains(&secret.to_string()), "{}", debug);
+            assert!(!debug.contains(&format!("{:x}", secret)), "{}", debug);
+            assert!(!debug.contains(&format!("{:X}", secret)), "{}", debug);
+        }
+    }
+
+    let key = KeyPrefixEncryption::generate_key(&mut rng);
+    assert_eq!(format!("{:?}", key), "<redacted>");
+    assert_eq!(format!("{:?}", TestEncryption::generate_key(&mut rng)), "<redacted>");
+}
+
+/// A message KDF with an array as constant input.
+struct ArrayLabelKdf;
+
+impl KeyDerivationFunction for ArrayLabelKdf {
+    type ChainKey = u64;
+    type Input = [u8; 4];
+    type OutputKey = u64;
+
+    fn derive_key(chain_key: u64, input: [u8; 4]) -> (u64, u64) {
+        let input = u32::from_le_bytes(input) as u64;
+        (toy_prf(chain_key, input, 1), toy_prf(chain_key, input, 2))
+    }
+}
+
+impl ConstantInputKeyRatchet for ArrayLabelKdf {
+    fn constant_input() -> [u8; 4] {
+        *b"msg\0"
+    }
+}
+
+/// A message KDF with a heap allocated constant input, which cannot be an associated constant.
+struct VecLabelKdf;
+
+impl KeyDerivationFunction for VecLabelKdf {
+    type ChainKey = u64;
+    type Input = Vec<u8>;
+    type OutputKey = u64;
+
+    fn derive_key(chain_key: u64, input: Vec<u8>) -> (u64, u64) {
+        let input = siphash24(&[0; 16], &input);
+        (toy_prf(chain_key, input, 1), toy_prf(chain_key, input, 2))
+    }
+}
+
+impl ConstantInputKeyRatchet for VecLabelKdf {
+    fn constant_input() -> Vec<u8> {
+        b"jester message chain".to_vec()
+    }
+}
+
+/// A message KDF implementing the deprecated trait with an associated constant.
+struct LegacyKdf;
+
+impl KeyDerivationFunction for LegacyKdf {
+    type ChainKey = ToyKey;
+    type Input = u64;
+    type OutputKey = ToyKey;
+
+    fn derive_key(chain_key: ToyKey, input: u64) -> (ToyKey, ToyKey) {
+        ToyKdf::derive_key(chain_key, input)
+    }
+}
+
+#[allow(deprecated)]
+impl crate::ConstInputKeyRatchet for LegacyKdf {
+    const INPUT: u64 = 0;
+}
+
+/// Ratchet `chain_key` forward `steps` times and collect the output keys.
+fn ratchet_outputs<K>(mut chain_key: K::ChainKey, steps: usize) -> Vec<K::OutputKey>
+where
+    K: ConstantInputKeyRatchet,
+{
+    let mut outputs = Vec::with_capacity(steps);
+    for _ in 0..steps {
+        let (next_chain_key, output_key) = K::derive_key_without_input(chain_key);
+        chain_key = next_chain_key;
+        outputs.push(output_key);
+    }
+    outputs
+}
+
+#[test]
+fn test_constant_input_types() {
+    let (_, array_output) = ArrayLabelKdf::derive_key(42, *b"msg\0");
+    assert_eq!(ratchet_outputs::<ArrayLabelKdf>(42, 3)[0], array_output);
+
+    let (_, vec_output) = VecLabelKdf::derive_key(42, b"jester message chain".to_vec());
+    assert_eq!(ratchet_outputs::<VecLabelKdf>(42, 3)[0], vec_output);
+    assert_ne!(ratchet_outputs::<VecLabelKdf>(42, 3), ratchet_outputs::<ArrayLabelKdf>(42, 3));
+
+    assert_eq!(
+        ratchet_outputs::<LegacyKdf>(TOY_ROOT_KEY, 3),
+        ratchet_outputs::<ToyKdf>(TOY_ROOT_KEY, 3)
+    );
+}
+
+impl SenderChainKey for ToyKey {
+    fn generate<R>(rng: &mut R) -> Self where
+        R: RngCore + CryptoRng {
+        Redacted::new(rng.next_u64())
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_le_bytes().to_vec()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        Some(Redacted::new(u64::from_le_bytes(bytes.try_into().ok()?)))
+    }
+}
+
+type ToyChannel = ToyProtocol<Established, HashMapKeyStore<u64, ToyKey>>;
+
+type ToyGroupSession = GroupSession<KeyPrefixEncryption, ToyKdf, &'static str, ToyKey, ToyKey>;
+
+/// The group sessions of the members of a group and the pairwise protocols between them, which are used to send
+/// the sender key distributions.
+struct ToyGroup {
+    rng: StdRng,
+    sessions: HashMap<&'static str, ToyGroupSession>,
+    channels: HashMap<(&'static str, &'static str), ToyChannel>,
+}
+
+impl ToyGroup {
+    fn new(seed: u64, members: &[&'static str]) -> Self {
+        let mut group = ToyGroup {
+            rng: StdRng::seed_from_u64(seed),
+            sessions: HashMap::new(),
+            channels: HashMap::new(),
+        };
+        let mut distributions = vec![];
+        for member in members.iter() {
+            let (session, member_distributions) =
+                ToyGroupSession::new(&mut group.rng, *member, members.iter().cloned());
+            group.sessions.insert(*member, session);
+            distributions.push((*member, member_distributions));
+        }
+
+        for (member, member_distributions) in distributions {
+            group.distribute(member, member_distributions);
+        }
+        group
+    }
+
+    /// Add `member` to the group and distribute the sender chains of all members to it and vice versa.
+    fn join(&mut self, member: &'static str) {
+        let members = self.sessions.keys().cloned().collect::<Vec<_>>();
+        let (session, distributions) = ToyGroupSession::new(&mut self.rng, member, members.iter().cloned());
+        self.sessions.insert(member, session);
+
+        for existing in members {
+            let distribution = self.sessions.get_mut(existing).unwrap().add_member(member);
+            self.distribute(existing, vec![distribution]);
+        }
+        self.distribute(member, distributions);
+    }
+
+    /// Send the distributions of `sender` over the pairwise protocols and process them at the recipients.
+    fn distribute(&mut self, sender: &'static str, distributions: Vec<SenderKeyDistribution<&'static str>>) {
+        for distribution in distributions {
+            let recipient = distribution.recipient;
+            if !self.channels.contains_key(&(sender, recipient)) {
+                let (initiator, addressee) =
+                    establish(&mut self.rng, HashMapKeyStore::new(), HashMapKeyStore::new());
+                self.channels.insert((sender, recipient), initiator);
+                self.channels.insert((recipient, sender), addressee);
+            }
+
+            let encrypted = self.channels.get_mut(&(sender, recipient)).unwrap().encrypt_message(&distribution.payload);
+            let payload = self.channels.get_mut(&(recipient, sender)).unwrap()
+                .decrypt_message(encrypted)
+                .unwrap();
+            self.session(recipient).process_distribution(sender, &payload).unwrap();
+        }
+    }
+
+    fn session(&mut self, member: &'static str) -> &mut ToyGroupSession {
+        self.sessions.get_mut(member).unwrap()
+    }
+}
+
+#[test]
+fn test_group_messages() {
+    let mut group = ToyGroup::new(11, &["alice", "bob", "carol"]);
+
+    let hello = group.session("alice").encrypt_to_group(b"hello group");
+    assert_eq!(hello.sender(), &"alice");
+    assert_eq!(group.session("bob").decrypt_group_message(hello.clone()).unwrap(), b"hello group");
+    assert_eq!(group.session("carol").decrypt_group_message(hello.clone()).unwrap(), b"hello group");
+    assert_replayed(group.session("bob").decrypt_group_message(hello), 0);
+
+    for (sender, text) in [("bob", &b"hi alice"[..]), ("carol", &b"hi all"[..]), ("bob", &b"again"[..])].iter() {
+        let message = group.session(sender).encrypt_to_group(text);
+        for recipient in ["alice", "bob", "carol"].iter().filter(|recipient| *recipient != sender) {
+            assert_eq!(group.session(recipient).decrypt_group_message(message.clone()).unwrap(), *text);
+        }
+    }
+
+    // after carol left, the rotated chain of alice is not distributed to carol anymore
+    let old_chain = group.session("alice").encrypt_to_group(b"before rotation");
+    let distributions = group.session("alice").remove_member(&mut StdRng::seed_from_u64(12), &"carol");
+    assert_eq!(distributions.iter().map(|d| d.recipient).collect::<Vec<_>>(), vec!["bob"]);
+    group.distribute("alice", distributions);
+
+    let rotated = group.session("alice").encrypt_to_group(b"without carol");
+    assert_eq!(group.session("bob").decrypt_group_message(rotated.clone()).unwrap(), b"without carol");
+    assert_unknown(group.session("carol").decrypt_group_message(rotated));
+
+    // a message of the old chain, that arrives after the rotation, is still decrypted from the skipped keys
+    assert_eq!(group.session("carol").decrypt_group_message(old_chain.clone()).unwrap(), b"before rotation");
+    assert_unknown(group.session("bob").decrypt_group_message(old_chain));
+}
+
+#[test]
+fn test_group_member_joins() {
+    let mut group = ToyGroup::new(13, &["alice", "bob"]);
+
+    let early = group.session("alice").encrypt_to_group(b"before dave joined");
+    assert_eq!(group.session("bob").decrypt_group_message(early.clone()).unwrap(), b"before dave joined");
+
+    group.join("dave");
+    assert_unknown(group.session("dave").decrypt_group_message(early));
+
+    let late = group.session("alice").encrypt_to_group(b"welcome dave");
+    assert_eq!(group.session("dave").decrypt_group_message(late.clone()).unwrap(), b"welcome dave");
+    assert_eq!(group.session("bob").decrypt_group_message(late).unwrap(), b"welcome dave");
+
+    let reply = group.session("dave").encrypt_to_group(b"thanks");
+    assert_eq!(group.session("alice").decrypt_group_message(reply.clone()).unwrap(), b"thanks");
+    assert_eq!(group.session("bob").decrypt_group_message(reply).unwrap(), b"thanks");
+}
+
+#[test]
+fn test_group_out_of_order() {
+    let mut group = ToyGroup::new(14, &["alice", "bob"]);
+
+    let messages = (0..5u8)
+        .map(|i| group.session("alice").encrypt_to_group(&[i]))
+        .collect::<Vec<_>>();
+
+    assert_eq!(group.session("bob").decrypt_group_message(messages[3].clone()).unwrap(), vec![3]);
+    assert_eq!(group.session("bob").stored_skipped_keys(), 3);
+
+    for i in [1, 0, 2].iter() {
+        match group.session("bob").decrypt_group_message(messages[*i].clone()) {
+            Err(DecryptionException::OutOfOrderMessage { decrypted_message }) => {
+                assert_eq!(decrypted_message, vec![*i as u8])
+            }
+            _ => panic!("message {} was not decrypted out of order", i),
+        }
+    }
+    assert_eq!(group.session("bob").stored_skipped_keys(), 0);
+    assert_replayed(group.session("bob").decrypt_group_message(messages[1].clone()), 1);
+
+    assert_eq!(group.session("bob").decrypt_group_message(messages[4].clone()).unwrap(), vec![4]);
+}
+
+#[test]
+fn test_group_tampered_message() {
+    let mut group = ToyGroup::new(16, &["alice", "bob"]);
+
+    let skipped = group.session("alice").encrypt_to_group(b"skipped");
+    let message = group.session("alice").encrypt_to_group(b"message");
+    let tampered = |message: &GroupMessage<&'static str, Vec<u8>>| {
+        let mut message = message.clone();
+        message.message[0] ^= 1;
+        message
+    };
+
+    // a forged message neither advances the chain nor uses up the key of the real message
+    assert_authentication_failed(group.session("bob").decrypt_group_message(tampered(&message)));
+    assert_eq!(group.session("bob").stored_skipped_keys(), 0);
+    assert_eq!(group.session("bob").decrypt_group_message(message).unwrap(), b"message");
+    assert_eq!(group.session("bob").stored_skipped_keys(), 1);
+
+    assert_authentication_failed(group.session("bob").decrypt_group_message(tampered(&skipped)));
+    assert_eq!(group.session("bob").stored_skipped_keys(), 1);
+    assert_out_of_order(group.session("bob").decrypt_group_message(skipped), b"skipped");
+}
+
+#[test]
+fn test_group_skip_limit() {
+    let mut group = ToyGroup::new(17, &["alice", "bob"]);
+
+    let mut forged = group.session("alice").encrypt_to_group(b"forged");
+    forged.message_number = MAX_SKIPPED_MESSAGES as usize + 1;
+    assert_unknown(group.session("bob").decrypt_group_message(forged));
+    assert_eq!(group.session("bob").stored_skipped_keys(), 0);
+
+    let message = group.session("alice").encrypt_to_group(b"message");
+    assert_eq!(group.session("bob").decrypt_group_message(message).unwrap(), b"message");
+    assert_eq!(group.session("bob").stored_skipped_keys(), 1);
+}
+
+#[test]
+fn test_group_distribution_rejected() {
+    let mut rng = StdRng::seed_from_u64(15);
+    let (mut alice, _) = ToyGroupSession::new(&mut rng, "alice", vec!["bob"]);
+    let (mut bob, distributions) = ToyGroupSession::new(&mut rng, "bob", vec!["alice"]);
+    let payload = distributions[0].payload.clone().into_inner();
+
+    assert_eq!(alice.process_distribution("mallory", &payload), Err(GroupException::UnknownMember {}));
+    assert_eq!(alice.process_distribution("bob", &payload[..11]), Err(GroupException::InvalidDistribution {}));
+    assert_eq!(alice.process_distribution("bob", &payload), Ok(()));
+    assert_eq!(alice.process_distribution("bob", &payload), Err(GroupException::OutdatedDistribution { key_id: 0 }));
+
+    let rotated = bob.rotate_sender_key(&mut rng);
+    assert_eq!(alice.process_distribution("bob", &rotated[0].payload), Ok(()));
+}
+
+type ToySymmetricChannel<Store> = SymmetricRatchetChannel<ToyKdf, KeyPrefixEncryption, Store>;
+
+fn assert_out_of_order(result: Result<Vec<u8>, DecryptionException>, expected: &[u8]) {
+    match result {
+        Err(DecryptionException::OutOfOrderMessage { decrypted_message }) => assert_eq!(decrypted_message, expected),
+        _ => panic!("message was not decrypted out of order"),
+    }
+}
+
+#[test]
+fn test_symmetric_channel_in_order() {
+    let mut sender = SymmetricRatchetChannel::<ToyKdf, KeyPrefixEncryption>::new(Redacted::new(16));
+    let mut receiver = SymmetricRatchetChannel::<ToyKdf, KeyPrefixEncryption>::new(Redacted::new(16));
+
+    for i in 0..5u8 {
+        let (index, cipher_text) = sender.encrypt_next(&[i]);
+        assert_eq!(index, i as u64);
+        assert_eq!(receiver.decrypt(index, &cipher_text).unwrap(), vec![i]);
+    }
+
+    assert_eq!(sender.chain_length(), 5);
+    assert_eq!(receiver.chain_length(), 5);
+    assert_eq!(receiver.stored_skipped_keys(), 0);
+}
+
+#[test]
+fn test_symmetric_channel_out_of_order() {
+    let mut sender = SymmetricRatchetChannel::<ToyKdf, KeyPrefixEncryption>::new(Redacted::new(17));
+    let mut receiver = SymmetricRatchetChannel::<ToyKdf, KeyPrefixEncryption>::new(Redacted::new(17));
+    let messages = (0..6u8).map(|i| sender.encrypt_next(&[i])).collect::<Vec<_>>();
+
+    assert_eq!(receiver.decrypt(messages[3].0, &messages[3].1).unwrap(), vec![3]);
+    assert_eq!(receiver.stored_skipped_keys(), 3);
+
+    for i in [2, 0].iter() {
+        assert_out_of_order(receiver.decrypt(messages[*i].0, &messages[*i].1), &[*i as u8]);
+    }
+    assert_eq!(receiver.stored_skipped_keys(), 1);
+    assert_replayed(receiver.decrypt(messages[3].0, &messages[3].1), 3);
+    assert_replayed(receiver.decrypt(messages[0].0, &messages[0].1), 0);
+
+    assert_eq!(receiver.decrypt(messages[5].0, &messages[5].1).unwrap(), vec![5]);
+    assert_out_of_order(receiver.decrypt(messages[4].0, &messages[4].1), &[4]);
+    assert_out_of_order(receiver.decrypt(messages[1].0, &messages[1].1), &[1]);
+    assert_eq!(receiver.stored_skipped_keys(), 0);
+}
+
+#[test]
+fn test_symmetric_channel_tampered_message() {
+    let mut sender = SymmetricRatchetChannel::<ToyKdf, KeyPrefixEncryption>::new(Redacted::new(19));
+    let mut receiver = SymmetricRatchetChannel::<ToyKdf, KeyPrefixEncryption>::new(Redacted::new(19));
+    let messages = (0..2u8).map(|i| sender.encrypt_next(&[i])).collect::<Vec<_>>();
+    let tampered = |cipher_text: &[u8]| {
+        let mut cipher_text = cipher_text.to_vec();
+        cipher_text[0] ^= 1;
+        cipher_text
+    };
+
+    // a forged message neither advances the chain nor uses up the key of the real message
+    assert_authentication_failed(receiver.decrypt(messages[1].0, &tampered(&messages[1].1)));
+    assert_eq!((receiver.chain_length(), receiver.stored_skipped_keys()), (0, 0));
+    assert_eq!(receiver.decrypt(messages[1].0, &messages[1].1).unwrap(), vec![1]);
+    assert_eq!((receiver.chain_length(), receiver.stored_skipped_keys()), (2, 1));
+
+    assert_authentication_failed(receiver.decrypt(messages[0].0, &tampered(&messages[0].1)));
+    assert_eq!(receiver.stored_skipped_keys(), 1);
+    assert_out_of_order(receiver.decrypt(messages[0].0, &messages[0].1), &[0]);
+}
+
+#[test]
+fn test_symmetric_channel_skipped_key_limits() {
+    let mut sender = SymmetricRatchetChannel::<ToyKdf, KeyPrefixEncryption>::new(Redacted::new(18));
+    let mut receiver = ToySymmetricChannel::new_with_store(Redacted::new(18), RingBufferKeyStore::with_capacity(2));
+    let messages = (0..5u8).map(|i| sender.encrypt_next(&[i])).collect::<Vec<_>>();
+
+    // the keys of messages 0 and 1 are evicted by the keys of messages 2 and 3
+    assert_eq!(receiver.decrypt(messages[4].0, &messages[4].1).unwrap(), vec![4]);
+    assert_eq!(receiver.stored_skipped_keys(), 2);
+    let evicted = receiver.decrypt(messages[0].0, &messages[0].1);
+    assert!(matches!(evicted, Err(DecryptionException::UnknownMessageHeader {})));
+    assert_out_of_order(receiver.decrypt(messages[3].0, &messages[3].1), &[3]);
+
+    // a message too far ahead of the chain is rejected without advancing the chain
+    let (_, cipher_text) = sender.encrypt_next(&[5]);
+    let too_far = receiver.decrypt(5 + MAX_SKIPPED_MESSAGES + 1, &cipher_text);
+    assert!(matches!(too_far, Err(DecryptionException::UnknownMessageHeader {})));
+    assert!(matches!(receiver.decrypt(u64::MAX, &cipher_text), Err(DecryptionException::UnknownMessageHeader {})));
+    assert_eq!(receiver.chain_length(), 5);
+    assert_eq!(receiver.decrypt(5, &cipher_text).unwrap(), vec![5]);
+}
+
+#[test]
+fn test_symmetric_channel_forward_secrecy() {
+    let mut sender = SymmetricRatchetChannel::<ToyKdf, KeyPrefixEncryption>::new(Redacted::new(19));
+    let mut receiver = SymmetricRatchetChannel::<ToyKdf, KeyPrefixEncryption>::new(Redacted::new(19));
+    let messages = (0..8u8).map(|i| sender.encrypt_next(&[i])).collect::<Vec<_>>();
+
+    assert_eq!(receiver.decrypt(messages[4].0, &messages[4].1).unwrap(), vec![4]);
+    for i in [1, 3, 0, 2].iter() {
+        assert_out_of_order(receiver.decrypt(messages[*i].0, &messages[*i].1), &[*i as u8]);
+    }
+    assert_eq!(receiver.decrypt(messages[6].0, &messages[6].1).unwrap(), vec![6]);
+
+    // after decrypting message 6, only the key of the skipped message 5 is left
+    for n in 0..=6 {
+        assert_eq!(receiver.skipped_key_store().contains(&(), n), n == 5);
+    }
+    assert_eq!(receiver.stored_skipped_keys(), 1);
+    assert_out_of_order(receiver.decrypt(messages[5].0, &messages[5].1), &[5]);
+    assert!(receiver.skipped_key_store().is_empty());
+}
+
+/// The protocol with `HashKdfChain`s as root and message chains, whose keys are `ChainKey`s.
+#[cfg(feature = "hash_kdf")]
+mod hash_kdf_protocol {
+    use super::*;
+    use crate::chain_key::ChainKey;
+    use jester_hashes::kdf::HashKdfChain;
+    use crate::message_encryption::GcmMessageEncryption;
+    use jester_encryption::aes::Aes256;
+    use jester_hashes::blake::blake2b::Blake2b;
+
+    /// The `ToyDiffieHellman` scheme with shared keys encoded as bytes, which is the input type of `HashKdfChain`.
+    struct ToyBytesDiffieHellman;
+
+    impl DiffieHellmanKeyExchangeScheme for ToyBytesDiffieHellman {
+        type PublicKey = u64;
+        type PrivateKey = u64;
+        type SharedKey = Vec<u8>;
+
+        fn generate_asymmetrical_key_pair<R>(rng: &mut R, generator: &u64) -> (u64, u64) where
+            R: RngCore + CryptoRng {
+            ToyDiffieHellman::generate_asymmetrical_key_pair(rng, generator)
+        }
+
+        fn generate_shared_secret(private_key: &u64, partner_key: &u64) -> Vec<u8> {
+            ToyDiffieHellman::generate_shared_secret(private_key, partner_key).to_le_bytes().to_vec()
+        }
+    }
+
+    #[cfg(feature = "resumption")]
+    impl DhKeyCodec for ToyBytesDiffieHellman {
+        fn encode_public_key(key: &u64) -> Vec<u8> {
+            ToyDiffieHellman::encode_public_key(key)
+        }
+
+        fn decode_public_key(bytes: &[u8]) -> Result<u64, KeyDecodeException> {
+            ToyDiffieHellman::decode_public_key(bytes)
+        }
+    }
+
+    /// An encryption scheme for testing, that prepends the key to the clear text and panics, if the key is wrong in
+    /// decryption.
+    struct ChainKeyPrefixEncryption;
+
+    impl SymmetricalEncryptionScheme for ChainKeyPrefixEncryption {
+        type Key = ChainKey;
+
+        fn generate_key<R>(rng: &mut R) -> Self::Key where
+            R: RngCore + CryptoRng {
+            ChainKey::new(rng.next_u64().to_le_bytes().to_vec())
+        }
+
+        fn encrypt_message(key: &Self::Key, message: &[u8]) -> Vec<u8> {
+            [key.as_bytes(), message].concat()
+        }
+
+        fn decrypt_message(key: &Self::Key, message: &[u8]) -> Vec<u8> {
+            if message.starts_with(key.as_bytes()) {
+                message[key.as_bytes().len()..].to_vec()
+            } else {
+                panic!("wrong key")
+            }
+        }
+    }
+
+    type HashKdfProtocol<State> = DoubleRatchetProtocol<
+        ToyBytesDiffieHellman, ChainKeyPrefixEncryption, HashKdfChain<SHA1Hash>, HashKdfChain<SHA1Hash>, u64, u64,
+        Vec<u8>, ChainKey, ChainKey, ChainKey, State>;
+
+    fn root_key() -> ChainKey {
+        ChainKey::new(TOY_ROOT_KEY.to_le_bytes().to_vec())
+    }
+
+    #[test]
+    fn test_chain_key_round_trip() {
+        let mut rng = StdRng::seed_from_u64(8);
+        let (initiator, hello) = HashKdfProtocol::<Initiator>::initialize_sending(
+            &mut rng, TOY_GENERATOR, root_key(), TOY_SUITE);
+        let mut addressee = HashKdfProtocol::<Established>::initialize_receiving(
+            &mut rng, TOY_GENERATOR, hello.public_key, root_key(), TOY_SUITE).unwrap();
+
+        let (mut initiator, clear_text) = initiator
+            .decrypt_first_message(addressee.encrypt_message(b"hello"))
+            .unwrap();
+        assert_eq!(clear_text, b"hello");
+
+        for _ in 0..3 {
+            assert_eq!(addressee.decrypt_message(initiator.encrypt_message(b"ping")).unwrap(), b"ping");
+            assert_eq!(initiator.decrypt_message(addressee.encrypt_message(b"pong")).unwrap(), b"pong");
+        }
+    }
+
+    type GcmProtocol<State> = DoubleRatchetProtocol<
+        ToyBytesDiffieHellman, GcmMessageEncryption<Blake2b, Aes256>, HashKdfChain<SHA1Hash>, HashKdfChain<SHA1Hash>,
+        u64, u64, Vec<u8>, ChainKey, ChainKey, ChainKey, State>;
+
+    fn tampered(message: &DoubleRatchetAlgorithmMessage<u64, Vec<u8>>) -> DoubleRatchetAlgorithmMessage<u64, Vec<u8>> {
+        let mut message = message.clone();
+        message.message.as_mut().unwrap()[0] ^= 1;
+        message
+    }
+
+    #[test]
+    fn test_tampered_cipher_text_rejected() {
+        let mut rng = StdRng::seed_from_u64(10);
+        let (initiator, hello) = GcmProtocol::<Initiator>::initialize_sending(
+            &mut rng, TOY_GENERATOR, root_key(), TOY_SUITE);
+        let mut addressee = GcmProtocol::<Established>::initialize_receiving(
+            &mut rng, TOY_GENERATOR, hello.public_key, root_key(), TOY_SUITE).unwrap();
+        let (mut initiator, _) = initiator
+            .decrypt_first_message(addressee.encrypt_message(b"hello"))
+            .unwrap();
+
+        // without padding, only the authentication tag reveals the forgery
+        let skipped = initiator.encrypt_message(b"skipped");
+        let ratchet_step = initiator.encrypt_message(b"ratchet step");
+        assert_authentication_failed(addressee.decrypt_message(tampered(&ratchet_step)));
+        assert_eq!(addressee.stored_skipped_keys(), 0);
+        assert_eq!(addressee.decrypt_message(ratchet_step).unwrap(), b"ratchet step");
+
+        assert_authentication_failed(addressee.decrypt_message(tampered(&skipped)));
+        assert_eq!(addressee.stored_skipped_keys(), 1);
+        match addressee.decrypt_message(skipped) {
+            Err(DecryptionException::OutOfOrderMessage { decrypted_message }) =>
+                assert_eq!(decrypted_message, b"skipped"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        let same_chain = initiator.encrypt_message(b"same chain");
+        assert_authentication_failed(addressee.decrypt_message(tampered(&same_chain)));
+        assert_eq!(addressee.decrypt_message(same_chain).unwrap(), b"same chain");
+    }
+
+    #[cfg(feature = "key_export")]
+    #[test]
+    fn test_key_export() {
+        let mut rng = StdRng::seed_from_u64(9);
+        let (initiator, hello) = HashKdfProtocol::<Initiator>::initialize_sending(
+            &mut rng, TOY_GENERATOR, root_key(), TOY_SUITE);
+        let mut addressee = HashKdfProtocol::<Established>::initialize_receiving(
+            &mut rng, TOY_GENERATOR, hello.public_key, root_key(), TOY_SUITE).unwrap();
+        let (mut initiator, _) = initiator
+            .decrypt_first_message(addressee.encrypt_message(b"hello"))
+            .unwrap();
+
+        let mut epochs = BTreeSet::new();
+        for round in 0..3u8 {
+            assert_export_and_send(&mut initiator, &mut addressee, round, &mut epochs);
+            assert_export_and_send(&mut addressee, &mut initiator, round, &mut epochs);
+        }
+        assert_eq!(epochs.len(), 6);
+    }
+
+    /// Check that both parties export the same material, which differs from all previous `epochs`, and send two
+    /// messages from `sender` to `receiver`.
+    #[cfg(feature = "key_export")]
+    fn assert_export_and_send(
+        sender: &mut HashKdfProtocol<Established>,
+        receiver: &mut HashKdfProtocol<Established>,
+        round: u8,
+        epochs: &mut BTreeSet<Vec<u8>>,
+    ) {
+        let exported = sender.export_key_material::<SHA1Hash>(b"attachments", 48);
+        assert_eq!(exported.len(), 48);
+        assert_eq!(receiver.export_key_material::<SHA1Hash>(b"attachments", 48), exported);
+        assert_ne!(receiver.export_key_material::<SHA1Hash>(b"avatars", 48), exported);
+        assert!(epochs.insert(exported));
+
+        // exporting does not advance any chain
+        for message_number in 0..2 {
+            let message = sender.encrypt_message(&[round, message_number]);
+            assert_eq!(receiver.decrypt_message(message).unwrap(), vec![round, message_number]);
+        }
+    }
+
+    /// Establish a session and exchange a message in either direction, so the resumption secrets of both parties agree
+    #[cfg(feature = "resumption")]
+    fn established_pair(seed: u64) -> (HashKdfProtocol<Established>, HashKdfProtocol<Established>) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (initiator, hello) = HashKdfProtocol::<Initiator>::initialize_sending(
+            &mut rng, TOY_GENERATOR, root_key(), TOY_SUITE);
+        let mut addressee = HashKdfProtocol::<Established>::initialize_receiving(
+            &mut rng, TOY_GENERATOR, hello.public_key, root_key(), TOY_SUITE).unwrap();
+        let (mut initiator, _) = initiator
+            .decrypt_first_message(addressee.encrypt_message(b"hello"))
+            .unwrap();
+        assert_eq!(addressee.decrypt_message(initiator.encrypt_message(b"hi")).unwrap(), b"hi");
+
+        (initiator, addressee)
+    }
+
+    /// Send messages in both directions for a few rounds, starting with `first`.
+    #[cfg(feature = "resumption")]
+    fn assert_conversation(first: &mut HashKdfProtocol<Established>, second: &mut HashKdfProtocol<Established>) {
+        for round in 0..3u8 {
+            for message_number in 0..2 {
+                let message = first.encrypt_message(&[round, message_number]);
+                assert_eq!(second.decrypt_message(message).unwrap(), vec![round, message_number]);
+            }
+            let message = second.encrypt_message(&[round]);
+            assert_eq!(first.decrypt_message(message).unwrap(), vec![round]);
+        }
+    }
+
+    #[cfg(feature = "resumption")]
+    #[test]
+    fn test_resumption_after_divergence() {
+        let (mut initiator, mut addressee) = established_pair(10);
+        let initiator_secret = initiator.export_resumption_secret::<SHA1Hash>();
+        let addressee_secret = addressee.export_resumption_secret::<SHA1Hash>();
+        assert_eq!(initiator_secret, addressee_secret);
+
+        // both parties lose most messages of the other one, so they store many skipped keys
+        for _ in 0..40 {
+            initiator.encrypt_message(b"lost");
+            addressee.encrypt_message(b"lost");
+        }
+        let _ = addressee.decrypt_message(initiator.encrypt_message(b"late"));
+        let _ = initiator.decrypt_message(addressee.encrypt_message(b"late"));
+        assert!(addressee.stored_skipped_keys() >= 40);
+        assert!(initiator.stored_skipped_keys() >= 40);
+
+        let request = addressee.initiate_resumption::<SHA1Hash>(&addressee_secret);
+        let response = initiator.accept_resumption::<SHA1Hash>(&initiator_secret, &request).unwrap();
+        assert_eq!(initiator.stored_skipped_keys(), 0);
+        assert_eq!(initiator.sending_chain_length, 0);
+        assert_eq!(initiator.receiving_chain_length, 0);
+
+        addressee.complete_resumption::<SHA1Hash>(&addressee_secret, &response).unwrap();
+        assert_eq!(addressee.stored_skipped_keys(), 0);
+        assert_eq!(addressee.previous_sending_chain_length, 0);
+        assert!(addressee.resumption.is_none());
+
+        // the accepting party has a sending chain, but either party may send first
+        assert_conversation(&mut addressee, &mut initiator);
+        assert_conversation(&mut initiator, &mut addressee);
+
+        // the resumed session has a new root chain key
+        assert_ne!(initiator.export_resumption_secret::<SHA1Hash>(), initiator_secret);
+        assert_eq!(
+            initiator.export_resumption_secret::<SHA1Hash>(),
+            addressee.export_resumption_secret::<SHA1Hash>()
+        );
+    }
+
+    #[cfg(feature = "resumption")]
+    #[test]
+    fn test_resumption_forgery_rejected() {
+        use crate::resumption::ResumptionException;
+
+        let (mut initiator, mut addressee) = established_pair(11);
+        let secret = initiator.export_resumption_secret::<SHA1Hash>();
+
+        let mut request = initiator.initiate_resumption::<SHA1Hash>(&secret);
+        assert_eq!(
+            addressee.accept_resumption::<SHA1Hash>(b"guessed resumption secret", &request),
+            Err(ResumptionException::InvalidMac {})
+        );
+
+        let genuine_key = request.public_key;
+        request.public_key = toy_modpow(TOY_GENERATOR, 4711);
+        assert_eq!(
+            addressee.accept_resumption::<SHA1Hash>(&secret, &request),
+            Err(ResumptionException::InvalidMac {})
+        );
+        request.public_key = genuine_key;
+
+        let mut other_suite = request.clone();
+        other_suite.cipher_suite.aead = AeadAlgorithm::Aes128Gcm;
+        assert_eq!(
+            addressee.accept_resumption::<SHA1Hash>(&secret, &other_suite),
+            Err(ResumptionException::SuiteMismatch { got: other_suite.cipher_suite, expected: TOY_SUITE })
+        );
+
+        // a response must be bound to the request
+        let mut response = addressee.accept_resumption::<SHA1Hash>(&secret, &request).unwrap();
+        response.mac[0] ^= 1;
+        assert_eq!(
+            initiator.complete_resumption::<SHA1Hash>(&secret, &response),
+            Err(ResumptionException::InvalidMac {})
+        );
+        response.mac[0] ^= 1;
+
+        // the rejected response did not change the initiating party, so the genuine one completes the resumption
+        initiator.complete_resumption::<SHA1Hash>(&secret, &response).unwrap();
+        assert_conversation(&mut initiator, &mut addressee);
+    }
+
+    #[cfg(feature = "resumption")]
+    #[test]
+    fn test_rejected_resumption_keeps_session() {
+        use crate::resumption::{ResumptionException, ResumptionResponse};
+
+        let (mut initiator, mut addressee) = established_pair(12);
+        let secret = initiator.export_resumption_secret::<SHA1Hash>();
+
+        let request = initiator.initiate_resumption::<SHA1Hash>(&secret);
+        assert_eq!(
+            addressee.accept_resumption::<SHA1Hash>(&secret[1..], &request),
+            Err(ResumptionException::InvalidMac {})
+        );
+
+        // a response is only accepted after a request
+        let response = ResumptionResponse {
+            cipher_suite: TOY_SUITE,
+            public_key: request.public_key,
+            mac: request.mac.clone(),
+        };
+        assert_eq!(
+            addressee.complete_resumption::<SHA1Hash>(&secret, &response),
+            Err(ResumptionException::NoPendingResumption {})
+        );
+
+        // neither the rejected request nor the pending one changed the session
+        assert_conversation(&mut initiator, &mut addressee);
+        assert_conversation(&mut addressee, &mut initiator);
+        assert_eq!(addressee.export_resumption_secret::<SHA1Hash>(), initiator.export_resumption_secret::<SHA1Hash>());
+    }
+}
+
+#[cfg(feature = "transcript")]
+mod transcript_vectors {
+    use super::*;
+    use crate::transcript::{Direction, TranscriptException, TranscriptRecorder};
+
+    /// The transcript of `record_conversation`, generated by this implementation
+    const GOLDEN_TRANSCRIPT: &str = include_str!("../test_vectors/toy_transcript.json");
+
+    /// The key pairs of the toy scheme with the given private keys
+    fn key_pairs(private_keys: &[u64]) -> Vec<(u64, u64)> {
+        private_keys.iter().map(|key| (*key, toy_modpow(TOY_GENERATOR, *key))).collect()
+    }
+
+    /// Run a conversation with fixed key pairs, that covers ratchet steps, a skipped message and an out-of-order
+    /// message, and record it.
+    fn record_conversation() -> TranscriptRecorder {
+        let recorder = TranscriptRecorder::new();
+        let (mut initiator, hello) = ToyProtocol::<Initiator, _>::initialize_sending_with_fixed_keys(
+            key_pairs(&[101, 102, 103]), TOY_ROOT_KEY, TOY_SUITE, HashMapKeyStore::new());
+        initiator.attach_transcript_recorder("initiator", recorder.clone());
+        let mut addressee = ToyProtocol::<Established, _>::initialize_receiving_with_fixed_keys(
+            key_pairs(&[201, 202, 203]), hello.public_key, TOY_ROOT_KEY, TOY_SUITE, HashMapKeyStore::new()).unwrap();
+        addressee.attach_transcript_recorder("addressee", recorder.clone());
+
+        let (mut initiator, _) = initiator.decrypt_first_message(addressee.encrypt_message(b"hello")).unwrap();
+
+        let first = initiator.encrypt_message(b"first");
+        let second = initiator.encrypt_message(b"second");
+        assert_eq!(addressee.decrypt_message(second).unwrap(), b"second");
+        match addressee.decrypt_message(first) {
+            Err(DecryptionException::OutOfOrderMessage { decrypted_message }) => assert_eq!(decrypted_message, b"first"),
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        assert_eq!(initiator.decrypt_message(addressee.encrypt_message(b"reply")).unwrap(), b"reply");
+        assert_eq!(addressee.decrypt_message(initiator.encrypt_message(b"last")).unwrap(), b"last");
+        recorder
+    }
+
+    #[test]
+    fn test_golden_transcript() {
+        let golden = TranscriptRecorder::parse_json(GOLDEN_TRANSCRIPT).unwrap();
+        let recorder = record_conversation();
+        let entries = recorder.entries();
+
+        assert_eq!(entries.len(), golden.len());
+        for (index, (entry, expected)) in entries.iter().zip(&golden).enumerate() {
+            assert_eq!(entry, expected, "transcript entry {} differs", index);
+        }
+        assert_eq!(recorder.to_json(), GOLDEN_TRANSCRIPT);
+
+        // every message is decrypted with the key it was encrypted with
+        for sent in entries.iter().filter(|entry| entry.direction == Direction::Sent) {
+            let received = entries.iter()
+                .find(|entry| entry.direction == Direction::Received && entry.public_key == sent.public_key
+                    && entry.message_number == sent.message_number)
+                .unwrap();
+            assert_eq!(received.message_key, sent.message_key);
+        }
+    }
+
+    #[test]
+    fn test_parse_transcript() {
+        let json = r#" [ { "message_key": "00ff", "receiving_chain_length": 2, "party": "bob", "direction": "received",
+            "public_key": "0a", "message_number": 1, "previous_chain_length": 0, "sending_chain_length": 3,
+            "comment": "unknown fields are ignored" } ] "#;
+        let entries = TranscriptRecorder::parse_json(json).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].party, "bob");
+        assert_eq!(entries[0].direction, Direction::Received);
+        assert_eq!((entries[0].message_number, entries[0].sending_chain_length), (1, 3));
+
+        assert_eq!(TranscriptRecorder::parse_json("[]").unwrap(), vec![]);
+        assert_eq!(TranscriptRecorder::parse_json("[{"), Err(TranscriptException::MalformedJson { position: 2 }));
+        assert_eq!(TranscriptRecorder::parse_json("[] []"), Err(TranscriptException::MalformedJson { position: 3 }));
+        assert_eq!(
+            TranscriptRecorder::parse_json(r#"[{"party": "bob", "direction": "sideways"}]"#),
+            Err(TranscriptException::InvalidField { field: "direction" }));
+        assert_eq!(
+            TranscriptRecorder::parse_json(r#"[{"party": 1}]"#),
+            Err(TranscriptException::InvalidField { field: "party" }));
+    }
+}
+
+#[test]
+fn test_capabilities() {
+    assert_eq!(capabilities().contains(Capabilities::HASH_KDF), cfg!(feature = "hash_kdf"));
+    assert_eq!(capabilities().contains(Capabilities::VAULT), cfg!(feature = "vault"));
+    assert_eq!(capabilities().contains(Capabilities::RESUMPTION), cfg!(feature = "resumption"));
+}
+
+#[test]
+#[cfg(feature = "transcript")]
+fn test_capabilities_transcript() {
+    assert!(capabilities().contains(Capabilities::TRANSCRIPT));
+    assert!(capabilities().names().contains(&"transcript"));
+}
+
+#[test]
+#[cfg(not(feature = "transcript"))]
+fn test_capabilities_without_transcript() {
+    assert!(!capabilities().contains(Capabilities::TRANSCRIPT));
+    assert!(!capabilities().names().contains(&"transcript"));
+}
@@ -0,0 +1,580 @@
+//! Deterministic transcripts of protocol runs, for comparing this implementation against other implementations of
+//! the Double Ratchet Algorithm. A `TranscriptRecorder` attached to a protocol records the header and the message key
+//! of every message the protocol encrypts or decrypts. Protocols initialized with fixed Diffie-Hellman key pairs
+//! produce the same transcript in every run, so a transcript can be stored as a test vector in JSON. This module is
+//! only available with the `transcript` feature.
+//!
+//! Never use fixed key pairs outside of tests: a key pair that is used by more than one protocol run breaks the
+//! security of all of them.
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex};
+
+use jester_encryption::diffie_hellman::{DiffieHellmanKeyExchangeScheme, KeyValidationException};
+use jester_encryption::SymmetricalEncryptionScheme;
+use jester_util::Redacted;
+
+use crate::chain_key::ChainKey;
+use crate::cipher_suite::CipherSuite;
+use crate::key_pair_source::KeyPairSource;
+use crate::skipped_keys::SkippedKeyStore;
+use crate::{state, ConstantInputKeyRatchet, DoubleRatchetAlgorithmMessage, DoubleRatchetProtocol, KeyDerivationFunction};
+
+/// Values that are written to a transcript as hex strings, like public keys and message keys.
+pub trait TranscriptValue {
+    /// The bytes of the value in the transcript
+    fn transcript_bytes(&self) -> Vec<u8>;
+}
+
+/// Integers are written in big endian byte order
+impl TranscriptValue for u64 {
+    fn transcript_bytes(&self) -> Vec<u8> {
+        self.to_be_bytes().to_vec()
+    }
+}
+
+impl TranscriptValue for Vec<u8> {
+    fn transcript_bytes(&self) -> Vec<u8> {
+        self.clone()
+    }
+}
+
+impl TranscriptValue for ChainKey {
+    fn transcript_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+impl<T> TranscriptValue for Redacted<T>
+where
+    T: TranscriptValue,
+{
+    fn transcript_bytes(&self) -> Vec<u8> {
+        (**self).transcript_bytes()
+    }
+}
+
+/// Whether a recorded message was sent or received by the recording protocol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+impl Direction {
+    fn as_str(self) -> &'static str {
+        match self {
+            Direction::Sent => "sent",
+            Direction::Received => "received",
+        }
+    }
+}
+
+/// The record of one encrypted or decrypted message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscriptEntry {
+    /// The name of the party that recorded the message
+    pub party: String,
+
+    /// Whether the party sent or received the message
+    pub direction: Direction,
+
+    /// The public key of the message header, as hex string
+    pub public_key: String,
+
+    /// The message number of the message header
+    pub message_number: usize,
+
+    /// The previous chain length of the message header
+    pub previous_chain_length: usize,
+
+    /// The key the message was encrypted with, as hex string
+    pub message_key: String,
+
+    /// The length of the party's sending chain after the message was processed
+    pub sending_chain_length: usize,
+
+    /// The length of the party's receiving chain after the message was processed
+    pub receiving_chain_length: usize,
+}
+
+/// Exceptions that can arise when parsing a transcript from JSON.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranscriptException {
+    /// The JSON is malformed or not an array of flat objects, starting at the given byte offset
+    MalformedJson { position: usize },
+
+    /// An entry lacks a field or the field has the wrong type
+    InvalidField { field: &'static str },
+}
+
+/// A recorder of protocol transcripts. Recorders are handles to a shared transcript, so one recorder can be attached
+/// to both parties of a conversation, recording all messages in the order they were processed.
+#[derive(Debug, Clone, Default)]
+pub struct TranscriptRecorder {
+    entries: Arc<Mutex<Vec<TranscriptEntry>>>,
+}
+
+impl TranscriptRecorder {
+    /// Create a recorder with an empty transcript.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All entries recorded so far
+    pub fn entries(&self) -> Vec<TranscriptEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    fn record(&self, entry: TranscriptEntry) {
+        self.entries.lock().unwrap().push(entry);
+    }
+
+    /// Write the transcript as JSON array with one object per line.
+    pub fn to_json(&self) -> String {
+        let entries = self.entries.lock().unwrap();
+        let mut json = String::from("[\n");
+        for (index, entry) in entries.iter().enumerate() {
+            write!(
+                json,
+                "  {{\"party\": \"{}\", \"direction\": \"{}\", \"public_key\": \"{}\", \"message_number\": {}, \
+                 \"previous_chain_length\": {}, \"message_key\": \"{}\", \"sending_chain_length\": {}, \
+                 \"receiving_chain_length\": {}}}",
+                entry.party,
+                entry.direction.as_str(),
+                entry.public_key,
+                entry.message_number,
+                entry.previous_chain_length,
+                entry.message_key,
+                entry.sending_chain_length,
+                entry.receiving_chain_length
+            )
+            .unwrap();
+            json.push_str(if index + 1 < entries.len() { ",\n" } else { "\n" });
+        }
+        json.push_str("]\n");
+        json
+    }
+
+    /// Parse a transcript written by `to_json` or by another implementation using the same field names. Whitespace
+    /// and the order of fields are not significant, unknown fields are ignored.
+    ///
+    /// # Returns
+    /// The entries of the transcript, or a `TranscriptException` if the JSON is not a valid transcript. Strings must
+    /// not contain escape sequences.
+    pub fn parse_json(json: &str) -> Result<Vec<TranscriptEntry>, TranscriptException> {
+        let mut parser = JsonParser { json: json.as_bytes(), position: 0 };
+        let mut entries = Vec::new();
+
+        parser.expect(b'[')?;
+        if !parser.consume(b']') {
+            loop {
+                entries.push(parser.entry()?);
+                if parser.consume(b']') {
+                    break;
+                }
+                parser.expect(b',')?;
+            }
+        }
+
+        parser.skip_whitespace();
+        if parser.position != json.len() {
+            return Err(parser.malformed());
+        }
+        Ok(entries)
+    }
+}
+
+/// A value of a flat JSON object
+enum JsonValue {
+    String(String),
+    Number(usize),
+}
+
+/// A parser for the subset of JSON that transcripts consist of
+struct JsonParser<'a> {
+    json: &'a [u8],
+    position: usize,
+}
+
+impl JsonParser<'_> {
+    fn malformed(&self) -> TranscriptException {
+        TranscriptException::MalformedJson { position: self.position }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.position < self.json.len() && self.json[self.position].is_ascii_whitespace() {
+            self.position += 1;
+        }
+    }
+
+    /// Consume the next non-whitespace byte, if it is `byte`.
+    fn consume(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        if self.json.get(self.position) == Some(&byte) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), TranscriptException> {
+        if self.consume(byte) {
+            Ok(())
+        } else {
+            Err(self.malformed())
+        }
+    }
+
+    fn string(&mut self) -> Result<String, TranscriptException> {
+        self.expect(b'"')?;
+        let start = self.position;
+        while let Some(byte) = self.json.get(self.position) {
+            match byte {
+                b'"' => {
+                    self.position += 1;
+                    return Ok(String::from_utf8_lossy(&self.json[start..self.position - 1]).into_owned());
+                }
+                b'\\' => return Err(self.malformed()),
+                _ => self.position += 1,
+            }
+        }
+        Err(self.malformed())
+    }
+
+    fn value(&mut self) -> Result<JsonValue, TranscriptException> {
+        self.skip_whitespace();
+        if self.json.get(self.position) == Some(&b'"') {
+            return self.string().map(JsonValue::String);
+        }
+
+        let start = self.position;
+        while self.json.get(self.position).is_some_and(u8::is_ascii_digit) {
+            self.position += 1;
+        }
+        std::str::from_utf8(&self.json[start..self.position])
+            .unwrap()
+            .parse()
+            .map(JsonValue::Number)
+            .map_err(|_| TranscriptException::MalformedJson { position: start })
+    }
+
+    fn entry(&mut self) -> Result<TranscriptEntry, TranscriptException> {
+        let mut fields = Vec::new();
+        self.expect(b'{')?;
+        if !self.consume(b'}') {
+            loop {
+                let name = self.string()?;
+                self.expect(b':')?;
+                fields.push((name, self.value()?));
+                if self.consume(b'}') {
+                    break;
+                }
+                self.expect(b',')?;
+            }
+        }
+
+        let string = |field: &'static str| match fields.iter().find(|(name, _)| name == field) {
+            Some((_, JsonValue::String(value))) => Ok(value.clone()),
+            _ => Err(TranscriptException::InvalidField { field }),
+        };
+        let number = |field: &'static str| match fields.iter().find(|(name, _)| name == field) {
+            Some((_, JsonValue::Number(value))) => Ok(*value),
+            _ => Err(TranscriptException::InvalidField { field }),
+        };
+
+        Ok(TranscriptEntry {
+            party: string("party")?,
+            direction: match string("direction")?.as_str() {
+                "sent" => Direction::Sent,
+                "received" => Direction::Received,
+                _ => return Err(TranscriptException::InvalidField { field: "direction" }),
+            },
+            public_key: string("public_key")?,
+            message_number: number("message_number")?,
+            previous_chain_length: number("previous_chain_length")?,
+            message_key: string("message_key")?,
+            sending_chain_length: number("sending_chain_length")?,
+            receiving_chain_length: number("receiving_chain_length")?,
+        })
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// A recorder attached to a protocol, with the encodings of the protocol's key types, so the protocol does not
+/// require its key types to be `TranscriptValue`s unless a recorder is attached.
+pub(crate) struct AttachedRecorder<DHPublicKey, MessageKey> {
+    party: String,
+    recorder: TranscriptRecorder,
+    encode_public_key: fn(&DHPublicKey) -> Vec<u8>,
+    encode_message_key: fn(&MessageKey) -> Vec<u8>,
+}
+
+/// A `KeyPairSource` handing out a fixed sequence of key pairs, so protocol runs are reproducible.
+///
+/// # Panics
+/// `next_key_pair` panics when all key pairs were handed out.
+pub struct FixedKeyPairSource<DHScheme>
+where
+    DHScheme: DiffieHellmanKeyExchangeScheme,
+{
+    key_pairs: VecDeque<(DHScheme::PrivateKey, DHScheme::PublicKey)>,
+}
+
+impl<DHScheme> FixedKeyPairSource<DHScheme>
+where
+    DHScheme: DiffieHellmanKeyExchangeScheme,
+{
+    /// Create a source handing out the `key_pairs` in order.
+    pub fn new(key_pairs: Vec<(DHScheme::PrivateKey, DHScheme::PublicKey)>) -> Self {
+        FixedKeyPairSource {
+            key_pairs: key_pairs.into(),
+        }
+    }
+}
+
+impl<DHScheme> KeyPairSource<DHScheme> for FixedKeyPairSource<DHScheme>
+where
+    DHScheme: DiffieHellmanKeyExchangeScheme,
+{
+    fn next_key_pair(&mut self) -> (DHScheme::PrivateKey, DHScheme::PublicKey) {
+        self.key_pairs
+            .pop_front()
+            .expect("all fixed key pairs were used")
+    }
+}
+
+impl<
+        DHScheme,
+        EncryptionScheme,
+        RootKdf,
+        MessageKdf,
+        DHPublicKey,
+        DHPrivateKey,
+        DHSharedKey,
+        RootChainKey,
+        MessageChainKey,
+        MessageKey,
+        State,
+        Store,
+    >
+    DoubleRatchetProtocol<
+        DHScheme,
+        EncryptionScheme,
+        RootKdf,
+        MessageKdf,
+        DHPublicKey,
+        DHPrivateKey,
+        DHSharedKey,
+        RootChainKey,
+        MessageChainKey,
+        MessageKey,
+        State,
+        Store,
+    >
+where
+    DHScheme: DiffieHellmanKeyExchangeScheme<
+        PublicKey = DHPublicKey,
+        PrivateKey = DHPrivateKey,
+        SharedKey = DHSharedKey,
+    >,
+    EncryptionScheme: SymmetricalEncryptionScheme<Key = MessageKey>,
+    RootKdf: KeyDerivationFunction<
+        ChainKey = RootChainKey,
+        Input = DHSharedKey,
+        OutputKey = MessageChainKey,
+    >,
+    MessageKdf: ConstantInputKeyRatchet<ChainKey = MessageChainKey, OutputKey = MessageKey>,
+    DHPublicKey: Clone + Eq + Hash,
+    State: state::ProtocolState,
+    Store: SkippedKeyStore<DHPublicKey, MessageKey>,
+{
+    /// Record all messages this protocol encrypts or decrypts from now on in the transcript of `recorder`. The
+    /// recorder stays attached when the protocol changes its state. Message keys are secret, so a recorder must only
+    /// be attached in tests.
+    /// # Parameters
+    /// - `party` the name of this party in the transcript
+    /// - `recorder` the recorder of the transcript
+    pub fn attach_transcript_recorder(&mut self, party: &str, recorder: TranscriptRecorder)
+    where
+        DHPublicKey: TranscriptValue,
+        MessageKey: TranscriptValue,
+    {
+        self.transcript = Some(AttachedRecorder {
+            party: party.to_string(),
+            recorder,
+            encode_public_key: DHPublicKey::transcript_bytes,
+            encode_message_key: MessageKey::transcript_bytes,
+        });
+    }
+
+    /// Record a message in the transcript of the attached recorder, if any. The chain lengths are taken from the
+    /// current protocol state, so this must be called after the chains were advanced for the message.
+    pub(crate) fn record_transcript(
+        &self,
+        direction: Direction,
+        public_key: &DHPublicKey,
+        message_number: usize,
+        previous_chain_length: usize,
+        message_key: &MessageKey,
+    ) {
+        if let Some(attached) = &self.transcript {
+            attached.recorder.record(TranscriptEntry {
+                party: attached.party.clone(),
+                direction,
+                public_key: to_hex(&(attached.encode_public_key)(public_key)),
+                message_number,
+                previous_chain_length,
+                message_key: to_hex(&(attached.encode_message_key)(message_key)),
+                sending_chain_length: self.sending_chain_length,
+                receiving_chain_length: self.receiving_chain_length,
+            });
+        }
+    }
+}
+
+impl<
+        DHScheme,
+        EncryptionScheme,
+        RootKdf,
+        MessageKdf,
+        DHPublicKey,
+        DHPrivateKey,
+        DHSharedKey,
+        RootChainKey,
+        MessageChainKey,
+        MessageKey,
+        Store,
+    >
+    DoubleRatchetProtocol<
+        DHScheme,
+        EncryptionScheme,
+        RootKdf,
+        MessageKdf,
+        DHPublicKey,
+        DHPrivateKey,
+        DHSharedKey,
+        RootChainKey,
+        MessageChainKey,
+        MessageKey,
+        state::Initiator,
+        Store,
+    >
+where
+    DHScheme: DiffieHellmanKeyExchangeScheme<
+            PublicKey = DHPublicKey,
+            PrivateKey = DHPrivateKey,
+            SharedKey = DHSharedKey,
+        > + 'static,
+    EncryptionScheme: SymmetricalEncryptionScheme<Key = MessageKey>,
+    RootKdf: KeyDerivationFunction<
+        ChainKey = RootChainKey,
+        Input = DHSharedKey,
+        OutputKey = MessageChainKey,
+    >,
+    MessageKdf: ConstantInputKeyRatchet<ChainKey = MessageChainKey, OutputKey = MessageKey>,
+    DHPublicKey: Clone + Eq + Hash + Send,
+    DHPrivateKey: Send,
+    Store: SkippedKeyStore<DHPublicKey, MessageKey>,
+{
+    /// Initialize the double ratchet protocol for the sending side like `initialize_sending_with_store`, but use the
+    /// given Diffie-Hellman key pairs in order instead of generating them. The first key pair is the initial one, every
+    /// Diffie-Hellman ratchet step uses the next one.
+    /// # Parameters
+    /// - `key_pairs` the key pairs of this party. There must be one more key pair than ratchet steps.
+    /// - `initial_root_chain_key` the initial common root key of both parties, agreed upon OTR
+    /// - `cipher_suite` the primitives of the session, which both parties must agree upon
+    /// - `skipped_key_store` the store for message keys of skipped messages
+    pub fn initialize_sending_with_fixed_keys(
+        key_pairs: Vec<(DHPrivateKey, DHPublicKey)>,
+        initial_root_chain_key: RootChainKey,
+        cipher_suite: CipherSuite,
+        skipped_key_store: Store,
+    ) -> (Self, DoubleRatchetAlgorithmMessage<DHPublicKey, Box<[u8]>>) {
+        Self::initialize_sending_with_key_pair_source(
+            FixedKeyPairSource::<DHScheme>::new(key_pairs),
+            initial_root_chain_key,
+            cipher_suite,
+            skipped_key_store,
+        )
+    }
+}
+
+impl<
+        DHScheme,
+        EncryptionScheme,
+        RootKdf,
+        MessageKdf,
+        DHPublicKey,
+        DHPrivateKey,
+        DHSharedKey,
+        RootChainKey,
+        MessageChainKey,
+        MessageKey,
+        Store,
+    >
+    DoubleRatchetProtocol<
+        DHScheme,
+        EncryptionScheme,
+        RootKdf,
+        MessageKdf,
+        DHPublicKey,
+        DHPrivateKey,
+        DHSharedKey,
+        RootChainKey,
+        MessageChainKey,
+        MessageKey,
+        state::Established,
+        Store,
+    >
+where
+    DHScheme: DiffieHellmanKeyExchangeScheme<
+            PublicKey = DHPublicKey,
+            PrivateKey = DHPrivateKey,
+            SharedKey = DHSharedKey,
+        > + 'static,
+    EncryptionScheme: SymmetricalEncryptionScheme<Key = MessageKey>,
+    RootKdf: KeyDerivationFunction<
+        ChainKey = RootChainKey,
+        Input = DHSharedKey,
+        OutputKey = MessageChainKey,
+    >,
+    MessageKdf: ConstantInputKeyRatchet<ChainKey = MessageChainKey, OutputKey = MessageKey>,
+    DHPublicKey: Clone + Eq + Hash + Send,
+    DHPrivateKey: Send,
+    Store: SkippedKeyStore<DHPublicKey, MessageKey>,
+{
+    /// Initialize the double ratchet protocol for the receiving side like `initialize_receiving_with_store`, but use
+    /// the given Diffie-Hellman key pairs in order instead of generating them. The first key pair is the initial one,
+    /// every Diffie-Hellman ratchet step uses the next one.
+    /// # Parameters
+    /// - `key_pairs` the key pairs of this party. There must be one more key pair than ratchet steps.
+    /// - `received_dh_public_key` the other party's Diffie-Hellman public key, that kicks off the DH-Ratchet
+    /// - `initial_root_chain_key` the initial common root key of both parties, that was agreed upon off the record.
+    /// - `cipher_suite` the primitives of the session, which both parties must agree upon
+    /// - `skipped_key_store` the store for message keys of skipped messages
+    ///
+    /// # Returns
+    /// The protocol or a `KeyValidationException` if `received_dh_public_key` is rejected by the Diffie-Hellman scheme
+    pub fn initialize_receiving_with_fixed_keys(
+        key_pairs: Vec<(DHPrivateKey, DHPublicKey)>,
+        received_dh_public_key: DHPublicKey,
+        initial_root_chain_key: RootChainKey,
+        cipher_suite: CipherSuite,
+        skipped_key_store: Store,
+    ) -> Result<Self, KeyValidationException> {
+        Self::initialize_receiving_with_key_pair_source(
+            FixedKeyPairSource::<DHScheme>::new(key_pairs),
+            received_dh_public_key,
+            initial_root_chain_key,
+            cipher_suite,
+            skipped_key_store,
+        )
+    }
+}
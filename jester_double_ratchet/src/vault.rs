@@ -0,0 +1,295 @@
+//! Passphrase protected storage of serialized session states. A vault key is derived from the passphrase with
+//! Argon2id and the state is encrypted with AES-256-GCM. The format version, the Argon2 parameters, the salt and the
+//! nonce are stored in a header in front of the cipher text, which is authenticated as associated data. This module
+//! is only available with the `vault` feature.
+//!
+//! The protocol has no serialization of its state yet, so the vault seals the bytes of a state serialized by the
+//! application.
+
+use std::convert::TryInto;
+
+use rand::{CryptoRng, RngCore};
+
+use jester_encryption::aes::gcm::{Aes256Gcm, GcmKey, NONCE_LENGTH};
+use jester_encryption::aes::Aes256;
+use jester_encryption::AuthenticatedEncryptionScheme;
+use jester_hashes::kdf::argon2::{argon2_derive_key, Argon2Parameters, Argon2Variant};
+use jester_util::Redacted;
+
+/// The version of the vault format, which is the first byte of every vault.
+pub const VAULT_VERSION: u8 = 1;
+
+/// The length of the random salt of the key derivation in bytes
+const SALT_LENGTH: usize = 16;
+
+/// The length of the authentication tag in bytes
+const TAG_LENGTH: usize = 16;
+
+/// The length of the header: version, the three Argon2 parameters as big endian `u32`, salt and nonce
+const HEADER_LENGTH: usize = 1 + 3 * 4 + SALT_LENGTH + NONCE_LENGTH;
+
+/// The length of the derived AES-256 key in bytes
+const KEY_LENGTH: usize = 32;
+
+/// Exceptions that can arise when opening a vault.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VaultException {
+    /// The vault is shorter than the header and authentication tag
+    Truncated { length: usize },
+
+    /// The vault was sealed with a format version this implementation does not support
+    UnsupportedVersion { version: u8 },
+
+    /// The key derivation parameters in the header are invalid, or exceed the parameters of the opening vault
+    InvalidParameters {},
+
+    /// The passphrase is wrong or the vault was modified
+    AuthenticationFailed {},
+}
+
+/// Seals serialized session states with a passphrase. The Argon2id parameters of a vault are used for sealing, and
+/// are the maximum accepted when opening a vault, so a modified header cannot make `open` exhaust the memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionVault {
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+impl SessionVault {
+    /// Create a vault deriving keys with Argon2id using the given parameters. See `Argon2Parameters` for their
+    /// limits.
+    pub fn new(memory_kib: u32, iterations: u32, parallelism: u32) -> Self {
+        SessionVault {
+            memory_kib,
+            iterations,
+            parallelism,
+        }
+    }
+
+    /// Encrypt a serialized session state with a key derived from `passphrase`, using a new random salt and nonce.
+    /// # Parameters
+    /// - `state` the serialized session state
+    /// - `passphrase` the passphrase required to open the vault
+    /// - `rng` a cryptographically secure random number generator
+    ///
+    /// # Returns
+    /// The header followed by the cipher text and authentication tag
+    ///
+    /// # Panics
+    /// If the Argon2 parameters of the vault are invalid.
+    pub fn seal<R>(&self, state: &[u8], passphrase: &[u8], rng: &mut R) -> Vec<u8>
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut salt = [0_u8; SALT_LENGTH];
+        rng.fill_bytes(&mut salt);
+        let mut nonce = [0_u8; NONCE_LENGTH];
+        rng.fill_bytes(&mut nonce);
+
+        let mut header = Vec::with_capacity(HEADER_LENGTH);
+        header.push(VAULT_VERSION);
+        header.extend_from_slice(&self.memory_kib.to_be_bytes());
+        header.extend_from_slice(&self.iterations.to_be_bytes());
+        header.extend_from_slice(&self.parallelism.to_be_bytes());
+        header.extend_from_slice(&salt);
+        header.extend_from_slice(&nonce);
+
+        let key = derive_vault_key(self, &salt, passphrase).expect("invalid Argon2 parameters");
+        let cipher = Aes256Gcm::<TAG_LENGTH>::encrypt_message(&key, &nonce, &header, state);
+
+        [header, cipher].concat()
+    }
+
+    /// Decrypt a session state sealed by `seal` with the same passphrase.
+    ///
+    /// # Returns
+    /// The serialized session state or a `VaultException` if the vault is truncated, was sealed with an unsupported
+    /// format version or with parameters exceeding the ones of this vault, the passphrase is wrong or the vault was
+    /// modified.
+    pub fn open(&self, vault: &[u8], passphrase: &[u8]) -> Result<Vec<u8>, VaultException> {
+        // the version is checked first, so future versions may change the length of the header
+        match vault.first() {
+            Some(&VAULT_VERSION) => {}
+            Some(&version) => return Err(VaultException::UnsupportedVersion { version }),
+            None => return Err(VaultException::Truncated { length: 0 }),
+        }
+
+        if vault.len() < HEADER_LENGTH + TAG_LENGTH {
+            return Err(VaultException::Truncated {
+                length: vault.len(),
+            });
+        }
+
+        let (header, cipher) = vault.split_at(HEADER_LENGTH);
+        let parameter = |index: usize| {
+            u32::from_be_bytes(header[1 + 4 * index..5 + 4 * index].try_into().unwrap())
+        };
+        let parameters = SessionVault::new(parameter(0), parameter(1), parameter(2));
+        if parameters.memory_kib > self.memory_kib
+            || parameters.iterations > self.iterations
+            || parameters.parallelism > self.parallelism
+        {
+            return Err(VaultException::InvalidParameters {});
+        }
+
+        let salt = &header[13..13 + SALT_LENGTH];
+        let nonce: [u8; NONCE_LENGTH] = header[13 + SALT_LENGTH..].try_into().unwrap();
+
+        let key = derive_vault_key(&parameters, salt, passphrase)
+            .ok_or(VaultException::InvalidParameters {})?;
+        Aes256Gcm::<TAG_LENGTH>::decrypt_message(&key, &nonce, header, cipher)
+            .map_err(|_| VaultException::AuthenticationFailed {})
+    }
+}
+
+/// A vault using Argon2id with 64 MiB of memory, three passes and a single lane.
+impl Default for SessionVault {
+    fn default() -> Self {
+        SessionVault::new(64 * 1024, 3, 1)
+    }
+}
+
+/// Derive the AES-256 key of a vault from the passphrase.
+///
+/// # Returns
+/// The key or `None` if the parameters are rejected by Argon2.
+fn derive_vault_key(
+    parameters: &SessionVault,
+    salt: &[u8],
+    passphrase: &[u8],
+) -> Option<GcmKey<Aes256>> {
+    let params = Argon2Parameters {
+        variant: Argon2Variant::Argon2id,
+        memory_kib: parameters.memory_kib,
+        iterations: parameters.iterations,
+        parallelism: parameters.parallelism,
+        salt: salt.to_vec(),
+        secret: None,
+        associated_data: None,
+        output_length: KEY_LENGTH,
+    };
+
+    let key = Redacted::new(argon2_derive_key(&params, passphrase).ok()?);
+    Some(GcmKey::new(&key))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    const STATE: &[u8] = b"serialized session state";
+
+    fn vault() -> SessionVault {
+        SessionVault::new(64, 1, 1)
+    }
+
+    fn sealed() -> Vec<u8> {
+        vault().seal(STATE, b"correct horse", &mut StdRng::seed_from_u64(1))
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let sealed = sealed();
+        assert_eq!(sealed.len(), HEADER_LENGTH + STATE.len() + TAG_LENGTH);
+        assert_eq!(vault().open(&sealed, b"correct horse"), Ok(STATE.to_vec()));
+
+        // a new salt and nonce are used for every seal
+        let resealed = vault().seal(STATE, b"correct horse", &mut StdRng::seed_from_u64(2));
+        assert_ne!(resealed[13..], sealed[13..]);
+        assert_eq!(
+            vault().open(&resealed, b"correct horse"),
+            Ok(STATE.to_vec())
+        );
+
+        let empty = vault().seal(b"", b"", &mut StdRng::seed_from_u64(3));
+        assert_eq!(vault().open(&empty, b""), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_wrong_passphrase() {
+        assert_eq!(
+            vault().open(&sealed(), b"battery staple"),
+            Err(VaultException::AuthenticationFailed {})
+        );
+    }
+
+    #[test]
+    fn test_tampering_detected() {
+        let sealed = sealed();
+
+        // header, salt, nonce, cipher text and tag
+        for position in 0..sealed.len() {
+            let mut tampered = sealed.clone();
+            tampered[position] ^= 0x01;
+
+            let expected = match position {
+                0 => VaultException::UnsupportedVersion {
+                    version: VAULT_VERSION ^ 0x01,
+                },
+                // flipping a bit of the parameters either exceeds the ones of the vault or zeroes them
+                1..=12 => VaultException::InvalidParameters {},
+                _ => VaultException::AuthenticationFailed {},
+            };
+            assert_eq!(
+                vault().open(&tampered, b"correct horse"),
+                Err(expected),
+                "position {}",
+                position
+            );
+        }
+    }
+
+    #[test]
+    fn test_truncated() {
+        let sealed = sealed();
+
+        for length in [0, 1, HEADER_LENGTH, HEADER_LENGTH + TAG_LENGTH - 1].iter() {
+            assert_eq!(
+                vault().open(&sealed[..*length], b"correct horse"),
+                Err(VaultException::Truncated { length: *length })
+            );
+        }
+
+        // truncating the cipher text or tag is detected by the authentication
+        assert_eq!(
+            vault().open(&sealed[..sealed.len() - 1], b"correct horse"),
+            Err(VaultException::AuthenticationFailed {})
+        );
+    }
+
+    #[test]
+    fn test_future_version_rejected() {
+        let mut future = sealed();
+        future[0] = VAULT_VERSION + 1;
+        future.extend_from_slice(b"an extended header of a future version");
+
+        assert_eq!(
+            vault().open(&future, b"correct horse"),
+            Err(VaultException::UnsupportedVersion {
+                version: VAULT_VERSION + 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_excessive_parameters_rejected() {
+        let strong = SessionVault::new(128, 2, 1).seal(
+            STATE,
+            b"correct horse",
+            &mut StdRng::seed_from_u64(4),
+        );
+
+        assert_eq!(
+            vault().open(&strong, b"correct horse"),
+            Err(VaultException::InvalidParameters {})
+        );
+        assert_eq!(
+            SessionVault::new(128, 2, 1).open(&strong, b"correct horse"),
+            Ok(STATE.to_vec())
+        );
+    }
+}
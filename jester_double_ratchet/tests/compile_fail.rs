@@ -0,0 +1,7 @@
+//! Checks that misuse of the key containers is rejected at compile time.
+
+#[test]
+fn test_compile_fail() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/compile_fail/*.rs");
+}
@@ -0,0 +1,7 @@
+use jester_double_ratchet::one_time_key::OneTimeKey;
+
+fn main() {
+    let key = OneTimeKey::new(42u64);
+    let copy = key.clone();
+    assert_eq!(key.use_once(), copy.use_once());
+}
@@ -0,0 +1,401 @@
+//! The Galois/Counter Mode (NIST SP 800-38D), which encrypts in counter mode and authenticates the cipher text and
+//! associated data with GHASH, a polynomial evaluation in GF(2^128).
+
+use std::convert::TryInto;
+use std::marker::PhantomData;
+
+use jester_hashes::constant_time_eq;
+use jester_maths::gf128::Gf128;
+use rand::{CryptoRng, RngCore};
+
+use crate::aes::{Aes128, Aes256, BlockCipher};
+use crate::{AuthenticatedEncryptionScheme, AuthenticationException};
+
+/// The length of the nonces of the `AuthenticatedEncryptionScheme` implementation in bytes.
+pub const NONCE_LENGTH: usize = 12;
+
+/// A block cipher `C` in Galois/Counter Mode, producing tags of `TAG_LENGTH` bytes. The tag length must be between
+/// 12 and 16 bytes, or 8 or 4 bytes for applications that can tolerate the reduced security of very short tags.
+pub struct Gcm<C, const TAG_LENGTH: usize>
+where
+    C: BlockCipher,
+{
+    marker: PhantomData<C>,
+}
+
+/// AES-128 in Galois/Counter Mode, with full length tags by default.
+pub type Aes128Gcm<const TAG_LENGTH: usize = 16> = Gcm<Aes128, TAG_LENGTH>;
+
+/// AES-256 in Galois/Counter Mode, with full length tags by default.
+pub type Aes256Gcm<const TAG_LENGTH: usize = 16> = Gcm<Aes256, TAG_LENGTH>;
+
+/// The key of the Galois/Counter Mode, which is the expanded key of the block cipher and the derived hash key.
+pub struct GcmKey<C>
+where
+    C: BlockCipher,
+{
+    cipher: C,
+    hash_key: Gf128,
+}
+
+impl<C> GcmKey<C>
+where
+    C: BlockCipher,
+{
+    /// Expand the given key of the block cipher.
+    ///
+    /// # Panics
+    /// If `key` does not have the key length of the block cipher.
+    pub fn new(key: &[u8]) -> Self {
+        let cipher = C::new(key);
+        let hash_key = Gf128::from_bytes(cipher.encrypt_block(&[0; 16]));
+
+        GcmKey { cipher, hash_key }
+    }
+
+    /// Generate the counter block the encryption starts with, which is derived from the initialization vector.
+    /// 96-bit vectors are used directly, all others are compressed with GHASH.
+    fn initial_counter_block(&self, iv: &[u8]) -> [u8; 16] {
+        assert!(
+            !iv.is_empty(),
+            "the initialization vector must not be empty"
+        );
+
+        if iv.len() == NONCE_LENGTH {
+            let mut block = [0; 16];
+            block[..NONCE_LENGTH].copy_from_slice(iv);
+            block[15] = 1;
+            block
+        } else {
+            self.ghash(&[], iv).to_bytes()
+        }
+    }
+
+    /// Compute GHASH over the padded associated data, the padded cipher text and the bit lengths of both.
+    fn ghash(&self, associated_data: &[u8], cipher: &[u8]) -> Gf128 {
+        let mut hash = Gf128::ZERO;
+        for data in [associated_data, cipher].iter() {
+            for chunk in data.chunks(16) {
+                let mut block = [0; 16];
+                block[..chunk.len()].copy_from_slice(chunk);
+                hash += Gf128::from_bytes(block);
+                hash *= self.hash_key;
+            }
+        }
+
+        let mut lengths = [0; 16];
+        lengths[..8].copy_from_slice(&(associated_data.len() as u64 * 8).to_be_bytes());
+        lengths[8..].copy_from_slice(&(cipher.len() as u64 * 8).to_be_bytes());
+        hash += Gf128::from_bytes(lengths);
+        hash * self.hash_key
+    }
+
+    /// Xor `data` with the key stream generated from the counter blocks following `counter_block`.
+    fn apply_key_stream(&self, counter_block: &[u8; 16], data: &[u8]) -> Vec<u8> {
+        let mut counter_block = *counter_block;
+        let mut output = Vec::with_capacity(data.len());
+
+        for chunk in data.chunks(16) {
+            increment_counter(&mut counter_block);
+            let key_stream = self.cipher.encrypt_block(&counter_block);
+            output.extend(chunk.iter().zip(key_stream.iter()).map(|(a, b)| a ^ b));
+        }
+
+        output
+    }
+
+    /// The full length tag of the cipher text.
+    fn tag(&self, counter_block: &[u8; 16], associated_data: &[u8], cipher: &[u8]) -> [u8; 16] {
+        let mask = Gf128::from_bytes(self.cipher.encrypt_block(counter_block));
+        (self.ghash(associated_data, cipher) + mask).to_bytes()
+    }
+}
+
+/// Increment the last 32 bits of the counter block modulo `2^32`.
+fn increment_counter(counter_block: &mut [u8; 16]) {
+    let counter = u32::from_be_bytes(counter_block[12..].try_into().unwrap());
+    counter_block[12..].copy_from_slice(&counter.wrapping_add(1).to_be_bytes());
+}
+
+impl<C, const TAG_LENGTH: usize> Gcm<C, TAG_LENGTH>
+where
+    C: BlockCipher,
+{
+    const VALID_TAG_LENGTH: () = assert!(
+        (TAG_LENGTH >= 12 && TAG_LENGTH <= 16) || TAG_LENGTH == 8 || TAG_LENGTH == 4,
+        "unsupported GCM tag length"
+    );
+
+    /// Encrypt and authenticate a message with an initialization vector of arbitrary length. Vectors of 96 bits are
+    /// the most efficient and are the only ones whose uniqueness is not weakened by the hashing of the vector. The
+    /// tag is appended to the cipher text.
+    ///
+    /// # Panics
+    /// If `iv` is empty.
+    pub fn encrypt_with_iv(
+        key: &GcmKey<C>,
+        iv: &[u8],
+        associated_data: &[u8],
+        message: &[u8],
+    ) -> Vec<u8> {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_TAG_LENGTH;
+
+        let counter_block = key.initial_counter_block(iv);
+        let mut cipher = key.apply_key_stream(&counter_block, message);
+        let tag = key.tag(&counter_block, associated_data, &cipher);
+
+        cipher.extend_from_slice(&tag[..TAG_LENGTH]);
+        cipher
+    }
+
+    /// Verify and decrypt a cipher text created by `encrypt_with_iv`. The plaintext is only decrypted if the tag
+    /// is valid.
+    ///
+    /// # Returns
+    /// The plaintext or an `AuthenticationException` if the cipher text is shorter than a tag or the tag is invalid.
+    ///
+    /// # Panics
+    /// If `iv` is empty.
+    pub fn decrypt_with_iv(
+        key: &GcmKey<C>,
+        iv: &[u8],
+        associated_data: &[u8],
+        cipher: &[u8],
+    ) -> Result<Vec<u8>, AuthenticationException> {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_TAG_LENGTH;
+
+        if cipher.len() < TAG_LENGTH {
+            return Err(AuthenticationException::MissingTag {});
+        }
+
+        let (cipher, tag) = cipher.split_at(cipher.len() - TAG_LENGTH);
+        let counter_block = key.initial_counter_block(iv);
+        let expected_tag = key.tag(&counter_block, associated_data, cipher);
+
+        if !constant_time_eq(&expected_tag[..TAG_LENGTH], tag) {
+            return Err(AuthenticationException::InvalidTag {});
+        }
+
+        Ok(key.apply_key_stream(&counter_block, cipher))
+    }
+}
+
+impl<C, const TAG_LENGTH: usize> AuthenticatedEncryptionScheme for Gcm<C, TAG_LENGTH>
+where
+    C: BlockCipher,
+{
+    type Key = GcmKey<C>;
+    type Nonce = [u8; NONCE_LENGTH];
+
+    fn generate_key<R>(rng: &mut R) -> Self::Key
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut key = vec![0; C::KEY_LENGTH];
+        rng.fill_bytes(&mut key);
+        GcmKey::new(&key)
+    }
+
+    fn encrypt_message(
+        key: &Self::Key,
+        nonce: &Self::Nonce,
+        associated_data: &[u8],
+        message: &[u8],
+    ) -> Vec<u8> {
+        Self::encrypt_with_iv(key, nonce, associated_data, message)
+    }
+
+    fn decrypt_message(
+        key: &Self::Key,
+        nonce: &Self::Nonce,
+        associated_data: &[u8],
+        cipher: &[u8],
+    ) -> Result<Vec<u8>, AuthenticationException> {
+        Self::decrypt_with_iv(key, nonce, associated_data, cipher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::thread_rng;
+
+    use super::*;
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    const KEY: &str = "feffe9928665731c6d6a8f9467308308";
+    const IV: &str = "cafebabefacedbaddecaf888";
+    const PLAIN: &str = "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a72\
+                         1c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b391aafd255";
+    const ASSOCIATED_DATA: &str = "feedfacedeadbeeffeedfacedeadbeefabaddad2";
+
+    /// Check a test vector of the GCM specification in both directions.
+    fn check_vector<C>(
+        key: &str,
+        iv: &str,
+        plain: &str,
+        associated_data: &str,
+        cipher: &str,
+        tag: &str,
+    ) where
+        C: BlockCipher,
+    {
+        let key = GcmKey::<C>::new(&from_hex(key));
+        let (iv, plain, associated_data) =
+            (from_hex(iv), from_hex(plain), from_hex(associated_data));
+        let expected = [from_hex(cipher), from_hex(tag)].concat();
+
+        assert_eq!(
+            Gcm::<C, 16>::encrypt_with_iv(&key, &iv, &associated_data, &plain),
+            expected
+        );
+        assert_eq!(
+            Gcm::<C, 16>::decrypt_with_iv(&key, &iv, &associated_data, &expected),
+            Ok(plain)
+        );
+    }
+
+    /// Test cases 1 to 6 of the GCM specification by McGrew and Viega.
+    #[test]
+    fn test_aes_128_vectors() {
+        let zero_key = "00000000000000000000000000000000";
+        let zero_iv = "000000000000000000000000";
+        check_vector::<Aes128>(
+            zero_key,
+            zero_iv,
+            "",
+            "",
+            "",
+            "58e2fccefa7e3061367f1d57a4e7455a",
+        );
+        check_vector::<Aes128>(
+            zero_key,
+            zero_iv,
+            "00000000000000000000000000000000",
+            "",
+            "0388dace60b6a392f328c2b971b2fe78",
+            "ab6e47d42cec13bdf53a67b21257bddf",
+        );
+        check_vector::<Aes128>(
+            KEY,
+            IV,
+            PLAIN,
+            "",
+            "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e\
+             21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091473f5985",
+            "4d5c2af327cd64a62cf35abd2ba6fab4",
+        );
+        check_vector::<Aes128>(
+            KEY,
+            IV,
+            &PLAIN[..120],
+            ASSOCIATED_DATA,
+            "42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e\
+             21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091",
+            "5bc94fbc3221a5db94fae95ae7121a47",
+        );
+        check_vector::<Aes128>(
+            KEY,
+            "cafebabefacedbad",
+            &PLAIN[..120],
+            ASSOCIATED_DATA,
+            "61353b4c2806934a777ff51fa22a4755699b2a714fcdc6f83766e5f97b6c7423\
+             73806900e49f24b22b097544d4896b424989b5e1ebac0f07c23f4598",
+            "3612d2e79e3b0785561be14aaca2fccb",
+        );
+        check_vector::<Aes128>(
+            KEY,
+            "9313225df88406e555909c5aff5269aa6a7a9538534f7da1e4c303d2a318a728\
+             c3c0c95156809539fcf0e2429a6b525416aedbf5a0de6a57a637b39b",
+            &PLAIN[..120],
+            ASSOCIATED_DATA,
+            "8ce24998625615b603a033aca13fb894be9112a5c3a211a8ba262a3cca7e2ca7\
+             01e4a9a4fba43c90ccdcb281d48c7c6fd62875d2aca417034c34aee5",
+            "619cc5aefffe0bfa462af43c1699d050",
+        );
+    }
+
+    /// Test case 16 of the GCM specification.
+    #[test]
+    fn test_aes_256_vector() {
+        check_vector::<Aes256>(
+            &[KEY, KEY].concat(),
+            IV,
+            &PLAIN[..120],
+            ASSOCIATED_DATA,
+            "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa\
+             8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f662",
+            "76fc6ece0f4e1768cddf8853bb2d551b",
+        );
+    }
+
+    #[test]
+    fn test_round_trip_with_associated_data() {
+        let mut rng = thread_rng();
+        let key = Aes256Gcm::<16>::generate_key(&mut rng);
+        let nonce = [7; NONCE_LENGTH];
+
+        for length in [0, 1, 15, 16, 17, 100].iter() {
+            let message = vec![0xA5; *length];
+            let cipher = Aes256Gcm::<16>::encrypt_message(&key, &nonce, b"header", &message);
+            assert_eq!(cipher.len(), length + 16);
+
+            assert_eq!(
+                Aes256Gcm::<16>::decrypt_message(&key, &nonce, b"header", &cipher),
+                Ok(message)
+            );
+            assert_eq!(
+                Aes256Gcm::<16>::decrypt_message(&key, &nonce, b"other header", &cipher),
+                Err(AuthenticationException::InvalidTag {})
+            );
+        }
+    }
+
+    #[test]
+    fn test_truncated_tag() {
+        let key = GcmKey::<Aes128>::new(&from_hex(KEY));
+        let nonce: [u8; NONCE_LENGTH] = from_hex(IV).try_into().unwrap();
+        let message = from_hex(PLAIN);
+
+        let full = Aes128Gcm::<16>::encrypt_message(&key, &nonce, b"", &message);
+        let truncated = Aes128Gcm::<12>::encrypt_message(&key, &nonce, b"", &message);
+        assert_eq!(truncated[..], full[..full.len() - 4]);
+        assert_eq!(
+            Aes128Gcm::<12>::decrypt_message(&key, &nonce, b"", &truncated),
+            Ok(message)
+        );
+        assert_eq!(
+            Aes128Gcm::<12>::decrypt_message(&key, &nonce, b"", &truncated[..8]),
+            Err(AuthenticationException::MissingTag {})
+        );
+    }
+
+    #[test]
+    fn test_tampered_cipher_rejected() {
+        let mut rng = thread_rng();
+        let key = Aes128Gcm::<16>::generate_key(&mut rng);
+        let nonce = [1; NONCE_LENGTH];
+        let cipher = Aes128Gcm::<16>::encrypt_message(&key, &nonce, b"", b"attack at dawn");
+
+        for position in 0..cipher.len() {
+            let mut tampered = cipher.clone();
+            tampered[position] ^= 0x01;
+            assert_eq!(
+                Aes128Gcm::<16>::decrypt_message(&key, &nonce, b"", &tampered),
+                Err(AuthenticationException::InvalidTag {})
+            );
+        }
+
+        assert_eq!(
+            Aes128Gcm::<16>::decrypt_message(&key, &[2; NONCE_LENGTH], b"", &cipher),
+            Err(AuthenticationException::InvalidTag {})
+        );
+    }
+}
@@ -0,0 +1,226 @@
+//! The forward cipher of the Advanced Encryption Standard (FIPS 197) and the Galois/Counter Mode built on top of it.
+//! Only the encryption direction of the block cipher is implemented, since the counter mode never inverts it.
+
+use std::convert::TryInto;
+
+pub mod gcm;
+
+/// A block cipher with a block size of 128 bits, of which only the forward direction is required.
+pub trait BlockCipher {
+    /// The length of a key in bytes.
+    const KEY_LENGTH: usize;
+
+    /// Expand the given key into the round keys of the cipher.
+    ///
+    /// # Panics
+    /// If `key` is not exactly `KEY_LENGTH` bytes long.
+    fn new(key: &[u8]) -> Self;
+
+    /// Encrypt a single block.
+    fn encrypt_block(&self, block: &[u8; 16]) -> [u8; 16];
+}
+
+/// AES with a key of `KEY_LENGTH` bytes. Only the key lengths of `Aes128` and `Aes256` are supported. The S-box is
+/// a lookup table, so the cipher is not hardened against cache-timing attacks.
+pub struct Aes<const KEY_LENGTH: usize> {
+    round_keys: Vec<[u8; 16]>,
+}
+
+/// AES with a 128-bit key and 10 rounds.
+pub type Aes128 = Aes<16>;
+
+/// AES with a 256-bit key and 14 rounds.
+pub type Aes256 = Aes<32>;
+
+const S_BOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+
+/// The round constants of the key expansion, which are the powers of `x` in GF(2^8).
+const ROUND_CONSTANTS: [u8; 10] = [0x01, 0x02, 0x04, 0x08, 0x10, 0x20, 0x40, 0x80, 0x1b, 0x36];
+
+impl<const KEY_LENGTH: usize> Aes<KEY_LENGTH> {
+    /// The number of rounds, which is 10 for 128-bit keys and 14 for 256-bit keys.
+    const ROUNDS: usize = KEY_LENGTH / 4 + 6;
+
+    /// Expand the key into `ROUNDS + 1` round keys, following section 5.2 of FIPS 197.
+    fn expand_key(key: &[u8; KEY_LENGTH]) -> Vec<[u8; 16]> {
+        let key_words = KEY_LENGTH / 4;
+        let total_words = 4 * (Self::ROUNDS + 1);
+
+        let mut words: Vec<[u8; 4]> = key.chunks(4).map(|word| word.try_into().unwrap()).collect();
+        for i in key_words..total_words {
+            let mut word = words[i - 1];
+            if i % key_words == 0 {
+                word.rotate_left(1);
+                word = sub_word(word);
+                word[0] ^= ROUND_CONSTANTS[i / key_words - 1];
+            } else if key_words > 6 && i % key_words == 4 {
+                word = sub_word(word);
+            }
+
+            let previous = words[i - key_words];
+            words.push([
+                word[0] ^ previous[0],
+                word[1] ^ previous[1],
+                word[2] ^ previous[2],
+                word[3] ^ previous[3],
+            ]);
+        }
+
+        words
+            .chunks(4)
+            .map(|round_key| round_key.concat().try_into().unwrap())
+            .collect()
+    }
+}
+
+impl<const KEY_LENGTH: usize> BlockCipher for Aes<KEY_LENGTH> {
+    const KEY_LENGTH: usize = KEY_LENGTH;
+
+    fn new(key: &[u8]) -> Self {
+        assert!(
+            KEY_LENGTH == 16 || KEY_LENGTH == 32,
+            "unsupported AES key length"
+        );
+        let key: &[u8; KEY_LENGTH] = key.try_into().expect("invalid AES key length");
+
+        Aes {
+            round_keys: Self::expand_key(key),
+        }
+    }
+
+    fn encrypt_block(&self, block: &[u8; 16]) -> [u8; 16] {
+        let mut state = *block;
+        add_round_key(&mut state, &self.round_keys[0]);
+
+        for round_key in &self.round_keys[1..Self::ROUNDS] {
+            sub_bytes(&mut state);
+            shift_rows(&mut state);
+            mix_columns(&mut state);
+            add_round_key(&mut state, round_key);
+        }
+
+        sub_bytes(&mut state);
+        shift_rows(&mut state);
+        add_round_key(&mut state, &self.round_keys[Self::ROUNDS]);
+
+        state
+    }
+}
+
+fn sub_word(word: [u8; 4]) -> [u8; 4] {
+    [
+        S_BOX[word[0] as usize],
+        S_BOX[word[1] as usize],
+        S_BOX[word[2] as usize],
+        S_BOX[word[3] as usize],
+    ]
+}
+
+fn add_round_key(state: &mut [u8; 16], round_key: &[u8; 16]) {
+    state
+        .iter_mut()
+        .zip(round_key.iter())
+        .for_each(|(byte, key)| *byte ^= key);
+}
+
+fn sub_bytes(state: &mut [u8; 16]) {
+    state
+        .iter_mut()
+        .for_each(|byte| *byte = S_BOX[*byte as usize]);
+}
+
+/// The state is stored column by column, so the byte of row `r` and column `c` is at index `r + 4c`. Row `r` is
+/// rotated to the left by `r` positions.
+fn shift_rows(state: &mut [u8; 16]) {
+    let previous = *state;
+    for row in 1..4 {
+        for column in 0..4 {
+            state[row + 4 * column] = previous[row + 4 * ((column + row) % 4)];
+        }
+    }
+}
+
+/// Multiply each column with the fixed polynomial `3x^3 + x^2 + x + 2` modulo `x^4 + 1`.
+fn mix_columns(state: &mut [u8; 16]) {
+    for column in state.chunks_mut(4) {
+        let [a0, a1, a2, a3]: [u8; 4] = (&*column).try_into().unwrap();
+        let sum = a0 ^ a1 ^ a2 ^ a3;
+
+        column[0] = a0 ^ sum ^ times_x(a0 ^ a1);
+        column[1] = a1 ^ sum ^ times_x(a1 ^ a2);
+        column[2] = a2 ^ sum ^ times_x(a2 ^ a3);
+        column[3] = a3 ^ sum ^ times_x(a3 ^ a0);
+    }
+}
+
+/// Multiply with `x` in GF(2^8) modulo `x^8 + x^4 + x^3 + x + 1`.
+fn times_x(byte: u8) -> u8 {
+    (byte << 1) ^ ((byte >> 7) * 0x1b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// The example vectors of FIPS 197, appendix C.
+    #[test]
+    fn test_fips_197_vectors() {
+        let plain: [u8; 16] = from_hex("00112233445566778899aabbccddeeff")
+            .try_into()
+            .unwrap();
+
+        let aes128 = Aes128::new(&from_hex("000102030405060708090a0b0c0d0e0f"));
+        assert_eq!(
+            aes128.encrypt_block(&plain).to_vec(),
+            from_hex("69c4e0d86a7b0430d8cdb78070b4c55a")
+        );
+
+        let aes256 = Aes256::new(&from_hex(
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f",
+        ));
+        assert_eq!(
+            aes256.encrypt_block(&plain).to_vec(),
+            from_hex("8ea2b7ca516745bfeafc49904b496089")
+        );
+    }
+
+    /// The last round key of the key expansion example of FIPS 197, appendix A.1.
+    #[test]
+    fn test_key_expansion() {
+        let aes128 = Aes128::new(&from_hex("2b7e151628aed2a6abf7158809cf4f3c"));
+        assert_eq!(
+            aes128.round_keys[10].to_vec(),
+            from_hex("d014f9a8c9ee2589e13f0cc8b6630ca6")
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_key_length() {
+        Aes128::new(&[0; 24]);
+    }
+}
@@ -1,9 +1,15 @@
 //! This module contains a trait for a Diffie-Hellman-key-exchange protocol and a default implementation for all
-//! implementors of `PrimeField` as defined in `jester_maths`.
+//! implementors of `DhGroup`, which are the prime fields defined in `jester_maths`.
 
+use num::{BigUint, Num, One};
 use rand::{CryptoRng, RngCore};
 
-use jester_maths::prime::PrimeField;
+use jester_hashes::{BlockHashFunction, HashValue};
+use jester_maths::fixed_base::FixedBaseExp;
+use jester_maths::prime::{
+    IetfGroup1, IetfGroup2, IetfGroup3, Mersenne107, Mersenne127, Mersenne127Fast, Mersenne13, Mersenne17, Mersenne19,
+    Mersenne2, Mersenne3, Mersenne31, Mersenne5, Mersenne61, Mersenne89, PrimeField,
+};
 
 /// A trait representing the symmetric key exchange scheme proposed by Diffie, Hellman and Merkle. Each party
 /// generates an asymmetrical key pair using `generate_asymmetrical_key_pair` and then exchanges public keys. Then
@@ -41,12 +47,137 @@ pub trait DiffieHellmanKeyExchangeScheme {
         private_key: &Self::PrivateKey,
         partner_key: &Self::PublicKey,
     ) -> Self::SharedKey;
+
+    /// Check whether a public key received by the other party is safe to use. Keys that would collapse the shared
+    /// secret to a predictable value must be rejected. The default implementation accepts all keys, as it knows
+    /// nothing about the key space.
+    /// # Parameters
+    /// - `partner_key` the public key received by the other party.
+    fn validate_public_key(_partner_key: &Self::PublicKey) -> Result<(), KeyValidationException> {
+        Ok(())
+    }
+
+    /// Generate the shared secret like `generate_shared_secret`, but validate the public key received by the other
+    /// party using `validate_public_key` first.
+    /// # Parameters
+    /// - `private_key` the private key that was generated by `generate_public_key`.
+    /// - `partner_key` the public key received by the other party.
+    ///
+    /// # Returns
+    /// The shared secret or a `KeyValidationException` if `partner_key` was rejected.
+    fn generate_shared_secret_checked(
+        private_key: &Self::PrivateKey,
+        partner_key: &Self::PublicKey,
+    ) -> Result<Self::SharedKey, KeyValidationException> {
+        Self::validate_public_key(partner_key)?;
+        Ok(Self::generate_shared_secret(private_key, partner_key))
+    }
+}
+
+/// Exceptions that can arise when validating a public key received by the other party.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyValidationException {
+    /// The key is one of the degenerate values `0`, `1` or `p - 1`, which would result in a predictable shared
+    /// secret.
+    DegenerateKey {},
+
+    /// The key is not a member of the subgroup the group's generator spans and might be an element of small order.
+    NotInSubgroup {},
+}
+
+/// A prime field that is used as group for the Diffie-Hellman-key-exchange. If the generator used with the group
+/// spans a subgroup of prime order, received public keys are checked to be members of this subgroup.
+pub trait DhGroup: PrimeField {
+    /// The prime order of the subgroup generated by the group's generator, if known.
+    fn subgroup_order() -> Option<BigUint> {
+        None
+    }
+
+    /// The generator of the subgroup of order `subgroup_order`, if the group defines one. Public keys generated from
+    /// another generator are rejected by `validate_public_key`.
+    fn subgroup_generator() -> Option<Self> {
+        None
+    }
+}
+
+impl DhGroup for Mersenne2 {}
+impl DhGroup for Mersenne3 {}
+impl DhGroup for Mersenne5 {}
+impl DhGroup for Mersenne13 {}
+impl DhGroup for Mersenne17 {}
+impl DhGroup for Mersenne19 {}
+impl DhGroup for Mersenne31 {}
+impl DhGroup for Mersenne61 {}
+impl DhGroup for Mersenne89 {}
+impl DhGroup for Mersenne107 {}
+impl DhGroup for Mersenne127 {}
+impl DhGroup for Mersenne127Fast {}
+
+/// The 160 bit prime order subgroup and its generator as defined in RFC 5114, section 2.1
+impl DhGroup for IetfGroup1 {
+    fn subgroup_order() -> Option<BigUint> {
+        BigUint::from_str_radix("F518AA8781A8DF278ABA4E7D64B7CB9D49462353", 16).ok()
+    }
+
+    fn subgroup_generator() -> Option<Self> {
+        Self::from_str_radix(
+            "A4D1CBD5C3FD34126765A442EFB99905F8104DD258AC507FD6406CFF14266D31266FEA1E5C41564B777E690F5504F213\
+             160217B4B01B886A5E91547F9E2749F4D7FBD7D3B9A92EE1909D0D2263F80A76A6A24C087A091F531DBF0A0169B6A28A\
+             D662A4D18E73AFA32D779D5918D08BC8858F4DCEF97C2A24855E6EEB22B3B2E5",
+            16,
+        )
+        .ok()
+    }
+}
+
+/// The 224 bit prime order subgroup and its generator as defined in RFC 5114, section 2.2
+impl DhGroup for IetfGroup2 {
+    fn subgroup_order() -> Option<BigUint> {
+        BigUint::from_str_radix("801C0D34C58D93FE997177101F80535A4738CEBCBF389A99B36371EB", 16).ok()
+    }
+
+    fn subgroup_generator() -> Option<Self> {
+        Self::from_str_radix(
+            "AC4032EF4F2D9AE39DF30B5C8FFDAC506CDEBE7B89998CAF74866A08CFE4FFE3A6824A4E10B9A6F0DD921F01A70C4AFA\
+             AB739D7700C29F52C57DB17C620A8652BE5E9001A8D66AD7C17669101999024AF4D027275AC1348BB8A762D0521BC98A\
+             E247150422EA1ED409939D54DA7460CDB5F6C6B250717CBEF180EB34118E98D119529A45D6F834566E3025E316A330EF\
+             BB77A86F0C1AB15B051AE3D428C8F8ACB70A8137150B8EEB10E183EDD19963DDD9E263E4770589EF6AA21E7F5F2FF381\
+             B539CCE3409D13CD566AFBB48D6C019181E1BCFE94B30269EDFE72FE9B6AA4BD7B5A0F1C71CFFF4C19C418E1F6EC0179\
+             81BC087F2A7065B384B890D3191F2BFA",
+            16,
+        )
+        .ok()
+    }
 }
 
-/// Implementation of the `DiffieHellmanKeyExchangeScheme` for all `PrimeField` types.
+/// The 256 bit prime order subgroup and its generator as defined in RFC 5114, section 2.3
+impl DhGroup for IetfGroup3 {
+    fn subgroup_order() -> Option<BigUint> {
+        BigUint::from_str_radix(
+            "8CF83642A709A097B447997640129DA299B1A47D1EB3750BA308B0FE64F5FBD3",
+            16,
+        )
+        .ok()
+    }
+
+    fn subgroup_generator() -> Option<Self> {
+        Self::from_str_radix(
+            "3FB32C9B73134D0B2E77506660EDBD484CA7B18F21EF205407F4793A1A0BA12510DBC15077BE463FFF4FED4AAC0BB555\
+             BE3A6C1B0C6B47B1BC3773BF7E8C6F62901228F8C28CBB18A55AE31341000A650196F931C77A57F2DDF463E5E9EC144B\
+             777DE62AAAB8A8628AC376D282D6ED3864E67982428EBC831D14348F6F2F9193B5045AF2767164E1DFC967C1FB3F2E55\
+             A4BD1BFFE83B9C80D052B985D182EA0ADB2A3B7313D3FE14C8484B1E052588B9B7D2BBD2DF016199ECD06E1557CD0915\
+             B3353BBB64E0EC377FD028370DF92B52C7891428CDC67EB6184B523D1DB246C32F63078490F00EF8D647D148D4795451\
+             5E2327CFEF98C582664B4C0F6CC41659",
+            16,
+        )
+        .ok()
+    }
+}
+
+/// Implementation of the `DiffieHellmanKeyExchangeScheme` for all `DhGroup` types.
 impl<T> DiffieHellmanKeyExchangeScheme for T
 where
-    T: PrimeField,
+    T: DhGroup,
 {
     type PublicKey = T;
     type PrivateKey = T;
@@ -78,14 +209,153 @@ where
             .modpow(&private_key.as_uint(), &T::field_prime().as_uint())
             .into()
     }
+
+    /// Reject the keys `0`, `1` and `p - 1`, and keys that are not members of the subgroup of order `q`, if the
+    /// group defines one, by checking `key^q == 1`.
+    fn validate_public_key(partner_key: &Self::PublicKey) -> Result<(), KeyValidationException> {
+        let prime = T::field_prime().as_uint();
+        let key = partner_key.as_uint();
+        if key <= BigUint::one() || key >= &prime - BigUint::one() {
+            return Err(KeyValidationException::DegenerateKey {});
+        }
+
+        match T::subgroup_order() {
+            Some(order) if !key.modpow(&order, &prime).is_one() => {
+                Err(KeyValidationException::NotInSubgroup {})
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// An extension of `DiffieHellmanKeyExchangeScheme` for schemes that can speed up the generation of key pairs by
+/// precomputing values from the generator. A precomputation can be reused for all key pairs with the same generator.
+pub trait PrecomputedKeyExchangeScheme: DiffieHellmanKeyExchangeScheme {
+    /// The values precomputed from a generator.
+    type Precomputation;
+
+    /// Precompute the values required by `generate_asymmetrical_key_pair_with` for a generator.
+    /// # Parameters
+    /// - `generator`: the domain parameters used during the protocol.
+    fn precompute_generator(generator: &Self::PublicKey) -> Self::Precomputation;
+
+    /// The generator a precomputation was created from.
+    fn precomputed_generator(precomputation: &Self::Precomputation) -> Self::PublicKey;
+
+    /// Generate a key pair like `generate_asymmetrical_key_pair`, using the precomputed values of the generator.
+    /// # Parameters
+    /// - `precomputation`: the values precomputed from the generator by `precompute_generator`.
+    /// - `rng`: a cryptographically secure random number generator.
+    fn generate_asymmetrical_key_pair_with<R>(
+        precomputation: &Self::Precomputation,
+        rng: &mut R,
+    ) -> (Self::PrivateKey, Self::PublicKey)
+    where
+        R: RngCore + CryptoRng;
+}
+
+/// Key pairs of `DhGroup`s are generated using the precomputed powers of the generator.
+impl<T> PrecomputedKeyExchangeScheme for T
+where
+    T: DhGroup,
+{
+    type Precomputation = FixedBaseExp<T>;
+
+    fn precompute_generator(generator: &T) -> FixedBaseExp<T> {
+        FixedBaseExp::new(generator.clone())
+    }
+
+    fn precomputed_generator(precomputation: &FixedBaseExp<T>) -> T {
+        precomputation.base().clone()
+    }
+
+    fn generate_asymmetrical_key_pair_with<R>(precomputation: &FixedBaseExp<T>, rng: &mut R) -> (T, T)
+    where
+        R: RngCore + CryptoRng,
+    {
+        let a = T::generate_random_member(rng);
+        let public_key = precomputation.pow(&a.as_uint());
+        (a, public_key)
+    }
+}
+
+/// A byte encoding of the public keys of a `DiffieHellmanKeyExchangeScheme`, so keys can be sent over the wire or
+/// stored, and a fingerprint of public keys that parties can compare to identify each other's keys.
+pub trait DhKeyCodec: DiffieHellmanKeyExchangeScheme {
+    /// Encode a public key into bytes. All keys of a scheme are encoded with the same length.
+    fn encode_public_key(key: &Self::PublicKey) -> Vec<u8>;
+
+    /// Decode a public key that was encoded by `encode_public_key`.
+    ///
+    /// # Returns
+    /// The decoded key or a `KeyDecodeException` if `bytes` is not the encoding of any public key.
+    fn decode_public_key(bytes: &[u8]) -> Result<Self::PublicKey, KeyDecodeException>;
+
+    /// Hash the encoding of a public key with the hash function `H`.
+    /// # Parameters
+    /// - `ctx` the context of the hash function
+    /// - `key` the public key to fingerprint
+    fn key_fingerprint<H>(ctx: &H::Context, key: &Self::PublicKey) -> Vec<u8>
+    where
+        H: BlockHashFunction,
+    {
+        H::digest_message(ctx, &Self::encode_public_key(key)).raw()
+    }
+}
+
+/// Exceptions that can arise when decoding a public key from bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyDecodeException {
+    /// The encoding does not have the length of all encoded keys of the scheme.
+    InvalidLength { expected: usize, given: usize },
+
+    /// The encoded number is not a member of the field, i.e. it is not less than the field's prime.
+    ValueOutOfRange {},
+}
+
+/// Public keys of `DhGroup`s are encoded as big endian numbers, padded with leading zeros to the byte length of
+/// the field's prime.
+impl<T> DhKeyCodec for T
+where
+    T: DhGroup,
+{
+    fn encode_public_key(key: &T) -> Vec<u8> {
+        let length = encoded_key_length::<T>();
+        let bytes = key.as_bytes_be();
+
+        let mut encoding = vec![0_u8; length - bytes.len()];
+        encoding.extend(bytes);
+        encoding
+    }
+
+    fn decode_public_key(bytes: &[u8]) -> Result<T, KeyDecodeException> {
+        let length = encoded_key_length::<T>();
+        if bytes.len() != length {
+            return Err(KeyDecodeException::InvalidLength {
+                expected: length,
+                given: bytes.len(),
+            });
+        }
+
+        T::from_bytes_be(bytes).ok_or(KeyDecodeException::ValueOutOfRange {})
+    }
+}
+
+/// The number of bytes required to encode the prime of the field `T`
+fn encoded_key_length<T>() -> usize
+where
+    T: PrimeField,
+{
+    T::field_prime().as_uint().bits().div_ceil(8)
 }
 
 #[cfg(test)]
 mod tests {
-    use num::Num;
+    use num::FromPrimitive;
     use rand::thread_rng;
 
-    use jester_maths::prime::IetfGroup3;
+    use jester_hashes::blake::blake2b::{Blake2b, Blake2bContext};
+    use jester_hashes::HashFunction;
 
     use super::*;
 
@@ -106,4 +376,157 @@ mod tests {
 
         assert_eq!(shared_key_1, shared_key_2)
     }
+
+    #[test]
+    fn test_key_exchange_mersenne127_fast() {
+        let mut rng = thread_rng();
+        let generator = Mersenne127Fast::from_u64(43).unwrap();
+
+        let (private_key_1, public_key_1) = Mersenne127Fast::generate_asymmetrical_key_pair(&mut rng, &generator);
+        let (private_key_2, public_key_2) = Mersenne127Fast::generate_asymmetrical_key_pair(&mut rng, &generator);
+
+        assert_eq!(public_key_1, generator.pow(&private_key_1));
+        assert_eq!(
+            Mersenne127Fast::generate_shared_secret(&private_key_1, &public_key_2),
+            Mersenne127Fast::generate_shared_secret(&private_key_2, &public_key_1)
+        );
+        let encoding = Mersenne127Fast::encode_public_key(&public_key_1);
+        assert_eq!(Mersenne127Fast::decode_public_key(&encoding), Ok(public_key_1));
+    }
+
+    #[test]
+    fn test_key_encoding_round_trip() {
+        let mut rng = thread_rng();
+        let generator = IetfGroup1::from_u64(2).unwrap();
+
+        for _ in 0..16 {
+            let (_, public_key) = IetfGroup1::generate_asymmetrical_key_pair(&mut rng, &generator);
+            let encoding = IetfGroup1::encode_public_key(&public_key);
+
+            assert_eq!(encoding.len(), 128);
+            assert_eq!(IetfGroup1::decode_public_key(&encoding), Ok(public_key));
+        }
+    }
+
+    #[test]
+    fn test_key_encoding_fixed_width() {
+        let key = IetfGroup1::from_u64(0x0102).unwrap();
+        let encoding = IetfGroup1::encode_public_key(&key);
+
+        assert_eq!(encoding.len(), 128);
+        assert!(encoding[..126].iter().all(|b| *b == 0));
+        assert_eq!(encoding[126..], [0x01, 0x02]);
+        assert_eq!(IetfGroup1::decode_public_key(&encoding), Ok(key));
+
+        assert_eq!(
+            IetfGroup1::decode_public_key(&encoding[1..]),
+            Err(KeyDecodeException::InvalidLength {
+                expected: 128,
+                given: 127
+            })
+        );
+    }
+
+    #[test]
+    fn test_key_decoding_rejects_prime() {
+        let prime = IetfGroup1::field_prime().as_uint().to_bytes_be();
+        assert_eq!(prime.len(), 128);
+
+        assert_eq!(
+            IetfGroup1::decode_public_key(&prime),
+            Err(KeyDecodeException::ValueOutOfRange {})
+        );
+    }
+
+    #[test]
+    fn test_key_fingerprint() {
+        let key = IetfGroup1::from_u64(0x0102).unwrap();
+        let ctx = Blake2bContext::new(32).unwrap();
+
+        let fingerprint = IetfGroup1::key_fingerprint::<Blake2b>(&ctx, &key);
+        assert_eq!(
+            fingerprint,
+            Blake2b::digest_message(&ctx, &IetfGroup1::encode_public_key(&key)).raw()
+        );
+        assert_ne!(
+            fingerprint,
+            IetfGroup1::key_fingerprint::<Blake2b>(&ctx, &IetfGroup1::from_u64(0x0201).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_degenerate_keys_rejected() {
+        let mut rng = thread_rng();
+        let generator = IetfGroup1::from_u64(2).unwrap();
+        let (private_key, _) = IetfGroup1::generate_asymmetrical_key_pair(&mut rng, &generator);
+
+        let p_minus_one: IetfGroup1 = (IetfGroup1::field_prime().as_uint() - BigUint::one()).into();
+        for key in &[IetfGroup1::from_u64(0).unwrap(), IetfGroup1::from_u64(1).unwrap(), p_minus_one] {
+            assert_eq!(
+                IetfGroup1::generate_shared_secret_checked(&private_key, key),
+                Err(KeyValidationException::DegenerateKey {})
+            );
+        }
+    }
+
+    #[test]
+    fn test_subgroup_validation() {
+        let mut rng = thread_rng();
+
+        let generator = IetfGroup1::subgroup_generator().unwrap();
+
+        let (private_key_1, public_key_1) = IetfGroup1::generate_asymmetrical_key_pair(&mut rng, &generator);
+        let (private_key_2, public_key_2) = IetfGroup1::generate_asymmetrical_key_pair(&mut rng, &generator);
+        assert_eq!(IetfGroup1::validate_public_key(&public_key_1), Ok(()));
+        assert_eq!(
+            IetfGroup1::generate_shared_secret_checked(&private_key_1, &public_key_2),
+            Ok(IetfGroup1::generate_shared_secret(&private_key_2, &public_key_1))
+        );
+
+        // 2 does not generate the subgroup of order q
+        assert_eq!(
+            IetfGroup1::validate_public_key(&IetfGroup1::from_u64(2).unwrap()),
+            Err(KeyValidationException::NotInSubgroup {})
+        );
+    }
+
+    #[test]
+    fn test_subgroup_generators() {
+        fn assert_generates_subgroup<T>()
+        where
+            T: DhGroup,
+        {
+            let generator = T::subgroup_generator().unwrap();
+            assert_eq!(T::validate_public_key(&generator), Ok(()));
+
+            let order = T::subgroup_order().unwrap();
+            assert!(generator.as_uint().modpow(&order, &T::field_prime().as_uint()).is_one());
+        }
+
+        assert_generates_subgroup::<IetfGroup1>();
+        assert_generates_subgroup::<IetfGroup2>();
+        assert_generates_subgroup::<IetfGroup3>();
+        assert!(Mersenne61::subgroup_generator().is_none());
+    }
+
+    #[test]
+    fn test_precomputed_key_exchange() {
+        let mut rng = thread_rng();
+        let generator = IetfGroup1::from_u64(2).unwrap();
+        let precomputation = IetfGroup1::precompute_generator(&generator);
+        assert_eq!(IetfGroup1::precomputed_generator(&precomputation), generator);
+
+        let (private_key_1, public_key_1) =
+            IetfGroup1::generate_asymmetrical_key_pair_with(&precomputation, &mut rng);
+        let (private_key_2, public_key_2) = IetfGroup1::generate_asymmetrical_key_pair(&mut rng, &generator);
+
+        assert_eq!(
+            public_key_1.as_uint(),
+            generator.as_uint().modpow(&private_key_1.as_uint(), &IetfGroup1::field_prime().as_uint())
+        );
+        assert_eq!(
+            IetfGroup1::generate_shared_secret(&private_key_1, &public_key_2),
+            IetfGroup1::generate_shared_secret(&private_key_2, &public_key_1)
+        );
+    }
 }
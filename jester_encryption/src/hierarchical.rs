@@ -0,0 +1,537 @@
+//! Deterministic hierarchical key derivation following the construction of BIP32. A master key and chain code are
+//! derived from a seed, and each key derives child keys by an index, so a tree of keys can be restored from the seed
+//! alone. HMAC-BLAKE2b-512 takes the place of HMAC-SHA512: the left half of its output tweaks the parent key and the
+//! right half is the child's chain code.
+//!
+//! Hardened children are derived from the parent's private key. Non-hardened children are derived from the parent's
+//! public key, so the holder of an `ExtendedPublicKey` can derive the public keys of all non-hardened children without
+//! knowing any private key. The key types are abstracted by `KeyDerivationGroup`, which is implemented for the private
+//! keys of the Diffie-Hellman groups in `DhKeyDerivation` and for raw symmetric keys in `SymmetricKeyDerivation`.
+
+use num::{BigUint, One, Zero};
+
+use jester_hashes::blake::blake2b::{Blake2b, Blake2bContext};
+use jester_hashes::hmac::hmac;
+
+use crate::diffie_hellman::{DhGroup, DhKeyCodec};
+
+/// The HMAC key deriving the master key from a seed.
+const MASTER_KEY_TAG: &[u8] = b"Jester seed";
+
+/// The byte length of chain codes, which is half the HMAC output.
+pub const CHAIN_CODE_LENGTH: usize = 32;
+
+/// Indices of hardened children have this bit set. Indices passed to the derivation functions must be below it.
+pub const HARDENED_OFFSET: u32 = 1 << 31;
+
+/// The operations on keys that hierarchical derivation requires.
+pub trait KeyDerivationGroup {
+    /// The type of the private keys of the tree
+    type PrivateKey: Clone;
+
+    /// Create the master private key from the left half of the HMAC output of the seed.
+    ///
+    /// # Returns
+    /// The key or `None`, if `material` does not result in a valid key.
+    fn master_private_key(&self, material: &[u8]) -> Option<Self::PrivateKey>;
+
+    /// Derive a child private key from its parent and the left half of the HMAC output of the derivation.
+    ///
+    /// # Returns
+    /// The key or `None`, if `material` does not result in a valid key.
+    fn child_private_key(&self, parent: &Self::PrivateKey, material: &[u8]) -> Option<Self::PrivateKey>;
+
+    /// Encode a private key into the HMAC input of hardened derivation. All keys are encoded with the same length.
+    fn encode_private_key(&self, key: &Self::PrivateKey) -> Vec<u8>;
+
+    /// Encode the public key of a private key into the HMAC input of non-hardened derivation.
+    ///
+    /// # Returns
+    /// The encoding or `None`, if the keys have no public counterpart and only hardened derivation is possible.
+    fn encode_public_key_of(&self, key: &Self::PrivateKey) -> Option<Vec<u8>>;
+}
+
+/// A `KeyDerivationGroup` whose private keys have public keys, such that the public key of a child can be derived
+/// from the public key of its parent.
+pub trait PublicKeyDerivationGroup: KeyDerivationGroup {
+    /// The type of the public keys of the tree
+    type PublicKey: Clone;
+
+    /// Compute the public key of a private key.
+    fn public_key(&self, key: &Self::PrivateKey) -> Self::PublicKey;
+
+    /// Encode a public key into the HMAC input of non-hardened derivation. It must agree with `encode_public_key_of`.
+    fn encode_public_key(&self, key: &Self::PublicKey) -> Vec<u8>;
+
+    /// Derive a child public key from its parent and the left half of the HMAC output of the derivation, such that
+    /// it is the public key of the private key `child_private_key` derives from the same material.
+    ///
+    /// # Returns
+    /// The key or `None`, if `material` does not result in a valid key.
+    fn child_public_key(&self, parent: &Self::PublicKey, material: &[u8]) -> Option<Self::PublicKey>;
+}
+
+/// Exceptions that can arise when deriving a child key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HierarchicalKeyException {
+    /// The index is not below `HARDENED_OFFSET`. Hardened children are selected with the `hardened` flag instead.
+    IndexOutOfRange { index: u32 },
+
+    /// The keys of the group have no public keys, so only hardened children can be derived.
+    PublicDerivationUnsupported {},
+
+    /// The derivation resulted in an invalid key. As in BIP32, the next index should be used instead.
+    InvalidChild { index: u32 },
+}
+
+/// A private key of a key tree with its chain code.
+///
+/// # Type Parameters
+/// - `G` the group of the keys
+#[derive(Clone)]
+pub struct ExtendedPrivateKey<G>
+where
+    G: KeyDerivationGroup,
+{
+    group: G,
+    key: G::PrivateKey,
+    chain_code: [u8; CHAIN_CODE_LENGTH],
+}
+
+/// The root of a key tree.
+pub type MasterKey<G> = ExtendedPrivateKey<G>;
+
+impl<G> ExtendedPrivateKey<G>
+where
+    G: KeyDerivationGroup + Clone,
+{
+    /// Derive the master key and chain code from a seed, by authenticating the seed with a fixed HMAC key.
+    /// # Parameters
+    /// - `group` the group of the keys of the tree
+    /// - `seed` the seed of the tree. It should contain at least 128 bits of entropy.
+    ///
+    /// # Returns
+    /// The master key or `None`, if the seed results in an invalid key. This is as unlikely as a hash collision for
+    /// groups of cryptographic size, and another seed must be used.
+    pub fn from_seed(group: G, seed: &[u8]) -> Option<Self> {
+        let (material, chain_code) = derive_material(MASTER_KEY_TAG, seed);
+        let key = group.master_private_key(&material)?;
+
+        Some(ExtendedPrivateKey {
+            group,
+            key,
+            chain_code,
+        })
+    }
+
+    /// Derive a child key. Hardened children are derived from the private key, so their public keys cannot be
+    /// derived from the parent's public key. Non-hardened and hardened children with the same index are unrelated.
+    /// # Parameters
+    /// - `index` the index of the child, below `HARDENED_OFFSET`
+    /// - `hardened` whether to derive the hardened child
+    ///
+    /// # Returns
+    /// The child key or a `HierarchicalKeyException` if the index is out of range, a non-hardened child is requested
+    /// from a group without public keys, or the index results in an invalid key.
+    pub fn derive_child(&self, index: u32, hardened: bool) -> Result<Self, HierarchicalKeyException> {
+        check_index(index)?;
+
+        let (data, child_index) = if hardened {
+            let mut data = vec![0x00];
+            data.extend(self.group.encode_private_key(&self.key));
+            (data, index | HARDENED_OFFSET)
+        } else {
+            let data = self
+                .group
+                .encode_public_key_of(&self.key)
+                .ok_or(HierarchicalKeyException::PublicDerivationUnsupported {})?;
+            (data, index)
+        };
+
+        let (material, chain_code) = derive_child_material(&self.chain_code, data, child_index);
+        let key = self
+            .group
+            .child_private_key(&self.key, &material)
+            .ok_or(HierarchicalKeyException::InvalidChild { index })?;
+
+        Ok(ExtendedPrivateKey {
+            group: self.group.clone(),
+            key,
+            chain_code,
+        })
+    }
+
+    /// Derive the key at the end of a path of child indices and hardened flags, starting at this key.
+    ///
+    /// # Returns
+    /// The key or the `HierarchicalKeyException` of the first derivation that failed.
+    pub fn derive_path(&self, path: &[(u32, bool)]) -> Result<Self, HierarchicalKeyException> {
+        path.iter()
+            .try_fold(self.clone(), |key, (index, hardened)| key.derive_child(*index, *hardened))
+    }
+
+    /// The private key
+    pub fn private_key(&self) -> &G::PrivateKey {
+        &self.key
+    }
+
+    /// The chain code, which is required besides the key to derive children.
+    pub fn chain_code(&self) -> &[u8; CHAIN_CODE_LENGTH] {
+        &self.chain_code
+    }
+}
+
+impl<G> ExtendedPrivateKey<G>
+where
+    G: PublicKeyDerivationGroup + Clone,
+{
+    /// The public key with the same chain code, which derives the public keys of all non-hardened children.
+    pub fn extended_public_key(&self) -> ExtendedPublicKey<G> {
+        ExtendedPublicKey {
+            group: self.group.clone(),
+            key: self.group.public_key(&self.key),
+            chain_code: self.chain_code,
+        }
+    }
+}
+
+/// A public key of a key tree with its chain code.
+///
+/// # Type Parameters
+/// - `G` the group of the keys
+#[derive(Clone)]
+pub struct ExtendedPublicKey<G>
+where
+    G: PublicKeyDerivationGroup,
+{
+    group: G,
+    key: G::PublicKey,
+    chain_code: [u8; CHAIN_CODE_LENGTH],
+}
+
+impl<G> ExtendedPublicKey<G>
+where
+    G: PublicKeyDerivationGroup + Clone,
+{
+    /// Derive the public key of the non-hardened child with `index`.
+    ///
+    /// # Returns
+    /// The child key or a `HierarchicalKeyException` if the index is out of range or results in an invalid key.
+    pub fn derive_child(&self, index: u32) -> Result<Self, HierarchicalKeyException> {
+        check_index(index)?;
+
+        let data = self.group.encode_public_key(&self.key);
+        let (material, chain_code) = derive_child_material(&self.chain_code, data, index);
+        let key = self
+            .group
+            .child_public_key(&self.key, &material)
+            .ok_or(HierarchicalKeyException::InvalidChild { index })?;
+
+        Ok(ExtendedPublicKey {
+            group: self.group.clone(),
+            key,
+            chain_code,
+        })
+    }
+
+    /// The public key
+    pub fn public_key(&self) -> &G::PublicKey {
+        &self.key
+    }
+
+    /// The chain code, which is required besides the key to derive children.
+    pub fn chain_code(&self) -> &[u8; CHAIN_CODE_LENGTH] {
+        &self.chain_code
+    }
+}
+
+/// Check that `index` does not have the hardened bit set.
+fn check_index(index: u32) -> Result<(), HierarchicalKeyException> {
+    if index >= HARDENED_OFFSET {
+        Err(HierarchicalKeyException::IndexOutOfRange { index })
+    } else {
+        Ok(())
+    }
+}
+
+/// Derive the material of a child key and its chain code from the parent's chain code, the encoded parent key and
+/// the child index including the hardened bit.
+fn derive_child_material(
+    chain_code: &[u8; CHAIN_CODE_LENGTH],
+    mut data: Vec<u8>,
+    child_index: u32,
+) -> (Vec<u8>, [u8; CHAIN_CODE_LENGTH]) {
+    data.extend_from_slice(&child_index.to_be_bytes());
+    derive_material(chain_code, &data)
+}
+
+/// Authenticate `data` with HMAC-BLAKE2b-512 and split the code into key material and chain code.
+fn derive_material(key: &[u8], data: &[u8]) -> (Vec<u8>, [u8; CHAIN_CODE_LENGTH]) {
+    let ctx = Blake2bContext::new(2 * CHAIN_CODE_LENGTH).unwrap();
+    let code = hmac::<Blake2b, Blake2bContext>(&ctx, key, data);
+
+    let mut chain_code = [0_u8; CHAIN_CODE_LENGTH];
+    chain_code.copy_from_slice(&code[CHAIN_CODE_LENGTH..]);
+    (code[..CHAIN_CODE_LENGTH].to_vec(), chain_code)
+}
+
+/// Hierarchical derivation of the private keys of a `DhGroup`. Private keys are exponents of the generator, and a
+/// child's key is the sum of the parent's key and the key material modulo the order of the generator, which is taken
+/// to be the subgroup order of the group if it has one and `p - 1` otherwise. The public key of a private key `a` is
+/// `g^a`, so the child's public key is the product of the parent's public key and `g` raised to the key material.
+///
+/// # Type Parameters
+/// - `T` the group
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DhKeyDerivation<T>
+where
+    T: DhGroup,
+{
+    generator: T,
+}
+
+impl<T> DhKeyDerivation<T>
+where
+    T: DhGroup,
+{
+    /// Derive keys for the Diffie-Hellman-key-exchange with `generator`. If the group has a subgroup order, the
+    /// generator must be a member of the subgroup.
+    pub fn new(generator: T) -> Self {
+        DhKeyDerivation { generator }
+    }
+
+    /// The order of the generator, which private keys are reduced by.
+    fn group_order(&self) -> BigUint {
+        T::subgroup_order().unwrap_or_else(|| T::field_prime().as_uint() - BigUint::one())
+    }
+
+    /// Reduce key material to an exponent.
+    fn reduce(&self, material: &[u8]) -> BigUint {
+        BigUint::from_bytes_be(material) % self.group_order()
+    }
+}
+
+impl<T> KeyDerivationGroup for DhKeyDerivation<T>
+where
+    T: DhGroup + DhKeyCodec<PublicKey = T>,
+{
+    type PrivateKey = T;
+
+    fn master_private_key(&self, material: &[u8]) -> Option<T> {
+        let key = self.reduce(material);
+        if key.is_zero() {
+            None
+        } else {
+            Some(key.into())
+        }
+    }
+
+    fn child_private_key(&self, parent: &T, material: &[u8]) -> Option<T> {
+        let key = (parent.as_uint() + self.reduce(material)) % self.group_order();
+        if key.is_zero() {
+            None
+        } else {
+            Some(key.into())
+        }
+    }
+
+    /// Keys are encoded as big endian numbers, padded with leading zeros to the byte length of the group order.
+    fn encode_private_key(&self, key: &T) -> Vec<u8> {
+        let length = self.group_order().bits().div_ceil(8);
+        let bytes = key.as_bytes_be();
+
+        let mut encoding = vec![0_u8; length - bytes.len()];
+        encoding.extend(bytes);
+        encoding
+    }
+
+    fn encode_public_key_of(&self, key: &T) -> Option<Vec<u8>> {
+        Some(self.encode_public_key(&self.public_key(key)))
+    }
+}
+
+impl<T> PublicKeyDerivationGroup for DhKeyDerivation<T>
+where
+    T: DhGroup + DhKeyCodec<PublicKey = T>,
+{
+    type PublicKey = T;
+
+    fn public_key(&self, key: &T) -> T {
+        self.generator.pow(key)
+    }
+
+    /// Public keys are encoded by `DhKeyCodec`.
+    fn encode_public_key(&self, key: &T) -> Vec<u8> {
+        T::encode_public_key(key)
+    }
+
+    fn child_public_key(&self, parent: &T, material: &[u8]) -> Option<T> {
+        let tweak = self.reduce(material);
+        let prime = T::field_prime().as_uint();
+        let key: T = (parent.as_uint() * self.generator.as_uint().modpow(&tweak, &prime) % prime).into();
+
+        // the private key of the child is zero
+        if key.is_one() {
+            None
+        } else {
+            Some(key)
+        }
+    }
+}
+
+/// Hierarchical derivation of raw 32 byte symmetric keys. The key material is used as the child's key, like SLIP-0010
+/// does for keys without a group structure. Symmetric keys have no public keys, so only hardened children can be
+/// derived.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SymmetricKeyDerivation;
+
+impl KeyDerivationGroup for SymmetricKeyDerivation {
+    type PrivateKey = [u8; 32];
+
+    fn master_private_key(&self, material: &[u8]) -> Option<[u8; 32]> {
+        let mut key = [0_u8; 32];
+        key.copy_from_slice(material);
+        Some(key)
+    }
+
+    fn child_private_key(&self, _parent: &[u8; 32], material: &[u8]) -> Option<[u8; 32]> {
+        self.master_private_key(material)
+    }
+
+    fn encode_private_key(&self, key: &[u8; 32]) -> Vec<u8> {
+        key.to_vec()
+    }
+
+    fn encode_public_key_of(&self, _key: &[u8; 32]) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num::Num;
+
+    use jester_maths::prime::{IetfGroup1, PrimeField};
+
+    use super::*;
+
+    const SEED: &[u8] = b"000102030405060708090a0b0c0d0e0f";
+
+    /// The generator of the subgroup of order q as defined in RFC 5114, section 2.1
+    fn generator() -> IetfGroup1 {
+        IetfGroup1::from_str_radix("A4D1CBD5C3FD34126765A442EFB99905F8104DD258AC507FD6406CFF14266D31266FEA1E5C41564B777E690F5504F213160217B4B01B886A5E91547F9E2749F4D7FBD7D3B9A92EE1909D0D2263F80A76A6A24C087A091F531DBF0A0169B6A28AD662A4D18E73AFA32D779D5918D08BC8858F4DCEF97C2A24855E6EEB22B3B2E5", 16).unwrap()
+    }
+
+    fn master_key() -> MasterKey<DhKeyDerivation<IetfGroup1>> {
+        MasterKey::from_seed(DhKeyDerivation::new(generator()), SEED).unwrap()
+    }
+
+    #[test]
+    fn test_deterministic_derivation() {
+        let path = [(0, true), (1, false), (2, true), (2, false)];
+        let first = master_key().derive_path(&path).unwrap();
+        let second = master_key().derive_path(&path).unwrap();
+
+        assert_eq!(first.private_key(), second.private_key());
+        assert_eq!(first.chain_code(), second.chain_code());
+        assert_ne!(first.private_key(), master_key().private_key());
+
+        let other = MasterKey::from_seed(DhKeyDerivation::new(generator()), b"another seed")
+            .unwrap()
+            .derive_path(&path)
+            .unwrap();
+        assert_ne!(first.private_key(), other.private_key());
+        assert_ne!(first.chain_code(), other.chain_code());
+
+        // private keys are reduced by the subgroup order
+        let order = IetfGroup1::subgroup_order().unwrap();
+        assert!(first.private_key().as_uint() < order);
+        assert_eq!(
+            DhKeyDerivation::new(generator()).encode_private_key(first.private_key()).len(),
+            20
+        );
+    }
+
+    #[test]
+    fn test_hardened_divergence() {
+        let master = master_key();
+        let hardened = master.derive_child(7, true).unwrap();
+        let normal = master.derive_child(7, false).unwrap();
+
+        assert_ne!(hardened.private_key(), normal.private_key());
+        assert_ne!(hardened.chain_code(), normal.chain_code());
+        assert_ne!(
+            master.derive_child(8, true).unwrap().private_key(),
+            hardened.private_key()
+        );
+    }
+
+    #[test]
+    fn test_public_derivation() {
+        let master = master_key();
+        let public_master = master.extended_public_key();
+        assert_eq!(
+            public_master.public_key(),
+            &generator().pow(master.private_key())
+        );
+
+        for index in 0..4 {
+            let child = master.derive_child(index, false).unwrap();
+            let public_child = public_master.derive_child(index).unwrap();
+
+            assert_eq!(child.extended_public_key().public_key(), public_child.public_key());
+            assert_eq!(child.chain_code(), public_child.chain_code());
+
+            // the public keys of hardened children cannot be derived from the parent's public key
+            let hardened = master.derive_child(index, true).unwrap();
+            assert_ne!(hardened.extended_public_key().public_key(), public_child.public_key());
+        }
+
+        let grandchild = master.derive_path(&[(3, true), (5, false)]).unwrap();
+        let public_grandchild = master
+            .derive_child(3, true)
+            .unwrap()
+            .extended_public_key()
+            .derive_child(5)
+            .unwrap();
+        assert_eq!(grandchild.extended_public_key().public_key(), public_grandchild.public_key());
+    }
+
+    #[test]
+    fn test_symmetric_derivation() {
+        let master = MasterKey::from_seed(SymmetricKeyDerivation, SEED).unwrap();
+        let child = master.derive_path(&[(0, true), (1, true)]).unwrap();
+
+        assert_eq!(
+            child.private_key(),
+            MasterKey::from_seed(SymmetricKeyDerivation, SEED)
+                .unwrap()
+                .derive_path(&[(0, true), (1, true)])
+                .unwrap()
+                .private_key()
+        );
+        assert_ne!(child.private_key(), master.private_key());
+        assert_ne!(
+            master.derive_child(1, true).unwrap().private_key(),
+            master.derive_child(0, true).unwrap().private_key()
+        );
+
+        assert_eq!(
+            master.derive_child(0, false).err(),
+            Some(HierarchicalKeyException::PublicDerivationUnsupported {})
+        );
+    }
+
+    #[test]
+    fn test_index_out_of_range() {
+        let master = master_key();
+        assert_eq!(
+            master.derive_child(HARDENED_OFFSET, true).err(),
+            Some(HierarchicalKeyException::IndexOutOfRange { index: HARDENED_OFFSET })
+        );
+        assert_eq!(
+            master.extended_public_key().derive_child(u32::MAX).err(),
+            Some(HierarchicalKeyException::IndexOutOfRange { index: u32::MAX })
+        );
+    }
+}
@@ -8,6 +8,28 @@ use rand::{CryptoRng, RngCore};
 
 pub mod rsa;
 pub mod diffie_hellman;
+pub mod aes;
+pub mod hierarchical;
+
+jester_util::capabilities! {
+    /// The encryption schemes of a build of this crate, see `capabilities`.
+    pub struct Capabilities {
+        const AES128 = 0, "aes128";
+        const AES256 = 1, "aes256";
+        const AES128_GCM = 2, "aes128-gcm";
+        const AES256_GCM = 3, "aes256-gcm";
+        const RSA = 4, "rsa";
+        const DIFFIE_HELLMAN = 5, "diffie-hellman";
+        /// The hierarchical key derivation of `hierarchical`
+        const HIERARCHICAL = 6, "hierarchical";
+    }
+}
+
+/// The encryption schemes this crate was compiled with. The crate has no optional features, so all schemes are
+/// available, but applications negotiating a scheme at runtime should consult this instead of assuming so.
+pub fn capabilities() -> Capabilities {
+    Capabilities::all()
+}
 
 /// A trait representing a symmetrical encryption scheme. It offers methods for generating a random key (though one
 /// might use a different scheme to generate a key) and encrypting and decrypting messages. No attempts are made to
@@ -27,6 +49,15 @@ pub trait SymmetricalEncryptionScheme {
 
     /// Decrypt a cipher text using the provided shared key. The clear text will be returned inside a `Box`.
     fn decrypt_message(key: &Self::Key, message: &[u8]) -> Vec<u8>;
+
+    /// Decrypt a cipher text like `decrypt_message`, but report a cipher text that fails authentication instead of
+    /// decrypting it. Schemes that do not authenticate their cipher texts never fail.
+    ///
+    /// # Returns
+    /// The clear text or an `AuthenticationException` if the scheme rejects the cipher text for `key`.
+    fn try_decrypt_message(key: &Self::Key, message: &[u8]) -> Result<Vec<u8>, AuthenticationException> {
+        Ok(Self::decrypt_message(key, message))
+    }
 }
 
 /// A trait representing an asymmetrical encryption scheme. It offers methods for generating a random key pair and
@@ -49,3 +80,58 @@ pub trait AsymmetricalEncryptionScheme {
     /// Decrypt a cipher text using the provided private key. The clear text will be returned inside a `Box`.
     fn decrypt_message(key: &Self::PrivateKey, cipher: &[u8]) -> Vec<u8>;
 }
+
+/// A trait representing an authenticated encryption scheme with associated data. Besides the message, a cipher text
+/// authenticates the associated data, which is not encrypted, like a protocol header. Each nonce must only be used
+/// once per key, which is not checked by the scheme.
+pub trait AuthenticatedEncryptionScheme {
+    type Key;
+    type Nonce;
+
+    /// Randomly generate a random key from the key space of this algorithm. For this purpose, `rng` is assumed to be
+    /// an instance of a well-seeded, cryptographically secure random number generator.
+    fn generate_key<R>(rng: &mut R) -> Self::Key
+    where
+        R: RngCore + CryptoRng;
+
+    /// Encrypt a message and authenticate it together with `associated_data`. The authentication tag is part of the
+    /// returned cipher text.
+    fn encrypt_message(key: &Self::Key, nonce: &Self::Nonce, associated_data: &[u8], message: &[u8]) -> Vec<u8>;
+
+    /// Verify a cipher text and the `associated_data` it was created with, and decrypt it. No part of the clear text
+    /// is released if the verification fails.
+    ///
+    /// # Returns
+    /// The clear text or an `AuthenticationException` if the cipher text was not created with the same key, nonce
+    /// and associated data.
+    fn decrypt_message(
+        key: &Self::Key,
+        nonce: &Self::Nonce,
+        associated_data: &[u8],
+        cipher: &[u8],
+    ) -> Result<Vec<u8>, AuthenticationException>;
+}
+
+/// Exceptions that can arise when decrypting the cipher text of an `AuthenticatedEncryptionScheme`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthenticationException {
+    /// The cipher text is too short to contain an authentication tag.
+    MissingTag {},
+
+    /// The authentication tag does not match the cipher text and associated data.
+    InvalidTag {},
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities() {
+        assert!(capabilities().contains(Capabilities::AES128_GCM | Capabilities::AES256_GCM));
+        assert_eq!(
+            capabilities().names(),
+            vec!["aes128", "aes256", "aes128-gcm", "aes256-gcm", "rsa", "diffie-hellman", "hierarchical"]
+        );
+    }
+}
@@ -1,52 +1,472 @@
+//! The RSA primitives of RFC 8017 without padding. Private keys carry the components of the Chinese remainder theorem
+//! (CRT), so the private-key operation is performed by one exponentiation of half (or a third of) the modulus size per
+//! prime, which are recombined using Garner's algorithm. Since a fault in one of these exponentiations reveals a
+//! factor of the modulus given the faulty output, the result is re-encrypted and compared to the input before it is
+//! released, unless this check is disabled in the key.
+
+use jester_maths::prime::PrimeGenerator;
+use jester_maths::prime_test::MillerRabin;
+use num::bigint::Sign;
+use num::{BigInt, BigUint, Integer, One};
+use rand::{CryptoRng, RngCore};
+
 use crate::AsymmetricalEncryptionScheme;
-use jester_maths::prime::PrimeField;
-use rand::{RngCore, CryptoRng};
-use num::{BigUint, Num};
-use std::marker::PhantomData;
 
-pub struct RSACryptoSystem<P> where P: Num {
-    marker: PhantomData<P>,
+/// The public exponent of generated keys.
+pub const RSA_PUBLIC_EXPONENT: u32 = 65537;
+
+/// The modulus size in bits of keys generated by `AsymmetricalEncryptionScheme::generate_keypair`.
+pub const DEFAULT_MODULUS_BITS: usize = 2048;
+
+/// The maximum number of primes of a generated modulus. Three primes are only recommended for moduli of at least
+/// 2048 bits, so each prime is still large enough.
+pub const MAX_PRIME_COUNT: usize = 3;
+
+/// The smallest supported size of a single prime in bits.
+const MIN_PRIME_BITS: usize = 32;
+
+pub struct RSACryptoSystem;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RSAPrivateKey {
+    pub n: BigUint,
+    pub e: BigUint,
+    pub d: BigUint,
+
+    /// The CRT components of the key. Without them, the private-key operation is a full exponentiation with `d`.
+    pub crt: Option<RSACrtComponents>,
+
+    /// Whether the result of each private-key operation is verified by re-encrypting it, which detects faults in
+    /// the computation before a faulty result can be used to factor the modulus.
+    pub verify_private_operations: bool,
 }
 
-pub struct RSAPrivateKey<P> {
-    pub d: P,
-    pub n: P,
+/// The CRT components of a private key with the notation of RFC 8017. The modulus is the product of `p`, `q` and the
+/// primes in `other_primes`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RSACrtComponents {
+    pub p: BigUint,
+    pub q: BigUint,
+
+    /// The exponent `d mod (p - 1)`
+    pub dp: BigUint,
+
+    /// The exponent `d mod (q - 1)`
+    pub dq: BigUint,
+
+    /// The coefficient `q^-1 mod p`
+    pub q_inv: BigUint,
+
+    /// The components of the third and any further prime of a multi-prime key
+    pub other_primes: Vec<RSAOtherPrime>,
 }
 
-pub struct RSAPublicKey<P> {
-    pub e: P,
-    pub n: P,
+/// The CRT components of the i-th prime `r` of a multi-prime key, for `i >= 3`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RSAOtherPrime {
+    pub r: BigUint,
+
+    /// The exponent `d mod (r - 1)`
+    pub d: BigUint,
+
+    /// The coefficient `(r_1 * ... * r_(i-1))^-1 mod r`
+    pub t: BigUint,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RSAPublicKey {
+    pub e: BigUint,
+    pub n: BigUint,
+}
+
+/// Exceptions that can arise from the RSA primitives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RSAException {
+    /// The message or cipher is not smaller than the modulus
+    OutOfRange {},
+
+    /// The public exponent is not invertible modulo the Carmichael function of the modulus
+    NonInvertibleExponent {},
+
+    /// The result of a private-key operation does not re-encrypt to its input, so the computation was faulty
+    FaultDetected {},
 }
 
-impl<P> AsymmetricalEncryptionScheme for RSACryptoSystem<P>
-    where P: PrimeField {
-    type PrivateKey = RSAPrivateKey<P>;
-    type PublicKey = RSAPublicKey<P>;
+impl RSAPrivateKey {
+    /// Create a private key with CRT components from the public exponent `e` and the distinct primes of the modulus.
+    /// The primality of `primes` is not checked. The result of private-key operations is verified by default.
+    ///
+    /// # Returns
+    /// The private key, or `RSAException::NonInvertibleExponent` if `e` shares a factor with any `prime - 1`.
+    ///
+    /// # Panics
+    /// If less than two primes are given.
+    pub fn from_primes(e: &BigUint, primes: &[BigUint]) -> Result<Self, RSAException> {
+        assert!(
+            primes.len() >= 2,
+            "an RSA modulus has at least two prime factors"
+        );
 
-    fn generate_keypair<R>(rng: &mut R) -> (Self::PrivateKey, Self::PublicKey) where
-        R: RngCore + CryptoRng {
-        unimplemented!()
+        let n = primes.iter().fold(BigUint::one(), |n, prime| n * prime);
+        let lambda = primes
+            .iter()
+            .fold(BigUint::one(), |lambda, prime| lambda.lcm(&(prime - 1u32)));
+        let d = mod_inverse(e, &lambda).ok_or(RSAException::NonInvertibleExponent {})?;
+
+        let (p, q) = (&primes[0], &primes[1]);
+        let mut product = p * q;
+        let mut other_primes = Vec::with_capacity(primes.len() - 2);
+        for r in &primes[2..] {
+            other_primes.push(RSAOtherPrime {
+                r: r.clone(),
+                d: &d % (r - 1u32),
+                t: mod_inverse(&product, r).expect("the primes are distinct"),
+            });
+            product *= r;
+        }
+
+        let crt = RSACrtComponents {
+            p: p.clone(),
+            q: q.clone(),
+            dp: &d % (p - 1u32),
+            dq: &d % (q - 1u32),
+            q_inv: mod_inverse(q, p).expect("the primes are distinct"),
+            other_primes,
+        };
+
+        Ok(RSAPrivateKey {
+            n,
+            e: e.clone(),
+            d,
+            crt: Some(crt),
+            verify_private_operations: true,
+        })
     }
 
-    /// Performs the RSA encryption on the message interpreted as an integer from `P` in little
-    /// endian byte order
+    /// The public key belonging to this private key.
+    pub fn public_key(&self) -> RSAPublicKey {
+        RSAPublicKey {
+            e: self.e.clone(),
+            n: self.n.clone(),
+        }
+    }
+}
+
+impl RSACrtComponents {
+    /// Exponentiate `input` with the private exponent by one exponentiation per prime, and recombine the results
+    /// with Garner's algorithm, following RFC 8017, section 5.1.2.
+    fn exponentiate(&self, input: &BigUint) -> BigUint {
+        let m_1 = input.modpow(&self.dp, &self.p);
+        #[cfg(test)]
+        let m_1 = tests::inject_fault(m_1);
+        let m_2 = input.modpow(&self.dq, &self.q);
+
+        let h = (&m_1 + &self.p - &m_2 % &self.p) * &self.q_inv % &self.p;
+        let mut m = m_2 + &self.q * h;
+
+        let mut product = &self.p * &self.q;
+        for prime in &self.other_primes {
+            let m_i = input.modpow(&prime.d, &prime.r);
+            let h = (m_i + &prime.r - &m % &prime.r) * &prime.t % &prime.r;
+            m += &product * h;
+            product *= &prime.r;
+        }
+
+        m
+    }
+}
+
+impl RSACryptoSystem {
+    /// Generate a key pair with a modulus of exactly `modulus_bits` bits, that is the product of `prime_count`
+    /// primes of roughly equal size. The public exponent is `RSA_PUBLIC_EXPONENT`.
+    ///
+    /// # Panics
+    /// If `prime_count` is not between 2 and `MAX_PRIME_COUNT`, or if the primes would be smaller than 32 bits.
+    pub fn generate_multi_prime_keypair<R>(
+        rng: &mut R,
+        modulus_bits: usize,
+        prime_count: usize,
+    ) -> (RSAPrivateKey, RSAPublicKey)
+    where
+        R: RngCore + CryptoRng,
+    {
+        assert!(
+            (2..=MAX_PRIME_COUNT).contains(&prime_count),
+            "unsupported number of RSA primes"
+        );
+        assert!(
+            modulus_bits / prime_count >= MIN_PRIME_BITS,
+            "RSA primes must have at least 32 bits"
+        );
+
+        let e = BigUint::from(RSA_PUBLIC_EXPONENT);
+        let prime_bits = modulus_bits / prime_count;
+
+        let mut primes: Vec<BigUint> = Vec::with_capacity(prime_count);
+        while primes.len() < prime_count {
+            // the last prime takes the remaining bits
+            let bits = if primes.len() == prime_count - 1 {
+                modulus_bits - prime_bits * (prime_count - 1)
+            } else {
+                prime_bits
+            };
+
+            let prime = MillerRabin::generate_random_prime(rng, bits);
+            if primes.contains(&prime) || !(&prime - 1u32).gcd(&e).is_one() {
+                continue;
+            }
+            primes.push(prime);
+
+            // the two top bits of each prime are set, which guarantees the modulus size only for two primes
+            if primes.len() == prime_count
+                && primes.iter().fold(BigUint::one(), |n, p| n * p).bits() != modulus_bits
+            {
+                primes.pop();
+            }
+        }
+
+        let private_key = RSAPrivateKey::from_primes(&e, &primes)
+            .expect("the public exponent is coprime to all prime - 1");
+        let public_key = private_key.public_key();
+        (private_key, public_key)
+    }
+
+    /// The encryption primitive RSAEP, which is also the signature verification primitive RSAVP1.
+    ///
+    /// # Returns
+    /// `message ^ e mod n`, or `RSAException::OutOfRange` if `message` is not smaller than the modulus.
+    pub fn encrypt(key: &RSAPublicKey, message: &BigUint) -> Result<BigUint, RSAException> {
+        if message >= &key.n {
+            return Err(RSAException::OutOfRange {});
+        }
+
+        Ok(message.modpow(&key.e, &key.n))
+    }
+
+    /// The decryption primitive RSADP. If the key has CRT components, they are used for the exponentiation.
+    ///
+    /// # Returns
+    /// `cipher ^ d mod n`, `RSAException::OutOfRange` if `cipher` is not smaller than the modulus, or
+    /// `RSAException::FaultDetected` if the result is verified and does not re-encrypt to `cipher`.
+    pub fn decrypt(key: &RSAPrivateKey, cipher: &BigUint) -> Result<BigUint, RSAException> {
+        if cipher >= &key.n {
+            return Err(RSAException::OutOfRange {});
+        }
+
+        let message = match &key.crt {
+            Some(crt) => crt.exponentiate(cipher),
+            None => cipher.modpow(&key.d, &key.n),
+        };
+
+        if key.verify_private_operations && message.modpow(&key.e, &key.n) != *cipher {
+            return Err(RSAException::FaultDetected {});
+        }
+
+        Ok(message)
+    }
+
+    /// The signature primitive RSASP1, which is the same operation as `decrypt`.
+    pub fn sign(key: &RSAPrivateKey, message: &BigUint) -> Result<BigUint, RSAException> {
+        Self::decrypt(key, message)
+    }
+
+    /// Verify a signature created by `sign` using the verification primitive RSAVP1.
+    pub fn verify(key: &RSAPublicKey, message: &BigUint, signature: &BigUint) -> bool {
+        Self::encrypt(key, signature).as_ref() == Ok(message)
+    }
+}
+
+impl AsymmetricalEncryptionScheme for RSACryptoSystem {
+    type PrivateKey = RSAPrivateKey;
+    type PublicKey = RSAPublicKey;
+
+    /// Generate a two-prime key pair with a modulus of `DEFAULT_MODULUS_BITS` bits.
+    fn generate_keypair<R>(rng: &mut R) -> (Self::PrivateKey, Self::PublicKey)
+    where
+        R: RngCore + CryptoRng,
+    {
+        Self::generate_multi_prime_keypair(rng, DEFAULT_MODULUS_BITS, 2)
+    }
+
+    /// Performs the RSA encryption on the message interpreted as an integer in little endian byte order
     fn encrypt_message(key: &Self::PublicKey, message: &[u8]) -> Vec<u8> {
-        let n = P::from_bytes_le(message);
-        if let Some(msg) = n {
-            msg.modpow(&key.e, &key.n).as_bytes_le()
-        } else {
-            panic!("message length exceeds group cardinality")
+        match Self::encrypt(key, &BigUint::from_bytes_le(message)) {
+            Ok(cipher) => cipher.to_bytes_le(),
+            Err(_) => panic!("message length exceeds group cardinality"),
         }
     }
 
-    /// Performs the RSA decryption on the cipher interpreted as an integer from `P` in little
-    /// endian byte order
+    /// Performs the RSA decryption on the cipher interpreted as an integer in little endian byte order
     fn decrypt_message(key: &Self::PrivateKey, cipher: &[u8]) -> Vec<u8> {
-        let n = P::from_bytes_le(cipher);
-        if let Some(c) = n {
-            c.modpow(&key.d, &key.n).as_bytes_le()
+        match Self::decrypt(key, &BigUint::from_bytes_le(cipher)) {
+            Ok(message) => message.to_bytes_le(),
+            Err(RSAException::FaultDetected {}) => panic!("RSA decryption fault detected"),
+            Err(_) => panic!("cipher length exceeds group cardinality"),
+        }
+    }
+}
+
+/// The inverse of `value` modulo `modulus`, if they are coprime.
+fn mod_inverse(value: &BigUint, modulus: &BigUint) -> Option<BigUint> {
+    let modulus = BigInt::from_biguint(Sign::Plus, modulus.clone());
+    let gcd = BigInt::from_biguint(Sign::Plus, value.clone()).extended_gcd(&modulus);
+
+    if gcd.gcd.is_one() {
+        gcd.x.mod_floor(&modulus).to_biguint()
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use num_bigint::RandBigInt;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    thread_local! {
+        /// Whether the next CRT exponentiation on this thread is corrupted.
+        static CORRUPT_NEXT_CRT: Cell<bool> = const { Cell::new(false) };
+    }
+
+    /// Test hook of the CRT exponentiation, which flips the lowest bit of `m_1` if a fault was requested.
+    pub(super) fn inject_fault(m_1: BigUint) -> BigUint {
+        if CORRUPT_NEXT_CRT.with(|corrupt| corrupt.replace(false)) {
+            m_1 ^ BigUint::one()
         } else {
-            panic!("cipher length exceeds group cardinality")
+            m_1
+        }
+    }
+
+    /// Check that the CRT and the plain exponentiation agree on random inputs, and that the results decrypt.
+    fn assert_crt_consistent<R: RngCore + CryptoRng>(rng: &mut R, private_key: &RSAPrivateKey) {
+        let public_key = private_key.public_key();
+        let plain_key = RSAPrivateKey {
+            crt: None,
+            ..private_key.clone()
+        };
+
+        for _ in 0..8 {
+            let input = rng.gen_biguint_below(&private_key.n);
+            let crt_result = RSACryptoSystem::decrypt(private_key, &input).unwrap();
+
+            assert_eq!(
+                crt_result,
+                RSACryptoSystem::decrypt(&plain_key, &input).unwrap()
+            );
+            assert_eq!(
+                RSACryptoSystem::encrypt(&public_key, &crt_result).unwrap(),
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_crt_agrees() {
+        let mut rng = StdRng::seed_from_u64(0x25A);
+        for modulus_bits in [1024, 2048].iter() {
+            let (private_key, public_key) =
+                RSACryptoSystem::generate_multi_prime_keypair(&mut rng, *modulus_bits, 2);
+            assert_eq!(public_key.n.bits(), *modulus_bits);
+            assert_eq!(public_key.e, BigUint::from(RSA_PUBLIC_EXPONENT));
+
+            assert_crt_consistent(&mut rng, &private_key);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_three_primes() {
+        let mut rng = StdRng::seed_from_u64(0x35A);
+        let (private_key, public_key) =
+            RSACryptoSystem::generate_multi_prime_keypair(&mut rng, 1536, 3);
+        assert_eq!(public_key.n.bits(), 1536);
+
+        let crt = private_key.crt.as_ref().unwrap();
+        assert_eq!(crt.other_primes.len(), 1);
+        assert_eq!(&crt.p * &crt.q * &crt.other_primes[0].r, public_key.n);
+
+        assert_crt_consistent(&mut rng, &private_key);
+    }
+
+    #[test]
+    fn test_fault_detected() {
+        let mut rng = StdRng::seed_from_u64(0xFA17);
+        let (mut private_key, public_key) =
+            RSACryptoSystem::generate_multi_prime_keypair(&mut rng, 1024, 2);
+        let message = rng.gen_biguint_below(&public_key.n);
+        let cipher = RSACryptoSystem::encrypt(&public_key, &message).unwrap();
+
+        CORRUPT_NEXT_CRT.with(|corrupt| corrupt.set(true));
+        assert_eq!(
+            RSACryptoSystem::decrypt(&private_key, &cipher),
+            Err(RSAException::FaultDetected {})
+        );
+        assert_eq!(
+            RSACryptoSystem::decrypt(&private_key, &cipher),
+            Ok(message.clone())
+        );
+
+        // without the check, the faulty result is released, and its difference to the correct result shares the
+        // factor q with the modulus
+        private_key.verify_private_operations = false;
+        CORRUPT_NEXT_CRT.with(|corrupt| corrupt.set(true));
+        let faulty = RSACryptoSystem::decrypt(&private_key, &cipher).unwrap();
+        assert_ne!(faulty, message);
+
+        let difference = if faulty > message {
+            &faulty - &message
+        } else {
+            &message - &faulty
+        };
+        assert_eq!(difference.gcd(&public_key.n), private_key.crt.unwrap().q);
+    }
+
+    #[test]
+    fn test_sign_and_encrypt() {
+        let mut rng = StdRng::seed_from_u64(0x516);
+        let (private_key, public_key) =
+            RSACryptoSystem::generate_multi_prime_keypair(&mut rng, 512, 2);
+
+        let message = BigUint::from(0x0102_0304u32);
+        let signature = RSACryptoSystem::sign(&private_key, &message).unwrap();
+        assert!(RSACryptoSystem::verify(&public_key, &message, &signature));
+        assert!(!RSACryptoSystem::verify(
+            &public_key,
+            &(message + 1u32),
+            &signature
+        ));
+
+        let cipher = RSACryptoSystem::encrypt_message(&public_key, b"RSA message");
+        assert_eq!(
+            RSACryptoSystem::decrypt_message(&private_key, &cipher),
+            b"RSA message".to_vec()
+        );
+
+        assert_eq!(
+            RSACryptoSystem::encrypt(&public_key, &public_key.n),
+            Err(RSAException::OutOfRange {})
+        );
+        assert_eq!(
+            RSACryptoSystem::decrypt(&private_key, &private_key.n),
+            Err(RSAException::OutOfRange {})
+        );
+    }
+
+    #[test]
+    fn test_non_invertible_exponent() {
+        // 3 divides 7 - 1
+        let primes = [BigUint::from(7u32), BigUint::from(11u32)];
+        assert_eq!(
+            RSAPrivateKey::from_primes(&BigUint::from(3u32), &primes),
+            Err(RSAException::NonInvertibleExponent {})
+        );
+
+        let private_key = RSAPrivateKey::from_primes(&BigUint::from(7u32), &primes).unwrap();
+        assert_eq!(private_key.n, BigUint::from(77u32));
+        assert_eq!((&private_key.d * 7u32) % 30u32, BigUint::one());
+    }
+}
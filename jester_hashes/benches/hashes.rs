@@ -0,0 +1,200 @@
+//! Throughput of the hash functions of this crate for one-shot digests and streaming updates, and of the HMAC based
+//! constructions built on them. Run it with `cargo bench -p jester_hashes --bench hashes` and add
+//! `--features rustcrypto-compat` to benchmark the RustCrypto implementations of the same functions as reference.
+//! A summary table of the 1 MiB throughput is printed after all benchmarks finished.
+
+use std::time::{Duration, Instant};
+
+use criterion::{black_box, BenchmarkId, Criterion, Throughput};
+
+use jester_hashes::blake::blake2b::{Blake2b, Blake2bContext};
+use jester_hashes::blake::blake2s::{Blake2s, Blake2sContext};
+use jester_hashes::hmac::hmac;
+use jester_hashes::kdf::{hkdf_derive_key, pbkdf2_derive_key};
+use jester_hashes::md5::MD5Hash;
+use jester_hashes::sha1::SHA1Hash;
+use jester_hashes::{BlockHashFunction, HashFunction};
+
+/// Message lengths of the one-shot digests.
+const ONE_SHOT_LENGTHS: [usize; 3] = [64, 4 * 1024, 1024 * 1024];
+
+/// The length of the message hashed by streaming updates and for the summary table.
+const STREAM_LENGTH: usize = 1024 * 1024;
+
+/// The length of each streaming update.
+const CHUNK_LENGTH: usize = 1024;
+
+/// PBKDF2 iterations per derived key.
+const PBKDF2_ITERATIONS: u32 = 1000;
+
+/// How long each hash function is measured for the summary table.
+const TABLE_MEASUREMENT_TIME: Duration = Duration::from_millis(500);
+
+/// Call the generic function `$function` once for each hash function of this crate, with the hash function as type
+/// parameter and its name, its context and the given arguments as parameters. New hash functions only need to be
+/// registered here.
+macro_rules! for_each_hash {
+    ($function:ident($($argument:expr),*)) => {
+        $function::<MD5Hash>("MD5", &(), $($argument),*);
+        $function::<SHA1Hash>("SHA-1", &(), $($argument),*);
+        $function::<Blake2b>("Blake2b", &Blake2bContext::new(64).unwrap(), $($argument),*);
+        $function::<Blake2s>("Blake2s", &Blake2sContext::new(32).unwrap(), $($argument),*);
+    };
+}
+
+/// Call the generic function `$function` once for each RustCrypto implementation of a hash function registered in
+/// `for_each_hash`, using the same names.
+#[cfg(feature = "rustcrypto-compat")]
+macro_rules! for_each_reference_hash {
+    ($function:ident($($argument:expr),*)) => {
+        $function::<md5::Md5>("MD5", $($argument),*);
+        $function::<sha1::Sha1>("SHA-1", $($argument),*);
+        $function::<blake2::Blake2b512>("Blake2b", $($argument),*);
+        $function::<blake2::Blake2s256>("Blake2s", $($argument),*);
+    };
+}
+
+fn bench_hash<H>(name: &str, ctx: &H::Context, criterion: &mut Criterion)
+    where H: BlockHashFunction
+{
+    let mut group = criterion.benchmark_group(name);
+    for length in ONE_SHOT_LENGTHS.iter() {
+        let message = vec![0xA5; *length];
+        group.throughput(Throughput::Bytes(*length as u64));
+        group.bench_with_input(BenchmarkId::new("one-shot", length), &message, |b, message| {
+            b.iter(|| H::digest_message(ctx, black_box(message)))
+        });
+    }
+
+    let message = vec![0xA5; STREAM_LENGTH];
+    group.throughput(Throughput::Bytes(STREAM_LENGTH as u64));
+    group.bench_with_input(BenchmarkId::new("streaming", STREAM_LENGTH), &message, |b, message| {
+        b.iter(|| {
+            let mut state = H::init_hash(ctx);
+            for chunk in message.chunks(CHUNK_LENGTH) {
+                H::update_hash(&mut state, black_box(chunk));
+            }
+            H::finish_hash(&mut state, ctx)
+        })
+    });
+
+    group.throughput(Throughput::Bytes(64));
+    group.bench_function("hmac", |b| {
+        b.iter(|| hmac::<H, H::Context>(ctx, black_box(b"benchmark key"), black_box(&[0xA5; 64])))
+    });
+
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("hkdf", |b| {
+        b.iter(|| hkdf_derive_key::<H, H::Context>(ctx, b"salt", black_box(b"input key material"), 32, b"info"))
+    });
+
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(PBKDF2_ITERATIONS as u64));
+    group.bench_function("pbkdf2", |b| {
+        b.iter(|| pbkdf2_derive_key::<H, H::Context>(ctx, black_box(b"password"), b"salt", PBKDF2_ITERATIONS, 32))
+    });
+    group.finish();
+}
+
+#[cfg(feature = "rustcrypto-compat")]
+fn bench_reference_hash<D>(name: &str, criterion: &mut Criterion)
+    where D: digest::Digest
+{
+    let mut group = criterion.benchmark_group(format!("{} (RustCrypto)", name));
+    for length in ONE_SHOT_LENGTHS.iter() {
+        let message = vec![0xA5; *length];
+        group.throughput(Throughput::Bytes(*length as u64));
+        group.bench_with_input(BenchmarkId::new("one-shot", length), &message, |b, message| {
+            b.iter(|| D::digest(black_box(message)))
+        });
+    }
+
+    let message = vec![0xA5; STREAM_LENGTH];
+    group.throughput(Throughput::Bytes(STREAM_LENGTH as u64));
+    group.bench_with_input(BenchmarkId::new("streaming", STREAM_LENGTH), &message, |b, message| {
+        b.iter(|| {
+            let mut state = D::new();
+            for chunk in message.chunks(CHUNK_LENGTH) {
+                state.update(black_box(chunk));
+            }
+            state.finalize()
+        })
+    });
+    group.finish();
+}
+
+/// Measure the throughput of `digest` applied to a message of `STREAM_LENGTH` bytes in MiB per second.
+fn measure_throughput<F>(mut digest: F) -> f64
+    where F: FnMut(&[u8])
+{
+    let message = vec![0xA5; STREAM_LENGTH];
+    let start = Instant::now();
+    let mut digests = 0;
+    while start.elapsed() < TABLE_MEASUREMENT_TIME {
+        digest(black_box(&message));
+        digests += 1;
+    }
+
+    digests as f64 * STREAM_LENGTH as f64 / (1024.0 * 1024.0) / start.elapsed().as_secs_f64()
+}
+
+/// A row of the summary table.
+struct TableRow {
+    name: &'static str,
+    throughput: f64,
+    reference_throughput: Option<f64>,
+}
+
+fn measure_hash<H>(name: &'static str, ctx: &H::Context, rows: &mut Vec<TableRow>)
+    where H: HashFunction
+{
+    rows.push(TableRow {
+        name,
+        throughput: measure_throughput(|message| {
+            H::digest_message(ctx, message);
+        }),
+        reference_throughput: None,
+    });
+}
+
+#[cfg(feature = "rustcrypto-compat")]
+fn measure_reference_hash<D>(name: &'static str, rows: &mut [TableRow])
+    where D: digest::Digest
+{
+    if let Some(row) = rows.iter_mut().find(|row| row.name == name) {
+        row.reference_throughput = Some(measure_throughput(|message| {
+            D::digest(message);
+        }));
+    }
+}
+
+fn print_table(rows: &[TableRow]) {
+    println!();
+    println!("one-shot throughput of {} MiB messages", STREAM_LENGTH / (1024 * 1024));
+    println!("| {:<8} | {:>12} | {:>12} | {:>6} |", "hash", "jester MiB/s", "ref. MiB/s", "ratio");
+    println!("|{:-<10}|{:->14}|{:->14}|{:->8}|", "", "", "", "");
+    for row in rows {
+        match row.reference_throughput {
+            Some(reference) => println!("| {:<8} | {:>12.1} | {:>12.1} | {:>6.2} |",
+                                        row.name, row.throughput, reference, row.throughput / reference),
+            None => println!("| {:<8} | {:>12.1} | {:>12} | {:>6} |", row.name, row.throughput, "-", "-"),
+        }
+    }
+}
+
+fn main() {
+    let mut criterion = Criterion::default().configure_from_args();
+    for_each_hash!(bench_hash(&mut criterion));
+    #[cfg(feature = "rustcrypto-compat")]
+    for_each_reference_hash!(bench_reference_hash(&mut criterion));
+    criterion.final_summary();
+
+    // `cargo test --benches` runs each benchmark once without the `--bench` flag, which needs no summary
+    if std::env::args().any(|argument| argument == "--bench") {
+        let mut rows = vec![];
+        for_each_hash!(measure_hash(&mut rows));
+        #[cfg(feature = "rustcrypto-compat")]
+        for_each_reference_hash!(measure_reference_hash(&mut rows));
+        print_table(&rows);
+    }
+}
@@ -0,0 +1,198 @@
+//! Hash a file or the standard input and print the digest as hex.
+//!
+//! ```text
+//! cargo run --example hash_cli -- [--algorithm NAME] [--progress] [FILE]
+//! ```
+//!
+//! Files are hashed in chunks with `hasher::hash_file`, the standard input is copied into a `hasher::Hasher`. The
+//! algorithm defaults to Blake2b, see `multi_digest::HashAlgorithm::name` for the names of all algorithms.
+
+use std::fmt::Display;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use jester_hashes::blake::blake2b::Blake2b;
+use jester_hashes::blake::blake2s::Blake2s;
+use jester_hashes::crc::crc32::Crc32;
+use jester_hashes::crc::crc32c::Crc32c;
+use jester_hashes::hasher::hash_file;
+use jester_hashes::md5::MD5Hash;
+use jester_hashes::multi_digest::HashAlgorithm;
+use jester_hashes::sha1::SHA1Hash;
+use jester_hashes::sm3::SM3Hash;
+use jester_hashes::whirlpool::WhirlpoolHash;
+use jester_hashes::{DefaultContextHash, HashFunction};
+
+/// The number of bytes of a file that are read and hashed at once
+const CHUNK_SIZE: usize = 64 * 1024;
+
+const USAGE: &str = "usage: hash_cli [--algorithm NAME] [--progress] [FILE]";
+
+/// The parsed command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Arguments {
+    algorithm: HashAlgorithm,
+    progress: bool,
+    /// The file to hash, or `None` to hash the standard input
+    path: Option<PathBuf>,
+}
+
+/// Parse the command line arguments, excluding the program name.
+///
+/// # Returns
+/// The arguments or a message describing the first invalid argument
+fn parse_arguments<I>(arguments: I) -> Result<Arguments, String>
+    where I: IntoIterator<Item = String>
+{
+    let mut parsed = Arguments { algorithm: HashAlgorithm::Blake2b, progress: false, path: None };
+
+    let mut arguments = arguments.into_iter();
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "-a" | "--algorithm" => {
+                let name = arguments.next().ok_or_else(|| format!("{} requires an algorithm name", argument))?;
+                parsed.algorithm = HashAlgorithm::from_name(&name)
+                    .ok_or_else(|| format!("unknown algorithm \"{}\"", name))?;
+            }
+            "-p" | "--progress" => parsed.progress = true,
+            "-" if parsed.path.is_none() => {}
+            option if option.starts_with('-') => return Err(format!("unknown option \"{}\"", option)),
+            _ if parsed.path.is_some() => return Err(format!("unexpected argument \"{}\"", argument)),
+            _ => parsed.path = Some(PathBuf::from(argument)),
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// The data to hash
+enum Input<'a> {
+    File { path: &'a Path, progress: Option<&'a mut dyn FnMut(u64, u64)> },
+    Reader(&'a mut dyn Read),
+}
+
+/// Hash the input with the hash function `H` in its default context.
+fn hash_input_hex<H>(input: Input<'_>) -> io::Result<String>
+    where H: HashFunction,
+          H::Context: Default,
+          H::HashData: Display
+{
+    match input {
+        Input::File { path, progress } =>
+            hash_file::<H>(&H::Context::default(), path, CHUNK_SIZE, progress).map(|hash| hash.to_string()),
+        Input::Reader(reader) => {
+            let mut hasher = H::hasher();
+            io::copy(reader, &mut hasher)?;
+            Ok(hasher.finalize().to_string())
+        }
+    }
+}
+
+/// Hash the input with the given algorithm and format the digest as lowercase hex.
+fn hash_hex(algorithm: HashAlgorithm, input: Input<'_>) -> io::Result<String> {
+    match algorithm {
+        HashAlgorithm::Md5 => hash_input_hex::<MD5Hash>(input),
+        HashAlgorithm::Sha1 => hash_input_hex::<SHA1Hash>(input),
+        HashAlgorithm::Blake2b => hash_input_hex::<Blake2b>(input),
+        HashAlgorithm::Blake2s => hash_input_hex::<Blake2s>(input),
+        HashAlgorithm::Sm3 => hash_input_hex::<SM3Hash>(input),
+        HashAlgorithm::Whirlpool => hash_input_hex::<WhirlpoolHash>(input),
+        HashAlgorithm::Crc32 => hash_input_hex::<Crc32>(input),
+        HashAlgorithm::Crc32c => hash_input_hex::<Crc32c>(input),
+    }
+}
+
+fn main() {
+    let arguments = parse_arguments(std::env::args().skip(1)).unwrap_or_else(|message| {
+        eprintln!("{}\n{}", message, USAGE);
+        process::exit(2)
+    });
+
+    let mut report = |done: u64, total: u64| eprint!("\r{:>3}%", (done * 100).checked_div(total).unwrap_or(100));
+    let result = match &arguments.path {
+        Some(path) => {
+            let progress = if arguments.progress { Some(&mut report as &mut dyn FnMut(u64, u64)) } else { None };
+            hash_hex(arguments.algorithm, Input::File { path, progress })
+        }
+        None => hash_hex(arguments.algorithm, Input::Reader(&mut io::stdin())),
+    };
+    if arguments.progress && arguments.path.is_some() {
+        eprintln!();
+    }
+
+    match result {
+        Ok(digest) => match &arguments.path {
+            Some(path) => println!("{}  {}", digest, path.display()),
+            None => println!("{}  -", digest),
+        },
+        Err(error) => {
+            eprintln!("hash_cli: {}", error);
+            process::exit(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arguments(line: &str) -> Result<Arguments, String> {
+        parse_arguments(line.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn test_parse_arguments() {
+        assert_eq!(
+            arguments(""),
+            Ok(Arguments { algorithm: HashAlgorithm::Blake2b, progress: false, path: None })
+        );
+        assert_eq!(
+            arguments("--algorithm SHA1 -p notes.txt"),
+            Ok(Arguments { algorithm: HashAlgorithm::Sha1, progress: true, path: Some(PathBuf::from("notes.txt")) })
+        );
+        assert_eq!(arguments("-a md5 -").unwrap().path, None);
+
+        assert!(arguments("--algorithm").is_err());
+        assert!(arguments("-a sha256").is_err());
+        assert!(arguments("--verbose").is_err());
+        assert!(arguments("first second").is_err());
+    }
+
+    #[test]
+    fn test_hash_reader() {
+        assert_eq!(
+            hash_hex(HashAlgorithm::Md5, Input::Reader(&mut &b"abc"[..])).unwrap(),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+        assert_eq!(
+            hash_hex(HashAlgorithm::Sha1, Input::Reader(&mut &b"abc"[..])).unwrap(),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+    }
+
+    #[test]
+    fn test_file_matches_reader() {
+        let data = b"hashed in chunks and streamed".repeat(10_000);
+        let path = std::env::temp_dir().join(format!("jester_hash_cli_{}", process::id()));
+        std::fs::write(&path, &data).unwrap();
+
+        let mut reports = 0;
+        let mut progress = |_, _| reports += 1;
+        for algorithm in HashAlgorithm::ALL.iter() {
+            let from_file = hash_hex(*algorithm, Input::File { path: &path, progress: Some(&mut progress) });
+            let from_reader = hash_hex(*algorithm, Input::Reader(&mut &data[..]));
+            assert_eq!(from_file.unwrap(), from_reader.unwrap());
+        }
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(reports, HashAlgorithm::ALL.len() * data.len().div_ceil(CHUNK_SIZE));
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let path = std::env::temp_dir().join(format!("jester_hash_cli_missing_{}", process::id()));
+        let result = hash_hex(HashAlgorithm::Md5, Input::File { path: &path, progress: None });
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+}
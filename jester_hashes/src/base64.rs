@@ -0,0 +1,77 @@
+//! A minimal base64 codec with the standard alphabet and without padding, as used by PHC formatted password hash
+//! strings.
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode `data` as unpadded base64.
+pub(crate) fn encode(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity((data.len() * 4).div_ceil(3));
+
+    for chunk in data.chunks(3) {
+        let mut group = [0_u8; 3];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let bits = (group[0] as u32) << 16 | (group[1] as u32) << 8 | group[2] as u32;
+
+        // a chunk of n bytes is encoded in n + 1 characters
+        for i in 0..=chunk.len() {
+            encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3F) as usize] as char);
+        }
+    }
+
+    encoded
+}
+
+/// Decode unpadded base64. Returns `None` if `encoded` contains characters outside the alphabet, has an impossible
+/// length or non-zero trailing bits.
+pub(crate) fn decode(encoded: &str) -> Option<Vec<u8>> {
+    let encoded = encoded.as_bytes();
+    if encoded.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut decoded = Vec::with_capacity(encoded.len() * 3 / 4);
+    for chunk in encoded.chunks(4) {
+        let mut bits = 0_u32;
+        for (i, character) in chunk.iter().enumerate() {
+            let value = ALPHABET.iter().position(|c| c == character)? as u32;
+            bits |= value << (18 - 6 * i);
+        }
+
+        let bytes = bits.to_be_bytes();
+        let length = chunk.len() - 1;
+
+        // reject non-canonical encodings
+        if bytes[1 + length..].iter().any(|&b| b != 0) {
+            return None;
+        }
+
+        decoded.extend_from_slice(&bytes[1..=length]);
+    }
+
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for length in 0..20 {
+            let data: Vec<u8> = (0..length).map(|i| (i * 37 + 11) as u8).collect();
+            assert_eq!(decode(&encode(&data)), Some(data));
+        }
+
+        assert_eq!(encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(encode(b"fooba"), "Zm9vYmE");
+        assert_eq!(encode(b"foob"), "Zm9vYg");
+    }
+
+    #[test]
+    fn test_malformed() {
+        assert_eq!(decode("Zm9vY"), None);
+        assert_eq!(decode("Zm9v!mE"), None);
+        assert_eq!(decode("Zm9vYh"), None);
+        assert_eq!(decode("Zm9vYg=="), None);
+    }
+}
@@ -0,0 +1,102 @@
+//! Buffering of messages whose length is not a multiple of eight bits. Bits are consumed most significant bit first,
+//! like in the padding of the Merkle–Damgård hash functions, so a message of `n` bits is the first `n` bits of its
+//! bytes.
+
+use std::borrow::Cow;
+
+/// The bits of an incomplete byte at the end of the data compressed so far. The bits are stored in the most
+/// significant bits of `byte`, all other bits are zero.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub(crate) struct PartialByte {
+    byte: u8,
+    bits: usize,
+}
+
+impl PartialByte {
+    /// The number of buffered bits, which is less than eight.
+    pub(crate) fn bits(&self) -> usize {
+        self.bits
+    }
+
+    /// Append the first `bit_len` bits of `data` to the buffered bits.
+    ///
+    /// # Returns
+    /// All complete bytes of the buffered bits and `data`. The bits of an incomplete last byte stay buffered. If no
+    /// bits are buffered, the bytes are borrowed from `data`.
+    ///
+    /// # Panics
+    /// If `data` has fewer than `bit_len` bits
+    pub(crate) fn append<'a>(&mut self, data: &'a [u8], bit_len: usize) -> Cow<'a, [u8]> {
+        assert!(bit_len <= data.len() * 8, "cannot take {} bits of {} bytes", bit_len, data.len());
+
+        let whole_bytes = bit_len / 8;
+        let rest = bit_len % 8;
+        let tail = if rest == 0 { 0 } else { data[whole_bytes] & !(0xFF >> rest) };
+
+        if self.bits == 0 {
+            *self = PartialByte { byte: tail, bits: rest };
+            return Cow::Borrowed(&data[..whole_bytes]);
+        }
+
+        // all bytes are shifted by the number of buffered bits
+        let shift = self.bits;
+        let mut bytes = Vec::with_capacity(whole_bytes + 1);
+        let mut carry = self.byte;
+        for byte in &data[..whole_bytes] {
+            bytes.push(carry | (byte >> shift));
+            carry = byte << (8 - shift);
+        }
+
+        let combined = carry | (tail >> shift);
+        if shift + rest >= 8 {
+            bytes.push(combined);
+            *self = PartialByte { byte: tail << (8 - shift), bits: shift + rest - 8 };
+        } else {
+            *self = PartialByte { byte: combined, bits: shift + rest };
+        }
+
+        Cow::Owned(bytes)
+    }
+
+    /// The buffered bits followed by the single 1-bit that starts the padding of the message.
+    pub(crate) fn padded(&self) -> u8 {
+        self.byte | (0x80 >> self.bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_aligned() {
+        let mut partial = PartialByte::default();
+        assert!(matches!(partial.append(&[1, 2, 3], 24), Cow::Borrowed(&[1, 2, 3])));
+        assert_eq!(partial.bits(), 0);
+
+        // the bits after `bit_len` are ignored
+        assert_eq!(partial.append(&[1, 0xFF], 11).as_ref(), &[1]);
+        assert_eq!(partial, PartialByte { byte: 0xE0, bits: 3 });
+        assert_eq!(partial.padded(), 0xF0);
+    }
+
+    #[test]
+    fn test_append_shifted() {
+        let mut partial = PartialByte::default();
+        partial.append(&[0xA0], 3);
+
+        assert_eq!(partial.append(&[0xFF, 0x0F], 16).as_ref(), &[0xBF, 0xE1]);
+        assert_eq!(partial, PartialByte { byte: 0xE0, bits: 3 });
+
+        // the buffered bits complete a byte
+        assert_eq!(partial.append(&[0x5F], 5).as_ref(), &[0xEB]);
+        assert_eq!(partial.bits(), 0);
+        assert_eq!(partial.padded(), 0x80);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_append_too_few_bits() {
+        PartialByte::default().append(&[0], 9);
+    }
+}
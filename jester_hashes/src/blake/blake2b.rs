@@ -1,8 +1,11 @@
 use std::convert::TryInto;
+use std::str::FromStr;
 
-use crate::{HashFunction, HashValue, BlockHashFunction};
-use crate::blake::{blake2_mix, SIGMA};
-use byteorder::{LittleEndian, WriteBytesExt};
+use crate::{HashFunction, HashValue, BlockHashFunction, ParseHexError};
+use crate::hex_format::decode_hex_bounded;
+use crate::bytes::read_u64_le;
+use crate::blake::{advance_counter, blake2_mix, validate_parameters, Blake2ParamError, SIGMA};
+use jester_util::Redacted;
 
 /// The initial state for any blake2b hash. From here, all blocks are applied.
 pub const INITIAL_2B: [u64; 8] = [
@@ -19,22 +22,75 @@ pub const INITIAL_2B: [u64; 8] = [
 pub const BLAKE_2B_WORD_LENGTH: usize = 64;
 pub const BLAKE_2B_ROUND_COUNT: usize = 12;
 pub const BLAKE_2B_BLOCK_SIZE: usize = 128;
+pub const BLAKE_2B_MAX_OUTPUT_LENGTH: usize = 64;
+pub const BLAKE_2B_MAX_KEY_LENGTH: usize = 64;
 
 /// A type for the Blake2b hash function. It does not carry actual data and exists solely for
 /// access to the function.
 pub struct Blake2b;
 
 /// A Blake2b hash output. It varies in length depending on the desired output length
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Blake2bHash {
     pub hash: Vec<u8>,
 }
 
+/// The parameters of a Blake2b hash: its output length and an optional key. The parameters are validated on
+/// construction, so a context always describes a valid Blake2b instance.
+#[derive(Debug, Clone)]
 pub struct Blake2bContext {
-    pub output_len: usize,
-    pub key: Vec<u8>,
+    output_len: usize,
+    key: Redacted<Vec<u8>>,
+}
+
+impl Blake2bContext {
+    /// Create the context of an unkeyed hash with `output_len` bytes of output.
+    ///
+    /// # Returns
+    /// The context, or a `Blake2ParamError` if `output_len` is not between 1 and `BLAKE_2B_MAX_OUTPUT_LENGTH`.
+    pub fn new(output_len: usize) -> Result<Self, Blake2ParamError> {
+        Self::new_keyed(output_len, &[])
+    }
+
+    /// Create the context of a keyed hash with `output_len` bytes of output. An empty key is the unkeyed hash.
+    ///
+    /// # Returns
+    /// The context, or a `Blake2ParamError` if `output_len` is not between 1 and `BLAKE_2B_MAX_OUTPUT_LENGTH`, or
+    /// if `key` is longer than `BLAKE_2B_MAX_KEY_LENGTH` bytes.
+    pub fn new_keyed(output_len: usize, key: &[u8]) -> Result<Self, Blake2ParamError> {
+        validate_parameters(output_len, key.len(), BLAKE_2B_MAX_OUTPUT_LENGTH)?;
+
+        Ok(Blake2bContext {
+            output_len,
+            key: Redacted::new(key.to_vec()),
+        })
+    }
+
+    /// The output length in bytes.
+    pub fn output_len(&self) -> usize {
+        self.output_len
+    }
+
+    /// The key, which is empty for unkeyed hashes.
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// Assert the invariants established by the constructors before the parameters are used.
+    fn debug_assert_valid(&self) {
+        debug_assert!((1..=BLAKE_2B_MAX_OUTPUT_LENGTH).contains(&self.output_len), "invalid blake2b output length");
+        debug_assert!(self.key.len() <= BLAKE_2B_MAX_KEY_LENGTH, "blake2b key too long");
+    }
+}
+
+/// The unkeyed hash with the maximum output length of 64 bytes.
+impl Default for Blake2bContext {
+    fn default() -> Self {
+        Self::new(BLAKE_2B_MAX_OUTPUT_LENGTH).unwrap()
+    }
 }
 
+#[derive(Clone)]
 pub struct Blake2bState {
     hash: [u64; 8],
     message_length: u128,
@@ -47,29 +103,24 @@ impl HashFunction for Blake2b {
     type HashState = Blake2bState;
     type HashData = Blake2bHash;
 
+    const MAX_OUTPUT_SIZE: usize = BLAKE_2B_MAX_OUTPUT_LENGTH;
+
+    fn output_size_for(ctx: &Self::Context) -> usize {
+        ctx.output_len
+    }
+
     fn init_hash(ctx: &Self::Context) -> Self::HashState {
-        let mut state = Blake2bState {
-            hash: INITIAL_2B,
-            message_length: 0,
-            remaining_data_buffer: [0_u8; BLAKE_2B_BLOCK_SIZE],
-            remaining_data_length: 0,
-        };
-
-        // parameter block
-        state.hash[0] ^= 0x0101_0000 ^ ((ctx.key.len() as u64) << 8) ^ ctx.output_len as u64;
-
-        // copy the key into the remaining data buffer and set the buffer to full. However, do
-        // not compress yet: If no further data is hashed, this is considered the last block,
-        // thus we cannot know whether the last block flag must be set.
-        if !ctx.key.is_empty() {
-            state.remaining_data_buffer[..ctx.key.len()].copy_from_slice(&ctx.key);
-            state.remaining_data_length = BLAKE_2B_BLOCK_SIZE;
-        }
+        ctx.debug_assert_valid();
+        init_parameterized(&sequential_parameter_block(ctx), &ctx.key)
+    }
 
-        state
+    /// Reset the state in place, copying the key into the existing data buffer.
+    fn reset_hash(hash: &mut Self::HashState, ctx: &Self::Context) {
+        ctx.debug_assert_valid();
+        reset_parameterized(hash, &sequential_parameter_block(ctx), &ctx.key)
     }
 
-    fn update_hash(hash: &mut Self::HashState, _ctx: &Self::Context, input: &[u8]) {
+    fn update_hash(hash: &mut Self::HashState, input: &[u8]) {
         // offset where to begin reading input data
         let mut input_data_offset;
 
@@ -90,7 +141,7 @@ impl HashFunction for Blake2b {
                 .copy_from_slice(&input[..input_data_offset]);
 
             // update message length by a block
-            hash.message_length += BLAKE_2B_BLOCK_SIZE as u128;
+            advance_counter(&mut hash.message_length, BLAKE_2B_BLOCK_SIZE);
 
             // compress the new block
             blake2b_compress(hash, &block, false);
@@ -111,7 +162,7 @@ impl HashFunction for Blake2b {
         // arrive.
         while input.len() - input_data_offset > BLAKE_2B_BLOCK_SIZE {
             // increase message length by one block
-            hash.message_length += BLAKE_2B_BLOCK_SIZE as u128;
+            advance_counter(&mut hash.message_length, BLAKE_2B_BLOCK_SIZE);
 
             // compress the next block
             blake2b_compress(
@@ -131,12 +182,8 @@ impl HashFunction for Blake2b {
     }
 
     fn finish_hash(hash: &mut Self::HashState, ctx: &Self::Context) -> Self::HashData {
-        if hash.message_length.wrapping_add(hash.remaining_data_length as u128) <
-            hash.message_length {
-            panic!("blake2b cannot hash more than 2**128-1 bytes")
-        } else {
-            hash.message_length += hash.remaining_data_length as u128;
-        }
+        // the counter of the last block includes the key block, if no other block was compressed before
+        advance_counter(&mut hash.message_length, hash.remaining_data_length);
 
         // pad last block with zeros
         let mut last_block = [0_u8; BLAKE_2B_BLOCK_SIZE];
@@ -151,28 +198,66 @@ impl HashFunction for Blake2b {
     fn digest_message(ctx: &Self::Context, input: &[u8]) -> Self::HashData {
         let mut hash_state = Self::init_hash(ctx);
 
-        Self::update_hash(&mut hash_state, ctx, input);
+        Self::update_hash(&mut hash_state, input);
         Self::finish_hash(&mut hash_state, ctx)
     }
 }
 
+/// Initialize a Blake2b state from an arbitrary parameter block, given as little endian words. This is used by the
+/// Blake2 variants that use other parameters than the sequential mode, like Blake2X.
+pub(crate) fn init_parameterized(parameter_block: &[u64; 8], key: &[u8]) -> Blake2bState {
+    let mut state = Blake2bState {
+        hash: INITIAL_2B,
+        message_length: 0,
+        remaining_data_buffer: [0_u8; BLAKE_2B_BLOCK_SIZE],
+        remaining_data_length: 0,
+    };
+
+    reset_parameterized(&mut state, parameter_block, key);
+    state
+}
+
+/// Reset a Blake2b state to the initial state of `init_parameterized` without constructing a new state.
+pub(crate) fn reset_parameterized(state: &mut Blake2bState, parameter_block: &[u64; 8], key: &[u8]) {
+    state.hash = INITIAL_2B;
+    state.message_length = 0;
+    state.remaining_data_length = 0;
+
+    for (word, parameter) in state.hash.iter_mut().zip(parameter_block) {
+        *word ^= parameter;
+    }
+
+    // copy the key into the remaining data buffer and set the buffer to full. However, do
+    // not compress yet: If no further data is hashed, this is considered the last block,
+    // thus we cannot know whether the last block flag must be set.
+    if !key.is_empty() {
+        state.remaining_data_buffer[..key.len()].copy_from_slice(key);
+        state.remaining_data_buffer[key.len()..].iter_mut().for_each(|byte| *byte = 0);
+        state.remaining_data_length = BLAKE_2B_BLOCK_SIZE;
+    }
+}
+
+/// The parameter block of the sequential mode: fanout and depth are 1, everything else is zero.
+fn sequential_parameter_block(ctx: &Blake2bContext) -> [u64; 8] {
+    let mut parameter_block = [0_u64; 8];
+    parameter_block[0] = 0x0101_0000 ^ ((ctx.key.len() as u64) << 8) ^ ctx.output_len as u64;
+    parameter_block
+}
+
 impl BlockHashFunction for Blake2b {
     fn block_size(_ctx: &Self::Context) -> usize {
         BLAKE_2B_BLOCK_SIZE
     }
-
-    fn output_size(ctx: &Self::Context) -> usize {
-        ctx.output_len
-    }
 }
 
 impl HashValue for Blake2bState {
+    /// Serializes the state words in little endian byte order, independent of the byte order of the host.
     fn raw(&self) -> Vec<u8> {
-        let mut b = vec![];
-        for i in 0..8 {
-            b.write_u64::<LittleEndian>(self.hash[i]).unwrap();
+        let mut bytes = Vec::with_capacity(self.hash.len() * 8);
+        for word in self.hash.iter() {
+            bytes.extend_from_slice(&word.to_le_bytes());
         }
-        b
+        bytes
     }
 }
 
@@ -182,6 +267,23 @@ impl HashValue for Blake2bHash {
     }
 }
 
+impl AsRef<[u8]> for Blake2bHash {
+    fn as_ref(&self) -> &[u8] {
+        &self.hash
+    }
+}
+
+impl_hex_format!(Blake2bHash);
+
+impl FromStr for Blake2bHash {
+    type Err = ParseHexError;
+
+    /// Parse the hex digits of a hash of 1 to 64 bytes in either case.
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        Ok(Blake2bHash { hash: decode_hex_bounded(hex, BLAKE_2B_MAX_OUTPUT_LENGTH)? })
+    }
+}
+
 #[allow(clippy::many_single_char_names)]
 fn blake2b_mix(vector: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize, x: u64, y: u64) {
     blake2_mix::<u64, 32, 24, 16, 63>(vector, a, b, c, d, x, y)
@@ -240,8 +342,8 @@ fn blake2b_compress(state: &mut Blake2bState, input: &[u8; 128], last_block: boo
 
 fn transform_block(bytes: &[u8; 128]) -> [u64; 16] {
     let mut block = [0_u64; 16];
-    for i in 0..16 {
-        block[i] = u64::from_le_bytes(bytes[i * 8..(i + 1) * 8].try_into().unwrap());
+    for (i, word) in block.iter_mut().enumerate() {
+        *word = read_u64_le(bytes, i * 8);
     }
     block
 }
\ No newline at end of file
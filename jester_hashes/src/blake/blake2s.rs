@@ -1,7 +1,10 @@
-use crate::blake::{blake2_mix, SIGMA};
+use crate::blake::{advance_counter, blake2_mix, validate_parameters, Blake2ParamError, SIGMA};
 use std::convert::TryInto;
-use crate::{HashFunction, BlockHashFunction, HashValue};
-use byteorder::{LittleEndian, WriteBytesExt};
+use std::str::FromStr;
+use crate::{HashFunction, BlockHashFunction, HashValue, ParseHexError};
+use crate::hex_format::decode_hex_bounded;
+use crate::bytes::read_u32_le;
+use jester_util::Redacted;
 
 pub const INITIAL_2S: [u32; 8] = [
     0x6A09E667, 0xBB67AE85, 0x3C6EF372, 0xA54FF53A, 0x510E527F, 0x9B05688C, 0x1F83D9AB, 0x5BE0CD19,
@@ -10,22 +13,75 @@ pub const INITIAL_2S: [u32; 8] = [
 pub const BLAKE_2S_WORD_LENGTH: usize = 32;
 pub const BLAKE_2S_ROUND_COUNT: usize = 10;
 pub const BLAKE_2S_BLOCK_SIZE: usize = 64;
+pub const BLAKE_2S_MAX_OUTPUT_LENGTH: usize = 32;
+pub const BLAKE_2S_MAX_KEY_LENGTH: usize = 32;
 
 /// A type for the Blake2s hash function. It does not carry actual data and exists solely for
 /// access to the function.
 pub struct Blake2s;
 
 /// A Blake2s hash output. It varies in length depending on the desired output length
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Blake2sHash {
     pub hash: Vec<u8>
 }
 
+/// The parameters of a Blake2s hash: its output length and an optional key. The parameters are validated on
+/// construction, so a context always describes a valid Blake2s instance.
+#[derive(Debug, Clone)]
 pub struct Blake2sContext {
-    pub output_len: usize,
-    pub key: Vec<u8>,
+    output_len: usize,
+    key: Redacted<Vec<u8>>,
 }
 
+impl Blake2sContext {
+    /// Create the context of an unkeyed hash with `output_len` bytes of output.
+    ///
+    /// # Returns
+    /// The context, or a `Blake2ParamError` if `output_len` is not between 1 and `BLAKE_2S_MAX_OUTPUT_LENGTH`.
+    pub fn new(output_len: usize) -> Result<Self, Blake2ParamError> {
+        Self::new_keyed(output_len, &[])
+    }
+
+    /// Create the context of a keyed hash with `output_len` bytes of output. An empty key is the unkeyed hash.
+    ///
+    /// # Returns
+    /// The context, or a `Blake2ParamError` if `output_len` is not between 1 and `BLAKE_2S_MAX_OUTPUT_LENGTH`, or
+    /// if `key` is longer than `BLAKE_2S_MAX_KEY_LENGTH` bytes.
+    pub fn new_keyed(output_len: usize, key: &[u8]) -> Result<Self, Blake2ParamError> {
+        validate_parameters(output_len, key.len(), BLAKE_2S_MAX_OUTPUT_LENGTH)?;
+
+        Ok(Blake2sContext {
+            output_len,
+            key: Redacted::new(key.to_vec()),
+        })
+    }
+
+    /// The output length in bytes.
+    pub fn output_len(&self) -> usize {
+        self.output_len
+    }
+
+    /// The key, which is empty for unkeyed hashes.
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// Assert the invariants established by the constructors before the parameters are used.
+    fn debug_assert_valid(&self) {
+        debug_assert!((1..=BLAKE_2S_MAX_OUTPUT_LENGTH).contains(&self.output_len), "invalid blake2s output length");
+        debug_assert!(self.key.len() <= BLAKE_2S_MAX_KEY_LENGTH, "blake2s key too long");
+    }
+}
+
+/// The unkeyed hash with the maximum output length of 32 bytes.
+impl Default for Blake2sContext {
+    fn default() -> Self {
+        Self::new(BLAKE_2S_MAX_OUTPUT_LENGTH).unwrap()
+    }
+}
+
+#[derive(Clone)]
 pub struct Blake2sState {
     hash: [u32; 8],
     message_length: u64,
@@ -43,7 +99,14 @@ impl HashFunction for Blake2s {
     type HashState = Blake2sState;
     type HashData = Blake2sHash;
 
+    const MAX_OUTPUT_SIZE: usize = BLAKE_2S_MAX_OUTPUT_LENGTH;
+
+    fn output_size_for(ctx: &Self::Context) -> usize {
+        ctx.output_len
+    }
+
     fn init_hash(ctx: &Self::Context) -> Self::HashState {
+        ctx.debug_assert_valid();
         let mut state = Blake2sState {
             hash: INITIAL_2S,
             message_length: 0,
@@ -51,21 +114,31 @@ impl HashFunction for Blake2s {
             remaining_data_length: 0,
         };
 
+        Self::reset_hash(&mut state, ctx);
+        state
+    }
+
+    /// Reset the state in place, copying the key into the existing data buffer.
+    fn reset_hash(hash: &mut Self::HashState, ctx: &Self::Context) {
+        ctx.debug_assert_valid();
+        hash.hash = INITIAL_2S;
+        hash.message_length = 0;
+        hash.remaining_data_length = 0;
+
         // parameter block
-        state.hash[0] ^= 0x0101_0000 ^ ((ctx.key.len() as u32) << 8) ^ ctx.output_len as u32;
+        hash.hash[0] ^= 0x0101_0000 ^ ((ctx.key.len() as u32) << 8) ^ ctx.output_len as u32;
 
         // copy the key into the remaining data buffer and set the buffer to full. However, do
         // not compress yet: If no further data is hashed, this is considered the last block,
         // thus we cannot know whether the last block flag must be set.
         if !ctx.key.is_empty() {
-            state.remaining_data_buffer[..ctx.key.len()].copy_from_slice(&ctx.key);
-            state.remaining_data_length = BLAKE_2S_BLOCK_SIZE;
+            hash.remaining_data_buffer[..ctx.key.len()].copy_from_slice(&ctx.key);
+            hash.remaining_data_buffer[ctx.key.len()..].iter_mut().for_each(|byte| *byte = 0);
+            hash.remaining_data_length = BLAKE_2S_BLOCK_SIZE;
         }
-
-        state
     }
 
-    fn update_hash(hash: &mut Self::HashState, _ctx: &Self::Context, input: &[u8]) {
+    fn update_hash(hash: &mut Self::HashState, input: &[u8]) {
         // offset where to begin reading input data
         let mut input_data_offset;
 
@@ -86,7 +159,7 @@ impl HashFunction for Blake2s {
                 .copy_from_slice(&input[..input_data_offset]);
 
             // update message length by a block
-            hash.message_length += BLAKE_2S_BLOCK_SIZE as u64;
+            advance_counter(&mut hash.message_length, BLAKE_2S_BLOCK_SIZE);
 
             // compress the new block
             blake2s_compress(hash, &block, false);
@@ -107,7 +180,7 @@ impl HashFunction for Blake2s {
         // arrive.
         while input.len() - input_data_offset > BLAKE_2S_BLOCK_SIZE {
             // increase message length by one block
-            hash.message_length += BLAKE_2S_BLOCK_SIZE as u64;
+            advance_counter(&mut hash.message_length, BLAKE_2S_BLOCK_SIZE);
 
             // compress the next block
             blake2s_compress(
@@ -127,12 +200,8 @@ impl HashFunction for Blake2s {
     }
 
     fn finish_hash(hash: &mut Self::HashState, ctx: &Self::Context) -> Self::HashData {
-        if hash.message_length.wrapping_add(hash.remaining_data_length as u64) <
-            hash.message_length {
-            panic!("blake2s cannot hash more than 2**64-1 bytes")
-        } else {
-            hash.message_length += hash.remaining_data_length as u64;
-        }
+        // the counter of the last block includes the key block, if no other block was compressed before
+        advance_counter(&mut hash.message_length, hash.remaining_data_length);
 
         // pad last block with zeros
         let mut last_block = [0_u8; BLAKE_2S_BLOCK_SIZE];
@@ -147,7 +216,7 @@ impl HashFunction for Blake2s {
     fn digest_message(ctx: &Self::Context, input: &[u8]) -> Self::HashData {
         let mut hash_state = Self::init_hash(ctx);
 
-        Self::update_hash(&mut hash_state, ctx, input);
+        Self::update_hash(&mut hash_state, input);
         Self::finish_hash(&mut hash_state, ctx)
     }
 }
@@ -156,19 +225,16 @@ impl BlockHashFunction for Blake2s {
     fn block_size(_ctx: &Self::Context) -> usize {
         BLAKE_2S_BLOCK_SIZE
     }
-
-    fn output_size(ctx: &Self::Context) -> usize {
-        ctx.output_len
-    }
 }
 
 impl HashValue for Blake2sState {
+    /// Serializes the state words in little endian byte order, independent of the byte order of the host.
     fn raw(&self) -> Vec<u8> {
-        let mut b = vec![];
-        for i in 0..8 {
-            b.write_u32::<LittleEndian>(self.hash[i]).unwrap();
+        let mut bytes = Vec::with_capacity(self.hash.len() * 4);
+        for word in self.hash.iter() {
+            bytes.extend_from_slice(&word.to_le_bytes());
         }
-        b
+        bytes
     }
 }
 
@@ -178,6 +244,23 @@ impl HashValue for Blake2sHash {
     }
 }
 
+impl AsRef<[u8]> for Blake2sHash {
+    fn as_ref(&self) -> &[u8] {
+        &self.hash
+    }
+}
+
+impl_hex_format!(Blake2sHash);
+
+impl FromStr for Blake2sHash {
+    type Err = ParseHexError;
+
+    /// Parse the hex digits of a hash of 1 to 32 bytes in either case.
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        Ok(Blake2sHash { hash: decode_hex_bounded(hex, BLAKE_2S_MAX_OUTPUT_LENGTH)? })
+    }
+}
+
 fn blake2s_compress(state: &mut Blake2sState, input: &[u8; 64], last_block: bool) {
     // initialize local working vector
     let mut vector: [u32; 16] = [0; 16];
@@ -233,8 +316,8 @@ fn blake2s_compress(state: &mut Blake2sState, input: &[u8; 64], last_block: bool
 /// little endian integers
 fn transform_block(bytes: &[u8; 64]) -> [u32; 16] {
     let mut block = [0_u32; 16];
-    for i in 0..16 {
-        block[i] = u32::from_le_bytes(bytes[i * 4..(i + 1) * 4].try_into().unwrap());
+    for (i, word) in block.iter_mut().enumerate() {
+        *word = read_u32_le(bytes, i * 4);
     }
     block
 }
\ No newline at end of file
@@ -0,0 +1,126 @@
+use std::str::FromStr;
+
+use crate::{HashFunction, HashValue, BlockHashFunction, ParseHexError};
+use crate::hex_format::decode_hex_bounded;
+use crate::blake::blake2b::{init_parameterized, Blake2b, Blake2bContext, Blake2bState, BLAKE_2B_BLOCK_SIZE};
+use jester_util::Redacted;
+
+/// The output length of a single Blake2b instance used within Blake2Xb.
+const BLAKE_2B_OUTPUT_SIZE: usize = 64;
+
+/// The maximum output length of Blake2Xb. The length `2^32 - 1` is reserved for an unknown output length.
+pub const BLAKE_2XB_MAX_OUTPUT_LENGTH: usize = u32::MAX as usize - 1;
+
+/// A type for the Blake2Xb extendable output function, as specified in "BLAKE2X" by Aumasson et al. It does not carry
+/// actual data and exists solely for access to the function.
+///
+/// The message is compressed into a root hash by Blake2b, with the requested output length encoded into the
+/// parameter block. The output is then the concatenation of the Blake2b hashes of the root hash with node offsets
+/// `0, 1, 2, ...`, so each output length results in an entirely different output.
+pub struct Blake2Xb;
+
+/// A Blake2Xb output of the requested length.
+#[derive(Debug, Clone)]
+pub struct Blake2XbHash {
+    pub hash: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Blake2XbContext {
+    /// The output length in bytes. It must be between `1` and `BLAKE_2XB_MAX_OUTPUT_LENGTH`.
+    pub output_len: usize,
+    pub key: Redacted<Vec<u8>>,
+}
+
+impl HashFunction for Blake2Xb {
+    type Context = Blake2XbContext;
+    type HashState = Blake2bState;
+    type HashData = Blake2XbHash;
+
+    const MAX_OUTPUT_SIZE: usize = BLAKE_2XB_MAX_OUTPUT_LENGTH;
+
+    fn output_size_for(ctx: &Self::Context) -> usize {
+        ctx.output_len
+    }
+
+    /// Initialize the root hash.
+    ///
+    /// # Panics
+    /// If the output length is zero or exceeds `BLAKE_2XB_MAX_OUTPUT_LENGTH`, or if the key is longer than 64 bytes.
+    fn init_hash(ctx: &Self::Context) -> Self::HashState {
+        assert!(
+            (1..=BLAKE_2XB_MAX_OUTPUT_LENGTH).contains(&ctx.output_len),
+            "blake2xb cannot produce {} bytes of output",
+            ctx.output_len
+        );
+        assert!(ctx.key.len() <= BLAKE_2B_OUTPUT_SIZE, "blake2xb keys cannot exceed 64 bytes");
+
+        // fanout and depth are 1 like in sequential mode, and the xof length shares the word with the node offset
+        let mut parameter_block = [0_u64; 8];
+        parameter_block[0] = 0x0101_0000 ^ ((ctx.key.len() as u64) << 8) ^ BLAKE_2B_OUTPUT_SIZE as u64;
+        parameter_block[1] = (ctx.output_len as u64) << 32;
+
+        init_parameterized(&parameter_block, &ctx.key)
+    }
+
+    fn update_hash(hash: &mut Self::HashState, input: &[u8]) {
+        Blake2b::update_hash(hash, input)
+    }
+
+    fn finish_hash(hash: &mut Self::HashState, ctx: &Self::Context) -> Self::HashData {
+        let root = Blake2b::finish_hash(hash, &root_context()).hash;
+
+        let mut output = Vec::with_capacity(ctx.output_len);
+        for node_offset in 0..ctx.output_len.div_ceil(BLAKE_2B_OUTPUT_SIZE) {
+            let block_len = (ctx.output_len - output.len()).min(BLAKE_2B_OUTPUT_SIZE);
+
+            // unkeyed hash of the root with leaf length and inner length set to the Blake2b output size
+            let mut parameter_block = [0_u64; 8];
+            parameter_block[0] = ((BLAKE_2B_OUTPUT_SIZE as u64) << 32) ^ block_len as u64;
+            parameter_block[1] = ((ctx.output_len as u64) << 32) ^ node_offset as u64;
+            parameter_block[2] = (BLAKE_2B_OUTPUT_SIZE as u64) << 8;
+
+            let block_ctx = Blake2bContext::new(block_len).unwrap();
+            let mut block_state = init_parameterized(&parameter_block, &[]);
+            Blake2b::update_hash(&mut block_state, &root);
+            output.extend(Blake2b::finish_hash(&mut block_state, &block_ctx).hash);
+        }
+
+        Blake2XbHash { hash: output }
+    }
+
+    fn digest_message(ctx: &Self::Context, input: &[u8]) -> Self::HashData {
+        let mut hash_state = Self::init_hash(ctx);
+
+        Self::update_hash(&mut hash_state, input);
+        Self::finish_hash(&mut hash_state, ctx)
+    }
+}
+
+impl BlockHashFunction for Blake2Xb {
+    fn block_size(_ctx: &Self::Context) -> usize {
+        BLAKE_2B_BLOCK_SIZE
+    }
+}
+
+impl HashValue for Blake2XbHash {
+    fn raw(&self) -> Vec<u8> {
+        self.hash.clone()
+    }
+}
+
+impl_hex_format!(Blake2XbHash);
+
+impl FromStr for Blake2XbHash {
+    type Err = ParseHexError;
+
+    /// Parse the hex digits of a hash of any valid output length in either case.
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        Ok(Blake2XbHash { hash: decode_hex_bounded(hex, BLAKE_2XB_MAX_OUTPUT_LENGTH)? })
+    }
+}
+
+/// The context of the root hash. The key is already part of the state, so it is not required here.
+fn root_context() -> Blake2bContext {
+    Blake2bContext::new(BLAKE_2B_OUTPUT_SIZE).unwrap()
+}
@@ -5,6 +5,7 @@ use num::traits::WrappingAdd;
 
 pub mod blake2b;
 pub mod blake2s;
+pub mod blake2xb;
 
 /// Blake2 round permutation matrix. In round i row i mod 10 is used to permute the input block.
 /// Column j denotes which input word is to be used as word j for the mixing function.
@@ -21,6 +22,42 @@ pub const SIGMA: [[usize; 16]; 10] = [
     [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],
 ];
 
+/// Errors that can arise when constructing the context of a Blake2 hash function with invalid parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Blake2ParamError {
+    /// The output length is zero or exceeds the maximum output length of the variant
+    InvalidOutputLength { output_len: usize, max: usize },
+
+    /// The key is longer than the maximum key length of the variant
+    KeyTooLong { key_len: usize, max: usize },
+}
+
+/// Check the output and key length of a Blake2 variant whose output and key are at most `max` bytes long.
+fn validate_parameters(output_len: usize, key_len: usize, max: usize) -> Result<(), Blake2ParamError> {
+    if !(1..=max).contains(&output_len) {
+        return Err(Blake2ParamError::InvalidOutputLength { output_len, max });
+    }
+
+    if key_len > max {
+        return Err(Blake2ParamError::KeyTooLong { key_len, max });
+    }
+
+    Ok(())
+}
+
+/// Advance the byte counter of a Blake2 state by the length of a block that is about to be compressed. The counter
+/// covers all bytes compressed so far including the current block, so a key block counts as a full block, even if the
+/// key is shorter. The final block is only counted with its actual length, not the padded length (RFC 7693, section
+/// 3.3).
+///
+/// # Panics
+/// If the counter overflows, i.e. the hashed message is too long for the Blake2 variant.
+fn advance_counter<N: PrimInt>(counter: &mut N, block_length: usize) {
+    *counter = N::from(block_length)
+        .and_then(|length| counter.checked_add(&length))
+        .unwrap_or_else(|| panic!("blake2 cannot hash more than 2**{}-1 bytes", N::zero().count_zeros()));
+}
+
 #[allow(clippy::many_single_char_names)]
 fn blake2_mix<N: WrappingAdd + PrimInt, const R1: u8, const R2: u8, const R3: u8, const R4: u8>(
     vector: &mut [N; 16],
@@ -44,69 +81,72 @@ fn blake2_mix<N: WrappingAdd + PrimInt, const R1: u8, const R2: u8, const R3: u8
 
 #[cfg(test)]
 pub(crate) mod blake2_tests {
-    use crate::{HashFunction, HashValue};
+    use crate::{DefaultContextHash, HashFunction, HashValue};
     use crate::blake::blake2b::{Blake2bContext, Blake2b};
-    use crate::tests::{EMPTY_MESSAGE, LONG_TEXT, SOME_TEXT, STREAM_TEXT};
+    use crate::tests::{assert_peek_consistent, assert_reset_consistent, EMPTY_MESSAGE, LONG_TEXT, SOME_TEXT, STREAM_TEXT};
     use crate::blake::blake2s::{Blake2s, Blake2sContext};
+    use crate::blake::blake2xb::{Blake2Xb, Blake2XbContext};
+    use crate::blake::{blake2_mix, Blake2ParamError, SIGMA};
+    use crate::blake::blake2b::INITIAL_2B;
+    use crate::blake::blake2b::Blake2bHash;
+    use crate::blake::blake2s::Blake2sHash;
+    use crate::blake::blake2xb::Blake2XbHash;
+    use crate::ParseHexError;
+    use jester_util::Redacted;
 
     #[test]
     fn blake2b_tests() {
-        let ctx = Blake2bContext {
-            output_len: 64,
-            key: vec![],
-        };
+        let ctx = Blake2bContext::new(64).unwrap();
 
         assert_eq!(
-            hex::encode(&Blake2b::digest_message(&ctx, EMPTY_MESSAGE.as_bytes()).raw()),
+            Blake2b::digest_message(&ctx, EMPTY_MESSAGE.as_bytes()).to_string(),
             "786a02f742015903c6c6fd852552d272912f4740e15847618a86e217f71f5419d25e1031afee585313896444934eb04b903a685b1448b755d56f701afe9be2ce"
         );
 
         assert_eq!(
-            hex::encode(&Blake2b::digest_message(&ctx, SOME_TEXT.as_bytes()).raw()),
+            Blake2b::digest_message(&ctx, SOME_TEXT.as_bytes()).to_string(),
             "fc918cde2b169d192d19438620f2a9b1d1d4cce16dc8b8e8600377a577a74ace2a65a21f1cb3d3f0e3abf97e88d804e8aa4d674df143e7070976018e2ae9060f"
         );
 
         assert_eq!(
-            hex::encode(&Blake2b::digest_message(&ctx, LONG_TEXT.as_bytes()).raw()),
+            Blake2b::digest_message(&ctx, LONG_TEXT.as_bytes()).to_string(),
             "ef403f8bd8f4f821376cf108e5004c78df3b7a99d198c166c7b8d1e6a409e10312bc273e3299a755b2cf75a5db85222266dd77215f80340363359656c621bf69"
         );
     }
 
     #[test]
     fn blake2b_stream_test() {
-        let ctx = Blake2bContext { output_len: 64, key: vec![] };
+        let ctx = Blake2bContext::new(64).unwrap();
         let mut hash_state = Blake2b::init_hash(&ctx);
-        Blake2b::update_hash(&mut hash_state, &ctx, STREAM_TEXT[0].as_bytes());
-        Blake2b::update_hash(&mut hash_state, &ctx, STREAM_TEXT[1].as_bytes());
-        Blake2b::update_hash(&mut hash_state, &ctx, STREAM_TEXT[2].as_bytes());
+        Blake2b::update_hash(&mut hash_state, STREAM_TEXT[0].as_bytes());
+        Blake2b::update_hash(&mut hash_state, STREAM_TEXT[1].as_bytes());
+        Blake2b::update_hash(&mut hash_state, STREAM_TEXT[2].as_bytes());
 
         let hash = Blake2b::finish_hash(&mut hash_state, &ctx);
         assert_eq!(
-            hex::encode(hash.raw()),
+            hash.to_string(),
             "a78ebb4446b81ff6bb63f5767e6fefaa9f9d994c1c7384398c990ce48484f9f4399bcb9009221fcaecef66b41d1f1273f707848eb9773d3c0cd5afd3c5fcdf02"
-        )
+        );
+        assert_eq!(hash, Blake2b::digest_message(&ctx, STREAM_TEXT.concat().as_bytes()));
+        assert_eq!(hash.as_ref(), &hash.raw()[..]);
     }
 
     #[test]
     fn blake2b_outsize_test() {
         // example from pyblake2 documentation: https://pythonhosted.org/pyblake2/examples.html
         assert_eq!(
-            hex::encode(
-                Blake2b::digest_message(
-                    &Blake2bContext { output_len: 10, key: vec![] },
-                    &vec![],
-                ).raw()
-            ),
+            Blake2b::digest_message(
+                &Blake2bContext::new(10).unwrap(),
+                &[],
+            ).to_string(),
             "6fa1d8fcfd719046d762"
         );
 
         assert_eq!(
-            hex::encode(
-                Blake2b::digest_message(
-                    &Blake2bContext { output_len: 11, key: vec![] },
-                    &vec![],
-                ).raw()
-            ),
+            Blake2b::digest_message(
+                &Blake2bContext::new(11).unwrap(),
+                &[],
+            ).to_string(),
             "eb6ec15daf9546254f0809"
         );
     }
@@ -115,74 +155,67 @@ pub(crate) mod blake2_tests {
     fn blake2b_keyed_hash_test() {
         // example from pyblake2 documentation: https://pythonhosted.org/pyblake2/examples.html
         assert_eq!(
-            hex::encode(
-                Blake2b::digest_message(
-                    &Blake2bContext { output_len: 16, key: "pseudorandom key".as_bytes().to_vec() },
-                    &"message data".as_bytes(),
-                ).raw()
-            ),
+            Blake2b::digest_message(
+                &Blake2bContext::new_keyed(16, "pseudorandom key".as_bytes()).unwrap(),
+                "message data".as_bytes(),
+            ).to_string(),
             "3d363ff7401e02026f4a4687d4863ced"
         );
     }
 
     #[test]
     fn blake2s_tests() {
-        let ctx = Blake2sContext {
-            output_len: 32,
-            key: vec![],
-        };
+        let ctx = Blake2sContext::new(32).unwrap();
 
         assert_eq!(
-            hex::encode(&Blake2s::digest_message(&ctx, EMPTY_MESSAGE.as_bytes()).raw()),
+            Blake2s::digest_message(&ctx, EMPTY_MESSAGE.as_bytes()).to_string(),
             "69217a3079908094e11121d042354a7c1f55b6482ca1a51e1b250dfd1ed0eef9"
         );
 
         assert_eq!(
-            hex::encode(&Blake2s::digest_message(&ctx, SOME_TEXT.as_bytes()).raw()),
+            Blake2s::digest_message(&ctx, SOME_TEXT.as_bytes()).to_string(),
             "bc4885e85b2a36cbea9cdc4f11c2d532a3b551a5f2fb4516ac7d7d526f6abf9b"
         );
 
         assert_eq!(
-            hex::encode(&Blake2s::digest_message(&ctx, LONG_TEXT.as_bytes()).raw()),
+            Blake2s::digest_message(&ctx, LONG_TEXT.as_bytes()).to_string(),
             "08e326307e3a5ec26308b887f4b4bffc45882f4e771768afc9f5b9ba812f6cb1"
         );
     }
 
     #[test]
     fn blake2s_stream_test() {
-        let ctx = Blake2sContext { output_len: 32, key: vec![] };
+        let ctx = Blake2sContext::new(32).unwrap();
         let mut hash_state = Blake2s::init_hash(&ctx);
-        Blake2s::update_hash(&mut hash_state, &ctx, STREAM_TEXT[0].as_bytes());
-        Blake2s::update_hash(&mut hash_state, &ctx, STREAM_TEXT[1].as_bytes());
-        Blake2s::update_hash(&mut hash_state, &ctx, STREAM_TEXT[2].as_bytes());
+        Blake2s::update_hash(&mut hash_state, STREAM_TEXT[0].as_bytes());
+        Blake2s::update_hash(&mut hash_state, STREAM_TEXT[1].as_bytes());
+        Blake2s::update_hash(&mut hash_state, STREAM_TEXT[2].as_bytes());
 
         let hash = Blake2s::finish_hash(&mut hash_state, &ctx);
         assert_eq!(
-            hex::encode(hash.raw()),
+            hash.to_string(),
             "47491576f075956e2e0420ae35e6b2258c24d22e70c2afecd9191a0d9eee39ee"
-        )
+        );
+        assert_eq!(hash, Blake2s::digest_message(&ctx, STREAM_TEXT.concat().as_bytes()));
+        assert_eq!(hash.as_ref(), &hash.raw()[..]);
     }
 
     #[test]
     fn blake2s_outsize_test() {
         // example from pyblake2 documentation: https://pythonhosted.org/pyblake2/examples.html
         assert_eq!(
-            hex::encode(
-                Blake2s::digest_message(
-                    &Blake2sContext { output_len: 10, key: vec![] },
-                    &vec![],
-                ).raw()
-            ),
+            Blake2s::digest_message(
+                &Blake2sContext::new(10).unwrap(),
+                &[],
+            ).to_string(),
             "1bf21a98c78a1c376ae9"
         );
 
         assert_eq!(
-            hex::encode(
-                Blake2s::digest_message(
-                    &Blake2sContext { output_len: 11, key: vec![] },
-                    &vec![],
-                ).raw()
-            ),
+            Blake2s::digest_message(
+                &Blake2sContext::new(11).unwrap(),
+                &[],
+            ).to_string(),
             "567004bf96e4a25773ebf4"
         );
     }
@@ -191,13 +224,338 @@ pub(crate) mod blake2_tests {
     fn blake2s_keyed_hash_test() {
         // example from pyblake2 documentation: https://pythonhosted.org/pyblake2/examples.html
         assert_eq!(
-            hex::encode(
-                Blake2s::digest_message(
-                    &Blake2sContext { output_len: 16, key: "pseudorandom key".as_bytes().to_vec() },
-                    &"message data".as_bytes(),
-                ).raw()
-            ),
+            Blake2s::digest_message(
+                &Blake2sContext::new_keyed(16, "pseudorandom key".as_bytes()).unwrap(),
+                "message data".as_bytes(),
+            ).to_string(),
             "ea0078ad4910a6e5c411bc62dc84a8c7"
         );
     }
-}
\ No newline at end of file
+
+    /// Keyed hashes of an empty message, a message of exactly one block and a message of multiple blocks. Vectors
+    /// were computed with pyblake2.
+    #[test]
+    fn blake2b_keyed_block_boundaries_test() {
+        let ctx = Blake2bContext::new_keyed(32, "pseudorandom key".as_bytes()).unwrap();
+        let message: Vec<u8> = (0..300).map(|i| (i * 31 % 251) as u8).collect();
+
+        assert_eq!(
+            Blake2b::digest_message(&ctx, &[]).to_string(),
+            "17bf1708f48269da6428dafff9e1aaa0e7e2ef2fafebb9b5597776ac1a3d4c89"
+        );
+        assert_eq!(
+            Blake2b::digest_message(&ctx, &message[..128]).to_string(),
+            "e5d70407dd797693263319b469749233b26fb720156bea5a457c3432e32b8f27"
+        );
+        assert_eq!(
+            Blake2b::digest_message(&ctx, &message).to_string(),
+            "ca597f6326546c22a0decfd842e797d69b1491c2812a1798dd8a32efdc911acb"
+        );
+    }
+
+    /// Keyed hashes of an empty message, a message of exactly one block and a message of multiple blocks. Vectors
+    /// were computed with pyblake2.
+    #[test]
+    fn blake2s_keyed_block_boundaries_test() {
+        let ctx = Blake2sContext::new_keyed(32, "pseudorandom key".as_bytes()).unwrap();
+        let message: Vec<u8> = (0..300).map(|i| (i * 31 % 251) as u8).collect();
+
+        assert_eq!(
+            Blake2s::digest_message(&ctx, &[]).to_string(),
+            "42e8e81816673011d6651e1e5c0d8c6dfd6414b0d6e1e5395a2294519de345b1"
+        );
+        assert_eq!(
+            Blake2s::digest_message(&ctx, &message[..64]).to_string(),
+            "f8e6c481f659b809295e0e960518cf0d175b81dc995b419d2345dca4cd00dcf4"
+        );
+        assert_eq!(
+            Blake2s::digest_message(&ctx, &message).to_string(),
+            "d02a254142c78fddc55b476985bd9f86301c602384616b9085f351ece9fc9e8e"
+        );
+    }
+
+    /// Hash messages of all lengths up to `max_length`, split into two updates at every possible point, and compare
+    /// the results with the one-shot digest.
+    fn assert_split_updates_consistent<H>(ctx: &H::Context, max_length: usize)
+        where H: HashFunction
+    {
+        let message: Vec<u8> = (0..max_length).map(|i| (i * 31 % 251) as u8).collect();
+
+        for length in 0..=max_length {
+            let expected = H::digest_message(ctx, &message[..length]).raw();
+
+            for split in 0..=length {
+                let mut hash_state = H::init_hash(ctx);
+                H::update_hash(&mut hash_state, &message[..split]);
+                H::update_hash(&mut hash_state, &message[split..length]);
+
+                assert_eq!(
+                    H::finish_hash(&mut hash_state, ctx).raw(),
+                    expected,
+                    "length {}, split at {}", length, split
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn blake2b_split_test() {
+        assert_split_updates_consistent::<Blake2b>(&Blake2bContext::new(64).unwrap(), 300);
+        assert_split_updates_consistent::<Blake2b>(
+            &Blake2bContext::new_keyed(32, "pseudorandom key".as_bytes()).unwrap(), 300);
+    }
+
+    #[test]
+    fn blake2s_split_test() {
+        assert_split_updates_consistent::<Blake2s>(&Blake2sContext::new(32).unwrap(), 300);
+        assert_split_updates_consistent::<Blake2s>(
+            &Blake2sContext::new_keyed(16, "pseudorandom key".as_bytes()).unwrap(), 300);
+    }
+
+    #[test]
+    fn blake2_peek_test() {
+        assert_peek_consistent::<Blake2b>(
+            &Blake2bContext::new_keyed(32, "pseudorandom key".as_bytes()).unwrap());
+        assert_peek_consistent::<Blake2s>(
+            &Blake2sContext::new_keyed(16, "pseudorandom key".as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn blake2b_reset_test() {
+        assert_reset_consistent::<Blake2b>(&Blake2bContext::new(64).unwrap());
+        assert_reset_consistent::<Blake2b>(
+            &Blake2bContext::new_keyed(32, "pseudorandom key".as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn blake2s_reset_test() {
+        assert_reset_consistent::<Blake2s>(&Blake2sContext::new(32).unwrap());
+        assert_reset_consistent::<Blake2s>(
+            &Blake2sContext::new_keyed(16, "pseudorandom key".as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn blake2b_context_test() {
+        for (output_len, valid) in [(0, false), (1, true), (64, true), (65, false)].iter() {
+            assert_eq!(Blake2bContext::new(*output_len).is_ok(), *valid);
+        }
+        assert_eq!(
+            Blake2bContext::new(65).err(),
+            Some(Blake2ParamError::InvalidOutputLength { output_len: 65, max: 64 })
+        );
+
+        for (key_len, valid) in [(0, true), (1, true), (64, true), (65, false)].iter() {
+            assert_eq!(Blake2bContext::new_keyed(32, &vec![0x42; *key_len]).is_ok(), *valid);
+        }
+        assert_eq!(
+            Blake2bContext::new_keyed(32, &[0; 65]).err(),
+            Some(Blake2ParamError::KeyTooLong { key_len: 65, max: 64 })
+        );
+
+        let ctx = Blake2bContext::new_keyed(64, b"key").unwrap();
+        assert_eq!(ctx.output_len(), 64);
+        assert_eq!(ctx.key(), b"key");
+    }
+
+    #[test]
+    fn blake2s_context_test() {
+        for (output_len, valid) in [(0, false), (1, true), (32, true), (33, false)].iter() {
+            assert_eq!(Blake2sContext::new(*output_len).is_ok(), *valid);
+        }
+        assert_eq!(
+            Blake2sContext::new(0).err(),
+            Some(Blake2ParamError::InvalidOutputLength { output_len: 0, max: 32 })
+        );
+
+        for (key_len, valid) in [(0, true), (1, true), (32, true), (33, false)].iter() {
+            assert_eq!(Blake2sContext::new_keyed(16, &vec![0x42; *key_len]).is_ok(), *valid);
+        }
+        assert_eq!(
+            Blake2sContext::new_keyed(16, &[0; 33]).err(),
+            Some(Blake2ParamError::KeyTooLong { key_len: 33, max: 32 })
+        );
+
+        let ctx = Blake2sContext::new(20).unwrap();
+        assert_eq!(ctx.output_len(), 20);
+        assert!(ctx.key().is_empty());
+    }
+
+    #[test]
+    fn blake2_default_context_test() {
+        assert_eq!(
+            Blake2b::digest(b"abc").raw(),
+            Blake2b::digest_message(&Blake2bContext::new(64).unwrap(), b"abc").raw()
+        );
+        assert_eq!(
+            Blake2s::digest(b"abc").raw(),
+            Blake2s::digest_message(&Blake2sContext::new(32).unwrap(), b"abc").raw()
+        );
+
+        let mut hasher = Blake2b::hasher();
+        hasher.update(b"a");
+        hasher.update(b"bc");
+        assert_eq!(hasher.finalize().raw(), Blake2b::digest(b"abc").raw());
+    }
+
+    #[test]
+    fn blake2_raw_byte_order_test() {
+        // the initial states of unkeyed hashes with full output length, whose words are serialized little endian
+        assert_eq!(
+            hex::encode(Blake2b::init_hash(&Blake2bContext::new(64).unwrap()).raw()),
+            "48c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5\
+             d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b"
+        );
+        assert_eq!(
+            hex::encode(Blake2s::init_hash(&Blake2sContext::new(32).unwrap()).raw()),
+            "47e6086b85ae67bb72f36e3c3af54fa57f520e518c68059babd9831f19cde05b"
+        );
+
+        // truncated outputs are the prefix of the serialized final state
+        let ctx = Blake2bContext::new(20).unwrap();
+        let mut state = Blake2b::init_hash(&ctx);
+        Blake2b::update_hash(&mut state, SOME_TEXT.as_bytes());
+        let digest = Blake2b::finish_hash(&mut state, &ctx);
+        assert_eq!(digest.raw()[..], state.raw()[..20]);
+
+        let ctx = Blake2sContext::new(20).unwrap();
+        let mut state = Blake2s::init_hash(&ctx);
+        Blake2s::update_hash(&mut state, SOME_TEXT.as_bytes());
+        let digest = Blake2s::finish_hash(&mut state, &ctx);
+        assert_eq!(digest.raw()[..], state.raw()[..20]);
+    }
+
+    #[test]
+    fn blake2_hex_round_trip_test() {
+        let blake2b = Blake2b::digest(SOME_TEXT.as_bytes());
+        let parsed: Blake2bHash = blake2b.to_string().to_uppercase().parse().unwrap();
+        assert_eq!(parsed.hash, blake2b.hash);
+        assert_eq!(format!("{:X}", parsed), blake2b.to_string().to_uppercase());
+        assert_eq!("".parse::<Blake2bHash>().unwrap_err(), ParseHexError::InvalidLength { length: 0 });
+        assert_eq!("ab".repeat(65).parse::<Blake2bHash>().unwrap_err(), ParseHexError::InvalidLength { length: 65 });
+
+        let blake2s = Blake2s::digest(SOME_TEXT.as_bytes());
+        let parsed: Blake2sHash = blake2s.to_string().to_uppercase().parse().unwrap();
+        assert_eq!(parsed.hash, blake2s.hash);
+        assert_eq!("Ab".repeat(20).parse::<Blake2sHash>().unwrap().hash, vec![0xAB; 20]);
+        assert_eq!("ab".repeat(33).parse::<Blake2sHash>().unwrap_err(), ParseHexError::InvalidLength { length: 33 });
+
+        let blake2xb = Blake2Xb::digest_message(&Blake2XbContext { output_len: 100, key: Redacted::default() }, &[]);
+        let parsed: Blake2XbHash = blake2xb.to_string().to_uppercase().parse().unwrap();
+        assert_eq!(parsed.hash, blake2xb.hash);
+        assert_eq!("".parse::<Blake2XbHash>().unwrap_err(), ParseHexError::InvalidLength { length: 0 });
+    }
+
+    #[test]
+    fn blake2_base64_test() {
+        // RFC 4648 test vectors, without padding
+        assert_eq!(Blake2bHash { hash: b"f".to_vec() }.to_base64(), "Zg");
+        assert_eq!(Blake2bHash { hash: b"fo".to_vec() }.to_base64(), "Zm8");
+        assert_eq!(Blake2bHash { hash: b"foobar".to_vec() }.to_base64(), "Zm9vYmFy");
+        assert_eq!(
+            Blake2s::digest_message(&Blake2sContext::new(32).unwrap(), &[]).to_base64(),
+            "aSF6MHmQgJThESHQQjVKfB9VtkgsoaUeGyUN/R7Q7vk"
+        );
+    }
+
+    #[test]
+    fn blake2_context_debug_test() {
+        let key = [0xA5u8, 0xC3, 0xE7, 0xF1, 0x9B, 0xD2, 0xB8, 0xEE];
+        let debug_outputs = [
+            format!("{:?}", Blake2bContext::new_keyed(64, &key).unwrap()),
+            format!("{:?}", Blake2sContext::new_keyed(32, &key).unwrap()),
+            format!("{:?}", Blake2XbContext { output_len: 100, key: key.to_vec().into() }),
+        ];
+
+        for debug in debug_outputs.iter() {
+            assert!(debug.contains("key: <redacted>"), "{}", debug);
+            assert!(!debug.contains(&hex::encode(key)));
+            assert!(!debug.contains(&format!("{:?}", key)));
+            for byte in key.iter() {
+                assert!(!debug.contains(&format!("{:x}", byte)), "{}", debug);
+                assert!(!debug.contains(&format!("{}", byte)), "{}", debug);
+            }
+        }
+    }
+
+    #[test]
+    fn blake2xb_tests() {
+        // test vectors in the layout of the Blake2X known answer tests: the input is 0x00..0xff and the key 0x00..0x3f
+        let input = (0..=255).collect::<Vec<u8>>();
+        let key = (0..64).collect::<Vec<u8>>();
+        let digest = |output_len| {
+            Blake2Xb::digest_message(&Blake2XbContext { output_len, key: key.clone().into() }, &input).to_string()
+        };
+
+        assert_eq!(digest(1), "64");
+        assert_eq!(
+            digest(64),
+            "4324561d76c370ef35ac36a4adf8f3773a50d86504bd284f71f7ce9e2bc4c1f1d34a7fb2d67561d101955d448b67577eb30dfee96a95c7f921ef53e20be8bc44"
+        );
+        assert_eq!(
+            digest(65),
+            "78f0ed6e220b3da3cc9381563b2f72c8dc830cb0f39a48c6ae479a6a78dcfa94002631dec467e9e9b47cc8f0887eb680e340aec3ec009d4a33d241533c76c8ca8c"
+        );
+        assert_eq!(
+            digest(130),
+            "83bed0d556798f2b419f7056e6d3ffada06e939b95a688d0ec8c6ac5ea45ab73a4cf01043e0a170766e21395f27ab4b78c435f5f0dfe6e93ab80df38610e41158429ddf20296f53a06a017723359fe22dc08b5da33f0800a4fe50118e8d7eab2f83a85cd764bf8a166903bd0e9dcfeeceba44ff4ca4439846458d31ea2bb564645d1"
+        );
+
+        assert_eq!(
+            Blake2Xb::digest_message(&Blake2XbContext { output_len: 32, key: Redacted::default() }, &[]).to_string(),
+            "1632e145f2e19726b6a9ef54bda8f2493d1147c00ab5b749572f27073e84ed07"
+        );
+    }
+
+    #[test]
+    fn blake2xb_output_length_test() {
+        // the output length is part of the root parameter block, so a shorter output is not a prefix of a longer one
+        let short = Blake2Xb::digest_message(&Blake2XbContext { output_len: 100, key: Redacted::default() }, SOME_TEXT.as_bytes());
+        let long = Blake2Xb::digest_message(&Blake2XbContext { output_len: 200, key: Redacted::default() }, SOME_TEXT.as_bytes());
+
+        assert_eq!(short.raw().len(), 100);
+        assert_eq!(long.raw().len(), 200);
+        assert_ne!(short.raw()[..], long.raw()[..100]);
+
+        assert_split_updates_consistent::<Blake2Xb>(&Blake2XbContext { output_len: 100, key: Redacted::default() }, 300);
+    }
+
+    #[test]
+    fn blake2b_mix_test() {
+        // the first round of compressing "abc" with Blake2b-512, from the example in RFC 7693, appendix A
+        let mut message = [0_u64; 16];
+        message[0] = 0x636261;
+
+        let mut vector = [0_u64; 16];
+        vector[..8].copy_from_slice(&INITIAL_2B);
+        vector[8..].copy_from_slice(&INITIAL_2B);
+        vector[0] ^= 0x01010040;
+        vector[12] ^= 3;
+        vector[14] = !vector[14];
+
+        let permutation = &SIGMA[0];
+        blake2_mix::<u64, 32, 24, 16, 63>(&mut vector, 0, 4, 8, 12, message[permutation[0]], message[permutation[1]]);
+        assert_eq!(
+            [vector[0], vector[4], vector[8], vector[12]],
+            [0xF0C9AA0F86491DEA, 0xB7DEFC3D0277E11F, 0xEE5E0F39647A9FFF, 0x7772FC2886A76C5F]
+        );
+
+        for (column, (a, b, c, d)) in [(1, 5, 9, 13), (2, 6, 10, 14), (3, 7, 11, 15), (0, 5, 10, 15), (1, 6, 11, 12),
+            (2, 7, 8, 13), (3, 4, 9, 14)].iter().enumerate() {
+            let (x, y) = (message[permutation[2 * column + 2]], message[permutation[2 * column + 3]]);
+            blake2_mix::<u64, 32, 24, 16, 63>(&mut vector, *a, *b, *c, *d, x, y);
+        }
+
+        assert_eq!(vector, [
+            0x86B7C1568029BB79, 0xC12CBCC809FF59F3, 0xC6A5214CC0EACA8E, 0x0C87CD524C14CC5D,
+            0x44EE6039BD86A9F7, 0xA447C850AA694A7E, 0xDE080F1BB1C0F84B, 0x595CB8A9A1ACA66C,
+            0xBEC3AE837EAC4887, 0x6267FC79DF9D6AD1, 0xFA87B01273FA6DBE, 0x521A715C63E08D8A,
+            0xE02D0975B8D37A83, 0x1C7B754F08B7D193, 0x8F885A76B6E578FE, 0x2318A24E2140FC64,
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "blake2xb cannot produce 0 bytes of output")]
+    fn blake2xb_empty_output_test() {
+        Blake2Xb::init_hash(&Blake2XbContext { output_len: 0, key: Redacted::default() });
+    }
+}
@@ -0,0 +1,95 @@
+//! Reads and writes of wide integers at an offset of a byte buffer, with an explicit byte order. Hash functions use
+//! them to append message lengths in their padding and to read message words from blocks, instead of computing the
+//! index of every byte by hand.
+
+use std::convert::TryInto;
+use std::mem::size_of;
+
+/// Write `value` in little endian byte order to the `size_of::<u64>()` bytes of `buffer` starting at `offset`.
+pub(crate) fn write_u64_le(buffer: &mut [u8], offset: usize, value: u64) {
+    debug_assert!(offset + size_of::<u64>() <= buffer.len(), "write exceeds the buffer");
+    buffer[offset..offset + size_of::<u64>()].copy_from_slice(&value.to_le_bytes());
+}
+
+/// Write `value` in big endian byte order to the `size_of::<u64>()` bytes of `buffer` starting at `offset`.
+pub(crate) fn write_u64_be(buffer: &mut [u8], offset: usize, value: u64) {
+    debug_assert!(offset + size_of::<u64>() <= buffer.len(), "write exceeds the buffer");
+    buffer[offset..offset + size_of::<u64>()].copy_from_slice(&value.to_be_bytes());
+}
+
+/// Write `value` in big endian byte order to the `size_of::<u128>()` bytes of `buffer` starting at `offset`.
+pub(crate) fn write_u128_be(buffer: &mut [u8], offset: usize, value: u128) {
+    debug_assert!(offset + size_of::<u128>() <= buffer.len(), "write exceeds the buffer");
+    buffer[offset..offset + size_of::<u128>()].copy_from_slice(&value.to_be_bytes());
+}
+
+/// Read a little endian `u32` from the `size_of::<u32>()` bytes of `buffer` starting at `offset`.
+pub(crate) fn read_u32_le(buffer: &[u8], offset: usize) -> u32 {
+    debug_assert!(offset + size_of::<u32>() <= buffer.len(), "read exceeds the buffer");
+    u32::from_le_bytes(buffer[offset..offset + size_of::<u32>()].try_into().unwrap())
+}
+
+/// Read a big endian `u32` from the `size_of::<u32>()` bytes of `buffer` starting at `offset`.
+pub(crate) fn read_u32_be(buffer: &[u8], offset: usize) -> u32 {
+    debug_assert!(offset + size_of::<u32>() <= buffer.len(), "read exceeds the buffer");
+    u32::from_be_bytes(buffer[offset..offset + size_of::<u32>()].try_into().unwrap())
+}
+
+/// Read a little endian `u64` from the `size_of::<u64>()` bytes of `buffer` starting at `offset`.
+pub(crate) fn read_u64_le(buffer: &[u8], offset: usize) -> u64 {
+    debug_assert!(offset + size_of::<u64>() <= buffer.len(), "read exceeds the buffer");
+    u64::from_le_bytes(buffer[offset..offset + size_of::<u64>()].try_into().unwrap())
+}
+
+/// Read a big endian `u64` from the `size_of::<u64>()` bytes of `buffer` starting at `offset`.
+pub(crate) fn read_u64_be(buffer: &[u8], offset: usize) -> u64 {
+    debug_assert!(offset + size_of::<u64>() <= buffer.len(), "read exceeds the buffer");
+    u64::from_be_bytes(buffer[offset..offset + size_of::<u64>()].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_u64() {
+        let mut buffer = [0_u8; 64];
+        write_u64_le(&mut buffer, 56, 0x0102030405060708);
+        assert_eq!(buffer[56..], [8, 7, 6, 5, 4, 3, 2, 1]);
+        assert!(buffer[..56].iter().all(|byte| *byte == 0));
+
+        write_u64_be(&mut buffer, 0, 0x0102030405060708);
+        assert_eq!(buffer[..8], [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(read_u64_be(&buffer, 0), 0x0102030405060708);
+        assert_eq!(read_u64_le(&buffer, 56), 0x0102030405060708);
+    }
+
+    #[test]
+    fn test_write_u128() {
+        let mut buffer = [0_u8; 16];
+        write_u128_be(&mut buffer, 0, 0x0102);
+        assert_eq!(buffer[14..], [1, 2]);
+        assert!(buffer[..14].iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn test_read_u32() {
+        let buffer = [0x78, 0x56, 0x34, 0x12, 0xFF];
+        assert_eq!(read_u32_le(&buffer, 0), 0x12345678);
+        assert_eq!(read_u32_be(&buffer, 0), 0x78563412);
+        assert_eq!(read_u32_le(&buffer, 1), 0xFF123456);
+        assert_eq!(read_u32_be(&buffer, 1), 0x563412FF);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_write_exceeds_buffer() {
+        write_u64_be(&mut [0_u8; 64], 57, 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_read_exceeds_buffer() {
+        read_u32_le(&[0_u8; 4], 1);
+    }
+}
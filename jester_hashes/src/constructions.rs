@@ -0,0 +1,225 @@
+//! Worked examples of constructions that are built from the bare block functions `md5::md5_block` and
+//! `sha1::sha1_block` instead of the full hash functions. They show how to experiment with hash-based designs
+//! without touching the internals of the hash implementations.
+//!
+//! The compression functions of MD5 and SHA-1 are Davies–Meyer constructions themselves: a block cipher `E` is keyed
+//! with the message block `m` and encrypts the chaining value `h`, and the result is added to `h`, i.e.
+//! `f(h, m) = E_m(h) + h`. Both functions are broken in terms of collision resistance, so these constructions are
+//! meant for study and testing, not for protecting data.
+
+use crate::md5::{self, md5_block};
+use crate::sha1::{self, sha1_block};
+
+/// The block length of MD5 and SHA-1 in bytes
+const BLOCK_LENGTH_BYTES: usize = 64;
+
+/// A compression function that maps a chaining value and a 64 byte block to a new chaining value, together with the
+/// conventions of its hash function.
+pub trait BlockFunction {
+    /// The words of a chaining value
+    type ChainingValue: Copy + Eq + AsRef<[u32]> + AsMut<[u32]>;
+
+    /// The initial chaining value of the hash function
+    const INITIAL: Self::ChainingValue;
+
+    /// Compress `block` into the chaining value `iv`.
+    fn compress(iv: Self::ChainingValue, block: &[u8; BLOCK_LENGTH_BYTES]) -> Self::ChainingValue;
+
+    /// Encode the length of a message in bits as it is appended by the padding of the hash function.
+    fn encode_length(bits: u64) -> [u8; 8];
+}
+
+/// The compression function of MD5, see `md5::md5_block`
+pub struct MD5Block;
+
+impl BlockFunction for MD5Block {
+    type ChainingValue = [u32; 4];
+
+    const INITIAL: Self::ChainingValue = [md5::INITIAL.0, md5::INITIAL.1, md5::INITIAL.2, md5::INITIAL.3];
+
+    fn compress(iv: Self::ChainingValue, block: &[u8; BLOCK_LENGTH_BYTES]) -> Self::ChainingValue {
+        md5_block(iv, block)
+    }
+
+    fn encode_length(bits: u64) -> [u8; 8] {
+        bits.to_le_bytes()
+    }
+}
+
+/// The compression function of SHA-1, see `sha1::sha1_block`
+pub struct SHA1Block;
+
+impl BlockFunction for SHA1Block {
+    type ChainingValue = [u32; 5];
+
+    const INITIAL: Self::ChainingValue =
+        [sha1::INITIAL.a, sha1::INITIAL.b, sha1::INITIAL.c, sha1::INITIAL.d, sha1::INITIAL.e];
+
+    fn compress(iv: Self::ChainingValue, block: &[u8; BLOCK_LENGTH_BYTES]) -> Self::ChainingValue {
+        sha1_block(iv, block)
+    }
+
+    fn encode_length(bits: u64) -> [u8; 8] {
+        bits.to_be_bytes()
+    }
+}
+
+/// A pseudo random function on inputs of exactly one chaining value, keyed with a 64 byte `key`. The key takes the
+/// place of the message block, so the compression function computes `E_key(input) + input`, which is the Davies–Meyer
+/// feed-forward of the block cipher `E` keyed with `key`. If `E` is a pseudo random permutation, the result is a
+/// pseudo random function up to the birthday bound of the chaining value.
+///
+/// # Type Parameters
+/// - `F` the compression function
+pub fn davies_meyer_prf<F>(key: &[u8; BLOCK_LENGTH_BYTES], input: F::ChainingValue) -> F::ChainingValue
+    where F: BlockFunction
+{
+    F::compress(input, key)
+}
+
+/// A double length hash in the style of MDC-2. Two chains start at the initial chaining value and its bitwise
+/// complement, both compress every block of the padded message, and afterwards they exchange the second halves of
+/// their chaining values. Without the exchange, the chains would be independent and a collision of one chain could
+/// be searched for on its own.
+///
+/// The message is padded like MD5 and SHA-1 messages, with a single one bit, zeros, and the message length in bits.
+///
+/// # Type Parameters
+/// - `F` the compression function
+///
+/// # Returns
+/// The final chaining values of both chains, which together are the hash
+pub fn double_length_hash<F>(message: &[u8]) -> (F::ChainingValue, F::ChainingValue)
+    where F: BlockFunction
+{
+    let mut left = F::INITIAL;
+    let mut right = F::INITIAL;
+    for word in right.as_mut() {
+        *word = !*word;
+    }
+
+    for_each_padded_block::<F, _>(message, |block| {
+        left = F::compress(left, block);
+        right = F::compress(right, block);
+
+        let words = left.as_ref().len();
+        for i in words / 2..words {
+            std::mem::swap(&mut left.as_mut()[i], &mut right.as_mut()[i]);
+        }
+    });
+
+    (left, right)
+}
+
+/// Call `compress` with every block of the padded `message`, without allocating.
+fn for_each_padded_block<F, C>(message: &[u8], mut compress: C)
+    where F: BlockFunction,
+          C: FnMut(&[u8; BLOCK_LENGTH_BYTES])
+{
+    let mut blocks = message.chunks_exact(BLOCK_LENGTH_BYTES);
+    for block in &mut blocks {
+        let mut full_block = [0; BLOCK_LENGTH_BYTES];
+        full_block.copy_from_slice(block);
+        compress(&full_block);
+    }
+
+    // the remainder, the one bit and the length fit into one block, if at least nine bytes are left
+    let remainder = blocks.remainder();
+    let mut last_blocks = [0; 2 * BLOCK_LENGTH_BYTES];
+    last_blocks[..remainder.len()].copy_from_slice(remainder);
+    last_blocks[remainder.len()] = 0x80;
+
+    let padded_length = if remainder.len() + 9 <= BLOCK_LENGTH_BYTES {
+        BLOCK_LENGTH_BYTES
+    } else {
+        2 * BLOCK_LENGTH_BYTES
+    };
+    last_blocks[padded_length - 8..padded_length].copy_from_slice(&F::encode_length(message.len() as u64 * 8));
+
+    for block in last_blocks[..padded_length].chunks_exact(BLOCK_LENGTH_BYTES) {
+        let mut full_block = [0; BLOCK_LENGTH_BYTES];
+        full_block.copy_from_slice(block);
+        compress(&full_block);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::fmt::Debug;
+    use std::hash::Hash;
+
+    use crate::md5::MD5Hash;
+    use crate::sha1::SHA1Hash;
+    use crate::{HashFunction, HashValue};
+
+    use super::*;
+
+    /// Messages around the block boundaries and the boundary of the padding
+    fn corpus() -> Vec<Vec<u8>> {
+        let mut corpus = vec![b"".to_vec(), b"a".to_vec(), b"abc".to_vec(), b"abd".to_vec()];
+        for &length in &[55, 56, 63, 64, 65, 119, 120, 128] {
+            corpus.push(vec![0x5A; length]);
+            corpus.push(vec![0; length]);
+        }
+        corpus
+    }
+
+    /// Fold the compression function over the padded message, which is the plain Merkle–Damgård hash
+    fn merkle_damgard<F>(message: &[u8]) -> Vec<u8>
+        where F: BlockFunction
+    {
+        let mut state = F::INITIAL;
+        for_each_padded_block::<F, _>(message, |block| state = F::compress(state, block));
+        state.as_ref().iter().flat_map(|word| word.to_be_bytes().to_vec()).collect()
+    }
+
+    fn assert_distinct<T>(values: &[T])
+        where T: Eq + Hash + Debug
+    {
+        assert_eq!(values.iter().collect::<HashSet<_>>().len(), values.len(), "{:?} contains duplicates", values);
+    }
+
+    #[test]
+    fn test_padding_matches_hash_functions() {
+        for message in corpus() {
+            let md5 = MD5Hash::digest_message(&(), &message);
+            let md5_words = [md5.0, md5.1, md5.2, md5.3];
+            let md5_bytes = md5_words.iter().flat_map(|word| word.to_be_bytes().to_vec()).collect::<Vec<_>>();
+            assert_eq!(merkle_damgard::<MD5Block>(&message), md5_bytes);
+
+            assert_eq!(merkle_damgard::<SHA1Block>(&message), SHA1Hash::digest_message(&(), &message).raw());
+        }
+    }
+
+    #[test]
+    fn test_davies_meyer_prf() {
+        let keys = [[0; 64], [1; 64], [0x5A; 64]];
+        let inputs = [SHA1Block::INITIAL, [0; 5], [1, 2, 3, 4, 5]];
+
+        let outputs = keys.iter()
+            .flat_map(|key| inputs.iter().map(move |input| davies_meyer_prf::<SHA1Block>(key, *input)))
+            .collect::<Vec<_>>();
+        assert_distinct(&outputs);
+
+        // the PRF is deterministic
+        assert_eq!(davies_meyer_prf::<SHA1Block>(&keys[2], inputs[2]), outputs[8]);
+        assert_eq!(davies_meyer_prf::<MD5Block>(&keys[1], [0; 4]), davies_meyer_prf::<MD5Block>(&keys[1], [0; 4]));
+        assert_ne!(davies_meyer_prf::<MD5Block>(&keys[1], [0; 4]), davies_meyer_prf::<MD5Block>(&keys[0], [0; 4]));
+    }
+
+    #[test]
+    fn test_double_length_hash() {
+        let corpus = corpus();
+
+        let md5_hashes = corpus.iter().map(|message| double_length_hash::<MD5Block>(message)).collect::<Vec<_>>();
+        let sha1_hashes = corpus.iter().map(|message| double_length_hash::<SHA1Block>(message)).collect::<Vec<_>>();
+        assert_distinct(&md5_hashes);
+        assert_distinct(&sha1_hashes);
+
+        for (message, (left, right)) in corpus.iter().zip(&sha1_hashes) {
+            assert_eq!(double_length_hash::<SHA1Block>(message), (*left, *right));
+            assert_ne!(left, right);
+        }
+    }
+}
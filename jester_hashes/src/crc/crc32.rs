@@ -0,0 +1,127 @@
+//! CRC-32 as specified by IEEE 802.3 and used by Ethernet, zip and png.
+
+use std::str::FromStr;
+
+use crate::crc::CrcTables;
+use crate::hex_format::decode_hex_array;
+use crate::{HashFunction, HashValue, ParseHexError};
+
+/// The reflected generator polynomial of CRC-32
+pub const POLYNOMIAL: u32 = 0xEDB8_8320;
+
+static TABLES: CrcTables = CrcTables::new(POLYNOMIAL);
+
+/// A finished CRC-32 checksum.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Crc32(pub u32);
+
+/// The state of an unfinished CRC-32 computation. The register does not contain the final xor.
+#[derive(Debug, Copy, Clone)]
+pub struct Crc32State {
+    pub register: u32,
+}
+
+impl HashFunction for Crc32 {
+    type Context = ();
+    type HashState = Crc32State;
+    type HashData = Crc32;
+
+    const MAX_OUTPUT_SIZE: usize = 4;
+
+    fn init_hash(_ctx: &Self::Context) -> Self::HashState {
+        Crc32State { register: !0 }
+    }
+
+    fn update_hash(hash: &mut Self::HashState, input: &[u8]) {
+        hash.register = TABLES.update(hash.register, input);
+    }
+
+    fn finish_hash(hash: &mut Self::HashState, _ctx: &Self::Context) -> Self::HashData {
+        Crc32(!hash.register)
+    }
+
+    fn digest_message(ctx: &Self::Context, input: &[u8]) -> Self::HashData {
+        let mut hash_state = Self::init_hash(ctx);
+        Self::update_hash(&mut hash_state, input);
+        Self::finish_hash(&mut hash_state, ctx)
+    }
+}
+
+impl HashValue for Crc32 {
+    /// The checksum as big endian bytes
+    fn raw(&self) -> Vec<u8> {
+        self.0.to_be_bytes().to_vec()
+    }
+}
+
+impl_hex_format!(Crc32);
+
+impl FromStr for Crc32 {
+    type Err = ParseHexError;
+
+    /// Parse the 8 hex digits of the big endian checksum in either case.
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        Ok(Crc32(u32::from_be_bytes(decode_hex_array(hex)?)))
+    }
+}
+
+/// Compute four bytes, that give a message the CRC-32 `target_crc` when they are appended to it.
+///
+/// # Parameters
+/// - `target_crc` the desired checksum of the forged message
+/// - `current_crc` the checksum of the message before the forged bytes are appended
+/// - `appended_len` how many zero bytes will follow the forged bytes. This allows forging messages where the
+///   forged bytes are followed by a zeroed field of known length, like a placeholder for the checksum itself.
+///
+/// # Returns
+/// The four bytes to append, in message order
+pub fn forge_append(target_crc: u32, current_crc: u32, appended_len: usize) -> [u8; 4] {
+    TABLES.forge_append(target_crc, current_crc, appended_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{LONG_TEXT, STREAM_TEXT};
+
+    #[test]
+    fn test_check_value() {
+        assert_eq!(Crc32::digest_message(&(), b"123456789"), Crc32(0xCBF4_3926));
+        assert_eq!(Crc32::digest_message(&(), b""), Crc32(0));
+        assert_eq!(Crc32::digest_message(&(), b"123456789").raw(), vec![0xCB, 0xF4, 0x39, 0x26]);
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let checksum = Crc32::digest_message(&(), b"123456789");
+        assert_eq!(checksum.to_string(), "cbf43926");
+        assert_eq!(format!("{:X}", checksum), "CBF43926");
+        assert_eq!("CBF43926".parse::<Crc32>().unwrap(), checksum);
+        assert_eq!("cbf439".parse::<Crc32>().unwrap_err(), ParseHexError::InvalidLength { length: 3 });
+    }
+
+    #[test]
+    fn test_stream() {
+        let mut hash_state = Crc32::init_hash(&());
+        for text in STREAM_TEXT.iter() {
+            Crc32::update_hash(&mut hash_state, text.as_bytes());
+        }
+
+        assert_eq!(
+            Crc32::finish_hash(&mut hash_state, &()),
+            Crc32::digest_message(&(), STREAM_TEXT.concat().as_bytes())
+        );
+    }
+
+    #[test]
+    fn test_forge_append() {
+        let message = LONG_TEXT.as_bytes();
+        let Crc32(current_crc) = Crc32::digest_message(&(), message);
+
+        let forged = [message, &forge_append(0xDEAD_BEEF, current_crc, 0)].concat();
+        assert_eq!(Crc32::digest_message(&(), &forged), Crc32(0xDEAD_BEEF));
+
+        let forged = [message, &forge_append(0x1234_5678, current_crc, 13), &[0; 13]].concat();
+        assert_eq!(Crc32::digest_message(&(), &forged), Crc32(0x1234_5678));
+    }
+}
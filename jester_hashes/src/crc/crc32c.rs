@@ -0,0 +1,127 @@
+//! CRC-32C (Castagnoli) as used by iSCSI, SCTP and ext4.
+
+use std::str::FromStr;
+
+use crate::crc::CrcTables;
+use crate::hex_format::decode_hex_array;
+use crate::{HashFunction, HashValue, ParseHexError};
+
+/// The reflected generator polynomial of CRC-32C
+pub const POLYNOMIAL: u32 = 0x82F6_3B78;
+
+static TABLES: CrcTables = CrcTables::new(POLYNOMIAL);
+
+/// A finished CRC-32C checksum.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Crc32c(pub u32);
+
+/// The state of an unfinished CRC-32C computation. The register does not contain the final xor.
+#[derive(Debug, Copy, Clone)]
+pub struct Crc32cState {
+    pub register: u32,
+}
+
+impl HashFunction for Crc32c {
+    type Context = ();
+    type HashState = Crc32cState;
+    type HashData = Crc32c;
+
+    const MAX_OUTPUT_SIZE: usize = 4;
+
+    fn init_hash(_ctx: &Self::Context) -> Self::HashState {
+        Crc32cState { register: !0 }
+    }
+
+    fn update_hash(hash: &mut Self::HashState, input: &[u8]) {
+        hash.register = TABLES.update(hash.register, input);
+    }
+
+    fn finish_hash(hash: &mut Self::HashState, _ctx: &Self::Context) -> Self::HashData {
+        Crc32c(!hash.register)
+    }
+
+    fn digest_message(ctx: &Self::Context, input: &[u8]) -> Self::HashData {
+        let mut hash_state = Self::init_hash(ctx);
+        Self::update_hash(&mut hash_state, input);
+        Self::finish_hash(&mut hash_state, ctx)
+    }
+}
+
+impl HashValue for Crc32c {
+    /// The checksum as big endian bytes
+    fn raw(&self) -> Vec<u8> {
+        self.0.to_be_bytes().to_vec()
+    }
+}
+
+impl_hex_format!(Crc32c);
+
+impl FromStr for Crc32c {
+    type Err = ParseHexError;
+
+    /// Parse the 8 hex digits of the big endian checksum in either case.
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        Ok(Crc32c(u32::from_be_bytes(decode_hex_array(hex)?)))
+    }
+}
+
+/// Compute four bytes, that give a message the CRC-32C `target_crc` when they are appended to it.
+///
+/// # Parameters
+/// - `target_crc` the desired checksum of the forged message
+/// - `current_crc` the checksum of the message before the forged bytes are appended
+/// - `appended_len` how many zero bytes will follow the forged bytes. This allows forging messages where the
+///   forged bytes are followed by a zeroed field of known length, like a placeholder for the checksum itself.
+///
+/// # Returns
+/// The four bytes to append, in message order
+pub fn forge_append(target_crc: u32, current_crc: u32, appended_len: usize) -> [u8; 4] {
+    TABLES.forge_append(target_crc, current_crc, appended_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{LONG_TEXT, STREAM_TEXT};
+
+    #[test]
+    fn test_check_value() {
+        assert_eq!(Crc32c::digest_message(&(), b"123456789"), Crc32c(0xE306_9283));
+        assert_eq!(Crc32c::digest_message(&(), b""), Crc32c(0));
+        assert_eq!(Crc32c::digest_message(&(), b"123456789").raw(), vec![0xE3, 0x06, 0x92, 0x83]);
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let checksum = Crc32c::digest_message(&(), b"123456789");
+        assert_eq!(checksum.to_string(), "e3069283");
+        assert_eq!(format!("{:X}", checksum), "E3069283");
+        assert_eq!("E3069283".parse::<Crc32c>().unwrap(), checksum);
+        assert_eq!("e30692".parse::<Crc32c>().unwrap_err(), ParseHexError::InvalidLength { length: 3 });
+    }
+
+    #[test]
+    fn test_stream() {
+        let mut hash_state = Crc32c::init_hash(&());
+        for text in STREAM_TEXT.iter() {
+            Crc32c::update_hash(&mut hash_state, text.as_bytes());
+        }
+
+        assert_eq!(
+            Crc32c::finish_hash(&mut hash_state, &()),
+            Crc32c::digest_message(&(), STREAM_TEXT.concat().as_bytes())
+        );
+    }
+
+    #[test]
+    fn test_forge_append() {
+        let message = LONG_TEXT.as_bytes();
+        let Crc32c(current_crc) = Crc32c::digest_message(&(), message);
+
+        let forged = [message, &forge_append(0xDEAD_BEEF, current_crc, 0)].concat();
+        assert_eq!(Crc32c::digest_message(&(), &forged), Crc32c(0xDEAD_BEEF));
+
+        let forged = [message, &forge_append(0x1234_5678, current_crc, 13), &[0; 13]].concat();
+        assert_eq!(Crc32c::digest_message(&(), &forged), Crc32c(0x1234_5678));
+    }
+}
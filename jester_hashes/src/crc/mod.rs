@@ -0,0 +1,102 @@
+//! Cyclic redundancy checks. Those are not cryptographic hash functions at all: A CRC is an affine function of the
+//! message over GF(2), so messages can be forged to have any desired checksum (see `crc32::forge_append`). They are
+//! provided for checksumming and for the manipulation of protocols that (wrongly) rely on them for integrity.
+//!
+//! All CRCs of this module are reflected 32 bit CRCs with an initial value and a final xor of `0xFFFFFFFF`. They are
+//! computed using the slice-by-8 approach, that processes eight bytes per table lookup round.
+
+pub mod crc32;
+pub mod crc32c;
+
+/// Lookup tables for the slice-by-8 computation of a reflected 32 bit CRC. `tables[0]` is the classic byte-wise
+/// table, and `tables[k]` is the table for a byte that is followed by `k` further bytes.
+pub(crate) struct CrcTables {
+    tables: [[u32; 256]; 8],
+
+    /// maps the most significant byte of an entry of `tables[0]` to its index. Used to run the CRC backwards.
+    reverse_index: [u8; 256],
+}
+
+impl CrcTables {
+    /// Generate the tables for the given reflected generator polynomial.
+    pub(crate) const fn new(polynomial: u32) -> Self {
+        let mut tables = [[0_u32; 256]; 8];
+        let mut reverse_index = [0_u8; 256];
+
+        let mut i = 0;
+        while i < 256 {
+            let mut crc = i as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                crc = if crc & 1 == 1 { (crc >> 1) ^ polynomial } else { crc >> 1 };
+                bit += 1;
+            }
+            tables[0][i] = crc;
+            reverse_index[(crc >> 24) as usize] = i as u8;
+            i += 1;
+        }
+
+        let mut k = 1;
+        while k < 8 {
+            let mut i = 0;
+            while i < 256 {
+                let previous = tables[k - 1][i];
+                tables[k][i] = (previous >> 8) ^ tables[0][(previous & 0xFF) as usize];
+                i += 1;
+            }
+            k += 1;
+        }
+
+        CrcTables { tables, reverse_index }
+    }
+
+    /// Feed `input` into the CRC `register`. The register does not contain the initial value and final xor.
+    pub(crate) fn update(&self, mut register: u32, input: &[u8]) -> u32 {
+        let t = &self.tables;
+
+        let mut chunks = input.chunks_exact(8);
+        for chunk in &mut chunks {
+            let low = register ^ u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+            let high = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+
+            register = t[7][(low & 0xFF) as usize]
+                ^ t[6][((low >> 8) & 0xFF) as usize]
+                ^ t[5][((low >> 16) & 0xFF) as usize]
+                ^ t[4][(low >> 24) as usize]
+                ^ t[3][(high & 0xFF) as usize]
+                ^ t[2][((high >> 8) & 0xFF) as usize]
+                ^ t[1][((high >> 16) & 0xFF) as usize]
+                ^ t[0][(high >> 24) as usize];
+        }
+
+        for byte in chunks.remainder() {
+            register = (register >> 8) ^ t[0][((register ^ *byte as u32) & 0xFF) as usize];
+        }
+
+        register
+    }
+
+    /// Undo the processing of a single zero byte by the CRC `register`.
+    fn reverse_zero_byte(&self, register: u32) -> u32 {
+        let index = self.reverse_index[(register >> 24) as usize];
+        ((register ^ self.tables[0][index as usize]) << 8) | index as u32
+    }
+
+    /// Compute four bytes, that bring a CRC from `current_crc` to `target_crc` if they are appended to the message,
+    /// followed by `appended_len` zero bytes. Both CRCs are finished CRC values.
+    pub(crate) fn forge_append(&self, target_crc: u32, current_crc: u32, appended_len: usize) -> [u8; 4] {
+        // run the CRC backwards from the target through the zero bytes
+        let mut register = !target_crc;
+        for _ in 0..appended_len {
+            register = self.reverse_zero_byte(register);
+        }
+
+        // feeding four bytes into a register is the same as xoring them into the register and feeding four zero
+        // bytes. Thus, running backwards through four zero bytes yields the register xor the forged bytes
+        for _ in 0..4 {
+            register = self.reverse_zero_byte(register);
+        }
+
+        (register ^ !current_crc).to_le_bytes()
+    }
+}
@@ -0,0 +1,121 @@
+//! An adapter that exposes any `HashFunction` as a `std::hash::Hasher`. This is meant for non-cryptographic uses like
+//! content addressing or deduplication, where a digest is used as the key of a `HashMap`.
+
+use std::hash::{BuildHasherDefault, Hasher};
+
+use crate::{HashFunction, HashValue};
+
+/// A `Hasher` that feeds all written data into the hash function `H`. `finish` does not consume the hasher, so the
+/// hash state is cloned to compute the digest, and writing may continue afterwards.
+pub struct DigestHasher<H>
+    where H: HashFunction
+{
+    ctx: H::Context,
+    state: H::HashState,
+}
+
+/// A `BuildHasher` for `DigestHasher`s of hash functions without context, usable for `HashMap`s and `HashSet`s.
+pub type BuildDigestHasher<H> = BuildHasherDefault<DigestHasher<H>>;
+
+impl<H> DigestHasher<H>
+    where H: HashFunction
+{
+    /// Create a new hasher for the hash function `H` with the given context.
+    pub fn new(ctx: H::Context) -> Self {
+        let state = H::init_hash(&ctx);
+        DigestHasher { ctx, state }
+    }
+
+    /// Discard all data written so far, so the hasher can be reused for the next message.
+    pub fn reset(&mut self) {
+        H::reset_hash(&mut self.state, &self.ctx)
+    }
+}
+
+impl<H> DigestHasher<H>
+    where H: HashFunction,
+          H::HashState: Clone
+{
+    /// Compute the full digest of all data written so far. The hasher is not consumed.
+    pub fn finish_full(&self) -> H::HashData {
+        H::peek_hash(&self.state, &self.ctx)
+    }
+}
+
+impl<H> Default for DigestHasher<H>
+    where H: HashFunction<Context=()>
+{
+    fn default() -> Self {
+        Self::new(())
+    }
+}
+
+impl<H> Hasher for DigestHasher<H>
+    where H: HashFunction,
+          H::HashState: Clone
+{
+    /// Returns the first eight bytes of the digest of all data written so far as a big endian integer.
+    fn finish(&self) -> u64 {
+        let mut prefix = [0u8; 8];
+        let digest = self.finish_full().raw();
+        let length = digest.len().min(prefix.len());
+        prefix[..length].copy_from_slice(&digest[..length]);
+
+        u64::from_be_bytes(prefix)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        H::update_hash(&mut self.state, bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::md5::MD5Hash;
+    use crate::sha1::SHA1Hash;
+    use crate::tests::LONG_TEXT;
+
+    #[test]
+    fn test_chunked_writes() {
+        let mut hasher = DigestHasher::<SHA1Hash>::default();
+        for chunk in LONG_TEXT.as_bytes().chunks(7) {
+            hasher.write(chunk);
+        }
+
+        let digest = SHA1Hash::digest_message(&(), LONG_TEXT.as_bytes()).raw();
+        assert_eq!(hasher.finish_full().raw(), digest);
+
+        let mut prefix = [0u8; 8];
+        prefix.copy_from_slice(&digest[..8]);
+        assert_eq!(hasher.finish(), u64::from_be_bytes(prefix));
+
+        // finishing does not consume the hasher
+        assert_eq!(hasher.finish_full().raw(), digest);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut hasher = DigestHasher::<MD5Hash>::default();
+        hasher.write(LONG_TEXT.as_bytes());
+        hasher.reset();
+        hasher.write(b"message");
+
+        assert_eq!(hasher.finish_full().raw(), MD5Hash::digest_message(&(), b"message").raw());
+    }
+
+    #[test]
+    fn test_hash_map() {
+        let mut map: HashMap<&str, usize, BuildDigestHasher<MD5Hash>> = HashMap::default();
+        map.insert("first", 1);
+        map.insert("second", 2);
+        map.insert("first", 3);
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get("first"), Some(&3));
+        assert_eq!(map.get("second"), Some(&2));
+        assert_eq!(map.get("third"), None);
+    }
+}
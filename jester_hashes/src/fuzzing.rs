@@ -0,0 +1,128 @@
+//! Helpers shared by the fuzz targets in the `fuzz` directory and the streaming tests of this crate. A fuzz input is
+//! decoded into a sequence of chunks, which are fed into a hash function by successive calls to `update_hash`. The
+//! resulting hash must equal the one-shot digest of all chunks. This module is only available with the `fuzzing`
+//! feature.
+
+use crate::blake::blake2b::{Blake2b, Blake2bContext};
+use crate::md5::MD5Hash;
+use crate::sha1::SHA1Hash;
+use crate::{HashFunction, HashValue};
+
+/// Key used for the keyed Blake2b checks
+const BLAKE_KEY: &[u8] = b"pseudorandom key";
+
+/// Decode a fuzz input into chunks. The input is read as a sequence of chunks, each one a length byte followed by as
+/// many bytes of data. The last chunk is truncated, if the input ends early.
+pub fn split_chunks(data: &[u8]) -> Vec<&[u8]> {
+    let mut chunks = vec![];
+    let mut remaining = data;
+
+    while let Some((length, rest)) = remaining.split_first() {
+        let (chunk, rest) = rest.split_at((*length as usize).min(rest.len()));
+        chunks.push(chunk);
+        remaining = rest;
+    }
+
+    chunks
+}
+
+/// Hash the `chunks` with one call to `update_hash` per chunk and assert that the result equals the digest of the
+/// concatenated chunks.
+pub fn assert_streaming_consistent<H>(ctx: &H::Context, chunks: &[&[u8]])
+    where H: HashFunction
+{
+    let mut hash_state = H::init_hash(ctx);
+    for chunk in chunks {
+        H::update_hash(&mut hash_state, chunk);
+    }
+
+    assert_eq!(
+        H::finish_hash(&mut hash_state, ctx).raw(),
+        H::digest_message(ctx, &chunks.concat()).raw(),
+        "streaming hash differs from one-shot hash for chunk lengths {:?}",
+        chunks.iter().map(|chunk| chunk.len()).collect::<Vec<_>>()
+    );
+}
+
+/// Check the streaming invariant of MD5 for a fuzz input
+pub fn check_md5(data: &[u8]) {
+    assert_streaming_consistent::<MD5Hash>(&(), &split_chunks(data));
+}
+
+/// Check the streaming invariant of SHA-1 for a fuzz input
+pub fn check_sha1(data: &[u8]) {
+    assert_streaming_consistent::<SHA1Hash>(&(), &split_chunks(data));
+}
+
+/// Check the streaming invariant of Blake2b for a fuzz input, with and without a key
+pub fn check_blake2b(data: &[u8]) {
+    let chunks = split_chunks(data);
+    assert_streaming_consistent::<Blake2b>(&Blake2bContext::new(64).unwrap(), &chunks);
+    assert_streaming_consistent::<Blake2b>(&Blake2bContext::new_keyed(32, BLAKE_KEY).unwrap(), &chunks);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::blake::blake2s::{Blake2s, Blake2sContext};
+    use crate::crc::crc32::Crc32;
+    use crate::crc::crc32c::Crc32c;
+    use crate::siphash::{SipHash24, SipHashContext};
+
+    /// Split the `message` at the given positions. Positions are reduced modulo the message length.
+    fn partition(message: &[u8], cuts: &[usize]) -> Vec<Vec<u8>> {
+        let mut cuts: Vec<usize> = cuts.iter().map(|cut| cut % (message.len() + 1)).collect();
+        cuts.push(0);
+        cuts.push(message.len());
+        cuts.sort_unstable();
+
+        cuts.windows(2).map(|window| message[window[0]..window[1]].to_vec()).collect()
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn streaming_equals_one_shot(message in vec(any::<u8>(), 0..4096), cuts in vec(any::<usize>(), 0..16)) {
+            let chunks = partition(&message, &cuts);
+            let chunks: Vec<&[u8]> = chunks.iter().map(Vec::as_slice).collect();
+
+            assert_streaming_consistent::<MD5Hash>(&(), &chunks);
+            assert_streaming_consistent::<SHA1Hash>(&(), &chunks);
+            assert_streaming_consistent::<Blake2b>(&Blake2bContext::new(64).unwrap(), &chunks);
+            assert_streaming_consistent::<Blake2b>(
+                &Blake2bContext::new_keyed(32, BLAKE_KEY).unwrap(), &chunks);
+            assert_streaming_consistent::<Blake2s>(&Blake2sContext::new(32).unwrap(), &chunks);
+            assert_streaming_consistent::<Blake2s>(
+                &Blake2sContext::new_keyed(16, BLAKE_KEY).unwrap(), &chunks);
+            assert_streaming_consistent::<Crc32>(&(), &chunks);
+            assert_streaming_consistent::<Crc32c>(&(), &chunks);
+            assert_streaming_consistent::<SipHash24>(&SipHashContext { key: [7; 16] }, &chunks);
+        }
+    }
+
+    /// Run all fuzz checks on the seed corpus of the fuzz targets, so the checks are exercised without a fuzzer.
+    #[test]
+    fn fuzz_corpus_test() {
+        let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz").join("corpus");
+        let mut inputs = 0;
+
+        for target in fs::read_dir(corpus).unwrap() {
+            for input in fs::read_dir(target.unwrap().path()).unwrap() {
+                let data = fs::read(input.unwrap().path()).unwrap();
+                check_md5(&data);
+                check_sha1(&data);
+                check_blake2b(&data);
+                inputs += 1;
+            }
+        }
+
+        assert!(inputs > 0, "the fuzz corpus is empty");
+    }
+}
@@ -0,0 +1,333 @@
+//! A streaming wrapper around a `HashFunction`, which owns the context and the hash state. It implements
+//! `std::io::Write`, so data can be hashed while it is copied from a reader with `std::io::copy`, without collecting
+//! it in memory first. With the `async-io` feature, it also implements `futures::io::AsyncWrite`, and `hash_reader`
+//! hashes an `AsyncRead` without depending on a particular executor. Files are hashed in chunks with `hash_file`,
+//! and many files at once with `hash_files`.
+
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::Path;
+#[cfg(feature = "async-io")]
+use std::pin::Pin;
+#[cfg(feature = "async-io")]
+use std::task::{Context, Poll};
+
+#[cfg(feature = "async-io")]
+use futures_io::{AsyncRead, AsyncWrite};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::HashFunction;
+
+/// The size of the buffer `hash_reader` reads into
+#[cfg(feature = "async-io")]
+const READ_BUFFER_SIZE: usize = 8 * 1024;
+
+/// A hash state together with its context. Data is fed with `update` or through `std::io::Write`, and `finalize`
+/// produces the digest.
+pub struct Hasher<H>
+    where H: HashFunction
+{
+    ctx: H::Context,
+    state: H::HashState,
+}
+
+impl<H> Hasher<H>
+    where H: HashFunction
+{
+    /// Start hashing with the given context.
+    pub fn new(ctx: H::Context) -> Self {
+        let state = H::init_hash(&ctx);
+        Hasher { ctx, state }
+    }
+
+    /// The context of the hash function.
+    pub fn context(&self) -> &H::Context {
+        &self.ctx
+    }
+
+    /// Compress `data` into the hash state.
+    pub fn update(&mut self, data: &[u8]) {
+        H::update_hash(&mut self.state, data)
+    }
+
+    /// Discard all data hashed so far.
+    pub fn reset(&mut self) {
+        H::reset_hash(&mut self.state, &self.ctx)
+    }
+
+    /// Finish the hash of all data fed into the hasher.
+    pub fn finalize(mut self) -> H::HashData {
+        H::finish_hash(&mut self.state, &self.ctx)
+    }
+}
+
+impl<H> io::Write for Hasher<H>
+    where H: HashFunction
+{
+    /// Hash all of `buf`. Writing never fails.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async-io")]
+impl<H> AsyncWrite for Hasher<H>
+    where H: HashFunction,
+          Self: Unpin
+{
+    /// Hash all of `buf`. Writing never fails and is always ready.
+    fn poll_write(self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        self.get_mut().update(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    /// Closing does not finish the hash, it still has to be obtained with `finalize`.
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Hash everything `reader` yields until it reaches its end, reading into a buffer of 8 KiB.
+///
+/// # Returns
+/// The hash of all data read, or the first error of the reader
+#[cfg(feature = "async-io")]
+pub async fn hash_reader<H, R>(ctx: H::Context, mut reader: R) -> io::Result<H::HashData>
+    where H: HashFunction,
+          R: AsyncRead + Unpin
+{
+    let mut hasher = Hasher::<H>::new(ctx);
+    let mut buffer = [0_u8; READ_BUFFER_SIZE];
+
+    loop {
+        let read = std::future::poll_fn(|cx| Pin::new(&mut reader).poll_read(cx, &mut buffer)).await;
+        match read {
+            Ok(0) => return Ok(hasher.finalize()),
+            Ok(length) => hasher.update(&buffer[..length]),
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// Hash the file at `path`, reading it in chunks of `chunk_size` bytes, so it is never held in memory completely.
+/// # Parameters
+/// - `ctx` the context of the hash function
+/// - `path` the path of the file
+/// - `chunk_size` the number of bytes read and compressed at once
+/// - `progress` called after every chunk with the number of bytes hashed so far and the size of the file when it was
+///   opened. It is not called for empty files.
+///
+/// # Returns
+/// The hash of the file's content, or the first error of opening or reading the file
+///
+/// # Panics
+/// If `chunk_size` is zero.
+pub fn hash_file<H>(ctx: &H::Context,
+                    path: &Path,
+                    chunk_size: usize,
+                    mut progress: Option<&mut dyn FnMut(u64, u64)>) -> io::Result<H::HashData>
+    where H: HashFunction
+{
+    assert!(chunk_size > 0, "cannot hash a file in chunks of zero bytes");
+
+    let mut file = File::open(path)?;
+    let total = file.metadata()?.len();
+
+    let mut state = H::init_hash(ctx);
+    let mut buffer = vec![0_u8; chunk_size];
+    let mut done = 0_u64;
+
+    loop {
+        match file.read(&mut buffer) {
+            Ok(0) => return Ok(H::finish_hash(&mut state, ctx)),
+            Ok(length) => {
+                H::update_hash(&mut state, &buffer[..length]);
+                done += length as u64;
+                if let Some(progress) = progress.as_mut() {
+                    progress(done, total);
+                }
+            }
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// Hash many files with `hash_file`, without reporting progress. With the `rayon` feature, the files are hashed in
+/// parallel. The bounds do not depend on the feature, so enabling it cannot break a caller.
+///
+/// # Returns
+/// The hash or the error of each file, in the order of `paths`
+///
+/// # Panics
+/// If `chunk_size` is zero.
+pub fn hash_files<H, P>(ctx: &H::Context, paths: &[P], chunk_size: usize) -> Vec<io::Result<H::HashData>>
+    where H: HashFunction,
+          H::Context: Sync,
+          H::HashData: Send,
+          P: AsRef<Path> + Sync
+{
+    #[cfg(feature = "rayon")]
+    let paths = paths.par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let paths = paths.iter();
+
+    paths.map(|path| hash_file::<H>(ctx, path.as_ref(), chunk_size, None)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use crate::HashValue;
+    use crate::md5::MD5Hash;
+    use crate::tests::LONG_TEXT;
+
+    /// A file in the temporary directory that is removed when dropped
+    struct TempFile(std::path::PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, content: &[u8]) -> Self {
+            let path = std::env::temp_dir().join(format!("jester_hashes_{}_{}", std::process::id(), name));
+            std::fs::write(&path, content).unwrap();
+            TempFile(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_streaming_write() {
+        let mut hasher = Hasher::<MD5Hash>::new(());
+        for chunk in LONG_TEXT.as_bytes().chunks(37) {
+            hasher.write_all(chunk).unwrap();
+        }
+
+        assert_eq!(hasher.finalize().raw(), MD5Hash::digest_message(&(), LONG_TEXT.as_bytes()).raw());
+    }
+
+    #[test]
+    fn test_io_copy() {
+        let mut hasher = Hasher::<MD5Hash>::new(());
+        hasher.update(b"discarded");
+        hasher.reset();
+
+        let copied = io::copy(&mut LONG_TEXT.as_bytes(), &mut hasher).unwrap();
+        assert_eq!(copied as usize, LONG_TEXT.len());
+        assert_eq!(hasher.finalize().raw(), MD5Hash::digest_message(&(), LONG_TEXT.as_bytes()).raw());
+    }
+
+    /// An in-memory reader that yields its data in chunks of varying, odd sizes and is pending before every other
+    /// chunk, like a network stream.
+    #[cfg(feature = "async-io")]
+    struct ChunkedReader {
+        data: Vec<u8>,
+        position: usize,
+        chunk: usize,
+        pending: bool,
+    }
+
+    #[cfg(feature = "async-io")]
+    impl AsyncRead for ChunkedReader {
+        fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+            self.pending = !self.pending;
+            if self.pending {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+
+            self.chunk = (self.chunk * 7 + 3) % 20011;
+            let length = self.chunk.min(buf.len()).min(self.data.len() - self.position);
+            buf[..length].copy_from_slice(&self.data[self.position..self.position + length]);
+            self.position += length;
+            Poll::Ready(Ok(length))
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "async-io")]
+    fn test_hash_reader() {
+        use crate::blake::blake2b::{Blake2b, Blake2bContext};
+
+        let data = LONG_TEXT.as_bytes().repeat(5 * 1024 * 1024 / LONG_TEXT.len());
+        let reader = ChunkedReader { data: data.clone(), position: 0, chunk: 1, pending: false };
+
+        let hash = futures::executor::block_on(hash_reader::<Blake2b, _>(Blake2bContext::default(), reader)).unwrap();
+        assert_eq!(hash.raw(), Blake2b::digest_message(&Blake2bContext::default(), &data).raw());
+    }
+
+    #[test]
+    #[cfg(feature = "async-io")]
+    fn test_async_write() {
+        use futures::io::AsyncWriteExt;
+
+        let mut hasher = Hasher::<MD5Hash>::new(());
+        futures::executor::block_on(async {
+            for chunk in LONG_TEXT.as_bytes().chunks(37) {
+                AsyncWriteExt::write_all(&mut hasher, chunk).await.unwrap();
+            }
+            hasher.close().await.unwrap();
+        });
+
+        assert_eq!(hasher.finalize().raw(), MD5Hash::digest_message(&(), LONG_TEXT.as_bytes()).raw());
+    }
+
+    #[test]
+    fn test_hash_file() {
+        let data = LONG_TEXT.as_bytes().repeat(20);
+        let file = TempFile::new("hash_file", &data);
+
+        let mut reports = vec![];
+        let mut progress = |done, total| reports.push((done, total));
+        let hash = hash_file::<MD5Hash>(&(), &file.0, 1000, Some(&mut progress)).unwrap();
+        assert_eq!(hash.raw(), MD5Hash::digest_message(&(), &data).raw());
+
+        // every chunk is reported, with a growing number of bytes hashed
+        assert_eq!(reports.len(), data.len().div_ceil(1000));
+        assert!(reports.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(reports.iter().all(|(_, total)| *total == data.len() as u64));
+        assert_eq!(reports.last(), Some(&(data.len() as u64, data.len() as u64)));
+    }
+
+    #[test]
+    fn test_hash_empty_file() {
+        let file = TempFile::new("hash_empty_file", b"");
+
+        let mut reports = 0;
+        let mut progress = |_, _| reports += 1;
+        let hash = hash_file::<MD5Hash>(&(), &file.0, 64, Some(&mut progress)).unwrap();
+        assert_eq!(hash.raw(), MD5Hash::digest_message(&(), b"").raw());
+        assert_eq!(reports, 0);
+    }
+
+    #[test]
+    fn test_hash_files() {
+        let first = TempFile::new("hash_files_first", b"first");
+        let second = TempFile::new("hash_files_second", LONG_TEXT.as_bytes());
+        let missing = std::env::temp_dir().join(format!("jester_hashes_{}_missing", std::process::id()));
+
+        let hashes = hash_files::<MD5Hash, _>(&(), &[&first.0, &missing, &second.0], 7);
+        assert_eq!(hashes.len(), 3);
+        assert_eq!(hashes[0].as_ref().unwrap().raw(), MD5Hash::digest_message(&(), b"first").raw());
+        assert_eq!(hashes[1].as_ref().err().unwrap().kind(), io::ErrorKind::NotFound);
+        assert_eq!(hashes[2].as_ref().unwrap().raw(), MD5Hash::digest_message(&(), LONG_TEXT.as_bytes()).raw());
+    }
+}
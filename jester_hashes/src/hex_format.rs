@@ -0,0 +1,116 @@
+//! Hexadecimal formatting and parsing of hash values. The `impl_hex_format!` macro implements `Display`, `LowerHex`
+//! and `UpperHex` for a `HashValue` type, all of which print the bytes of `raw()`.
+
+use std::convert::TryInto;
+use std::fmt;
+
+use crate::ParseHexError;
+
+/// Implement `Display` and `LowerHex` as the lowercase hex of `raw()` and `UpperHex` as the uppercase hex of `raw()`
+/// for each of the given types.
+macro_rules! impl_hex_format {
+    ($($hash:ty),+ $(,)?) => {
+        $(
+            impl std::fmt::Display for $hash {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    std::fmt::LowerHex::fmt(self, f)
+                }
+            }
+
+            impl std::fmt::LowerHex for $hash {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    crate::hex_format::write_hex(f, &crate::HashValue::raw(self), false)
+                }
+            }
+
+            impl std::fmt::UpperHex for $hash {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    crate::hex_format::write_hex(f, &crate::HashValue::raw(self), true)
+                }
+            }
+        )+
+    };
+}
+
+/// Write `bytes` as two hex digits each.
+pub(crate) fn write_hex(f: &mut fmt::Formatter<'_>, bytes: &[u8], uppercase: bool) -> fmt::Result {
+    for byte in bytes {
+        if uppercase {
+            write!(f, "{:02X}", byte)?;
+        } else {
+            write!(f, "{:02x}", byte)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Decode a hex string of either case.
+///
+/// # Returns
+/// The decoded bytes, or a `ParseHexError` if `hex` has an odd length or contains a non-hex character.
+pub(crate) fn decode_hex(hex: &str) -> Result<Vec<u8>, ParseHexError> {
+    let digits = hex
+        .chars()
+        .enumerate()
+        .map(|(position, character)| {
+            character.to_digit(16).map(|digit| digit as u8).ok_or(ParseHexError::InvalidCharacter { position })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if digits.len() % 2 != 0 {
+        return Err(ParseHexError::OddLength { length: digits.len() });
+    }
+
+    Ok(digits.chunks_exact(2).map(|pair| pair[0] << 4 | pair[1]).collect())
+}
+
+/// Decode a hex string of exactly `N` bytes.
+///
+/// # Returns
+/// The decoded bytes, or a `ParseHexError` if `hex` is no valid hex or does not encode `N` bytes.
+pub(crate) fn decode_hex_array<const N: usize>(hex: &str) -> Result<[u8; N], ParseHexError> {
+    let bytes = decode_hex(hex)?;
+    let length = bytes.len();
+    bytes.try_into().map_err(|_| ParseHexError::InvalidLength { length })
+}
+
+/// Decode a hex string of at least one and at most `max_length` bytes, the valid output lengths of the variable
+/// length hash functions.
+///
+/// # Returns
+/// The decoded bytes, or a `ParseHexError` if `hex` is no valid hex or its length is out of range.
+pub(crate) fn decode_hex_bounded(hex: &str, max_length: usize) -> Result<Vec<u8>, ParseHexError> {
+    let bytes = decode_hex(hex)?;
+    if bytes.is_empty() || bytes.len() > max_length {
+        return Err(ParseHexError::InvalidLength { length: bytes.len() });
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_hex() {
+        assert_eq!(decode_hex("").unwrap(), Vec::<u8>::new());
+        assert_eq!(decode_hex("00ff7A9b").unwrap(), vec![0x00, 0xFF, 0x7A, 0x9B]);
+        assert_eq!(decode_hex("abc"), Err(ParseHexError::OddLength { length: 3 }));
+        assert_eq!(decode_hex("0g"), Err(ParseHexError::InvalidCharacter { position: 1 }));
+        assert_eq!(decode_hex("+1"), Err(ParseHexError::InvalidCharacter { position: 0 }));
+        assert_eq!(decode_hex("ä0"), Err(ParseHexError::InvalidCharacter { position: 0 }));
+    }
+
+    #[test]
+    fn test_decode_hex_length() {
+        assert_eq!(decode_hex_array::<2>("beef").unwrap(), [0xBE, 0xEF]);
+        assert_eq!(decode_hex_array::<2>("be"), Err(ParseHexError::InvalidLength { length: 1 }));
+        assert_eq!(decode_hex_array::<2>("beef00"), Err(ParseHexError::InvalidLength { length: 3 }));
+
+        assert_eq!(decode_hex_bounded("beef", 2).unwrap(), vec![0xBE, 0xEF]);
+        assert_eq!(decode_hex_bounded("", 2), Err(ParseHexError::InvalidLength { length: 0 }));
+        assert_eq!(decode_hex_bounded("beef00", 2), Err(ParseHexError::InvalidLength { length: 3 }));
+    }
+}
@@ -1,4 +1,10 @@
-use crate::{BlockHashFunction, HashValue};
+use crate::{constant_time_eq, BlockHashFunction, HashValue};
+use jester_util::Redacted;
+use std::convert::TryInto;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
 /// Generate a keyed-hash message authentication code from a `HashFunction` and a given key using the HMAC protocol
 /// of RFC 2104.
@@ -12,30 +18,136 @@ use crate::{BlockHashFunction, HashValue};
 pub fn hmac<Hash, Context>(ctx: &Context, key: &[u8], message: &[u8]) -> Vec<u8>
     where Hash: BlockHashFunction<Context=Context>,
 {
-    let block_size = Hash::block_size(ctx);
+    let (mut inner_state, mut outer_state) = key_schedule::<Hash>(ctx, key);
 
-    let shortened_key = if key.len() > block_size {
-        Hash::digest_message(ctx, key).raw()
-    } else {
-        key.into()
-    };
+    Hash::update_hash(&mut inner_state, message);
+    Hash::update_hash(&mut outer_state, &Hash::finish_hash(&mut inner_state, ctx).raw());
 
-    let padded_key = if shortened_key.len() < block_size {
-        pad(key, block_size)
-    } else {
-        shortened_key
-    };
+    Hash::finish_hash(&mut outer_state, ctx).raw()
+}
+
+/// Generate a keyed-hash message authentication code like `hmac`, but into a buffer of `N` bytes on the stack. `N`
+/// must be the `MAX_OUTPUT_SIZE` of the hash function, which is asserted at compile time.
+/// #Parameters
+/// - `ctx` the context of the hash function
+/// - `key` a secret key for the authentication code
+/// - `message` an arbitrary-sized message to authenticate
+///
+/// #Outputs
+/// Returns the raw authentication code
+///
+/// #Panics
+/// If the hash function produces less than `MAX_OUTPUT_SIZE` bytes in the context `ctx`, e.g. for truncated Blake2
+/// outputs.
+pub fn hmac_array<H, const N: usize>(ctx: &H::Context, key: &[u8], message: &[u8]) -> [u8; N]
+    where H: BlockHashFunction
+{
+    const { assert!(N == H::MAX_OUTPUT_SIZE, "the buffer size must be the maximum output size of the hash function") };
+    assert_eq!(H::output_size_for(ctx), N, "the context truncates the maximum output size of the hash function");
+
+    let (mut inner_state, mut outer_state) = key_schedule::<H>(ctx, key);
+
+    let mut inner_hash = [0_u8; N];
+    H::update_hash(&mut inner_state, message);
+    inner_hash.copy_from_slice(&H::finish_hash(&mut inner_state, ctx).raw());
+
+    let mut tag = [0_u8; N];
+    H::update_hash(&mut outer_state, &inner_hash);
+    tag.copy_from_slice(&H::finish_hash(&mut outer_state, ctx).raw());
+    tag
+}
+
+/// Verify many HMAC `tags` of `(message, tag)` pairs under the same `key`. The hash states of the inner and outer
+/// padded key are computed once and cloned for each message, and each tag is compared in constant time. With the
+/// `rayon` feature, the items are verified in parallel.
+/// #Parameters
+/// - `ctx` the context of the hash function
+/// - `key` the secret key of the authentication codes
+/// - `items` pairs of messages and the tags to verify
+///
+/// #Outputs
+/// Returns whether each tag is valid, in the order of `items`. Tags of the wrong length are invalid.
+#[cfg(not(feature = "rayon"))]
+pub fn hmac_verify_batch<H>(ctx: &H::Context, key: &[u8], items: &[(&[u8], &[u8])]) -> Vec<bool>
+    where H: BlockHashFunction,
+          H::HashState: Clone
+{
+    let (inner_state, outer_state) = key_schedule::<H>(ctx, key);
 
-    let mut outer_message = padded_key
+    items
         .iter()
-        .map(|v| v ^ 0x5C)
-        .collect::<Vec<_>>();
-    let mut inner_message = padded_key.iter().map(|v| v ^ 0x36).collect::<Vec<_>>();
+        .map(|(message, tag)| verify_tag::<H>(ctx, &inner_state, &outer_state, message, tag))
+        .collect()
+}
+
+/// Verify many HMAC `tags` of `(message, tag)` pairs under the same `key`. The hash states of the inner and outer
+/// padded key are computed once and cloned for each message, and each tag is compared in constant time. With the
+/// `rayon` feature, the items are verified in parallel.
+/// #Parameters
+/// - `ctx` the context of the hash function
+/// - `key` the secret key of the authentication codes
+/// - `items` pairs of messages and the tags to verify
+///
+/// #Outputs
+/// Returns whether each tag is valid, in the order of `items`. Tags of the wrong length are invalid.
+#[cfg(feature = "rayon")]
+pub fn hmac_verify_batch<H>(ctx: &H::Context, key: &[u8], items: &[(&[u8], &[u8])]) -> Vec<bool>
+    where H: BlockHashFunction,
+          H::Context: Sync,
+          H::HashState: Clone + Sync
+{
+    let (inner_state, outer_state) = key_schedule::<H>(ctx, key);
+
+    items
+        .par_iter()
+        .map(|(message, tag)| verify_tag::<H>(ctx, &inner_state, &outer_state, message, tag))
+        .collect()
+}
+
+/// Compress the inner and outer padded key into two fresh hash states.
+fn key_schedule<H>(ctx: &H::Context, key: &[u8]) -> (H::HashState, H::HashState)
+    where H: BlockHashFunction
+{
+    let padded_key = padded_key::<H>(ctx, key);
+
+    let mut inner_state = H::init_hash(ctx);
+    H::update_hash(&mut inner_state, &padded_key.iter().map(|v| v ^ 0x36).collect::<Vec<_>>());
+
+    let mut outer_state = H::init_hash(ctx);
+    H::update_hash(&mut outer_state, &padded_key.iter().map(|v| v ^ 0x5C).collect::<Vec<_>>());
+
+    (inner_state, outer_state)
+}
+
+/// Compute the tag of `message` from the precomputed key schedule and compare it with `tag` in constant time.
+fn verify_tag<H>(ctx: &H::Context, inner_state: &H::HashState, outer_state: &H::HashState, message: &[u8],
+                 tag: &[u8]) -> bool
+    where H: BlockHashFunction,
+          H::HashState: Clone
+{
+    let mut inner_state = inner_state.clone();
+    H::update_hash(&mut inner_state, message);
+    let inner_hash = H::finish_hash(&mut inner_state, ctx).raw();
+
+    let mut outer_state = outer_state.clone();
+    H::update_hash(&mut outer_state, &inner_hash);
+
+    constant_time_eq(&H::finish_hash(&mut outer_state, ctx).raw(), tag)
+}
+
+/// Shorten `key` to the hash of the key, if it is longer than a block, and pad it with zeros to the block size.
+fn padded_key<H>(ctx: &H::Context, key: &[u8]) -> Vec<u8>
+    where H: BlockHashFunction
+{
+    let block_size = H::block_size(ctx);
 
-    inner_message.append(&mut message.to_vec());
-    outer_message.append(&mut Hash::digest_message(ctx,&inner_message).raw());
+    let shortened_key = if key.len() > block_size {
+        H::digest_message(ctx, key).raw()
+    } else {
+        key.into()
+    };
 
-    Hash::digest_message(ctx, &outer_message).raw()
+    pad(&shortened_key, block_size)
 }
 
 fn pad(key: &[u8], length: usize) -> Vec<u8> {
@@ -44,11 +156,299 @@ fn pad(key: &[u8], length: usize) -> Vec<u8> {
     padded_vec
 }
 
+/// Version of the binary format of `RotatingKeySet::to_bytes`
+const KEY_SET_FORMAT_VERSION: u8 = 1;
+
+/// A MAC key of a `RotatingKeySet`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RotatingKey {
+    /// The id that is prepended to the tags produced under this key
+    pub key_id: u32,
+
+    /// The secret key
+    pub key: Redacted<Vec<u8>>,
+
+    /// The unix time in seconds after which tags produced under this key are no longer accepted, if any
+    pub not_after: Option<u64>,
+}
+
+/// An HMAC tag together with the id of the key it was produced under.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaggedMac {
+    pub key_id: u32,
+    pub tag: Vec<u8>,
+}
+
+impl TaggedMac {
+    /// Encode the tag as the big endian key id followed by the raw tag.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.key_id.to_be_bytes().to_vec();
+        bytes.extend_from_slice(&self.tag);
+        bytes
+    }
+
+    /// Decode a tag encoded by `to_bytes`.
+    ///
+    /// #Outputs
+    /// Returns the tagged MAC, or `None` if `bytes` is too short to contain a key id.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 4 {
+            return None;
+        }
+
+        let (key_id, tag) = bytes.split_at(4);
+        Some(TaggedMac { key_id: u32::from_be_bytes(key_id.try_into().unwrap()), tag: tag.to_vec() })
+    }
+}
+
+/// The result of verifying a tag with a `RotatingKeySet`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyOutcome {
+    /// The tag is valid under the newest key
+    ValidCurrent,
+
+    /// The tag is valid under an older key, that did not expire yet. The message should be authenticated under the
+    /// current key again.
+    ValidStale { key_id: u32 },
+
+    /// The tag is malformed or wrong, its key is unknown or its key expired
+    Invalid,
+}
+
+/// Exceptions that can arise when modifying or decoding a `RotatingKeySet`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeySetException {
+    /// The key set already contains a key with this id
+    DuplicateKeyId { key_id: u32 },
+
+    /// The key set does not contain a key with this id
+    UnknownKeyId { key_id: u32 },
+
+    /// The newest key cannot be removed, as it is used for signing
+    CurrentKeyRemoved { key_id: u32 },
+
+    /// The encoded key set is truncated, has trailing data, contains no keys or has an unsupported version
+    MalformedKeySet {},
+}
+
+/// A set of HMAC keys for key rotation. New tags are produced under the newest key, while tags produced under older
+/// keys remain valid until their key expires or is removed, so keys can be rotated without invalidating all tags at
+/// once. Each tag is prefixed with the id of its key, so verification requires a single HMAC computation.
+pub struct RotatingKeySet<H>
+    where H: BlockHashFunction
+{
+    ctx: H::Context,
+    keys: Vec<RotatingKey>,
+}
+
+impl<H> RotatingKeySet<H>
+    where H: BlockHashFunction
+{
+    /// Create a key set with a single key, that is used for signing until the keys are rotated.
+    /// #Parameters
+    /// - `ctx` the context of the hash function
+    /// - `key_id` the id of the key
+    /// - `key` the secret key
+    /// - `not_after` the unix time in seconds after which tags of this key are rejected, if any
+    pub fn new(ctx: H::Context, key_id: u32, key: &[u8], not_after: Option<u64>) -> Self {
+        RotatingKeySet {
+            ctx,
+            keys: vec![RotatingKey { key_id, key: Redacted::new(key.to_vec()), not_after }],
+        }
+    }
+
+    /// Add a new key, that replaces the current key for signing. The previous keys are still used for verification.
+    ///
+    /// #Outputs
+    /// Returns a `KeySetException` if the set already contains a key with the same id.
+    pub fn rotate(&mut self, key_id: u32, key: &[u8], not_after: Option<u64>) -> Result<(), KeySetException> {
+        if self.key(key_id).is_some() {
+            return Err(KeySetException::DuplicateKeyId { key_id });
+        }
+
+        self.keys.push(RotatingKey { key_id, key: Redacted::new(key.to_vec()), not_after });
+        Ok(())
+    }
+
+    /// Remove an older key, so tags produced under it are rejected, even if it did not expire yet.
+    ///
+    /// #Outputs
+    /// Returns a `KeySetException` if the key is unknown or the current key.
+    pub fn remove(&mut self, key_id: u32) -> Result<(), KeySetException> {
+        let position = self.keys
+            .iter()
+            .position(|key| key.key_id == key_id)
+            .ok_or(KeySetException::UnknownKeyId { key_id })?;
+
+        if position == self.keys.len() - 1 {
+            return Err(KeySetException::CurrentKeyRemoved { key_id });
+        }
+
+        self.keys.remove(position);
+        Ok(())
+    }
+
+    /// The id of the newest key, that is used for signing
+    pub fn current_key_id(&self) -> u32 {
+        self.current_key().key_id
+    }
+
+    /// All keys from the oldest to the newest one
+    pub fn keys(&self) -> &[RotatingKey] {
+        &self.keys
+    }
+
+    /// Authenticate `message` under the newest key.
+    pub fn sign(&self, message: &[u8]) -> TaggedMac {
+        let current = self.current_key();
+
+        TaggedMac { key_id: current.key_id, tag: hmac::<H, H::Context>(&self.ctx, &current.key, message) }
+    }
+
+    /// Verify an encoded `TaggedMac` of `message` at the current system time.
+    pub fn verify(&self, message: &[u8], tagged: &[u8]) -> VerifyOutcome {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+        self.verify_at(now, message, tagged)
+    }
+
+    /// Verify an encoded `TaggedMac` of `message` at the unix time `now` in seconds. The tag is only checked against
+    /// the key identified by its key id. Keys are expired at all times after their `not_after` time.
+    pub fn verify_at(&self, now: u64, message: &[u8], tagged: &[u8]) -> VerifyOutcome {
+        let tagged = match TaggedMac::from_bytes(tagged) {
+            Some(tagged) => tagged,
+            None => return VerifyOutcome::Invalid,
+        };
+
+        let key = match self.key(tagged.key_id) {
+            Some(key) => key,
+            None => return VerifyOutcome::Invalid,
+        };
+
+        if key.not_after.is_some_and(|not_after| now > not_after) {
+            return VerifyOutcome::Invalid;
+        }
+
+        if !constant_time_eq(&hmac::<H, H::Context>(&self.ctx, &key.key, message), &tagged.tag) {
+            return VerifyOutcome::Invalid;
+        }
+
+        if key.key_id == self.current_key_id() {
+            VerifyOutcome::ValidCurrent
+        } else {
+            VerifyOutcome::ValidStale { key_id: key.key_id }
+        }
+    }
+
+    /// Encode all keys, so the key set can be persisted. The encoding contains the secret keys in plain and must be
+    /// stored as securely as the keys themselves. The context of the hash function is not encoded.
+    ///
+    /// The encoding is a version byte, the number of keys as big endian `u32` and then for each key from the oldest
+    /// to the newest one: the key id as big endian `u32`, a byte that is 1 if the key expires followed by its
+    /// `not_after` time as big endian `u64` or 0 if it does not expire, and the length of the key as big endian `u32`
+    /// followed by the key.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![KEY_SET_FORMAT_VERSION];
+        bytes.extend_from_slice(&(self.keys.len() as u32).to_be_bytes());
+
+        for key in self.keys.iter() {
+            bytes.extend_from_slice(&key.key_id.to_be_bytes());
+            match key.not_after {
+                Some(not_after) => {
+                    bytes.push(1);
+                    bytes.extend_from_slice(&not_after.to_be_bytes());
+                }
+                None => bytes.push(0),
+            }
+            bytes.extend_from_slice(&(key.key.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(&key.key);
+        }
+
+        bytes
+    }
+
+    /// Decode a key set encoded by `to_bytes`.
+    /// #Parameters
+    /// - `ctx` the context of the hash function, which is not part of the encoding
+    /// - `bytes` the encoded key set
+    ///
+    /// #Outputs
+    /// Returns the key set, or a `KeySetException` if the encoding is malformed or contains a key id twice.
+    pub fn from_bytes(ctx: H::Context, bytes: &[u8]) -> Result<Self, KeySetException> {
+        let mut reader = KeySetReader { bytes };
+
+        if reader.take(1)? != [KEY_SET_FORMAT_VERSION] {
+            return Err(KeySetException::MalformedKeySet {});
+        }
+
+        let count = reader.read_u32()?;
+        if count == 0 {
+            return Err(KeySetException::MalformedKeySet {});
+        }
+
+        let mut keys: Vec<RotatingKey> = vec![];
+        for _ in 0..count {
+            let key_id = reader.read_u32()?;
+            let not_after = match reader.take(1)? {
+                [0] => None,
+                [1] => Some(u64::from_be_bytes(reader.take(8)?.try_into().unwrap())),
+                _ => return Err(KeySetException::MalformedKeySet {}),
+            };
+            let key_length = reader.read_u32()? as usize;
+            let key = reader.take(key_length)?.to_vec();
+
+            if keys.iter().any(|key| key.key_id == key_id) {
+                return Err(KeySetException::DuplicateKeyId { key_id });
+            }
+            keys.push(RotatingKey { key_id, key: Redacted::new(key), not_after });
+        }
+
+        if !reader.bytes.is_empty() {
+            return Err(KeySetException::MalformedKeySet {});
+        }
+
+        Ok(RotatingKeySet { ctx, keys })
+    }
+
+    fn current_key(&self) -> &RotatingKey {
+        self.keys.last().expect("a key set contains at least one key")
+    }
+
+    fn key(&self, key_id: u32) -> Option<&RotatingKey> {
+        self.keys.iter().find(|key| key.key_id == key_id)
+    }
+}
+
+/// Reads the fields of an encoded `RotatingKeySet`, failing on truncated input.
+struct KeySetReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> KeySetReader<'a> {
+    fn take(&mut self, length: usize) -> Result<&'a [u8], KeySetException> {
+        if self.bytes.len() < length {
+            return Err(KeySetException::MalformedKeySet {});
+        }
+
+        let (field, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Ok(field)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, KeySetException> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use crate::blake::blake2b::{Blake2b, Blake2bContext};
+    use crate::blake::blake2s::{Blake2s, Blake2sContext};
     use crate::md5::MD5Hash;
+    use crate::sm3::SM3Hash;
+    use crate::whirlpool::WhirlpoolHash;
+    use crate::{BlockHashFunction, HashFunction, HashValue};
 
-    use super::hmac;
+    use super::{hmac, hmac_array, hmac_verify_batch, KeySetException, RotatingKeySet, TaggedMac, VerifyOutcome};
     use crate::sha1::SHA1Hash;
 
     const HMAC_EXAMPLE: &[u8] = b"The quick brown fox jumps over the lazy dog";
@@ -68,4 +468,183 @@ mod tests {
             "de7c9b85b8b78aa6bc8a7a36f70a90701c9db4d9"
         );
     }
+
+    #[test]
+    fn test_hmac_long_key() {
+        // keys longer than a block are replaced by their hash
+        let long_key = [0xAA_u8; 131];
+        assert_eq!(
+            hmac::<SHA1Hash, ()>(&(), &long_key, HMAC_EXAMPLE),
+            hmac::<SHA1Hash, ()>(&(), &SHA1Hash::digest_message(&(), &long_key).raw(), HMAC_EXAMPLE)
+        );
+    }
+
+    /// Compute the HMAC into a stack buffer, which only compiles if `N` is the maximum output size of `H`.
+    fn assert_hmac_array<H, const N: usize>(ctx: &H::Context)
+        where H: BlockHashFunction
+    {
+        let tag: [u8; N] = hmac_array::<H, N>(ctx, b"key", HMAC_EXAMPLE);
+        assert_eq!(tag.to_vec(), hmac::<H, H::Context>(ctx, b"key", HMAC_EXAMPLE));
+    }
+
+    #[test]
+    fn test_hmac_array() {
+        assert_hmac_array::<MD5Hash, 16>(&());
+        assert_hmac_array::<SHA1Hash, 20>(&());
+        assert_hmac_array::<SM3Hash, 32>(&());
+        assert_hmac_array::<WhirlpoolHash, 64>(&());
+        assert_hmac_array::<Blake2b, 64>(&Blake2bContext::default());
+        assert_hmac_array::<Blake2s, 32>(&Blake2sContext::default());
+
+        assert_eq!(
+            hex::encode(hmac_array::<MD5Hash, 16>(&(), b"key", HMAC_EXAMPLE)),
+            "80070713463e7749b90c2dc24911e275"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "truncates the maximum output size")]
+    fn test_hmac_array_truncated_output() {
+        hmac_array::<Blake2b, 64>(&Blake2bContext::new(32).unwrap(), b"key", HMAC_EXAMPLE);
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        let messages: Vec<&[u8]> = vec![HMAC_EXAMPLE, b"", b"another message", &[0x42; 200]];
+        let tags: Vec<Vec<u8>> = messages.iter().map(|message| hmac::<SHA1Hash, ()>(&(), b"key", message)).collect();
+
+        let mut corrupted_tag = tags[2].clone();
+        corrupted_tag[7] ^= 1;
+        let items: Vec<(&[u8], &[u8])> = vec![
+            (messages[0], &tags[0]),
+            (messages[1], &tags[0]),
+            (messages[1], &tags[1]),
+            (messages[2], &corrupted_tag),
+            (messages[3], &tags[3]),
+            (messages[2], &tags[2]),
+        ];
+
+        let results = hmac_verify_batch::<SHA1Hash>(&(), b"key", &items);
+        assert_eq!(results, vec![true, false, true, false, true, true]);
+        assert!(hmac_verify_batch::<SHA1Hash>(&(), b"other key", &items).iter().all(|valid| !valid));
+
+        // the batch agrees with verifying each item on its own
+        for ((message, tag), valid) in items.iter().zip(results) {
+            assert_eq!(hmac::<SHA1Hash, ()>(&(), b"key", message) == *tag, valid);
+        }
+    }
+
+    #[test]
+    fn test_verify_batch_edge_cases() {
+        assert!(hmac_verify_batch::<MD5Hash>(&(), b"key", &[]).is_empty());
+
+        let tag = hmac::<MD5Hash, ()>(&(), b"key", HMAC_EXAMPLE);
+        let items: Vec<(&[u8], &[u8])> = vec![(HMAC_EXAMPLE, &tag[..15]), (HMAC_EXAMPLE, &[]), (HMAC_EXAMPLE, &tag)];
+        assert_eq!(hmac_verify_batch::<MD5Hash>(&(), b"key", &items), vec![false, false, true]);
+    }
+
+    #[test]
+    fn test_rotating_key_set() {
+        let mut keys = RotatingKeySet::<SHA1Hash>::new((), 1, b"first key", None);
+        let first = keys.sign(HMAC_EXAMPLE);
+        assert_eq!(first.key_id, 1);
+        assert_eq!(first.tag, hmac::<SHA1Hash, ()>(&(), b"first key", HMAC_EXAMPLE));
+        assert_eq!(keys.verify(HMAC_EXAMPLE, &first.to_bytes()), VerifyOutcome::ValidCurrent);
+
+        keys.rotate(2, b"second key", None).unwrap();
+        assert_eq!(keys.rotate(1, b"another key", None), Err(KeySetException::DuplicateKeyId { key_id: 1 }));
+        let second = keys.sign(HMAC_EXAMPLE);
+        assert_eq!(keys.current_key_id(), 2);
+        assert_eq!(second.key_id, 2);
+        assert_eq!(keys.verify(HMAC_EXAMPLE, &second.to_bytes()), VerifyOutcome::ValidCurrent);
+        assert_eq!(keys.verify(HMAC_EXAMPLE, &first.to_bytes()), VerifyOutcome::ValidStale { key_id: 1 });
+        assert_eq!(keys.verify(b"other message", &first.to_bytes()), VerifyOutcome::Invalid);
+
+        // a tag is only checked against the key of its id
+        let relabeled = TaggedMac { key_id: 2, tag: first.tag.clone() };
+        assert_eq!(keys.verify(HMAC_EXAMPLE, &relabeled.to_bytes()), VerifyOutcome::Invalid);
+
+        assert_eq!(keys.remove(2), Err(KeySetException::CurrentKeyRemoved { key_id: 2 }));
+        assert_eq!(keys.remove(3), Err(KeySetException::UnknownKeyId { key_id: 3 }));
+        keys.remove(1).unwrap();
+        assert_eq!(keys.verify(HMAC_EXAMPLE, &first.to_bytes()), VerifyOutcome::Invalid);
+    }
+
+    #[test]
+    fn test_rotating_key_set_expiry() {
+        let mut keys = RotatingKeySet::<MD5Hash>::new((), 7, b"old key", Some(1000));
+        let old = keys.sign(HMAC_EXAMPLE).to_bytes();
+        keys.rotate(8, b"new key", None).unwrap();
+
+        assert_eq!(keys.verify_at(999, HMAC_EXAMPLE, &old), VerifyOutcome::ValidStale { key_id: 7 });
+        assert_eq!(keys.verify_at(1000, HMAC_EXAMPLE, &old), VerifyOutcome::ValidStale { key_id: 7 });
+        assert_eq!(keys.verify_at(1001, HMAC_EXAMPLE, &old), VerifyOutcome::Invalid);
+        assert_eq!(keys.verify(HMAC_EXAMPLE, &old), VerifyOutcome::Invalid);
+    }
+
+    #[test]
+    fn test_rotating_key_set_malformed_tags() {
+        let keys = RotatingKeySet::<SHA1Hash>::new((), 1, b"key", None);
+        let tagged = keys.sign(HMAC_EXAMPLE).to_bytes();
+
+        assert_eq!(TaggedMac::from_bytes(&tagged[..3]), None);
+        for length in 0..tagged.len() {
+            assert_eq!(keys.verify(HMAC_EXAMPLE, &tagged[..length]), VerifyOutcome::Invalid);
+        }
+
+        // unknown key id
+        let mut unknown = tagged.clone();
+        unknown[3] = 2;
+        assert_eq!(keys.verify(HMAC_EXAMPLE, &unknown), VerifyOutcome::Invalid);
+
+        let mut overlong = tagged;
+        overlong.push(0);
+        assert_eq!(keys.verify(HMAC_EXAMPLE, &overlong), VerifyOutcome::Invalid);
+    }
+
+    #[test]
+    fn test_rotating_key_set_encoding() {
+        let mut keys = RotatingKeySet::<SHA1Hash>::new((), 1, b"first key", Some(1 << 40));
+        keys.rotate(0xDEADBEEF, &[0x42; 100], None).unwrap();
+        let stale = keys.keys()[0].clone();
+        let old = RotatingKeySet::<SHA1Hash>::new((), 1, b"first key", None).sign(HMAC_EXAMPLE).to_bytes();
+
+        let encoded = keys.to_bytes();
+        let decoded = RotatingKeySet::<SHA1Hash>::from_bytes((), &encoded).unwrap();
+        assert_eq!(decoded.keys(), keys.keys());
+        assert_eq!(decoded.keys()[0], stale);
+        assert_eq!(decoded.sign(HMAC_EXAMPLE), keys.sign(HMAC_EXAMPLE));
+        assert_eq!(decoded.verify_at(0, HMAC_EXAMPLE, &old), VerifyOutcome::ValidStale { key_id: 1 });
+
+        for length in 0..encoded.len() {
+            assert_eq!(
+                RotatingKeySet::<SHA1Hash>::from_bytes((), &encoded[..length]).err(),
+                Some(KeySetException::MalformedKeySet {})
+            );
+        }
+
+        let mut trailing = encoded.clone();
+        trailing.push(0);
+        assert_eq!(
+            RotatingKeySet::<SHA1Hash>::from_bytes((), &trailing).err(),
+            Some(KeySetException::MalformedKeySet {})
+        );
+
+        let mut unknown_version = encoded;
+        unknown_version[0] = 2;
+        assert_eq!(
+            RotatingKeySet::<SHA1Hash>::from_bytes((), &unknown_version).err(),
+            Some(KeySetException::MalformedKeySet {})
+        );
+        assert_eq!(
+            RotatingKeySet::<SHA1Hash>::from_bytes((), &[1, 0, 0, 0, 0]).err(),
+            Some(KeySetException::MalformedKeySet {})
+        );
+
+        let duplicate = [1, 0, 0, 0, 2, 0, 0, 0, 5, 0, 0, 0, 0, 0, 0, 0, 0, 5, 0, 0, 0, 0, 0];
+        assert_eq!(
+            RotatingKeySet::<SHA1Hash>::from_bytes((), &duplicate).err(),
+            Some(KeySetException::DuplicateKeyId { key_id: 5 })
+        );
+    }
 }
@@ -0,0 +1,495 @@
+//! Argon2 memory-hard password hashing function as specified in RFC 9106. All three variants Argon2d, Argon2i and
+//! Argon2id are supported. Lanes are computed sequentially, so the parallelism parameter only influences the output,
+//! not the runtime.
+
+use std::convert::TryInto;
+
+use crate::{HashFunction, HashValue};
+use crate::blake::blake2b::{Blake2b, Blake2bContext};
+
+/// The Argon2 version implemented by this module (0x13).
+pub const ARGON2_VERSION: u32 = 0x13;
+
+/// Number of 64 bit words in a memory block (1 KiB).
+const BLOCK_WORDS: usize = 128;
+
+/// Number of slices each lane is divided into. Blocks may only reference blocks of other lanes, that lie in already
+/// completed slices.
+const SYNC_POINTS: usize = 4;
+
+/// Output length of Blake2b in bytes, used for the pre-hash and the variable-length hash.
+const BLAKE2B_OUTPUT_LENGTH: usize = 64;
+
+/// A memory block of the Argon2 memory matrix
+type Block = [u64; BLOCK_WORDS];
+
+/// The Argon2 variant, which decides how reference blocks are selected.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Argon2Variant {
+    /// Data-dependent memory access. Fastest and most resistant against tradeoff attacks, but susceptible to
+    /// side-channel attacks.
+    Argon2d,
+
+    /// Data-independent memory access, resistant against side-channel attacks.
+    Argon2i,
+
+    /// Data-independent memory access in the first half of the first pass and data-dependent access afterwards. This
+    /// is the recommended variant.
+    Argon2id,
+}
+
+impl Argon2Variant {
+    /// The type identifier `y` hashed into the pre-hash and the address blocks
+    fn type_id(self) -> u32 {
+        match self {
+            Argon2Variant::Argon2d => 0,
+            Argon2Variant::Argon2i => 1,
+            Argon2Variant::Argon2id => 2,
+        }
+    }
+}
+
+/// Parameters of an Argon2 invocation.
+pub struct Argon2Parameters {
+    /// The variant of Argon2 to use
+    pub variant: Argon2Variant,
+
+    /// Memory size in KiB. It must be at least `8 * parallelism` and is rounded down to a multiple of
+    /// `4 * parallelism`.
+    pub memory_kib: u32,
+
+    /// Number of passes over the memory. Must be at least one.
+    pub iterations: u32,
+
+    /// Number of lanes. Must be between 1 and 2^24 - 1.
+    pub parallelism: u32,
+
+    /// The salt. Must be at least 8 bytes long.
+    pub salt: Vec<u8>,
+
+    /// An optional secret value (pepper)
+    pub secret: Option<Vec<u8>>,
+
+    /// Optional associated data
+    pub associated_data: Option<Vec<u8>>,
+
+    /// The length of the derived key in bytes. Must be at least 4.
+    pub output_length: usize,
+}
+
+/// Exceptions that can arise from invalid Argon2 parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Argon2Exception {
+    /// The parallelism was zero or exceeded 2^24 - 1
+    InvalidParallelism { parallelism: u32 },
+
+    /// Less memory than the minimum of `8 * parallelism` KiB was given
+    MemoryTooSmall { required: u32, given: u32 },
+
+    /// The number of iterations was zero
+    ZeroIterations {},
+
+    /// The salt is shorter than 8 bytes
+    SaltTooShort { length: usize },
+
+    /// The requested output is shorter than 4 bytes
+    OutputTooShort { length: usize },
+}
+
+/// Derive a key of `params.output_length` bytes from `password` using Argon2 with the given parameters.
+///
+/// # Returns
+/// The derived key, or an `Argon2Exception` if the parameters are invalid.
+pub fn argon2_derive_key(params: &Argon2Parameters, password: &[u8]) -> Result<Vec<u8>, Argon2Exception> {
+    validate_parameters(params)?;
+
+    let lanes = params.parallelism as usize;
+    let memory_blocks = (params.memory_kib as usize / (SYNC_POINTS * lanes)) * SYNC_POINTS * lanes;
+    let lane_length = memory_blocks / lanes;
+    let segment_length = lane_length / SYNC_POINTS;
+
+    let pre_hash = initial_hash(params, password);
+
+    // the memory matrix is stored lane by lane, block (lane, column) is at index lane * lane_length + column
+    let mut memory: Vec<Block> = vec![[0_u64; BLOCK_WORDS]; memory_blocks];
+    for lane in 0..lanes {
+        for column in 0..2 {
+            let seed = [&pre_hash[..], &(column as u32).to_le_bytes(), &(lane as u32).to_le_bytes()].concat();
+            memory[lane * lane_length + column] = block_from_bytes(&variable_length_hash(&seed, 1024));
+        }
+    }
+
+    let instance = Instance {
+        variant: params.variant,
+        iterations: params.iterations,
+        memory_blocks,
+        lanes,
+        lane_length,
+        segment_length,
+    };
+
+    for pass in 0..params.iterations {
+        for slice in 0..SYNC_POINTS {
+            for lane in 0..lanes {
+                instance.fill_segment(&mut memory, pass, slice, lane);
+            }
+        }
+    }
+
+    // xor the last column and hash it to the output length
+    let mut final_block = memory[lane_length - 1];
+    for lane in 1..lanes {
+        xor_block(&mut final_block, &memory[lane * lane_length + lane_length - 1]);
+    }
+
+    Ok(variable_length_hash(&block_to_bytes(&final_block), params.output_length))
+}
+
+fn validate_parameters(params: &Argon2Parameters) -> Result<(), Argon2Exception> {
+    if params.parallelism == 0 || params.parallelism >= 1 << 24 {
+        return Err(Argon2Exception::InvalidParallelism { parallelism: params.parallelism });
+    }
+
+    if params.memory_kib < 8 * params.parallelism {
+        return Err(Argon2Exception::MemoryTooSmall {
+            required: 8 * params.parallelism,
+            given: params.memory_kib,
+        });
+    }
+
+    if params.iterations == 0 {
+        return Err(Argon2Exception::ZeroIterations {});
+    }
+
+    if params.salt.len() < 8 {
+        return Err(Argon2Exception::SaltTooShort { length: params.salt.len() });
+    }
+
+    if params.output_length < 4 {
+        return Err(Argon2Exception::OutputTooShort { length: params.output_length });
+    }
+
+    Ok(())
+}
+
+/// The pre-hash `H_0` over all parameters and inputs.
+fn initial_hash(params: &Argon2Parameters, password: &[u8]) -> Vec<u8> {
+    let ctx = Blake2bContext::new(BLAKE2B_OUTPUT_LENGTH).unwrap();
+    let mut state = Blake2b::init_hash(&ctx);
+
+    for value in &[
+        params.parallelism,
+        params.output_length as u32,
+        params.memory_kib,
+        params.iterations,
+        ARGON2_VERSION,
+        params.variant.type_id(),
+    ] {
+        Blake2b::update_hash(&mut state, &value.to_le_bytes());
+    }
+
+    let secret = params.secret.as_deref().unwrap_or(&[]);
+    let associated_data = params.associated_data.as_deref().unwrap_or(&[]);
+    for input in &[password, &params.salt[..], secret, associated_data] {
+        Blake2b::update_hash(&mut state, &(input.len() as u32).to_le_bytes());
+        Blake2b::update_hash(&mut state, input);
+    }
+
+    Blake2b::finish_hash(&mut state, &ctx).raw()
+}
+
+/// The variable-length hash function `H'` built from Blake2b, that produces `output_length` bytes.
+fn variable_length_hash(input: &[u8], output_length: usize) -> Vec<u8> {
+    let message = [&(output_length as u32).to_le_bytes(), input].concat();
+
+    if output_length <= BLAKE2B_OUTPUT_LENGTH {
+        return Blake2b::digest_message(&Blake2bContext::new(output_length).unwrap(), &message).raw();
+    }
+
+    // chain full Blake2b outputs and take the first half of each, the last hash has the remaining length
+    let ctx = Blake2bContext::new(BLAKE2B_OUTPUT_LENGTH).unwrap();
+    let full_hashes = output_length.div_ceil(32) - 2;
+
+    let mut output = Vec::with_capacity(output_length);
+    let mut hash = Blake2b::digest_message(&ctx, &message).raw();
+    output.extend_from_slice(&hash[..32]);
+
+    for _ in 1..full_hashes {
+        hash = Blake2b::digest_message(&ctx, &hash).raw();
+        output.extend_from_slice(&hash[..32]);
+    }
+
+    let last_ctx = Blake2bContext::new(output_length - 32 * full_hashes).unwrap();
+    output.extend(Blake2b::digest_message(&last_ctx, &hash).raw());
+    output
+}
+
+/// The derived memory layout of an Argon2 invocation
+struct Instance {
+    variant: Argon2Variant,
+    iterations: u32,
+    memory_blocks: usize,
+    lanes: usize,
+    lane_length: usize,
+    segment_length: usize,
+}
+
+impl Instance {
+    /// Compute all blocks of one segment, that is one slice of one lane.
+    fn fill_segment(&self, memory: &mut [Block], pass: u32, slice: usize, lane: usize) {
+        let data_independent = match self.variant {
+            Argon2Variant::Argon2d => false,
+            Argon2Variant::Argon2i => true,
+            Argon2Variant::Argon2id => pass == 0 && slice < SYNC_POINTS / 2,
+        };
+
+        // the first two blocks of each lane are already computed from the pre-hash
+        let start_index = if pass == 0 && slice == 0 { 2 } else { 0 };
+
+        let mut address_input: Block = [0_u64; BLOCK_WORDS];
+        let mut address_block: Block = [0_u64; BLOCK_WORDS];
+        if data_independent {
+            address_input[0] = pass as u64;
+            address_input[1] = lane as u64;
+            address_input[2] = slice as u64;
+            address_input[3] = self.memory_blocks as u64;
+            address_input[4] = self.iterations as u64;
+            address_input[5] = self.variant.type_id() as u64;
+
+            if start_index != 0 {
+                address_block = next_address_block(&mut address_input);
+            }
+        }
+
+        for index in start_index..self.segment_length {
+            let column = slice * self.segment_length + index;
+            let current = lane * self.lane_length + column;
+            let previous = if column == 0 { current + self.lane_length - 1 } else { current - 1 };
+
+            let pseudo_random = if data_independent {
+                if index % BLOCK_WORDS == 0 {
+                    address_block = next_address_block(&mut address_input);
+                }
+                address_block[index % BLOCK_WORDS]
+            } else {
+                memory[previous][0]
+            };
+
+            // the first slice of the first pass can only reference its own lane
+            let reference_lane = if pass == 0 && slice == 0 {
+                lane
+            } else {
+                ((pseudo_random >> 32) as usize) % self.lanes
+            };
+
+            let reference_column = self.reference_column(
+                pass, slice, index, pseudo_random & 0xFFFF_FFFF, reference_lane == lane);
+
+            let mut block = compress(&memory[previous], &memory[reference_lane * self.lane_length + reference_column]);
+            if pass > 0 {
+                xor_block(&mut block, &memory[current]);
+            }
+            memory[current] = block;
+        }
+    }
+
+    /// Map the pseudo random value `j1` to a column of the reference lane, among all blocks that may be referenced by
+    /// the block at `index` of the current segment.
+    fn reference_column(&self, pass: u32, slice: usize, index: usize, j1: u64, same_lane: bool) -> usize {
+        // blocks of the current slice in other lanes are not finished, and the directly preceding block is always
+        // used as the first input anyway
+        let reference_area_size = if pass == 0 {
+            if slice == 0 || same_lane {
+                slice * self.segment_length + index - 1
+            } else if index == 0 {
+                slice * self.segment_length - 1
+            } else {
+                slice * self.segment_length
+            }
+        } else if same_lane {
+            self.lane_length - self.segment_length + index - 1
+        } else if index == 0 {
+            self.lane_length - self.segment_length - 1
+        } else {
+            self.lane_length - self.segment_length
+        } as u64;
+
+        // non-uniform mapping, that favours recently computed blocks
+        let x = (j1 * j1) >> 32;
+        let y = (reference_area_size * x) >> 32;
+        let relative_position = (reference_area_size - 1 - y) as usize;
+
+        let start_position = if pass == 0 || slice == SYNC_POINTS - 1 { 0 } else { (slice + 1) * self.segment_length };
+
+        (start_position + relative_position) % self.lane_length
+    }
+}
+
+/// Increment the counter of the address input block and compute the next block of pseudo random addresses.
+fn next_address_block(address_input: &mut Block) -> Block {
+    let zero_block: Block = [0_u64; BLOCK_WORDS];
+
+    address_input[6] += 1;
+    compress(&zero_block, &compress(&zero_block, address_input))
+}
+
+/// The compression function `G`, that mixes two blocks into a new one.
+fn compress(x: &Block, y: &Block) -> Block {
+    let mut r = *x;
+    xor_block(&mut r, y);
+
+    // apply the permutation to the rows, then to the columns of the 8x8 matrix of 16 byte registers
+    let mut q = r;
+    for row in 0..8 {
+        let mut indices = [0_usize; 16];
+        for (i, index) in indices.iter_mut().enumerate() {
+            *index = 16 * row + i;
+        }
+        permute(&mut q, &indices);
+    }
+
+    for column in 0..8 {
+        let mut indices = [0_usize; 16];
+        for (i, index) in indices.iter_mut().enumerate() {
+            *index = 16 * (i / 2) + 2 * column + i % 2;
+        }
+        permute(&mut q, &indices);
+    }
+
+    xor_block(&mut q, &r);
+    q
+}
+
+/// The Blake2b round function without message words, applied to the 16 words of `block` at `indices`.
+fn permute(block: &mut Block, indices: &[usize; 16]) {
+    let mut v = [0_u64; 16];
+    for (word, &index) in v.iter_mut().zip(indices.iter()) {
+        *word = block[index];
+    }
+
+    mix(&mut v, 0, 4, 8, 12);
+    mix(&mut v, 1, 5, 9, 13);
+    mix(&mut v, 2, 6, 10, 14);
+    mix(&mut v, 3, 7, 11, 15);
+    mix(&mut v, 0, 5, 10, 15);
+    mix(&mut v, 1, 6, 11, 12);
+    mix(&mut v, 2, 7, 8, 13);
+    mix(&mut v, 3, 4, 9, 14);
+
+    for (word, &index) in v.iter().zip(indices.iter()) {
+        block[index] = *word;
+    }
+}
+
+/// The Blake2b mixing function with the additions replaced by the multiplication-hardened `a + b + 2 * lo(a) * lo(b)`
+fn mix(v: &mut [u64; 16], a: usize, b: usize, c: usize, d: usize) {
+    v[a] = hardened_add(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(32);
+    v[c] = hardened_add(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(24);
+
+    v[a] = hardened_add(v[a], v[b]);
+    v[d] = (v[d] ^ v[a]).rotate_right(16);
+    v[c] = hardened_add(v[c], v[d]);
+    v[b] = (v[b] ^ v[c]).rotate_right(63);
+}
+
+fn hardened_add(x: u64, y: u64) -> u64 {
+    let product = (x & 0xFFFF_FFFF).wrapping_mul(y & 0xFFFF_FFFF);
+    x.wrapping_add(y).wrapping_add(product.wrapping_mul(2))
+}
+
+fn xor_block(target: &mut Block, other: &Block) {
+    for (word, other_word) in target.iter_mut().zip(other.iter()) {
+        *word ^= other_word;
+    }
+}
+
+fn block_from_bytes(bytes: &[u8]) -> Block {
+    let mut block = [0_u64; BLOCK_WORDS];
+    for (word, chunk) in block.iter_mut().zip(bytes.chunks_exact(8)) {
+        *word = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    block
+}
+
+fn block_to_bytes(block: &Block) -> Vec<u8> {
+    block.iter().flat_map(|word| word.to_le_bytes().to_vec()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The parameters of the RFC 9106 test vectors for the given variant
+    fn rfc_parameters(variant: Argon2Variant) -> Argon2Parameters {
+        Argon2Parameters {
+            variant,
+            memory_kib: 32,
+            iterations: 3,
+            parallelism: 4,
+            salt: vec![0x02; 16],
+            secret: Some(vec![0x03; 8]),
+            associated_data: Some(vec![0x04; 12]),
+            output_length: 32,
+        }
+    }
+
+    #[test]
+    fn test_argon2d_vector() {
+        assert_eq!(
+            hex::encode(argon2_derive_key(&rfc_parameters(Argon2Variant::Argon2d), &[0x01; 32]).unwrap()),
+            "512b391b6f1162975371d30919734294f868e3be3984f3c1a13a4db9fabe4acb"
+        );
+    }
+
+    #[test]
+    fn test_argon2i_vector() {
+        assert_eq!(
+            hex::encode(argon2_derive_key(&rfc_parameters(Argon2Variant::Argon2i), &[0x01; 32]).unwrap()),
+            "c814d9d1dc7f37aa13f0d77f2494bda1c8de6b016dd388d29952a4c4672b6ce8"
+        );
+    }
+
+    #[test]
+    fn test_argon2id_vector() {
+        assert_eq!(
+            hex::encode(argon2_derive_key(&rfc_parameters(Argon2Variant::Argon2id), &[0x01; 32]).unwrap()),
+            "0d640df58d78766c08c037a34a8b53c9d01ef0452d75b65eb52520e96b01e659"
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let params = Argon2Parameters {
+            variant: Argon2Variant::Argon2id,
+            memory_kib: 16,
+            iterations: 1,
+            parallelism: 1,
+            salt: b"somesalt".to_vec(),
+            secret: None,
+            associated_data: None,
+            output_length: 100,
+        };
+
+        let key = argon2_derive_key(&params, b"password").unwrap();
+        assert_eq!(key.len(), 100);
+        assert_eq!(key, argon2_derive_key(&params, b"password").unwrap());
+        assert_ne!(key, argon2_derive_key(&params, b"passwort").unwrap());
+
+        let params = Argon2Parameters { salt: b"otherSalt".to_vec(), ..params };
+        assert_ne!(key, argon2_derive_key(&params, b"password").unwrap());
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        let params = Argon2Parameters { memory_kib: 16, ..rfc_parameters(Argon2Variant::Argon2id) };
+        assert_eq!(
+            argon2_derive_key(&params, b"password"),
+            Err(Argon2Exception::MemoryTooSmall { required: 32, given: 16 })
+        );
+
+        let params = Argon2Parameters { salt: vec![0; 4], ..rfc_parameters(Argon2Variant::Argon2id) };
+        assert_eq!(argon2_derive_key(&params, b"password"), Err(Argon2Exception::SaltTooShort { length: 4 }));
+    }
+}
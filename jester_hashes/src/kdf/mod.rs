@@ -0,0 +1,145 @@
+//! Traits and implementations for key derivation functions
+
+use std::f64;
+use std::ops::Deref;
+
+use crate::BlockHashFunction;
+use crate::hmac::hmac;
+
+pub mod argon2;
+pub mod password_hash;
+pub mod scrypt;
+
+pub use password_hash::{KdfPolicy, ParseError, PasswordHash, PasswordHashAlgorithm};
+
+/// HMAC based key derivation function. A key of length `output_length` is generated.
+pub fn hkdf_derive_key<Hash, Context>(
+    ctx: &Context, salt: &[u8], ikm: &[u8], output_length: usize, info: &[u8]) -> Vec<u8>
+    where Hash: BlockHashFunction<Context=Context>
+{
+    let pseudo_random_key = hmac::<Hash, Context>(ctx, salt, ikm);
+    let partials: usize = f64::ceil(output_length as f64 / Hash::output_size(ctx) as f64) as usize;
+    let mut parts: Vec<Vec<u8>> = vec![vec![]; partials + 1];
+
+    for i in 1..=partials {
+        parts[i] = hmac::<Hash, Context>(ctx, &*pseudo_random_key,
+                        &vec![parts[i - 1].deref(), info, &[(i & 0xFF) as u8]].concat())
+    }
+
+    parts.concat()
+}
+
+/// Password based key derivation function 2 (RFC 8018) using HMAC as pseudo random function. A key of length
+/// `output_length` is derived from `password` and `salt` using `iterations` HMAC invocations per output block.
+pub fn pbkdf2_derive_key<Hash, Context>(
+    ctx: &Context, password: &[u8], salt: &[u8], iterations: u32, output_length: usize) -> Vec<u8>
+    where Hash: BlockHashFunction<Context=Context>
+{
+    let mut output = Vec::with_capacity(output_length);
+    let mut block_index: u32 = 1;
+
+    while output.len() < output_length {
+        let mut u = hmac::<Hash, Context>(ctx, password, &[salt, &block_index.to_be_bytes()].concat());
+        let mut block = u.clone();
+
+        for _ in 1..iterations {
+            u = hmac::<Hash, Context>(ctx, password, &u);
+            block.iter_mut().zip(u.iter()).for_each(|(b, u)| *b ^= u);
+        }
+
+        let length = block.len().min(output_length - output.len());
+        output.extend_from_slice(&block[..length]);
+        block_index += 1;
+    }
+
+    output
+}
+
+/// Domain separation byte appended to the HMAC message that derives the next chain key.
+const CHAIN_KEY_DOMAIN: u8 = 0x01;
+
+/// Domain separation byte appended to the HMAC message that derives the output key.
+const OUTPUT_KEY_DOMAIN: u8 = 0x02;
+
+/// A key derivation chain built from a block hash function. Each step consumes a chain key and some input and
+/// produces the next chain key and an output key, both derived by HMAC keyed with the current chain key. The two
+/// HMAC invocations are separated by distinct domain bytes appended to the input.
+pub struct HashKdfChain<H>
+    where H: BlockHashFunction
+{
+    ctx: H::Context,
+}
+
+impl<H> HashKdfChain<H>
+    where H: BlockHashFunction
+{
+    /// Create a new chain using the hash function `H` with the given context.
+    pub fn new(ctx: H::Context) -> Self {
+        HashKdfChain { ctx }
+    }
+
+    /// The hash context used by this chain
+    pub fn context(&self) -> &H::Context {
+        &self.ctx
+    }
+
+    /// Derive the next chain key and an output key from the current `chain_key` and `input`.
+    ///
+    /// #Outputs
+    /// Returns a tuple of the next chain key and the output key
+    pub fn step(&self, chain_key: &[u8], input: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let next_chain_key = hmac::<H, H::Context>(&self.ctx, chain_key, &[input, &[CHAIN_KEY_DOMAIN]].concat());
+        let output_key = hmac::<H, H::Context>(&self.ctx, chain_key, &[input, &[OUTPUT_KEY_DOMAIN]].concat());
+
+        (next_chain_key, output_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sha1::SHA1Hash;
+
+    #[test]
+    fn test_hdkf() {
+        // TODO
+    }
+
+    #[test]
+    fn test_pbkdf2() {
+        // test vectors from RFC 6070
+        assert_eq!(
+            hex::encode(pbkdf2_derive_key::<SHA1Hash, ()>(&(), b"password", b"salt", 1, 20)),
+            "0c60c80f961f0e71f3a9b524af6012062fe037a6"
+        );
+
+        assert_eq!(
+            hex::encode(pbkdf2_derive_key::<SHA1Hash, ()>(&(), b"password", b"salt", 4096, 20)),
+            "4b007901b765489abead49d926f721d065a429c1"
+        );
+
+        assert_eq!(
+            hex::encode(pbkdf2_derive_key::<SHA1Hash, ()>(
+                &(), b"passwordPASSWORDpassword", b"saltSALTsaltSALTsaltSALTsaltSALTsalt", 4096, 25)),
+            "3d2eec4fe41c849b80c8d83662c0e44a8b291a964cf2f07038"
+        );
+    }
+
+    #[test]
+    fn test_chain_deterministic() {
+        let chain = HashKdfChain::<SHA1Hash>::new(());
+
+        assert_eq!(chain.step(b"chain key", b"input"), chain.step(b"chain key", b"input"));
+    }
+
+    #[test]
+    fn test_chain_divergence() {
+        let chain = HashKdfChain::<SHA1Hash>::new(());
+        let (chain_key, output_key) = chain.step(b"chain key", b"input");
+        let (other_chain_key, other_output_key) = chain.step(b"chain key", b"other input");
+
+        assert_ne!(chain_key, output_key);
+        assert_ne!(chain_key, other_chain_key);
+        assert_ne!(output_key, other_output_key);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,351 @@
+//! Storage format for password hashes. A `PasswordHash` bundles the derived key with the algorithm, its parameters and
+//! the salt, and is encoded in the PHC string format, e.g. `$pbkdf2-sha1$i=1000$<salt>$<hash>` or
+//! `$argon2id$v=19$m=65536,t=3,p=4$<salt>$<hash>`, where salt and hash are unpadded base64.
+
+use crate::base64;
+use crate::constant_time_eq;
+use crate::kdf::argon2::{argon2_derive_key, Argon2Exception, Argon2Parameters, Argon2Variant, ARGON2_VERSION};
+use crate::kdf::pbkdf2_derive_key;
+use crate::sha1::SHA1Hash;
+
+/// The minimum length of the salt of a parsed hash in bytes, which is the minimum salt length of Argon2
+const MIN_SALT_LENGTH: usize = 8;
+
+/// The minimum length of the hash of a parsed hash in bytes. Shorter hashes are too easy to match by chance.
+const MIN_HASH_LENGTH: usize = 16;
+
+/// A password hashing algorithm together with its cost parameters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PasswordHashAlgorithm {
+    /// PBKDF2 with HMAC-SHA1
+    Pbkdf2Sha1 { iterations: u32 },
+
+    /// Argon2 of the given variant, without secret or associated data
+    Argon2 {
+        variant: Argon2Variant,
+        memory_kib: u32,
+        iterations: u32,
+        parallelism: u32,
+    },
+}
+
+impl PasswordHashAlgorithm {
+    /// Derive `output_length` bytes from `password` and `salt` using this algorithm.
+    fn derive_key(&self, password: &[u8], salt: &[u8], output_length: usize) -> Result<Vec<u8>, Argon2Exception> {
+        match *self {
+            PasswordHashAlgorithm::Pbkdf2Sha1 { iterations } =>
+                Ok(pbkdf2_derive_key::<SHA1Hash, ()>(&(), password, salt, iterations, output_length)),
+            PasswordHashAlgorithm::Argon2 { variant, memory_kib, iterations, parallelism } => {
+                let params = Argon2Parameters {
+                    variant,
+                    memory_kib,
+                    iterations,
+                    parallelism,
+                    salt: salt.to_vec(),
+                    secret: None,
+                    associated_data: None,
+                    output_length,
+                };
+
+                argon2_derive_key(&params, password)
+            }
+        }
+    }
+
+    /// The algorithm identifier of the PHC string
+    fn identifier(&self) -> &'static str {
+        match self {
+            PasswordHashAlgorithm::Pbkdf2Sha1 { .. } => "pbkdf2-sha1",
+            PasswordHashAlgorithm::Argon2 { variant: Argon2Variant::Argon2d, .. } => "argon2d",
+            PasswordHashAlgorithm::Argon2 { variant: Argon2Variant::Argon2i, .. } => "argon2i",
+            PasswordHashAlgorithm::Argon2 { variant: Argon2Variant::Argon2id, .. } => "argon2id",
+        }
+    }
+}
+
+/// The parameters new password hashes should be created with. Stored hashes that deviate from the policy should be
+/// recomputed on the next successful login.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct KdfPolicy {
+    pub algorithm: PasswordHashAlgorithm,
+    pub output_length: usize,
+}
+
+/// A stored password hash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PasswordHash {
+    pub algorithm: PasswordHashAlgorithm,
+    pub salt: Vec<u8>,
+    pub hash: Vec<u8>,
+}
+
+/// Errors that can arise when parsing a PHC formatted password hash string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The string does not consist of the expected `$`-separated fields
+    InvalidFormat {},
+
+    /// The algorithm identifier is not supported
+    UnknownAlgorithm { identifier: String },
+
+    /// The Argon2 version is not supported
+    UnsupportedVersion { version: String },
+
+    /// A parameter is missing or malformed, or the salt or hash is too short
+    InvalidParameter { parameter: &'static str },
+
+    /// The salt or hash is not valid base64
+    InvalidBase64 {},
+}
+
+impl PasswordHash {
+    /// Hash `password` with a fresh `salt` according to `policy`.
+    ///
+    /// # Returns
+    /// The password hash, or an `Argon2Exception` if the policy has invalid Argon2 parameters.
+    pub fn create(password: &[u8], salt: &[u8], policy: &KdfPolicy) -> Result<Self, Argon2Exception> {
+        Ok(PasswordHash {
+            algorithm: policy.algorithm,
+            salt: salt.to_vec(),
+            hash: policy.algorithm.derive_key(password, salt, policy.output_length)?,
+        })
+    }
+
+    /// Encode this hash as a PHC string.
+    pub fn encode(&self) -> String {
+        let parameters = match self.algorithm {
+            PasswordHashAlgorithm::Pbkdf2Sha1 { iterations } => format!("i={}", iterations),
+            PasswordHashAlgorithm::Argon2 { memory_kib, iterations, parallelism, .. } =>
+                format!("v={}$m={},t={},p={}", ARGON2_VERSION, memory_kib, iterations, parallelism),
+        };
+
+        format!(
+            "${}${}${}${}",
+            self.algorithm.identifier(),
+            parameters,
+            base64::encode(&self.salt),
+            base64::encode(&self.hash)
+        )
+    }
+
+    /// Parse a PHC string as produced by `encode`. Salts shorter than eight bytes, hashes shorter than sixteen bytes
+    /// and zero PBKDF2 iterations are rejected with `ParseError::InvalidParameter`.
+    pub fn parse(encoded: &str) -> Result<Self, ParseError> {
+        let fields: Vec<&str> = encoded.split('$').collect();
+        if fields.len() < 2 || !fields[0].is_empty() {
+            return Err(ParseError::InvalidFormat {});
+        }
+
+        let (algorithm, remaining_fields) = match fields[1] {
+            "pbkdf2-sha1" => {
+                if fields.len() != 5 {
+                    return Err(ParseError::InvalidFormat {});
+                }
+
+                let iterations = parse_parameter(fields[2], "i")?;
+                if iterations == 0 {
+                    return Err(ParseError::InvalidParameter { parameter: "i" });
+                }
+                (PasswordHashAlgorithm::Pbkdf2Sha1 { iterations }, &fields[3..])
+            }
+            identifier @ "argon2d" | identifier @ "argon2i" | identifier @ "argon2id" => {
+                if fields.len() != 6 {
+                    return Err(ParseError::InvalidFormat {});
+                }
+
+                if parse_parameter(fields[2], "v").ok() != Some(ARGON2_VERSION) {
+                    return Err(ParseError::UnsupportedVersion { version: fields[2].to_string() });
+                }
+
+                let parameters: Vec<&str> = fields[3].split(',').collect();
+                if parameters.len() != 3 {
+                    return Err(ParseError::InvalidFormat {});
+                }
+
+                let variant = match identifier {
+                    "argon2d" => Argon2Variant::Argon2d,
+                    "argon2i" => Argon2Variant::Argon2i,
+                    _ => Argon2Variant::Argon2id,
+                };
+
+                let algorithm = PasswordHashAlgorithm::Argon2 {
+                    variant,
+                    memory_kib: parse_parameter(parameters[0], "m")?,
+                    iterations: parse_parameter(parameters[1], "t")?,
+                    parallelism: parse_parameter(parameters[2], "p")?,
+                };
+                (algorithm, &fields[4..])
+            }
+            identifier => return Err(ParseError::UnknownAlgorithm { identifier: identifier.to_string() }),
+        };
+
+        let salt = base64::decode(remaining_fields[0]).ok_or(ParseError::InvalidBase64 {})?;
+        let hash = base64::decode(remaining_fields[1]).ok_or(ParseError::InvalidBase64 {})?;
+        if salt.len() < MIN_SALT_LENGTH {
+            return Err(ParseError::InvalidParameter { parameter: "salt" });
+        }
+        if hash.len() < MIN_HASH_LENGTH {
+            return Err(ParseError::InvalidParameter { parameter: "hash" });
+        }
+
+        Ok(PasswordHash { algorithm, salt, hash })
+    }
+
+    /// Check whether `password` matches this hash. The comparison of the derived key is done in constant time. An
+    /// empty hash matches no password.
+    pub fn verify(&self, password: &[u8]) -> bool {
+        if self.hash.is_empty() {
+            return false;
+        }
+
+        match self.algorithm.derive_key(password, &self.salt, self.hash.len()) {
+            Ok(derived_key) => constant_time_eq(&derived_key, &self.hash),
+            Err(_) => false,
+        }
+    }
+
+    /// Whether this hash was created with other parameters than `policy` demands, so it should be replaced by a
+    /// fresh hash of the password.
+    pub fn rehash_needed(&self, policy: &KdfPolicy) -> bool {
+        self.algorithm != policy.algorithm || self.hash.len() != policy.output_length
+    }
+}
+
+/// Parse a `name=value` parameter field
+fn parse_parameter(field: &str, name: &'static str) -> Result<u32, ParseError> {
+    let mut split = field.splitn(2, '=');
+    match (split.next(), split.next()) {
+        (Some(key), Some(value)) if key == name =>
+            value.parse().map_err(|_| ParseError::InvalidParameter { parameter: name }),
+        _ => Err(ParseError::InvalidParameter { parameter: name }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PBKDF2_POLICY: KdfPolicy = KdfPolicy {
+        algorithm: PasswordHashAlgorithm::Pbkdf2Sha1 { iterations: 1000 },
+        output_length: 20,
+    };
+
+    const ARGON2_POLICY: KdfPolicy = KdfPolicy {
+        algorithm: PasswordHashAlgorithm::Argon2 {
+            variant: Argon2Variant::Argon2id,
+            memory_kib: 16,
+            iterations: 2,
+            parallelism: 1,
+        },
+        output_length: 32,
+    };
+
+    #[test]
+    fn test_encode_parse() {
+        let hash = PasswordHash::create(b"password", b"somesalt", &PBKDF2_POLICY).unwrap();
+        let encoded = hash.encode();
+        assert!(encoded.starts_with("$pbkdf2-sha1$i=1000$c29tZXNhbHQ$"));
+        assert_eq!(PasswordHash::parse(&encoded), Ok(hash));
+
+        let hash = PasswordHash::create(b"password", b"somesalt", &ARGON2_POLICY).unwrap();
+        let encoded = hash.encode();
+        assert!(encoded.starts_with("$argon2id$v=19$m=16,t=2,p=1$c29tZXNhbHQ$"));
+        assert_eq!(PasswordHash::parse(&encoded), Ok(hash));
+    }
+
+    #[test]
+    fn test_verify() {
+        for policy in &[PBKDF2_POLICY, ARGON2_POLICY] {
+            let hash = PasswordHash::parse(&PasswordHash::create(b"password", b"somesalt", policy).unwrap().encode())
+                .unwrap();
+
+            assert!(hash.verify(b"password"));
+            assert!(!hash.verify(b"passwort"));
+            assert!(!hash.verify(b""));
+        }
+    }
+
+    #[test]
+    fn test_malformed() {
+        assert_eq!(PasswordHash::parse(""), Err(ParseError::InvalidFormat {}));
+        assert_eq!(PasswordHash::parse("pbkdf2-sha1$i=1$c29tZQ$c29tZQ"), Err(ParseError::InvalidFormat {}));
+        assert_eq!(PasswordHash::parse("$pbkdf2-sha1$i=1$c29tZQ"), Err(ParseError::InvalidFormat {}));
+        assert_eq!(
+            PasswordHash::parse("$scrypt$i=1$c29tZQ$c29tZQ"),
+            Err(ParseError::UnknownAlgorithm { identifier: "scrypt".to_string() })
+        );
+        assert_eq!(
+            PasswordHash::parse("$pbkdf2-sha1$n=1$c29tZQ$c29tZQ"),
+            Err(ParseError::InvalidParameter { parameter: "i" })
+        );
+        assert_eq!(
+            PasswordHash::parse("$pbkdf2-sha1$i=many$c29tZQ$c29tZQ"),
+            Err(ParseError::InvalidParameter { parameter: "i" })
+        );
+        assert_eq!(PasswordHash::parse("$pbkdf2-sha1$i=1$c29tZQ$c2*tZQ"), Err(ParseError::InvalidBase64 {}));
+        assert_eq!(
+            PasswordHash::parse("$argon2id$v=16$m=16,t=2,p=1$c29tZQ$c29tZQ"),
+            Err(ParseError::UnsupportedVersion { version: "v=16".to_string() })
+        );
+        assert_eq!(
+            PasswordHash::parse("$argon2id$v=19$m=16,t=2$c29tZQ$c29tZQ"),
+            Err(ParseError::InvalidFormat {})
+        );
+    }
+
+    #[test]
+    fn test_short_fields() {
+        // the salt "somesalt" and the sixteen byte hash "some longer hash"
+        let salt = "c29tZXNhbHQ";
+        let hash = "c29tZSBsb25nZXIgaGFzaA";
+        assert!(PasswordHash::parse(&format!("$pbkdf2-sha1$i=1000${}${}", salt, hash)).is_ok());
+
+        assert_eq!(
+            PasswordHash::parse(&format!("$pbkdf2-sha1$i=1000${}$", salt)),
+            Err(ParseError::InvalidParameter { parameter: "hash" })
+        );
+        assert_eq!(
+            PasswordHash::parse(&format!("$pbkdf2-sha1$i=1000${}$c29tZQ", salt)),
+            Err(ParseError::InvalidParameter { parameter: "hash" })
+        );
+        assert_eq!(
+            PasswordHash::parse(&format!("$pbkdf2-sha1$i=1000$${}", hash)),
+            Err(ParseError::InvalidParameter { parameter: "salt" })
+        );
+        assert_eq!(
+            PasswordHash::parse(&format!("$pbkdf2-sha1$i=1000$c29tZQ${}", hash)),
+            Err(ParseError::InvalidParameter { parameter: "salt" })
+        );
+        assert_eq!(
+            PasswordHash::parse(&format!("$pbkdf2-sha1$i=0${}${}", salt, hash)),
+            Err(ParseError::InvalidParameter { parameter: "i" })
+        );
+    }
+
+    #[test]
+    fn test_verify_empty_hash() {
+        let hash = PasswordHash {
+            algorithm: PBKDF2_POLICY.algorithm,
+            salt: b"somesalt".to_vec(),
+            hash: vec![],
+        };
+        assert!(!hash.verify(b"password"));
+        assert!(!hash.verify(b""));
+    }
+
+    #[test]
+    fn test_rehash_needed() {
+        let hash = PasswordHash::create(b"password", b"somesalt", &PBKDF2_POLICY).unwrap();
+        assert!(!hash.rehash_needed(&PBKDF2_POLICY));
+        assert!(hash.rehash_needed(&ARGON2_POLICY));
+
+        let stronger_policy = KdfPolicy {
+            algorithm: PasswordHashAlgorithm::Pbkdf2Sha1 { iterations: 2000 },
+            ..PBKDF2_POLICY
+        };
+        assert!(hash.rehash_needed(&stronger_policy));
+
+        let longer_policy = KdfPolicy { output_length: 32, ..PBKDF2_POLICY };
+        assert!(hash.rehash_needed(&longer_policy));
+    }
+}
@@ -0,0 +1,382 @@
+//! The scrypt memory-hard password-based key derivation function as specified in RFC 7914. The password is expanded
+//! by PBKDF2, mixed by the sequential memory-hard function ROMix built on the Salsa20/8 core and compressed by PBKDF2
+//! again. RFC 7914 instantiates PBKDF2 with HMAC-SHA-256; the hash function is a type parameter here, so the derived
+//! keys only match other scrypt implementations if it is SHA-256. Blocks are mixed sequentially, so the
+//! parallelization parameter only influences the output, not the runtime.
+
+use std::convert::TryInto;
+use std::mem::swap;
+
+use crate::BlockHashFunction;
+use crate::kdf::pbkdf2_derive_key;
+
+/// Number of 32 bit words in a Salsa20 block (64 bytes).
+const SALSA_WORDS: usize = 16;
+
+/// Number of bytes of a scrypt block per unit of the block size parameter `r`.
+const BLOCK_BYTES_PER_R: usize = 128;
+
+/// Parameters of a scrypt invocation.
+pub struct ScryptParameters {
+    /// The CPU/memory cost `N`. Must be a power of two greater than one and less than `2^(16 * block_size)`.
+    pub cost: u64,
+
+    /// The block size `r`. Must be at least one.
+    pub block_size: u32,
+
+    /// The parallelization parameter `p`. Must be at least one and `parallelism * block_size` must be less than
+    /// `2^30`.
+    pub parallelism: u32,
+
+    /// The salt
+    pub salt: Vec<u8>,
+
+    /// The length of the derived key in bytes. Must be at least one.
+    pub output_length: usize,
+
+    /// The maximum number of bytes the derivation may allocate for its blocks. Parameters requiring more memory are
+    /// rejected, so parameters received from an untrusted source cannot exhaust the memory.
+    pub memory_limit: usize,
+}
+
+/// Exceptions that can arise from invalid scrypt parameters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScryptException {
+    /// The cost was not a power of two greater than one
+    CostNotPowerOfTwo { cost: u64 },
+
+    /// The cost was not less than `2^(16 * block_size)`
+    CostTooLarge { cost: u64, block_size: u32 },
+
+    /// The block size was zero
+    ZeroBlockSize {},
+
+    /// The parallelism was zero or its product with the block size was not less than 2^30
+    InvalidParallelism { parallelism: u32, block_size: u32 },
+
+    /// The requested output was empty
+    ZeroOutputLength {},
+
+    /// The parameters require more memory than the given limit
+    MemoryLimitExceeded { required: usize, limit: usize },
+}
+
+/// Derive a key of `params.output_length` bytes from `password` using scrypt, with PBKDF2 instantiated with HMAC
+/// over the hash function `H`.
+///
+/// # Returns
+/// The derived key, or a `ScryptException` if the parameters are invalid.
+pub fn scrypt_derive_key<H>(
+    ctx: &H::Context,
+    params: &ScryptParameters,
+    password: &[u8],
+) -> Result<Vec<u8>, ScryptException>
+    where H: BlockHashFunction
+{
+    validate_parameters(params)?;
+
+    let r = params.block_size as usize;
+    let block_words = 2 * r * SALSA_WORDS;
+    let expanded = pbkdf2_derive_key::<H, H::Context>(
+        ctx, password, &params.salt, 1, params.parallelism as usize * BLOCK_BYTES_PER_R * r);
+
+    let mut words = expanded
+        .chunks_exact(4)
+        .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+        .collect::<Vec<_>>();
+    for block in words.chunks_exact_mut(block_words) {
+        ro_mix(block, params.cost as usize, r);
+    }
+
+    let mut mixed = Vec::with_capacity(expanded.len());
+    for word in words.iter() {
+        mixed.extend_from_slice(&word.to_le_bytes());
+    }
+
+    Ok(pbkdf2_derive_key::<H, H::Context>(ctx, password, &mixed, 1, params.output_length))
+}
+
+fn validate_parameters(params: &ScryptParameters) -> Result<(), ScryptException> {
+    if params.block_size == 0 {
+        return Err(ScryptException::ZeroBlockSize {});
+    }
+
+    if params.cost < 2 || !params.cost.is_power_of_two() {
+        return Err(ScryptException::CostNotPowerOfTwo { cost: params.cost });
+    }
+
+    if params.block_size < 4 && params.cost >= 1 << (16 * params.block_size) {
+        return Err(ScryptException::CostTooLarge { cost: params.cost, block_size: params.block_size });
+    }
+
+    if params.parallelism == 0 || params.parallelism as u64 * params.block_size as u64 >= 1 << 30 {
+        return Err(ScryptException::InvalidParallelism {
+            parallelism: params.parallelism,
+            block_size: params.block_size,
+        });
+    }
+
+    if params.output_length == 0 {
+        return Err(ScryptException::ZeroOutputLength {});
+    }
+
+    let required = required_memory(params);
+    if required > params.memory_limit {
+        return Err(ScryptException::MemoryLimitExceeded { required, limit: params.memory_limit });
+    }
+
+    Ok(())
+}
+
+/// The number of bytes allocated for the blocks: the expanded password of `p` blocks, the `N` blocks of the ROMix
+/// scratch vector and two working blocks. Saturates at `usize::MAX`.
+fn required_memory(params: &ScryptParameters) -> usize {
+    let block_bytes = BLOCK_BYTES_PER_R as u128 * params.block_size as u128;
+    let blocks = params.cost as u128 + params.parallelism as u128 + 2;
+    (block_bytes * blocks).try_into().unwrap_or(usize::MAX)
+}
+
+/// The sequential memory-hard function ROMix, which mixes `block` in place. The block consists of `2 * r` Salsa20
+/// blocks and `cost` must be a power of two.
+fn ro_mix(block: &mut [u32], cost: usize, r: usize) {
+    let block_words = block.len();
+    let mut scratch = vec![0u32; cost * block_words];
+    let mut x = block.to_vec();
+    let mut y = vec![0u32; block_words];
+
+    for scratch_block in scratch.chunks_exact_mut(block_words) {
+        scratch_block.copy_from_slice(&x);
+        block_mix(&x, &mut y, r);
+        swap(&mut x, &mut y);
+    }
+
+    for _ in 0..cost {
+        let j = integerify(&x, r) & (cost - 1);
+        x.iter_mut()
+            .zip(&scratch[j * block_words..(j + 1) * block_words])
+            .for_each(|(word, scratch_word)| *word ^= scratch_word);
+        block_mix(&x, &mut y, r);
+        swap(&mut x, &mut y);
+    }
+
+    block.copy_from_slice(&x);
+}
+
+/// The lower bits of the first word of the last Salsa20 block in `block`. Only the lower 64 bits are used, which
+/// suffices for every cost accepted by `validate_parameters` on 64 bit platforms.
+fn integerify(block: &[u32], r: usize) -> usize {
+    let last = (2 * r - 1) * SALSA_WORDS;
+    (block[last] as u64 | (block[last + 1] as u64) << 32) as usize
+}
+
+/// The scryptBlockMix function, which mixes the `2 * r` Salsa20 blocks of `input` into `output`. The mixed blocks
+/// with even indices are written to the first half of the output, those with odd indices to the second half.
+fn block_mix(input: &[u32], output: &mut [u32], r: usize) {
+    let mut x: [u32; SALSA_WORDS] = input[(2 * r - 1) * SALSA_WORDS..].try_into().unwrap();
+
+    for (i, chunk) in input.chunks_exact(SALSA_WORDS).enumerate() {
+        x.iter_mut().zip(chunk).for_each(|(word, input_word)| *word ^= input_word);
+        salsa20_8(&mut x);
+
+        let target = (i / 2 + (i % 2) * r) * SALSA_WORDS;
+        output[target..target + SALSA_WORDS].copy_from_slice(&x);
+    }
+}
+
+/// The Salsa20/8 core, the Salsa20 hash function reduced to eight rounds, applied to `block` in place.
+fn salsa20_8(block: &mut [u32; SALSA_WORDS]) {
+    let mut x = *block;
+
+    for _ in 0..4 {
+        // column round
+        quarter_round(&mut x, 0, 4, 8, 12);
+        quarter_round(&mut x, 5, 9, 13, 1);
+        quarter_round(&mut x, 10, 14, 2, 6);
+        quarter_round(&mut x, 15, 3, 7, 11);
+
+        // row round
+        quarter_round(&mut x, 0, 1, 2, 3);
+        quarter_round(&mut x, 5, 6, 7, 4);
+        quarter_round(&mut x, 10, 11, 8, 9);
+        quarter_round(&mut x, 15, 12, 13, 14);
+    }
+
+    block.iter_mut().zip(x.iter()).for_each(|(word, mixed)| *word = word.wrapping_add(*mixed));
+}
+
+#[allow(clippy::many_single_char_names)]
+fn quarter_round(x: &mut [u32; SALSA_WORDS], a: usize, b: usize, c: usize, d: usize) {
+    x[b] ^= x[a].wrapping_add(x[d]).rotate_left(7);
+    x[c] ^= x[b].wrapping_add(x[a]).rotate_left(9);
+    x[d] ^= x[c].wrapping_add(x[b]).rotate_left(13);
+    x[a] ^= x[d].wrapping_add(x[c]).rotate_left(18);
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use sha2::Digest;
+
+    use crate::{HashFunction, HashValue};
+    use crate::sha1::SHA1Hash;
+    use super::*;
+
+    /// SHA-256 of the `sha2` crate, which is not part of this crate yet, to check the RFC 7914 test vectors.
+    struct Sha256;
+
+    struct Sha256Hash(Vec<u8>);
+
+    impl HashValue for Sha256Hash {
+        fn raw(&self) -> Vec<u8> {
+            self.0.clone()
+        }
+    }
+
+    impl HashFunction for Sha256 {
+        type Context = ();
+        type HashState = sha2::Sha256;
+        type HashData = Sha256Hash;
+
+        const MAX_OUTPUT_SIZE: usize = 32;
+
+        fn init_hash(_ctx: &()) -> sha2::Sha256 {
+            sha2::Sha256::new()
+        }
+
+        fn update_hash(hash: &mut sha2::Sha256, input: &[u8]) {
+            hash.update(input);
+        }
+
+        fn finish_hash(hash: &mut sha2::Sha256, _ctx: &()) -> Sha256Hash {
+            Sha256Hash(hash.finalize_reset().to_vec())
+        }
+
+        fn digest_message(_ctx: &(), input: &[u8]) -> Sha256Hash {
+            Sha256Hash(sha2::Sha256::digest(input).to_vec())
+        }
+    }
+
+    impl BlockHashFunction for Sha256 {
+        fn block_size(_ctx: &()) -> usize {
+            64
+        }
+    }
+
+    fn parameters(cost: u64, block_size: u32, parallelism: u32, salt: &[u8]) -> ScryptParameters {
+        ScryptParameters {
+            cost,
+            block_size,
+            parallelism,
+            salt: salt.to_vec(),
+            output_length: 64,
+            memory_limit: 1 << 30,
+        }
+    }
+
+    fn rfc_vector(password: &[u8], params: &ScryptParameters) -> String {
+        hex::encode(scrypt_derive_key::<Sha256>(&(), params, password).unwrap())
+    }
+
+    #[test]
+    fn test_salsa20_8_vector() {
+        let input = hex::decode("7e879a214f3ec9867ca940e641718f26baee555b8c61c1b50df846116dcd3b1d\
+                                 ee24f319df9b3d8514121e4b5ac5aa3276021d2909c74829edebc68db8b8c25e").unwrap();
+        let mut block: [u32; SALSA_WORDS] = [0; SALSA_WORDS];
+        for (word, bytes) in block.iter_mut().zip(input.chunks_exact(4)) {
+            *word = u32::from_le_bytes(bytes.try_into().unwrap());
+        }
+
+        salsa20_8(&mut block);
+
+        let output = block.iter().flat_map(|word| word.to_le_bytes().to_vec()).collect::<Vec<_>>();
+        assert_eq!(
+            hex::encode(output),
+            "a41f859c6608cc993b81cacb020cef05044b2181a2fd337dfd7b1c6396682f29\
+             b4393168e3c9e6bcfe6bc5b7a06d96bae424cc102c91745c24ad673dc7618f81"
+        );
+    }
+
+    #[test]
+    fn test_scrypt_vectors() {
+        assert_eq!(
+            rfc_vector(b"", &parameters(16, 1, 1, b"")),
+            "77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442\
+             fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906"
+        );
+        assert_eq!(
+            rfc_vector(b"password", &parameters(1024, 8, 16, b"NaCl")),
+            "fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b373162\
+             2eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640"
+        );
+        assert_eq!(
+            rfc_vector(b"pleaseletmein", &parameters(16384, 8, 1, b"SodiumChloride")),
+            "7023bdcb3afd7348461c06cd81fd38ebfda8fbba904f8e3ea9b543f6545da1f2\
+             d5432955613f0fcf62d49705242a9af9e61e85dc0d651e40dfcf017b45575887"
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn test_scrypt_large_vector() {
+        assert_eq!(
+            rfc_vector(b"pleaseletmein", &ScryptParameters {
+                memory_limit: 2 << 30,
+                ..parameters(1048576, 8, 1, b"SodiumChloride")
+            }),
+            "2101cb9b6a511aaeaddbbe09cf70f881ec568d574a2ffd4dabe5ee9820adaa47\
+             8e56fd8f4ba5d09ffa1c6d927c40f4c337304049e8a952fbcbf45c6fa77a41a4"
+        );
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        let derive = |params: &ScryptParameters| scrypt_derive_key::<SHA1Hash>(&(), params, b"password");
+
+        assert_eq!(derive(&parameters(0, 1, 1, b"")), Err(ScryptException::CostNotPowerOfTwo { cost: 0 }));
+        assert_eq!(derive(&parameters(1, 1, 1, b"")), Err(ScryptException::CostNotPowerOfTwo { cost: 1 }));
+        assert_eq!(derive(&parameters(24, 1, 1, b"")), Err(ScryptException::CostNotPowerOfTwo { cost: 24 }));
+        assert_eq!(
+            derive(&parameters(1 << 16, 1, 1, b"")),
+            Err(ScryptException::CostTooLarge { cost: 1 << 16, block_size: 1 })
+        );
+        assert_eq!(derive(&parameters(16, 0, 1, b"")), Err(ScryptException::ZeroBlockSize {}));
+        assert_eq!(
+            derive(&parameters(16, 1, 0, b"")),
+            Err(ScryptException::InvalidParallelism { parallelism: 0, block_size: 1 })
+        );
+        assert_eq!(
+            derive(&parameters(16, 1 << 10, 1 << 20, b"")),
+            Err(ScryptException::InvalidParallelism { parallelism: 1 << 20, block_size: 1 << 10 })
+        );
+        assert_eq!(
+            derive(&ScryptParameters { output_length: 0, ..parameters(16, 1, 1, b"") }),
+            Err(ScryptException::ZeroOutputLength {})
+        );
+
+        // 16 scratch blocks, one expanded password block and two working blocks of 128 bytes each
+        assert_eq!(
+            derive(&ScryptParameters { memory_limit: 19 * 128 - 1, ..parameters(16, 1, 1, b"") }),
+            Err(ScryptException::MemoryLimitExceeded { required: 19 * 128, limit: 19 * 128 - 1 })
+        );
+        assert!(derive(&ScryptParameters { memory_limit: 19 * 128, ..parameters(16, 1, 1, b"") }).is_ok());
+        assert_eq!(
+            derive(&parameters(1 << 40, 8, 1, b"")),
+            Err(ScryptException::MemoryLimitExceeded { required: ((1 << 40) + 3) * 1024, limit: 1 << 30 })
+        );
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        #[test]
+        fn test_cost_changes_output(password in proptest::collection::vec(any::<u8>(), 0..32), log_cost in 1..6u32) {
+            let params = parameters(1 << log_cost, 1, 1, b"salt");
+            let doubled = parameters(1 << (log_cost + 1), 1, 1, b"salt");
+
+            prop_assert_ne!(
+                scrypt_derive_key::<SHA1Hash>(&(), &params, &password).unwrap(),
+                scrypt_derive_key::<SHA1Hash>(&(), &doubled, &password).unwrap()
+            );
+        }
+    }
+}
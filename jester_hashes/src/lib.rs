@@ -1,54 +1,128 @@
 //! This crate contains various software-implementations of common hash algorithms. All implementations offer
 //! granular APIs, so the hash can be manually forged and manipulated.
-
-use std::{mem::MaybeUninit, ptr};
-
+//!
+//! A hash is computed in streaming fashion by initializing a state with the context of the hash function,
+//! compressing any number of chunks into it, and finishing it with the context again:
+//!
+//! ```
+//! use jester_hashes::blake::blake2b::{Blake2b, Blake2bContext};
+//! use jester_hashes::HashFunction;
+//!
+//! let ctx = Blake2bContext::new(32).unwrap();
+//! let mut state = Blake2b::init_hash(&ctx);
+//! Blake2b::update_hash(&mut state, b"streamed ");
+//! Blake2b::update_hash(&mut state, b"message");
+//!
+//! let streamed = Blake2b::finish_hash(&mut state, &ctx);
+//! assert_eq!(streamed.to_string(), Blake2b::digest_message(&ctx, b"streamed message").to_string());
+//! ```
+
+use crate::hasher::Hasher;
+
+#[macro_use]
+mod hex_format;
+
+pub mod constructions;
 pub mod hmac;
 pub mod kdf;
 pub mod md5;
+pub mod multi_digest;
 pub mod sha1;
 pub mod blake;
-
-/// Copies the ``source`` array to the ``dest`` array with respect to alignment and endianness. ``source`` must be at
-/// least four times bigger than ``dest``, otherwise this function's behavior is undefined. Data from ``source``
-/// will be treated as little endian integers
-pub(crate) unsafe fn align_to_u32a_le(dest: &mut [u32], source: &[u8]) {
-    assert!(source.len() >= dest.len() * 4);
-
-    let mut byte_ptr: *const u8 = source.get_unchecked(0);
-    let mut dword_ptr: *mut u32 = dest.get_unchecked_mut(0);
-
-    for _ in 0..dest.len() {
-        let mut current = MaybeUninit::uninit();
-        ptr::copy_nonoverlapping(byte_ptr, current.as_mut_ptr() as *mut _ as *mut u8, 4);
-        *dword_ptr = u32::from_le(current.assume_init());
-        dword_ptr = dword_ptr.offset(1);
-        byte_ptr = byte_ptr.offset(4);
+pub mod crc;
+pub mod digest_hasher;
+pub mod hasher;
+pub mod siphash;
+pub mod sm3;
+pub mod tuple_hash;
+pub mod whirlpool;
+#[cfg(feature = "rustcrypto-compat")]
+pub mod rustcrypto_compat;
+#[cfg(any(test, feature = "fuzzing"))]
+#[doc(hidden)]
+pub mod fuzzing;
+
+mod base64;
+mod bits;
+mod bytes;
+
+jester_util::capabilities! {
+    /// The hash functions and optional features of a build of this crate, see `capabilities`.
+    pub struct Capabilities {
+        const MD5 = 0, "md5";
+        const SHA1 = 1, "sha1";
+        const BLAKE2B = 2, "blake2b";
+        const BLAKE2S = 3, "blake2s";
+        const SM3 = 4, "sm3";
+        const WHIRLPOOL = 5, "whirlpool";
+        const CRC32 = 6, "crc32";
+        const CRC32C = 7, "crc32c";
+        const SIPHASH = 8, "siphash";
+        /// The `digest` traits of `rustcrypto_compat`
+        const RUSTCRYPTO_COMPAT = 16, "rustcrypto-compat";
+        /// `AsyncWrite` for `hasher::Hasher` and `hasher::hash_reader`
+        const ASYNC_IO = 17, "async-io";
+        /// `hmac::hmac_verify_batch` and `hasher::hash_files`
+        const RAYON = 18, "rayon";
+        /// The checks of the fuzz targets in `fuzzing`
+        const FUZZING = 19, "fuzzing";
     }
 }
 
-/// Copies the ``source`` array to the ``dest`` array with respect to alignment and endianness. ``source`` must be at
-/// least four times bigger than ``dest``, otherwise this function's behavior is undefined. Data from ``source``
-/// will be treated as big endian integers
-pub(crate) unsafe fn align_to_u32a_be(dest: &mut [u32], source: &[u8]) {
-    assert!(source.len() >= dest.len() * 4);
-
-    let mut byte_ptr: *const u8 = source.get_unchecked(0);
-    let mut dword_ptr: *mut u32 = dest.get_unchecked_mut(0);
+/// The hash functions and optional features this crate was compiled with. Applications that select an algorithm at
+/// runtime, e.g. from a stored digest or a peer's offer, consult it instead of assuming that every algorithm exists.
+pub fn capabilities() -> Capabilities {
+    (Capabilities::MD5 | Capabilities::SHA1 | Capabilities::BLAKE2B | Capabilities::BLAKE2S | Capabilities::SM3
+        | Capabilities::WHIRLPOOL | Capabilities::CRC32 | Capabilities::CRC32C | Capabilities::SIPHASH)
+        .with(Capabilities::RUSTCRYPTO_COMPAT, cfg!(feature = "rustcrypto-compat"))
+        .with(Capabilities::ASYNC_IO, cfg!(feature = "async-io"))
+        .with(Capabilities::RAYON, cfg!(feature = "rayon"))
+        .with(Capabilities::FUZZING, cfg!(feature = "fuzzing"))
+}
 
-    for _ in 0..dest.len() {
-        let mut current = MaybeUninit::uninit();
-        ptr::copy_nonoverlapping(byte_ptr, current.as_mut_ptr() as *mut _ as *mut u8, 4);
-        *dword_ptr = u32::from_be(current.assume_init());
-        dword_ptr = dword_ptr.offset(1);
-        byte_ptr = byte_ptr.offset(4);
+/// Compare two byte slices in time independent of the position of the first difference.
+pub fn constant_time_eq(lhs: &[u8], rhs: &[u8]) -> bool {
+    if lhs.len() != rhs.len() {
+        return false;
     }
+
+    lhs.iter().zip(rhs.iter()).fold(0_u8, |difference, (a, b)| difference | (a ^ b)) == 0
 }
 
 /// Output of a `HashFunction`.
 pub trait HashValue {
     /// Obtain the hash as a raw byte array.
     fn raw(&self) -> Vec<u8>;
+
+    /// Obtain the first `n` bytes of the raw hash.
+    ///
+    /// # Panics
+    /// If `n` exceeds the length of the hash.
+    fn truncated(&self, n: usize) -> Vec<u8> {
+        let mut raw = self.raw();
+        assert!(n <= raw.len(), "cannot truncate a {} byte hash to {} bytes", raw.len(), n);
+
+        raw.truncate(n);
+        raw
+    }
+
+    /// Encode the raw hash as unpadded base64 with the standard alphabet.
+    fn to_base64(&self) -> String {
+        base64::encode(&self.raw())
+    }
+}
+
+/// Errors that can arise when parsing a hash value from its hex representation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseHexError {
+    /// The character at `position` is no hex digit
+    InvalidCharacter { position: usize },
+
+    /// The string has an odd number of hex digits
+    OddLength { length: usize },
+
+    /// The string encodes `length` bytes, which is no valid output length of the hash function
+    InvalidLength { length: usize },
 }
 
 /// An implementation of a hashing algorithm. It defines three implementation dependent types,
@@ -67,38 +141,163 @@ pub trait HashFunction {
     /// correct usage.
     type HashData: HashValue;
 
+    /// The largest number of bytes a hash value of this function has in any context. It can be used to size buffers
+    /// for hash values at compile time.
+    const MAX_OUTPUT_SIZE: usize;
+
+    /// The number of bytes of the hash values produced with the given context. It never exceeds
+    /// `MAX_OUTPUT_SIZE`, and is equal to it for hash functions whose output size does not depend on the context.
+    fn output_size_for(_ctx: &Self::Context) -> usize {
+        Self::MAX_OUTPUT_SIZE
+    }
+
     /// Obtain an initial hash value (usually the IV) conforming to the parameters set by the
     /// given `Self::Context`. The given `ctx` value may not be changed or
     fn init_hash(ctx: &Self::Context) -> Self::HashState;
 
+    /// Reset a hash state to the initial state given by `ctx`, as if it was obtained by `init_hash`. This allows
+    /// reusing a state for many messages. Implementations may override this to reset the state in place without
+    /// allocating a new state.
+    fn reset_hash(hash: &mut Self::HashState, ctx: &Self::Context) {
+        *hash = Self::init_hash(ctx)
+    }
+
     /// Update the hash with more data. If not all data can be consumed, the remaining buffer
-    /// will be stored within the given context structure.
-    fn update_hash(hash: &mut Self::HashState, ctx: &Self::Context, input: &[u8]);
+    /// will be stored within the given hash state. The state carries everything required for
+    /// compression, so unlike `init_hash` and `finish_hash` this function takes no context.
+    fn update_hash(hash: &mut Self::HashState, input: &[u8]);
 
     /// Finish the hash using the last bit of input data. The resulting hash is returned. The
     /// given `ctx` is then in a final state and may not be used for further hashing without a
-    /// previous call of `init_hash`.
+    /// previous call of `init_hash` or `reset_hash`.
     fn finish_hash(hash: &mut Self::HashState, ctx: &Self::Context) -> Self::HashData;
 
+    /// Obtain the hash of all data compressed into `hash` so far, without finishing the state itself. The state
+    /// is cloned and the clone is finished, so hashing can continue afterwards.
+    fn peek_hash(hash: &Self::HashState, ctx: &Self::Context) -> Self::HashData
+        where Self::HashState: Clone
+    {
+        Self::finish_hash(&mut hash.clone(), ctx)
+    }
+
     /// Convenience method to initialize a hash state and completely compress the given `input`
     /// into it. Then the final hash is returned.
     fn digest_message(ctx: &Self::Context, input: &[u8]) -> Self::HashData;
 }
 
+/// Convenience methods for hash functions whose context has a sensible default, e.g. hash functions without
+/// parameters, whose context is `()`. It is implemented for all such hash functions.
+pub trait DefaultContextHash: HashFunction
+    where Self::Context: Default
+{
+    /// Compute the hash of `data` with the default context.
+    fn digest(data: &[u8]) -> Self::HashData {
+        Self::digest_message(&Self::Context::default(), data)
+    }
+
+    /// Create a streaming `Hasher` with the default context.
+    fn hasher() -> Hasher<Self>
+        where Self: Sized
+    {
+        Hasher::new(Self::Context::default())
+    }
+}
+
+impl<H> DefaultContextHash for H
+    where H: HashFunction,
+          H::Context: Default
+{}
+
 /// A special hash function that consumes input in blocks of uniform size.
 pub trait BlockHashFunction: HashFunction {
     /// Obtain the block size this hash consumes given the specified context.
     fn block_size(ctx: &Self::Context) -> usize;
 
-    /// Obtain the output size this hash will produce given the specified context.
-    fn output_size(ctx: &Self::Context) -> usize;
+    /// Obtain the output size this hash will produce given the specified context. This is
+    /// `HashFunction::output_size_for`.
+    fn output_size(ctx: &Self::Context) -> usize {
+        Self::output_size_for(ctx)
+    }
+}
+
+/// A hash function that accepts messages of any number of bits, not only whole bytes, as specified for MD5 and the
+/// SHA family. Bits are consumed most significant bit first, so a message of `n` bits is the first `n` bits of its
+/// bytes. Bits and bytes can be mixed in a stream, and compressing `8 * n` bits is equivalent to `update_hash` with
+/// `n` bytes.
+pub trait BitHashFunction: HashFunction {
+    /// Update the hash with the first `bit_len` bits of `data`. Bits of an incomplete last byte are buffered in the
+    /// state, and all following data is appended to them.
+    ///
+    /// # Panics
+    /// If `data` has fewer than `bit_len` bits
+    fn update_hash_bits(hash: &mut Self::HashState, ctx: &Self::Context, data: &[u8], bit_len: usize);
+
+    /// Finish the hash of a message that may end in an incomplete byte. The padding starts directly after the last
+    /// bit of the message, and the appended message length counts bits.
+    fn finish_hash_bits(hash: &mut Self::HashState, ctx: &Self::Context) -> Self::HashData {
+        Self::finish_hash(hash, ctx)
+    }
+}
+
+/// Expand the hash of `input` to `output_length` bytes in counter mode. The output is the concatenation of
+/// `H(input || 0) || H(input || 1) || ...`, where the counter is a big endian 32 bit integer, truncated to
+/// `output_length` bytes.
+pub fn expand_digest<H>(ctx: &H::Context, input: &[u8], output_length: usize) -> Vec<u8>
+    where H: BlockHashFunction
+{
+    let mut output = Vec::with_capacity(output_length);
+    let mut counter: u32 = 0;
+
+    while output.len() < output_length {
+        let block = H::digest_message(ctx, &[input, &counter.to_be_bytes()].concat()).raw();
+        let length = block.len().min(output_length - output.len());
+        output.extend_from_slice(&block[..length]);
+        counter += 1;
+    }
+
+    output
+}
+
+/// The mask generation function MGF1 of RFC 8017, used by the RSA padding schemes OAEP and PSS. It generates a mask
+/// of `mask_length` bytes from `seed`.
+pub fn mgf1<H>(ctx: &H::Context, seed: &[u8], mask_length: usize) -> Vec<u8>
+    where H: BlockHashFunction
+{
+    expand_digest::<H>(ctx, seed, mask_length)
+}
+
+/// An observer of the intermediate round states of a compression function. It is called after every round with the
+/// round index and the working state. Compression functions are generic over the observer, so the no-op default
+/// `NoObserver` is optimized away entirely.
+pub trait RoundObserver<S> {
+    /// Called after round `round` with the working state `state`.
+    fn observe_round(&mut self, round: usize, state: &S) {
+        let _ = (round, state);
+    }
+}
+
+/// A `RoundObserver` that ignores all rounds.
+pub struct NoObserver;
+
+impl<S> RoundObserver<S> for NoObserver {}
+
+/// A trace buffer that records the state of round `i` at index `i`.
+impl<S, const N: usize> RoundObserver<S> for [S; N]
+    where S: Copy
+{
+    fn observe_round(&mut self, round: usize, state: &S) {
+        self[round] = *state;
+    }
 }
 
 #[cfg(test)]
 pub(crate) mod tests {
     use hex;
 
+    use std::collections::HashSet;
+
     use super::*;
+    use super::blake::blake2b::{Blake2b, Blake2bContext};
     use super::md5::MD5Hash;
     use super::sha1::SHA1Hash;
 
@@ -130,20 +329,42 @@ which was His Holy Word. To bring forth His vision, to make it manifest in the w
 after He was gone."
     ];
 
+    #[test]
+    fn test_capabilities() {
+        let capabilities = capabilities();
+        assert!(capabilities.contains(Capabilities::MD5 | Capabilities::SHA1 | Capabilities::BLAKE2B));
+        assert_eq!(capabilities.contains(Capabilities::RUSTCRYPTO_COMPAT), cfg!(feature = "rustcrypto-compat"));
+        assert_eq!(capabilities.contains(Capabilities::RAYON), cfg!(feature = "rayon"));
+    }
+
+    #[test]
+    #[cfg(feature = "async-io")]
+    fn test_capabilities_async_io() {
+        assert!(capabilities().contains(Capabilities::ASYNC_IO));
+        assert!(capabilities().names().contains(&"async-io"));
+    }
+
+    #[test]
+    #[cfg(not(feature = "async-io"))]
+    fn test_capabilities_without_async_io() {
+        assert!(!capabilities().contains(Capabilities::ASYNC_IO));
+        assert!(!capabilities().names().contains(&"async-io"));
+    }
+
     #[test]
     fn test_md5() {
         assert_eq!(
-            hex::encode(&MD5Hash::digest_message(&(), EMPTY_MESSAGE.as_bytes()).raw()),
+            MD5Hash::digest_message(&(), EMPTY_MESSAGE.as_bytes()).to_string(),
             "d41d8cd98f00b204e9800998ecf8427e"
         );
 
         assert_eq!(
-            hex::encode(&MD5Hash::digest_message(&(), SOME_TEXT.as_bytes()).raw()),
+            MD5Hash::digest(SOME_TEXT.as_bytes()).to_string(),
             "9cf653b21b12797c80f769c8a753c360"
         );
 
         assert_eq!(
-            hex::encode(&MD5Hash::digest_message(&(), LONG_TEXT.as_bytes()).raw()),
+            MD5Hash::digest(LONG_TEXT.as_bytes()).to_string(),
             "fd87f4b9821fe2223f006c3495324541"
         );
     }
@@ -152,28 +373,29 @@ after He was gone."
     fn test_md5_stream() {
         let ctx = ();
         let mut hash_state = MD5Hash::init_hash(&ctx);
-        MD5Hash::update_hash(&mut hash_state, &ctx, STREAM_TEXT[0].as_bytes());
-        MD5Hash::update_hash(&mut hash_state, &ctx, STREAM_TEXT[1].as_bytes());
-        MD5Hash::update_hash(&mut hash_state, &ctx, STREAM_TEXT[2].as_bytes());
+        MD5Hash::update_hash(&mut hash_state, STREAM_TEXT[0].as_bytes());
+        MD5Hash::update_hash(&mut hash_state, STREAM_TEXT[1].as_bytes());
+        MD5Hash::update_hash(&mut hash_state, STREAM_TEXT[2].as_bytes());
 
         let hash = MD5Hash::finish_hash(&mut hash_state, &ctx);
-        assert_eq!(hex::encode(hash.raw()), "4ede84ae4c00b7c8f1683ca6bbacd3b1");
+        assert_eq!(hash.to_string(), "4ede84ae4c00b7c8f1683ca6bbacd3b1");
+        assert_eq!(hash, MD5Hash::digest(STREAM_TEXT.concat().as_bytes()));
     }
 
     #[test]
     fn test_sha1() {
         assert_eq!(
-            hex::encode(&SHA1Hash::digest_message(&(), EMPTY_MESSAGE.as_bytes()).raw()),
+            SHA1Hash::digest_message(&(), EMPTY_MESSAGE.as_bytes()).to_string(),
             "da39a3ee5e6b4b0d3255bfef95601890afd80709"
         );
 
         assert_eq!(
-            hex::encode(&SHA1Hash::digest_message(&(), SOME_TEXT.as_bytes()).raw()),
+            SHA1Hash::digest(SOME_TEXT.as_bytes()).to_string(),
             "931bec5eec465b2e742deafbdcae2681820a4ac9"
         );
 
         assert_eq!(
-            hex::encode(&SHA1Hash::digest_message(&(), LONG_TEXT.as_bytes()).raw()),
+            SHA1Hash::digest(LONG_TEXT.as_bytes()).to_string(),
             "ae410e98987c6543498833540e93dd7129fc8e0b"
         );
     }
@@ -182,18 +404,227 @@ after He was gone."
     fn test_sha1_stream() {
         let ctx = ();
         let mut hash_state = SHA1Hash::init_hash(&ctx);
-        SHA1Hash::update_hash(&mut hash_state, &ctx, STREAM_TEXT[0].as_bytes());
-        SHA1Hash::update_hash(&mut hash_state, &ctx, STREAM_TEXT[1].as_bytes());
-        SHA1Hash::update_hash(&mut hash_state, &ctx, STREAM_TEXT[2].as_bytes());
+        SHA1Hash::update_hash(&mut hash_state, STREAM_TEXT[0].as_bytes());
+        SHA1Hash::update_hash(&mut hash_state, STREAM_TEXT[1].as_bytes());
+        SHA1Hash::update_hash(&mut hash_state, STREAM_TEXT[2].as_bytes());
 
         let hash = SHA1Hash::finish_hash(&mut hash_state, &ctx);
-        assert_eq!(hex::encode(hash.raw()), "c11280314809ce63f5d17a92b9a858317141f747");
+        assert_eq!(hash.to_string(), "c11280314809ce63f5d17a92b9a858317141f747");
+        assert_eq!(hash, SHA1Hash::digest(STREAM_TEXT.concat().as_bytes()));
+    }
+
+    #[test]
+    fn test_digest_set_deduplication() {
+        let messages = [SOME_TEXT, LONG_TEXT, SOME_TEXT, EMPTY_MESSAGE, LONG_TEXT];
+
+        let md5_digests = messages.iter().map(|m| MD5Hash::digest(m.as_bytes())).collect::<HashSet<_>>();
+        assert_eq!(md5_digests.len(), 3);
+        assert!(md5_digests.contains(&"d41d8cd98f00b204e9800998ecf8427e".parse().unwrap()));
+
+        let sha1_digests = messages.iter().map(|m| SHA1Hash::digest(m.as_bytes())).collect::<HashSet<_>>();
+        assert_eq!(sha1_digests.len(), 3);
+        assert!(sha1_digests.contains(&SHA1Hash::digest(LONG_TEXT.as_bytes())));
+
+        // digests of different lengths are distinct, even if one is a prefix of the other
+        let short = Blake2bContext::new(32).unwrap();
+        let blake_digests = messages.iter()
+            .flat_map(|m| vec![Blake2b::digest(m.as_bytes()), Blake2b::digest_message(&short, m.as_bytes())])
+            .collect::<HashSet<_>>();
+        assert_eq!(blake_digests.len(), 6);
+    }
+
+    /// Check that resetting a used hash state results in the same digests as a freshly initialized state, also when
+    /// the state is reset repeatedly.
+    pub(crate) fn assert_reset_consistent<H>(ctx: &H::Context)
+        where H: HashFunction
+    {
+        let expected = H::digest_message(ctx, SOME_TEXT.as_bytes()).raw();
+
+        // reset a finished state
+        let mut hash_state = H::init_hash(ctx);
+        H::update_hash(&mut hash_state, LONG_TEXT.as_bytes());
+        H::finish_hash(&mut hash_state, ctx);
+        H::reset_hash(&mut hash_state, ctx);
+        H::update_hash(&mut hash_state, SOME_TEXT.as_bytes());
+        assert_eq!(H::finish_hash(&mut hash_state, ctx).raw(), expected);
+
+        // reset a state with buffered data
+        H::reset_hash(&mut hash_state, ctx);
+        H::update_hash(&mut hash_state, &LONG_TEXT.as_bytes()[..97]);
+        H::reset_hash(&mut hash_state, ctx);
+        H::update_hash(&mut hash_state, SOME_TEXT.as_bytes());
+        assert_eq!(H::finish_hash(&mut hash_state, ctx).raw(), expected);
+
+        for _ in 0..10_000 {
+            H::reset_hash(&mut hash_state, ctx);
+            H::update_hash(&mut hash_state, SOME_TEXT.as_bytes());
+            assert_eq!(H::finish_hash(&mut hash_state, ctx).raw(), expected);
+        }
+    }
+
+    /// Check that peeking at a state in the middle of a stream returns the hash of the data so far and does not
+    /// change the final hash.
+    pub(crate) fn assert_peek_consistent<H>(ctx: &H::Context)
+        where H: HashFunction,
+              H::HashState: Clone
+    {
+        let mut hash_state = H::init_hash(ctx);
+        let mut prefix = Vec::new();
+        for chunk in &STREAM_TEXT {
+            H::update_hash(&mut hash_state, chunk.as_bytes());
+            prefix.extend_from_slice(chunk.as_bytes());
+
+            assert_eq!(H::peek_hash(&hash_state, ctx).raw(), H::digest_message(ctx, &prefix).raw());
+        }
+
+        assert_eq!(H::finish_hash(&mut hash_state, ctx).raw(), H::digest_message(ctx, &prefix).raw());
+    }
+
+    #[test]
+    fn test_md5_peek() {
+        assert_peek_consistent::<MD5Hash>(&());
+    }
+
+    #[test]
+    fn test_sha1_peek() {
+        assert_peek_consistent::<SHA1Hash>(&());
+    }
+
+    #[test]
+    fn test_md5_reset() {
+        assert_reset_consistent::<MD5Hash>(&());
+    }
+
+    #[test]
+    fn test_sha1_reset() {
+        assert_reset_consistent::<SHA1Hash>(&());
+    }
+
+    /// The `len` bits of `data` starting at bit `start`, most significant bit first.
+    fn bit_slice(data: &[u8], start: usize, len: usize) -> Vec<u8> {
+        let mut bits = vec![0_u8; len.div_ceil(8)];
+        for i in 0..len {
+            let bit = (data[(start + i) / 8] >> (7 - (start + i) % 8)) & 1;
+            bits[i / 8] |= bit << (7 - i % 8);
+        }
+        bits
+    }
+
+    /// Check that hashing whole bytes with the bit oriented functions results in the same digests as the byte
+    /// oriented functions, also when the stream is split within bytes and bits are mixed with bytes.
+    pub(crate) fn assert_bits_consistent<H>(ctx: &H::Context)
+        where H: BitHashFunction
+    {
+        let message = LONG_TEXT.as_bytes();
+
+        // messages ending around the block boundaries
+        for &len in &[0, 1, 55, 56, 63, 64, 65, 119, 120, message.len()] {
+            let mut hash_state = H::init_hash(ctx);
+            H::update_hash_bits(&mut hash_state, ctx, message, len * 8);
+            assert_eq!(H::finish_hash_bits(&mut hash_state, ctx).raw(), H::digest_message(ctx, &message[..len]).raw());
+        }
+
+        // chunks of bits that are not aligned to bytes, followed by bytes after an incomplete byte
+        let expected = H::digest_message(ctx, message).raw();
+        for &chunk_bits in &[1, 3, 7, 13, 100] {
+            let mut hash_state = H::init_hash(ctx);
+            let mut offset = 0;
+            while offset + chunk_bits <= 8 * 100 {
+                H::update_hash_bits(&mut hash_state, ctx, &bit_slice(message, offset, chunk_bits), chunk_bits);
+                offset += chunk_bits;
+            }
+
+            let rest = bit_slice(message, offset, message.len() * 8 - offset);
+            H::update_hash(&mut hash_state, &rest[..rest.len() - 1]);
+            let last_bits = (message.len() * 8 - offset) % 8;
+            let last_bits = if last_bits == 0 { 8 } else { last_bits };
+            H::update_hash_bits(&mut hash_state, ctx, &rest[rest.len() - 1..], last_bits);
+            assert_eq!(H::finish_hash_bits(&mut hash_state, ctx).raw(), expected, "chunks of {} bits", chunk_bits);
+        }
+    }
+
+    #[test]
+    fn test_md5_bits() {
+        assert_bits_consistent::<MD5Hash>(&());
+    }
+
+    #[test]
+    fn test_sha1_bits() {
+        assert_bits_consistent::<SHA1Hash>(&());
+    }
+
+    #[test]
+    fn test_truncated() {
+        let digest = SHA1Hash::digest_message(&(), SOME_TEXT.as_bytes());
+
+        assert_eq!(digest.truncated(0), Vec::<u8>::new());
+        assert_eq!(digest.truncated(4), digest.raw()[..4].to_vec());
+        assert_eq!(digest.truncated(20), digest.raw());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_truncated_overlong() {
+        SHA1Hash::digest_message(&(), SOME_TEXT.as_bytes()).truncated(21);
     }
 
     #[test]
-    fn test_align_to_u32a_le() {
-        let mut dest = [0u32; 2];
-        unsafe { align_to_u32a_le(&mut dest, &[0x78, 0x56, 0x34, 0x12, 0xFF, 0x00, 0xFF, 0x00]) }
-        assert_eq!([0x1234_5678u32, 0x00FF_00FFu32], dest)
+    fn test_expand_digest() {
+        let expanded = expand_digest::<MD5Hash>(&(), SOME_TEXT.as_bytes(), 100);
+
+        assert_eq!(expanded.len(), 100);
+        assert_eq!(expanded, expand_digest::<MD5Hash>(&(), SOME_TEXT.as_bytes(), 100));
+        assert_eq!(expanded[..40], expand_digest::<MD5Hash>(&(), SOME_TEXT.as_bytes(), 40)[..]);
+        assert!(expand_digest::<MD5Hash>(&(), SOME_TEXT.as_bytes(), 0).is_empty());
+    }
+
+    /// Assert that `output_size` stays within `MAX_OUTPUT_SIZE` and matches the length of the produced hash value.
+    fn assert_output_size_bounded<H>(ctx: &H::Context)
+        where H: BlockHashFunction
+    {
+        assert!(H::output_size(ctx) <= H::MAX_OUTPUT_SIZE);
+        assert_eq!(H::output_size(ctx), H::output_size_for(ctx));
+        assert_eq!(H::digest_message(ctx, SOME_TEXT.as_bytes()).raw().len(), H::output_size(ctx));
+    }
+
+    #[test]
+    fn test_output_size_bounded() {
+        use crate::blake::blake2b::{Blake2b, Blake2bContext};
+        use crate::blake::blake2s::{Blake2s, Blake2sContext};
+        use crate::blake::blake2xb::{Blake2Xb, Blake2XbContext};
+        use crate::sm3::SM3Hash;
+        use crate::whirlpool::WhirlpoolHash;
+
+        assert_eq!(MD5Hash::MAX_OUTPUT_SIZE, 16);
+        assert_eq!(SHA1Hash::MAX_OUTPUT_SIZE, 20);
+        assert_eq!(Blake2b::MAX_OUTPUT_SIZE, 64);
+        assert_eq!(Blake2s::MAX_OUTPUT_SIZE, 32);
+
+        assert_output_size_bounded::<MD5Hash>(&());
+        assert_output_size_bounded::<SHA1Hash>(&());
+        assert_output_size_bounded::<SM3Hash>(&());
+        assert_output_size_bounded::<WhirlpoolHash>(&());
+
+        for output_len in 1..=64 {
+            assert_output_size_bounded::<Blake2b>(&Blake2bContext::new(output_len).unwrap());
+            assert_output_size_bounded::<Blake2b>(&Blake2bContext::new_keyed(output_len, b"key").unwrap());
+        }
+        for output_len in 1..=32 {
+            assert_output_size_bounded::<Blake2s>(&Blake2sContext::new(output_len).unwrap());
+        }
+        for output_len in [1, 64, 65, 1000] {
+            assert_output_size_bounded::<Blake2Xb>(&Blake2XbContext { output_len, key: Default::default() });
+        }
+    }
+
+    #[test]
+    fn test_mgf1() {
+        assert_eq!(hex::encode(mgf1::<SHA1Hash>(&(), b"foo", 3)), "1ac907");
+        assert_eq!(hex::encode(mgf1::<SHA1Hash>(&(), b"foo", 5)), "1ac9075cd4");
+        assert_eq!(hex::encode(mgf1::<SHA1Hash>(&(), b"bar", 5)), "bc0c655e01");
+        assert_eq!(
+            hex::encode(mgf1::<SHA1Hash>(&(), b"bar", 50)),
+            "bc0c655e016bc2931d85a2e675181adcef7f581f76df2739da74faac41627be2f7f415c89e983fd0ce80ced9878641cb4876"
+        );
     }
 }
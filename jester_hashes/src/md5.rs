@@ -1,11 +1,16 @@
 #![allow(clippy::unreadable_literal)]
 #![allow(clippy::zero_prefixed_literal)]
 
-use std::mem;
-use std::mem::size_of;
+use std::mem::{size_of, take};
 
-use crate::{align_to_u32a_le, BlockHashFunction, HashFunction, HashValue};
+use crate::{BitHashFunction, BlockHashFunction, HashFunction, HashValue, NoObserver, RoundObserver};
+use crate::bits::PartialByte;
+use crate::bytes::{read_u32_le, write_u64_le};
 use std::convert::TryInto;
+use std::str::FromStr;
+
+use crate::ParseHexError;
+use crate::hex_format::decode_hex_array;
 
 /// the hash block length in bytes
 const BLOCK_LENGTH_BYTES: usize = 64;
@@ -17,13 +22,15 @@ const BLOCK_LENGTH_DOUBLE_WORDS: usize = BLOCK_LENGTH_BYTES / 4;
 pub const INITIAL: MD5Hash = MD5Hash(0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476);
 
 /// A tuple struct containing all four bytes of an MD5 Hash.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct MD5Hash(pub u32, pub u32, pub u32, pub u32);
 
+#[derive(Clone)]
 pub struct MD5HashState {
     hash: MD5Hash,
     message_length: u64,
     remaining_data: Vec<u8>,
+    partial_byte: PartialByte,
 }
 
 /// bits rotated per round
@@ -46,10 +53,48 @@ static MAGIC_SINUS_SCALARS: [u32; 64] = [
 ];
 
 fn round_function(hash: &mut MD5HashState, input: &[u8; BLOCK_LENGTH_BYTES]) {
+    hash.hash = compress(&hash.hash, input, &mut NoObserver);
+
+    if hash.message_length as u128 + 64_u128 * 8 > u64::MAX as u128 {
+        // todo maybe throw an error here?
+        panic!("cannot hash more than 2**64 - 1 bits.")
+    } else {
+        hash.message_length += 64 * 8
+    }
+}
+
+/// Compute the trace of a single compression of `block` into the chaining value `state`. The trace contains the
+/// working state `(a, b, c, d)` after each of the 64 rounds, before it is added to the chaining value.
+///
+/// # Returns
+/// A tuple of the trace and the resulting chaining value
+pub fn compress_trace(state: &MD5Hash, block: &[u8; BLOCK_LENGTH_BYTES]) -> ([MD5Hash; 64], MD5Hash) {
+    let mut trace = [*state; 64];
+    let chaining_value = compress(state, block, &mut trace);
+    (trace, chaining_value)
+}
+
+/// Compress one block into the chaining value `iv`. This is the bare MD5 compression function without padding or
+/// length tracking, as a building block for custom constructions like those of `constructions`. Compressing the padded
+/// message into `INITIAL` block by block yields the MD5 hash.
+///
+/// # Returns
+/// The words of the new chaining value in the order `(a, b, c, d)`
+pub fn md5_block(iv: [u32; 4], block: &[u8; BLOCK_LENGTH_BYTES]) -> [u32; 4] {
+    let MD5Hash(a, b, c, d) = compress(&MD5Hash(iv[0], iv[1], iv[2], iv[3]), block, &mut NoObserver);
+    [a, b, c, d]
+}
+
+/// Compress one block into the chaining value `state` and report the working state after each round to `observer`.
+fn compress<O>(state: &MD5Hash, input: &[u8; BLOCK_LENGTH_BYTES], observer: &mut O) -> MD5Hash
+    where O: RoundObserver<MD5Hash>
+{
     let mut input_block = [0_u32; BLOCK_LENGTH_DOUBLE_WORDS];
-    unsafe { align_to_u32a_le(&mut input_block, input) };
+    for (i, word) in input_block.iter_mut().enumerate() {
+        *word = read_u32_le(input, i * 4);
+    }
 
-    let mut round_state = hash.hash;
+    let mut round_state = *state;
 
     for i in 0..BLOCK_LENGTH_BYTES {
         let (scrambled_data, message_index) = match i {
@@ -84,19 +129,16 @@ fn round_function(hash: &mut MD5HashState, input: &[u8; BLOCK_LENGTH_BYTES]) {
             ROUND_ROTATION_COUNT[i],
         ));
         round_state.0 = temp;
-    }
-
-    hash.hash.0 = hash.hash.0.wrapping_add(round_state.0);
-    hash.hash.1 = hash.hash.1.wrapping_add(round_state.1);
-    hash.hash.2 = hash.hash.2.wrapping_add(round_state.2);
-    hash.hash.3 = hash.hash.3.wrapping_add(round_state.3);
 
-    if hash.message_length as u128 + 64_u128 * 8 > u64::MAX as u128 {
-        // todo maybe throw an error here?
-        panic!("cannot hash more than 2**64 - 1 bits.")
-    } else {
-        hash.message_length += 64 * 8
+        observer.observe_round(i, &round_state);
     }
+
+    MD5Hash(
+        state.0.wrapping_add(round_state.0),
+        state.1.wrapping_add(round_state.1),
+        state.2.wrapping_add(round_state.2),
+        state.3.wrapping_add(round_state.3),
+    )
 }
 
 impl HashFunction for MD5Hash {
@@ -104,8 +146,18 @@ impl HashFunction for MD5Hash {
     type HashState = MD5HashState;
     type HashData = MD5Hash;
 
+    const MAX_OUTPUT_SIZE: usize = size_of::<MD5Hash>();
+
     fn init_hash(_ctx: &Self::Context) -> Self::HashState {
-        MD5HashState { hash: INITIAL, message_length: 0, remaining_data: vec![] }
+        MD5HashState { hash: INITIAL, message_length: 0, remaining_data: vec![], partial_byte: PartialByte::default() }
+    }
+
+    /// Reset the state in place, keeping the allocation of the remaining data buffer.
+    fn reset_hash(hash: &mut Self::HashState, _ctx: &Self::Context) {
+        hash.hash = INITIAL;
+        hash.message_length = 0;
+        hash.remaining_data.clear();
+        hash.partial_byte = PartialByte::default();
     }
 
     /// Compute one round of the MD5 hash function.
@@ -117,7 +169,12 @@ impl HashFunction for MD5Hash {
     ///
     /// # Returns
     /// A new `MD5HashState` computed from the input state and the input data block.
-    fn update_hash(hash: &mut Self::HashState, _ctx: &Self::Context, input: &[u8]) {
+    fn update_hash(hash: &mut Self::HashState, input: &[u8]) {
+        // bytes following an incomplete byte are not aligned to the buffer
+        if hash.partial_byte.bits() != 0 {
+            return Self::update_hash_bits(hash, &(), input, input.len() * 8);
+        }
+
         // offset of input data that is already processed during the use of the remaining data
         // stored in the state
         let mut input_data_offset = 0;
@@ -166,35 +223,30 @@ impl HashFunction for MD5Hash {
         let mut last_block = [0_u8; BLOCK_LENGTH_BYTES];
         last_block[..remaining_data.len()].copy_from_slice(&remaining_data);
 
+        let remaining_bits = remaining_data.len() * 8 + hash.partial_byte.bits();
         let message_length_bits =
-            if hash.message_length as u128 +
-                remaining_data.len() as u128 * 8_u128 > u64::MAX as u128 {
+            if hash.message_length as u128 + remaining_bits as u128 > u64::MAX as u128 {
                 // todo maybe throw an error here?
                 panic!("cannot hash more than 2**64 - 1 bits.")
             } else {
-                hash.message_length + (remaining_data.len() * 8) as u64
+                hash.message_length + remaining_bits as u64
             };
 
-        // append a single 1-bit to the end of the message
-        last_block[remaining_data.len()] = 0x80_u8;
+        // append a single 1-bit to the end of the message, which may end in an incomplete byte
+        last_block[remaining_data.len()] = hash.partial_byte.padded();
 
         // if there is not enough space for the message length to be appended, a new block must be
         // created
         if remaining_data.len() + 1 + size_of::<u64>() > BLOCK_LENGTH_BYTES {
             let mut overflow_block = [0_u8; BLOCK_LENGTH_BYTES];
             // append the message length in bits
-            for i in 0..8 {
-                overflow_block[BLOCK_LENGTH_BYTES - 8 + i] =
-                    (message_length_bits >> (i * 8) as u64) as u8;
-            }
+            write_u64_le(&mut overflow_block, BLOCK_LENGTH_BYTES - size_of::<u64>(), message_length_bits);
 
             round_function(hash, &last_block);
             round_function(hash, &overflow_block);
         } else {
             // append the message length in bits
-            for i in 0..8 {
-                last_block[56 + i] = (message_length_bits >> (i * 8) as u64) as u8;
-            }
+            write_u64_le(&mut last_block, BLOCK_LENGTH_BYTES - size_of::<u64>(), message_length_bits);
 
             round_function(hash, &last_block);
         }
@@ -204,7 +256,7 @@ impl HashFunction for MD5Hash {
 
     fn digest_message(ctx: &Self::Context, input: &[u8]) -> Self::HashData {
         let mut hash_state = Self::init_hash(ctx);
-        Self::update_hash(&mut hash_state, ctx, &input);
+        Self::update_hash(&mut hash_state, input);
 
         // pad and digest last block
         Self::finish_hash(&mut hash_state, ctx)
@@ -215,23 +267,134 @@ impl BlockHashFunction for MD5Hash {
     fn block_size(_ctx: &Self::Context) -> usize {
         BLOCK_LENGTH_BYTES
     }
+}
+
+impl BitHashFunction for MD5Hash {
+    fn update_hash_bits(hash: &mut Self::HashState, _ctx: &Self::Context, data: &[u8], bit_len: usize) {
+        // the complete bytes are compressed like byte oriented input, while the partial byte is kept aside
+        let mut partial_byte = take(&mut hash.partial_byte);
+        let bytes = partial_byte.append(data, bit_len);
+        Self::update_hash(hash, &bytes);
+        hash.partial_byte = partial_byte;
+    }
+}
 
-    fn output_size(_ctx: &Self::Context) -> usize {
-        size_of::<MD5Hash>()
+impl MD5Hash {
+    /// The 16 bytes of the hash, like `raw()` but without allocating. The words are serialized in little endian
+    /// byte order.
+    pub fn to_bytes(&self) -> [u8; 16] {
+        let mut bytes = [0; 16];
+        for (chunk, word) in bytes.chunks_exact_mut(4).zip([self.0, self.1, self.2, self.3].iter()) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        bytes
     }
 }
 
 impl HashValue for MD5Hash {
-    /// Generates a raw `[u8; 16]` array from the current hash state.
+    /// Generates the 16 bytes of the hash from the current hash state. The words are serialized in little endian
+    /// byte order, independent of the byte order of the host.
     fn raw(&self) -> Vec<u8> {
-        unsafe {
-            mem::transmute::<[u32; 4], [u8; 16]>([
-                u32::from_le(self.0),
-                u32::from_le(self.1),
-                u32::from_le(self.2),
-                u32::from_le(self.3),
-            ])
-        }
-            .to_vec()
+        self.to_bytes().to_vec()
+    }
+}
+
+impl_hex_format!(MD5Hash);
+
+impl FromStr for MD5Hash {
+    type Err = ParseHexError;
+
+    /// Parse the 32 hex digits of `raw()` in either case. The words are read in little endian byte order.
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        let bytes = decode_hex_array::<16>(hex)?;
+        let word = |i: usize| read_u32_le(&bytes, 4 * i);
+        Ok(MD5Hash(word(0), word(1), word(2), word(3)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(hash: &MD5Hash) -> (u32, u32, u32, u32) {
+        (hash.0, hash.1, hash.2, hash.3)
+    }
+
+    #[test]
+    fn test_compress_trace() {
+        let block: [u8; 64] = [0x5A; 64];
+        let mut state = MD5Hash::init_hash(&());
+        round_function(&mut state, &block);
+
+        let (trace, chaining_value) = compress_trace(&INITIAL, &block);
+        assert_eq!(chaining_value, state.hash);
+
+        // the chaining value is the sum of the last round state and the input state
+        assert_eq!(words(&chaining_value).0, INITIAL.0.wrapping_add(trace[63].0));
+    }
+
+    #[test]
+    fn test_block_function() {
+        let initial = [INITIAL.0, INITIAL.1, INITIAL.2, INITIAL.3];
+
+        // "abc" padded to a single block, with its length in bits in little endian byte order
+        let mut block = [0; 64];
+        block[..4].copy_from_slice(b"abc\x80");
+        block[56] = 24;
+
+        let MD5Hash(a, b, c, d) = "900150983cd24fb0d6963f7d28e17f72".parse().unwrap();
+        assert_eq!(md5_block(initial, &block), [a, b, c, d]);
+
+        // the first block of a longer message is compressed into the initial state alone
+        let (_, MD5Hash(a, b, c, d)) = compress_trace(&INITIAL, &[0x5A; 64]);
+        assert_eq!(md5_block(initial, &[0x5A; 64]), [a, b, c, d]);
+    }
+
+    #[test]
+    fn test_raw_byte_order() {
+        let hash = MD5Hash(0x01234567, 0x89ABCDEF, 0xFEDCBA98, 0x76543210);
+
+        assert_eq!(hash.raw(), vec![
+            0x67, 0x45, 0x23, 0x01, 0xEF, 0xCD, 0xAB, 0x89, 0x98, 0xBA, 0xDC, 0xFE, 0x10, 0x32, 0x54, 0x76,
+        ]);
+        assert_eq!(hash.to_bytes().to_vec(), hash.raw());
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let hash = MD5Hash(0x01234567, 0x89ABCDEF, 0xFEDCBA98, 0x76543210);
+        assert_eq!(hash.to_string(), "67452301efcdab8998badcfe10325476");
+        assert_eq!(format!("{:x}", hash), "67452301efcdab8998badcfe10325476");
+        assert_eq!(format!("{:X}", hash), "67452301EFCDAB8998BADCFE10325476");
+
+        let parsed: MD5Hash = "67452301EFcdab8998BADCFE10325476".parse().unwrap();
+        assert_eq!(parsed, hash);
+        assert_eq!(parsed.to_string(), hash.to_string());
+
+        assert_eq!("67452301".parse::<MD5Hash>().unwrap_err(), ParseHexError::InvalidLength { length: 4 });
+        assert_eq!(
+            "67452301efcdab8998badcfe1032547x".parse::<MD5Hash>().unwrap_err(),
+            ParseHexError::InvalidCharacter { position: 31 }
+        );
+    }
+
+    #[test]
+    fn test_bit_messages() {
+        let digest_bits = |data: &[u8], bit_len| {
+            let mut hash_state = MD5Hash::init_hash(&());
+            MD5Hash::update_hash_bits(&mut hash_state, &(), data, bit_len);
+            MD5Hash::finish_hash_bits(&mut hash_state, &()).to_string()
+        };
+
+        assert_eq!(digest_bits(&[0x98], 5), "e0ce190aabc2e4aa602238ca5b81dd6d");
+        assert_eq!(digest_bits(&(0..200).collect::<Vec<u8>>(), 1003), "56152c953c96f91ec40fbffef1a82b4b");
+    }
+
+    #[test]
+    fn test_zero_block_rounds() {
+        let (trace, _) = compress_trace(&INITIAL, &[0; 64]);
+
+        assert_eq!(words(&trace[0]), (0x10325476, 0xa51fe774, 0xefcdab89, 0x98badcfe));
+        assert_eq!(words(&trace[1]), (0x98badcfe, 0x219552de, 0xa51fe774, 0xefcdab89));
     }
 }
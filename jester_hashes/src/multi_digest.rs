@@ -0,0 +1,438 @@
+//! A self-describing digest container for storage that may migrate hash algorithms over time. A `MultiDigest` is
+//! encoded like a multihash: the code of the algorithm as an unsigned varint, the length of the digest as an unsigned
+//! varint and the digest bytes. The codes are those of the multicodec table, including the per-length codes of
+//! Blake2b and Blake2s. Whirlpool and CRC-32C have no code in the table and use codes of its private use range.
+//!
+//! A digest can be verified without knowing its algorithm at compile time, which is why only unkeyed hash functions
+//! whose context has a default are supported.
+
+use crate::blake::blake2b::{Blake2b, Blake2bContext, BLAKE_2B_MAX_OUTPUT_LENGTH};
+use crate::blake::blake2s::{Blake2s, Blake2sContext, BLAKE_2S_MAX_OUTPUT_LENGTH};
+use crate::crc::crc32::Crc32;
+use crate::crc::crc32c::Crc32c;
+use crate::md5::MD5Hash;
+use crate::sha1::SHA1Hash;
+use crate::sm3::SM3Hash;
+use crate::whirlpool::WhirlpoolHash;
+use crate::{capabilities, constant_time_eq, Capabilities, DefaultContextHash, HashFunction, HashValue};
+
+/// The first code of the Blake2b codes, which is the code of the digest with 1 byte
+const BLAKE_2B_CODE_BASE: u64 = 0xb201;
+
+/// The first code of the Blake2s codes, which is the code of the digest with 1 byte
+const BLAKE_2S_CODE_BASE: u64 = 0xb241;
+
+/// The longest varint accepted when decoding, as in the multiformats specification
+const MAX_VARINT_LENGTH: usize = 9;
+
+/// A function that digests data with the given number of bytes of output
+type DigestFunction = fn(&[u8], usize) -> Vec<u8>;
+
+/// The hash algorithms a `MultiDigest` can identify.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    Blake2b,
+    Blake2s,
+    Sm3,
+    Whirlpool,
+    Crc32,
+    Crc32c,
+}
+
+impl HashAlgorithm {
+    /// All algorithms, in the order of their declaration
+    pub const ALL: [HashAlgorithm; 8] = [
+        HashAlgorithm::Md5,
+        HashAlgorithm::Sha1,
+        HashAlgorithm::Blake2b,
+        HashAlgorithm::Blake2s,
+        HashAlgorithm::Sm3,
+        HashAlgorithm::Whirlpool,
+        HashAlgorithm::Crc32,
+        HashAlgorithm::Crc32c,
+    ];
+
+    /// The lowercase name of the algorithm, e.g. to select it on a command line
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Sha1 => "sha1",
+            HashAlgorithm::Blake2b => "blake2b",
+            HashAlgorithm::Blake2s => "blake2s",
+            HashAlgorithm::Sm3 => "sm3",
+            HashAlgorithm::Whirlpool => "whirlpool",
+            HashAlgorithm::Crc32 => "crc32",
+            HashAlgorithm::Crc32c => "crc32c",
+        }
+    }
+
+    /// Look up an algorithm by its `name`, ignoring case.
+    ///
+    /// # Returns
+    /// The algorithm or `None` if no algorithm has the given name
+    pub fn from_name(name: &str) -> Option<Self> {
+        HashAlgorithm::ALL.iter().copied().find(|algorithm| algorithm.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Whether the algorithm is compiled into this build, see `crate::capabilities`. Digests of unavailable
+    /// algorithms are rejected when decoded and never verify.
+    pub fn is_available(self) -> bool {
+        capabilities().contains(self.capability())
+    }
+
+    /// The flag of the algorithm in the `Capabilities` of the crate
+    fn capability(self) -> Capabilities {
+        match self {
+            HashAlgorithm::Md5 => Capabilities::MD5,
+            HashAlgorithm::Sha1 => Capabilities::SHA1,
+            HashAlgorithm::Blake2b => Capabilities::BLAKE2B,
+            HashAlgorithm::Blake2s => Capabilities::BLAKE2S,
+            HashAlgorithm::Sm3 => Capabilities::SM3,
+            HashAlgorithm::Whirlpool => Capabilities::WHIRLPOOL,
+            HashAlgorithm::Crc32 => Capabilities::CRC32,
+            HashAlgorithm::Crc32c => Capabilities::CRC32C,
+        }
+    }
+
+    /// The code identifying a digest of `length` bytes of this algorithm. Only the codes of Blake2b and Blake2s
+    /// depend on the length.
+    fn code(self, length: usize) -> u64 {
+        match self {
+            HashAlgorithm::Md5 => 0xd5,
+            HashAlgorithm::Sha1 => 0x11,
+            HashAlgorithm::Blake2b => BLAKE_2B_CODE_BASE + length as u64 - 1,
+            HashAlgorithm::Blake2s => BLAKE_2S_CODE_BASE + length as u64 - 1,
+            HashAlgorithm::Sm3 => 0x534d,
+            HashAlgorithm::Whirlpool => 0x30_0001,
+            HashAlgorithm::Crc32 => 0x0132,
+            HashAlgorithm::Crc32c => 0x30_0002,
+        }
+    }
+
+    /// The algorithm identified by `code` and the length of its digests.
+    fn from_code(code: u64) -> Option<(Self, usize)> {
+        let blake_2b_codes = BLAKE_2B_CODE_BASE..BLAKE_2B_CODE_BASE + BLAKE_2B_MAX_OUTPUT_LENGTH as u64;
+        let blake_2s_codes = BLAKE_2S_CODE_BASE..BLAKE_2S_CODE_BASE + BLAKE_2S_MAX_OUTPUT_LENGTH as u64;
+
+        let algorithm = match code {
+            0xd5 => HashAlgorithm::Md5,
+            0x11 => HashAlgorithm::Sha1,
+            code if blake_2b_codes.contains(&code) =>
+                return Some((HashAlgorithm::Blake2b, (code - BLAKE_2B_CODE_BASE) as usize + 1)),
+            code if blake_2s_codes.contains(&code) =>
+                return Some((HashAlgorithm::Blake2s, (code - BLAKE_2S_CODE_BASE) as usize + 1)),
+            0x534d => HashAlgorithm::Sm3,
+            0x30_0001 => HashAlgorithm::Whirlpool,
+            0x0132 => HashAlgorithm::Crc32,
+            0x30_0002 => HashAlgorithm::Crc32c,
+            _ => return None,
+        };
+
+        Some((algorithm, algorithm.digest_function().0))
+    }
+
+    /// The registry of digest functions: the output size and a function that digests data with `length` bytes of
+    /// output. Only Blake2b and Blake2s make use of the length, all other algorithms have a fixed output size.
+    fn digest_function(self) -> (usize, DigestFunction) {
+        fn fixed<H>(data: &[u8], _length: usize) -> Vec<u8>
+            where H: HashFunction,
+                  H::Context: Default
+        {
+            H::digest(data).raw()
+        }
+
+        match self {
+            HashAlgorithm::Md5 => (MD5Hash::MAX_OUTPUT_SIZE, fixed::<MD5Hash>),
+            HashAlgorithm::Sha1 => (SHA1Hash::MAX_OUTPUT_SIZE, fixed::<SHA1Hash>),
+            HashAlgorithm::Blake2b => (Blake2b::MAX_OUTPUT_SIZE, |data, length| {
+                Blake2b::digest_message(&Blake2bContext::new(length).unwrap(), data).raw()
+            }),
+            HashAlgorithm::Blake2s => (Blake2s::MAX_OUTPUT_SIZE, |data, length| {
+                Blake2s::digest_message(&Blake2sContext::new(length).unwrap(), data).raw()
+            }),
+            HashAlgorithm::Sm3 => (SM3Hash::MAX_OUTPUT_SIZE, fixed::<SM3Hash>),
+            HashAlgorithm::Whirlpool => (WhirlpoolHash::MAX_OUTPUT_SIZE, fixed::<WhirlpoolHash>),
+            HashAlgorithm::Crc32 => (Crc32::MAX_OUTPUT_SIZE, fixed::<Crc32>),
+            HashAlgorithm::Crc32c => (Crc32c::MAX_OUTPUT_SIZE, fixed::<Crc32c>),
+        }
+    }
+}
+
+/// A hash function whose digests can be wrapped in a `MultiDigest`.
+pub trait IdentifiedHashFunction: HashFunction {
+    /// The algorithm identifying the digests of this hash function
+    const ALGORITHM: HashAlgorithm;
+}
+
+impl IdentifiedHashFunction for MD5Hash {
+    const ALGORITHM: HashAlgorithm = HashAlgorithm::Md5;
+}
+
+impl IdentifiedHashFunction for SHA1Hash {
+    const ALGORITHM: HashAlgorithm = HashAlgorithm::Sha1;
+}
+
+impl IdentifiedHashFunction for Blake2b {
+    const ALGORITHM: HashAlgorithm = HashAlgorithm::Blake2b;
+}
+
+impl IdentifiedHashFunction for Blake2s {
+    const ALGORITHM: HashAlgorithm = HashAlgorithm::Blake2s;
+}
+
+impl IdentifiedHashFunction for SM3Hash {
+    const ALGORITHM: HashAlgorithm = HashAlgorithm::Sm3;
+}
+
+impl IdentifiedHashFunction for WhirlpoolHash {
+    const ALGORITHM: HashAlgorithm = HashAlgorithm::Whirlpool;
+}
+
+impl IdentifiedHashFunction for Crc32 {
+    const ALGORITHM: HashAlgorithm = HashAlgorithm::Crc32;
+}
+
+impl IdentifiedHashFunction for Crc32c {
+    const ALGORITHM: HashAlgorithm = HashAlgorithm::Crc32c;
+}
+
+/// A digest together with the algorithm that produced it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiDigest {
+    algorithm: HashAlgorithm,
+    digest: Vec<u8>,
+}
+
+/// Errors that can arise when decoding an encoded `MultiDigest`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input ends within a varint or the digest
+    Truncated {},
+
+    /// A varint is longer than `MAX_VARINT_LENGTH` bytes
+    MalformedVarint {},
+
+    /// The algorithm code is not supported
+    UnknownAlgorithm { code: u64 },
+
+    /// The algorithm code is known, but the algorithm is not compiled into this build
+    UnavailableAlgorithm { algorithm: HashAlgorithm },
+
+    /// The digest length does not match the algorithm code
+    InvalidLength { algorithm: HashAlgorithm, length: usize },
+
+    /// The input continues after the digest
+    TrailingBytes { count: usize },
+}
+
+impl MultiDigest {
+    /// Hash `data` with the hash function `H` in the context `ctx`. The context must be unkeyed, otherwise the digest
+    /// cannot be verified.
+    pub fn digest<H>(ctx: &H::Context, data: &[u8]) -> Self
+        where H: IdentifiedHashFunction
+    {
+        MultiDigest {
+            algorithm: H::ALGORITHM,
+            digest: H::digest_message(ctx, data).raw(),
+        }
+    }
+
+    /// The algorithm that produced the digest
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// The raw digest without algorithm code and length
+    pub fn digest_bytes(&self) -> &[u8] {
+        &self.digest
+    }
+
+    /// Whether this is the digest of `data`. The digest is recomputed with the algorithm of this digest and compared
+    /// in constant time.
+    pub fn verify(&self, data: &[u8]) -> bool {
+        if !self.algorithm.is_available() {
+            return false;
+        }
+
+        let (_, digest_function) = self.algorithm.digest_function();
+        constant_time_eq(&digest_function(data, self.digest.len()), &self.digest)
+    }
+
+    /// Encode this digest as the varint of its algorithm code, the varint of its length and the digest bytes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoded = Vec::with_capacity(2 * MAX_VARINT_LENGTH + self.digest.len());
+        encode_varint(self.algorithm.code(self.digest.len()), &mut encoded);
+        encode_varint(self.digest.len() as u64, &mut encoded);
+        encoded.extend_from_slice(&self.digest);
+        encoded
+    }
+
+    /// Decode a digest encoded by `encode`.
+    ///
+    /// # Returns
+    /// The digest, or a `DecodeError` if the input is malformed, the algorithm is unknown or unavailable or the length
+    /// of the digest does not match its algorithm.
+    pub fn decode(encoded: &[u8]) -> Result<Self, DecodeError> {
+        let (code, remaining) = decode_varint(encoded)?;
+        let (length, remaining) = decode_varint(remaining)?;
+
+        let (algorithm, expected_length) =
+            HashAlgorithm::from_code(code).ok_or(DecodeError::UnknownAlgorithm { code })?;
+        if !algorithm.is_available() {
+            return Err(DecodeError::UnavailableAlgorithm { algorithm });
+        }
+        if length != expected_length as u64 {
+            return Err(DecodeError::InvalidLength { algorithm, length: length as usize });
+        }
+
+        if remaining.len() < expected_length {
+            return Err(DecodeError::Truncated {});
+        }
+        if remaining.len() > expected_length {
+            return Err(DecodeError::TrailingBytes { count: remaining.len() - expected_length });
+        }
+
+        Ok(MultiDigest {
+            algorithm,
+            digest: remaining.to_vec(),
+        })
+    }
+}
+
+/// Append `value` as an unsigned LEB128 varint to `output`.
+fn encode_varint(mut value: u64, output: &mut Vec<u8>) {
+    while value >= 0x80 {
+        output.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+/// Decode an unsigned LEB128 varint from the start of `input`.
+///
+/// # Returns
+/// The value and the remaining input
+fn decode_varint(input: &[u8]) -> Result<(u64, &[u8]), DecodeError> {
+    let mut value = 0_u64;
+    for (index, byte) in input.iter().enumerate() {
+        if index == MAX_VARINT_LENGTH {
+            return Err(DecodeError::MalformedVarint {});
+        }
+
+        value |= u64::from(byte & 0x7f) << (7 * index);
+        if byte & 0x80 == 0 {
+            return Ok((value, &input[index + 1..]));
+        }
+    }
+
+    if input.len() >= MAX_VARINT_LENGTH {
+        Err(DecodeError::MalformedVarint {})
+    } else {
+        Err(DecodeError::Truncated {})
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: &[u8] = b"The quick brown fox jumps over the lazy dog";
+
+    fn assert_round_trip<H>(ctx: &H::Context)
+        where H: IdentifiedHashFunction
+    {
+        let digest = MultiDigest::digest::<H>(ctx, DATA);
+        assert_eq!(digest.algorithm(), H::ALGORITHM);
+        assert_eq!(digest.digest_bytes(), H::digest_message(ctx, DATA).raw().as_slice());
+        assert_eq!(MultiDigest::decode(&digest.encode()), Ok(digest.clone()));
+        assert!(digest.verify(DATA));
+        assert!(!digest.verify(b"The quick brown fox jumps over the lazy cog"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        assert_round_trip::<MD5Hash>(&());
+        assert_round_trip::<SHA1Hash>(&());
+        assert_round_trip::<Blake2b>(&Blake2bContext::default());
+        assert_round_trip::<Blake2b>(&Blake2bContext::new(32).unwrap());
+        assert_round_trip::<Blake2b>(&Blake2bContext::new(1).unwrap());
+        assert_round_trip::<Blake2s>(&Blake2sContext::default());
+        assert_round_trip::<Blake2s>(&Blake2sContext::new(20).unwrap());
+        assert_round_trip::<SM3Hash>(&());
+        assert_round_trip::<WhirlpoolHash>(&());
+        assert_round_trip::<Crc32>(&());
+        assert_round_trip::<Crc32c>(&());
+    }
+
+    #[test]
+    fn test_stable_encoding() {
+        assert_eq!(
+            hex::encode(MultiDigest::digest::<MD5Hash>(&(), b"abc").encode()),
+            "d50110900150983cd24fb0d6963f7d28e17f72"
+        );
+        assert_eq!(
+            hex::encode(MultiDigest::digest::<SHA1Hash>(&(), b"abc").encode()),
+            "1114a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            hex::encode(&MultiDigest::digest::<Blake2b>(&Blake2bContext::new(32).unwrap(), b"abc").encode()[..4]),
+            "a0e40220"
+        );
+    }
+
+    #[test]
+    fn test_names() {
+        for algorithm in HashAlgorithm::ALL.iter() {
+            assert_eq!(HashAlgorithm::from_name(algorithm.name()), Some(*algorithm));
+        }
+
+        assert_eq!(HashAlgorithm::from_name("BLAKE2b"), Some(HashAlgorithm::Blake2b));
+        assert_eq!(HashAlgorithm::from_name("sha256"), None);
+    }
+
+    #[test]
+    fn test_registry_capabilities() {
+        let mut registered = Capabilities::empty();
+        for algorithm in HashAlgorithm::ALL.iter() {
+            assert!(algorithm.is_available());
+            assert!(!registered.contains(algorithm.capability()));
+            assert_eq!(algorithm.capability().names(), vec![algorithm.name()]);
+            registered |= algorithm.capability();
+        }
+
+        assert!(capabilities().contains(registered));
+    }
+
+    #[test]
+    fn test_verify_tampered_digest() {
+        let mut encoded = MultiDigest::digest::<SHA1Hash>(&(), DATA).encode();
+        *encoded.last_mut().unwrap() ^= 1;
+
+        let digest = MultiDigest::decode(&encoded).unwrap();
+        assert!(!digest.verify(DATA));
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let encoded = MultiDigest::digest::<MD5Hash>(&(), DATA).encode();
+
+        assert_eq!(MultiDigest::decode(&[]), Err(DecodeError::Truncated {}));
+        assert_eq!(MultiDigest::decode(&encoded[..1]), Err(DecodeError::Truncated {}));
+        assert_eq!(MultiDigest::decode(&encoded[..encoded.len() - 1]), Err(DecodeError::Truncated {}));
+        assert_eq!(
+            MultiDigest::decode(&[&encoded[..], &[0]].concat()),
+            Err(DecodeError::TrailingBytes { count: 1 })
+        );
+        assert_eq!(
+            MultiDigest::decode(&[0x12, 0x20]),
+            Err(DecodeError::UnknownAlgorithm { code: 0x12 })
+        );
+        assert_eq!(
+            MultiDigest::decode(&[0xd5, 0x01, 0x14]),
+            Err(DecodeError::InvalidLength { algorithm: HashAlgorithm::Md5, length: 20 })
+        );
+        assert_eq!(MultiDigest::decode(&[0xff; 12]), Err(DecodeError::MalformedVarint {}));
+    }
+}
@@ -0,0 +1,149 @@
+//! Wrappers that implement the traits of the RustCrypto `digest` crate for the hash functions of this crate, so they
+//! can be used in generic code written against `digest::Digest`. The wrappers delegate to the `HashFunction`
+//! implementations and only support their default parameters.
+//!
+//! This module is only available with the `rustcrypto-compat` feature.
+
+use std::fmt;
+
+use digest::consts::{U16, U20, U64};
+use digest::{FixedOutput, FixedOutputReset, HashMarker, Output, OutputSizeUser, Reset, Update};
+
+use crate::blake::blake2b::{Blake2b, Blake2bContext};
+use crate::md5::MD5Hash;
+use crate::sha1::SHA1Hash;
+use crate::{HashFunction, HashValue};
+
+/// Generate a wrapper type around the state of a `HashFunction` and implement the `digest` traits for it.
+macro_rules! compat_wrapper {
+    ($(#[$attr:meta])* $name:ident, $hash:ty, $context:expr, $output_size:ty) => {
+        $(#[$attr])*
+        #[derive(Clone)]
+        pub struct $name {
+            state: <$hash as HashFunction>::HashState,
+        }
+
+        impl $name {
+            fn context() -> <$hash as HashFunction>::Context {
+                $context
+            }
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                $name { state: <$hash>::init_hash(&Self::context()) }
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(concat!(stringify!($name), " { ... }"))
+            }
+        }
+
+        impl HashMarker for $name {}
+
+        impl OutputSizeUser for $name {
+            type OutputSize = $output_size;
+        }
+
+        impl Update for $name {
+            fn update(&mut self, data: &[u8]) {
+                <$hash>::update_hash(&mut self.state, data)
+            }
+        }
+
+        impl FixedOutput for $name {
+            fn finalize_into(mut self, out: &mut Output<Self>) {
+                let hash = <$hash>::finish_hash(&mut self.state, &Self::context());
+                out.copy_from_slice(&hash.raw());
+            }
+        }
+
+        impl Reset for $name {
+            fn reset(&mut self) {
+                <$hash>::reset_hash(&mut self.state, &Self::context())
+            }
+        }
+
+        impl FixedOutputReset for $name {
+            fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+                let hash = <$hash>::finish_hash(&mut self.state, &Self::context());
+                out.copy_from_slice(&hash.raw());
+                self.reset();
+            }
+        }
+    };
+}
+
+compat_wrapper!(
+    /// MD5 as a `digest::Digest`.
+    CompatMd5, MD5Hash, (), U16
+);
+
+compat_wrapper!(
+    /// SHA-1 as a `digest::Digest`.
+    CompatSha1, SHA1Hash, (), U20
+);
+
+compat_wrapper!(
+    /// Unkeyed Blake2b with 64 bytes of output as a `digest::Digest`.
+    CompatBlake2b512, Blake2b, Blake2bContext::new(64).unwrap(), U64
+);
+
+#[cfg(test)]
+mod tests {
+    use digest::dev::fixed_reset_test;
+    use digest::Digest;
+
+    use super::*;
+    use crate::tests::{EMPTY_MESSAGE, LONG_TEXT, SOME_TEXT};
+
+    fn assert_conformance<D>(vectors: &[(&str, &str)])
+        where D: FixedOutputReset + fmt::Debug + Clone + Default + Update + HashMarker
+    {
+        for (input, output) in vectors {
+            let output = hex::decode(output).unwrap();
+            if let Some(failure) = fixed_reset_test::<D>(input.as_bytes(), &output) {
+                panic!("{:?} failed for input {:?}: {}", D::default(), input, failure);
+            }
+        }
+    }
+
+    #[test]
+    fn test_md5() {
+        assert_conformance::<CompatMd5>(&[
+            (EMPTY_MESSAGE, "d41d8cd98f00b204e9800998ecf8427e"),
+            ("The quick brown fox jumps over the lazy dog", "9e107d9d372bb6826bd81d3542a419d6"),
+        ]);
+    }
+
+    #[test]
+    fn test_sha1() {
+        assert_conformance::<CompatSha1>(&[
+            (EMPTY_MESSAGE, "da39a3ee5e6b4b0d3255bfef95601890afd80709"),
+            ("The quick brown fox jumps over the lazy dog", "2fd4e1c67a2d28fced849ee1bb76e7391b93eb12"),
+        ]);
+    }
+
+    #[test]
+    fn test_blake2b() {
+        assert_conformance::<CompatBlake2b512>(&[
+            (EMPTY_MESSAGE, "786a02f742015903c6c6fd852552d272912f4740e15847618a86e217f71f5419d25e1031afee585313896444934eb04b903a685b1448b755d56f701afe9be2ce"),
+            (SOME_TEXT, "fc918cde2b169d192d19438620f2a9b1d1d4cce16dc8b8e8600377a577a74ace2a65a21f1cb3d3f0e3abf97e88d804e8aa4d674df143e7070976018e2ae9060f"),
+            (LONG_TEXT, "ef403f8bd8f4f821376cf108e5004c78df3b7a99d198c166c7b8d1e6a409e10312bc273e3299a755b2cf75a5db85222266dd77215f80340363359656c621bf69"),
+        ]);
+    }
+
+    /// The wrappers can be used in code that is generic over `Digest`.
+    #[test]
+    fn test_generic_digest() {
+        fn digest_hex<D: Digest>(input: &[u8]) -> String {
+            hex::encode(D::digest(input))
+        }
+
+        assert_eq!(digest_hex::<CompatMd5>(b"abc"), "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(digest_hex::<CompatSha1>(b"abc"), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        assert_eq!(<CompatBlake2b512 as Digest>::output_size(), 64);
+    }
+}
@@ -4,8 +4,14 @@ use std::mem;
 use std::mem::size_of;
 use std::mem::take;
 
-use crate::{align_to_u32a_be, HashFunction, HashValue, BlockHashFunction};
+use crate::{BitHashFunction, HashFunction, HashValue, BlockHashFunction, NoObserver, RoundObserver};
+use crate::bits::PartialByte;
+use crate::bytes::{read_u32_be, write_u64_be};
 use std::convert::TryInto;
+use std::str::FromStr;
+
+use crate::ParseHexError;
+use crate::hex_format::decode_hex_array;
 
 const BLOCK_LENGTH_BYTES: usize = 64;
 
@@ -19,7 +25,7 @@ pub const INITIAL: SHA1Hash = SHA1Hash {
 };
 
 /// A SHA1 hash state. It consists mainly out of 5 double-words named `a`, `b`, `c`, `d` and `e`.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct SHA1Hash {
     pub a: u32,
     pub b: u32,
@@ -28,15 +34,56 @@ pub struct SHA1Hash {
     pub e: u32,
 }
 
+#[derive(Clone)]
 pub struct SHA1HashState {
     hash: SHA1Hash,
     message_length: u64,
     remaining_data: Vec<u8>,
+    partial_byte: PartialByte,
 }
 
 fn round_function(hash: &mut SHA1HashState, block: &[u8; 64]) {
+    hash.hash = compress(&hash.hash, block, &mut NoObserver);
+
+    if hash.message_length as u128 + 64_u128 * 8 > u64::MAX as u128 {
+        // todo maybe throw an error here?
+        panic!("cannot hash more than 2**64 - 1 bits.")
+    } else {
+        hash.message_length += 64 * 8
+    }
+}
+
+/// Compute the trace of a single compression of `block` into the chaining value `state`. The trace contains the
+/// working state `(a, b, c, d, e)` after each of the 80 rounds, before it is added to the chaining value.
+///
+/// # Returns
+/// A tuple of the trace and the resulting chaining value
+pub fn compress_trace(state: &SHA1Hash, block: &[u8; 64]) -> ([SHA1Hash; 80], SHA1Hash) {
+    let mut trace = [*state; 80];
+    let chaining_value = compress(state, block, &mut trace);
+    (trace, chaining_value)
+}
+
+/// Compress one block into the chaining value `iv`. This is the bare SHA-1 compression function without padding or
+/// length tracking, as a building block for custom constructions like those of `constructions`. Compressing the padded
+/// message into `INITIAL` block by block yields the SHA-1 hash.
+///
+/// # Returns
+/// The words of the new chaining value in the order `(a, b, c, d, e)`
+pub fn sha1_block(iv: [u32; 5], block: &[u8; 64]) -> [u32; 5] {
+    let state = SHA1Hash { a: iv[0], b: iv[1], c: iv[2], d: iv[3], e: iv[4] };
+    let SHA1Hash { a, b, c, d, e } = compress(&state, block, &mut NoObserver);
+    [a, b, c, d, e]
+}
+
+/// Compress one block into the chaining value `state` and report the working state after each round to `observer`.
+fn compress<O>(state: &SHA1Hash, block: &[u8; 64], observer: &mut O) -> SHA1Hash
+    where O: RoundObserver<SHA1Hash>
+{
     let mut extended_block = [0_u32; 80];
-    unsafe { align_to_u32a_be(&mut extended_block[0..16], block) };
+    for (i, word) in extended_block[0..16].iter_mut().enumerate() {
+        *word = read_u32_be(block, i * 4);
+    }
 
     for i in 16..80 {
         extended_block[i] = u32::rotate_left(
@@ -48,7 +95,7 @@ fn round_function(hash: &mut SHA1HashState, block: &[u8; 64]) {
         )
     }
 
-    let mut round_state = hash.hash;
+    let mut round_state = *state;
 
     for (i, data_word) in extended_block.iter().enumerate() {
         let (scrambled_data, magic_constant) = match i {
@@ -77,19 +124,16 @@ fn round_function(hash: &mut SHA1HashState, block: &[u8; 64]) {
         round_state.c = u32::rotate_left(round_state.b, 30);
         round_state.b = round_state.a;
         round_state.a = temp;
-    }
 
-    hash.hash.a = hash.hash.a.wrapping_add(round_state.a);
-    hash.hash.b = hash.hash.b.wrapping_add(round_state.b);
-    hash.hash.c = hash.hash.c.wrapping_add(round_state.c);
-    hash.hash.d = hash.hash.d.wrapping_add(round_state.d);
-    hash.hash.e = hash.hash.e.wrapping_add(round_state.e);
+        observer.observe_round(i, &round_state);
+    }
 
-    if hash.message_length as u128 + 64_u128 * 8 > u64::MAX as u128 {
-        // todo maybe throw an error here?
-        panic!("cannot hash more than 2**64 - 1 bits.")
-    } else {
-        hash.message_length += 64 * 8
+    SHA1Hash {
+        a: state.a.wrapping_add(round_state.a),
+        b: state.b.wrapping_add(round_state.b),
+        c: state.c.wrapping_add(round_state.c),
+        d: state.d.wrapping_add(round_state.d),
+        e: state.e.wrapping_add(round_state.e),
     }
 }
 
@@ -98,11 +142,26 @@ impl HashFunction for SHA1Hash {
     type HashState = SHA1HashState;
     type HashData = SHA1Hash;
 
+    const MAX_OUTPUT_SIZE: usize = mem::size_of::<Self>();
+
     fn init_hash(_ctx: &Self::Context) -> Self::HashState {
-        SHA1HashState { hash: INITIAL, message_length: 0, remaining_data: vec![] }
+        SHA1HashState { hash: INITIAL, message_length: 0, remaining_data: vec![], partial_byte: PartialByte::default() }
+    }
+
+    /// Reset the state in place, keeping the allocation of the remaining data buffer.
+    fn reset_hash(hash: &mut Self::HashState, _ctx: &Self::Context) {
+        hash.hash = INITIAL;
+        hash.message_length = 0;
+        hash.remaining_data.clear();
+        hash.partial_byte = PartialByte::default();
     }
 
-    fn update_hash(hash: &mut Self::HashState, _ctx: &Self::Context, input: &[u8]) {
+    fn update_hash(hash: &mut Self::HashState, input: &[u8]) {
+        // bytes following an incomplete byte are not aligned to the buffer
+        if hash.partial_byte.bits() != 0 {
+            return Self::update_hash_bits(hash, &(), input, input.len() * 8);
+        }
+
         // offset of input data that is already processed during the use of the remaining data
         // stored in the state
         let mut input_data_offset = 0;
@@ -145,6 +204,7 @@ impl HashFunction for SHA1Hash {
     fn finish_hash(hash: &mut Self::HashState, _ctx: &Self::Context) ->
                                                                                    Self::HashData {
         let remaining_data = take(&mut hash.remaining_data);
+        let partial_byte = take(&mut hash.partial_byte);
 
         // prepare a zero-padded full-length block
         let mut last_block = [0_u8; BLOCK_LENGTH_BYTES];
@@ -152,16 +212,16 @@ impl HashFunction for SHA1Hash {
         // append the last part of message to the block
         last_block[..remaining_data.len()].copy_from_slice(&remaining_data);
 
-        // append a single 1-bit to the end of the message
-        last_block[remaining_data.len()] = 0x80_u8;
+        // append a single 1-bit to the end of the message, which may end in an incomplete byte
+        last_block[remaining_data.len()] = partial_byte.padded();
 
+        let remaining_bits = remaining_data.len() * 8 + partial_byte.bits();
         let message_length_bits =
-            if hash.message_length as u128 +
-                remaining_data.len() as u128 * 8_u128 > u64::MAX as u128 {
+            if hash.message_length as u128 + remaining_bits as u128 > u64::MAX as u128 {
                 // todo maybe throw an error here?
                 panic!("cannot hash more than 2**64 - 1 bits.")
             } else {
-                hash.message_length + (remaining_data.len() * 8) as u64
+                hash.message_length + remaining_bits as u64
             };
 
         // if there is not enough space for the message length to be appended, a new block must be
@@ -169,20 +229,13 @@ impl HashFunction for SHA1Hash {
         if remaining_data.len() + 1 + size_of::<u64>() > BLOCK_LENGTH_BYTES {
             let mut overflow_block = [0_u8; BLOCK_LENGTH_BYTES];
             // append the message length in bits
-            for i in 0..8 {
-                // note, that the number is appended backwards because it must be handled as a big endian number
-                overflow_block[BLOCK_LENGTH_BYTES - i - 1] =
-                    (message_length_bits >> (i * 8) as u64) as u8;
-            }
+            write_u64_be(&mut overflow_block, BLOCK_LENGTH_BYTES - size_of::<u64>(), message_length_bits);
 
             round_function(hash, &last_block);
             round_function(hash, &overflow_block);
         } else {
             // append the message length in bits
-            for i in 0..8 {
-                // note, that the number is appended backwards because it must be handled as a big endian number
-                last_block[63 - i] = (message_length_bits >> (i * 8) as u64) as u8;
-            }
+            write_u64_be(&mut last_block, BLOCK_LENGTH_BYTES - size_of::<u64>(), message_length_bits);
 
             round_function(hash, &last_block);
         }
@@ -194,7 +247,7 @@ impl HashFunction for SHA1Hash {
         let mut hash_state = Self::init_hash(ctx);
 
         // digest all data
-        Self::update_hash(&mut hash_state, ctx, &input);
+        Self::update_hash(&mut hash_state, input);
 
         // finish hashing by padding the remaining data within the hash state and digesting it
         Self::finish_hash(&mut hash_state, ctx);
@@ -203,19 +256,36 @@ impl HashFunction for SHA1Hash {
     }
 }
 
+impl SHA1Hash {
+    /// The 20 bytes of the hash, like `raw()` but without allocating. The words are serialized in big endian byte
+    /// order.
+    pub fn to_bytes(&self) -> [u8; 20] {
+        let mut bytes = [0; 20];
+        for (chunk, word) in bytes.chunks_exact_mut(4).zip([self.a, self.b, self.c, self.d, self.e].iter()) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        bytes
+    }
+}
+
 impl HashValue for SHA1Hash {
-    /// Generates a raw `[u8; 20]` array from the current hash state.
+    /// Generates the 20 bytes of the hash from the current hash state. The words are serialized in big endian byte
+    /// order, independent of the byte order of the host.
     fn raw(&self) -> Vec<u8> {
-        unsafe {
-            mem::transmute::<[u32; 5], [u8; 20]>([
-                u32::from_be(self.a),
-                u32::from_be(self.b),
-                u32::from_be(self.c),
-                u32::from_be(self.d),
-                u32::from_be(self.e),
-            ])
-        }
-            .to_vec()
+        self.to_bytes().to_vec()
+    }
+}
+
+impl_hex_format!(SHA1Hash);
+
+impl FromStr for SHA1Hash {
+    type Err = ParseHexError;
+
+    /// Parse the 40 hex digits of `raw()` in either case. The words are read in big endian byte order.
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        let bytes = decode_hex_array::<20>(hex)?;
+        let word = |i: usize| read_u32_be(&bytes, 4 * i);
+        Ok(SHA1Hash { a: word(0), b: word(1), c: word(2), d: word(3), e: word(4) })
     }
 }
 
@@ -223,8 +293,121 @@ impl BlockHashFunction for SHA1Hash {
     fn block_size(_ctx: &Self::Context) -> usize {
         BLOCK_LENGTH_BYTES
     }
+}
+
+impl BitHashFunction for SHA1Hash {
+    fn update_hash_bits(hash: &mut Self::HashState, _ctx: &Self::Context, data: &[u8], bit_len: usize) {
+        // the complete bytes are compressed like byte oriented input, while the partial byte is kept aside
+        let mut partial_byte = take(&mut hash.partial_byte);
+        let bytes = partial_byte.append(data, bit_len);
+        Self::update_hash(hash, &bytes);
+        hash.partial_byte = partial_byte;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(hash: &SHA1Hash) -> (u32, u32, u32, u32, u32) {
+        (hash.a, hash.b, hash.c, hash.d, hash.e)
+    }
+
+    #[test]
+    fn test_raw_byte_order() {
+        let hash = SHA1Hash { a: 0x01234567, b: 0x89ABCDEF, c: 0xFEDCBA98, d: 0x76543210, e: 0xF0E1D2C3 };
+
+        assert_eq!(hash.raw(), vec![
+            0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF, 0xFE, 0xDC, 0xBA, 0x98, 0x76, 0x54, 0x32, 0x10,
+            0xF0, 0xE1, 0xD2, 0xC3,
+        ]);
+        assert_eq!(hash.to_bytes().to_vec(), hash.raw());
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let hash = SHA1Hash { a: 0x01234567, b: 0x89ABCDEF, c: 0xFEDCBA98, d: 0x76543210, e: 0xF0E1D2C3 };
+        assert_eq!(hash.to_string(), "0123456789abcdeffedcba9876543210f0e1d2c3");
+        assert_eq!(format!("{:X}", hash), "0123456789ABCDEFFEDCBA9876543210F0E1D2C3");
+
+        let parsed: SHA1Hash = "0123456789ABCDEFfedcba9876543210F0e1D2c3".parse().unwrap();
+        assert_eq!(parsed, hash);
+        assert_eq!(parsed.to_string(), hash.to_string());
+
+        assert_eq!(
+            "0123456789abcdeffedcba9876543210f0e1d2".parse::<SHA1Hash>().unwrap_err(),
+            ParseHexError::InvalidLength { length: 19 }
+        );
+        assert_eq!("0".parse::<SHA1Hash>().unwrap_err(), ParseHexError::OddLength { length: 1 });
+    }
+
+    #[test]
+    fn test_compress_trace() {
+        let block: [u8; 64] = [0x5A; 64];
+        let mut state = SHA1Hash::init_hash(&());
+        round_function(&mut state, &block);
+
+        let (trace, chaining_value) = compress_trace(&INITIAL, &block);
+        assert_eq!(chaining_value, state.hash);
+
+        // the chaining value is the sum of the last round state and the input state
+        assert_eq!(chaining_value.e, INITIAL.e.wrapping_add(trace[79].e));
+    }
+
+    #[test]
+    fn test_block_function() {
+        let initial = [INITIAL.a, INITIAL.b, INITIAL.c, INITIAL.d, INITIAL.e];
+
+        // "abc" padded to a single block, with its length in bits in big endian byte order
+        let mut block = [0; 64];
+        block[..4].copy_from_slice(b"abc\x80");
+        block[63] = 24;
+
+        let SHA1Hash { a, b, c, d, e } = "a9993e364706816aba3e25717850c26c9cd0d89d".parse().unwrap();
+        assert_eq!(sha1_block(initial, &block), [a, b, c, d, e]);
+
+        // the first block of a longer message is compressed into the initial state alone
+        let (_, SHA1Hash { a, b, c, d, e }) = compress_trace(&INITIAL, &[0x5A; 64]);
+        assert_eq!(sha1_block(initial, &[0x5A; 64]), [a, b, c, d, e]);
+    }
+
+    fn digest_bits(data: &[u8], bit_len: usize) -> String {
+        let mut hash_state = SHA1Hash::init_hash(&());
+        SHA1Hash::update_hash_bits(&mut hash_state, &(), data, bit_len);
+        SHA1Hash::finish_hash_bits(&mut hash_state, &()).to_string()
+    }
+
+    #[test]
+    fn test_bit_vectors() {
+        // the vectors of one, two and five bits are the published bit oriented vectors of the SHAVS and of Brian
+        // Gladman, the others were computed with an independent bit oriented implementation
+        let vectors = [
+            (1, 0x00, "bb6b3e18f0115b57925241676f5b1ae88747b08a"),
+            (2, 0x40, "ec6b39952e1a3ec3ab3507185cf756181c84bbe2"),
+            (3, 0x80, "a37596ec13a0d2f9e6c0b8b96f9112823aa6d961"),
+            (4, 0x90, "df782d9df7adfe15b0c7b54cacf855dd6eae19c6"),
+            (5, 0x98, "29826b003b906e660eff4027ce98af3531ac75ba"),
+            (6, 0x24, "c541e33c9719e5d50775bbb11732081d3d18b9c2"),
+            (7, 0x5a, "375e57d0257e33e2236b661577525173023a13bc"),
+        ];
+
+        for &(bit_len, message, digest) in &vectors {
+            assert_eq!(digest_bits(&[message], bit_len), digest, "message of {} bits", bit_len);
+
+            // the bits after the message are ignored
+            assert_eq!(digest_bits(&[message | (0xFF >> bit_len)], bit_len), digest);
+        }
+
+        // the partial byte and the padding span two blocks
+        let message = (0..200).collect::<Vec<u8>>();
+        assert_eq!(digest_bits(&message, 1003), "a0631bcc33b4d7383d724ccb470bc0d20e93a4ed");
+    }
+
+    #[test]
+    fn test_zero_block_rounds() {
+        let (trace, _) = compress_trace(&INITIAL, &[0; 64]);
 
-    fn output_size(_ctx: &Self::Context) -> usize {
-        mem::size_of::<Self>()
+        assert_eq!(words(&trace[0]), (0x9fb498b3, 0x67452301, 0x7bf36ae2, 0x98badcfe, 0x10325476));
+        assert_eq!(words(&trace[1]), (0x5d43e380, 0x9fb498b3, 0x59d148c0, 0x7bf36ae2, 0x98badcfe));
     }
 }
@@ -0,0 +1,284 @@
+//! SipHash, a keyed pseudo random function by Jean-Philippe Aumasson and Daniel J. Bernstein. It is meant for
+//! hash-flooding resistant hash tables and short message authentication codes. The number of compression rounds `C`
+//! and finalization rounds `D` are type parameters, the common variants are `SipHash24` and `SipHash13`.
+
+use std::convert::TryInto;
+use std::str::FromStr;
+
+use crate::hex_format::decode_hex_array;
+use crate::{HashFunction, HashValue, ParseHexError};
+
+/// The SipHash function with `C` compression rounds per message word and `D` finalization rounds.
+pub struct SipHash<const C: usize, const D: usize>;
+
+/// SipHash-2-4 as recommended by the original paper.
+pub type SipHash24 = SipHash<2, 4>;
+
+/// SipHash-1-3, a faster variant with fewer rounds.
+pub type SipHash13 = SipHash<1, 3>;
+
+/// The key of a SipHash instance.
+pub struct SipHashContext {
+    pub key: [u8; 16],
+}
+
+/// The internal state of SipHash. The four state words `v` are public, so the state can be inspected and forged.
+#[derive(Debug, Clone)]
+pub struct SipHashState {
+    pub v: [u64; 4],
+    message_length: usize,
+    remaining_data_buffer: [u8; 8],
+    remaining_data_length: usize,
+}
+
+/// A SipHash output.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SipHashValue(pub u64);
+
+/// Initialize the state words from the key.
+fn initial_state(key: &[u8; 16]) -> [u64; 4] {
+    let k0 = u64::from_le_bytes(key[..8].try_into().unwrap());
+    let k1 = u64::from_le_bytes(key[8..].try_into().unwrap());
+
+    [
+        k0 ^ 0x736f_6d65_7073_6575,
+        k1 ^ 0x646f_7261_6e64_6f6d,
+        k0 ^ 0x6c79_6765_6e65_7261,
+        k1 ^ 0x7465_6462_7974_6573,
+    ]
+}
+
+/// The SipRound permutation.
+fn sip_round(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13);
+    v[1] ^= v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16);
+    v[3] ^= v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21);
+    v[3] ^= v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17);
+    v[1] ^= v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+/// Compress a single message word into the state using `C` rounds.
+fn compress<const C: usize>(v: &mut [u64; 4], word: u64) {
+    v[3] ^= word;
+    for _ in 0..C {
+        sip_round(v);
+    }
+    v[0] ^= word;
+}
+
+/// Compress the last, incomplete word (along with the message length) and finalize the state using `D` rounds.
+fn finalize<const C: usize, const D: usize>(v: &mut [u64; 4], remaining_data: &[u8], message_length: usize) -> u64 {
+    let mut last_word = [0_u8; 8];
+    last_word[..remaining_data.len()].copy_from_slice(remaining_data);
+    last_word[7] = message_length as u8;
+    compress::<C>(v, u64::from_le_bytes(last_word));
+
+    v[2] ^= 0xFF;
+    for _ in 0..D {
+        sip_round(v);
+    }
+
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+/// Compute a SipHash of `data` at once, without the buffering of the streaming API.
+fn digest<const C: usize, const D: usize>(key: &[u8; 16], data: &[u8]) -> u64 {
+    let mut v = initial_state(key);
+
+    let mut words = data.chunks_exact(8);
+    for word in &mut words {
+        compress::<C>(&mut v, u64::from_le_bytes(word.try_into().unwrap()));
+    }
+
+    finalize::<C, D>(&mut v, words.remainder(), data.len())
+}
+
+/// Compute the SipHash-2-4 of `data` under the given `key`.
+pub fn siphash24(key: &[u8; 16], data: &[u8]) -> u64 {
+    digest::<2, 4>(key, data)
+}
+
+/// Compute the SipHash-1-3 of `data` under the given `key`.
+pub fn siphash13(key: &[u8; 16], data: &[u8]) -> u64 {
+    digest::<1, 3>(key, data)
+}
+
+impl<const C: usize, const D: usize> HashFunction for SipHash<C, D> {
+    type Context = SipHashContext;
+    type HashState = SipHashState;
+    type HashData = SipHashValue;
+
+    const MAX_OUTPUT_SIZE: usize = 8;
+
+    fn init_hash(ctx: &Self::Context) -> Self::HashState {
+        SipHashState {
+            v: initial_state(&ctx.key),
+            message_length: 0,
+            remaining_data_buffer: [0_u8; 8],
+            remaining_data_length: 0,
+        }
+    }
+
+    fn update_hash(hash: &mut Self::HashState, input: &[u8]) {
+        hash.message_length = hash.message_length.wrapping_add(input.len());
+
+        // fill up the remaining data buffer first and compress it, if it is full
+        let buffered = input.len().min(8 - hash.remaining_data_length);
+        hash.remaining_data_buffer[hash.remaining_data_length..hash.remaining_data_length + buffered]
+            .copy_from_slice(&input[..buffered]);
+        hash.remaining_data_length += buffered;
+
+        if hash.remaining_data_length < 8 {
+            return;
+        }
+
+        compress::<C>(&mut hash.v, u64::from_le_bytes(hash.remaining_data_buffer));
+        hash.remaining_data_length = 0;
+
+        // compress all complete words of the input and store the remainder
+        let mut words = input[buffered..].chunks_exact(8);
+        for word in &mut words {
+            compress::<C>(&mut hash.v, u64::from_le_bytes(word.try_into().unwrap()));
+        }
+
+        let remainder = words.remainder();
+        hash.remaining_data_buffer[..remainder.len()].copy_from_slice(remainder);
+        hash.remaining_data_length = remainder.len();
+    }
+
+    fn finish_hash(hash: &mut Self::HashState, _ctx: &Self::Context) -> Self::HashData {
+        SipHashValue(finalize::<C, D>(
+            &mut hash.v,
+            &hash.remaining_data_buffer[..hash.remaining_data_length],
+            hash.message_length,
+        ))
+    }
+
+    fn digest_message(ctx: &Self::Context, input: &[u8]) -> Self::HashData {
+        SipHashValue(digest::<C, D>(&ctx.key, input))
+    }
+}
+
+impl HashValue for SipHashValue {
+    /// The output as little endian bytes, as in the reference implementation
+    fn raw(&self) -> Vec<u8> {
+        self.0.to_le_bytes().to_vec()
+    }
+}
+
+impl_hex_format!(SipHashValue);
+
+impl FromStr for SipHashValue {
+    type Err = ParseHexError;
+
+    /// Parse the 16 hex digits of the little endian output in either case.
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        Ok(SipHashValue(u64::from_le_bytes(decode_hex_array(hex)?)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The key `00 01 .. 0f` of the reference test vectors
+    const TEST_KEY: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
+
+    /// SipHash-2-4 reference vectors from the SipHash paper. Vector `i` is the hash of the message `00 01 .. (i-1)`
+    /// under `TEST_KEY`.
+    const VECTORS_SIP24: [u64; 64] = [
+        0x726fdb47dd0e0e31, 0x74f839c593dc67fd, 0x0d6c8009d9a94f5a, 0x85676696d7fb7e2d,
+        0xcf2794e0277187b7, 0x18765564cd99a68d, 0xcbc9466e58fee3ce, 0xab0200f58b01d137,
+        0x93f5f5799a932462, 0x9e0082df0ba9e4b0, 0x7a5dbbc594ddb9f3, 0xf4b32f46226bada7,
+        0x751e8fbc860ee5fb, 0x14ea5627c0843d90, 0xf723ca908e7af2ee, 0xa129ca6149be45e5,
+        0x3f2acc7f57c29bdb, 0x699ae9f52cbe4794, 0x4bc1b3f0968dd39c, 0xbb6dc91da77961bd,
+        0xbed65cf21aa2ee98, 0xd0f2cbb02e3b67c7, 0x93536795e3a33e88, 0xa80c038ccd5ccec8,
+        0xb8ad50c6f649af94, 0xbce192de8a85b8ea, 0x17d835b85bbb15f3, 0x2f2e6163076bcfad,
+        0xde4daaaca71dc9a5, 0xa6a2506687956571, 0xad87a3535c49ef28, 0x32d892fad841c342,
+        0x7127512f72f27cce, 0xa7f32346f95978e3, 0x12e0b01abb051238, 0x15e034d40fa197ae,
+        0x314dffbe0815a3b4, 0x027990f029623981, 0xcadcd4e59ef40c4d, 0x9abfd8766a33735c,
+        0x0e3ea96b5304a7d0, 0xad0c42d6fc585992, 0x187306c89bc215a9, 0xd4a60abcf3792b95,
+        0xf935451de4f21df2, 0xa9538f0419755787, 0xdb9acddff56ca510, 0xd06c98cd5c0975eb,
+        0xe612a3cb9ecba951, 0xc766e62cfcadaf96, 0xee64435a9752fe72, 0xa192d576b245165a,
+        0x0a8787bf8ecb74b2, 0x81b3e73d20b49b6f, 0x7fa8220ba3b2ecea, 0x245731c13ca42499,
+        0xb78dbfaf3a8d83bd, 0xea1ad565322a1a0b, 0x60e61c23a3795013, 0x6606d7e446282b93,
+        0x6ca4ecb15c5f91e1, 0x9f626da15c9625f3, 0xe51b38608ef25f57, 0x958a324ceb064572,
+    ];
+
+    fn test_message() -> Vec<u8> {
+        (0..64).collect()
+    }
+
+    #[test]
+    fn test_siphash24_vectors() {
+        let message = test_message();
+        let ctx = SipHashContext { key: TEST_KEY };
+
+        for (length, expected) in VECTORS_SIP24.iter().enumerate() {
+            assert_eq!(siphash24(&TEST_KEY, &message[..length]), *expected);
+
+            let mut hash_state = SipHash24::init_hash(&ctx);
+            SipHash24::update_hash(&mut hash_state, &message[..length]);
+            assert_eq!(SipHash24::finish_hash(&mut hash_state, &ctx), SipHashValue(*expected));
+        }
+
+        assert_eq!(
+            SipHash24::digest_message(&ctx, &[]).raw(),
+            vec![0x31, 0x0e, 0x0e, 0xdd, 0x47, 0xdb, 0x6f, 0x72]
+        );
+    }
+
+    #[test]
+    fn test_siphash13() {
+        let message = test_message();
+        let key = [0_u8; 16];
+
+        assert_eq!(siphash13(&key, &message[..0]), 0xd1fba762150c532c);
+        assert_eq!(siphash13(&key, &message[..1]), 0x68a914128e01e473);
+        assert_eq!(siphash13(&key, &message[..7]), 0x2f098ab0c751325a);
+        assert_eq!(siphash13(&key, &message[..8]), 0xead411e67ebe2eea);
+        assert_eq!(siphash13(&key, &message[..15]), 0xf30eb725bb91c9ea);
+        assert_eq!(siphash13(&key, &message[..63]), 0x385d3e39e5f37359);
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let hash = SipHashValue(0x0123_4567_89AB_CDEF);
+        assert_eq!(hash.to_string(), "efcdab8967452301");
+        assert_eq!(format!("{:X}", hash), "EFCDAB8967452301");
+        assert_eq!("EFcdAB8967452301".parse::<SipHashValue>().unwrap(), hash);
+        assert_eq!(
+            "efcdab896745230100".parse::<SipHashValue>().unwrap_err(),
+            ParseHexError::InvalidLength { length: 9 }
+        );
+    }
+
+    #[test]
+    fn test_stream() {
+        let message = test_message();
+        let ctx = SipHashContext { key: TEST_KEY };
+
+        // split every test vector message at all pairs of split points
+        for (length, expected) in VECTORS_SIP24.iter().enumerate() {
+            for first_split in 0..=length {
+                for second_split in first_split..=length {
+                    let mut hash_state = SipHash24::init_hash(&ctx);
+                    SipHash24::update_hash(&mut hash_state, &message[..first_split]);
+                    SipHash24::update_hash(&mut hash_state, &message[first_split..second_split]);
+                    SipHash24::update_hash(&mut hash_state, &message[second_split..length]);
+
+                    assert_eq!(SipHash24::finish_hash(&mut hash_state, &ctx), SipHashValue(*expected));
+                }
+            }
+        }
+    }
+}
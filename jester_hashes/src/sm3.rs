@@ -0,0 +1,370 @@
+//! The SM3 hash function as standardized in GB/T 32905-2016. It is a Merkle-Damgård construction similar to SHA-256
+//! with 64-byte blocks and 256-bit hashes.
+#![allow(clippy::unreadable_literal)]
+
+use crate::bytes::{read_u32_be, write_u64_be};
+use std::convert::TryInto;
+use std::mem::size_of;
+use std::mem::take;
+use std::str::FromStr;
+
+use crate::{HashFunction, HashValue, BlockHashFunction, NoObserver, RoundObserver};
+
+use crate::ParseHexError;
+use crate::hex_format::decode_hex_array;
+
+const BLOCK_LENGTH_BYTES: usize = 64;
+
+/// The number of rounds of the compression function
+const ROUNDS: usize = 64;
+
+/// The initial state for any SM3 hash. From here, all blocks are applied.
+pub const INITIAL: SM3Hash = SM3Hash {
+    a: 0x7380166F,
+    b: 0x4914B2B9,
+    c: 0x172442D7,
+    d: 0xDA8A0600,
+    e: 0xA96F30BC,
+    f: 0x163138AA,
+    g: 0xE38DEE4D,
+    h: 0xB0FB0E4E,
+};
+
+/// A SM3 hash state. It consists of 8 double-words named `a` to `h`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SM3Hash {
+    pub a: u32,
+    pub b: u32,
+    pub c: u32,
+    pub d: u32,
+    pub e: u32,
+    pub f: u32,
+    pub g: u32,
+    pub h: u32,
+}
+
+#[derive(Clone)]
+pub struct SM3HashState {
+    hash: SM3Hash,
+    message_length: u64,
+    remaining_data: Vec<u8>,
+}
+
+fn round_function(hash: &mut SM3HashState, block: &[u8; 64]) {
+    hash.hash = compress(&hash.hash, block, &mut NoObserver);
+
+    if hash.message_length as u128 + 64_u128 * 8 > u64::MAX as u128 {
+        panic!("cannot hash more than 2**64 - 1 bits.")
+    } else {
+        hash.message_length += 64 * 8
+    }
+}
+
+/// The permutation P0 of the compression function
+fn p0(x: u32) -> u32 {
+    x ^ x.rotate_left(9) ^ x.rotate_left(17)
+}
+
+/// The permutation P1 of the message expansion
+fn p1(x: u32) -> u32 {
+    x ^ x.rotate_left(15) ^ x.rotate_left(23)
+}
+
+/// Expand a block into the 68 words `W` and the 64 words `W′`, where `W′[j] = W[j] ^ W[j + 4]`.
+///
+/// # Returns
+/// A tuple of `W` and `W′`
+pub fn expand_message(block: &[u8; 64]) -> ([u32; 68], [u32; 64]) {
+    let mut words = [0_u32; 68];
+    for (i, word) in words[0..16].iter_mut().enumerate() {
+        *word = read_u32_be(block, i * 4);
+    }
+
+    for j in 16..68 {
+        words[j] = p1(words[j - 16] ^ words[j - 9] ^ words[j - 3].rotate_left(15))
+            ^ words[j - 13].rotate_left(7)
+            ^ words[j - 6];
+    }
+
+    let mut mixed_words = [0_u32; 64];
+    for j in 0..64 {
+        mixed_words[j] = words[j] ^ words[j + 4];
+    }
+
+    (words, mixed_words)
+}
+
+/// Compute the trace of a single compression of `block` into the chaining value `state`. The trace contains the
+/// working state `(a, ..., h)` after each of the 64 rounds, before it is added to the chaining value.
+///
+/// # Returns
+/// A tuple of the trace and the resulting chaining value
+pub fn compress_trace(state: &SM3Hash, block: &[u8; 64]) -> ([SM3Hash; ROUNDS], SM3Hash) {
+    let mut trace = [*state; ROUNDS];
+    let chaining_value = compress(state, block, &mut trace);
+    (trace, chaining_value)
+}
+
+/// Compress one block into the chaining value `state` and report the working state after each round to `observer`.
+/// Use `NoObserver` to only compute the new chaining value.
+pub fn compress<O>(state: &SM3Hash, block: &[u8; 64], observer: &mut O) -> SM3Hash
+    where O: RoundObserver<SM3Hash>
+{
+    let (words, mixed_words) = expand_message(block);
+    let mut round_state = *state;
+
+    for j in 0..ROUNDS {
+        let (round_constant, ff, gg) = if j < 16 {
+            (
+                0x79CC4519_u32,
+                round_state.a ^ round_state.b ^ round_state.c,
+                round_state.e ^ round_state.f ^ round_state.g,
+            )
+        } else {
+            (
+                0x7A879D8A_u32,
+                (round_state.a & round_state.b) | (round_state.a & round_state.c) | (round_state.b & round_state.c),
+                (round_state.e & round_state.f) | (!round_state.e & round_state.g),
+            )
+        };
+
+        let ss1 = round_state.a.rotate_left(12)
+            .wrapping_add(round_state.e)
+            .wrapping_add(round_constant.rotate_left(j as u32 % 32))
+            .rotate_left(7);
+        let ss2 = ss1 ^ round_state.a.rotate_left(12);
+        let tt1 = ff.wrapping_add(round_state.d).wrapping_add(ss2).wrapping_add(mixed_words[j]);
+        let tt2 = gg.wrapping_add(round_state.h).wrapping_add(ss1).wrapping_add(words[j]);
+
+        round_state.d = round_state.c;
+        round_state.c = round_state.b.rotate_left(9);
+        round_state.b = round_state.a;
+        round_state.a = tt1;
+        round_state.h = round_state.g;
+        round_state.g = round_state.f.rotate_left(19);
+        round_state.f = round_state.e;
+        round_state.e = p0(tt2);
+
+        observer.observe_round(j, &round_state);
+    }
+
+    SM3Hash {
+        a: state.a ^ round_state.a,
+        b: state.b ^ round_state.b,
+        c: state.c ^ round_state.c,
+        d: state.d ^ round_state.d,
+        e: state.e ^ round_state.e,
+        f: state.f ^ round_state.f,
+        g: state.g ^ round_state.g,
+        h: state.h ^ round_state.h,
+    }
+}
+
+impl HashFunction for SM3Hash {
+    type Context = ();
+    type HashState = SM3HashState;
+    type HashData = SM3Hash;
+
+    const MAX_OUTPUT_SIZE: usize = size_of::<Self>();
+
+    fn init_hash(_ctx: &Self::Context) -> Self::HashState {
+        SM3HashState { hash: INITIAL, message_length: 0, remaining_data: vec![] }
+    }
+
+    /// Reset the state in place, keeping the allocation of the remaining data buffer.
+    fn reset_hash(hash: &mut Self::HashState, _ctx: &Self::Context) {
+        hash.hash = INITIAL;
+        hash.message_length = 0;
+        hash.remaining_data.clear();
+    }
+
+    fn update_hash(hash: &mut Self::HashState, input: &[u8]) {
+        // offset of input data that is already processed during the use of the remaining data
+        // stored in the state
+        let mut input_data_offset = 0;
+
+        // digest remaining data from the state, if any and copy a prefix from input data that
+        if !hash.remaining_data.is_empty() {
+            // fills one block of data
+            if hash.remaining_data.len() + input.len() >= BLOCK_LENGTH_BYTES {
+                // move the remaining data outside the buffer and append new input data to fill
+                // first block
+                input_data_offset = BLOCK_LENGTH_BYTES - hash.remaining_data.len();
+
+                let mut first_block = [0u8; BLOCK_LENGTH_BYTES];
+                first_block[..hash.remaining_data.len()].copy_from_slice(&hash.remaining_data);
+                first_block[hash.remaining_data.len()..]
+                    .copy_from_slice(&input[..input_data_offset]);
+
+                // hash first block
+                round_function(hash, &first_block);
+            } else { // else copy the input data into the vec and wait for more data
+                hash.remaining_data.extend_from_slice(input);
+                return;
+            }
+        }
+
+        // calculate how many full blocks remain in the input buffer
+        let message_blocks_count = (input.len() - input_data_offset) / BLOCK_LENGTH_BYTES;
+
+        // digest full blocks
+        for i in 0..message_blocks_count {
+            round_function(hash, &input[input_data_offset + i * BLOCK_LENGTH_BYTES..
+                input_data_offset + (i + 1) * BLOCK_LENGTH_BYTES].try_into().unwrap())
+        }
+
+        // copy remaining data into hash state
+        let remaining_data = &input[input_data_offset + message_blocks_count * BLOCK_LENGTH_BYTES..];
+        hash.remaining_data = remaining_data.to_vec();
+    }
+
+    fn finish_hash(hash: &mut Self::HashState, _ctx: &Self::Context) -> Self::HashData {
+        let remaining_data = take(&mut hash.remaining_data);
+
+        // prepare a zero-padded full-length block
+        let mut last_block = [0_u8; BLOCK_LENGTH_BYTES];
+
+        // append the last part of message to the block
+        last_block[..remaining_data.len()].copy_from_slice(&remaining_data);
+
+        // append a single 1-bit to the end of the message
+        last_block[remaining_data.len()] = 0x80_u8;
+
+        let message_length_bits =
+            if hash.message_length as u128 + remaining_data.len() as u128 * 8_u128 > u64::MAX as u128 {
+                panic!("cannot hash more than 2**64 - 1 bits.")
+            } else {
+                hash.message_length + (remaining_data.len() * 8) as u64
+            };
+
+        // if there is not enough space for the message length to be appended, a new block must be
+        // created
+        if remaining_data.len() + 1 + size_of::<u64>() > BLOCK_LENGTH_BYTES {
+            let mut overflow_block = [0_u8; BLOCK_LENGTH_BYTES];
+            write_u64_be(&mut overflow_block, BLOCK_LENGTH_BYTES - size_of::<u64>(), message_length_bits);
+
+            round_function(hash, &last_block);
+            round_function(hash, &overflow_block);
+        } else {
+            write_u64_be(&mut last_block, BLOCK_LENGTH_BYTES - size_of::<u64>(), message_length_bits);
+
+            round_function(hash, &last_block);
+        }
+
+        hash.hash
+    }
+
+    fn digest_message(ctx: &Self::Context, input: &[u8]) -> Self::HashData {
+        let mut hash_state = Self::init_hash(ctx);
+
+        // digest all data
+        Self::update_hash(&mut hash_state, input);
+
+        // finish hashing by padding the remaining data within the hash state and digesting it
+        Self::finish_hash(&mut hash_state, ctx)
+    }
+}
+
+impl HashValue for SM3Hash {
+    /// Generates the 32 bytes of the hash from the current hash state. The words are serialized in big endian byte
+    /// order, independent of the byte order of the host.
+    fn raw(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(size_of::<SM3Hash>());
+        for word in [self.a, self.b, self.c, self.d, self.e, self.f, self.g, self.h].iter() {
+            bytes.extend_from_slice(&word.to_be_bytes());
+        }
+        bytes
+    }
+}
+
+impl_hex_format!(SM3Hash);
+
+impl FromStr for SM3Hash {
+    type Err = ParseHexError;
+
+    /// Parse the 64 hex digits of `raw()` in either case. The words are read in big endian byte order.
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        let bytes = decode_hex_array::<32>(hex)?;
+        let word = |i: usize| read_u32_be(&bytes, 4 * i);
+        Ok(SM3Hash { a: word(0), b: word(1), c: word(2), d: word(3), e: word(4), f: word(5), g: word(6), h: word(7) })
+    }
+}
+
+impl BlockHashFunction for SM3Hash {
+    fn block_size(_ctx: &Self::Context) -> usize {
+        BLOCK_LENGTH_BYTES
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DefaultContextHash;
+    use crate::tests::{assert_peek_consistent, assert_reset_consistent, STREAM_TEXT};
+
+    #[test]
+    fn test_standard_vectors() {
+        assert_eq!(
+            SM3Hash::digest(b"abc").to_string(),
+            "66c7f0f462eeedd9d1f2d46bdc10e4e24167c4875cf2f7a2297da02b8f4ba8e0"
+        );
+        assert_eq!(
+            SM3Hash::digest(b"abcd".repeat(16).as_slice()).to_string(),
+            "debe9ff92275b8a138604889c18e5a4d6fdb70e5387e5765293dcba39c0c5732"
+        );
+    }
+
+    #[test]
+    fn test_message_expansion() {
+        // the example of the standard: the padded message "abc"
+        let mut block = [0_u8; 64];
+        block[..4].copy_from_slice(&[0x61, 0x62, 0x63, 0x80]);
+        block[63] = 0x18;
+
+        let (words, mixed_words) = expand_message(&block);
+        assert_eq!(words[16], 0x9092E200);
+        assert_eq!(words[67], 0xB99C0545);
+        assert_eq!(mixed_words[0], 0x61626380 ^ words[4]);
+
+        let (trace, chaining_value) = compress_trace(&INITIAL, &block);
+        assert_eq!(trace[0].a, 0xB9EDC12B);
+        assert_eq!(chaining_value, SM3Hash::digest(b"abc"));
+    }
+
+    #[test]
+    fn test_stream() {
+        let ctx = ();
+        let mut hash_state = SM3Hash::init_hash(&ctx);
+        let mut message = Vec::new();
+        for chunk in STREAM_TEXT.iter() {
+            SM3Hash::update_hash(&mut hash_state, chunk.as_bytes());
+            message.extend_from_slice(chunk.as_bytes());
+        }
+        assert_eq!(SM3Hash::finish_hash(&mut hash_state, &ctx), SM3Hash::digest(&message));
+
+        // the 64-byte vector streamed in uneven chunks
+        let mut hash_state = SM3Hash::init_hash(&ctx);
+        for chunk in b"abcd".repeat(16).chunks(7) {
+            SM3Hash::update_hash(&mut hash_state, chunk);
+        }
+        assert_eq!(
+            SM3Hash::finish_hash(&mut hash_state, &ctx).to_string(),
+            "debe9ff92275b8a138604889c18e5a4d6fdb70e5387e5765293dcba39c0c5732"
+        );
+    }
+
+    #[test]
+    fn test_peek_and_reset() {
+        assert_peek_consistent::<SM3Hash>(&());
+        assert_reset_consistent::<SM3Hash>(&());
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let hash = SM3Hash::digest(b"abc");
+        let parsed: SM3Hash = hash.to_string().to_uppercase().parse().unwrap();
+        assert_eq!(parsed, hash);
+
+        assert_eq!("0".parse::<SM3Hash>().unwrap_err(), ParseHexError::OddLength { length: 1 });
+    }
+}
@@ -0,0 +1,134 @@
+//! Unambiguous hashing of a sequence of byte strings. Hashing the plain concatenation of several fields lets
+//! different sequences collide, since `"ab" || "c"` equals `"a" || "bc"`. The `TupleHasher` frames the fields instead,
+//! so that the hashed encoding is prefix-free: it starts with an encoding version and a caller-supplied
+//! domain-separation tag, followed by the number of fields and each field prefixed with its length. All lengths are
+//! encoded as 64 bit little endian integers.
+
+use crate::BlockHashFunction;
+
+/// Version of the encoding hashed by the `TupleHasher`. It is the first byte of every encoding.
+const TUPLE_ENCODING_VERSION: u8 = 1;
+
+/// Streaming hasher of a sequence of byte strings with a fixed number of fields, which is committed to upfront.
+pub struct TupleHasher<'a, H>
+    where H: BlockHashFunction
+{
+    ctx: &'a H::Context,
+    state: H::HashState,
+    remaining_fields: usize,
+}
+
+impl<'a, H> TupleHasher<'a, H>
+    where H: BlockHashFunction
+{
+    /// Start hashing a tuple of exactly `field_count` fields.
+    /// #Parameters
+    /// - `ctx` the context of the hash function
+    /// - `domain` a tag separating the tuples of different protocols or purposes
+    /// - `field_count` the number of fields that will be passed to `field`
+    pub fn new(ctx: &'a H::Context, domain: &[u8], field_count: usize) -> Self {
+        let mut state = H::init_hash(ctx);
+        H::update_hash(&mut state, &[TUPLE_ENCODING_VERSION]);
+        H::update_hash(&mut state, &(domain.len() as u64).to_le_bytes());
+        H::update_hash(&mut state, domain);
+        H::update_hash(&mut state, &(field_count as u64).to_le_bytes());
+
+        TupleHasher {
+            ctx,
+            state,
+            remaining_fields: field_count,
+        }
+    }
+
+    /// Append the next field of the tuple.
+    ///
+    /// # Panics
+    /// If all fields announced in `new` were already appended.
+    pub fn field(&mut self, field: &[u8]) {
+        assert!(self.remaining_fields > 0, "more fields than announced were appended to the tuple");
+        self.remaining_fields -= 1;
+
+        H::update_hash(&mut self.state, &(field.len() as u64).to_le_bytes());
+        H::update_hash(&mut self.state, field);
+    }
+
+    /// Finish the hash of the tuple.
+    ///
+    /// # Panics
+    /// If fewer fields than announced in `new` were appended.
+    pub fn finalize(mut self) -> H::HashData {
+        assert_eq!(self.remaining_fields, 0, "fewer fields than announced were appended to the tuple");
+        H::finish_hash(&mut self.state, self.ctx)
+    }
+}
+
+/// Hash all `fields` as a tuple with the domain-separation tag `domain`. This is the one-shot equivalent of appending
+/// each field to a `TupleHasher`.
+pub fn hash_tuple<H>(ctx: &H::Context, domain: &[u8], fields: &[&[u8]]) -> H::HashData
+    where H: BlockHashFunction
+{
+    let mut hasher = TupleHasher::<H>::new(ctx, domain, fields.len());
+    for field in fields {
+        hasher.field(field);
+    }
+    hasher.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sha1::SHA1Hash;
+    use crate::HashValue;
+    use super::*;
+
+    const DOMAIN: &[u8] = b"jester test tuple";
+
+    fn digest(fields: &[&[u8]]) -> Vec<u8> {
+        hash_tuple::<SHA1Hash>(&(), DOMAIN, fields).raw()
+    }
+
+    #[test]
+    fn test_framing() {
+        let reference = digest(&[b"ab", b"c"]);
+
+        // reordering, splitting and merging fields
+        assert_ne!(reference, digest(&[b"c", b"ab"]));
+        assert_ne!(reference, digest(&[b"a", b"bc"]));
+        assert_ne!(reference, digest(&[b"a", b"b", b"c"]));
+        assert_ne!(reference, digest(&[b"abc"]));
+
+        // empty fields are fields, too
+        assert_ne!(reference, digest(&[b"ab", b"c", b""]));
+        assert_ne!(digest(&[]), digest(&[b""]));
+
+        assert_ne!(reference, hash_tuple::<SHA1Hash>(&(), b"other domain", &[b"ab", b"c"]).raw());
+        assert_eq!(reference, digest(&[b"ab", b"c"]));
+    }
+
+    #[test]
+    fn test_streaming() {
+        let fields: [&[u8]; 4] = [b"", &[0x42; 200], b"jester", &[0; 64]];
+
+        let mut hasher = TupleHasher::<SHA1Hash>::new(&(), DOMAIN, fields.len());
+        for field in fields.iter() {
+            hasher.field(field);
+        }
+
+        assert_eq!(hasher.finalize().raw(), digest(&fields));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_missing_field() {
+        let mut hasher = TupleHasher::<SHA1Hash>::new(&(), DOMAIN, 2);
+        hasher.field(b"a");
+        hasher.finalize();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_excess_field() {
+        let mut hasher = TupleHasher::<SHA1Hash>::new(&(), DOMAIN, 1);
+        hasher.field(b"a");
+        hasher.field(b"b");
+    }
+}
@@ -0,0 +1,414 @@
+//! The Whirlpool hash function as standardized in ISO/IEC 10118-3. It is a Miyaguchi-Preneel construction over the
+//! dedicated 512-bit block cipher W and produces 512-bit hashes. It is provided for interoperability with legacy
+//! systems and should not be chosen for new designs.
+#![allow(clippy::unreadable_literal)]
+
+use std::convert::TryInto;
+use std::mem::size_of;
+use std::mem::take;
+use std::str::FromStr;
+
+use crate::{BlockHashFunction, HashFunction, HashValue};
+
+use crate::ParseHexError;
+use crate::hex_format::decode_hex_array;
+use crate::bytes::{read_u64_be, write_u128_be};
+
+const BLOCK_LENGTH_BYTES: usize = 64;
+
+/// The length of the bit length field appended during padding
+const LENGTH_FIELD_BYTES: usize = 32;
+
+/// The number of rounds of the W block cipher
+const ROUNDS: usize = 10;
+
+/// The reduction polynomial `x^8 + x^4 + x^3 + x^2 + 1` of the field GF(2^8) used by W
+const REDUCTION_POLYNOMIAL: u16 = 0x11D;
+
+/// The first row of the circulant diffusion matrix of W
+const DIFFUSION_ROW: [u8; 8] = [0x01, 0x01, 0x04, 0x01, 0x08, 0x05, 0x02, 0x09];
+
+/// The mini-box E of the S-box construction
+const MINI_BOX_E: [u8; 16] = [0x1, 0xB, 0x9, 0xC, 0xD, 0x6, 0xF, 0x3, 0xE, 0x8, 0x7, 0x4, 0xA, 0x2, 0x5, 0x0];
+
+/// The mini-box R of the S-box construction
+const MINI_BOX_R: [u8; 16] = [0x7, 0xC, 0xB, 0xD, 0xE, 0x4, 0x9, 0xF, 0x6, 0x3, 0x8, 0xA, 0x2, 0x5, 0x1, 0x0];
+
+/// The S-box of W, built from its mini-boxes at compile time
+static SBOX: [u8; 256] = generate_sbox();
+
+/// The combined substitution and diffusion tables of W. Entry `k` maps a byte in column `k` of a row to its
+/// contribution to the output row.
+static TABLES: [[u64; 256]; 8] = generate_tables(&SBOX);
+
+/// The round constants of the key schedule. They only populate the first row of the key, so only that row is stored.
+static ROUND_CONSTANTS: [u64; ROUNDS] = generate_round_constants(&SBOX);
+
+/// Multiply two elements of GF(2^8) modulo `REDUCTION_POLYNOMIAL`.
+const fn gf_multiply(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0_u8;
+    while b != 0 {
+        if b & 1 == 1 {
+            product ^= a;
+        }
+
+        let carry = a & 0x80 != 0;
+        a <<= 1;
+        if carry {
+            a ^= (REDUCTION_POLYNOMIAL & 0xFF) as u8;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+/// Generate the S-box of W from the mini-boxes E, its inverse and R.
+const fn generate_sbox() -> [u8; 256] {
+    let mut inverse_e = [0_u8; 16];
+    let mut i = 0;
+    while i < 16 {
+        inverse_e[MINI_BOX_E[i] as usize] = i as u8;
+        i += 1;
+    }
+
+    let mut sbox = [0_u8; 256];
+    let mut input = 0;
+    while input < 256 {
+        let high = MINI_BOX_E[input >> 4];
+        let low = inverse_e[input & 0xF];
+        let mixed = MINI_BOX_R[(high ^ low) as usize];
+        sbox[input] = MINI_BOX_E[(high ^ mixed) as usize] << 4 | inverse_e[(low ^ mixed) as usize];
+        input += 1;
+    }
+    sbox
+}
+
+/// Generate the lookup tables combining the S-box and the diffusion layer. Table `0` holds the products of each
+/// substituted byte with `DIFFUSION_ROW` as a big endian word; the other tables are its byte rotations, since the
+/// diffusion matrix is circulant.
+const fn generate_tables(sbox: &[u8; 256]) -> [[u64; 256]; 8] {
+    let mut tables = [[0_u64; 256]; 8];
+    let mut input = 0;
+    while input < 256 {
+        let mut word = 0_u64;
+        let mut column = 0;
+        while column < 8 {
+            word = word << 8 | gf_multiply(sbox[input], DIFFUSION_ROW[column]) as u64;
+            column += 1;
+        }
+
+        let mut table = 0;
+        while table < 8 {
+            tables[table][input] = word.rotate_right(8 * table as u32);
+            table += 1;
+        }
+        input += 1;
+    }
+    tables
+}
+
+/// Generate the first rows of the round constants, which are consecutive entries of the S-box.
+const fn generate_round_constants(sbox: &[u8; 256]) -> [u64; ROUNDS] {
+    let mut constants = [0_u64; ROUNDS];
+    let mut round = 0;
+    while round < ROUNDS {
+        let mut column = 0;
+        while column < 8 {
+            constants[round] = constants[round] << 8 | sbox[8 * round + column] as u64;
+            column += 1;
+        }
+        round += 1;
+    }
+    constants
+}
+
+/// The initial state for any Whirlpool hash, which is all zeroes.
+pub const INITIAL: WhirlpoolHash = WhirlpoolHash { rows: [0; 8] };
+
+/// A Whirlpool hash state. It consists of the 8 rows of the 8x8 byte matrix, each stored as a big endian word.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WhirlpoolHash {
+    pub rows: [u64; 8],
+}
+
+#[derive(Clone)]
+pub struct WhirlpoolHashState {
+    hash: WhirlpoolHash,
+    message_length: u128,
+    remaining_data: Vec<u8>,
+}
+
+fn round_function(hash: &mut WhirlpoolHashState, block: &[u8; 64]) {
+    hash.hash = compress(&hash.hash, block);
+    hash.message_length = hash.message_length.checked_add(64 * 8).expect("cannot hash more than 2**128 - 1 bits.");
+}
+
+/// Apply one round of W to `state`: the S-box, the cyclical column shift and the diffusion are looked up in the
+/// combined tables, followed by adding `key`.
+fn round(state: &[u64; 8], key: &[u64; 8]) -> [u64; 8] {
+    let mut output = *key;
+    for (row, output_row) in output.iter_mut().enumerate() {
+        for (column, table) in TABLES.iter().enumerate() {
+            // column k of the output row is taken from the row k positions above
+            let byte = state[(row + 8 - column) % 8] >> (56 - 8 * column) & 0xFF;
+            *output_row ^= table[byte as usize];
+        }
+    }
+    output
+}
+
+/// Compress one block into the chaining value `state` in Miyaguchi-Preneel mode: the block is encrypted with W under
+/// the chaining value as key, and both the block and the chaining value are added to the cipher text.
+fn compress(state: &WhirlpoolHash, block: &[u8; 64]) -> WhirlpoolHash {
+    let mut message = [0_u64; 8];
+    for (i, row) in message.iter_mut().enumerate() {
+        *row = read_u64_be(block, i * 8);
+    }
+
+    let mut key = state.rows;
+    let mut cipher_state = [0_u64; 8];
+    for row in 0..8 {
+        cipher_state[row] = message[row] ^ key[row];
+    }
+
+    for round_constant in ROUND_CONSTANTS.iter() {
+        let mut constant = [0_u64; 8];
+        constant[0] = *round_constant;
+
+        key = round(&key, &constant);
+        cipher_state = round(&cipher_state, &key);
+    }
+
+    let mut rows = [0_u64; 8];
+    for row in 0..8 {
+        rows[row] = cipher_state[row] ^ state.rows[row] ^ message[row];
+    }
+    WhirlpoolHash { rows }
+}
+
+impl HashFunction for WhirlpoolHash {
+    type Context = ();
+    type HashState = WhirlpoolHashState;
+    type HashData = WhirlpoolHash;
+
+    const MAX_OUTPUT_SIZE: usize = BLOCK_LENGTH_BYTES;
+
+    fn init_hash(_ctx: &Self::Context) -> Self::HashState {
+        WhirlpoolHashState { hash: INITIAL, message_length: 0, remaining_data: vec![] }
+    }
+
+    /// Reset the state in place, keeping the allocation of the remaining data buffer.
+    fn reset_hash(hash: &mut Self::HashState, _ctx: &Self::Context) {
+        hash.hash = INITIAL;
+        hash.message_length = 0;
+        hash.remaining_data.clear();
+    }
+
+    fn update_hash(hash: &mut Self::HashState, input: &[u8]) {
+        // offset of input data that is already processed during the use of the remaining data
+        // stored in the state
+        let mut input_data_offset = 0;
+
+        // digest remaining data from the state, if any and copy a prefix from input data that
+        if !hash.remaining_data.is_empty() {
+            // fills one block of data
+            if hash.remaining_data.len() + input.len() >= BLOCK_LENGTH_BYTES {
+                // move the remaining data outside the buffer and append new input data to fill
+                // first block
+                input_data_offset = BLOCK_LENGTH_BYTES - hash.remaining_data.len();
+
+                let mut first_block = [0u8; BLOCK_LENGTH_BYTES];
+                first_block[..hash.remaining_data.len()].copy_from_slice(&hash.remaining_data);
+                first_block[hash.remaining_data.len()..]
+                    .copy_from_slice(&input[..input_data_offset]);
+
+                // hash first block
+                round_function(hash, &first_block);
+            } else { // else copy the input data into the vec and wait for more data
+                hash.remaining_data.extend_from_slice(input);
+                return;
+            }
+        }
+
+        // calculate how many full blocks remain in the input buffer
+        let message_blocks_count = (input.len() - input_data_offset) / BLOCK_LENGTH_BYTES;
+
+        // digest full blocks
+        for i in 0..message_blocks_count {
+            round_function(hash, &input[input_data_offset + i * BLOCK_LENGTH_BYTES..
+                input_data_offset + (i + 1) * BLOCK_LENGTH_BYTES].try_into().unwrap())
+        }
+
+        // copy remaining data into hash state
+        let remaining_data = &input[input_data_offset + message_blocks_count * BLOCK_LENGTH_BYTES..];
+        hash.remaining_data = remaining_data.to_vec();
+    }
+
+    fn finish_hash(hash: &mut Self::HashState, _ctx: &Self::Context) -> Self::HashData {
+        let remaining_data = take(&mut hash.remaining_data);
+
+        // prepare a zero-padded full-length block
+        let mut last_block = [0_u8; BLOCK_LENGTH_BYTES];
+
+        // append the last part of message to the block
+        last_block[..remaining_data.len()].copy_from_slice(&remaining_data);
+
+        // append a single 1-bit to the end of the message
+        last_block[remaining_data.len()] = 0x80_u8;
+
+        let message_length_bits = hash.message_length.checked_add(remaining_data.len() as u128 * 8)
+            .expect("cannot hash more than 2**128 - 1 bits.");
+
+        // the length is a 256 bit big endian number, of which only the lower 128 bits can be set
+        let length_offset = BLOCK_LENGTH_BYTES - size_of::<u128>();
+
+        // if there is not enough space for the message length to be appended, a new block must be
+        // created
+        if remaining_data.len() + 1 + LENGTH_FIELD_BYTES > BLOCK_LENGTH_BYTES {
+            let mut overflow_block = [0_u8; BLOCK_LENGTH_BYTES];
+            write_u128_be(&mut overflow_block, length_offset, message_length_bits);
+
+            round_function(hash, &last_block);
+            round_function(hash, &overflow_block);
+        } else {
+            write_u128_be(&mut last_block, length_offset, message_length_bits);
+
+            round_function(hash, &last_block);
+        }
+
+        hash.hash
+    }
+
+    fn digest_message(ctx: &Self::Context, input: &[u8]) -> Self::HashData {
+        let mut hash_state = Self::init_hash(ctx);
+
+        // digest all data
+        Self::update_hash(&mut hash_state, input);
+
+        // finish hashing by padding the remaining data within the hash state and digesting it
+        Self::finish_hash(&mut hash_state, ctx)
+    }
+}
+
+impl HashValue for WhirlpoolHash {
+    /// Generates the 64 bytes of the hash from the current hash state. The rows are serialized in big endian byte
+    /// order, independent of the byte order of the host.
+    fn raw(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(BLOCK_LENGTH_BYTES);
+        for row in self.rows.iter() {
+            bytes.extend_from_slice(&row.to_be_bytes());
+        }
+        bytes
+    }
+}
+
+impl_hex_format!(WhirlpoolHash);
+
+impl FromStr for WhirlpoolHash {
+    type Err = ParseHexError;
+
+    /// Parse the 128 hex digits of `raw()` in either case. The rows are read in big endian byte order.
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        let bytes = decode_hex_array::<64>(hex)?;
+        let mut rows = [0_u64; 8];
+        for (i, row) in rows.iter_mut().enumerate() {
+            *row = read_u64_be(&bytes, i * 8);
+        }
+        Ok(WhirlpoolHash { rows })
+    }
+}
+
+impl BlockHashFunction for WhirlpoolHash {
+    fn block_size(_ctx: &Self::Context) -> usize {
+        BLOCK_LENGTH_BYTES
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DefaultContextHash;
+    use crate::tests::{assert_peek_consistent, assert_reset_consistent, STREAM_TEXT};
+
+    #[test]
+    fn test_tables() {
+        assert_eq!(&SBOX[..8], &[0x18, 0x23, 0xC6, 0xE8, 0x87, 0xB8, 0x01, 0x4F]);
+        assert_eq!(SBOX[255], 0x86);
+        assert_eq!(TABLES[0][0], 0x18186018C07830D8);
+        assert_eq!(ROUND_CONSTANTS[0], 0x1823C6E887B8014F);
+    }
+
+    #[test]
+    fn test_iso_vectors() {
+        assert_eq!(
+            WhirlpoolHash::digest(b"").to_string(),
+            "19fa61d75522a4669b44e39c1d2e1726c530232130d407f89afee0964997f7a7\
+             3e83be698b288febcf88e3e03c4f0757ea8964e59b63d93708b138cc42a66eb3"
+        );
+        assert_eq!(
+            WhirlpoolHash::digest(b"a").to_string(),
+            "8aca2602792aec6f11a67206531fb7d7f0dff59413145e6973c45001d0087b42\
+             d11bc645413aeff63a42391a39145a591a92200d560195e53b478584fdae231a"
+        );
+        assert_eq!(
+            WhirlpoolHash::digest(b"abc").to_string(),
+            "4e2448a4c6f486bb16b6562c73b4020bf3043e3a731bce721ae1b303d97e6d4c\
+             7181eebdb6c57e277d0e34957114cbd6c797fc9d95d8b582d225292076d4eef5"
+        );
+        assert_eq!(
+            WhirlpoolHash::digest(b"The quick brown fox jumps over the lazy dog").to_string(),
+            "b97de512e91e3828b40d2b0fdce9ceb3c4a71f9bea8d88e75c4fa854df36725f\
+             d2b52eb6544edcacd6f8beddfea403cb55ae31f03ad62a5ef54e42ee82c3fb35"
+        );
+    }
+
+    #[test]
+    fn test_padding_boundaries() {
+        // a length field that does not fit behind the message requires an additional block
+        let long = [b'x'; 96];
+        for length in [31, 32, 63, 64, 65, 95].iter() {
+            let mut hash_state = WhirlpoolHash::init_hash(&());
+            WhirlpoolHash::update_hash(&mut hash_state, &long[..*length]);
+            let hash = WhirlpoolHash::finish_hash(&mut hash_state, &());
+            let expected_blocks = (length + 1 + LENGTH_FIELD_BYTES).div_ceil(BLOCK_LENGTH_BYTES);
+
+            assert_eq!(hash_state.message_length, (expected_blocks * BLOCK_LENGTH_BYTES * 8) as u128);
+            assert_eq!(hash, WhirlpoolHash::digest(&long[..*length]));
+        }
+    }
+
+    #[test]
+    fn test_stream() {
+        let ctx = ();
+        let mut hash_state = WhirlpoolHash::init_hash(&ctx);
+        let mut message = Vec::new();
+        for chunk in STREAM_TEXT.iter() {
+            WhirlpoolHash::update_hash(&mut hash_state, chunk.as_bytes());
+            message.extend_from_slice(chunk.as_bytes());
+        }
+
+        assert_eq!(WhirlpoolHash::finish_hash(&mut hash_state, &ctx), WhirlpoolHash::digest(&message));
+
+        // bytewise updates are buffered until a block is complete
+        let mut hash_state = WhirlpoolHash::init_hash(&ctx);
+        for byte in message.iter() {
+            WhirlpoolHash::update_hash(&mut hash_state, &[*byte]);
+        }
+        assert_eq!(WhirlpoolHash::finish_hash(&mut hash_state, &ctx), WhirlpoolHash::digest(&message));
+    }
+
+    #[test]
+    fn test_peek_and_reset() {
+        assert_peek_consistent::<WhirlpoolHash>(&());
+        assert_reset_consistent::<WhirlpoolHash>(&());
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let hash = WhirlpoolHash::digest(b"abc");
+        let parsed: WhirlpoolHash = hash.to_string().to_uppercase().parse().unwrap();
+        assert_eq!(parsed, hash);
+
+        assert_eq!("00".parse::<WhirlpoolHash>().unwrap_err(), ParseHexError::InvalidLength { length: 1 });
+    }
+}
@@ -0,0 +1,226 @@
+//! Digests compared against test vectors in the format of the NIST CAVP response files, which are stored in
+//! `tests/vectors/`. Every hash function is registered with one line of `cavp_tests!`, which tests the files
+//! `<PREFIX>ShortMsg.rsp`, `<PREFIX>LongMsg.rsp` and `<PREFIX>Monte.rsp`:
+//!
+//! - every message of the ShortMsg and LongMsg files is hashed with `digest_message` and streamed into a `Hasher`
+//!   in randomly sized chunks
+//! - the Monte Carlo file is checked with the procedure of the SHA validation system, which chains 1000 hashes of the
+//!   three previous digests per checkpoint
+//!
+//! Only byte oriented files are supported, i.e. all message lengths must be multiples of eight bits.
+
+use std::fs;
+use std::path::PathBuf;
+
+use jester_hashes::hasher::Hasher;
+use jester_hashes::md5::MD5Hash;
+use jester_hashes::sha1::SHA1Hash;
+use jester_hashes::{HashFunction, HashValue};
+
+/// The number of hashes chained between two checkpoints of the Monte Carlo test
+const MONTE_CARLO_ITERATIONS: usize = 1000;
+
+/// A message and its digest from a ShortMsg or LongMsg file
+#[derive(Debug)]
+struct MessageVector {
+    /// The line of the `Len` entry of the vector, to locate failing vectors
+    line: usize,
+    message: Vec<u8>,
+    digest: Vec<u8>,
+}
+
+/// The seed and the digests at all checkpoints of a Monte Carlo file
+#[derive(Debug)]
+struct MonteCarloVectors {
+    seed: Vec<u8>,
+    checkpoints: Vec<Vec<u8>>,
+}
+
+fn read_vector_file(name: &str) -> String {
+    let path = [env!("CARGO_MANIFEST_DIR"), "tests", "vectors", name].iter().collect::<PathBuf>();
+    fs::read_to_string(&path).unwrap_or_else(|error| panic!("cannot read {}: {}", path.display(), error))
+}
+
+/// The `key = value` entries of a response file with their line numbers. Comments, blank lines and the bracketed
+/// parameter lines are skipped.
+fn entries(contents: &str) -> impl Iterator<Item = (usize, &str, &str)> {
+    contents.lines().enumerate().filter_map(|(index, line)| {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+            return None;
+        }
+
+        let mut parts = line.splitn(2, '=');
+        let key = parts.next().unwrap().trim();
+        let value = parts.next().unwrap_or_else(|| panic!("line {} is no entry: {}", index + 1, line)).trim();
+        Some((index + 1, key, value))
+    })
+}
+
+fn parse_hex(line: usize, hex: &str) -> Vec<u8> {
+    hex::decode(hex).unwrap_or_else(|error| panic!("line {} contains invalid hex: {}", line, error))
+}
+
+/// Parse the vectors of a ShortMsg or LongMsg file.
+fn parse_message_vectors(contents: &str) -> Vec<MessageVector> {
+    let mut vectors = vec![];
+    let mut length = None;
+    let mut message = None;
+
+    for (line, key, value) in entries(contents) {
+        match key {
+            "Len" => length = Some((line, value.parse::<usize>().expect("the length is no number"))),
+            "Msg" => message = Some(parse_hex(line, value)),
+            "MD" => {
+                let (line, bits) = length.take().expect("a digest precedes its length");
+                let mut message = message.take().expect("a digest precedes its message");
+                assert_eq!(bits % 8, 0, "the message at line {} is not byte oriented", line);
+
+                // the empty message is written as a single zero byte
+                message.truncate(bits / 8);
+                assert_eq!(message.len(), bits / 8, "the message at line {} is shorter than its length", line);
+                vectors.push(MessageVector { line, message, digest: parse_hex(line, value) });
+            }
+            _ => panic!("unknown entry {} at line {}", key, line),
+        }
+    }
+
+    vectors
+}
+
+/// Parse the seed and the checkpoints of a Monte Carlo file.
+fn parse_monte_carlo_vectors(contents: &str) -> MonteCarloVectors {
+    let mut seed = None;
+    let mut checkpoints = vec![];
+
+    for (line, key, value) in entries(contents) {
+        match key {
+            "Seed" => seed = Some(parse_hex(line, value)),
+            "COUNT" => assert_eq!(value.parse::<usize>().ok(), Some(checkpoints.len()), "unexpected count at line {}",
+                                  line),
+            "MD" => checkpoints.push(parse_hex(line, value)),
+            _ => panic!("unknown entry {} at line {}", key, line),
+        }
+    }
+
+    MonteCarloVectors { seed: seed.expect("the file has no seed"), checkpoints }
+}
+
+/// A xorshift generator for the chunk sizes of the streaming path. It only has to vary the chunk boundaries, so a
+/// seeded generator without further dependencies is sufficient.
+struct ChunkSizes(u64);
+
+impl ChunkSizes {
+    fn next(&mut self, max: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % (max as u64 + 1)) as usize
+    }
+}
+
+/// Hash all vectors of a ShortMsg or LongMsg file with `digest_message` and with a `Hasher` that is updated with
+/// randomly sized chunks, including empty ones.
+fn check_message_vectors<H>(file: &str)
+    where H: HashFunction,
+          H::Context: Default
+{
+    let vectors = parse_message_vectors(&read_vector_file(file));
+    assert!(!vectors.is_empty(), "{} contains no vectors", file);
+
+    let mut chunk_sizes = ChunkSizes(0x9E37_79B9_7F4A_7C15);
+    for vector in vectors {
+        let digest = H::digest_message(&H::Context::default(), &vector.message).raw();
+        assert_eq!(hex::encode(digest), hex::encode(&vector.digest), "{}:{}", file, vector.line);
+
+        let mut hasher = Hasher::<H>::new(H::Context::default());
+        let mut remaining = &vector.message[..];
+        while !remaining.is_empty() {
+            let (chunk, rest) = remaining.split_at(chunk_sizes.next(remaining.len().min(200)));
+            hasher.update(chunk);
+            remaining = rest;
+        }
+        assert_eq!(hex::encode(hasher.finalize().raw()), hex::encode(&vector.digest), "{}:{} streamed", file,
+                   vector.line);
+    }
+}
+
+/// Run the Monte Carlo test: starting from the seed, every digest is the hash of the three previous digests, and the
+/// digest after `MONTE_CARLO_ITERATIONS` hashes is the next checkpoint and the seed of the next iterations.
+fn check_monte_carlo_vectors<H>(file: &str)
+    where H: HashFunction,
+          H::Context: Default
+{
+    let vectors = parse_monte_carlo_vectors(&read_vector_file(file));
+    assert!(!vectors.checkpoints.is_empty(), "{} contains no checkpoints", file);
+
+    let ctx = H::Context::default();
+    let mut seed = vectors.seed;
+    for (count, checkpoint) in vectors.checkpoints.iter().enumerate() {
+        let mut digests = [seed.clone(), seed.clone(), seed];
+        for _ in 0..MONTE_CARLO_ITERATIONS {
+            let digest = H::digest_message(&ctx, &digests.concat()).raw();
+            digests.rotate_left(1);
+            digests[2] = digest;
+        }
+
+        let [_, _, digest] = digests;
+        assert_eq!(hex::encode(&digest), hex::encode(checkpoint), "{} COUNT = {}", file, count);
+        seed = digest;
+    }
+}
+
+/// Register the response files `<prefix>ShortMsg.rsp`, `<prefix>LongMsg.rsp` and `<prefix>Monte.rsp` of the hash
+/// function `hash` as tests in the module `name`.
+macro_rules! cavp_tests {
+    ($name:ident, $hash:ty, $prefix:literal) => {
+        mod $name {
+            use super::*;
+
+            #[test]
+            fn short_messages() {
+                check_message_vectors::<$hash>(concat!($prefix, "ShortMsg.rsp"));
+            }
+
+            #[test]
+            fn long_messages() {
+                check_message_vectors::<$hash>(concat!($prefix, "LongMsg.rsp"));
+            }
+
+            #[test]
+            fn monte_carlo() {
+                check_monte_carlo_vectors::<$hash>(concat!($prefix, "Monte.rsp"));
+            }
+        }
+    };
+}
+
+cavp_tests!(sha1, SHA1Hash, "SHA1");
+cavp_tests!(md5, MD5Hash, "MD5");
+
+#[test]
+fn test_parse_message_vectors() {
+    let contents = "#  comment\r\n[L = 20]\r\n\r\n\
+                    Len = 0\r\nMsg = 00\r\nMD = 0102\r\n\r\n\
+                    Len = 16\r\nMsg = abcd\r\nMD = 03\r\n";
+    let vectors = parse_message_vectors(contents);
+
+    assert_eq!(vectors.len(), 2);
+    assert_eq!((vectors[0].line, vectors[0].message.as_slice(), vectors[0].digest.as_slice()),
+               (4, &[][..], &[1, 2][..]));
+    assert_eq!((vectors[1].line, vectors[1].message.as_slice(), vectors[1].digest.as_slice()),
+               (8, &[0xab, 0xcd][..], &[3][..]));
+}
+
+#[test]
+#[should_panic(expected = "not byte oriented")]
+fn test_parse_bit_oriented_vector() {
+    parse_message_vectors("Len = 3\nMsg = 80\nMD = 00\n");
+}
+
+#[test]
+fn test_parse_monte_carlo_vectors() {
+    let vectors = parse_monte_carlo_vectors("[L = 20]\n\nSeed = 00ff\n\nCOUNT = 0\nMD = 01\n\nCOUNT = 1\nMD = 02\n");
+    assert_eq!(vectors.seed, vec![0, 0xff]);
+    assert_eq!(vectors.checkpoints, vec![vec![1], vec![2]]);
+}
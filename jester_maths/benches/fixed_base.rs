@@ -0,0 +1,54 @@
+//! A rough comparison of `FixedBaseExp::pow` and `modpow` for key pair generation in `IetfGroup3`. Run it with
+//! `cargo bench -p jester_maths --bench fixed_base`.
+
+use std::time::{Duration, Instant};
+
+use num::{BigUint, Num};
+use rand::thread_rng;
+
+use jester_maths::fixed_base::FixedBaseExp;
+use jester_maths::prime::{IetfGroup3, PrimeField};
+
+const GENERATOR: &str = "AC4032EF4F2D9AE39DF30B5C8FFDAC506CDEBE7B89998CAF74866A08CFE4FFE3A6824A4E10B9A6F0DD921F01A70C4AFAAB739D7700C29F52C57DB17C620A8652BE5E9001A8D66AD7C17669101999024AF4D027275AC1348BB8A762D0521BC98AE247150422EA1ED409939D54DA7460CDB5F6C6B250717CBEF180EB34118E98D119529A45D6F834566E3025E316A330EFBB77A86F0C1AB15B051AE3D428C8F8ACB70A8137150B8EEB10E183EDD19963DDD9E263E4770589EF6AA21E7F5F2FF381B539CCE3409D13CD566AFBB48D6C019181E1BCFE94B30269EDFE72FE9B6AA4BD7B5A0F1C71CFFF4C19C418E1F6EC017981BC087F2A7065B384B890D3191F2BFA";
+
+const ITERATIONS: u32 = 200;
+
+/// Measure the average duration of `f` applied to each of the `exponents`.
+fn measure<F>(exponents: &[BigUint], f: F) -> Duration
+where
+    F: Fn(&BigUint) -> BigUint,
+{
+    let start = Instant::now();
+    for exponent in exponents {
+        assert!(f(exponent) > BigUint::from(0_u8));
+    }
+    start.elapsed() / exponents.len() as u32
+}
+
+fn main() {
+    let mut rng = thread_rng();
+    let generator = IetfGroup3::from_str_radix(GENERATOR, 16).unwrap();
+    let prime = IetfGroup3::field_prime().as_uint();
+
+    let start = Instant::now();
+    let precomputation = FixedBaseExp::new(generator.clone());
+    println!("precomputation: {:?}", start.elapsed());
+
+    let exponents = (0..ITERATIONS)
+        .map(|_| IetfGroup3::generate_random_member(&mut rng).as_uint())
+        .collect::<Vec<_>>();
+
+    let modpow = measure(&exponents, |exponent| {
+        generator.as_uint().modpow(exponent, &prime)
+    });
+    let fixed_base = measure(&exponents, |exponent| {
+        precomputation.pow(exponent).as_uint()
+    });
+
+    println!("modpow:         {:?} per exponentiation", modpow);
+    println!("fixed base:     {:?} per exponentiation", fixed_base);
+    println!(
+        "speedup:        {:.2}x",
+        modpow.as_secs_f64() / fixed_base.as_secs_f64()
+    );
+}
@@ -0,0 +1,64 @@
+//! A rough comparison of the arithmetic of `Mersenne127Fast` and the `BigUint` backed `Mersenne127`. Run it with
+//! `cargo bench -p jester_maths --bench mersenne127`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use rand::thread_rng;
+
+use jester_maths::prime::{Mersenne127, Mersenne127Fast, PrimeField};
+
+const ITERATIONS: u32 = 100_000;
+
+const INVERSIONS: u32 = 1_000;
+
+/// Measure the average duration of applying `f` to each consecutive pair of `elements`.
+fn measure<T, F>(elements: &[T], iterations: u32, f: F) -> Duration
+where
+    T: Clone,
+    F: Fn(T, T) -> T,
+{
+    let start = Instant::now();
+    for i in 0..iterations as usize {
+        let (a, b) = (elements[i % elements.len()].clone(), elements[(i + 1) % elements.len()].clone());
+        black_box(f(a, b));
+    }
+    start.elapsed() / iterations
+}
+
+fn main() {
+    let mut rng = thread_rng();
+    let generic = (0..1000)
+        .map(|_| Mersenne127::generate_random_nonzero_member(&mut rng))
+        .collect::<Vec<_>>();
+    let fast = generic
+        .iter()
+        .map(|element| Mersenne127Fast::from(element.as_uint()))
+        .collect::<Vec<_>>();
+
+    for (operation, generic_time, fast_time) in &[
+        (
+            "addition",
+            measure(&generic, ITERATIONS, |a, b| a + b),
+            measure(&fast, ITERATIONS, |a, b| a + b),
+        ),
+        (
+            "multiplication",
+            measure(&generic, ITERATIONS, |a, b| a * b),
+            measure(&fast, ITERATIONS, |a, b| a * b),
+        ),
+        (
+            "inversion",
+            measure(&generic, INVERSIONS, |a, _| a.inverse()),
+            measure(&fast, INVERSIONS, |a, _| a.inverse()),
+        ),
+    ] {
+        println!(
+            "{:<15} Mersenne127: {:>10?}  Mersenne127Fast: {:>10?}  speedup: {:.1}x",
+            operation,
+            generic_time,
+            fast_time,
+            generic_time.as_secs_f64() / fast_time.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! Exponentiation with a fixed base, like the generator of a Diffie-Hellman group. Powers of the base are precomputed
+//! once, so each exponentiation only multiplies precomputed values instead of squaring the base repeatedly. Products
+//! are reduced using Barrett reduction, which replaces the division by the prime with two multiplications.
+
+use std::iter::once;
+
+use num::{BigUint, One};
+
+use crate::prime::PrimeField;
+
+/// The number of exponent bits processed by a single precomputed multiplication.
+const WINDOW_BITS: usize = 4;
+
+/// The number of precomputed powers per window.
+const WINDOW_SIZE: usize = 1 << WINDOW_BITS;
+
+/// Precomputed powers of a fixed base within a prime field. For each `4`-bit window `i` of exponents up to the bit
+/// length of the field's prime, the powers `base^(j * 16^i)` for `j` in `0..16` are stored, so an exponentiation
+/// requires one multiplication per window. The table of a 2048-bit prime requires about 2 MiB of memory.
+///
+/// # Type Parameters
+/// - `T` the prime field the base is a member of
+#[derive(Debug, Clone)]
+pub struct FixedBaseExp<T>
+where
+    T: PrimeField,
+{
+    base: T,
+    window_powers: Vec<Vec<BigUint>>,
+    reduction: BarrettReduction,
+}
+
+/// The precomputed values for Barrett reduction modulo a `k`-bit prime `p`.
+#[derive(Debug, Clone)]
+struct BarrettReduction {
+    prime: BigUint,
+    prime_bits: usize,
+
+    /// `floor(4^k / p)`
+    factor: BigUint,
+}
+
+impl BarrettReduction {
+    fn new(prime: BigUint) -> Self {
+        let prime_bits = prime.bits();
+        let factor = (BigUint::one() << (2 * prime_bits)) / &prime;
+
+        BarrettReduction {
+            prime,
+            prime_bits,
+            factor,
+        }
+    }
+
+    /// Reduce `x` modulo the prime. The quotient estimate is at most two less than the actual quotient, if `x` is
+    /// less than the square of the prime.
+    fn reduce(&self, x: BigUint) -> BigUint {
+        let quotient = ((&x >> (self.prime_bits - 1)) * &self.factor) >> (self.prime_bits + 1);
+
+        let mut remainder = x - quotient * &self.prime;
+        while remainder >= self.prime {
+            remainder -= &self.prime;
+        }
+        remainder
+    }
+}
+
+impl<T> FixedBaseExp<T>
+where
+    T: PrimeField,
+{
+    /// Precompute the powers of `base` for all exponents up to the bit length of the field's prime.
+    pub fn new(base: T) -> Self {
+        let reduction = BarrettReduction::new(T::field_prime().as_uint());
+        let windows = reduction.prime_bits.div_ceil(WINDOW_BITS);
+
+        let mut window_powers = Vec::with_capacity(windows);
+        let mut window_base = base.as_uint();
+        for _ in 0..windows {
+            let mut powers = Vec::with_capacity(WINDOW_SIZE);
+            powers.push(BigUint::one());
+            for j in 1..WINDOW_SIZE {
+                powers.push(reduction.reduce(&powers[j - 1] * &window_base));
+            }
+
+            window_base = reduction.reduce(&powers[WINDOW_SIZE - 1] * &window_base);
+            window_powers.push(powers);
+        }
+
+        FixedBaseExp {
+            base,
+            window_powers,
+            reduction,
+        }
+    }
+
+    /// The base whose powers are precomputed.
+    pub fn base(&self) -> &T {
+        &self.base
+    }
+
+    /// Raise the base to the power of `exponent`. Exponents exceeding the bit length of the field's prime are not
+    /// covered by the precomputed powers and fall back to `modpow`.
+    pub fn pow(&self, exponent: &BigUint) -> T {
+        if exponent.bits() > self.window_powers.len() * WINDOW_BITS {
+            return self
+                .base
+                .as_uint()
+                .modpow(exponent, &self.reduction.prime)
+                .into();
+        }
+
+        let mut result = BigUint::one();
+        let windows = exponent
+            .to_bytes_le()
+            .into_iter()
+            .flat_map(|byte| once(byte & 0x0F).chain(once(byte >> 4)));
+        for (powers, window) in self.window_powers.iter().zip(windows) {
+            if window != 0 {
+                result = self.reduction.reduce(result * &powers[window as usize]);
+            }
+        }
+
+        result.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num::{FromPrimitive, Num, Zero};
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::prime::{IetfGroup3, Mersenne61};
+
+    #[test]
+    fn test_pow_matches_modpow() {
+        let mut rng = thread_rng();
+        let generator = IetfGroup3::from_str_radix("AC4032EF4F2D9AE39DF30B5C8FFDAC506CDEBE7B89998CAF74866A08CFE4FFE3A6824A4E10B9A6F0DD921F01A70C4AFAAB739D7700C29F52C57DB17C620A8652BE5E9001A8D66AD7C17669101999024AF4D027275AC1348BB8A762D0521BC98AE247150422EA1ED409939D54DA7460CDB5F6C6B250717CBEF180EB34118E98D119529A45D6F834566E3025E316A330EFBB77A86F0C1AB15B051AE3D428C8F8ACB70A8137150B8EEB10E183EDD19963DDD9E263E4770589EF6AA21E7F5F2FF381B539CCE3409D13CD566AFBB48D6C019181E1BCFE94B30269EDFE72FE9B6AA4BD7B5A0F1C71CFFF4C19C418E1F6EC017981BC087F2A7065B384B890D3191F2BFA", 16).unwrap();
+        let precomputation = FixedBaseExp::new(generator.clone());
+        let prime = IetfGroup3::field_prime().as_uint();
+
+        for _ in 0..64 {
+            let exponent = IetfGroup3::generate_random_member(&mut rng).as_uint();
+            assert_eq!(
+                precomputation.pow(&exponent).as_uint(),
+                generator.as_uint().modpow(&exponent, &prime)
+            );
+        }
+    }
+
+    #[test]
+    fn test_pow_edge_exponents() {
+        let base = Mersenne61::from_u64(3).unwrap();
+        let precomputation = FixedBaseExp::new(base.clone());
+        let prime = Mersenne61::field_prime().as_uint();
+
+        let exponents = vec![
+            BigUint::zero(),
+            BigUint::one(),
+            BigUint::from_u64(16).unwrap(),
+            &prime - BigUint::one(),
+            // exceeds the precomputed windows
+            BigUint::from_u128(1 << 70).unwrap() + BigUint::from_u64(5).unwrap(),
+        ];
+        for exponent in exponents {
+            assert_eq!(
+                precomputation.pow(&exponent).as_uint(),
+                base.as_uint().modpow(&exponent, &prime)
+            );
+        }
+
+        let zero = FixedBaseExp::new(Mersenne61::zero());
+        assert_eq!(zero.pow(&BigUint::zero()), Mersenne61::one());
+        assert_eq!(zero.pow(&BigUint::from_u64(7).unwrap()), Mersenne61::zero());
+    }
+}
@@ -0,0 +1,203 @@
+//! The binary field GF(2^128) as used by GHASH in the Galois/Counter Mode of block ciphers. Field elements are
+//! polynomials over GF(2) modulo `x^128 + x^7 + x^2 + x + 1`.
+
+use std::ops::{Add, AddAssign, Mul, MulAssign};
+
+/// An element of GF(2^128) in the bit order of GCM: the most significant bit of the first byte of a block is the
+/// coefficient of `x^0` and the least significant bit of the last byte is the coefficient of `x^127`. Addition is
+/// xor, multiplication is carry-less multiplication followed by a reduction modulo the field polynomial. On x86-64
+/// processors that support it, the multiplication uses the `pclmulqdq` instruction.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Gf128(u128);
+
+impl Gf128 {
+    /// The additive identity
+    pub const ZERO: Gf128 = Gf128(0);
+
+    /// The multiplicative identity, which is the polynomial `1`.
+    pub const ONE: Gf128 = Gf128(1 << 127);
+
+    /// Interpret a block of 16 bytes as a field element.
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        Gf128(u128::from_be_bytes(bytes))
+    }
+
+    /// The block of 16 bytes representing this field element.
+    pub fn to_bytes(self) -> [u8; 16] {
+        self.0.to_be_bytes()
+    }
+}
+
+impl Add for Gf128 {
+    type Output = Gf128;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn add(self, rhs: Gf128) -> Gf128 {
+        Gf128(self.0 ^ rhs.0)
+    }
+}
+
+impl AddAssign for Gf128 {
+    fn add_assign(&mut self, rhs: Gf128) {
+        *self = *self + rhs
+    }
+}
+
+impl Mul for Gf128 {
+    type Output = Gf128;
+
+    fn mul(self, rhs: Gf128) -> Gf128 {
+        // reversing the bits yields the usual representation with the coefficient of x^i at bit i
+        let (high, low) = clmul(self.0.reverse_bits(), rhs.0.reverse_bits());
+        Gf128(reduce(high, low).reverse_bits())
+    }
+}
+
+impl MulAssign for Gf128 {
+    fn mul_assign(&mut self, rhs: Gf128) {
+        *self = *self * rhs
+    }
+}
+
+/// Carry-less multiplication of two 128 bit polynomials, returning the high and low half of the 256 bit product.
+fn clmul(a: u128, b: u128) -> (u128, u128) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("pclmulqdq") {
+            return unsafe { clmul_intrinsic(a, b) };
+        }
+    }
+
+    clmul_portable(a, b)
+}
+
+/// Carry-less multiplication without branches depending on the operands.
+fn clmul_portable(a: u128, b: u128) -> (u128, u128) {
+    let mut high = 0_u128;
+    let mut low = 0_u128;
+
+    for i in 0..128 {
+        let mask = ((b >> i) & 1).wrapping_neg();
+        low ^= (a << i) & mask;
+        // a >> 128 would overflow, and bit 0 contributes nothing to the high half
+        high ^= (a.checked_shr(128 - i).unwrap_or(0)) & mask;
+    }
+
+    (high, low)
+}
+
+/// Carry-less multiplication using the `pclmulqdq` instruction on the four products of the 64 bit halves.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "pclmulqdq")]
+unsafe fn clmul_intrinsic(a: u128, b: u128) -> (u128, u128) {
+    use std::arch::x86_64::{__m128i, _mm_clmulepi64_si128, _mm_set_epi64x, _mm_storeu_si128};
+
+    let to_u128 = |value: __m128i| {
+        let mut bytes = [0_u8; 16];
+        _mm_storeu_si128(bytes.as_mut_ptr() as *mut __m128i, value);
+        u128::from_le_bytes(bytes)
+    };
+
+    let a = _mm_set_epi64x((a >> 64) as i64, a as i64);
+    let b = _mm_set_epi64x((b >> 64) as i64, b as i64);
+
+    let low = to_u128(_mm_clmulepi64_si128(a, b, 0x00));
+    let high = to_u128(_mm_clmulepi64_si128(a, b, 0x11));
+    let middle =
+        to_u128(_mm_clmulepi64_si128(a, b, 0x01)) ^ to_u128(_mm_clmulepi64_si128(a, b, 0x10));
+
+    (high ^ (middle >> 64), low ^ (middle << 64))
+}
+
+/// Reduce the product `high * x^128 + low` modulo `x^128 + x^7 + x^2 + x + 1`. Since `x^128` is congruent to
+/// `x^7 + x^2 + x + 1`, the high half is multiplied with it and added to the low half. The bits of this product
+/// exceeding 128 bits are folded back the same way.
+fn reduce(high: u128, low: u128) -> u128 {
+    let folded = high ^ (high >> 127) ^ (high >> 126) ^ (high >> 121);
+    low ^ folded ^ (folded << 1) ^ (folded << 2) ^ (folded << 7)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The multiplication algorithm of NIST SP 800-38D, section 6.3, operating on the GCM bit order directly.
+    fn reference_mul(x: Gf128, y: Gf128) -> Gf128 {
+        let mut z = 0_u128;
+        let mut v = y.0;
+
+        for i in (0..128).rev() {
+            if (x.0 >> i) & 1 == 1 {
+                z ^= v;
+            }
+            v = if v & 1 == 1 {
+                (v >> 1) ^ (0xE1 << 120)
+            } else {
+                v >> 1
+            };
+        }
+
+        Gf128(z)
+    }
+
+    /// A deterministic sequence of field elements covering sparse and dense bit patterns.
+    fn elements() -> Vec<Gf128> {
+        let mut state = 0x0123_4567_89AB_CDEF_FEDC_BA98_7654_3210_u128;
+        let mut elements = vec![Gf128::ZERO, Gf128::ONE, Gf128(1), Gf128(u128::MAX)];
+        for _ in 0..64 {
+            state = state
+                .wrapping_mul(0x2360_ED05_1FC6_5DA4_4385_DF64_9FCC_F645)
+                .wrapping_add(1);
+            elements.push(Gf128(state));
+        }
+
+        elements
+    }
+
+    #[test]
+    fn test_multiplication() {
+        for x in elements() {
+            for y in elements() {
+                assert_eq!(x * y, reference_mul(x, y), "{:?} * {:?}", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_portable_clmul() {
+        for x in elements() {
+            for y in elements() {
+                let (high, low) = clmul_portable(x.0, y.0);
+                assert_eq!(
+                    Gf128(reduce(high, low).reverse_bits()),
+                    reference_mul(x.reverse(), y.reverse())
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_field_identities() {
+        for x in elements() {
+            assert_eq!(x * Gf128::ONE, x);
+            assert_eq!(x * Gf128::ZERO, Gf128::ZERO);
+            assert_eq!(x + x, Gf128::ZERO);
+            assert_eq!(Gf128::from_bytes(x.to_bytes()), x);
+        }
+    }
+
+    /// The product `H * X` of the first GHASH step of test case 2 of the GCM specification.
+    #[test]
+    fn test_ghash_vector() {
+        let h = Gf128(0x66e9_4bd4_ef8a_2c3b_884c_fa59_ca34_2b2e);
+        let x = Gf128(0x0388_dace_60b6_a392_f328_c2b9_71b2_fe78);
+
+        assert_eq!(h * x, Gf128(0x5e2e_c746_9170_6288_2c85_b068_5353_deb7));
+    }
+
+    impl Gf128 {
+        fn reverse(self) -> Gf128 {
+            Gf128(self.0.reverse_bits())
+        }
+    }
+}
@@ -3,5 +3,10 @@
 
 #![recursion_limit = "256"]
 
+pub mod fixed_base;
+pub mod gf128;
 pub mod prime;
-pub mod prime_test;
\ No newline at end of file
+pub mod prime_test;
+pub mod rng;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
\ No newline at end of file
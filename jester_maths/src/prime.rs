@@ -1,12 +1,29 @@
 //! This module defines the trait `PrimeField` to be used as a numerical data type for large-prime-field algebra.
 //! It also defines macros for defining such types and provides implementations for common operations on such.
-//! Furthermore, it provides types for prime fields built from Mersenne numbers.
+//! Furthermore, it provides types for prime fields built from Mersenne numbers, and `Mersenne127Fast`, which exploits
+//! the shape of the Mersenne prime `2^127 - 1` for its reduction.
+//!
+//! # Timing
+//! Field elements are backed by `BigUint`, whose arithmetic takes time depending on the magnitude of its operands.
+//! Only the following operations do not branch on the values of field elements:
+//! - `PrimeField::conditional_select` and `PrimeField::conditional_swap`
+//! - `PrimeField::sub_mod`, and thus the subtraction of fields generated by `prime_fields!`
+//!
+//! They operate on byte buffers padded to the length of the prime, but they still convert their operands from and to
+//! `BigUint`, whose representation omits leading zeros. All other operations are not constant-time: addition,
+//! multiplication, division and remainders use `BigUint` arithmetic, `inverse` uses the extended euclidean algorithm
+//! whose recursion depth depends on the value, `pow`, `modpow`, `legendre` and `sqrt` use variable-time
+//! exponentiation, and `checked_sub`, `abs_diff` and the conversions from bytes compare their operands.
+//!
+//! `Mersenne127Fast` is backed by a `u128` instead. Its addition, subtraction, multiplication, `sub_mod`,
+//! `conditional_select`, `pow` and `inverse` do not branch on the values of field elements.
 
 use std::fmt::Debug;
+use std::hint::black_box;
 use std::iter::{Product, Sum};
 
 use mashup::*;
-use num::{BigUint, FromPrimitive, Num};
+use num::{BigUint, FromPrimitive, Num, One, ToPrimitive, Zero};
 pub use num_bigint;
 use num_bigint::RandBigInt;
 pub use once_cell;
@@ -71,15 +88,8 @@ macro_rules! prime_fields {
                     type Output = Self;
 
                     fn sub(self, rhs: $name) -> Self::Output {
-                        let mut sum = if self >= rhs {
-                            ::std::ops::Sub::sub(&self.0.clone(), &rhs.0)
-                        } else {
-                            let inverse = ::std::ops::Sub::sub("prime" $name.clone(), rhs.clone());
-                            ::std::ops::Add::add(&self.0.clone(), &inverse.0)
-                        };
-
-                        ::std::ops::RemAssign::rem_assign(&mut sum, "prime" $name.0.clone());
-                        $name(sum)
+                        // select the wrapped or unwrapped difference instead of comparing the operands
+                        $crate::prime::PrimeField::sub_mod(&self, &rhs)
                     }
                 }
             }
@@ -193,7 +203,8 @@ macro_rules! prime_fields {
                 impl num::FromPrimitive for $name {
                     fn from_i64(n: i64) -> Option<Self> {
                         if n < 0 {
-                            $crate::prime::num_bigint::BigUint::from_i64(-n).map(|a| ::std::ops::Sub::sub("prime" $name.clone(), a.into()))
+                            // the magnitude of `i64::MIN` is not representable as an `i64`
+                            $crate::prime::num_bigint::BigUint::from_u64(n.unsigned_abs()).map(|a| ::std::ops::Sub::sub("prime" $name.clone(), a.into()))
                         } else {
                             $crate::prime::num_bigint::BigUint::from_i64(n).map(|o| o.into())
                         }
@@ -237,12 +248,163 @@ pub trait PrimeField: Num + Clone + Sum + Product + From<BigUint> + FromPrimitiv
         self.as_uint().modpow(&exponent.as_uint(), &modulus.as_uint()).into()
     }
 
+    /// Subtract `rhs` from `self` without wrapping around the prime. Returns `None` if `rhs` is larger than `self`.
+    fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        let lhs = self.as_uint();
+        let rhs = rhs.as_uint();
+
+        if lhs >= rhs {
+            Some((lhs - rhs).into())
+        } else {
+            None
+        }
+    }
+
+    /// Subtract `rhs` from `self` modulo the prime without branching on the operands. The difference and the
+    /// difference wrapped around the prime are both calculated on byte buffers of the prime's length, and the result
+    /// is selected by the borrow of the difference.
+    fn sub_mod(&self, rhs: &Self) -> Self {
+        let prime = Self::field_prime().as_uint();
+        let length = prime.bits().div_ceil(8);
+        let lhs = to_fixed_bytes(&(self.as_uint() % &prime), length);
+        let rhs = to_fixed_bytes(&(rhs.as_uint() % &prime), length);
+
+        let (difference, borrow) = sub_with_borrow(&lhs, &rhs);
+        let wrapped = add_wrapping(&difference, &to_fixed_bytes(&prime, length));
+        BigUint::from_bytes_le(&select_bytes(&difference, &wrapped, borrow)).into()
+    }
+
+    /// Select `b` if `choice` is set and `a` otherwise, without branching on `choice` or the values. Both values are
+    /// masked as byte buffers of the prime's length, so the selection takes the same time for all elements.
+    fn conditional_select(a: &Self, b: &Self, choice: bool) -> Self {
+        let prime = Self::field_prime().as_uint();
+        let length = prime.bits().div_ceil(8);
+        let a = to_fixed_bytes(&(a.as_uint() % &prime), length);
+        let b = to_fixed_bytes(&(b.as_uint() % &prime), length);
+
+        BigUint::from_bytes_le(&select_bytes(&a, &b, choice as u8)).into()
+    }
+
+    /// Swap `a` and `b` if `choice` is set, without branching on `choice` or the values. See `conditional_select`.
+    fn conditional_swap(a: &mut Self, b: &mut Self, choice: bool) {
+        let swapped_a = Self::conditional_select(a, b, choice);
+        let swapped_b = Self::conditional_select(b, a, choice);
+        *a = swapped_a;
+        *b = swapped_b;
+    }
+
+    /// The absolute difference of `self` and `rhs`, which is calculated without wrapping around the prime.
+    fn abs_diff(&self, rhs: &Self) -> Self {
+        self.checked_sub(rhs)
+            .unwrap_or_else(|| rhs.checked_sub(self).unwrap())
+    }
+
     /// Calculate the multiplicative inverse of this element.
     fn inverse(&self) -> Self {
         let (_, _, inverse) = Self::extended_greatest_common_divisor(&Self::field_prime(), self);
         inverse
     }
 
+    /// The additive inverse of this element, which is `p - self` for non-zero elements and zero for zero.
+    fn neg(&self) -> Self {
+        Self::zero().sub_mod(self)
+    }
+
+    /// Lift a signed integer into the field. The magnitude of `n` is reduced modulo the prime and negated if `n` is
+    /// negative, so unlike `FromPrimitive::from_i64` the conversion cannot fail.
+    fn from_signed(n: i64) -> Self {
+        let magnitude = Self::from(BigUint::from(n.unsigned_abs()));
+        if n < 0 {
+            magnitude.neg()
+        } else {
+            magnitude
+        }
+    }
+
+    /// The multiplicative inverse of `k!`, as it appears in the Lagrange coefficients of consecutive indices.
+    ///
+    /// # Panics
+    /// If `k` is not less than the prime, because `k!` is zero in the field then.
+    fn small_factorial_inverse(k: u64) -> Self {
+        let factorial = (1..=k).map(|factor| Self::from(BigUint::from(factor))).product::<Self>();
+        assert!(!factorial.is_zero(), "{}! is not invertible in the field", k);
+        factorial.inverse()
+    }
+
+    /// The Legendre symbol of this element: `1` if it is a non-zero square, `-1` if it is not a square and `0` if it
+    /// is zero. It is calculated using Euler's criterion as `self ^ ((p - 1) / 2)`.
+    fn legendre(&self) -> i8 {
+        let prime = Self::field_prime().as_uint();
+        let value = self.as_uint();
+
+        if value.is_zero() {
+            0
+        } else if prime == BigUint::from(2u32) || value.modpow(&((&prime - 1u32) >> 1), &prime).is_one() {
+            1
+        } else {
+            -1
+        }
+    }
+
+    /// Calculate a square root of this element, or return `None` if it is not a square. Which of both roots is
+    /// returned is unspecified. If `p ≡ 3 (mod 4)`, the root is calculated by a single exponentiation, otherwise the
+    /// Tonelli-Shanks algorithm is used.
+    fn sqrt(&self) -> Option<Self> {
+        match self.legendre() {
+            0 => return Some(Self::zero()),
+            -1 => return None,
+            _ => {}
+        }
+
+        let prime = Self::field_prime().as_uint();
+        let value = self.as_uint();
+
+        if prime == BigUint::from(2u32) {
+            return Some(self.clone());
+        }
+
+        if &prime % 4u32 == BigUint::from(3u32) {
+            return Some(value.modpow(&((&prime + 1u32) >> 2), &prime).into());
+        }
+
+        // write p - 1 = q * 2^s with odd q
+        let mut q = &prime - 1u32;
+        let mut s = 0;
+        while (&q % 2u32).is_zero() {
+            q >>= 1;
+            s += 1;
+        }
+
+        // find any non-square z
+        let mut z = BigUint::from(2u32);
+        while Self::from(z.clone()).legendre() != -1 {
+            z += 1u32;
+        }
+
+        let mut m = s;
+        let mut c = z.modpow(&q, &prime);
+        let mut t = value.modpow(&q, &prime);
+        let mut root = value.modpow(&((&q + 1u32) >> 1), &prime);
+
+        while !t.is_one() {
+            // find the least i, such that t ^ (2 ^ i) = 1
+            let mut i = 0;
+            let mut t_power = t.clone();
+            while !t_power.is_one() {
+                t_power = &t_power * &t_power % &prime;
+                i += 1;
+            }
+
+            let b = c.modpow(&(BigUint::one() << (m - i - 1)), &prime);
+            m = i;
+            c = &b * &b % &prime;
+            t = t * &c % &prime;
+            root = root * &b % &prime;
+        }
+
+        Some(root.into())
+    }
+
     /// The extended euclidean algorithm within this integer prime field.
     fn extended_greatest_common_divisor(a: &Self, b: &Self) -> (Self, Self, Self) {
         if b.is_zero() {
@@ -258,7 +420,39 @@ pub trait PrimeField: Num + Clone + Sum + Product + From<BigUint> + FromPrimitiv
     /// generated field elements is not worse than guarantees by the underlying random number generator, however this
     /// method might invoke the `rng` multiple times to achieve that. It is assumed that `rng` is well-seeded and
     /// cryptographically secure.
-    fn generate_random_member<R: RngCore + CryptoRng + RandBigInt>(rng: &mut R) -> Self {
+    ///
+    /// Random bytes of the prime's bit length are drawn until they form a number below the prime. Since the prime is
+    /// at least half of the sampled range, less than two draws are expected.
+    fn generate_random_member<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        let prime = Self::field_prime().as_uint();
+        let bits = prime.bits();
+        let mut bytes = vec![0u8; bits.div_ceil(8)];
+        let top_byte_mask = 0xFF_u8 >> (bytes.len() * 8 - bits);
+
+        loop {
+            rng.fill_bytes(&mut bytes);
+            bytes[0] &= top_byte_mask;
+
+            let candidate = BigUint::from_bytes_be(&bytes);
+            if candidate < prime {
+                return candidate.into();
+            }
+        }
+    }
+
+    /// Generate a random member of this field, that is not zero. See `generate_random_member`.
+    fn generate_random_nonzero_member<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        loop {
+            let member = Self::generate_random_member(rng);
+            if !member.is_zero() {
+                return member;
+            }
+        }
+    }
+
+    /// Generate a random member of this field using the `RandBigInt` extension of `num-bigint`.
+    #[deprecated(note = "use `generate_random_member`, which only requires `RngCore + CryptoRng`")]
+    fn generate_random_member_bigint<R: RngCore + CryptoRng + RandBigInt>(rng: &mut R) -> Self {
         rng.gen_biguint_below(&Self::field_prime().as_uint()).into()
     }
 
@@ -321,6 +515,240 @@ prime_fields!(
     ("87A8E61DB4B6663CFFBBD19C651959998CEEF608660DD0F25D2CEED4435E3B00E00DF8F1D61957D4FAF7DF4561B2AA3016C3D91134096FAA3BF4296D830E9A7C209E0C6497517ABD5A8A9D306BCF67ED91F9E6725B4758C022E0B1EF4275BF7B6C5BFC11D45F9088B941F54EB1E59BB8BC39A0BF12307F5C4FDB70C581B23F76B63ACAE1CAA6B7902D52526735488A0EF13C6D9A51BFA4AB3AD8347796524D8EF6A167B5A41825D967E144E5140564251CCACB83E6B486F6B3CA3F7971506026C0B857F689962856DED4010ABD0BE621C3A3960A54E710C375F26375D7014103A4B54330C198AF126116D2276E11715F693877FAD7EF09CADB094AE91E1A1597", 16)
 );
 
+/// The little endian bytes of `value`, padded with zeros to `length` bytes.
+fn to_fixed_bytes(value: &BigUint, length: usize) -> Vec<u8> {
+    let mut bytes = value.to_bytes_le();
+    bytes.resize(length, 0);
+    bytes
+}
+
+/// Select the bytes of `b` if `choice` is `1` and the bytes of `a` if it is `0`. The mask derived from `choice` is
+/// hidden from the optimizer, so it cannot turn the selection into a branch.
+fn select_bytes(a: &[u8], b: &[u8], choice: u8) -> Vec<u8> {
+    let mask = black_box(0u8.wrapping_sub(choice));
+    a.iter().zip(b).map(|(a, b)| a ^ (mask & (a ^ b))).collect()
+}
+
+/// Subtract two little endian numbers of the same length, wrapping around on underflow.
+///
+/// # Returns
+/// The difference and the final borrow, which is `1` if `rhs` is larger than `lhs`
+fn sub_with_borrow(lhs: &[u8], rhs: &[u8]) -> (Vec<u8>, u8) {
+    let mut borrow = 0u16;
+    let difference = lhs
+        .iter()
+        .zip(rhs)
+        .map(|(lhs, rhs)| {
+            let difference = (*lhs as u16).wrapping_sub(*rhs as u16).wrapping_sub(borrow);
+            borrow = (difference >> 8) & 1;
+            difference as u8
+        })
+        .collect();
+    (difference, borrow as u8)
+}
+
+/// Add two little endian numbers of the same length, dropping the final carry.
+fn add_wrapping(lhs: &[u8], rhs: &[u8]) -> Vec<u8> {
+    let mut carry = 0u16;
+    lhs.iter()
+        .zip(rhs)
+        .map(|(lhs, rhs)| {
+            let sum = *lhs as u16 + *rhs as u16 + carry;
+            carry = sum >> 8;
+            sum as u8
+        })
+        .collect()
+}
+
+/// The Mersenne prime `2^127 - 1` as the backing integer of `Mersenne127Fast`
+const MERSENNE_127: u128 = (1 << 127) - 1;
+
+/// The prime field of the Mersenne prime `2^127 - 1`, like `Mersenne127`, but backed by a single `u128` instead of a
+/// `BigUint`. Products are reduced by folding the bits above the 127th bit back onto the lower bits, which replaces
+/// the division of the `BigUint` remainder by shifts and additions, and inverses are calculated by Fermat
+/// exponentiation with this multiplication.
+///
+/// Elements convert from and to `BigUint` like the fields generated by `prime_fields!`, and `Div` and `Rem` are the
+/// integer operations on the representatives, too. See the module documentation for the operations that do not branch
+/// on the values of their operands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Hash)]
+pub struct Mersenne127Fast(u128);
+
+impl Mersenne127Fast {
+    /// Reduce any `u128` modulo the prime.
+    fn reduce(value: u128) -> u128 {
+        // 2^127 is congruent to one, so the top bit is added to the lower 127 bits
+        let folded = (value & MERSENNE_127) + (value >> 127);
+        let (reduced, borrow) = folded.overflowing_sub(MERSENNE_127);
+        select_u128(reduced, folded, borrow)
+    }
+
+    /// Multiply two representatives of at most 127 bits into a 254 bit product and reduce it modulo the prime.
+    fn multiply(lhs: u128, rhs: u128) -> u128 {
+        let (lhs_low, lhs_high) = (lhs as u64 as u128, lhs >> 64);
+        let (rhs_low, rhs_high) = (rhs as u64 as u128, rhs >> 64);
+
+        // schoolbook multiplication of the 64 bit limbs
+        let low = lhs_low * rhs_low;
+        let (middle, middle_carry) = (lhs_low * rhs_high).overflowing_add(lhs_high * rhs_low);
+        let high = lhs_high * rhs_high;
+
+        let (product_low, low_carry) = low.overflowing_add(middle << 64);
+        let product_high = high + (middle >> 64) + ((middle_carry as u128) << 64) + low_carry as u128;
+
+        // 2^127 is congruent to one, so the product splits into its lower 127 bits and the rest
+        Self::reduce((product_low & MERSENNE_127) + ((product_high << 1) | (product_low >> 127)))
+    }
+}
+
+/// Select `b` if `choice` is set and `a` otherwise, without branching on `choice`.
+fn select_u128(a: u128, b: u128, choice: bool) -> u128 {
+    let mask = black_box(0u128.wrapping_sub(choice as u128));
+    a ^ (mask & (a ^ b))
+}
+
+impl std::ops::Add<Mersenne127Fast> for Mersenne127Fast {
+    type Output = Self;
+
+    fn add(self, rhs: Mersenne127Fast) -> Self::Output {
+        // representatives are at most the prime itself, so the sum cannot overflow
+        Mersenne127Fast(Self::reduce(self.0 + rhs.0))
+    }
+}
+
+impl std::ops::Sub<Mersenne127Fast> for Mersenne127Fast {
+    type Output = Self;
+
+    fn sub(self, rhs: Mersenne127Fast) -> Self::Output {
+        let (difference, borrow) = self.0.overflowing_sub(rhs.0);
+        let wrapped = difference.wrapping_add(MERSENNE_127);
+        Mersenne127Fast(Self::reduce(select_u128(difference, wrapped, borrow)))
+    }
+}
+
+impl std::ops::Mul<Mersenne127Fast> for Mersenne127Fast {
+    type Output = Self;
+
+    fn mul(self, rhs: Mersenne127Fast) -> Self::Output {
+        Mersenne127Fast(Self::multiply(self.0, rhs.0))
+    }
+}
+
+impl std::ops::Div<Mersenne127Fast> for Mersenne127Fast {
+    type Output = Self;
+
+    fn div(self, rhs: Mersenne127Fast) -> Self::Output {
+        Mersenne127Fast(Self::reduce(self.0 / rhs.0))
+    }
+}
+
+impl std::ops::Rem<Mersenne127Fast> for Mersenne127Fast {
+    type Output = Self;
+
+    fn rem(self, rhs: Mersenne127Fast) -> Self::Output {
+        Mersenne127Fast(self.0 % rhs.0)
+    }
+}
+
+impl Zero for Mersenne127Fast {
+    fn zero() -> Self {
+        Mersenne127Fast(0)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl One for Mersenne127Fast {
+    fn one() -> Self {
+        Mersenne127Fast(1)
+    }
+}
+
+impl Num for Mersenne127Fast {
+    type FromStrRadixErr = num::bigint::ParseBigIntError;
+
+    fn from_str_radix(str: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+        BigUint::from_str_radix(str, radix).map(Self::from)
+    }
+}
+
+impl Sum for Mersenne127Fast {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), |sum, x| sum + x)
+    }
+}
+
+impl Product for Mersenne127Fast {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::one(), |product, x| product * x)
+    }
+}
+
+impl From<Mersenne127Fast> for BigUint {
+    fn from(v: Mersenne127Fast) -> Self {
+        BigUint::from(v.0)
+    }
+}
+
+impl From<BigUint> for Mersenne127Fast {
+    fn from(v: BigUint) -> Self {
+        let reduced = v % BigUint::from(MERSENNE_127);
+        Mersenne127Fast(reduced.to_u128().unwrap())
+    }
+}
+
+impl FromPrimitive for Mersenne127Fast {
+    fn from_i64(n: i64) -> Option<Self> {
+        Some(Self::from_signed(n))
+    }
+
+    fn from_u64(n: u64) -> Option<Self> {
+        Some(Mersenne127Fast(n as u128))
+    }
+}
+
+impl PrimeField for Mersenne127Fast {
+    fn field_prime() -> Self {
+        Mersenne127Fast(MERSENNE_127)
+    }
+
+    fn as_uint(&self) -> BigUint {
+        (*self).into()
+    }
+
+    /// Square and multiply over all 127 bits of the exponent, using the folding multiplication.
+    fn pow(&self, exponent: &Self) -> Self {
+        let base = Self::reduce(self.0);
+        let mut result = 1;
+        for bit in (0..127).rev() {
+            result = Self::multiply(result, result);
+            let product = Self::multiply(result, base);
+            result = select_u128(result, product, (exponent.0 >> bit) & 1 == 1);
+        }
+        Mersenne127Fast(result)
+    }
+
+    fn sub_mod(&self, rhs: &Self) -> Self {
+        *self - *rhs
+    }
+
+    fn conditional_select(a: &Self, b: &Self, choice: bool) -> Self {
+        Mersenne127Fast(select_u128(Self::reduce(a.0), Self::reduce(b.0), choice))
+    }
+
+    /// Fermat's little theorem, so the inverse is `self ^ (p - 2)`. The inverse of zero is zero.
+    fn inverse(&self) -> Self {
+        self.pow(&Mersenne127Fast(MERSENNE_127 - 2))
+    }
+
+    fn from_signed(n: i64) -> Self {
+        let magnitude = Mersenne127Fast(n.unsigned_abs() as u128);
+        Self::conditional_select(&magnitude, &magnitude.neg(), n < 0)
+    }
+}
+
 /// This trait defines a function to randomly generate a prime number of a given size
 pub trait PrimeGenerator {
     fn generate_random_prime<R>(rng: &mut R, bit_size: usize) -> BigUint
@@ -330,9 +758,17 @@ pub trait PrimeGenerator {
 
 #[cfg(test)]
 mod tests {
-    use num::{Num, One};
+    use num::{Num, One, ToPrimitive, Zero};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use std::cell::Cell;
+    use std::cmp::Ordering;
+    use std::ops::{Add, Div, Mul, Rem, Sub};
 
     use super::*;
+    use crate::rng::{CryptoRngCore, DynCryptoRng};
+    use crate::testing::assert_field_axioms;
 
     #[test]
     fn test_addition() {
@@ -345,6 +781,31 @@ mod tests {
     }
 
     /// Test, whether an overflowing subtraction correctly wraps around the mersenne number 2^89-1
+    #[test]
+    fn test_field_axioms() {
+        let mut rng = StdRng::seed_from_u64(0xF1E1D);
+        assert_field_axioms::<Mersenne2, _>(&mut rng, 100);
+        assert_field_axioms::<Mersenne3, _>(&mut rng, 100);
+        assert_field_axioms::<Mersenne5, _>(&mut rng, 100);
+        assert_field_axioms::<Mersenne13, _>(&mut rng, 100);
+        assert_field_axioms::<Mersenne17, _>(&mut rng, 100);
+        assert_field_axioms::<Mersenne19, _>(&mut rng, 100);
+        assert_field_axioms::<Mersenne31, _>(&mut rng, 100);
+        assert_field_axioms::<Mersenne61, _>(&mut rng, 100);
+        assert_field_axioms::<Mersenne89, _>(&mut rng, 100);
+        assert_field_axioms::<Mersenne107, _>(&mut rng, 100);
+        assert_field_axioms::<Mersenne127, _>(&mut rng, 100);
+        assert_field_axioms::<Mersenne127Fast, _>(&mut rng, 100);
+    }
+
+    #[test]
+    fn test_field_axioms_ietf_groups() {
+        let mut rng = StdRng::seed_from_u64(0x5114);
+        assert_field_axioms::<IetfGroup1, _>(&mut rng, 20);
+        assert_field_axioms::<IetfGroup2, _>(&mut rng, 20);
+        assert_field_axioms::<IetfGroup3, _>(&mut rng, 20);
+    }
+
     #[test]
     fn test_subtraction() {
         let result = Mersenne89::one() - Mersenne89::from_str_radix("645784", 10).unwrap();
@@ -353,4 +814,449 @@ mod tests {
             result
         )
     }
+
+    #[test]
+    fn test_checked_sub() {
+        let two = Mersenne5::from_u64(2).unwrap();
+        let five = Mersenne5::from_u64(5).unwrap();
+
+        assert_eq!(five.checked_sub(&two), Mersenne5::from_u64(3));
+        assert_eq!(two.checked_sub(&five), None);
+        assert_eq!(two.checked_sub(&two), Some(Mersenne5::zero()));
+        assert_eq!(two.abs_diff(&five), Mersenne5::from_u64(3).unwrap());
+        assert_eq!(five.abs_diff(&two), Mersenne5::from_u64(3).unwrap());
+        assert_eq!(two - five, Mersenne5::from_u64(28).unwrap());
+    }
+
+    #[test]
+    fn test_signed_helpers() {
+        for n in [0, 1, -1, 2, -2, 31, -31, 32, -32, i64::MAX, -i64::MAX, i64::MIN].iter() {
+            assert_eq!(Mersenne5::from_signed(*n), Mersenne5::from_i64(*n).unwrap(), "conversion of {}", n);
+            assert_eq!(Mersenne89::from_signed(*n), Mersenne89::from_i64(*n).unwrap(), "conversion of {}", n);
+        }
+
+        assert_eq!(Mersenne5::zero().neg(), Mersenne5::zero());
+        assert_eq!(Mersenne5::one().neg(), Mersenne5::from_u64(30).unwrap());
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..100 {
+            let x = Mersenne89::generate_random_member(&mut rng);
+            assert_eq!(x.neg().neg(), x);
+            assert!((x.neg() + x).is_zero());
+        }
+    }
+
+    #[test]
+    fn test_small_factorial_inverse() {
+        assert_eq!(Mersenne5::small_factorial_inverse(0), Mersenne5::one());
+        assert_eq!(Mersenne5::small_factorial_inverse(1), Mersenne5::one());
+        let mut factorial = 1;
+        for k in 2..31 {
+            factorial = factorial * k % 31;
+            assert!((Mersenne5::small_factorial_inverse(k) * Mersenne5::from_u64(factorial).unwrap()).is_one());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_small_factorial_inverse_of_prime() {
+        Mersenne5::small_factorial_inverse(31);
+    }
+
+    /// Check the square roots of all elements of the field `F` and return the number of non-zero squares
+    fn assert_exhaustive_sqrt<F: PrimeField>() -> usize {
+        let prime = F::field_prime().as_uint().to_u64().unwrap();
+        let mut squares = 0;
+
+        for value in 1..prime {
+            let element = F::from_u64(value).unwrap();
+            match element.sqrt() {
+                Some(root) => {
+                    assert_eq!(element.legendre(), 1);
+                    assert_eq!(root.clone() * root, element);
+                    squares += 1;
+                }
+                None => assert_eq!(element.legendre(), -1),
+            }
+        }
+
+        assert_eq!(F::zero().sqrt(), Some(F::zero()));
+        assert_eq!(F::zero().legendre(), 0);
+        squares
+    }
+
+    // primes congruent to 1 modulo 4, to test the Tonelli-Shanks algorithm
+    prime_fields!(Fermat3("257", 10), Prime97("97", 10));
+
+    #[test]
+    fn test_sqrt_exhaustive() {
+        assert_eq!(assert_exhaustive_sqrt::<Mersenne5>(), 15);
+        assert_eq!(assert_exhaustive_sqrt::<Mersenne13>(), 4095);
+        assert_eq!(assert_exhaustive_sqrt::<Fermat3>(), 128);
+        assert_eq!(assert_exhaustive_sqrt::<Prime97>(), 48);
+    }
+
+    #[test]
+    fn test_sqrt_random() {
+        let mut rng = StdRng::seed_from_u64(4);
+
+        for _ in 0..100 {
+            let element = Mersenne89::generate_random_nonzero_member(&mut rng);
+            let square = element.clone() * element.clone();
+
+            let root = square.sqrt().unwrap();
+            assert!(root == element || root == Mersenne89::zero() - element.clone());
+            assert_eq!(square.legendre(), 1);
+
+            // -1 is no square, because p ≡ 3 (mod 4), thus the negated square is no square either
+            let negated_square = Mersenne89::zero() - square;
+            assert_eq!(negated_square.sqrt(), None);
+            assert_eq!(negated_square.legendre(), -1);
+        }
+    }
+
+    #[test]
+    fn test_random_member_range() {
+        let mut rng = StdRng::seed_from_u64(1);
+
+        for _ in 0..1000 {
+            assert!(Mersenne13::generate_random_member(&mut rng).as_uint() < Mersenne13::field_prime().as_uint());
+            assert!(Mersenne89::generate_random_member(&mut rng).as_uint() < Mersenne89::field_prime().as_uint());
+            assert!(!Mersenne2::generate_random_nonzero_member(&mut rng).is_zero());
+        }
+    }
+
+    /// Sort random members of `Mersenne13` into ten buckets and check the chi-squared statistic against the 0.999
+    /// quantile of the chi-squared distribution with nine degrees of freedom.
+    #[test]
+    fn test_random_member_distribution() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let samples = 100_000;
+        let mut buckets = [0usize; 10];
+
+        for _ in 0..samples {
+            let member: u64 = Mersenne13::generate_random_member(&mut rng).as_uint().to_u64().unwrap();
+            buckets[(member * 10 / 8191) as usize] += 1;
+        }
+
+        let expected = samples as f64 / 10.0;
+        let chi_squared: f64 = buckets
+            .iter()
+            .map(|&count| (count as f64 - expected).powi(2) / expected)
+            .sum();
+
+        assert!(chi_squared < 27.88, "chi-squared statistic {} indicates bias", chi_squared);
+    }
+
+    #[test]
+    fn test_random_member_dyn_rng() {
+        let mut seeded = StdRng::seed_from_u64(3);
+        let mut rng = DynCryptoRng::new(&mut seeded);
+        let member = Mersenne61::generate_random_member(&mut rng);
+
+        let mut seeded = StdRng::seed_from_u64(3);
+        let dyn_rng: &mut dyn CryptoRngCore = &mut seeded;
+        assert_eq!(Mersenne61::generate_random_member(dyn_rng), member);
+    }
+
+    /// Check `sub_mod`, `conditional_select` and `conditional_swap` for all pairs of elements of the field `F`
+    fn assert_exhaustive_constant_time_ops<F: PrimeField + PartialEq>() {
+        let prime = F::field_prime().as_uint().to_u64().unwrap();
+
+        for lhs in 0..prime {
+            for rhs in 0..prime {
+                let (a, b) = (F::from_u64(lhs).unwrap(), F::from_u64(rhs).unwrap());
+                assert_eq!(a.sub_mod(&b), F::from_u64((lhs + prime - rhs) % prime).unwrap());
+                assert_eq!(F::conditional_select(&a, &b, false), a);
+                assert_eq!(F::conditional_select(&a, &b, true), b);
+
+                let (mut x, mut y) = (a.clone(), b.clone());
+                F::conditional_swap(&mut x, &mut y, false);
+                assert_eq!((&x, &y), (&a, &b));
+                F::conditional_swap(&mut x, &mut y, true);
+                assert_eq!((x, y), (b, a));
+            }
+        }
+
+        // the prime itself is not a canonical representative
+        assert_eq!(F::one().sub_mod(&F::field_prime()), F::one());
+        assert_eq!(F::conditional_select(&F::one(), &F::field_prime(), true), F::zero());
+    }
+
+    #[test]
+    fn test_constant_time_ops_exhaustive() {
+        assert_exhaustive_constant_time_ops::<Mersenne2>();
+        assert_exhaustive_constant_time_ops::<Mersenne3>();
+        assert_exhaustive_constant_time_ops::<Mersenne5>();
+        assert_exhaustive_constant_time_ops::<Prime97>();
+        assert_exhaustive_constant_time_ops::<Fermat3>();
+    }
+
+    #[test]
+    fn test_constant_time_ops_random() {
+        let mut rng = StdRng::seed_from_u64(5);
+
+        for _ in 0..100 {
+            let a = IetfGroup2::generate_random_member(&mut rng);
+            let b = IetfGroup2::generate_random_member(&mut rng);
+            let prime = IetfGroup2::field_prime().as_uint();
+            let expected = (a.as_uint() + &prime - b.as_uint()) % &prime;
+
+            assert_eq!(a.sub_mod(&b).as_uint(), expected);
+            assert_eq!(IetfGroup2::conditional_select(&a, &b, true), b);
+        }
+    }
+
+    thread_local! {
+        static COMPARISONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// A field element that counts its comparisons, to check that the constant-time operations never compare
+    /// elements
+    #[derive(Clone, Debug, PartialEq)]
+    struct Traced(Fermat3);
+
+    impl PartialOrd for Traced {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            COMPARISONS.with(|comparisons| comparisons.set(comparisons.get() + 1));
+            self.0.partial_cmp(&other.0)
+        }
+
+        fn ge(&self, other: &Self) -> bool {
+            COMPARISONS.with(|comparisons| comparisons.set(comparisons.get() + 1));
+            self.0 >= other.0
+        }
+    }
+
+    impl Add for Traced {
+        type Output = Self;
+
+        fn add(self, rhs: Self) -> Self {
+            Traced(self.0 + rhs.0)
+        }
+    }
+
+    impl Sub for Traced {
+        type Output = Self;
+
+        fn sub(self, rhs: Self) -> Self {
+            self.sub_mod(&rhs)
+        }
+    }
+
+    impl Mul for Traced {
+        type Output = Self;
+
+        fn mul(self, rhs: Self) -> Self {
+            Traced(self.0 * rhs.0)
+        }
+    }
+
+    impl Div for Traced {
+        type Output = Self;
+
+        fn div(self, rhs: Self) -> Self {
+            Traced(self.0 / rhs.0)
+        }
+    }
+
+    impl Rem for Traced {
+        type Output = Self;
+
+        fn rem(self, rhs: Self) -> Self {
+            Traced(self.0 % rhs.0)
+        }
+    }
+
+    impl Zero for Traced {
+        fn zero() -> Self {
+            Traced(Fermat3::zero())
+        }
+
+        fn is_zero(&self) -> bool {
+            self.0.is_zero()
+        }
+    }
+
+    impl One for Traced {
+        fn one() -> Self {
+            Traced(Fermat3::one())
+        }
+    }
+
+    impl Num for Traced {
+        type FromStrRadixErr = <Fermat3 as Num>::FromStrRadixErr;
+
+        fn from_str_radix(str: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+            Fermat3::from_str_radix(str, radix).map(Traced)
+        }
+    }
+
+    impl Sum for Traced {
+        fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+            Traced(iter.map(|element| element.0).sum())
+        }
+    }
+
+    impl Product for Traced {
+        fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+            Traced(iter.map(|element| element.0).product())
+        }
+    }
+
+    impl From<BigUint> for Traced {
+        fn from(value: BigUint) -> Self {
+            Traced(value.into())
+        }
+    }
+
+    impl FromPrimitive for Traced {
+        fn from_i64(n: i64) -> Option<Self> {
+            Fermat3::from_i64(n).map(Traced)
+        }
+
+        fn from_u64(n: u64) -> Option<Self> {
+            Fermat3::from_u64(n).map(Traced)
+        }
+    }
+
+    impl PrimeField for Traced {
+        fn field_prime() -> Self {
+            Traced(Fermat3::field_prime())
+        }
+
+        fn as_uint(&self) -> BigUint {
+            self.0.as_uint()
+        }
+    }
+
+    #[test]
+    fn test_constant_time_ops_never_compare() {
+        let (mut a, mut b) = (Traced::from_u64(3).unwrap(), Traced::from_u64(200).unwrap());
+
+        assert_eq!(a.clone() - b.clone(), Traced::from_u64(60).unwrap());
+        assert_eq!(Traced::conditional_select(&a, &b, true), b);
+        Traced::conditional_swap(&mut a, &mut b, true);
+        assert_eq!(COMPARISONS.with(Cell::get), 0);
+
+        // the counter does detect comparisons
+        assert!(a >= b);
+        assert_eq!(COMPARISONS.with(Cell::get), 1);
+    }
+
+    /// Compare the result of an operation on `Mersenne127Fast` with the same operation on `Mersenne127`
+    fn assert_same(operation: &str, fast: Mersenne127Fast, generic: Mersenne127, operands: &[&Mersenne127]) {
+        assert_eq!(fast.as_uint(), generic.as_uint(), "{} of {:?}", operation, operands);
+    }
+
+    /// The same representative in both fields, without reducing it, so the prime itself can be used as an operand
+    fn representatives(value: &BigUint) -> (Mersenne127Fast, Mersenne127) {
+        (Mersenne127Fast(value.to_u128().unwrap()), Mersenne127(value.clone()))
+    }
+
+    #[test]
+    fn test_mersenne127_fast_differential() {
+        let mut rng = StdRng::seed_from_u64(127);
+        let prime = Mersenne127::field_prime().as_uint();
+
+        // the representatives at the edges of the limbs and the prime itself, as returned by `field_prime`
+        let mut elements = vec![
+            BigUint::zero(),
+            BigUint::one(),
+            BigUint::from(u64::MAX),
+            BigUint::one() << 64,
+            BigUint::one() << 126,
+            &prime - 1u32,
+            prime.clone(),
+        ];
+        elements.extend((0..200).map(|_| Mersenne127::generate_random_member(&mut rng).as_uint()));
+
+        for a in &elements {
+            let (fast_a, generic_a) = representatives(a);
+
+            // the inverse of zero is unspecified
+            if !(a % &prime).is_zero() {
+                assert_same("inverse", fast_a.inverse(), generic_a.inverse(), &[&generic_a]);
+            }
+            assert_same("negation", fast_a.neg(), generic_a.neg(), &[&generic_a]);
+
+            // exponentiation is slow for the generic field, so only the edge values and a few random exponents are used
+            for exponent in &elements[..16] {
+                let (fast_exponent, generic_exponent) = representatives(exponent);
+                let operands = [&generic_a, &generic_exponent];
+                assert_same("power", fast_a.pow(&fast_exponent), generic_a.pow(&generic_exponent), &operands);
+            }
+
+            for b in &elements {
+                let (fast_b, generic_b) = representatives(b);
+                let operands = [&generic_a, &generic_b];
+
+                assert_same("sum", fast_a + fast_b, generic_a.clone() + generic_b.clone(), &operands);
+                assert_same("difference", fast_a - fast_b, generic_a.clone() - generic_b.clone(), &operands);
+                assert_same("product", fast_a * fast_b, generic_a.clone() * generic_b.clone(), &operands);
+                if !b.is_zero() {
+                    assert_same("quotient", fast_a / fast_b, generic_a.clone() / generic_b.clone(), &operands);
+                    assert_same("remainder", fast_a % fast_b, generic_a.clone() % generic_b.clone(), &operands);
+                }
+            }
+        }
+    }
+
+    /// Apply `count` random additions, subtractions and multiplications to an accumulator of both fields, so results
+    /// of the fast field are reused as operands like in protocols.
+    fn assert_random_operations(seed: u64, count: usize) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut fast = Mersenne127Fast::one();
+        let mut generic = Mersenne127::one();
+
+        for i in 0..count {
+            let operand = Mersenne127::generate_random_member(&mut rng);
+            let fast_operand = Mersenne127Fast::from(operand.as_uint());
+
+            match i % 4 {
+                0 => {
+                    fast = fast + fast_operand;
+                    generic = generic + operand;
+                }
+                1 => {
+                    fast = fast - fast_operand;
+                    generic = generic - operand;
+                }
+                _ => {
+                    fast = fast * fast_operand;
+                    generic = generic * operand;
+                }
+            }
+            assert_eq!(fast.as_uint(), generic.as_uint(), "operation {}", i);
+        }
+    }
+
+    #[test]
+    fn test_mersenne127_fast_random_operations() {
+        assert_random_operations(0x7F, 100_000);
+    }
+
+    /// Run with `cargo test --release -p jester_maths -- --ignored`, it takes minutes without optimizations.
+    #[test]
+    #[ignore]
+    fn test_mersenne127_fast_random_operations_large() {
+        assert_random_operations(0x7F7F, 4_000_000);
+    }
+
+    #[test]
+    fn test_mersenne127_fast_conversions() {
+        let prime = Mersenne127::field_prime().as_uint();
+        assert_eq!(Mersenne127Fast::field_prime().as_uint(), prime);
+        assert_eq!(Mersenne127Fast::from(prime.clone()), Mersenne127Fast::zero());
+        assert_eq!(Mersenne127Fast::from(&prime * &prime + 5u32), Mersenne127Fast::from_u64(5).unwrap());
+        assert_eq!(Mersenne127Fast::from_i64(-1).unwrap().as_uint(), &prime - 1u32);
+        assert_eq!(
+            Mersenne127Fast::from_signed(i64::MIN).as_uint(),
+            Mersenne127::from_signed(i64::MIN).as_uint()
+        );
+        assert_eq!(
+            Mersenne127Fast::from_str_radix("170141183460469231731687303715884105728", 10),
+            Ok(Mersenne127Fast::one())
+        );
+        assert_eq!(Mersenne127Fast::one().sqrt().map(|root| root * root), Some(Mersenne127Fast::one()));
+    }
 }
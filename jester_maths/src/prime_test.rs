@@ -1,6 +1,141 @@
+use num::{BigUint, Integer, One, ToPrimitive, Zero};
+use num_bigint::RandBigInt;
+use rand::{CryptoRng, RngCore};
+
+use crate::prime::PrimeGenerator;
+
 /// Trait for algorithms to test whether a specified number is prime.
 pub trait PrimeTest<P> {
 
     /// Test whether the given numeral is a prime number
     fn is_prime(number: &P) -> bool;
-}
\ No newline at end of file
+}
+
+/// The primes below 256, used for trial division before the Miller-Rabin rounds.
+const SMALL_PRIMES: [u32; 54] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89, 97,
+    101, 103, 107, 109, 113, 127, 131, 137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193,
+    197, 199, 211, 223, 227, 229, 233, 239, 241, 251,
+];
+
+/// The number of Miller-Rabin rounds. Each round uses one of the small primes as witness.
+const MILLER_RABIN_ROUNDS: usize = 40;
+
+/// The Miller-Rabin probabilistic primality test with a fixed set of witnesses, preceded by trial division. The
+/// witnesses are the first `MILLER_RABIN_ROUNDS` primes, so composites specifically constructed to pass the test
+/// for these witnesses are declared prime. It is meant for testing random candidates, e.g. to generate primes, not
+/// for numbers chosen by an adversary.
+pub struct MillerRabin;
+
+impl PrimeTest<BigUint> for MillerRabin {
+    fn is_prime(number: &BigUint) -> bool {
+        if let Some(small) = number.to_u32() {
+            if small < 2 {
+                return false;
+            }
+            if SMALL_PRIMES.contains(&small) {
+                return true;
+            }
+        }
+
+        if SMALL_PRIMES.iter().any(|prime| (number % *prime).is_zero()) {
+            return false;
+        }
+
+        // write number - 1 = 2^s * d with odd d
+        let number_minus_one = number - 1u32;
+        let s = (0..).find(|index| bit(&number_minus_one, *index)).unwrap();
+        let d = &number_minus_one >> s;
+
+        SMALL_PRIMES[..MILLER_RABIN_ROUNDS].iter().all(|witness| {
+            let mut x = BigUint::from(*witness).modpow(&d, number);
+            if x.is_one() || x == number_minus_one {
+                return true;
+            }
+
+            for _ in 1..s {
+                x = &x * &x % number;
+                if x == number_minus_one {
+                    return true;
+                }
+            }
+
+            false
+        })
+    }
+}
+
+impl PrimeGenerator for MillerRabin {
+    /// Generate a random prime of exactly `bit_size` bits, whose two most significant bits are set. Thus, the
+    /// product of two such primes has exactly twice as many bits.
+    ///
+    /// # Panics
+    /// If `bit_size` is less than 3.
+    fn generate_random_prime<R>(rng: &mut R, bit_size: usize) -> BigUint
+    where
+        R: RngCore + CryptoRng,
+    {
+        assert!(
+            bit_size >= 3,
+            "primes with the two top bits set have at least 3 bits"
+        );
+        let top_bits = BigUint::from(3u32) << (bit_size - 2);
+
+        loop {
+            let candidate = rng.gen_biguint(bit_size) | &top_bits | BigUint::one();
+            if Self::is_prime(&candidate) {
+                return candidate;
+            }
+        }
+    }
+}
+
+/// Whether the bit with index `bit` of `number` is set.
+fn bit(number: &BigUint, bit: usize) -> bool {
+    (number >> bit).is_odd()
+}
+
+#[cfg(test)]
+mod tests {
+    use num::Num;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn test_small_numbers() {
+        let primes = (0u32..1000)
+            .filter(|n| MillerRabin::is_prime(&BigUint::from(*n)))
+            .collect::<Vec<_>>();
+
+        assert_eq!(primes.len(), 168);
+        assert_eq!(&primes[..5], &[2, 3, 5, 7, 11]);
+        assert_eq!(primes[167], 997);
+    }
+
+    #[test]
+    fn test_large_numbers() {
+        let mersenne127 = (BigUint::one() << 127) - 1u32;
+        assert!(MillerRabin::is_prime(&mersenne127));
+        assert!(!MillerRabin::is_prime(&((BigUint::one() << 128) - 1u32)));
+        assert!(!MillerRabin::is_prime(&(&mersenne127 * &mersenne127)));
+
+        // the Carmichael number 561 and a strong pseudoprime to all bases up to 23 without small factors
+        assert!(!MillerRabin::is_prime(&BigUint::from(561u32)));
+        assert!(!MillerRabin::is_prime(
+            &BigUint::from_str_radix("3825123056546413051", 10).unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_generate_prime() {
+        let mut rng = StdRng::seed_from_u64(0x5EED);
+        for bit_size in [3, 16, 256].iter() {
+            let prime = MillerRabin::generate_random_prime(&mut rng, *bit_size);
+            assert_eq!(prime.bits(), *bit_size);
+            assert!(bit(&prime, bit_size - 2));
+            assert!(MillerRabin::is_prime(&prime));
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! Helpers for passing cryptographically secure random number generators as trait objects.
+
+use rand::{CryptoRng, Error, RngCore};
+
+/// A random number generator that is cryptographically secure. Unlike `RngCore + CryptoRng`, this trait can be used
+/// as a trait object, so `&mut dyn CryptoRngCore` keeps the guarantee of `CryptoRng`.
+pub trait CryptoRngCore: RngCore + CryptoRng {}
+
+impl<R> CryptoRngCore for R where R: RngCore + CryptoRng {}
+
+/// A sized wrapper around a `&mut dyn CryptoRngCore`, for APIs that require a sized `RngCore + CryptoRng`.
+pub struct DynCryptoRng<'a> {
+    inner: &'a mut dyn CryptoRngCore,
+}
+
+impl<'a> DynCryptoRng<'a> {
+    pub fn new(inner: &'a mut dyn CryptoRngCore) -> Self {
+        DynCryptoRng { inner }
+    }
+}
+
+impl RngCore for DynCryptoRng<'_> {
+    fn next_u32(&mut self) -> u32 {
+        self.inner.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.inner.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.inner.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.inner.try_fill_bytes(dest)
+    }
+}
+
+impl CryptoRng for DynCryptoRng<'_> {}
@@ -0,0 +1,127 @@
+//! Test helpers for types implementing the traits of this crate. They are used by the tests of this crate and are
+//! exposed with the `testing` feature, so types generated by `prime_fields!` in other crates can be checked, too.
+
+use num::BigUint;
+use rand::{CryptoRng, RngCore};
+
+use crate::prime::PrimeField;
+
+/// Check the field axioms and the consistency of the conversions of the prime field `T` for `iterations` random
+/// triples of field elements.
+///
+/// # Panics
+/// If any axiom does not hold, naming the axiom and the elements violating it.
+pub fn assert_field_axioms<T, R>(rng: &mut R, iterations: usize)
+where
+    T: PrimeField,
+    R: RngCore + CryptoRng,
+{
+    assert_conversions::<T>();
+
+    for _ in 0..iterations {
+        let a = T::generate_random_member(rng);
+        let b = T::generate_random_member(rng);
+        let c = T::generate_random_member(rng);
+
+        assert_eq!(a.clone() + b.clone(), b.clone() + a.clone(), "addition is not commutative");
+        assert_eq!(a.clone() * b.clone(), b.clone() * a.clone(), "multiplication is not commutative");
+        assert_eq!(
+            (a.clone() + b.clone()) + c.clone(),
+            a.clone() + (b.clone() + c.clone()),
+            "addition is not associative"
+        );
+        assert_eq!(
+            (a.clone() * b.clone()) * c.clone(),
+            a.clone() * (b.clone() * c.clone()),
+            "multiplication is not associative"
+        );
+        assert_eq!(
+            a.clone() * (b.clone() + c.clone()),
+            a.clone() * b.clone() + a.clone() * c.clone(),
+            "multiplication does not distribute over addition"
+        );
+
+        assert_eq!(a.clone() + T::zero(), a, "zero is not the additive identity");
+        assert_eq!(a.clone() * T::one(), a, "one is not the multiplicative identity");
+        assert!((a.clone() * T::zero()).is_zero(), "zero is not absorbing");
+        assert!((a.clone() - a.clone()).is_zero(), "{:?} - {:?} is not zero", a, a);
+        assert!((a.clone() + (T::zero() - a.clone())).is_zero(), "{:?} has no additive inverse", a);
+        if !a.is_zero() {
+            assert!((a.clone() * a.inverse()).is_one(), "{:?} has no multiplicative inverse", a);
+        }
+
+        assert_eq!(
+            a.clone() - b.clone(),
+            a.clone() + (T::field_prime() - b.clone()),
+            "{:?} - {:?} is not {:?} + (p - {:?})",
+            a, b, a, b
+        );
+
+        let elements = [a.clone(), b.clone(), c.clone()];
+        assert_eq!(
+            elements.iter().cloned().sum::<T>(),
+            elements.iter().cloned().fold(T::zero(), |sum, x| sum + x),
+            "the sum is not consistent with addition"
+        );
+        assert_eq!(
+            elements.iter().cloned().product::<T>(),
+            elements.iter().cloned().fold(T::one(), |product, x| product * x),
+            "the product is not consistent with multiplication"
+        );
+
+        // conversions of unreduced integers reduce them into the field
+        let prime = T::field_prime().as_uint();
+        let multiple = BigUint::from(rng.next_u32()) * &prime;
+        assert!(a.as_uint() < prime, "{:?} is not reduced", a);
+        assert_eq!(T::from(a.as_uint() + &multiple), a, "conversion from BigUint does not reduce");
+        for radix in [2, 10, 16, 36].iter() {
+            let digits = a.as_uint().to_str_radix(*radix);
+            assert_eq!(
+                T::from_str_radix(&digits, *radix).ok(),
+                Some(a.clone()),
+                "{} does not parse in radix {}",
+                digits,
+                radix
+            );
+
+            let unreduced = (a.as_uint() + &multiple).to_str_radix(*radix);
+            assert_eq!(
+                T::from_str_radix(&unreduced, *radix).ok(),
+                Some(a.clone()),
+                "parsing does not reduce {}",
+                unreduced
+            );
+        }
+    }
+}
+
+/// Check the conversions from primitive integers at the boundaries of their ranges.
+fn assert_conversions<T>()
+where
+    T: PrimeField,
+{
+    let reduce = |n: u64| T::from(BigUint::from(n));
+
+    assert!(T::sum(std::iter::empty()).is_zero(), "the empty sum is not zero");
+    assert!(T::product(std::iter::empty()).is_one(), "the empty product is not one");
+    assert!((T::field_prime() + T::zero()).is_zero(), "the prime is not reduced to zero");
+
+    for n in [0, 1, 2, 1 << 32, u64::MAX].iter() {
+        assert_eq!(T::from_u64(*n).unwrap(), reduce(*n), "conversion of {} does not reduce", n);
+    }
+    for n in [1, 2, i64::MAX].iter() {
+        assert_eq!(T::from_i64(*n).unwrap(), reduce(*n as u64), "conversion of {} does not reduce", n);
+        assert_eq!(
+            T::from_i64(-n).unwrap(),
+            T::zero() - reduce(*n as u64),
+            "conversion of {} is not the additive inverse",
+            -n
+        );
+    }
+    assert_eq!(
+        T::from_i64(i64::MIN).unwrap(),
+        T::zero() - reduce(1 << 63),
+        "conversion of {} is not the additive inverse",
+        i64::MIN
+    );
+}
@@ -0,0 +1,226 @@
+//! Split a passphrase into shares and reconstruct it from a subset of them.
+//!
+//! ```text
+//! cargo run --example secret_sharing -- [--shares N] [--threshold K] PASSPHRASE
+//! ```
+//!
+//! The passphrase is shared with `ChunkedSecretSharing`, so it may be longer than a field element. Every share is
+//! printed as hex, which is what a shareholder would store. The passphrase is then recovered from the last
+//! `threshold` shares, and the recovery from one share less fails the integrity check.
+
+use std::process;
+
+use jester_maths::prime::Mersenne127;
+use jester_sharing::chunked_secret_sharing::{ChunkedSecretSharing, ChunkedShare};
+use jester_sharing::shamir_secret_sharing::ShamirSecretSharing;
+use jester_sharing::FieldRng;
+use rand::thread_rng;
+
+type PassphraseSharing = ChunkedSecretSharing<Mersenne127, ShamirSecretSharing>;
+
+type PassphraseShare = ChunkedShare<(usize, Mersenne127)>;
+
+const USAGE: &str = "usage: secret_sharing [--shares N] [--threshold K] PASSPHRASE";
+
+/// The parsed command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Arguments {
+    shares: usize,
+    threshold: usize,
+    passphrase: String,
+}
+
+/// Parse the command line arguments, excluding the program name.
+///
+/// # Returns
+/// The arguments or a message describing the first invalid argument
+fn parse_arguments<I>(arguments: I) -> Result<Arguments, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut shares = 5;
+    let mut threshold = 3;
+    let mut passphrase = None;
+
+    let mut arguments = arguments.into_iter();
+    while let Some(argument) = arguments.next() {
+        match argument.as_str() {
+            "-n" | "--shares" | "-k" | "--threshold" => {
+                let value = arguments
+                    .next()
+                    .and_then(|value| value.parse::<usize>().ok())
+                    .ok_or_else(|| format!("{} requires a number", argument))?;
+                if argument.ends_with("shares") || argument == "-n" {
+                    shares = value;
+                } else {
+                    threshold = value;
+                }
+            }
+            option if option.starts_with('-') => {
+                return Err(format!("unknown option \"{}\"", option))
+            }
+            _ if passphrase.is_some() => {
+                return Err(format!("unexpected argument \"{}\"", argument))
+            }
+            _ => passphrase = Some(argument),
+        }
+    }
+
+    if threshold < 2 || threshold > shares {
+        return Err(format!(
+            "the threshold must be within 2..={}, but is {}",
+            shares, threshold
+        ));
+    }
+
+    Ok(Arguments {
+        shares,
+        threshold,
+        passphrase: passphrase.ok_or("the passphrase is missing")?,
+    })
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Split the passphrase into `count` shares, of which `threshold` are required to recover it.
+///
+/// # Returns
+/// The shares encoded as hex
+fn split_passphrase<R>(rng: &mut R, passphrase: &str, count: usize, threshold: usize) -> Vec<String>
+where
+    R: FieldRng,
+{
+    PassphraseSharing::share_bytes(rng, passphrase.as_bytes(), count, threshold)
+        .iter()
+        .map(|share: &PassphraseShare| to_hex(&share.to_bytes()))
+        .collect()
+}
+
+/// Recover the passphrase from shares created by `split_passphrase`. All given shares are used, so at least the
+/// original threshold of shares must be given.
+///
+/// # Returns
+/// The passphrase, or a description of why it could not be recovered
+fn recover_passphrase(shares: &[String]) -> Result<String, String> {
+    let shares = shares
+        .iter()
+        .map(|share| from_hex(share).and_then(|bytes| PassphraseShare::from_bytes(&bytes)))
+        .collect::<Option<Vec<_>>>()
+        .ok_or("a share is malformed")?;
+
+    let passphrase = PassphraseSharing::reconstruct_bytes(&shares, shares.len())
+        .map_err(|exception| format!("the shares do not recover a passphrase: {:?}", exception))?;
+    String::from_utf8(passphrase).map_err(|_| "the passphrase is no valid UTF-8".to_string())
+}
+
+fn main() {
+    let arguments = parse_arguments(std::env::args().skip(1)).unwrap_or_else(|message| {
+        eprintln!("{}\n{}", message, USAGE);
+        process::exit(2)
+    });
+
+    let shares = split_passphrase(
+        &mut thread_rng(),
+        &arguments.passphrase,
+        arguments.shares,
+        arguments.threshold,
+    );
+    for (index, share) in shares.iter().enumerate() {
+        println!("share {}: {}", index + 1, share);
+    }
+    println!();
+
+    let subset = &shares[shares.len() - arguments.threshold..];
+    match recover_passphrase(subset) {
+        Ok(passphrase) => println!("recovered from {} shares: {}", subset.len(), passphrase),
+        Err(reason) => println!("recovery from {} shares failed: {}", subset.len(), reason),
+    }
+
+    let too_few = &subset[1..];
+    match recover_passphrase(too_few) {
+        Ok(passphrase) => println!("recovered from {} shares: {}", too_few.len(), passphrase),
+        Err(reason) => println!("recovery from {} shares failed: {}", too_few.len(), reason),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn arguments(line: &str) -> Result<Arguments, String> {
+        parse_arguments(line.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn test_parse_arguments() {
+        assert_eq!(
+            arguments("hunter2"),
+            Ok(Arguments {
+                shares: 5,
+                threshold: 3,
+                passphrase: "hunter2".to_string()
+            })
+        );
+        assert_eq!(
+            arguments("--shares 7 -k 4 hunter2"),
+            Ok(Arguments {
+                shares: 7,
+                threshold: 4,
+                passphrase: "hunter2".to_string()
+            })
+        );
+
+        assert!(arguments("").is_err());
+        assert!(arguments("--shares many hunter2").is_err());
+        assert!(arguments("-n 3 -k 4 hunter2").is_err());
+        assert!(arguments("-k 1 hunter2").is_err());
+        assert!(arguments("hunter2 hunter3").is_err());
+    }
+
+    #[test]
+    fn test_recover_from_any_subset() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let passphrase =
+            "correct horse battery staple, but much longer than a single field element";
+        let shares = split_passphrase(&mut rng, passphrase, 5, 3);
+        assert_eq!(shares.len(), 5);
+
+        for subset in &[[0, 1, 2], [1, 3, 4], [4, 0, 2]] {
+            let subset = subset
+                .iter()
+                .map(|i| shares[*i].clone())
+                .collect::<Vec<_>>();
+            assert_eq!(recover_passphrase(&subset), Ok(passphrase.to_string()));
+        }
+        assert_eq!(recover_passphrase(&shares), Ok(passphrase.to_string()));
+    }
+
+    #[test]
+    fn test_recover_failures() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let shares = split_passphrase(&mut rng, "hunter2", 5, 3);
+
+        assert!(recover_passphrase(&shares[..2]).is_err());
+        assert!(
+            recover_passphrase(&[shares[0].clone(), shares[1].clone(), "0g".to_string()]).is_err()
+        );
+        assert!(
+            recover_passphrase(&[shares[0].clone(), shares[1].clone(), shares[1].clone()]).is_err()
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! Limits on the number of operations a scheme runs concurrently. Unbounded schemes start one operation per element
+//! of their input, e.g. one `distribute_secret` per helper of an unbounded inversion. Over a real network, thousands
+//! of simultaneous operations flood the transport, so protocols can limit how many of them are polled at once.
+
+use futures::future::join_all;
+use futures::{Future, StreamExt};
+
+/// How many operations of a scheme may be in flight at the same time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProtocolConcurrency {
+    /// All operations are polled at once
+    #[default]
+    Unlimited,
+
+    /// At most the given number of operations are polled at once. Further operations are polled as soon as earlier
+    /// ones complete.
+    Limited(usize),
+}
+
+/// Configuration of a protocol that is shared by all schemes it is used with.
+pub trait ProtocolConfig {
+    /// The number of operations a scheme may run concurrently within this protocol. The default implementation does
+    /// not limit concurrency.
+    fn concurrency(&self) -> ProtocolConcurrency {
+        ProtocolConcurrency::Unlimited
+    }
+}
+
+/// Await all `futures` while polling at most as many of them at once as `concurrency` permits. The futures are
+/// polled in order, so if every participant of a protocol creates the futures of the same operations in the same
+/// order, all participants work on the same window of operations and none of them waits for an operation another
+/// participant did not start.
+///
+/// # Returns
+/// The outputs of the futures in the order of `futures`
+///
+/// # Panics
+/// If `concurrency` is `ProtocolConcurrency::Limited(0)`.
+pub async fn join_limited<F>(concurrency: ProtocolConcurrency, futures: Vec<F>) -> Vec<F::Output>
+where
+    F: Future,
+{
+    match concurrency {
+        ProtocolConcurrency::Unlimited => join_all(futures).await,
+        ProtocolConcurrency::Limited(limit) => {
+            assert!(limit > 0, "at least one operation must be polled at once");
+            futures::stream::iter(futures)
+                .buffered(limit)
+                .collect()
+                .await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// A future that counts how many futures of its kind are polled concurrently
+    async fn tracked(value: usize, active: &AtomicUsize, peak: &AtomicUsize) -> usize {
+        let now_active = active.fetch_add(1, Ordering::SeqCst) + 1;
+        peak.fetch_max(now_active, Ordering::SeqCst);
+        // yield once, so the other futures of the window are polled before this one completes
+        let mut yielded = false;
+        futures::future::poll_fn(|cx| {
+            if yielded {
+                std::task::Poll::Ready(())
+            } else {
+                yielded = true;
+                cx.waker().wake_by_ref();
+                std::task::Poll::Pending
+            }
+        })
+        .await;
+        active.fetch_sub(1, Ordering::SeqCst);
+        value
+    }
+
+    #[test]
+    fn test_join_limited() {
+        for (concurrency, expected_peak) in &[
+            (ProtocolConcurrency::Unlimited, 20),
+            (ProtocolConcurrency::Limited(3), 3),
+            (ProtocolConcurrency::Limited(1), 1),
+        ] {
+            let (active, peak) = (AtomicUsize::new(0), AtomicUsize::new(0));
+            let futures = (0..20)
+                .map(|value| tracked(value, &active, &peak))
+                .collect();
+
+            let outputs = block_on(join_limited(*concurrency, futures));
+            assert_eq!(outputs, (0..20).collect::<Vec<_>>());
+            assert_eq!(peak.load(Ordering::SeqCst), *expected_peak);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_join_limited_zero() {
+        block_on(join_limited(
+            ProtocolConcurrency::Limited(0),
+            vec![async { 1 }],
+        ));
+    }
+}
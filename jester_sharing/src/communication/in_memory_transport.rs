@@ -0,0 +1,121 @@
+//! An in-memory implementation of `ShareTransport` that connects participants living in the same process. It is
+//! useful for testing protocols without setting up an actual network.
+
+use crate::ShareTransport;
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use futures::lock::Mutex;
+use futures::{Future, StreamExt};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Counts the messages of an in-memory network that were sent but not yet received, so tests can observe how much
+/// a protocol floods its transport.
+#[derive(Debug, Default)]
+pub struct MessageGauge {
+    in_flight: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl MessageGauge {
+    /// The number of messages that are currently in flight
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// The largest number of messages that were in flight at the same time
+    pub fn peak(&self) -> usize {
+        self.peak.load(Ordering::SeqCst)
+    }
+
+    /// Reset the peak to the number of messages currently in flight, to observe the next computation only.
+    pub fn reset_peak(&self) {
+        self.peak.store(self.in_flight(), Ordering::SeqCst);
+    }
+
+    fn on_send(&self) {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.peak.fetch_max(in_flight, Ordering::SeqCst);
+    }
+
+    fn on_receive(&self) {
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// One endpoint of a fully connected in-memory network. Messages are delivered through unbounded channels, so sending
+/// never blocks.
+pub struct InMemoryTransport {
+    participant_id: usize,
+    senders: Vec<UnboundedSender<(usize, Vec<u8>)>>,
+    receiver: Mutex<UnboundedReceiver<(usize, Vec<u8>)>>,
+    gauge: Arc<MessageGauge>,
+}
+
+impl InMemoryTransport {
+    /// Create a fully connected network of `participant_count` endpoints. The endpoint at index `i` of the returned
+    /// vector belongs to the participant with id `i + 1`.
+    pub fn create_network(participant_count: usize) -> Vec<Self> {
+        let (senders, receivers): (Vec<_>, Vec<_>) =
+            (0..participant_count).map(|_| unbounded()).unzip();
+        let gauge = Arc::new(MessageGauge::default());
+
+        receivers
+            .into_iter()
+            .enumerate()
+            .map(|(index, receiver)| InMemoryTransport {
+                participant_id: index + 1,
+                senders: senders.clone(),
+                receiver: Mutex::new(receiver),
+                gauge: gauge.clone(),
+            })
+            .collect()
+    }
+
+    /// The id of the participant this endpoint belongs to.
+    pub fn participant_id(&self) -> usize {
+        self.participant_id
+    }
+
+    /// The gauge of the messages in flight, which is shared by all endpoints of the network.
+    pub fn gauge(&self) -> Arc<MessageGauge> {
+        self.gauge.clone()
+    }
+}
+
+impl ShareTransport for InMemoryTransport {
+    fn send_to<'a>(
+        &'a self,
+        participant: usize,
+        message: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+        Box::pin(async move {
+            self.gauge.on_send();
+            self.senders[participant - 1]
+                .unbounded_send((self.participant_id, message))
+                .expect("receiving participant disconnected")
+        })
+    }
+
+    fn broadcast<'a>(&'a self, message: Vec<u8>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+        Box::pin(async move {
+            for participant in (1..=self.senders.len()).filter(|id| *id != self.participant_id) {
+                self.send_to(participant, message.clone()).await
+            }
+        })
+    }
+
+    fn receive<'a>(&'a self) -> Pin<Box<dyn Future<Output = (usize, Vec<u8>)> + Send + 'a>> {
+        Box::pin(async move {
+            let message = self
+                .receiver
+                .lock()
+                .await
+                .next()
+                .await
+                .expect("all participants disconnected");
+            self.gauge.on_receive();
+            message
+        })
+    }
+}
@@ -0,0 +1,191 @@
+//! Reconstruction of a revealed secret while its shares arrive one at a time. The secret is available as soon as
+//! `threshold` shares were received, and every later share is checked against the polynomial interpolated from the
+//! first `threshold` shares.
+
+use crate::shamir_secret_sharing::LagrangeBasis;
+use crate::PrimeField;
+
+/// The state of an `IncrementalReveal` after a share was added.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RevealProgress<T> {
+    /// The given number of additional shares is required to reconstruct the secret.
+    NeedMore(usize),
+
+    /// The threshold was met by the added share and the secret was reconstructed. This is reported only once.
+    Ready(T),
+
+    /// The share was added after the secret was reconstructed and it lies on the interpolated polynomial.
+    Consistent,
+
+    /// The share with the given index does not lie on the polynomial interpolated from the other shares, or it
+    /// cannot be a share at all, because its index is used twice or is a multiple of the field's prime. The share
+    /// is discarded.
+    Inconsistent { index: usize },
+}
+
+/// A state machine collecting the Shamir shares of one revealed secret in the order they arrive.
+///
+/// # Type Parameters
+/// - `T` the prime field the secret is shared in
+#[derive(Debug, Clone)]
+pub struct IncrementalReveal<T>
+where
+    T: PrimeField,
+{
+    threshold: usize,
+    shares: Vec<(usize, T)>,
+    secret: Option<T>,
+}
+
+impl<T> IncrementalReveal<T>
+where
+    T: PrimeField,
+{
+    /// Create a new reveal of a secret that was shared with the given reconstruction `threshold`.
+    ///
+    /// # Panics
+    /// If `threshold` is zero.
+    pub fn new(threshold: usize) -> Self {
+        assert!(
+            threshold > 0,
+            "the reconstruction threshold must not be zero"
+        );
+
+        IncrementalReveal {
+            threshold,
+            shares: Vec::with_capacity(threshold),
+            secret: None,
+        }
+    }
+
+    /// The reconstructed secret, if enough shares were added.
+    pub fn secret(&self) -> Option<&T> {
+        self.secret.as_ref()
+    }
+
+    /// Add the next share. Until the threshold is met, shares cannot be verified, so the secret is reconstructed
+    /// from the first `threshold` shares and all later shares are checked against them.
+    pub fn add_share(&mut self, share: (usize, T)) -> RevealProgress<T> {
+        let (index, value) = share;
+
+        let x = T::from_usize(index).unwrap();
+        if x.is_zero()
+            || self
+                .shares
+                .iter()
+                .any(|(other, _)| T::from_usize(*other).unwrap() == x)
+        {
+            return RevealProgress::Inconsistent { index };
+        }
+
+        if self.secret.is_some() {
+            let interpolation_shares = &self.shares[..self.threshold];
+            let indices = interpolation_shares
+                .iter()
+                .map(|(index, _)| *index)
+                .collect::<Vec<_>>();
+            let expected = LagrangeBasis::new_at(&indices, index)
+                .and_then(|basis| basis.interpolate(interpolation_shares))
+                .expect("interpolation shares were validated before");
+
+            return if expected == value {
+                self.shares.push((index, value));
+                RevealProgress::Consistent
+            } else {
+                RevealProgress::Inconsistent { index }
+            };
+        }
+
+        self.shares.push((index, value));
+        if self.shares.len() < self.threshold {
+            return RevealProgress::NeedMore(self.threshold - self.shares.len());
+        }
+
+        let indices = self
+            .shares
+            .iter()
+            .map(|(index, _)| *index)
+            .collect::<Vec<_>>();
+        let secret = LagrangeBasis::new(&indices)
+            .and_then(|basis| basis.interpolate(&self.shares))
+            .expect("share indices were validated before");
+
+        self.secret = Some(secret.clone());
+        RevealProgress::Ready(secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jester_maths::prime::Mersenne61;
+    use num::{FromPrimitive, One, Zero};
+
+    /// Shares of the secret 42 with threshold 3, evaluated from `f(x) = 42 + 5x + 7x^2`.
+    fn share(index: usize) -> (usize, Mersenne61) {
+        let x = Mersenne61::from_usize(index).unwrap();
+        let coefficients = [42, 5, 7].iter().map(|c| Mersenne61::from_u64(*c).unwrap());
+        let value = coefficients
+            .rev()
+            .fold(Mersenne61::zero(), |acc, coefficient| {
+                acc * x.clone() + coefficient
+            });
+
+        (index, value)
+    }
+
+    #[test]
+    fn test_ready_at_threshold() {
+        let mut reveal = IncrementalReveal::new(3);
+
+        assert_eq!(reveal.add_share(share(4)), RevealProgress::NeedMore(2));
+        assert_eq!(reveal.add_share(share(2)), RevealProgress::NeedMore(1));
+        assert_eq!(reveal.secret(), None);
+        assert_eq!(
+            reveal.add_share(share(5)),
+            RevealProgress::Ready(Mersenne61::from_u64(42).unwrap())
+        );
+        assert_eq!(reveal.secret(), Mersenne61::from_u64(42).as_ref());
+    }
+
+    #[test]
+    fn test_consistent_extra_shares() {
+        let mut reveal = IncrementalReveal::new(3);
+        for index in 1..=3 {
+            reveal.add_share(share(index));
+        }
+
+        for index in 4..=6 {
+            assert_eq!(reveal.add_share(share(index)), RevealProgress::Consistent);
+        }
+    }
+
+    #[test]
+    fn test_corrupted_late_share() {
+        let mut reveal = IncrementalReveal::new(3);
+        for index in 1..=3 {
+            reveal.add_share(share(index));
+        }
+
+        let (index, value) = share(4);
+        assert_eq!(
+            reveal.add_share((index, value + Mersenne61::one())),
+            RevealProgress::Inconsistent { index: 4 }
+        );
+
+        // the corrupted share is discarded, so the correct one is still accepted
+        assert_eq!(reveal.add_share(share(4)), RevealProgress::Consistent);
+    }
+
+    #[test]
+    fn test_duplicate_index() {
+        let mut reveal = IncrementalReveal::new(3);
+
+        assert_eq!(reveal.add_share(share(1)), RevealProgress::NeedMore(2));
+        assert_eq!(
+            reveal.add_share(share(1)),
+            RevealProgress::Inconsistent { index: 1 }
+        );
+        assert_eq!(reveal.add_share(share(2)), RevealProgress::NeedMore(1));
+    }
+}
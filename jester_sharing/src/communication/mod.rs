@@ -1,15 +1,50 @@
 //! This module defines traits modeling client communication during protocol evaluation. Different protocols require
-//! different models of communication. This module does not provide implementations, as network implementation is out
-//! of this crate's scope.
+//! different models of communication. This module does not provide network implementations, as those are out of this
+//! crate's scope. However, it provides a generic protocol that implements the communication schemes on top of any
+//! `ShareTransport`, so only the exchange of raw bytes must be implemented.
 
 use crate::ThresholdSecretSharingScheme;
 use futures::Future;
 use std::pin::Pin;
 
+pub use concurrency::{join_limited, ProtocolConcurrency, ProtocolConfig};
+pub use incremental_reveal::{IncrementalReveal, RevealProgress};
+pub use rounds::{
+    FinishedRounds, OpeningRound, RoundException, RoundParticipants, RoundTransition,
+};
+
+pub mod concurrency;
+pub mod in_memory_transport;
+pub mod incremental_reveal;
+pub mod rounds;
+pub mod shamir_clique_protocol;
+
+/// An opaque identifier of one logical value communicated in a `CliqueCommunicationScheme`. When multiple
+/// sub-protocols communicate concurrently, all messages of one reveal or distribution carry the same tag, so a
+/// backend can tell which logical value a received share belongs to. Tags are obtained via `new_session_tag`, thus
+/// all participants must request tags in the same order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct SessionTag(u64);
+
+impl SessionTag {
+    /// Create a tag from its numeric representation, e.g. when decoding a received message.
+    pub fn from_u64(value: u64) -> Self {
+        SessionTag(value)
+    }
+
+    /// The numeric representation of this tag, e.g. for encoding it into a message.
+    pub fn to_u64(self) -> u64 {
+        self.0
+    }
+}
+
 /// A trait marking a scheme where `N` party members communicate to each other via a broadcast or a peer to peer network
 /// thus every client knows every other client. Secrets can be revealed by sending the own share to all participants
-/// and new secrets can be distributed by sending one share of it to all members
-pub trait CliqueCommunicationScheme<T, S>: ThresholdSecretSharingScheme<T, S> {
+/// and new secrets can be distributed by sending one share of it to all members. Unbounded schemes consult the
+/// `ProtocolConfig` of the protocol for how many of their operations they may run concurrently.
+pub trait CliqueCommunicationScheme<T, S>:
+    ThresholdSecretSharingScheme<T, S> + ProtocolConfig
+{
     /// All parties reveal their shares of a secret so it can be reconstructed as soon as all shares were
     /// received.
     ///
@@ -26,4 +61,99 @@ pub trait CliqueCommunicationScheme<T, S>: ThresholdSecretSharingScheme<T, S> {
     /// # Returns
     /// Returns a future on the shares that other participants sent in return
     fn distribute_secret(&mut self, secret: T) -> Pin<Box<dyn Future<Output = Vec<S>> + Send>>;
+
+    /// Obtain the tag for the next logical value revealed or distributed by this participant. The default
+    /// implementation returns the default tag for backends that do not correlate messages.
+    fn new_session_tag(&mut self) -> SessionTag {
+        SessionTag::default()
+    }
+
+    /// Like `reveal_shares`, but all messages of this reveal are correlated by the given `tag`. The default
+    /// implementation ignores the tag.
+    fn reveal_shares_tagged(
+        &mut self,
+        _tag: SessionTag,
+        share: S,
+    ) -> Pin<Box<dyn Future<Output = T> + Send>> {
+        self.reveal_shares(share)
+    }
+
+    /// Like `distribute_secret`, but all messages of this distribution are correlated by the given `tag`. The
+    /// default implementation ignores the tag.
+    fn distribute_secret_tagged(
+        &mut self,
+        _tag: SessionTag,
+        secret: T,
+    ) -> Pin<Box<dyn Future<Output = Vec<S>> + Send>> {
+        self.distribute_secret(secret)
+    }
+}
+
+/// A trait marking a communication backend that can exchange raw messages besides shares. Unlike shares, raw messages
+/// are not interpreted by the protocol, so schemes can exchange auxiliary data such as commitments. Backends are not
+/// required to support raw messages, thus schemes that exchange them require this trait in addition to the
+/// `CliqueCommunicationScheme`.
+pub trait RawCommunicationScheme {
+    /// Send the raw `message` to all other participants, correlated by the given `tag`.
+    fn broadcast_bytes(
+        &mut self,
+        tag: SessionTag,
+        message: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+
+    /// Send the raw `message` to the participant with the id `participant` only, correlated by the given `tag`.
+    fn send_bytes(
+        &mut self,
+        tag: SessionTag,
+        participant: usize,
+        message: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+
+    /// Collect one raw message correlated by `tag` from every other participant, whether it was broadcast or sent
+    /// to this participant only.
+    ///
+    /// # Returns
+    /// Returns a future on tuples of the senders' ids and their messages, ordered by the ids
+    #[allow(clippy::type_complexity)]
+    fn collect_bytes(
+        &mut self,
+        tag: SessionTag,
+    ) -> Pin<Box<dyn Future<Output = Vec<(usize, Vec<u8>)>> + Send>>;
+}
+
+/// Exceptions that can arise when another participant deviates from a protocol, or when a scheme is invoked with
+/// inputs it cannot operate on. Deviations identify the deviating participant, so it can be excluded from further
+/// computations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProtocolException {
+    /// The participant opened a value that does not match the commitment it sent before
+    CommitmentMismatch { participant: usize },
+
+    /// The participant sent a message that cannot be decoded
+    MalformedMessage { participant: usize },
+
+    /// The scheme cannot operate on the given inputs. All participants reject the same inputs, so no participant
+    /// deviated from the protocol.
+    InvalidInput { reason: &'static str },
+}
+
+/// A transport layer that exchanges raw messages between the `N` participants of a protocol. Participants are
+/// identified by their ids `1..=N`. The transport must deliver all messages reliably, but messages need not be
+/// delivered in the order they were sent.
+pub trait ShareTransport {
+    /// Send a `message` to the participant with the id `participant`.
+    fn send_to<'a>(
+        &'a self,
+        participant: usize,
+        message: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+
+    /// Send a `message` to all participants except the sending one.
+    fn broadcast<'a>(&'a self, message: Vec<u8>) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+
+    /// Receive the next message sent to this participant by any other participant.
+    ///
+    /// # Returns
+    /// Returns a future on a tuple of the sender's id and the message
+    fn receive<'a>(&'a self) -> Pin<Box<dyn Future<Output = (usize, Vec<u8>)> + Send + 'a>>;
 }
@@ -0,0 +1,362 @@
+//! Building blocks for protocols that are driven round by round instead of through a `CliqueCommunicationScheme`.
+//! A round is a state machine that tells which messages to send and consumes the messages received from the other
+//! participants, without performing any I/O itself. This suits transports that are strictly request/response,
+//! where each round is one exchange. All participants must be in the same round, so a driver has to deliver the
+//! messages of one round before the messages of the next one, but within a round messages may arrive in any order.
+
+use std::convert::TryInto;
+use std::marker::PhantomData;
+use std::mem::size_of;
+
+use crate::{IndexedShare, PrimeField, ThresholdSecretSharingScheme};
+
+/// The participants of a protocol driven in rounds. Participants are identified by their ids `1..=count`, and the
+/// participant with id `i` holds the shares with index `i`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundParticipants {
+    participant_id: usize,
+    participant_count: usize,
+    threshold: usize,
+}
+
+impl RoundParticipants {
+    /// Describe the participant `participant_id` of `participant_count` participants, that share secrets with the
+    /// given reconstruction `threshold`.
+    ///
+    /// # Panics
+    /// If `participant_id` is not within `1..=participant_count` or `threshold` is not within
+    /// `1..=participant_count`.
+    pub fn new(participant_id: usize, participant_count: usize, threshold: usize) -> Self {
+        assert!(
+            (1..=participant_count).contains(&participant_id),
+            "participant id {} is not within 1..={}",
+            participant_id,
+            participant_count
+        );
+        assert!(
+            (1..=participant_count).contains(&threshold),
+            "threshold {} is not within 1..={}",
+            threshold,
+            participant_count
+        );
+
+        RoundParticipants {
+            participant_id,
+            participant_count,
+            threshold,
+        }
+    }
+
+    /// The id of this participant
+    pub fn participant_id(&self) -> usize {
+        self.participant_id
+    }
+
+    /// The number of participants including this one
+    pub fn participant_count(&self) -> usize {
+        self.participant_count
+    }
+
+    /// The reconstruction threshold of the shared secrets
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// The ids of all participants except this one
+    pub fn others(&self) -> impl Iterator<Item = usize> {
+        let participant_id = self.participant_id;
+        (1..=self.participant_count).filter(move |id| *id != participant_id)
+    }
+}
+
+/// Exceptions that can arise when a round receives a message. The message is discarded and the round stays
+/// unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RoundException {
+    /// The sender is not one of the other participants
+    UnknownParticipant { participant: usize },
+
+    /// The sender already delivered its message of this round
+    DuplicateMessage { participant: usize },
+
+    /// The message cannot be decoded, or does not contain as many field elements as expected
+    MalformedPayload { participant: usize },
+}
+
+/// The state of a protocol after a round received a message.
+#[derive(Debug)]
+pub enum RoundTransition<R, N> {
+    /// The round still waits for messages of other participants
+    Pending(R),
+
+    /// The round received all messages and the protocol advanced to the next round, or finished
+    Next(N),
+
+    /// The message was discarded, because of the given reason. The round is unchanged.
+    Rejected(R, RoundException),
+}
+
+/// The state of a protocol that completed all rounds.
+#[derive(Debug, Clone)]
+pub struct FinishedRounds<S> {
+    shares: Vec<S>,
+}
+
+impl<S> FinishedRounds<S> {
+    pub(crate) fn new(shares: Vec<S>) -> Self {
+        FinishedRounds { shares }
+    }
+
+    /// The output shares of the protocol
+    pub fn finish(self) -> Vec<S> {
+        self.shares
+    }
+}
+
+/// A round in which all participants reveal their shares of multiple secrets to each other, like
+/// `CliqueCommunicationScheme::reveal_shares` does for each of the shares.
+///
+/// # Type Parameters
+/// - `T` the secret type
+/// - `S` the share type
+/// - `P` the sharing scheme used to reconstruct the secrets
+#[derive(Debug, Clone)]
+pub struct OpeningRound<T, S, P> {
+    participants: RoundParticipants,
+    payload: Vec<u8>,
+    received: Vec<(usize, Vec<T>)>,
+    share: PhantomData<S>,
+    protocol: PhantomData<P>,
+}
+
+impl<T, S, P> OpeningRound<T, S, P>
+where
+    T: PrimeField,
+    S: IndexedShare<T>,
+    P: ThresholdSecretSharingScheme<T, S>,
+{
+    /// Start revealing the secrets of this participant's `shares`.
+    pub fn new(participants: RoundParticipants, shares: &[S]) -> Self {
+        let values = shares
+            .iter()
+            .map(|share| share.to_indexed().1)
+            .collect::<Vec<_>>();
+
+        OpeningRound {
+            participants,
+            payload: encode_field_elements(&values),
+            received: vec![(participants.participant_id(), values)],
+            share: PhantomData,
+            protocol: PhantomData,
+        }
+    }
+
+    /// The messages this participant sends in this round, addressed to the ids of their recipients.
+    pub fn messages_to_send(&self) -> Vec<(usize, Vec<u8>)> {
+        self.participants
+            .others()
+            .map(|participant| (participant, self.payload.clone()))
+            .collect()
+    }
+
+    /// Receive the message of participant `from`. As soon as the messages of all participants were received, the
+    /// secrets are reconstructed.
+    ///
+    /// # Returns
+    /// The revealed secrets in the order of the shares, once all messages were received
+    pub fn receive(mut self, from: usize, payload: &[u8]) -> RoundTransition<Self, Vec<T>> {
+        let expected_count = self.received[0].1.len();
+        let values = match receive_field_elements(&self.participants, &self.received, from, payload)
+        {
+            Ok(values) if values.len() == expected_count => values,
+            Ok(_) => {
+                return RoundTransition::Rejected(
+                    self,
+                    RoundException::MalformedPayload { participant: from },
+                )
+            }
+            Err(exception) => return RoundTransition::Rejected(self, exception),
+        };
+
+        self.received.push((from, values));
+        if self.received.len() < self.participants.participant_count() {
+            return RoundTransition::Pending(self);
+        }
+
+        let threshold = self.participants.threshold();
+        let secrets = (0..expected_count)
+            .map(|index| {
+                let shares = self
+                    .received
+                    .iter()
+                    .map(|(participant, values)| {
+                        S::from_indexed(*participant, values[index].clone())
+                    })
+                    .collect::<Vec<_>>();
+                P::reconstruct_secret(&shares, threshold)
+                    .expect("shares of distinct participants cannot be rejected")
+            })
+            .collect();
+        RoundTransition::Next(secrets)
+    }
+}
+
+/// Validate the sender of a message and decode its field elements.
+///
+/// # Returns
+/// The field elements, or a `RoundException` if the sender is not another participant or already delivered its
+/// message, or the payload is malformed.
+pub(crate) fn receive_field_elements<T, V>(
+    participants: &RoundParticipants,
+    received: &[(usize, V)],
+    from: usize,
+    payload: &[u8],
+) -> Result<Vec<T>, RoundException>
+where
+    T: PrimeField,
+{
+    if from == participants.participant_id()
+        || !(1..=participants.participant_count()).contains(&from)
+    {
+        return Err(RoundException::UnknownParticipant { participant: from });
+    }
+
+    if received.iter().any(|(participant, _)| *participant == from) {
+        return Err(RoundException::DuplicateMessage { participant: from });
+    }
+
+    decode_field_elements(payload).ok_or(RoundException::MalformedPayload { participant: from })
+}
+
+/// Encode field elements into the payload of a message. Each element is prefixed with the length of its little
+/// endian representation as a little endian `u32`.
+pub fn encode_field_elements<T>(elements: &[T]) -> Vec<u8>
+where
+    T: PrimeField,
+{
+    let mut payload = Vec::new();
+    for element in elements {
+        let bytes = element.as_bytes_le();
+        payload.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        payload.extend_from_slice(&bytes);
+    }
+    payload
+}
+
+/// Decode the field elements of a payload encoded by `encode_field_elements`.
+///
+/// # Returns
+/// The field elements, or `None` if the payload is truncated or an element exceeds the field.
+pub fn decode_field_elements<T>(mut payload: &[u8]) -> Option<Vec<T>>
+where
+    T: PrimeField,
+{
+    let mut elements = Vec::new();
+    while !payload.is_empty() {
+        if payload.len() < size_of::<u32>() {
+            return None;
+        }
+
+        let (length, rest) = payload.split_at(size_of::<u32>());
+        let length = u32::from_le_bytes(length.try_into().unwrap()) as usize;
+        if rest.len() < length {
+            return None;
+        }
+
+        let (element, rest) = rest.split_at(length);
+        elements.push(T::from_bytes_le(element)?);
+        payload = rest;
+    }
+    Some(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shamir_secret_sharing::ShamirSecretSharing;
+    use jester_maths::prime::Mersenne61;
+    use num::FromPrimitive;
+
+    fn element(value: usize) -> Mersenne61 {
+        Mersenne61::from_usize(value).unwrap()
+    }
+
+    #[test]
+    fn test_field_element_codec() {
+        let elements = vec![element(0), element(1), element(1 << 40)];
+        let payload = encode_field_elements(&elements);
+        assert_eq!(decode_field_elements(&payload), Some(elements));
+        assert_eq!(decode_field_elements::<Mersenne61>(&[]), Some(vec![]));
+
+        // truncated length prefix and element
+        assert_eq!(decode_field_elements::<Mersenne61>(&payload[..2]), None);
+        assert_eq!(
+            decode_field_elements::<Mersenne61>(&payload[..payload.len() - 1]),
+            None
+        );
+
+        // an element exceeding the field
+        let mut exceeding = 8_u32.to_le_bytes().to_vec();
+        exceeding.extend_from_slice(&[0xFF; 8]);
+        assert_eq!(decode_field_elements::<Mersenne61>(&exceeding), None);
+    }
+
+    #[test]
+    fn test_opening_round_rejects_messages() {
+        let participants = RoundParticipants::new(1, 3, 2);
+        let round = OpeningRound::<Mersenne61, _, ShamirSecretSharing>::new(
+            participants,
+            &[(1, element(5))],
+        );
+        assert_eq!(
+            round
+                .messages_to_send()
+                .iter()
+                .map(|(participant, _)| *participant)
+                .collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+
+        let payload = encode_field_elements(&[element(7)]);
+        let round = match round.receive(1, &payload) {
+            RoundTransition::Rejected(round, exception) => {
+                assert_eq!(
+                    exception,
+                    RoundException::UnknownParticipant { participant: 1 }
+                );
+                round
+            }
+            _ => panic!("a message from the receiving participant was accepted"),
+        };
+        let round = match round.receive(2, &encode_field_elements(&[element(7), element(8)])) {
+            RoundTransition::Rejected(round, exception) => {
+                assert_eq!(
+                    exception,
+                    RoundException::MalformedPayload { participant: 2 }
+                );
+                round
+            }
+            _ => panic!("a message with too many elements was accepted"),
+        };
+        let round = match round.receive(2, &payload) {
+            RoundTransition::Pending(round) => round,
+            _ => panic!("the round completed before all messages were received"),
+        };
+        let round = match round.receive(2, &payload) {
+            RoundTransition::Rejected(round, exception) => {
+                assert_eq!(
+                    exception,
+                    RoundException::DuplicateMessage { participant: 2 }
+                );
+                round
+            }
+            _ => panic!("a duplicate message was accepted"),
+        };
+
+        // the shares lie on f(x) = 3 + 2x
+        match round.receive(3, &encode_field_elements(&[element(9)])) {
+            RoundTransition::Next(secrets) => assert_eq!(secrets, vec![element(3)]),
+            _ => panic!("the round did not complete"),
+        }
+    }
+}
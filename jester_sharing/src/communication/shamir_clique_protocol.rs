@@ -0,0 +1,1949 @@
+//! A generic protocol implementing `CliqueCommunicationScheme` for Shamir's secret sharing on top of any
+//! `ShareTransport`. Implementors of actual networks only have to provide a transport for raw bytes, while the
+//! generation, exchange and reconstruction of shares is handled by this protocol.
+
+use crate::beaver_randomization_multiplication::{BeaverCommunicationScheme, BeaverTripleSource};
+use crate::participant_index_map::ParticipantIndexMap;
+use crate::shamir_secret_sharing::ShamirSecretSharingScheme;
+use crate::{
+    CliqueCommunicationScheme, IncrementalReveal, PrimeField, ProtocolConcurrency, ProtocolConfig,
+    RawCommunicationScheme, RevealProgress, SessionTag, ShareTransport,
+};
+use futures::lock::Mutex;
+use futures::Future;
+use rand::rngs::StdRng;
+use rand::FromEntropy;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryInto;
+use std::iter;
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// A participant of a Shamir secret sharing protocol where all `participant_count` participants communicate with
+/// each other over a `ShareTransport`. Every call to `reveal_shares` or `distribute_secret` is an operation of the
+/// protocol, that is identified by a `SessionTag`. Tags are handed out in order by `new_session_tag`, thus all
+/// participants must start the same operations in the same order, but the operations may be awaited in any order.
+/// By default, unbounded schemes start all their operations at once, which can be limited with `set_concurrency`.
+/// Shares are indexed by participant id, unless stable indices are assigned with `set_participant_indices`.
+///
+/// All messages carry the tag of their operation. A message that cannot belong to its tag, because its sender
+/// already delivered a valid share for the tag or because the operation of the tag already completed, causes a panic
+/// instead of being used in the reconstruction. Malformed messages are dropped, so a peer cannot abort the protocol
+/// with them: messages of unknown senders, messages too short to carry a tag, and shares that are no element of the
+/// field. The operation keeps waiting for a well-formed message of the same sender.
+///
+/// # Type Parameters
+/// - `T` the prime field secrets are shared in
+/// - `Transport` the transport used to exchange shares with other participants
+/// - `TripleSource` the source of beaver triples for multiplication. If multiplication is not required, any type
+///   can be used here, the protocol only implements `BeaverCommunicationScheme` if it is a `BeaverTripleSource`.
+pub struct ShamirCliqueProtocol<T, Transport, TripleSource> {
+    participant_id: usize,
+    participant_count: usize,
+    /// The share index of every participant, in the order of the participant ids
+    share_indices: Vec<usize>,
+    threshold: usize,
+    tag_counter: u64,
+    concurrency: ProtocolConcurrency,
+    rng: StdRng,
+    channel: Arc<MessageChannel<Transport>>,
+    triple_source: TripleSource,
+    data: PhantomData<T>,
+}
+
+/// Received messages grouped by the tag of the operation they belong to, and the tags of all completed operations.
+#[derive(Default)]
+struct Inbox {
+    pending: HashMap<SessionTag, Vec<(usize, Vec<u8>)>>,
+    completed: HashSet<SessionTag>,
+}
+
+/// The transport of a protocol instance and all messages that were received but not yet requested by an operation.
+struct MessageChannel<Transport> {
+    transport: Transport,
+    participant_count: usize,
+    inbox: Mutex<Inbox>,
+}
+
+impl<T, Transport, TripleSource> ShamirCliqueProtocol<T, Transport, TripleSource>
+where
+    T: PrimeField,
+    Transport: ShareTransport,
+{
+    /// Create a new protocol instance for one participant.
+    /// # Parameters
+    /// - `participant_id` the id of this participant. Ids range from `1` to `participant_count` and are used as the
+    ///   indices of shares this participant holds, unless other indices are assigned with
+    ///   `set_participant_indices`.
+    /// - `participant_count` how many participants take part in the protocol
+    /// - `threshold` how many shares are required to reconstruct a secret. Must be at least two and at most
+    ///   `participant_count`.
+    /// - `transport` the transport connecting this participant with all others
+    /// - `triple_source` the source of beaver triples for this participant
+    pub fn new(
+        participant_id: usize,
+        participant_count: usize,
+        threshold: usize,
+        transport: Transport,
+        triple_source: TripleSource,
+    ) -> Self {
+        Self::with_rng(
+            participant_id,
+            participant_count,
+            threshold,
+            transport,
+            triple_source,
+            StdRng::from_entropy(),
+        )
+    }
+
+    /// Create a new protocol instance for one participant, that generates the shares of its secrets using the
+    /// given random number generator. Use this constructor with a seeded generator to obtain reproducible protocol
+    /// runs. For the remaining parameters see `new`.
+    pub fn with_rng(
+        participant_id: usize,
+        participant_count: usize,
+        threshold: usize,
+        transport: Transport,
+        triple_source: TripleSource,
+        rng: StdRng,
+    ) -> Self {
+        assert!(participant_id >= 1 && participant_id <= participant_count);
+        assert!(threshold > 1 && threshold <= participant_count);
+
+        ShamirCliqueProtocol {
+            participant_id,
+            participant_count,
+            share_indices: (1..=participant_count).collect(),
+            threshold,
+            tag_counter: 0,
+            concurrency: ProtocolConcurrency::Unlimited,
+            rng,
+            channel: Arc::new(MessageChannel {
+                transport,
+                participant_count,
+                inbox: Mutex::new(Inbox::default()),
+            }),
+            triple_source,
+            data: PhantomData,
+        }
+    }
+
+    /// The id of this participant.
+    pub fn participant_id(&self) -> usize {
+        self.participant_id
+    }
+
+    /// The index of the shares this participant holds.
+    pub fn share_index(&self) -> usize {
+        self.share_indices[self.participant_id - 1]
+    }
+
+    /// Index the shares of every participant by its identity instead of its participant id, so the shares a
+    /// participant receives do not depend on the order participant ids were handed out in. All participants must
+    /// assign the same indices before the first operation, and shares obtained from the `TripleSource` must be
+    /// indexed the same way.
+    ///
+    /// # Parameters
+    /// - `map` the stable share indices of all participant identities
+    /// - `identities` the identities of all participants, in the order of their participant ids
+    ///
+    /// # Panics
+    /// If `identities` does not contain one identity per participant or an identity is not part of `map`.
+    pub fn set_participant_indices(&mut self, map: &ParticipantIndexMap, identities: &[Vec<u8>]) {
+        assert_eq!(
+            identities.len(),
+            self.participant_count,
+            "every participant requires an identity"
+        );
+
+        self.share_indices = identities
+            .iter()
+            .map(|id| {
+                map.index_of(id)
+                    .expect("the identity of a participant is not part of the index map")
+            })
+            .collect();
+    }
+
+    /// The source of beaver triples of this participant, e.g. to refill a `TriplePool` between multiplications.
+    pub fn triple_source_mut(&mut self) -> &mut TripleSource {
+        &mut self.triple_source
+    }
+
+    /// Limit the number of operations unbounded schemes run concurrently within this protocol. Operations are still
+    /// started in the same order, so the limit does not change the computed shares.
+    pub fn set_concurrency(&mut self, concurrency: ProtocolConcurrency) {
+        self.concurrency = concurrency;
+    }
+}
+
+impl<Transport> MessageChannel<Transport>
+where
+    Transport: ShareTransport,
+{
+    /// Receive messages until `count` messages tagged with `tag` are present and return them. Messages with other
+    /// tags are kept in the inbox until they are requested. Messages of senders that are no participant and
+    /// messages without a tag are dropped, as are messages tagged with `tag` whose payload is not `valid`. Payloads
+    /// can only be validated once their tag is requested, so a sender may have several pending messages for a tag,
+    /// of which only one may be valid.
+    ///
+    /// # Panics
+    /// If a received message is mis-tagged, i.e. its sender delivered two valid messages with the same tag or the
+    /// operation of its tag already completed.
+    async fn receive_messages<F>(
+        &self,
+        tag: SessionTag,
+        count: usize,
+        valid: F,
+    ) -> Vec<(usize, Vec<u8>)>
+    where
+        F: Fn(&[u8]) -> bool,
+    {
+        loop {
+            let mut inbox = self.inbox.lock().await;
+            if let Some(messages) = inbox.pending.get_mut(&tag) {
+                messages.retain(|(_, payload)| valid(payload));
+                for (index, (sender, _)) in messages.iter().enumerate() {
+                    assert!(
+                        messages[..index].iter().all(|(other, _)| other != sender),
+                        "participant {} sent two shares for session tag {:?}",
+                        sender,
+                        tag
+                    );
+                }
+            }
+            if inbox.pending.get(&tag).map_or(0, Vec::len) >= count {
+                inbox.completed.insert(tag);
+                return inbox.pending.remove(&tag).unwrap();
+            }
+
+            let (sender, message) = self.transport.receive().await;
+            if sender == 0 || sender > self.participant_count {
+                continue;
+            }
+            let (message_tag, payload) = match decode_message(&message) {
+                Some(decoded) => decoded,
+                None => continue,
+            };
+            assert!(
+                !inbox.completed.contains(&message_tag),
+                "participant {} sent a share for session tag {:?}, which was already completed",
+                sender,
+                message_tag
+            );
+
+            inbox
+                .pending
+                .entry(message_tag)
+                .or_insert_with(Vec::new)
+                .push((sender, payload.to_vec()));
+        }
+    }
+}
+
+/// Prepend the session tag to the payload of a message
+fn encode_message(tag: SessionTag, payload: &[u8]) -> Vec<u8> {
+    [&tag.to_u64().to_le_bytes()[..], payload].concat()
+}
+
+/// Split a message into its session tag and its payload, or return `None` if it is too short to carry a tag
+fn decode_message(message: &[u8]) -> Option<(SessionTag, &[u8])> {
+    if message.len() < size_of::<u64>() {
+        return None;
+    }
+    let (tag, payload) = message.split_at(size_of::<u64>());
+    Some((
+        SessionTag::from_u64(u64::from_le_bytes(tag.try_into().unwrap())),
+        payload,
+    ))
+}
+
+/// Whether the payload of a message is a field element
+fn is_field_element<T>(payload: &[u8]) -> bool
+where
+    T: PrimeField,
+{
+    T::from_bytes_le(payload).is_some()
+}
+
+/// Decode a field element sent by another participant, whose payload was validated by `is_field_element`
+fn decode_field_element<T>(payload: &[u8]) -> T
+where
+    T: PrimeField,
+{
+    T::from_bytes_le(payload).expect("received field elements are validated on receipt")
+}
+
+impl<T, Transport, TripleSource> ShamirSecretSharingScheme<T>
+    for ShamirCliqueProtocol<T, Transport, TripleSource>
+where
+    T: PrimeField,
+{
+}
+
+impl<T, Transport, TripleSource> ProtocolConfig
+    for ShamirCliqueProtocol<T, Transport, TripleSource>
+{
+    fn concurrency(&self) -> ProtocolConcurrency {
+        self.concurrency
+    }
+}
+
+impl<T, Transport, TripleSource> CliqueCommunicationScheme<T, (usize, T)>
+    for ShamirCliqueProtocol<T, Transport, TripleSource>
+where
+    T: PrimeField + Send + Sync + 'static,
+    Transport: ShareTransport + Send + Sync + 'static,
+{
+    fn reveal_shares(&mut self, share: (usize, T)) -> Pin<Box<dyn Future<Output = T> + Send>> {
+        let tag = self.new_session_tag();
+        self.reveal_shares_tagged(tag, share)
+    }
+
+    fn distribute_secret(
+        &mut self,
+        secret: T,
+    ) -> Pin<Box<dyn Future<Output = Vec<(usize, T)>> + Send>> {
+        let tag = self.new_session_tag();
+        self.distribute_secret_tagged(tag, secret)
+    }
+
+    /// Tags are consecutive numbers, starting at zero.
+    fn new_session_tag(&mut self) -> SessionTag {
+        let tag = SessionTag::from_u64(self.tag_counter);
+        self.tag_counter += 1;
+        tag
+    }
+
+    /// Broadcast the share to all other participants and reconstruct the secret as soon as all their shares of it
+    /// were received. The secret is interpolated from the first `threshold` shares in order of arrival, and all
+    /// further shares are verified against it.
+    ///
+    /// # Panics
+    /// If a participant revealed a share that is inconsistent with the shares of the other participants.
+    fn reveal_shares_tagged(
+        &mut self,
+        tag: SessionTag,
+        share: (usize, T),
+    ) -> Pin<Box<dyn Future<Output = T> + Send>> {
+        let channel = self.channel.clone();
+        let participant_count = self.participant_count;
+        let share_indices = self.share_indices.clone();
+        let threshold = self.threshold;
+
+        Box::pin(async move {
+            channel
+                .transport
+                .broadcast(encode_message(tag, &share.1.as_bytes_le()))
+                .await;
+
+            let received_shares = channel
+                .receive_messages(tag, participant_count - 1, is_field_element::<T>)
+                .await
+                .into_iter()
+                .map(|(sender, payload)| {
+                    (share_indices[sender - 1], decode_field_element(&payload))
+                });
+
+            let mut reveal = IncrementalReveal::new(threshold);
+            for share in iter::once(share).chain(received_shares) {
+                let index = share.0;
+                if let RevealProgress::Inconsistent { .. } = reveal.add_share(share) {
+                    panic!(
+                        "participant {} revealed a share for session tag {:?}, which is inconsistent with the \
+                         other shares",
+                        index, tag
+                    );
+                }
+            }
+
+            reveal
+                .secret()
+                .cloned()
+                .expect("received shares cannot be used for reconstruction")
+        })
+    }
+
+    /// Generate one share of the secret for every participant, send them and collect the shares of all other
+    /// participants' secrets. The returned shares are ordered by the id of the participant that shared them and carry
+    /// the share index of this participant.
+    fn distribute_secret_tagged(
+        &mut self,
+        tag: SessionTag,
+        secret: T,
+    ) -> Pin<Box<dyn Future<Output = Vec<(usize, T)>> + Send>> {
+        // each participant receives the share at its own index
+        let shares =
+            Self::generate_shares_at(&mut self.rng, &secret, &self.share_indices, self.threshold)
+                .expect("share indices are distinct and nonzero");
+        let channel = self.channel.clone();
+        let participant_id = self.participant_id;
+        let participant_count = self.participant_count;
+        let share_index = self.share_index();
+
+        Box::pin(async move {
+            let mut own_share = None;
+            for (receiver, (_, share)) in (1..=participant_count).zip(shares) {
+                if receiver == participant_id {
+                    own_share = Some(share);
+                } else {
+                    channel
+                        .transport
+                        .send_to(receiver, encode_message(tag, &share.as_bytes_le()))
+                        .await;
+                }
+            }
+
+            let mut received_shares = channel
+                .receive_messages(tag, participant_count - 1, is_field_element::<T>)
+                .await
+                .into_iter()
+                .map(|(sender, payload)| (sender, decode_field_element(&payload)))
+                .collect::<Vec<_>>();
+            received_shares.push((participant_id, own_share.unwrap()));
+            received_shares.sort_by_key(|(sender, _)| *sender);
+
+            received_shares
+                .into_iter()
+                .map(|(_, share)| (share_index, share))
+                .collect()
+        })
+    }
+}
+
+impl<T, Transport, TripleSource> RawCommunicationScheme
+    for ShamirCliqueProtocol<T, Transport, TripleSource>
+where
+    T: PrimeField + Send + Sync + 'static,
+    Transport: ShareTransport + Send + Sync + 'static,
+{
+    fn broadcast_bytes(
+        &mut self,
+        tag: SessionTag,
+        message: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let channel = self.channel.clone();
+
+        Box::pin(async move {
+            channel
+                .transport
+                .broadcast(encode_message(tag, &message))
+                .await
+        })
+    }
+
+    fn send_bytes(
+        &mut self,
+        tag: SessionTag,
+        participant: usize,
+        message: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        let channel = self.channel.clone();
+
+        Box::pin(async move {
+            channel
+                .transport
+                .send_to(participant, encode_message(tag, &message))
+                .await
+        })
+    }
+
+    /// Raw messages share the inbox with shares, so the same checks apply to them.
+    ///
+    /// # Panics
+    /// If a received message is mis-tagged (see `ShamirCliqueProtocol`).
+    fn collect_bytes(
+        &mut self,
+        tag: SessionTag,
+    ) -> Pin<Box<dyn Future<Output = Vec<(usize, Vec<u8>)>> + Send>> {
+        let channel = self.channel.clone();
+        let participant_count = self.participant_count;
+
+        Box::pin(async move {
+            let mut messages = channel
+                .receive_messages(tag, participant_count - 1, |_| true)
+                .await;
+            messages.sort_by_key(|(sender, _)| *sender);
+            messages
+        })
+    }
+}
+
+impl<T, Transport, TripleSource> BeaverCommunicationScheme<(usize, T)>
+    for ShamirCliqueProtocol<T, Transport, TripleSource>
+where
+    TripleSource: BeaverTripleSource<(usize, T)>,
+{
+    fn get_reconstruction_threshold(&self) -> usize {
+        self.threshold
+    }
+
+    fn obtain_beaver_triples<'a>(
+        &'a mut self,
+        count: usize,
+    ) -> Pin<Box<dyn Future<Output = Vec<((usize, T), (usize, T), (usize, T))>> + Send + 'a>> {
+        self.triple_source.obtain_beaver_triples(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feldman_vss::SchnorrGroup;
+    use crate::in_memory_transport::InMemoryTransport;
+    use crate::inversion::unbounded_inversion::JointUnboundedInversion;
+    use crate::inversion::unbounded_inversion_rounds::{
+        InversionMultiplicationRound, InversionRandomnessRound, InversionRevealRound,
+        UnboundedInversionRounds,
+    };
+    use crate::key_generation::pedersen_key_generation::PedersenKeyGeneration;
+    use crate::multiplication::beaver_multiply_rounds::{BeaverMultiplyRounds, BeaverOpeningRound};
+    use crate::multiplication::beaver_randomization_multiplication::BeaverRerandomizationMultiplication;
+    use crate::multiplication::triple_pool::TriplePool;
+    use crate::random_number_generation::committed_sum_random_number_generation::CommittedSumRandomNumberGeneration;
+    use crate::random_number_generation::sum_random_number_generation::SumRandomNumberGeneration;
+    use crate::shamir_secret_sharing::ShamirSecretSharing;
+    use crate::share_redistribution::shamir_share_redistribution::ShamirShareRedistribution;
+    use crate::shared_or_function::joint_unbounded_or::JointUnboundedOrFunction;
+    use crate::shared_or_function::or_accumulator::OrAccumulator;
+    use crate::testing::DeterministicProtocolRng;
+    use crate::{
+        BigUint, CommittedRandomNumberGenerationScheme,
+        CommittedRandomNumberGenerationSchemeDelegate, CommittedRandomNumberGenerationSchemeMarker,
+        Delegate, DistributedKey, DistributedKeyGenerationScheme, FieldRng, FinishedRounds,
+        IndexedShare, LinearSharingScheme, ProtocolException, RandomNumberGenerationScheme,
+        RandomNumberGenerationSchemeDelegate, RandomNumberGenerationSchemeMarker,
+        RedistributionParticipants, RoundParticipants, RoundTransition, ShareRedistributionScheme,
+        ThresholdSecretSharingScheme, UnboundedInversionScheme, UnboundedInversionSchemeDelegate,
+        UnboundedInversionSchemeMarker, UnboundedMultiplicationScheme,
+        UnboundedMultiplicationSchemeDelegate, UnboundedMultiplicationSchemeMarker,
+        UnboundedOrFunctionScheme,
+        UnboundedOrFunctionSchemeDelegate, UnboundedOrFunctionSchemeMarker,
+    };
+    use futures::executor::block_on;
+    use futures::future::join_all;
+    use jester_maths::prime::{Mersenne31, Mersenne61};
+    use jester_maths::prime_fields;
+    use mashup::*;
+    use num::{FromPrimitive, One, Zero};
+    use rand::{Rng, SeedableRng};
+    use std::time::{Duration, Instant};
+
+    const PARTICIPANT_COUNT: usize = 3;
+    const THRESHOLD: usize = 2;
+
+    /// The protocol of the in-memory network over the field `F`
+    type FieldCliqueProtocol<F> = ShamirCliqueProtocol<F, InMemoryTransport, TestTripleDealer<F>>;
+
+    type TestCliqueProtocol = FieldCliqueProtocol<Mersenne61>;
+
+    /// A trusted dealer of beaver triples. All participants' dealers are seeded identically, so they generate the
+    /// same triples and each of them hands out the shares of its participant.
+    struct TestTripleDealer<F> {
+        participant_id: usize,
+        rng: StdRng,
+        field: PhantomData<F>,
+    }
+
+    impl<F> TestTripleDealer<F>
+    where
+        F: PrimeField,
+    {
+        /// Create the dealer of the participant `participant_id`. All dealers created from the same `seed` deal the
+        /// same triples.
+        fn new(participant_id: usize, seed: u64) -> Self {
+            TestTripleDealer {
+                participant_id,
+                rng: StdRng::seed_from_u64(seed),
+                field: PhantomData,
+            }
+        }
+
+        /// Share the `secret` among all participants and return the share of this dealer's participant
+        fn share(&mut self, secret: F) -> (usize, F) {
+            FieldCliqueProtocol::<F>::generate_shares(
+                &mut self.rng,
+                &secret,
+                PARTICIPANT_COUNT,
+                THRESHOLD,
+            )
+            .swap_remove(self.participant_id - 1)
+        }
+    }
+
+    impl<F> BeaverTripleSource<(usize, F)> for TestTripleDealer<F>
+    where
+        F: PrimeField + Send + 'static,
+    {
+        fn obtain_beaver_triples<'a>(
+            &'a mut self,
+            count: usize,
+        ) -> Pin<Box<dyn Future<Output = Vec<((usize, F), (usize, F), (usize, F))>> + Send + 'a>> {
+            let triples = (0..count)
+                .map(|_| {
+                    let a: F = self.rng.next_field_element("TestTripleDealer");
+                    let b: F = self.rng.next_field_element("TestTripleDealer");
+                    let c = a.clone() * b.clone();
+                    (self.share(a), self.share(b), self.share(c))
+                })
+                .collect::<Vec<_>>();
+
+            Box::pin(async move { triples })
+        }
+    }
+
+    impl<F> RandomNumberGenerationSchemeMarker for FieldCliqueProtocol<F> {
+        type Marker = Delegate;
+    }
+
+    impl<F, T, S, P> RandomNumberGenerationSchemeDelegate<T, S, P> for FieldCliqueProtocol<F>
+    where
+        P: ThresholdSecretSharingScheme<T, S>
+            + LinearSharingScheme<T, S>
+            + CliqueCommunicationScheme<T, S>,
+        T: PrimeField,
+        S: 'static,
+    {
+        type Delegate = SumRandomNumberGeneration<T, S, P>;
+    }
+
+    impl CommittedRandomNumberGenerationSchemeMarker for TestCliqueProtocol {
+        type Marker = Delegate;
+    }
+
+    impl<T, S, P> CommittedRandomNumberGenerationSchemeDelegate<T, S, P> for TestCliqueProtocol
+    where
+        P: ThresholdSecretSharingScheme<T, S>
+            + LinearSharingScheme<T, S>
+            + CliqueCommunicationScheme<T, S>
+            + RawCommunicationScheme
+            + Send,
+        T: PrimeField + Send + Sync + 'static,
+        S: IndexedShare<T> + Send + 'static,
+    {
+        type Delegate = CommittedSumRandomNumberGeneration<T, S, P>;
+    }
+
+    impl<F> UnboundedMultiplicationSchemeMarker for FieldCliqueProtocol<F> {
+        type Marker = Delegate;
+    }
+
+    impl<F, T, S, P> UnboundedMultiplicationSchemeDelegate<T, S, P> for FieldCliqueProtocol<F>
+    where
+        P: ThresholdSecretSharingScheme<T, S>
+            + LinearSharingScheme<T, S>
+            + CliqueCommunicationScheme<T, S>
+            + BeaverCommunicationScheme<S>
+            + Send
+            + Sync,
+        T: PrimeField + Send + Sync,
+        S: Send + Sync + Clone + 'static,
+    {
+        type Delegate = BeaverRerandomizationMultiplication<T, S, P>;
+    }
+
+    impl<F> UnboundedInversionSchemeMarker for FieldCliqueProtocol<F> {
+        type Marker = Delegate;
+    }
+
+    impl<F, T, S, P> UnboundedInversionSchemeDelegate<T, S, P> for FieldCliqueProtocol<F>
+    where
+        P: ThresholdSecretSharingScheme<T, S>
+            + LinearSharingScheme<T, S>
+            + CliqueCommunicationScheme<T, S>
+            + UnboundedMultiplicationScheme<T, S, P>
+            + RandomNumberGenerationScheme<T, S, P>
+            + Send
+            + Sync,
+        T: Send + Sync + PrimeField,
+        S: Send + Sync + Clone + 'static,
+    {
+        type Delegate = JointUnboundedInversion<T, S, P>;
+    }
+
+    impl UnboundedOrFunctionSchemeMarker for TestCliqueProtocol {
+        type Marker = Delegate;
+    }
+
+    impl<T, S, P> UnboundedOrFunctionSchemeDelegate<T, S, P> for TestCliqueProtocol
+    where
+        P: ThresholdSecretSharingScheme<T, S>
+            + LinearSharingScheme<T, S>
+            + CliqueCommunicationScheme<T, S>
+            + UnboundedMultiplicationScheme<T, S, P>
+            + RandomNumberGenerationScheme<T, S, P>
+            + UnboundedInversionScheme<T, S, P>
+            + Send
+            + Sync,
+        T: Send + Sync + PrimeField + 'static,
+        S: Send + Sync + Clone + 'static,
+    {
+        type Delegate = JointUnboundedOrFunction<T, S, P>;
+    }
+
+    /// Everything a single participant observed during a run of the unbounded or function
+    #[derive(Debug, PartialEq)]
+    struct OrTranscript {
+        or_share: (usize, Mersenne61),
+        revealed: Mersenne61,
+        drawn_elements: Vec<(&'static str, BigUint)>,
+    }
+
+    /// Create the protocol instances of all participants, connected by an in-memory network. All randomness of the
+    /// participants is derived from `seed`.
+    fn create_participants<F>(seed: u64) -> Vec<FieldCliqueProtocol<F>>
+    where
+        F: PrimeField,
+    {
+        InMemoryTransport::create_network(PARTICIPANT_COUNT)
+            .into_iter()
+            .map(|transport| {
+                let participant_id = transport.participant_id();
+                FieldCliqueProtocol::with_rng(
+                    participant_id,
+                    PARTICIPANT_COUNT,
+                    THRESHOLD,
+                    transport,
+                    TestTripleDealer::new(participant_id, seed),
+                    StdRng::seed_from_u64(seed + participant_id as u64),
+                )
+            })
+            .collect()
+    }
+
+    /// Share each of the `secrets` among all participants. The returned vector contains the shares of each
+    /// participant.
+    fn share_secrets<F>(seed: u64, secrets: &[F]) -> Vec<Vec<(usize, F)>>
+    where
+        F: PrimeField,
+    {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let shares = secrets
+            .iter()
+            .map(|secret| {
+                FieldCliqueProtocol::<F>::generate_shares(&mut rng, secret, PARTICIPANT_COUNT, THRESHOLD)
+            })
+            .collect::<Vec<_>>();
+
+        (0..PARTICIPANT_COUNT)
+            .map(|participant| shares.iter().map(|s| s[participant].clone()).collect())
+            .collect()
+    }
+
+    fn run_unbounded_or(seed: u64, bits: &[Mersenne61]) -> Vec<OrTranscript> {
+        let mut participants = create_participants(seed);
+
+        block_on(join_all(
+            participants.iter_mut().zip(share_secrets(seed, bits)).map(
+                |(protocol, shares)| async move {
+                    let mut rng = DeterministicProtocolRng::seed_from_u64(
+                        seed + protocol.participant_id() as u64,
+                    );
+                    let or_share =
+                        TestCliqueProtocol::unbounded_shared_or(&mut rng, protocol, &shares)
+                            .await
+                            .unwrap();
+                    let revealed = protocol.reveal_shares(or_share.clone()).await;
+                    OrTranscript {
+                        or_share,
+                        revealed,
+                        drawn_elements: rng.transcript().to_vec(),
+                    }
+                },
+            ),
+        ))
+    }
+
+    fn evaluate_unbounded_or(bits: &[Mersenne61]) -> Vec<Mersenne61> {
+        run_unbounded_or(0, bits)
+            .into_iter()
+            .map(|transcript| transcript.revealed)
+            .collect()
+    }
+
+    #[test]
+    fn test_distribute_and_reveal() {
+        let mut participants = create_participants(0);
+
+        let revealed = block_on(join_all(participants.iter_mut().map(
+            |protocol| async move {
+                let secret = Mersenne61::from_usize(protocol.participant_id() * 10).unwrap();
+                let shares = protocol.distribute_secret(secret).await;
+                let sum = TestCliqueProtocol::sum_shares(&shares).unwrap();
+                protocol.reveal_shares(sum).await
+            },
+        )));
+
+        assert!(revealed
+            .iter()
+            .all(|r| *r == Mersenne61::from_usize(60).unwrap()));
+    }
+
+    #[test]
+    fn test_distribute_with_participant_indices() {
+        // participant ids are handed out in a different order than the sorted identities
+        let identities = vec![b"carol".to_vec(), b"alice".to_vec(), b"bob".to_vec()];
+        let map = ParticipantIndexMap::hashed(identities.clone(), 1000).unwrap();
+
+        let mut participants = create_participants(0);
+        for protocol in &mut participants {
+            protocol.set_participant_indices(&map, &identities);
+        }
+
+        let results = block_on(join_all(participants.iter_mut().map(
+            |protocol| async move {
+                let secret = Mersenne61::from_usize(protocol.participant_id() * 10).unwrap();
+                let shares = protocol.distribute_secret(secret).await;
+                let sum = TestCliqueProtocol::sum_shares(&shares).unwrap();
+                (sum.clone(), protocol.reveal_shares(sum).await)
+            },
+        )));
+
+        for (identity, (sum, revealed)) in identities.iter().zip(&results) {
+            assert_eq!(Some(sum.0), map.index_of(identity));
+            assert_eq!(*revealed, Mersenne61::from_usize(60).unwrap());
+        }
+
+        // the shares can be stored by identity and reconstructed without the participant ids
+        let stored = identities
+            .iter()
+            .zip(&results)
+            .map(|(identity, ((_, value), _))| (identity.clone(), value.clone()))
+            .collect::<Vec<_>>();
+        let collected = stored
+            .iter()
+            .skip(1)
+            .map(|(identity, value)| (map.index_of(identity).unwrap(), value.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            TestCliqueProtocol::reconstruct_secret(&collected, THRESHOLD),
+            Ok(Mersenne61::from_usize(60).unwrap())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "which is inconsistent with the other shares")]
+    fn test_inconsistent_share_detected() {
+        let mut participants = create_participants(0);
+        let shares = share_secrets(0, &[Mersenne61::from_usize(5).unwrap()]);
+
+        block_on(join_all(participants.iter_mut().zip(shares).map(
+            |(protocol, mut shares)| async move {
+                let (index, mut share) = shares.remove(0);
+                if index == 1 {
+                    share = share + Mersenne61::one();
+                }
+                protocol.reveal_shares((index, share)).await
+            },
+        )));
+    }
+
+    /// A transport that changes the session tag of all messages with the tag `from` to the tag `to`, simulating a
+    /// transport that misdelivers shares to another logical value.
+    struct MisTaggingTransport {
+        transport: InMemoryTransport,
+        from: SessionTag,
+        to: SessionTag,
+    }
+
+    impl ShareTransport for MisTaggingTransport {
+        fn send_to<'a>(
+            &'a self,
+            participant: usize,
+            message: Vec<u8>,
+        ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+            let (tag, payload) = decode_message(&message).unwrap();
+            let tag = if tag == self.from { self.to } else { tag };
+            self.transport
+                .send_to(participant, encode_message(tag, payload))
+        }
+
+        fn broadcast<'a>(
+            &'a self,
+            message: Vec<u8>,
+        ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+            Box::pin(async move {
+                for participant in
+                    (1..=PARTICIPANT_COUNT).filter(|id| *id != self.transport.participant_id())
+                {
+                    self.send_to(participant, message.clone()).await
+                }
+            })
+        }
+
+        fn receive<'a>(&'a self) -> Pin<Box<dyn Future<Output = (usize, Vec<u8>)> + Send + 'a>> {
+            self.transport.receive()
+        }
+    }
+
+    #[test]
+    fn test_tagged_operations_awaited_out_of_order() {
+        let mut participants = create_participants(0);
+
+        let revealed = block_on(join_all(participants.iter_mut().map(
+            |protocol| async move {
+                let id = protocol.participant_id();
+                let first_tag = protocol.new_session_tag();
+                let second_tag = protocol.new_session_tag();
+
+                let second = protocol.distribute_secret_tagged(
+                    second_tag,
+                    Mersenne61::from_usize(id * 100).unwrap(),
+                );
+                let first = protocol
+                    .distribute_secret_tagged(first_tag, Mersenne61::from_usize(id).unwrap());
+                let (second, first) = (second.await, first.await);
+
+                let first_tag = protocol.new_session_tag();
+                let second_tag = protocol.new_session_tag();
+                let second = protocol.reveal_shares_tagged(
+                    second_tag,
+                    TestCliqueProtocol::sum_shares(&second).unwrap(),
+                );
+                let first = protocol.reveal_shares_tagged(
+                    first_tag,
+                    TestCliqueProtocol::sum_shares(&first).unwrap(),
+                );
+                (first.await, second.await)
+            },
+        )));
+
+        assert!(revealed.iter().all(|r| *r
+            == (
+                Mersenne61::from_usize(6).unwrap(),
+                Mersenne61::from_usize(600).unwrap()
+            )));
+    }
+
+    #[test]
+    fn test_malformed_messages_dropped() {
+        // the network has an endpoint more than the protocol has participants, so its messages have an unknown sender
+        let mut transports = InMemoryTransport::create_network(PARTICIPANT_COUNT + 1);
+        let stranger = transports.pop().unwrap();
+
+        let tag = SessionTag::from_u64(0);
+        block_on(async {
+            for receiver in 1..=PARTICIPANT_COUNT {
+                let sender = &transports[receiver % PARTICIPANT_COUNT];
+
+                // truncated messages without a complete tag
+                sender.send_to(receiver, vec![]).await;
+                sender.send_to(receiver, vec![0; 7]).await;
+
+                // shares that exceed the field
+                sender
+                    .send_to(receiver, encode_message(tag, &[0xFF; 8]))
+                    .await;
+                sender
+                    .send_to(receiver, encode_message(tag, &[1; 64]))
+                    .await;
+
+                stranger.send_to(receiver, encode_message(tag, &[1])).await;
+            }
+        });
+
+        let mut participants = transports
+            .into_iter()
+            .map(|transport| {
+                let participant_id = transport.participant_id();
+                ShamirCliqueProtocol::<Mersenne61, _, ()>::with_rng(
+                    participant_id,
+                    PARTICIPANT_COUNT,
+                    THRESHOLD,
+                    transport,
+                    (),
+                    StdRng::seed_from_u64(participant_id as u64),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let revealed = block_on(join_all(participants.iter_mut().map(
+            |protocol| async move {
+                let id = protocol.participant_id();
+                let shares = protocol
+                    .distribute_secret(Mersenne61::from_usize(id).unwrap())
+                    .await;
+                protocol
+                    .reveal_shares(TestCliqueProtocol::sum_shares(&shares).unwrap())
+                    .await
+            },
+        )));
+
+        assert!(revealed
+            .iter()
+            .all(|r| *r == Mersenne61::from_usize(6).unwrap()));
+    }
+
+    #[test]
+    fn test_invalid_share_for_pending_tag_dropped() {
+        let mut transports = InMemoryTransport::create_network(2);
+        let channel = MessageChannel {
+            transport: transports.pop().unwrap(),
+            participant_count: 2,
+            inbox: Mutex::new(Inbox::default()),
+        };
+        let sender = &transports[0];
+        let (awaited_tag, other_tag) = (SessionTag::from_u64(0), SessionTag::from_u64(1));
+        let share = Mersenne61::from_usize(5).unwrap().as_bytes_le();
+
+        block_on(async {
+            // the invalid and the valid share for the other tag arrive while the awaited tag is received
+            sender
+                .send_to(2, encode_message(other_tag, &[0xFF; 8]))
+                .await;
+            sender.send_to(2, encode_message(other_tag, &share)).await;
+            sender.send_to(2, encode_message(awaited_tag, &share)).await;
+
+            let valid = is_field_element::<Mersenne61>;
+            assert_eq!(
+                channel.receive_messages(awaited_tag, 1, valid).await,
+                vec![(1, share.clone())]
+            );
+            assert_eq!(
+                channel.receive_messages(other_tag, 1, valid).await,
+                vec![(1, share.clone())]
+            );
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "participant 1 sent")]
+    fn test_mis_tagged_share_detected() {
+        let mut participants = InMemoryTransport::create_network(PARTICIPANT_COUNT)
+            .into_iter()
+            .map(|transport| {
+                let participant_id = transport.participant_id();
+                ShamirCliqueProtocol::<Mersenne61, _, ()>::with_rng(
+                    participant_id,
+                    PARTICIPANT_COUNT,
+                    THRESHOLD,
+                    MisTaggingTransport {
+                        transport,
+                        from: SessionTag::from_u64(if participant_id == 1 { 1 } else { u64::MAX }),
+                        to: SessionTag::from_u64(0),
+                    },
+                    (),
+                    StdRng::seed_from_u64(participant_id as u64),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        block_on(join_all(participants.iter_mut().map(
+            |protocol| async move {
+                let first = protocol.reveal_shares((protocol.participant_id(), Mersenne61::one()));
+                let second =
+                    protocol.reveal_shares((protocol.participant_id(), Mersenne61::zero()));
+                join_all(vec![first, second]).await
+            },
+        )));
+    }
+
+    #[test]
+    fn test_committed_random_number_generation() {
+        // the contribution is the first element drawn by both schemes, so equally seeded runs generate equal numbers
+        let mut participants = create_participants(0);
+        let uncommitted = block_on(join_all(participants.iter_mut().map(
+            |protocol| async move {
+                let mut rng = StdRng::seed_from_u64(protocol.participant_id() as u64);
+                let share =
+                    TestCliqueProtocol::generate_random_number_sharing(&mut rng, protocol).await;
+                protocol.reveal_shares(share).await
+            },
+        )));
+
+        let mut participants = create_participants(0);
+        let committed = block_on(join_all(participants.iter_mut().map(
+            |protocol| async move {
+                let mut rng = StdRng::seed_from_u64(protocol.participant_id() as u64);
+                let participants =
+                    RoundParticipants::new(protocol.participant_id(), PARTICIPANT_COUNT, THRESHOLD);
+                let share = TestCliqueProtocol::generate_committed_random_number_sharing(
+                    &mut rng,
+                    protocol,
+                    participants,
+                )
+                .await
+                .unwrap();
+                protocol.reveal_shares(share).await
+            },
+        )));
+
+        assert_eq!(committed, uncommitted);
+    }
+
+    /// A transport that flips a bit of all messages it sends to other participants with the session tag `tag`,
+    /// simulating a participant that opens a commitment to another value.
+    struct TamperingTransport {
+        transport: InMemoryTransport,
+        tag: SessionTag,
+    }
+
+    impl ShareTransport for TamperingTransport {
+        fn send_to<'a>(
+            &'a self,
+            participant: usize,
+            mut message: Vec<u8>,
+        ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+            if decode_message(&message).unwrap().0 == self.tag {
+                // the lowest bit of the first salt element, behind the tag and the length prefix of the element
+                message[size_of::<u64>() + size_of::<u32>()] ^= 1;
+            }
+            self.transport.send_to(participant, message)
+        }
+
+        fn broadcast<'a>(
+            &'a self,
+            message: Vec<u8>,
+        ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+            self.transport.broadcast(message)
+        }
+
+        fn receive<'a>(&'a self) -> Pin<Box<dyn Future<Output = (usize, Vec<u8>)> + Send + 'a>> {
+            self.transport.receive()
+        }
+    }
+
+    #[test]
+    fn test_committed_random_number_generation_detects_altered_opening() {
+        let mut participants = InMemoryTransport::create_network(PARTICIPANT_COUNT)
+            .into_iter()
+            .map(|transport| {
+                let participant_id = transport.participant_id();
+                ShamirCliqueProtocol::<Mersenne61, _, ()>::with_rng(
+                    participant_id,
+                    PARTICIPANT_COUNT,
+                    THRESHOLD,
+                    TamperingTransport {
+                        transport,
+                        // the openings are sent with the second tag
+                        tag: SessionTag::from_u64(if participant_id == 2 { 1 } else { u64::MAX }),
+                    },
+                    (),
+                    StdRng::seed_from_u64(participant_id as u64),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let results = block_on(join_all(participants.iter_mut().map(
+            |protocol| async move {
+                let mut rng = StdRng::seed_from_u64(protocol.participant_id() as u64);
+                let participants = RoundParticipants::new(
+                    protocol.participant_id(),
+                    PARTICIPANT_COUNT,
+                    THRESHOLD,
+                );
+                <CommittedSumRandomNumberGeneration<_, _, _>>::generate_committed_random_number_sharing(
+                    &mut rng,
+                    protocol,
+                    participants,
+                )
+                .await
+            },
+        )));
+
+        assert!(results[1].is_ok());
+        for result in [&results[0], &results[2]].iter() {
+            assert_eq!(
+                **result,
+                Err(ProtocolException::CommitmentMismatch { participant: 2 })
+            );
+        }
+    }
+
+    const KEY_PARTICIPANT_COUNT: usize = 4;
+    const KEY_THRESHOLD: usize = 3;
+
+    // 2^61 - 1 divides the order of the multiplicative group of this field, and 2^52 generates that subgroup
+    prime_fields!(CommitmentPrime("119903836479112085453", 10));
+
+    type TestKey = DistributedKey<(usize, Mersenne61), CommitmentPrime>;
+
+    fn key_group() -> SchnorrGroup<CommitmentPrime, Mersenne61> {
+        SchnorrGroup::new(CommitmentPrime::from(BigUint::from(1_u64 << 52))).unwrap()
+    }
+
+    /// Generate a distributed key among `KEY_PARTICIPANT_COUNT` participants, connected by the transports that
+    /// `create_transport` wraps around the in-memory transports
+    fn generate_distributed_keys<Transport>(
+        create_transport: impl Fn(InMemoryTransport) -> Transport,
+    ) -> Vec<TestKey>
+    where
+        Transport: ShareTransport + Send + Sync + 'static,
+    {
+        let mut participants = InMemoryTransport::create_network(KEY_PARTICIPANT_COUNT)
+            .into_iter()
+            .map(|transport| {
+                let participant_id = transport.participant_id();
+                ShamirCliqueProtocol::<Mersenne61, _, ()>::with_rng(
+                    participant_id,
+                    KEY_PARTICIPANT_COUNT,
+                    KEY_THRESHOLD,
+                    create_transport(transport),
+                    (),
+                    StdRng::seed_from_u64(participant_id as u64),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        block_on(join_all(participants.iter_mut().map(
+            |protocol| async move {
+                let mut rng = StdRng::seed_from_u64(protocol.participant_id() as u64);
+                let participants = RoundParticipants::new(
+                    protocol.participant_id(),
+                    KEY_PARTICIPANT_COUNT,
+                    KEY_THRESHOLD,
+                );
+                <PedersenKeyGeneration<_, _, _, _>>::generate_distributed_key(
+                    &mut rng,
+                    protocol,
+                    participants,
+                    &key_group(),
+                )
+                .await
+            },
+        )))
+    }
+
+    /// Assert that all `keys` agree on the public key and that every `KEY_THRESHOLD` of their shares reconstruct the
+    /// matching private key. There must be `KEY_THRESHOLD` or `KEY_THRESHOLD + 1` keys.
+    fn assert_consistent_keys(keys: &[&TestKey]) {
+        let public_key = keys[0].public_key();
+        assert!(keys.iter().all(|key| key.public_key() == public_key));
+
+        // exclude every key in turn if there are more keys than required, otherwise exclude none
+        let excluded = if keys.len() > KEY_THRESHOLD {
+            0..keys.len()
+        } else {
+            keys.len()..keys.len() + 1
+        };
+        for excluded in excluded {
+            let shares = keys
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| *index != excluded)
+                .map(|(_, key)| key.share().clone())
+                .collect::<Vec<_>>();
+            let private_key =
+                ShamirSecretSharing::reconstruct_secret(&shares, KEY_THRESHOLD).unwrap();
+            assert_eq!(key_group().exp(&private_key), *public_key);
+        }
+    }
+
+    #[test]
+    fn test_distributed_key_generation() {
+        let keys = generate_distributed_keys(|transport| transport);
+
+        for key in keys.iter() {
+            assert_eq!(key.qualified(), &[1, 2, 3, 4]);
+        }
+        assert_consistent_keys(&keys.iter().collect::<Vec<_>>());
+    }
+
+    /// A transport of a dealer that sends an invalid share to participant 3 and answers the complaint of
+    /// participant 3 with another invalid share
+    struct InconsistentDealerTransport {
+        transport: InMemoryTransport,
+        dealer: bool,
+    }
+
+    impl InconsistentDealerTransport {
+        /// Flip the lowest bit of the first field element of the message, if it is tagged with `tag`
+        fn tamper(&self, tag: u64, mut message: Vec<u8>) -> Vec<u8> {
+            let offset = size_of::<u64>() + size_of::<u32>();
+            if self.dealer
+                && decode_message(&message).unwrap().0 == SessionTag::from_u64(tag)
+                && message.len() > offset
+            {
+                message[offset] ^= 1;
+            }
+            message
+        }
+    }
+
+    impl ShareTransport for InconsistentDealerTransport {
+        fn send_to<'a>(
+            &'a self,
+            participant: usize,
+            message: Vec<u8>,
+        ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+            // the shares are sent with the second tag
+            let message = if participant == 3 {
+                self.tamper(1, message)
+            } else {
+                message
+            };
+            self.transport.send_to(participant, message)
+        }
+
+        fn broadcast<'a>(
+            &'a self,
+            message: Vec<u8>,
+        ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+            // the answers to complaints are broadcast with the fourth tag
+            self.transport.broadcast(self.tamper(3, message))
+        }
+
+        fn receive<'a>(&'a self) -> Pin<Box<dyn Future<Output = (usize, Vec<u8>)> + Send + 'a>> {
+            self.transport.receive()
+        }
+    }
+
+    #[test]
+    fn test_distributed_key_generation_excludes_inconsistent_dealer() {
+        let keys = generate_distributed_keys(|transport| InconsistentDealerTransport {
+            dealer: transport.participant_id() == 2,
+            transport,
+        });
+
+        let honest_keys = [&keys[0], &keys[2], &keys[3]];
+        for key in honest_keys.iter() {
+            assert_eq!(key.qualified(), &[1, 3, 4]);
+        }
+        assert_consistent_keys(&honest_keys);
+    }
+
+    #[test]
+    fn test_share_redistribution() {
+        const OLD_COUNT: usize = 5;
+        const NEW_COUNT: usize = 3;
+        const OLD_INDICES: [usize; 3] = [1, 3, 4];
+
+        let secret = Mersenne61::from_u64(0xC0FFEE).unwrap();
+        let old_shares = ShamirSecretSharing::generate_shares(
+            &mut StdRng::seed_from_u64(1),
+            &secret,
+            OLD_COUNT,
+            3,
+        );
+
+        // the old and the new participants are disjoint, so the transition clique holds all of them
+        let mut participants = InMemoryTransport::create_network(OLD_COUNT + NEW_COUNT)
+            .into_iter()
+            .map(|transport| {
+                let participant_id = transport.participant_id();
+                ShamirCliqueProtocol::<Mersenne61, _, ()>::with_rng(
+                    participant_id,
+                    OLD_COUNT + NEW_COUNT,
+                    THRESHOLD,
+                    transport,
+                    (),
+                    StdRng::seed_from_u64(participant_id as u64),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let new_shares = block_on(join_all(participants.iter_mut().map(|protocol| {
+            let participant_id = protocol.participant_id();
+            let share = old_shares.get(participant_id - 1).cloned();
+            async move {
+                let mut rng = StdRng::seed_from_u64(participant_id as u64);
+                let participants = RedistributionParticipants::new(
+                    participant_id,
+                    OLD_COUNT,
+                    &OLD_INDICES,
+                    NEW_COUNT,
+                    2,
+                );
+                <ShamirShareRedistribution<_, _, _>>::redistribute(
+                    &mut rng,
+                    protocol,
+                    participants,
+                    share,
+                )
+                .await
+                .unwrap()
+            }
+        })));
+
+        assert!(new_shares[..OLD_COUNT].iter().all(Option::is_none));
+        let new_shares = new_shares[OLD_COUNT..]
+            .iter()
+            .map(|share| share.clone().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            new_shares.iter().map(|(index, _)| *index).collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+
+        for excluded in 0..NEW_COUNT {
+            let shares = new_shares
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| *index != excluded)
+                .map(|(_, share)| share.clone())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                ShamirSecretSharing::reconstruct_secret(&shares, 2).unwrap(),
+                secret
+            );
+        }
+
+        // old shares cannot be combined with the new ones
+        let mixed = [new_shares[0].clone(), old_shares[1].clone()];
+        assert_ne!(
+            ShamirSecretSharing::reconstruct_secret(&mixed, 2).unwrap(),
+            secret
+        );
+        let mixed = [
+            old_shares[3].clone(),
+            old_shares[4].clone(),
+            new_shares[0].clone(),
+        ];
+        assert_ne!(
+            ShamirSecretSharing::reconstruct_secret(&mixed, 3).unwrap(),
+            secret
+        );
+    }
+
+    #[test]
+    fn test_unbounded_or_one() {
+        let bits = vec![Mersenne61::one(), Mersenne61::zero(), Mersenne61::one()];
+        assert!(evaluate_unbounded_or(&bits)
+            .iter()
+            .all(|r| *r == Mersenne61::one()));
+    }
+
+    #[test]
+    fn test_unbounded_or_zero() {
+        let bits = vec![Mersenne61::zero(), Mersenne61::zero(), Mersenne61::zero()];
+        assert!(evaluate_unbounded_or(&bits)
+            .iter()
+            .all(|r| *r == Mersenne61::zero()));
+    }
+
+    /// Absorb the `bits` into an `OrAccumulator` of every participant, either one at a time or all at once, and
+    /// reveal the accumulated OR.
+    fn accumulate_or(bits: &[Mersenne61], batched: bool) -> Vec<Mersenne61> {
+        let mut participants = create_participants(0);
+
+        block_on(join_all(participants.iter_mut().zip(share_secrets(0, bits)).map(
+            |(protocol, shares)| async move {
+                let mut accumulator = OrAccumulator::<Mersenne61, Share, _>::new();
+                if batched {
+                    accumulator.absorb_many(protocol, &shares).await;
+                } else {
+                    for share in &shares {
+                        accumulator.absorb(protocol, share).await;
+                    }
+                }
+
+                let or = accumulator.current().unwrap().clone();
+                protocol.reveal_shares(or).await
+            },
+        )))
+    }
+
+    #[test]
+    fn test_or_accumulator() {
+        let bits = |bits: &[usize]| {
+            bits.iter()
+                .map(|bit| Mersenne61::from_usize(*bit).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        for batched in &[false, true] {
+            assert_eq!(
+                accumulate_or(&bits(&[0, 0, 1, 0]), *batched),
+                vec![Mersenne61::one(); PARTICIPANT_COUNT]
+            );
+            assert_eq!(
+                accumulate_or(&bits(&[0, 0, 0]), *batched),
+                vec![Mersenne61::zero(); PARTICIPANT_COUNT]
+            );
+            assert_eq!(
+                accumulate_or(&bits(&[1, 1, 0, 1, 1]), *batched),
+                vec![Mersenne61::one(); PARTICIPANT_COUNT]
+            );
+        }
+
+        let accumulator = OrAccumulator::<Mersenne61, Share, TestCliqueProtocol>::new();
+        assert_eq!(accumulator.current(), None);
+    }
+
+    #[test]
+    fn test_seeded_runs_reproducible() {
+        let bits = vec![Mersenne61::zero(), Mersenne61::one(), Mersenne61::zero()];
+
+        let first_run = run_unbounded_or(7, &bits);
+        let second_run = run_unbounded_or(7, &bits);
+
+        assert!(first_run
+            .iter()
+            .all(|transcript| !transcript.drawn_elements.is_empty()));
+        assert_eq!(first_run, second_run);
+        assert_ne!(first_run, run_unbounded_or(8, &bits));
+    }
+
+    #[test]
+    fn test_unbounded_inversion() {
+        let elements = vec![
+            Mersenne61::from_usize(1).unwrap(),
+            Mersenne61::from_usize(4).unwrap(),
+            Mersenne61::from_usize(6).unwrap(),
+        ];
+        let mut participants = create_participants(0);
+
+        let revealed = block_on(join_all(
+            participants
+                .iter_mut()
+                .zip(share_secrets(0, &elements))
+                .map(|(protocol, shares)| async move {
+                    let mut rng = StdRng::seed_from_u64(protocol.participant_id() as u64);
+                    let inverses =
+                        TestCliqueProtocol::unbounded_inverse(&mut rng, protocol, &shares).await;
+                    join_all(inverses.into_iter().map(|i| protocol.reveal_shares(i))).await
+                }),
+        ));
+
+        for inverses in revealed {
+            for (element, inverse) in elements.iter().zip(inverses) {
+                assert_eq!(element.clone() * inverse, Mersenne61::one());
+            }
+        }
+    }
+
+    /// Invert the `elements` with the given concurrency of all participants.
+    ///
+    /// # Returns
+    /// The inverses revealed by every participant and the peak of messages in flight during the inversion
+    fn run_inversions(
+        concurrency: ProtocolConcurrency,
+        elements: &[Mersenne61],
+    ) -> (Vec<Vec<Mersenne61>>, usize) {
+        let mut participants = create_participants(0);
+        for protocol in participants.iter_mut() {
+            protocol.set_concurrency(concurrency);
+        }
+        let gauge = participants[0].channel.transport.gauge();
+
+        let inverses = block_on(join_all(
+            participants.iter_mut().zip(share_secrets(0, elements)).map(
+                |(protocol, shares)| async move {
+                    let mut rng = StdRng::seed_from_u64(protocol.participant_id() as u64);
+                    TestCliqueProtocol::unbounded_inverse(&mut rng, protocol, &shares).await
+                },
+            ),
+        ));
+        let peak = gauge.peak();
+
+        let revealed = block_on(join_all(participants.iter_mut().zip(inverses).map(
+            |(protocol, inverses)| async move {
+                join_all(inverses.into_iter().map(|i| protocol.reveal_shares(i))).await
+            },
+        )));
+        (revealed, peak)
+    }
+
+    #[test]
+    fn test_unbounded_inversion_limited_concurrency() {
+        const LIMIT: usize = 8;
+        let elements = (1..=100)
+            .map(|element| Mersenne61::from_usize(element).unwrap())
+            .collect::<Vec<_>>();
+
+        let (revealed, peak) = run_inversions(ProtocolConcurrency::Limited(LIMIT), &elements);
+        for inverses in revealed {
+            assert_eq!(inverses.len(), elements.len());
+            for (element, inverse) in elements.iter().zip(inverses) {
+                assert_eq!(element.clone() * inverse, Mersenne61::one());
+            }
+        }
+
+        // a participant starts an operation only after it completed the operation `LIMIT` places before, which
+        // requires the messages of all other participants. Thus every participant is at most `LIMIT` operations
+        // ahead of the operations another participant received, and at most `2 * LIMIT` messages are in flight
+        // between any ordered pair of participants.
+        let bound = PARTICIPANT_COUNT * (PARTICIPANT_COUNT - 1) * 2 * LIMIT;
+        assert!(peak <= bound, "{} messages were in flight", peak);
+
+        let (_, unlimited_peak) = run_inversions(ProtocolConcurrency::Unlimited, &elements);
+        assert!(unlimited_peak > bound);
+    }
+
+    /// Multiply the `partners` with the inverses of the `elements` over `Mersenne31`, either with the fused
+    /// `unbounded_inverse_then_multiply` or with an inversion followed by a multiplication.
+    ///
+    /// # Returns
+    /// The products revealed by every participant
+    fn run_inverse_then_multiply(
+        fused: bool,
+        elements: &[Mersenne31],
+        partners: &[Mersenne31],
+    ) -> Vec<Vec<Mersenne31>> {
+        let mut participants = create_participants::<Mersenne31>(0);
+
+        block_on(join_all(
+            participants
+                .iter_mut()
+                .zip(share_secrets(0, elements))
+                .zip(share_secrets(1, partners))
+                .map(|((protocol, elements), partners)| async move {
+                    let mut rng = StdRng::seed_from_u64(protocol.participant_id() as u64);
+                    let products = if fused {
+                        FieldCliqueProtocol::unbounded_inverse_then_multiply(
+                            &mut rng, protocol, &elements, &partners,
+                        )
+                        .await
+                    } else {
+                        let inverses =
+                            FieldCliqueProtocol::unbounded_inverse(&mut rng, protocol, &elements)
+                                .await;
+                        FieldCliqueProtocol::unbounded_multiply(
+                            protocol,
+                            &partners.into_iter().zip(inverses).collect::<Vec<_>>(),
+                        )
+                        .await
+                    };
+                    join_all(products.into_iter().map(|p| protocol.reveal_shares(p))).await
+                }),
+        ))
+    }
+
+    #[test]
+    fn test_unbounded_inverse_then_multiply() {
+        let mut rng = StdRng::seed_from_u64(31);
+        let mut random_elements = |count: usize| {
+            (0..count)
+                .map(|_| loop {
+                    let element: Mersenne31 = rng.next_field_element("test");
+                    if !element.is_zero() {
+                        break element;
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+        let elements = random_elements(20);
+        let partners = random_elements(20);
+
+        let fused = run_inverse_then_multiply(true, &elements, &partners);
+        assert_eq!(fused, run_inverse_then_multiply(false, &elements, &partners));
+        for products in fused {
+            for ((element, partner), product) in elements.iter().zip(&partners).zip(products) {
+                assert_eq!(element.clone() * product, partner.clone());
+            }
+        }
+    }
+
+    type Share = (usize, Mersenne61);
+
+    /// A round of a protocol driven in rounds, so the tests can drive all rounds alike
+    trait TestRound: Sized {
+        type Next;
+
+        fn messages(&self) -> Vec<(usize, Vec<u8>)>;
+
+        fn deliver(self, from: usize, payload: &[u8]) -> RoundTransition<Self, Self::Next>;
+    }
+
+    macro_rules! impl_test_round {
+        ($round:ty, $next:ty) => {
+            impl TestRound for $round {
+                type Next = $next;
+
+                fn messages(&self) -> Vec<(usize, Vec<u8>)> {
+                    self.messages_to_send()
+                }
+
+                fn deliver(self, from: usize, payload: &[u8]) -> RoundTransition<Self, Self::Next> {
+                    self.receive(from, payload)
+                }
+            }
+        };
+    }
+
+    impl_test_round!(
+        BeaverOpeningRound<Mersenne61, Share, ShamirSecretSharing>,
+        FinishedRounds<Share>
+    );
+    impl_test_round!(
+        InversionRandomnessRound<Mersenne61, Share, ShamirSecretSharing>,
+        InversionMultiplicationRound<Mersenne61, Share, ShamirSecretSharing>
+    );
+    impl_test_round!(
+        InversionMultiplicationRound<Mersenne61, Share, ShamirSecretSharing>,
+        InversionRevealRound<Mersenne61, Share, ShamirSecretSharing>
+    );
+    impl_test_round!(
+        InversionRevealRound<Mersenne61, Share, ShamirSecretSharing>,
+        FinishedRounds<Share>
+    );
+
+    /// Deliver the messages of one round of all participants in an order shuffled by `rng`.
+    ///
+    /// # Returns
+    /// The next rounds of all participants
+    fn run_round<R>(rounds: Vec<R>, rng: &mut StdRng) -> Vec<R::Next>
+    where
+        R: TestRound,
+    {
+        let mut messages = rounds
+            .iter()
+            .enumerate()
+            .flat_map(|(index, round)| {
+                round
+                    .messages()
+                    .into_iter()
+                    .map(move |(recipient, payload)| (index + 1, recipient, payload))
+            })
+            .collect::<Vec<_>>();
+        rng.shuffle(&mut messages);
+
+        let mut rounds = rounds
+            .into_iter()
+            .map(Ok)
+            .collect::<Vec<Result<R, R::Next>>>();
+        for (sender, recipient, payload) in messages {
+            let round = match rounds.remove(recipient - 1) {
+                Ok(round) => round,
+                Err(_) => panic!(
+                    "participant {} advanced before receiving all messages",
+                    recipient
+                ),
+            };
+
+            let progress = match round.deliver(sender, &payload) {
+                RoundTransition::Pending(round) => Ok(round),
+                RoundTransition::Next(next) => Err(next),
+                RoundTransition::Rejected(_, exception) => {
+                    panic!("message rejected: {:?}", exception)
+                }
+            };
+            rounds.insert(recipient - 1, progress);
+        }
+
+        rounds
+            .into_iter()
+            .map(|progress| match progress {
+                Err(next) => next,
+                Ok(_) => panic!("a participant did not receive all messages"),
+            })
+            .collect()
+    }
+
+    /// Reveal the secrets of the `shares` of all participants, of which the shares of each participant are in one
+    /// vector.
+    fn reveal_all(shares: &[Vec<Share>]) -> Vec<Mersenne61> {
+        (0..shares[0].len())
+            .map(|index| {
+                let secret_shares = shares.iter().map(|s| s[index].clone()).collect::<Vec<_>>();
+                ShamirSecretSharing::reconstruct_secret(&secret_shares, THRESHOLD).unwrap()
+            })
+            .collect()
+    }
+
+    /// Obtain the beaver triples of all participants, which are the same as the first triples they obtain in a run
+    /// of `create_participants(seed)`.
+    fn dealt_triples(seed: u64, count: usize) -> Vec<Vec<(Share, Share, Share)>> {
+        (1..=PARTICIPANT_COUNT)
+            .map(|participant_id| {
+                let mut dealer = TestTripleDealer::new(participant_id, seed);
+                block_on(dealer.obtain_beaver_triples(count))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_beaver_rounds_match_futures() {
+        let factors = [3, 5, 11, 7, 2, 13]
+            .iter()
+            .map(|factor| Mersenne61::from_usize(*factor).unwrap())
+            .collect::<Vec<_>>();
+        let pairs = share_secrets(0, &factors)
+            .into_iter()
+            .map(|shares| {
+                let (lhs, rhs) = shares.split_at(3);
+                lhs.iter()
+                    .cloned()
+                    .zip(rhs.iter().cloned())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut participants = create_participants(0);
+        let futures_products =
+            block_on(join_all(participants.iter_mut().zip(pairs.clone()).map(
+                |(protocol, pairs)| TestCliqueProtocol::unbounded_multiply(protocol, &pairs),
+            )));
+
+        let rounds = pairs
+            .iter()
+            .zip(dealt_triples(0, 3))
+            .enumerate()
+            .map(|(index, (pairs, triples))| {
+                BeaverMultiplyRounds::new::<_, _, ShamirSecretSharing>(
+                    RoundParticipants::new(index + 1, PARTICIPANT_COUNT, THRESHOLD),
+                    pairs,
+                    triples,
+                )
+            })
+            .collect::<Vec<_>>();
+        let round_products = run_round(rounds, &mut StdRng::seed_from_u64(1))
+            .into_iter()
+            .map(FinishedRounds::finish)
+            .collect::<Vec<_>>();
+
+        // both paths use the same triples, so they compute the same shares
+        assert_eq!(round_products, futures_products);
+        assert_eq!(
+            reveal_all(&round_products),
+            vec![21, 10, 143]
+                .into_iter()
+                .map(|product| Mersenne61::from_usize(product).unwrap())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_pooled_triples_multiply() {
+        let factors = [3, 5, 11, 7, 2, 13]
+            .iter()
+            .map(|factor| Mersenne61::from_usize(*factor).unwrap())
+            .collect::<Vec<_>>();
+        let pairs = share_secrets(0, &factors)
+            .into_iter()
+            .map(|shares| {
+                let (lhs, rhs) = shares.split_at(3);
+                lhs.iter()
+                    .cloned()
+                    .zip(rhs.iter().cloned())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut participants = InMemoryTransport::create_network(PARTICIPANT_COUNT)
+            .into_iter()
+            .map(|transport| {
+                let participant_id = transport.participant_id();
+                ShamirCliqueProtocol::<Mersenne61, _, _>::with_rng(
+                    participant_id,
+                    PARTICIPANT_COUNT,
+                    THRESHOLD,
+                    transport,
+                    TriplePool::new(
+                        4,
+                        TestTripleDealer::new(participant_id, 0),
+                    ),
+                    StdRng::seed_from_u64(participant_id as u64),
+                )
+            })
+            .collect::<Vec<_>>();
+        for protocol in &mut participants {
+            block_on(protocol.triple_source_mut().refill());
+        }
+
+        let products = block_on(join_all(participants.iter_mut().zip(pairs).map(
+            |(protocol, pairs)| {
+                BeaverRerandomizationMultiplication::<Mersenne61, Share, _>::unbounded_multiply(
+                    protocol, &pairs,
+                )
+            },
+        )));
+        assert_eq!(
+            reveal_all(&products),
+            vec![21, 10, 143]
+                .into_iter()
+                .map(|product| Mersenne61::from_usize(product).unwrap())
+                .collect::<Vec<_>>()
+        );
+
+        // the pool holds the triple the multiplication did not use, and the dealer's triples after a refill
+        let dealt = dealt_triples(0, 5);
+        for (protocol, dealt) in participants.iter_mut().zip(dealt) {
+            let pool = protocol.triple_source_mut();
+            assert_eq!(pool.len(), 1);
+            assert_eq!(block_on(pool.refill()), 3);
+            assert_eq!(pool.take(2).unwrap(), dealt[3..].to_vec());
+        }
+    }
+
+    #[test]
+    fn test_inversion_rounds() {
+        let elements = vec![
+            Mersenne61::from_usize(1).unwrap(),
+            Mersenne61::from_usize(4).unwrap(),
+            Mersenne61::from_usize(6).unwrap(),
+        ];
+        let mut rng = StdRng::seed_from_u64(2);
+
+        let rounds = share_secrets(0, &elements)
+            .iter()
+            .zip(dealt_triples(0, elements.len()))
+            .enumerate()
+            .map(|(index, (shares, triples))| {
+                UnboundedInversionRounds::new::<_, _, ShamirSecretSharing, _>(
+                    &mut StdRng::seed_from_u64(index as u64),
+                    RoundParticipants::new(index + 1, PARTICIPANT_COUNT, THRESHOLD),
+                    shares,
+                    triples,
+                )
+            })
+            .collect::<Vec<_>>();
+        let rounds = run_round(rounds, &mut rng);
+        let rounds = run_round(rounds, &mut rng);
+        let inverses = run_round(rounds, &mut rng)
+            .into_iter()
+            .map(FinishedRounds::finish)
+            .collect::<Vec<_>>();
+
+        for (element, inverse) in elements.iter().zip(reveal_all(&inverses)) {
+            assert_eq!(element.clone() * inverse, Mersenne61::one());
+        }
+    }
+
+    /// The size of the set in `test_private_set_membership`
+    const SET_SIZE: usize = 16;
+
+    /// Compute whether the element at `query` is in the set described by the membership `flags` of all elements,
+    /// with both the flags and the one-hot encoded query shared among all participants. Only the membership bit is
+    /// revealed: the flags are multiplied with the query bits, so at most the product of the queried element is
+    /// non-zero, and the OR of all products is the membership bit.
+    fn private_set_membership(flags: &[bool], query: usize) -> Vec<Mersenne61> {
+        let bit = |b: bool| Mersenne61::from_usize(b as usize).unwrap();
+        let mut secrets: Vec<_> = flags.iter().map(|flag| bit(*flag)).collect();
+        secrets.extend((0..flags.len()).map(|index| bit(index == query)));
+
+        let mut participants = create_participants(query as u64);
+        block_on(join_all(
+            participants
+                .iter_mut()
+                .zip(share_secrets(query as u64, &secrets))
+                .map(|(protocol, shares)| async move {
+                    let mut rng = StdRng::seed_from_u64(protocol.participant_id() as u64);
+                    let (flags, selectors) = shares.split_at(shares.len() / 2);
+
+                    let products = TestCliqueProtocol::unbounded_multiply(
+                        protocol,
+                        &flags
+                            .iter()
+                            .cloned()
+                            .zip(selectors.iter().cloned())
+                            .collect::<Vec<_>>(),
+                    )
+                    .await;
+                    let membership =
+                        TestCliqueProtocol::unbounded_shared_or(&mut rng, protocol, &products)
+                            .await
+                            .unwrap();
+                    protocol.reveal_shares(membership).await
+                }),
+        ))
+    }
+
+    #[test]
+    fn test_private_set_membership() {
+        let flags: Vec<_> = (0..SET_SIZE).map(|index| index % 3 == 0).collect();
+
+        // the OR polynomial of degree 16 is far larger than in the other tests, so this also guards its performance
+        let start = Instant::now();
+        for query in &[0, 5, 9, 15] {
+            let expected = Mersenne61::from_usize(flags[*query] as usize).unwrap();
+            assert!(private_set_membership(&flags, *query)
+                .iter()
+                .all(|membership| *membership == expected));
+        }
+        assert!(start.elapsed() < Duration::from_secs(30));
+    }
+}
@@ -1,6 +1,7 @@
 use crate::{
     CliqueCommunicationScheme, ConditionalSelectionScheme, LinearSharingScheme,
     MultiplicationScheme, PrimeField, ThresholdSecretSharingScheme,
+    UnboundedConditionalSelectionScheme, UnboundedMultiplicationScheme,
 };
 use futures::Future;
 use std::marker::PhantomData;
@@ -33,8 +34,12 @@ where
         condition: &S,
         lhs: &S,
         rhs: &S,
-    ) -> Pin<Box<dyn Future<Output = S> + 'a>> {
-        let operands_difference = P::sub_shares(lhs, rhs);
+    ) -> Pin<Box<dyn Future<Output = S> + Send + 'a>> {
+        let operands_difference = P::linear_combination(
+            &[lhs.clone(), rhs.clone()],
+            &[T::one(), T::zero() - T::one()],
+        )
+        .unwrap();
         let rhs = rhs.clone();
         let condition = condition.clone();
 
@@ -44,3 +49,47 @@ where
         })
     }
 }
+
+impl<T, S, P> UnboundedConditionalSelectionScheme<T, S, P> for JointConditionalSelection<T, S, P>
+where
+    P: ThresholdSecretSharingScheme<T, S>
+        + LinearSharingScheme<T, S>
+        + CliqueCommunicationScheme<T, S>
+        + MultiplicationScheme<T, S, P>
+        + UnboundedMultiplicationScheme<T, S, P>
+        + Send
+        + Sync,
+    T: Send + Sync + PrimeField,
+    S: Send + Sync + Clone + 'static,
+{
+    fn unbounded_conditional_selection<'a>(
+        protocol: &'a mut P,
+        selections: &[(S, S, S)],
+    ) -> Pin<Box<dyn Future<Output = Vec<S>> + Send + 'a>> {
+        if selections.is_empty() {
+            return Box::pin(async { vec![] });
+        }
+
+        let pairs: Vec<_> = selections
+            .iter()
+            .map(|(condition, lhs, rhs)| {
+                let operands_difference = P::linear_combination(
+                    &[lhs.clone(), rhs.clone()],
+                    &[T::one(), T::zero() - T::one()],
+                )
+                .unwrap();
+                (condition.clone(), operands_difference)
+            })
+            .collect();
+        let rhs: Vec<_> = selections.iter().map(|(_, _, rhs)| rhs.clone()).collect();
+
+        Box::pin(async move {
+            P::unbounded_multiply(protocol, &pairs)
+                .await
+                .iter()
+                .zip(rhs)
+                .map(|(product, rhs)| P::add_shares(product, &rhs))
+                .collect()
+        })
+    }
+}
@@ -7,7 +7,8 @@ pub mod joint_conditional_selection;
 
 /// A protocol for the joint selection of either side of a ternary expression `condition ? lhs : rhs` without
 /// any participant learning the value of `condition` or the expression chosen by the protocol. This protocol cannot
-/// be invoked in parallel, as it uses a two-stage multiplication protocol.
+/// be invoked in parallel, as it uses a two-stage multiplication protocol. Use `UnboundedConditionalSelectionScheme`
+/// to perform multiple selections at once.
 #[delegatable_protocol]
 pub trait ConditionalSelectionScheme<T, S, P>
 where
@@ -30,5 +31,27 @@ where
         condition: &S,
         lhs: &S,
         rhs: &S,
-    ) -> Pin<Box<dyn Future<Output = S> + 'a>>;
+    ) -> Pin<Box<dyn Future<Output = S> + Send + 'a>>;
+}
+
+/// An extension to `ConditionalSelectionScheme` that performs multiple selections at once, using a single round of
+/// multiplication for all of them.
+#[delegatable_protocol]
+pub trait UnboundedConditionalSelectionScheme<T, S, P>
+where
+    P: Send + Sync,
+    T: Send + Sync,
+    S: Send + Sync,
+{
+    /// Select either side of each ternary expression `condition ? lhs : rhs` in parallel.
+    /// # Parameters
+    /// - `protocol` an instance of the sub-protocols used. See `ConditionalSelectionScheme`.
+    /// - `selections` triples of `(condition, lhs, rhs)`, where each `condition` is a share on either `0` or `1`
+    ///
+    /// # Returns
+    /// Returns a future on the selected shares, in the order of `selections`
+    fn unbounded_conditional_selection<'a>(
+        protocol: &'a mut P,
+        selections: &[(S, S, S)],
+    ) -> Pin<Box<dyn Future<Output = Vec<S>> + Send + 'a>>;
 }
@@ -0,0 +1,194 @@
+//! Object-safe façades of the sharing scheme traits. `ThresholdSecretSharingScheme` and `LinearSharingScheme` only
+//! have associated functions, so a scheme cannot be chosen at runtime. `DynSharingScheme` offers the same operations
+//! as methods on `&self`, so schemes can be held as `Box<dyn DynSharingScheme<T>>`, for example after selecting one
+//! by name with `sharing_scheme_by_name`.
+
+use std::marker::PhantomData;
+
+use jester_maths::rng::{CryptoRngCore, DynCryptoRng};
+
+use crate::additive_secret_sharing::{AdditiveSecretSharing, AdditiveShare};
+use crate::shamir_secret_sharing::ShamirSecretSharing;
+use crate::{
+    LinearSharingScheme, PrimeField, ReconstructionException, ThresholdSecretSharingScheme,
+};
+
+/// A share that can be represented as the index of the participant holding it and a field element. Shares of all
+/// schemes behind a `DynSharingScheme` are exchanged in this representation, so their types do not differ.
+pub trait IndexedShare<T> {
+    /// The index and value of the share
+    fn to_indexed(&self) -> (usize, T);
+
+    /// Restore a share from its index and value
+    fn from_indexed(index: usize, value: T) -> Self;
+}
+
+impl<T> IndexedShare<T> for (usize, T)
+where
+    T: Clone,
+{
+    fn to_indexed(&self) -> (usize, T) {
+        self.clone()
+    }
+
+    fn from_indexed(index: usize, value: T) -> Self {
+        (index, value)
+    }
+}
+
+impl<T> IndexedShare<T> for AdditiveShare<T>
+where
+    T: Clone,
+{
+    fn to_indexed(&self) -> (usize, T) {
+        (self.index, self.value.clone())
+    }
+
+    fn from_indexed(index: usize, value: T) -> Self {
+        AdditiveShare { index, value }
+    }
+}
+
+/// An object-safe linear threshold secret sharing scheme. Shares are represented as pairs of the participant index
+/// and a field element (see `IndexedShare`).
+///
+/// # Type Parameters
+/// - `T` type of secrets that can be shared using this scheme
+pub trait DynSharingScheme<T> {
+    /// The name the scheme is selected by in `sharing_scheme_by_name`
+    fn name(&self) -> &'static str;
+
+    /// Generate `count` shares of `secret`, of which `threshold` are required for reconstruction. See
+    /// `ThresholdSecretSharingScheme::generate_shares`.
+    fn generate_shares(
+        &self,
+        rng: &mut dyn CryptoRngCore,
+        secret: &T,
+        count: usize,
+        threshold: usize,
+    ) -> Vec<(usize, T)>;
+
+    /// Reconstruct the secret from at least `threshold` shares. See
+    /// `ThresholdSecretSharingScheme::reconstruct_secret`.
+    fn reconstruct_secret(
+        &self,
+        shares: &[(usize, T)],
+        threshold: usize,
+    ) -> Result<T, ReconstructionException>;
+
+    /// Sum two shares resulting in a new share of their secrets' sum.
+    fn add_shares(&self, lhs: &(usize, T), rhs: &(usize, T)) -> (usize, T);
+
+    /// Subtract `rhs` from `lhs` resulting in a new share of their secrets' difference.
+    fn sub_shares(&self, lhs: &(usize, T), rhs: &(usize, T)) -> (usize, T);
+
+    /// Add a scalar of the field `T` to the `share`
+    fn add_scalar(&self, share: &(usize, T), scalar: &T) -> (usize, T);
+
+    /// Multiply a `share` with a `scalar` of the field `T`.
+    fn multiply_scalar(&self, share: &(usize, T), scalar: &T) -> (usize, T);
+}
+
+/// Adapter implementing `DynSharingScheme` for a scheme `P` implementing the static sharing traits with shares of
+/// type `S`.
+pub struct DynSharingAdapter<P, S> {
+    name: &'static str,
+    scheme: PhantomData<(P, S)>,
+}
+
+impl<P, S> DynSharingAdapter<P, S> {
+    /// Wrap the scheme `P`, which is selected by `name`.
+    pub fn new(name: &'static str) -> Self {
+        DynSharingAdapter {
+            name,
+            scheme: PhantomData,
+        }
+    }
+}
+
+impl<T, S, P> DynSharingScheme<T> for DynSharingAdapter<P, S>
+where
+    T: Clone,
+    P: ThresholdSecretSharingScheme<T, S> + LinearSharingScheme<T, S>,
+    S: IndexedShare<T>,
+{
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn generate_shares(
+        &self,
+        rng: &mut dyn CryptoRngCore,
+        secret: &T,
+        count: usize,
+        threshold: usize,
+    ) -> Vec<(usize, T)> {
+        P::generate_shares(&mut DynCryptoRng::new(rng), secret, count, threshold)
+            .iter()
+            .map(IndexedShare::to_indexed)
+            .collect()
+    }
+
+    fn reconstruct_secret(
+        &self,
+        shares: &[(usize, T)],
+        threshold: usize,
+    ) -> Result<T, ReconstructionException> {
+        P::reconstruct_secret(&to_shares(shares), threshold)
+    }
+
+    fn add_shares(&self, lhs: &(usize, T), rhs: &(usize, T)) -> (usize, T) {
+        P::add_shares(&to_share(lhs), &to_share(rhs)).to_indexed()
+    }
+
+    fn sub_shares(&self, lhs: &(usize, T), rhs: &(usize, T)) -> (usize, T) {
+        P::sub_shares(&to_share(lhs), &to_share(rhs)).to_indexed()
+    }
+
+    fn add_scalar(&self, share: &(usize, T), scalar: &T) -> (usize, T) {
+        P::add_scalar(&to_share(share), scalar).to_indexed()
+    }
+
+    fn multiply_scalar(&self, share: &(usize, T), scalar: &T) -> (usize, T) {
+        P::multiply_scalar(&to_share(share), scalar).to_indexed()
+    }
+}
+
+/// Convert an indexed share into the share type of a scheme.
+fn to_share<T, S>(share: &(usize, T)) -> S
+where
+    T: Clone,
+    S: IndexedShare<T>,
+{
+    S::from_indexed(share.0, share.1.clone())
+}
+
+/// Convert indexed shares into the share type of a scheme.
+fn to_shares<T, S>(shares: &[(usize, T)]) -> Vec<S>
+where
+    T: Clone,
+    S: IndexedShare<T>,
+{
+    shares.iter().map(to_share).collect()
+}
+
+/// Select one of the sharing schemes of this crate by name. The names are `"shamir"` for
+/// `ShamirSecretSharing` and `"additive"` for `AdditiveSecretSharing`.
+///
+/// # Returns
+/// The scheme, or `None` if no scheme has the given name.
+pub fn sharing_scheme_by_name<T>(name: &str) -> Option<Box<dyn DynSharingScheme<T>>>
+where
+    T: PrimeField + 'static,
+{
+    match name {
+        "shamir" => Some(Box::new(
+            DynSharingAdapter::<ShamirSecretSharing, (usize, T)>::new("shamir"),
+        )),
+        "additive" => Some(Box::new(DynSharingAdapter::<
+            AdditiveSecretSharing,
+            AdditiveShare<T>,
+        >::new("additive"))),
+        _ => None,
+    }
+}
@@ -1,11 +1,12 @@
+use crate::FieldRng;
 use crate::{Delegate, LinearSharingScheme, PrimeField, ThresholdSecretSharingScheme};
 use futures::Future;
-use rand::{CryptoRng, RngCore};
 use std::pin::Pin;
 
 use jester_sharing_proc::delegatable_protocol;
 
 pub mod unbounded_inversion;
+pub mod unbounded_inversion_rounds;
 
 /// A trait to generate the multiplicative inverse of a secret shared among the participants.
 /// # Type Parameters
@@ -15,9 +16,9 @@ pub mod unbounded_inversion;
 #[delegatable_protocol]
 pub trait InversionScheme<T, S, P>
 where
-    P: ThresholdSecretSharingScheme<T, S> + LinearSharingScheme<T, S>,
-    T: PrimeField,
-    S: Clone + 'static,
+    P: ThresholdSecretSharingScheme<T, S> + LinearSharingScheme<T, S> + Send + Sync,
+    T: PrimeField + Send + Sync,
+    S: Clone + Send + Sync + 'static,
 {
     /// Asynchronously calculate the multiplicative inverse of the secret shared by `share`. If the secret evaluates
     /// to zero, the output of this function is undefined. Given the nature of secret sharing, it is impossible for
@@ -30,9 +31,9 @@ where
         rng: &'a mut R,
         protocol: &'a mut P,
         share: &S,
-    ) -> Pin<Box<dyn Future<Output = S> + 'a>>
+    ) -> Pin<Box<dyn Future<Output = S> + Send + 'a>>
     where
-        R: RngCore + CryptoRng;
+        R: FieldRng;
 }
 
 /// A trait to generate the multiplicative inverse of a set of secrets shared among the participants. Can be used to
@@ -44,9 +45,9 @@ where
 #[delegatable_protocol]
 pub trait UnboundedInversionScheme<T, S, P>
 where
-    P: ThresholdSecretSharingScheme<T, S> + LinearSharingScheme<T, S>,
-    T: PrimeField,
-    S: Clone + 'static,
+    P: ThresholdSecretSharingScheme<T, S> + LinearSharingScheme<T, S> + Send + Sync,
+    T: PrimeField + Send + Sync,
+    S: Clone + Send + Sync + 'static,
 {
     /// Asynchronously calculate the multiplicative inverse of the secrets shared by `shares`. If a secret evaluates
     /// to zero, the result of its inverse is undefined. Given the nature of secret sharing, it is impossible for
@@ -59,7 +60,28 @@ where
         rng: &'a mut R,
         protocol: &'a mut P,
         shares: &[S],
-    ) -> Pin<Box<dyn Future<Output = Vec<S>> + 'a>>
+    ) -> Pin<Box<dyn Future<Output = Vec<S>> + Send + 'a>>
     where
-        R: RngCore + CryptoRng;
+        R: FieldRng;
+
+    /// Asynchronously calculate the products `partners[i] * elements[i]^-1` of the secrets shared by `elements` and
+    /// `partners`. This is equivalent to `unbounded_inverse` of the elements followed by an unbounded multiplication
+    /// with the partners, but implementations may save communication rounds by multiplying the partners together with
+    /// the elements. If an element evaluates to zero, its result is undefined.
+    /// # Parameters
+    /// - `rng` a cryptographically secure random number generator
+    /// - `protocol` the primitives required for this scheme
+    /// - `elements` shares of the secrets to invert
+    /// - `partners` shares of the secrets to multiply with the inverses, one per element
+    ///
+    /// # Panics
+    /// If the number of partners differs from the number of elements.
+    fn unbounded_inverse_then_multiply<'a, R>(
+        rng: &'a mut R,
+        protocol: &'a mut P,
+        elements: &[S],
+        partners: &[S],
+    ) -> Pin<Box<dyn Future<Output = Vec<S>> + Send + 'a>>
+    where
+        R: FieldRng;
 }
@@ -1,11 +1,12 @@
-use futures::future::join_all;
+use crate::inversion::unbounded_inversion_rounds::UnboundedInversionRounds;
 
 use crate::{
-    CliqueCommunicationScheme, InversionScheme, LinearSharingScheme, RandomNumberGenerationScheme,
-    ThresholdSecretSharingScheme, UnboundedInversionScheme, UnboundedMultiplicationScheme,
+    join_limited, CliqueCommunicationScheme, InversionScheme, LinearSharingScheme,
+    RandomNumberGenerationScheme, ThresholdSecretSharingScheme, UnboundedInversionScheme,
+    UnboundedMultiplicationScheme,
 };
 
-use crate::{CryptoRng, PrimeField, RngCore};
+use crate::{FieldRng, PrimeField};
 use futures::Future;
 use std::marker::PhantomData;
 use std::pin::Pin;
@@ -14,6 +15,11 @@ use std::pin::Pin;
 /// `CliqueCommunicationScheme`. This protocol relies on the fact, tht the input parameters are not zero. If one
 /// input parameter is a share on the value zero, the protocol will output random garbage. Since the garbage might not
 /// lead to a successful calculation, participants could learn that the input had at least one zero in it.
+///
+/// The protocol generates one helper and reveals one element per input, of which at most as many are in flight at
+/// once as the `ProtocolConfig::concurrency` of the protocol permits. `unbounded_inverse_then_multiply` additionally
+/// multiplies the partners with the same helpers in the multiplication round, so it requires two round-trip-times as
+/// well.
 pub struct JointUnboundedInversion<T, S, P>
 where
     P: ThresholdSecretSharingScheme<T, S>
@@ -47,15 +53,12 @@ where
         rng: &'a mut R,
         protocol: &'a mut P,
         share: &S,
-    ) -> Pin<Box<dyn Future<Output = S> + 'a>>
+    ) -> Pin<Box<dyn Future<Output = S> + Send + 'a>>
     where
-        R: RngCore + CryptoRng,
+        R: FieldRng,
     {
-        let share_clone = share.clone();
-        Box::pin(async move {
-            let mut inverse_vec = Self::unbounded_inverse(rng, protocol, &[share_clone]).await;
-            inverse_vec.pop().unwrap()
-        })
+        let inverse_vec = Self::unbounded_inverse(rng, protocol, std::slice::from_ref(share));
+        Box::pin(async move { inverse_vec.await.pop().unwrap() })
     }
 }
 
@@ -75,18 +78,23 @@ where
         rng: &'a mut R,
         protocol: &'a mut P,
         shares: &[S],
-    ) -> Pin<Box<dyn Future<Output = Vec<S>> + 'a>>
+    ) -> Pin<Box<dyn Future<Output = Vec<S>> + Send + 'a>>
     where
-        R: RngCore + CryptoRng,
+        R: FieldRng,
     {
+        if shares.is_empty() {
+            return Box::pin(async { vec![] });
+        }
+
         let bound = shares.len();
+        let concurrency = protocol.concurrency();
         let helpers: Vec<_> = (0..bound)
             .map(|_| P::generate_random_number_sharing(rng, protocol))
             .collect();
         let shares_iter = shares.to_vec();
 
         Box::pin(async move {
-            let helpers = join_all(helpers).await;
+            let helpers = join_limited(concurrency, helpers).await;
 
             let rerandomized_elements = P::unbounded_multiply(
                 protocol,
@@ -99,16 +107,75 @@ where
 
             let revealed_elements = rerandomized_elements
                 .into_iter()
-                .map(|e| protocol.reveal_shares(e));
-            let revealed_elements = join_all(revealed_elements).await;
+                .map(|e| {
+                    let tag = protocol.new_session_tag();
+                    protocol.reveal_shares_tagged(tag, e)
+                })
+                .collect();
+            let revealed_elements = join_limited(concurrency, revealed_elements).await;
+
+            UnboundedInversionRounds::inverses::<T, S, P>(helpers, &revealed_elements)
+        })
+    }
+
+    /// Rerandomize each element `[x]` and its partner `[y]` with the same helper `[r]` in a single multiplication
+    /// round, and reveal only `x * r`. The result `[y * r] * (x * r)^-1` is the partner multiplied with the inverse of
+    /// the element. This requires one round less than an inversion followed by a multiplication, at the same number
+    /// of beaver triples and reveals.
+    fn unbounded_inverse_then_multiply<'a, R>(
+        rng: &'a mut R,
+        protocol: &'a mut P,
+        elements: &[S],
+        partners: &[S],
+    ) -> Pin<Box<dyn Future<Output = Vec<S>> + Send + 'a>>
+    where
+        R: FieldRng,
+    {
+        assert_eq!(
+            elements.len(),
+            partners.len(),
+            "every element requires exactly one partner"
+        );
+
+        if elements.is_empty() {
+            return Box::pin(async { vec![] });
+        }
 
-            revealed_elements
+        let bound = elements.len();
+        let concurrency = protocol.concurrency();
+        let helpers: Vec<_> = (0..bound)
+            .map(|_| P::generate_random_number_sharing(rng, protocol))
+            .collect();
+        let factors = elements
+            .iter()
+            .chain(partners.iter())
+            .cloned()
+            .collect::<Vec<_>>();
+
+        Box::pin(async move {
+            let helpers = join_limited(concurrency, helpers).await;
+
+            // the elements and the partners are multiplied with the same helpers in one round
+            let mut rerandomized = P::unbounded_multiply(
+                protocol,
+                &factors
+                    .into_iter()
+                    .zip(helpers.iter().chain(helpers.iter()).cloned())
+                    .collect::<Vec<_>>(),
+            )
+            .await;
+            let rerandomized_partners = rerandomized.split_off(bound);
+
+            let revealed_elements = rerandomized
                 .into_iter()
-                .zip(helpers)
-                .map(|(hidden_element, helper)| {
-                    P::multiply_scalar(&helper, &hidden_element.inverse())
+                .map(|e| {
+                    let tag = protocol.new_session_tag();
+                    protocol.reveal_shares_tagged(tag, e)
                 })
-                .collect()
+                .collect();
+            let revealed_elements = join_limited(concurrency, revealed_elements).await;
+
+            UnboundedInversionRounds::inverses::<T, S, P>(rerandomized_partners, &revealed_elements)
         })
     }
 }
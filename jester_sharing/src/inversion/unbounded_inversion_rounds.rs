@@ -0,0 +1,292 @@
+//! Unbounded inversion driven in rounds (see `communication::rounds`). `JointUnboundedInversion` performs the same
+//! steps through a `CliqueCommunicationScheme`, but obtains the random helpers and multiplies through the schemes
+//! its protocol delegates to.
+
+use std::marker::PhantomData;
+
+use crate::multiplication::beaver_multiply_rounds::{BeaverMultiplyRounds, BeaverOpeningRound};
+use crate::rounds::{encode_field_elements, receive_field_elements};
+use crate::{
+    FieldRng, FinishedRounds, IndexedShare, LinearSharingScheme, OpeningRound, PrimeField,
+    RoundException, RoundParticipants, RoundTransition, ThresholdSecretSharingScheme,
+};
+
+/// Entry point of an unbounded inversion driven in rounds. The inversion requires three rounds: the participants
+/// jointly generate a random helper `[r]` for each element `[x]` in `InversionRandomnessRound`, multiply them in
+/// `InversionMultiplicationRound` and open the products `x * r` in `InversionRevealRound`. The inverse is the
+/// helper multiplied with the inverse of the opened product. Like `JointUnboundedInversion`, the output is garbage
+/// if an element is zero.
+pub struct UnboundedInversionRounds;
+
+impl UnboundedInversionRounds {
+    /// Start inverting the secrets of `shares`, using one beaver triple `([a], [b], [c])` with `c = a * b` per share
+    /// for the multiplication. All participants must use shares of the same triples in the same order.
+    ///
+    /// # Type Parameters
+    /// - `T` the secret type
+    /// - `S` the share type
+    /// - `P` the sharing scheme of the shares
+    ///
+    /// # Parameters
+    /// - `rng` a cryptographically secure random number generator
+    /// - `participants` the participants of the inversion
+    /// - `shares` shares of the secrets to invert
+    /// - `triples` one beaver triple per share
+    ///
+    /// # Panics
+    /// If the number of triples differs from the number of shares.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new<T, S, P, R>(
+        rng: &mut R,
+        participants: RoundParticipants,
+        shares: &[S],
+        triples: Vec<(S, S, S)>,
+    ) -> InversionRandomnessRound<T, S, P>
+    where
+        T: PrimeField,
+        S: IndexedShare<T> + Clone,
+        P: ThresholdSecretSharingScheme<T, S> + LinearSharingScheme<T, S>,
+        R: FieldRng,
+    {
+        assert_eq!(
+            shares.len(),
+            triples.len(),
+            "every share requires exactly one beaver triple"
+        );
+
+        // every participant contributes a random summand of each helper, of which participant i receives share i
+        let contributions = shares
+            .iter()
+            .map(|_| {
+                let summand = rng.next_field_element("UnboundedInversionRounds");
+                P::generate_shares(
+                    rng,
+                    &summand,
+                    participants.participant_count(),
+                    participants.threshold(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let share_values_for = |participant: usize| {
+            contributions
+                .iter()
+                .map(|summand_shares| summand_shares[participant - 1].to_indexed().1)
+                .collect::<Vec<T>>()
+        };
+
+        InversionRandomnessRound {
+            participants,
+            messages: participants
+                .others()
+                .map(|participant| {
+                    (
+                        participant,
+                        encode_field_elements(&share_values_for(participant)),
+                    )
+                })
+                .collect(),
+            received: vec![(
+                participants.participant_id(),
+                share_values_for(participants.participant_id()),
+            )],
+            shares: shares.to_vec(),
+            triples,
+            protocol: PhantomData,
+        }
+    }
+
+    /// Compute the inverses from the random `helpers` and the opened products of the inverted elements and the
+    /// helpers.
+    pub(crate) fn inverses<T, S, P>(helpers: Vec<S>, products: &[T]) -> Vec<S>
+    where
+        T: PrimeField,
+        P: LinearSharingScheme<T, S>,
+    {
+        helpers
+            .iter()
+            .zip(products)
+            .map(|(helper, product)| P::multiply_scalar(helper, &product.inverse()))
+            .collect()
+    }
+}
+
+/// The first round of an unbounded inversion, in which each participant sends the shares of random summands of the
+/// helpers to the other participants.
+///
+/// # Type Parameters
+/// - `T` the secret type
+/// - `S` the share type
+/// - `P` the sharing scheme of the shares
+pub struct InversionRandomnessRound<T, S, P> {
+    participants: RoundParticipants,
+    messages: Vec<(usize, Vec<u8>)>,
+    received: Vec<(usize, Vec<T>)>,
+    shares: Vec<S>,
+    triples: Vec<(S, S, S)>,
+    protocol: PhantomData<P>,
+}
+
+impl<T, S, P> InversionRandomnessRound<T, S, P>
+where
+    T: PrimeField,
+    S: IndexedShare<T> + Clone,
+    P: ThresholdSecretSharingScheme<T, S> + LinearSharingScheme<T, S>,
+{
+    /// The messages this participant sends in this round, addressed to the ids of their recipients.
+    pub fn messages_to_send(&self) -> Vec<(usize, Vec<u8>)> {
+        self.messages.clone()
+    }
+
+    /// Receive the message of participant `from`. Once the messages of all participants were received, the helpers
+    /// are the sums of the received shares and the inversion advances to the multiplication.
+    pub fn receive(
+        mut self,
+        from: usize,
+        payload: &[u8],
+    ) -> RoundTransition<Self, InversionMultiplicationRound<T, S, P>> {
+        let values = match receive_field_elements(&self.participants, &self.received, from, payload)
+        {
+            Ok(values) if values.len() == self.shares.len() => values,
+            Ok(_) => {
+                return RoundTransition::Rejected(
+                    self,
+                    RoundException::MalformedPayload { participant: from },
+                )
+            }
+            Err(exception) => return RoundTransition::Rejected(self, exception),
+        };
+
+        self.received.push((from, values));
+        if self.received.len() < self.participants.participant_count() {
+            return RoundTransition::Pending(self);
+        }
+
+        let participant_id = self.participants.participant_id();
+        let helpers = (0..self.shares.len())
+            .map(|index| {
+                let summands = self
+                    .received
+                    .iter()
+                    .map(|(_, values)| S::from_indexed(participant_id, values[index].clone()))
+                    .collect::<Vec<_>>();
+                P::sum_shares(&summands).expect("helpers have at least one summand")
+            })
+            .collect::<Vec<_>>();
+        let pairs = self
+            .shares
+            .into_iter()
+            .zip(helpers.iter().cloned())
+            .collect::<Vec<_>>();
+
+        RoundTransition::Next(InversionMultiplicationRound {
+            participants: self.participants,
+            multiplication: BeaverMultiplyRounds::new(self.participants, &pairs, self.triples),
+            helpers,
+        })
+    }
+}
+
+/// The second round of an unbounded inversion, in which the elements are multiplied with their helpers.
+///
+/// # Type Parameters
+/// - `T` the secret type
+/// - `S` the share type
+/// - `P` the sharing scheme of the shares
+pub struct InversionMultiplicationRound<T, S, P> {
+    participants: RoundParticipants,
+    multiplication: BeaverOpeningRound<T, S, P>,
+    helpers: Vec<S>,
+}
+
+impl<T, S, P> InversionMultiplicationRound<T, S, P>
+where
+    T: PrimeField,
+    S: IndexedShare<T>,
+    P: ThresholdSecretSharingScheme<T, S> + LinearSharingScheme<T, S>,
+{
+    /// The messages this participant sends in this round, addressed to the ids of their recipients.
+    pub fn messages_to_send(&self) -> Vec<(usize, Vec<u8>)> {
+        self.multiplication.messages_to_send()
+    }
+
+    /// Receive the message of participant `from`. Once the messages of all participants were received, the
+    /// inversion advances to opening the products.
+    pub fn receive(
+        self,
+        from: usize,
+        payload: &[u8],
+    ) -> RoundTransition<Self, InversionRevealRound<T, S, P>> {
+        let InversionMultiplicationRound {
+            participants,
+            multiplication,
+            helpers,
+        } = self;
+
+        match multiplication.receive(from, payload) {
+            RoundTransition::Pending(multiplication) => {
+                RoundTransition::Pending(InversionMultiplicationRound {
+                    participants,
+                    multiplication,
+                    helpers,
+                })
+            }
+            RoundTransition::Rejected(multiplication, exception) => RoundTransition::Rejected(
+                InversionMultiplicationRound {
+                    participants,
+                    multiplication,
+                    helpers,
+                },
+                exception,
+            ),
+            RoundTransition::Next(products) => RoundTransition::Next(InversionRevealRound {
+                opening: OpeningRound::new(participants, &products.finish()),
+                helpers,
+            }),
+        }
+    }
+}
+
+/// The last round of an unbounded inversion, in which the products of the elements and their helpers are opened.
+///
+/// # Type Parameters
+/// - `T` the secret type
+/// - `S` the share type
+/// - `P` the sharing scheme of the shares
+pub struct InversionRevealRound<T, S, P> {
+    opening: OpeningRound<T, S, P>,
+    helpers: Vec<S>,
+}
+
+impl<T, S, P> InversionRevealRound<T, S, P>
+where
+    T: PrimeField,
+    S: IndexedShare<T>,
+    P: ThresholdSecretSharingScheme<T, S> + LinearSharingScheme<T, S>,
+{
+    /// The messages this participant sends in this round, addressed to the ids of their recipients.
+    pub fn messages_to_send(&self) -> Vec<(usize, Vec<u8>)> {
+        self.opening.messages_to_send()
+    }
+
+    /// Receive the message of participant `from`. Once the messages of all participants were received, the
+    /// inversion is finished and the shares of the inverses are available in the order of the elements.
+    pub fn receive(self, from: usize, payload: &[u8]) -> RoundTransition<Self, FinishedRounds<S>> {
+        let InversionRevealRound { opening, helpers } = self;
+
+        match opening.receive(from, payload) {
+            RoundTransition::Pending(opening) => {
+                RoundTransition::Pending(InversionRevealRound { opening, helpers })
+            }
+            RoundTransition::Rejected(opening, exception) => {
+                RoundTransition::Rejected(InversionRevealRound { opening, helpers }, exception)
+            }
+            RoundTransition::Next(products) => {
+                RoundTransition::Next(FinishedRounds::new(UnboundedInversionRounds::inverses::<
+                    T,
+                    S,
+                    P,
+                >(helpers, &products)))
+            }
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use crate::feldman_vss::SchnorrGroup;
+use crate::{
+    CliqueCommunicationScheme, Delegate, FieldRng, IndexedShare, PrimeField,
+    RawCommunicationScheme, RoundParticipants,
+};
+use futures::Future;
+use jester_sharing_proc::delegatable_protocol;
+use std::pin::Pin;
+
+pub mod pedersen_key_generation;
+
+/// The outcome of a distributed key generation for one participant.
+///
+/// # Type Parameters
+/// - `S` the share type
+/// - `G` the prime field the public key is a member of
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DistributedKey<S, G> {
+    share: S,
+    public_key: G,
+    qualified: Vec<usize>,
+}
+
+impl<S, G> DistributedKey<S, G> {
+    /// Create the outcome of a key generation.
+    ///
+    /// # Parameters
+    /// - `share` this participant's share of the private key
+    /// - `public_key` the public key `g^x` of the shared private key `x`
+    /// - `qualified` the ids of all participants whose contributions make up the key, in ascending order
+    pub fn new(share: S, public_key: G, qualified: Vec<usize>) -> Self {
+        DistributedKey {
+            share,
+            public_key,
+            qualified,
+        }
+    }
+
+    /// This participant's share of the private key
+    pub fn share(&self) -> &S {
+        &self.share
+    }
+
+    /// The public key `g^x` of the shared private key `x`. All honest participants obtain the same public key.
+    pub fn public_key(&self) -> &G {
+        &self.public_key
+    }
+
+    /// The ids of all participants whose contributions make up the key, in ascending order. Participants missing
+    /// from this list were disqualified.
+    pub fn qualified(&self) -> &[usize] {
+        &self.qualified
+    }
+
+    /// Consume the key and return this participant's share of the private key
+    pub fn into_share(self) -> S {
+        self.share
+    }
+}
+
+/// A scheme that generates a shared private key `x` and its public key `g^x` in a Schnorr group, without any
+/// participant learning `x`. Keys generated this way can be used by threshold signature or decryption schemes in
+/// place of keys shared by a trusted dealer.
+/// # Type Parameters
+/// - `T` the secret type, which is the field of exponents of the group
+/// - `S` the share type
+/// - `P` the protocol this scheme requires. It must support exchanging raw messages (see
+///   `RawCommunicationScheme`).
+/// - `G` the prime field the group elements are members of
+#[delegatable_protocol]
+pub trait DistributedKeyGenerationScheme<T, S, P, G>
+where
+    P: CliqueCommunicationScheme<T, S> + RawCommunicationScheme + Send,
+    T: PrimeField + Send + Sync + 'static,
+    S: IndexedShare<T> + Send + 'static,
+    G: PrimeField + Send + Sync + 'static,
+{
+    /// Generate a shared private key and obtain a share of it, without learning the private key.
+    ///
+    /// # Parameters
+    /// - `rng` a cryptographically secure random number generator
+    /// - `protocol` the protocol instance this scheme is used within.
+    /// - `participants` the participants of the protocol, whose shares are generated by this scheme
+    /// - `group` the group the public key is a member of
+    ///
+    /// # Returns
+    /// Returns a future on this participant's part of the generated key
+    fn generate_distributed_key<'a, R>(
+        rng: &mut R,
+        protocol: &'a mut P,
+        participants: RoundParticipants,
+        group: &SchnorrGroup<G, T>,
+    ) -> Pin<Box<dyn Future<Output = DistributedKey<S, G>> + Send + 'a>>
+    where
+        R: FieldRng;
+}
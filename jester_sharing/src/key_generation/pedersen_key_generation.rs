@@ -0,0 +1,243 @@
+//! Pedersen's distributed key generation. Every participant deals a random contribution with Feldman's verifiable
+//! secret sharing, so every recipient can verify its share against the broadcast commitments of the dealer. A
+//! recipient of an invalid share broadcasts a complaint against the dealer, who must answer it by broadcasting the
+//! disputed share. Dealers whose commitments or answers are invalid are disqualified by all participants alike. The
+//! private key is the sum of the contributions of all qualified dealers, and the public key is the product of their
+//! commitments to their contributions.
+
+use crate::feldman_vss::{FeldmanVss, SchnorrGroup};
+use crate::rounds::{decode_field_elements, encode_field_elements};
+use crate::{
+    CliqueCommunicationScheme, DistributedKey, DistributedKeyGenerationScheme, FieldRng,
+    IndexedShare, PrimeField, RawCommunicationScheme, RoundParticipants,
+};
+use futures::Future;
+use std::convert::TryInto;
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::pin::Pin;
+
+/// A distributed key generation scheme, where every participant acts as a dealer of Feldman's verifiable secret
+/// sharing. A participant distributing inconsistent shares is excluded from the key, if it does not resolve the
+/// complaints against it.
+/// # Usage
+/// ```
+/// use jester_sharing::{DistributedKeyGenerationSchemeDelegate, DistributedKeyGenerationSchemeMarker, Delegate,
+/// CliqueCommunicationScheme, RawCommunicationScheme, IndexedShare, PrimeField};
+/// use jester_sharing::key_generation::pedersen_key_generation::PedersenKeyGeneration;
+///
+/// struct ExampleProtocol;
+///
+/// // snip: implementations for ThresholdSecretSharingScheme, CliqueCommunicationScheme and RawCommunicationScheme for
+/// // ExampleProtocol
+///
+/// impl DistributedKeyGenerationSchemeMarker for ExampleProtocol {
+///     type Marker = Delegate;
+/// }
+///
+/// impl<T, S, P, G> DistributedKeyGenerationSchemeDelegate<T, S, P, G> for ExampleProtocol
+/// where
+///     P: CliqueCommunicationScheme<T, S> + RawCommunicationScheme + Send,
+///     T: PrimeField + Send + Sync + 'static,
+///     S: IndexedShare<T> + Send + 'static,
+///     G: PrimeField + Send + Sync + 'static, {
+///     type Delegate = PedersenKeyGeneration<T, S, P, G>;
+/// }
+/// ```
+pub struct PedersenKeyGeneration<T, S, P, G> {
+    data: PhantomData<T>,
+    share: PhantomData<S>,
+    protocol: PhantomData<P>,
+    group: PhantomData<G>,
+}
+
+/// Encode the ids of the dealers a participant complains about into the payload of a message
+fn encode_complaints(dealers: &[usize]) -> Vec<u8> {
+    dealers
+        .iter()
+        .flat_map(|dealer| (*dealer as u64).to_le_bytes().to_vec())
+        .collect()
+}
+
+/// Decode the complaints of the participant `complainer`. Ids that do not name another participant are ignored, as
+/// is a malformed payload.
+///
+/// # Returns
+/// The ids of the accused dealers in ascending order
+fn decode_complaints(payload: &[u8], complainer: usize, participant_count: usize) -> Vec<usize> {
+    if !payload.len().is_multiple_of(size_of::<u64>()) {
+        return vec![];
+    }
+
+    let mut dealers = payload
+        .chunks(size_of::<u64>())
+        .map(|id| u64::from_le_bytes(id.try_into().unwrap()))
+        .filter(|id| *id >= 1 && *id <= participant_count as u64 && *id != complainer as u64)
+        .map(|id| id as usize)
+        .collect::<Vec<_>>();
+    dealers.sort();
+    dealers.dedup();
+    dealers
+}
+
+impl<T, S, P, G> DistributedKeyGenerationScheme<T, S, P, G> for PedersenKeyGeneration<T, S, P, G>
+where
+    P: CliqueCommunicationScheme<T, S> + RawCommunicationScheme + Send,
+    T: PrimeField + Send + Sync + 'static,
+    S: IndexedShare<T> + Send + 'static,
+    G: PrimeField + Send + Sync + 'static,
+{
+    fn generate_distributed_key<'a, R>(
+        rng: &mut R,
+        protocol: &'a mut P,
+        participants: RoundParticipants,
+        group: &SchnorrGroup<G, T>,
+    ) -> Pin<Box<dyn Future<Output = DistributedKey<S, G>> + Send + 'a>>
+    where
+        R: FieldRng,
+    {
+        let participant_id = participants.participant_id();
+        let participant_count = participants.participant_count();
+        let threshold = participants.threshold();
+
+        let contribution = rng.next_field_element("PedersenKeyGeneration");
+        let (shares, commitments) =
+            FeldmanVss::generate_shares(rng, group, &contribution, participant_count, threshold);
+        let group = group.clone();
+
+        let commitment_tag = protocol.new_session_tag();
+        let share_tag = protocol.new_session_tag();
+        let complaint_tag = protocol.new_session_tag();
+        let response_tag = protocol.new_session_tag();
+
+        Box::pin(async move {
+            protocol
+                .broadcast_bytes(commitment_tag, encode_field_elements(&commitments))
+                .await;
+            let received_commitments = protocol.collect_bytes(commitment_tag).await;
+
+            // no share is sent before the commitments of all dealers were received
+            for recipient in participants.others() {
+                protocol
+                    .send_bytes(
+                        share_tag,
+                        recipient,
+                        encode_field_elements(&[shares[recipient - 1].1.clone()]),
+                    )
+                    .await;
+            }
+            let received_shares = protocol.collect_bytes(share_tag).await;
+
+            // the commitments and shares of all dealers, indexed by dealer id. A dealer without valid commitments is
+            // disqualified, as its commitments are the same for all participants.
+            let mut dealer_commitments = vec![None; participant_count];
+            let mut dealer_shares = vec![None; participant_count];
+            dealer_commitments[participant_id - 1] = Some(commitments);
+            dealer_shares[participant_id - 1] = Some(shares[participant_id - 1].1.clone());
+            for (dealer, payload) in received_commitments {
+                dealer_commitments[dealer - 1] =
+                    decode_field_elements::<G>(&payload).filter(|commitments| {
+                        commitments.len() == threshold
+                            && commitments
+                                .iter()
+                                .all(|commitment| group.contains(commitment))
+                    });
+            }
+            for (dealer, payload) in received_shares {
+                dealer_shares[dealer - 1] = decode_field_elements::<T>(&payload)
+                    .filter(|share| share.len() == 1)
+                    .map(|mut share| share.pop().unwrap());
+            }
+
+            let accused = participants
+                .others()
+                .filter(|dealer| match &dealer_commitments[dealer - 1] {
+                    Some(commitments) => !dealer_shares[dealer - 1].as_ref().is_some_and(|share| {
+                        FeldmanVss::verify_share(
+                            &group,
+                            &(participant_id, share.clone()),
+                            commitments,
+                        )
+                    }),
+                    None => false,
+                })
+                .collect::<Vec<_>>();
+            protocol
+                .broadcast_bytes(complaint_tag, encode_complaints(&accused))
+                .await;
+
+            let mut complaints = protocol
+                .collect_bytes(complaint_tag)
+                .await
+                .into_iter()
+                .map(|(complainer, payload)| {
+                    (
+                        complainer,
+                        decode_complaints(&payload, complainer, participant_count),
+                    )
+                })
+                .collect::<Vec<_>>();
+            complaints.push((participant_id, accused));
+            complaints.sort_by_key(|(complainer, _)| *complainer);
+
+            // answer all complaints by revealing the disputed shares, ordered by the id of the complainer
+            let response = complaints
+                .iter()
+                .filter(|(_, dealers)| dealers.contains(&participant_id))
+                .map(|(complainer, _)| shares[complainer - 1].1.clone())
+                .collect::<Vec<_>>();
+            protocol
+                .broadcast_bytes(response_tag, encode_field_elements(&response))
+                .await;
+
+            for (dealer, payload) in protocol.collect_bytes(response_tag).await {
+                let complainers = complaints
+                    .iter()
+                    .filter(|(_, dealers)| dealers.contains(&dealer))
+                    .map(|(complainer, _)| *complainer)
+                    .collect::<Vec<_>>();
+                let revealed = match &dealer_commitments[dealer - 1] {
+                    Some(commitments) => decode_field_elements::<T>(&payload).filter(|revealed| {
+                        revealed.len() == complainers.len()
+                            && complainers.iter().zip(revealed).all(|(complainer, share)| {
+                                FeldmanVss::verify_share(
+                                    &group,
+                                    &(*complainer, share.clone()),
+                                    commitments,
+                                )
+                            })
+                    }),
+                    None => continue,
+                };
+
+                match revealed {
+                    Some(mut revealed) => {
+                        if let Some(position) =
+                            complainers.iter().position(|c| *c == participant_id)
+                        {
+                            dealer_shares[dealer - 1] = Some(revealed.swap_remove(position));
+                        }
+                    }
+                    None => dealer_commitments[dealer - 1] = None,
+                }
+            }
+
+            // every share of a qualified dealer was either verified or revealed in answer to a complaint
+            let qualified = (1..=participant_count)
+                .filter(|dealer| dealer_commitments[dealer - 1].is_some())
+                .collect::<Vec<_>>();
+            let share = qualified.iter().fold(T::zero(), |sum, dealer| {
+                sum + dealer_shares[dealer - 1].clone().unwrap()
+            });
+            let public_key = qualified.iter().fold(G::one(), |product, dealer| {
+                product * dealer_commitments[dealer - 1].as_ref().unwrap()[0].clone()
+            });
+
+            DistributedKey::new(
+                S::from_indexed(participant_id, share),
+                public_key,
+                qualified,
+            )
+        })
+    }
+}
@@ -5,9 +5,12 @@
 
 pub use communication::*;
 pub use conditional_selection::*;
+pub use dyn_sharing::*;
 pub use inversion::*;
+pub use key_generation::*;
 pub use multiplication::*;
 pub use random_number_generation::*;
+pub use share_redistribution::*;
 pub use shared_or_function::*;
 pub use threshold_sharing::*;
 
@@ -17,13 +20,43 @@ pub use rand::{CryptoRng, RngCore};
 
 pub mod communication;
 pub mod conditional_selection;
+pub mod dyn_sharing;
 pub mod inversion;
+pub mod key_generation;
 pub mod multiplication;
+pub mod observer;
 pub mod prefix_or_function;
 pub mod random_number_generation;
+pub mod share_redistribution;
 pub mod shared_or_function;
+pub mod testing;
 pub mod threshold_sharing;
 
+jester_util::capabilities! {
+    /// The sharing schemes and optional features of a build of this crate, see `capabilities`.
+    pub struct Capabilities {
+        const SHAMIR = 0, "shamir";
+        const ADDITIVE = 1, "additive";
+        const BLIND = 2, "blind";
+        const FELDMAN_VSS = 3, "feldman-vss";
+        /// The commit-then-reveal random number generation and the chunked sharing of byte strings
+        const COMMITMENTS = 16, "commitments";
+        /// The encrypted export and import of a `TriplePool`
+        const PERSISTENCE = 17, "persistence";
+        /// The `ShamirDealer`, which derives the coefficients of its sharings from a dealer secret
+        const DEALER = 18, "dealer";
+    }
+}
+
+/// The sharing schemes and optional features this crate was compiled with, e.g. to agree with the other participants
+/// on a protocol that all of them can run.
+pub fn capabilities() -> Capabilities {
+    (Capabilities::SHAMIR | Capabilities::ADDITIVE | Capabilities::BLIND | Capabilities::FELDMAN_VSS)
+        .with(Capabilities::COMMITMENTS, cfg!(feature = "commitments"))
+        .with(Capabilities::PERSISTENCE, cfg!(feature = "persistence"))
+        .with(Capabilities::DEALER, cfg!(feature = "dealer"))
+}
+
 /// Protocol marker for delegated protocol implementations
 pub struct Delegate;
 
@@ -0,0 +1,129 @@
+//! Beaver multiplication driven in rounds (see `communication::rounds`). `BeaverRerandomizationMultiplication`
+//! performs the same steps, but opens the masked factors through a `CliqueCommunicationScheme`.
+
+use crate::{
+    FinishedRounds, IndexedShare, LinearSharingScheme, OpeningRound, PrimeField, RoundParticipants,
+    RoundTransition, ThresholdSecretSharingScheme,
+};
+
+/// Entry point of a Beaver multiplication driven in rounds. The multiplication requires a single round, in which
+/// the factors masked by a beaver triple each are opened.
+pub struct BeaverMultiplyRounds;
+
+impl BeaverMultiplyRounds {
+    /// Start multiplying the `pairs` of shares using one beaver triple `([a], [b], [c])` with `c = a * b` per pair.
+    /// All participants must use shares of the same triples in the same order.
+    ///
+    /// # Type Parameters
+    /// - `T` the secret type
+    /// - `S` the share type
+    /// - `P` the sharing scheme of the shares
+    ///
+    /// # Panics
+    /// If the number of triples differs from the number of pairs.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new<T, S, P>(
+        participants: RoundParticipants,
+        pairs: &[(S, S)],
+        triples: Vec<(S, S, S)>,
+    ) -> BeaverOpeningRound<T, S, P>
+    where
+        T: PrimeField,
+        S: IndexedShare<T>,
+        P: ThresholdSecretSharingScheme<T, S> + LinearSharingScheme<T, S>,
+    {
+        let opening_shares = Self::opening_shares::<T, S, P>(pairs, &triples);
+
+        BeaverOpeningRound {
+            opening: OpeningRound::new(participants, &opening_shares),
+            triples,
+        }
+    }
+
+    /// The shares to open for multiplying the `pairs`: the differences `[y] - [b]` and `[x] - [a]` of the factors
+    /// `([x], [y])` of each pair and their beaver triple, in this order.
+    pub(crate) fn opening_shares<T, S, P>(pairs: &[(S, S)], triples: &[(S, S, S)]) -> Vec<S>
+    where
+        P: LinearSharingScheme<T, S>,
+    {
+        assert_eq!(
+            pairs.len(),
+            triples.len(),
+            "every pair requires exactly one beaver triple"
+        );
+
+        pairs
+            .iter()
+            .zip(triples)
+            .flat_map(|((lhs, rhs), (a, b, _))| {
+                let delta_share = P::sub_shares(rhs, b);
+                let epsilon_share = P::sub_shares(lhs, a);
+                vec![delta_share, epsilon_share]
+            })
+            .collect()
+    }
+
+    /// Compute the shares of the products from the beaver `triples` and the opened differences, which are in the
+    /// order of `opening_shares`.
+    pub(crate) fn products<T, S, P>(triples: Vec<(S, S, S)>, opened: &[T]) -> Vec<S>
+    where
+        T: PrimeField,
+        P: LinearSharingScheme<T, S>,
+    {
+        triples
+            .into_iter()
+            .zip(opened.chunks_exact(2))
+            .map(|((a, b, c), differences)| {
+                let (delta, epsilon) = (&differences[0], &differences[1]);
+                P::add_scalar(
+                    &P::add_shares(
+                        &P::add_shares(&c, &P::multiply_scalar(&b, epsilon)),
+                        &P::multiply_scalar(&a, delta),
+                    ),
+                    &(epsilon.clone() * delta.clone()),
+                )
+            })
+            .collect()
+    }
+}
+
+/// The round of a Beaver multiplication, in which the masked factors are opened.
+///
+/// # Type Parameters
+/// - `T` the secret type
+/// - `S` the share type
+/// - `P` the sharing scheme of the shares
+pub struct BeaverOpeningRound<T, S, P> {
+    opening: OpeningRound<T, S, P>,
+    triples: Vec<(S, S, S)>,
+}
+
+impl<T, S, P> BeaverOpeningRound<T, S, P>
+where
+    T: PrimeField,
+    S: IndexedShare<T>,
+    P: ThresholdSecretSharingScheme<T, S> + LinearSharingScheme<T, S>,
+{
+    /// The messages this participant sends in this round, addressed to the ids of their recipients.
+    pub fn messages_to_send(&self) -> Vec<(usize, Vec<u8>)> {
+        self.opening.messages_to_send()
+    }
+
+    /// Receive the message of participant `from`. Once the messages of all participants were received, the
+    /// multiplication is finished and the shares of the products are available in the order of the pairs.
+    pub fn receive(self, from: usize, payload: &[u8]) -> RoundTransition<Self, FinishedRounds<S>> {
+        let BeaverOpeningRound { opening, triples } = self;
+
+        match opening.receive(from, payload) {
+            RoundTransition::Pending(opening) => {
+                RoundTransition::Pending(BeaverOpeningRound { opening, triples })
+            }
+            RoundTransition::Rejected(opening, exception) => {
+                RoundTransition::Rejected(BeaverOpeningRound { opening, triples }, exception)
+            }
+            RoundTransition::Next(opened) => RoundTransition::Next(FinishedRounds::new(
+                BeaverMultiplyRounds::products::<T, S, P>(triples, &opened),
+            )),
+        }
+    }
+}
@@ -1,12 +1,13 @@
 use std::future::Future;
 use std::pin::Pin;
 
-use futures::{future::join_all, join};
+use futures::join;
 
 use jester_maths::prime::PrimeField;
 
+use crate::multiplication::beaver_multiply_rounds::BeaverMultiplyRounds;
 use crate::{
-    CliqueCommunicationScheme, LinearSharingScheme, MultiplicationScheme,
+    join_limited, CliqueCommunicationScheme, LinearSharingScheme, MultiplicationScheme,
     ThresholdSecretSharingScheme, UnboundedMultiplicationScheme,
 };
 use std::marker::PhantomData;
@@ -25,6 +26,18 @@ pub trait BeaverCommunicationScheme<S> {
     ) -> Pin<Box<dyn Future<Output = Vec<(S, S, S)>> + Send + 'a>>;
 }
 
+/// A source of random share triples `([a], [b], [c])` where `c = a * b` holds. It can be plugged into protocols
+/// that implement `BeaverCommunicationScheme` generically, so the origin of triples (a trusted dealer, a precomputed
+/// pool or an offline phase) can be chosen independently of the communication.
+pub trait BeaverTripleSource<S> {
+    /// Obtain `count` random triples of shares `([a], [b], [c])` where `c = a * b` holds. Every participant must
+    /// obtain shares of the same triples in the same order.
+    fn obtain_beaver_triples<'a>(
+        &'a mut self,
+        count: usize,
+    ) -> Pin<Box<dyn Future<Output = Vec<(S, S, S)>> + Send + 'a>>;
+}
+
 pub struct BeaverRerandomizationMultiplication<T, S, P>
 where
     P: ThresholdSecretSharingScheme<T, S>
@@ -51,43 +64,34 @@ where
     T: PrimeField + Send + Sync,
     S: Clone + Send + Sync + 'static,
 {
+    /// Multiply all `pairs` with one beaver triple each. An empty slice of pairs is multiplied without obtaining
+    /// triples or communicating. At most as many openings are in flight at once as the
+    /// `ProtocolConfig::concurrency` of the protocol permits.
     fn unbounded_multiply<'a>(
         protocol: &'a mut P,
         pairs: &[(S, S)],
     ) -> Pin<Box<dyn Future<Output = Vec<S>> + Send + 'a>> {
+        if pairs.is_empty() {
+            return Box::pin(async { vec![] });
+        }
+
         let pairs_clone: Vec<_> = pairs.to_vec();
 
         Box::pin(async move {
             let beaver_triples = protocol.obtain_beaver_triples(pairs_clone.len()).await;
+            let opening_shares =
+                BeaverMultiplyRounds::opening_shares::<T, S, P>(&pairs_clone, &beaver_triples);
 
-            let multiplications = pairs_clone
+            let openings = opening_shares
                 .into_iter()
-                .zip(beaver_triples.clone())
-                .map(|((lhs, rhs), (a, b, _))| {
-                    let epsilon_share = P::sub_shares(&lhs, &a);
-                    let delta_share = P::sub_shares(&rhs, &b);
-
-                    let delta = protocol.reveal_shares(delta_share);
-                    let epsilon = protocol.reveal_shares(epsilon_share);
-
-                    async { join!(delta, epsilon) }
+                .map(|share| {
+                    let tag = protocol.new_session_tag();
+                    protocol.reveal_shares_tagged(tag, share)
                 })
-                .collect::<Vec<_>>();
+                .collect();
+            let opened = join_limited(protocol.concurrency(), openings).await;
 
-            join_all(multiplications)
-                .await
-                .into_iter()
-                .zip(beaver_triples)
-                .map(|((delta, epsilon), (a, b, c))| {
-                    P::add_scalar(
-                        &P::add_shares(
-                            &P::add_shares(&c, &P::multiply_scalar(&b, &epsilon)),
-                            &P::multiply_scalar(&a, &delta),
-                        ),
-                        &(epsilon.clone() * delta.clone()),
-                    )
-                })
-                .collect()
+            BeaverMultiplyRounds::products::<T, S, P>(beaver_triples, &opened)
         })
     }
 }
@@ -113,23 +117,21 @@ where
         let rhs = rhs.clone();
 
         Box::pin(async move {
-            let (a, b, c) = protocol.obtain_beaver_triples(1).await.pop().unwrap();
-
-            let epsilon_share = P::sub_shares(&lhs, &a);
-            let delta_share = P::sub_shares(&rhs, &b);
+            let beaver_triples = protocol.obtain_beaver_triples(1).await;
+            let opening_shares =
+                BeaverMultiplyRounds::opening_shares::<T, S, P>(&[(lhs, rhs)], &beaver_triples);
 
+            let delta_tag = protocol.new_session_tag();
+            let epsilon_tag = protocol.new_session_tag();
+            let mut opening_shares = opening_shares.into_iter();
             let (delta, epsilon) = join!(
-                protocol.reveal_shares(delta_share),
-                protocol.reveal_shares(epsilon_share)
+                protocol.reveal_shares_tagged(delta_tag, opening_shares.next().unwrap()),
+                protocol.reveal_shares_tagged(epsilon_tag, opening_shares.next().unwrap())
             );
 
-            P::add_scalar(
-                &P::add_shares(
-                    &P::add_shares(&c, &P::multiply_scalar(&b, &epsilon)),
-                    &P::multiply_scalar(&a, &delta),
-                ),
-                &(epsilon.clone() * delta.clone()),
-            )
+            BeaverMultiplyRounds::products::<T, S, P>(beaver_triples, &[delta, epsilon])
+                .pop()
+                .unwrap()
         })
     }
 }
@@ -6,7 +6,9 @@ use jester_sharing_proc::delegatable_protocol;
 use std::future::Future;
 use std::pin::Pin;
 
+pub mod beaver_multiply_rounds;
 pub mod beaver_randomization_multiplication;
+pub mod triple_pool;
 
 /// A multiplication scheme. This multiplication scheme is potentially very complex and requires at least one round
 /// of communication which in turn requires it to capture a mutable reference to protocol it is defined on. This in
@@ -0,0 +1,422 @@
+//! A pool of precomputed beaver triples. Generating triples is the expensive offline phase of the beaver
+//! multiplication, so a deployment generates them ahead of time, e.g. while the protocol is idle, and multiplications
+//! only draw them from the pool. With the `persistence` feature, pools can be exported encrypted and imported again,
+//! so precomputed triples survive restarts.
+
+use crate::beaver_randomization_multiplication::BeaverTripleSource;
+use futures::Future;
+use std::collections::VecDeque;
+use std::pin::Pin;
+
+#[cfg(feature = "persistence")]
+use crate::rounds::{decode_field_elements, encode_field_elements};
+#[cfg(feature = "persistence")]
+use crate::{CryptoRng, IndexedShare, PrimeField, RngCore};
+#[cfg(feature = "persistence")]
+use jester_encryption::AuthenticatedEncryptionScheme;
+#[cfg(feature = "persistence")]
+use std::convert::TryInto;
+#[cfg(feature = "persistence")]
+use std::mem::size_of;
+
+/// The associated data of exported pools, so their cipher texts cannot be mistaken for other data encrypted with the
+/// same key
+#[cfg(feature = "persistence")]
+const EXPORT_DOMAIN: &[u8] = b"jester_sharing triple pool";
+
+/// The pool holds fewer triples than were requested. No triples are taken from the pool in this case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolExhausted {
+    pub requested: usize,
+    pub available: usize,
+}
+
+/// Exceptions that can arise when importing an exported pool.
+#[cfg(feature = "persistence")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PoolImportException {
+    /// The export was not created with the same key, or it was tampered with
+    AuthenticationFailed {},
+
+    /// The export was authenticated, but it does not contain triples of the requested field
+    MalformedPool {},
+}
+
+/// A pool of beaver triples that is filled from a `BeaverTripleSource`. Triples are handed out in the order the
+/// source generated them, so all participants must fill and drain their pools in the same order.
+///
+/// The pool implements `BeaverTripleSource` itself, so it can be plugged into protocols like
+/// `ShamirCliqueProtocol`, whose multiplications then transparently draw their triples from the pool.
+///
+/// # Type Parameters
+/// - `S` the share type of the triples
+/// - `Source` the source the pool is refilled from, like a trusted dealer or a distributed offline phase
+pub struct TriplePool<S, Source> {
+    triples: VecDeque<(S, S, S)>,
+    target_size: usize,
+    source: Source,
+}
+
+impl<S, Source> TriplePool<S, Source> {
+    /// Create an empty pool. Call `refill` to fill it before the first multiplication.
+    /// # Parameters
+    /// - `target_size` how many triples the pool holds after a refill
+    /// - `source` the source of the pool's triples
+    pub fn new(target_size: usize, source: Source) -> Self {
+        TriplePool {
+            triples: VecDeque::with_capacity(target_size),
+            target_size,
+            source,
+        }
+    }
+
+    /// How many triples the pool holds after a refill
+    pub fn target_size(&self) -> usize {
+        self.target_size
+    }
+
+    /// The number of triples currently in the pool
+    pub fn len(&self) -> usize {
+        self.triples.len()
+    }
+
+    /// Whether the pool holds no triples
+    pub fn is_empty(&self) -> bool {
+        self.triples.is_empty()
+    }
+
+    /// Take the `count` oldest triples from the pool. Taking triples never waits for the source, so an exhausted
+    /// pool is reported instead of blocking the multiplication.
+    ///
+    /// # Returns
+    /// The triples, or `PoolExhausted` if the pool holds fewer than `count` triples
+    pub fn take(&mut self, count: usize) -> Result<Vec<(S, S, S)>, PoolExhausted> {
+        if count > self.triples.len() {
+            return Err(PoolExhausted {
+                requested: count,
+                available: self.triples.len(),
+            });
+        }
+
+        Ok(self.triples.drain(..count).collect())
+    }
+
+    /// Top the pool up to its target size with fresh triples of its source. If the source communicates with other
+    /// participants, all of them must refill their pools at the same time.
+    ///
+    /// # Returns
+    /// A future on the number of triples added to the pool
+    pub fn refill<'a>(&'a mut self) -> Pin<Box<dyn Future<Output = usize> + Send + 'a>>
+    where
+        S: Send,
+        Source: BeaverTripleSource<S> + Send,
+    {
+        Box::pin(async move {
+            let missing = self.target_size.saturating_sub(self.triples.len());
+            if missing > 0 {
+                let triples = self.source.obtain_beaver_triples(missing).await;
+                self.triples.extend(triples);
+            }
+            missing
+        })
+    }
+}
+
+#[cfg(feature = "persistence")]
+impl<S, Source> TriplePool<S, Source> {
+    /// Export all triples of the pool, encrypted and authenticated with `key`. The pool is consumed, because a
+    /// triple that is used twice leaks the factors of both multiplications. For the same reason, an export must
+    /// only be imported once.
+    ///
+    /// # Type Parameters
+    /// - `T` the field the shares of the triples are in
+    /// - `E` the authenticated encryption scheme of the export
+    ///
+    /// # Parameters
+    /// - `rng` a cryptographically secure random number generator for the nonce of the export
+    /// - `key` the key of the export
+    ///
+    /// # Returns
+    /// The random nonce followed by the cipher text of the triples
+    pub fn export_encrypted<T, E, R>(self, rng: &mut R, key: &E::Key) -> Vec<u8>
+    where
+        T: PrimeField,
+        S: IndexedShare<T>,
+        E: AuthenticatedEncryptionScheme,
+        E::Nonce: Default + AsRef<[u8]> + AsMut<[u8]>,
+        R: RngCore + CryptoRng,
+    {
+        let mut indices = Vec::with_capacity(size_of::<u64>() * (3 * self.triples.len() + 1));
+        let mut values = Vec::with_capacity(3 * self.triples.len());
+        indices.extend_from_slice(&(self.triples.len() as u64).to_le_bytes());
+        for (a, b, c) in &self.triples {
+            for share in &[a, b, c] {
+                let (index, value) = share.to_indexed();
+                indices.extend_from_slice(&(index as u64).to_le_bytes());
+                values.push(value);
+            }
+        }
+        let clear_text = [indices, encode_field_elements(&values)].concat();
+
+        let mut nonce = E::Nonce::default();
+        rng.fill_bytes(nonce.as_mut());
+        let cipher_text = E::encrypt_message(key, &nonce, EXPORT_DOMAIN, &clear_text);
+        [nonce.as_ref(), &cipher_text].concat()
+    }
+
+    /// Import the triples of a pool exported by `export_encrypted` with the same `key`. The triples are added after
+    /// the triples already in the pool.
+    ///
+    /// # Returns
+    /// The number of imported triples, or a `PoolImportException` if the export cannot be decrypted. Nothing is
+    /// imported in that case.
+    pub fn import_encrypted<T, E>(
+        &mut self,
+        key: &E::Key,
+        exported: &[u8],
+    ) -> Result<usize, PoolImportException>
+    where
+        T: PrimeField,
+        S: IndexedShare<T>,
+        E: AuthenticatedEncryptionScheme,
+        E::Nonce: Default + AsRef<[u8]> + AsMut<[u8]>,
+    {
+        let mut nonce = E::Nonce::default();
+        let nonce_length = nonce.as_ref().len();
+        if exported.len() < nonce_length {
+            return Err(PoolImportException::AuthenticationFailed {});
+        }
+
+        let (nonce_bytes, cipher_text) = exported.split_at(nonce_length);
+        nonce.as_mut().copy_from_slice(nonce_bytes);
+        let clear_text = E::decrypt_message(key, &nonce, EXPORT_DOMAIN, cipher_text)
+            .map_err(|_| PoolImportException::AuthenticationFailed {})?;
+
+        let triples =
+            decode_triples::<T, S>(&clear_text).ok_or(PoolImportException::MalformedPool {})?;
+        let count = triples.len();
+        self.triples.extend(triples);
+        Ok(count)
+    }
+}
+
+/// Decode the triples of the clear text of an export.
+///
+/// # Returns
+/// The triples, or `None` if the clear text does not contain triples of the field `T`
+#[cfg(feature = "persistence")]
+fn decode_triples<T, S>(clear_text: &[u8]) -> Option<Vec<(S, S, S)>>
+where
+    T: PrimeField,
+    S: IndexedShare<T>,
+{
+    let mut words = clear_text.chunks(size_of::<u64>());
+    let mut next_word = || -> Option<usize> {
+        let word = words.next()?;
+        Some(u64::from_le_bytes(word.try_into().ok()?) as usize)
+    };
+
+    let count = next_word()?;
+    if count > clear_text.len() / (3 * size_of::<u64>()) {
+        return None;
+    }
+    let indices = (0..3 * count)
+        .map(|_| next_word())
+        .collect::<Option<Vec<_>>>()?;
+
+    let values = decode_field_elements::<T>(&clear_text[size_of::<u64>() * (3 * count + 1)..])?;
+    if values.len() != 3 * count {
+        return None;
+    }
+
+    let mut shares = indices
+        .into_iter()
+        .zip(values)
+        .map(|(index, value)| S::from_indexed(index, value));
+    Some(
+        (0..count)
+            .map(|_| {
+                (
+                    shares.next().unwrap(),
+                    shares.next().unwrap(),
+                    shares.next().unwrap(),
+                )
+            })
+            .collect(),
+    )
+}
+
+impl<S, Source> BeaverTripleSource<S> for TriplePool<S, Source>
+where
+    S: Send,
+{
+    /// Take the triples from the pool. Multiplications cannot handle a missing triple, so the pool must be refilled
+    /// in time.
+    ///
+    /// # Panics
+    /// If the pool holds fewer than `count` triples
+    fn obtain_beaver_triples<'a>(
+        &'a mut self,
+        count: usize,
+    ) -> Pin<Box<dyn Future<Output = Vec<(S, S, S)>> + Send + 'a>> {
+        let triples = self.take(count).unwrap_or_else(|exhausted| {
+            panic!(
+                "the triple pool holds {} triples, but {} were requested",
+                exhausted.available, exhausted.requested
+            )
+        });
+        Box::pin(async move { triples })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use jester_maths::prime::Mersenne61;
+    use num::FromPrimitive;
+
+    /// A source handing out triples of consecutive numbers, so the order of triples can be checked
+    #[derive(Default)]
+    struct CountingSource {
+        counter: usize,
+    }
+
+    impl CountingSource {
+        fn share(&mut self) -> (usize, Mersenne61) {
+            self.counter += 1;
+            (1, Mersenne61::from_usize(self.counter).unwrap())
+        }
+    }
+
+    impl BeaverTripleSource<(usize, Mersenne61)> for CountingSource {
+        fn obtain_beaver_triples<'a>(
+            &'a mut self,
+            count: usize,
+        ) -> Pin<
+            Box<
+                dyn Future<
+                        Output = Vec<(
+                            (usize, Mersenne61),
+                            (usize, Mersenne61),
+                            (usize, Mersenne61),
+                        )>,
+                    > + Send
+                    + 'a,
+            >,
+        > {
+            let triples = (0..count)
+                .map(|_| (self.share(), self.share(), self.share()))
+                .collect::<Vec<_>>();
+            Box::pin(async move { triples })
+        }
+    }
+
+    fn share(value: usize) -> (usize, Mersenne61) {
+        (1, Mersenne61::from_usize(value).unwrap())
+    }
+
+    #[test]
+    fn test_take_draws_down_pool() {
+        let mut pool = TriplePool::new(4, CountingSource::default());
+        assert!(pool.is_empty());
+        assert_eq!(block_on(pool.refill()), 4);
+        assert_eq!(pool.len(), 4);
+
+        assert_eq!(pool.take(1), Ok(vec![(share(1), share(2), share(3))]));
+        assert_eq!(pool.take(2).unwrap().len(), 2);
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn test_exhausted_pool() {
+        let mut pool = TriplePool::new(2, CountingSource::default());
+        assert_eq!(
+            pool.take(1),
+            Err(PoolExhausted {
+                requested: 1,
+                available: 0
+            })
+        );
+
+        block_on(pool.refill());
+        assert_eq!(
+            pool.take(3),
+            Err(PoolExhausted {
+                requested: 3,
+                available: 2
+            })
+        );
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[test]
+    fn test_refill_restores_capacity() {
+        let mut pool = TriplePool::new(3, CountingSource::default());
+        block_on(pool.refill());
+        pool.take(2).unwrap();
+
+        // only the taken triples are generated again, and they are handed out after the remaining one
+        assert_eq!(block_on(pool.refill()), 2);
+        assert_eq!(pool.len(), pool.target_size());
+        assert_eq!(block_on(pool.refill()), 0);
+        assert_eq!(
+            pool.take(2),
+            Ok(vec![
+                (share(7), share(8), share(9)),
+                (share(10), share(11), share(12))
+            ])
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "the triple pool holds 0 triples, but 2 were requested")]
+    fn test_exhausted_pool_as_source() {
+        let mut pool = TriplePool::<(usize, Mersenne61), _>::new(2, CountingSource::default());
+        block_on(pool.obtain_beaver_triples(2));
+    }
+
+    #[cfg(feature = "persistence")]
+    #[test]
+    fn test_export_import_round_trip() {
+        use jester_encryption::aes::gcm::Aes256Gcm;
+        use rand::rngs::StdRng;
+        use rand::SeedableRng;
+
+        let mut rng = StdRng::seed_from_u64(428);
+        let key = Aes256Gcm::<16>::generate_key(&mut rng);
+
+        let mut pool = TriplePool::new(5, CountingSource::default());
+        block_on(pool.refill());
+        pool.take(1).unwrap();
+        let exported = pool.export_encrypted::<Mersenne61, Aes256Gcm, _>(&mut rng, &key);
+
+        let mut restored = TriplePool::new(5, CountingSource::default());
+        assert_eq!(
+            restored.import_encrypted::<Mersenne61, Aes256Gcm>(&key, &exported),
+            Ok(4)
+        );
+        assert_eq!(
+            restored.take(4).unwrap(),
+            (1..5)
+                .map(|triple| (
+                    share(3 * triple + 1),
+                    share(3 * triple + 2),
+                    share(3 * triple + 3)
+                ))
+                .collect::<Vec<_>>()
+        );
+
+        let mut tampered = exported.clone();
+        tampered[20] ^= 1;
+        assert_eq!(
+            restored.import_encrypted::<Mersenne61, Aes256Gcm>(&key, &tampered),
+            Err(PoolImportException::AuthenticationFailed {})
+        );
+        assert_eq!(
+            restored.import_encrypted::<Mersenne61, Aes256Gcm>(&key, &exported[..8]),
+            Err(PoolImportException::AuthenticationFailed {})
+        );
+        assert!(restored.is_empty());
+    }
+}
@@ -0,0 +1,467 @@
+//! An observer mechanism to inspect the communication of a protocol for debugging purposes. A protocol is wrapped
+//! into an `ObservedProtocol`, that reports every revealed value, every distributed secret and every completed
+//! multiplication round to a `ProtocolObserver`. All schemes executed on the wrapper use the scheme implementations
+//! of the wrapped protocol, but communicate through the wrapper, so the communication of nested schemes is observed
+//! as well.
+//!
+//! The wrapper supports protocols with Shamir shares.
+
+use crate::beaver_randomization_multiplication::BeaverCommunicationScheme;
+use crate::shamir_secret_sharing::ShamirSecretSharingScheme;
+use crate::{
+    CliqueCommunicationScheme, ConditionalSelectionSchemeDelegate,
+    ConditionalSelectionSchemeMarker, Delegate, InversionSchemeDelegate, InversionSchemeMarker,
+    LinearSharingScheme, MultiplicationScheme, MultiplicationSchemeDelegate,
+    MultiplicationSchemeMarker, OrFunctionSchemeDelegate, OrFunctionSchemeMarker, PrimeField,
+    ProtocolConcurrency, ProtocolConfig, RandomBitGenerationSchemeDelegate,
+    RandomBitGenerationSchemeMarker, RandomNumberGenerationScheme,
+    RandomNumberGenerationSchemeDelegate, RandomNumberGenerationSchemeMarker,
+    RawCommunicationScheme, SessionTag, ThresholdSecretSharingScheme,
+    UnboundedConditionalSelectionSchemeDelegate, UnboundedConditionalSelectionSchemeMarker,
+    UnboundedInversionScheme, UnboundedInversionSchemeDelegate, UnboundedInversionSchemeMarker,
+    UnboundedMultiplicationScheme, UnboundedMultiplicationSchemeDelegate,
+    UnboundedMultiplicationSchemeMarker, UnboundedOrFunctionSchemeDelegate,
+    UnboundedOrFunctionSchemeMarker,
+};
+use futures::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+/// Callbacks that are invoked by an `ObservedProtocol` during protocol execution. Callbacks may be invoked from
+/// within futures, thus they only receive a shared reference to the observer.
+///
+/// # Type Parameters
+/// - `T` the secret type of the observed protocol
+/// - `S` the share type of the observed protocol
+pub trait ProtocolObserver<T, S> {
+    /// A secret was revealed to all participants.
+    ///
+    /// # Parameters
+    /// - `label` the label of the observed protocol at the time the reveal was started
+    /// - `value` the revealed secret
+    fn on_reveal(&self, label: &'static str, value: &T);
+
+    /// A secret was distributed among all participants and this participant received its shares.
+    ///
+    /// # Parameters
+    /// - `label` the label of the observed protocol at the time the distribution was started
+    /// - `secret_known` whether this participant knows the distributed secret. In a clique distribution every
+    ///   participant contributes a secret of its own, so it knows at least one of them.
+    fn on_distribute(&self, label: &'static str, secret_known: bool);
+
+    /// A round of communication of the scheme `scheme_name` completed.
+    fn on_round_complete(&self, scheme_name: &'static str);
+}
+
+/// A protocol wrapper that reports all communication of the wrapped protocol `P` to a `ProtocolObserver`. It
+/// implements all schemes the wrapped protocol delegates, by using the same delegates.
+pub struct ObservedProtocol<P, T> {
+    protocol: P,
+    observer: Arc<dyn ProtocolObserver<T, (usize, T)> + Send + Sync>,
+    label: &'static str,
+}
+
+impl<P, T> ObservedProtocol<P, T> {
+    /// Wrap the `protocol` and report its communication to the `observer`.
+    pub fn new(
+        protocol: P,
+        observer: Arc<dyn ProtocolObserver<T, (usize, T)> + Send + Sync>,
+    ) -> Self {
+        ObservedProtocol {
+            protocol,
+            observer,
+            label: "",
+        }
+    }
+
+    /// Set the label that is reported alongside all reveals and distributions started from now on. Labels can be
+    /// used to mark the phases of a computation.
+    pub fn set_label(&mut self, label: &'static str) {
+        self.label = label;
+    }
+
+    /// The wrapped protocol
+    pub fn protocol(&self) -> &P {
+        &self.protocol
+    }
+
+    /// Unwrap the observed protocol
+    pub fn into_inner(self) -> P {
+        self.protocol
+    }
+}
+
+impl<P, T> ShamirSecretSharingScheme<T> for ObservedProtocol<P, T>
+where
+    P: ShamirSecretSharingScheme<T>,
+    T: PrimeField,
+{
+}
+
+impl<P, T> ProtocolConfig for ObservedProtocol<P, T>
+where
+    P: ProtocolConfig,
+{
+    fn concurrency(&self) -> ProtocolConcurrency {
+        self.protocol.concurrency()
+    }
+}
+
+impl<P, T> CliqueCommunicationScheme<T, (usize, T)> for ObservedProtocol<P, T>
+where
+    P: CliqueCommunicationScheme<T, (usize, T)> + ShamirSecretSharingScheme<T>,
+    T: PrimeField + Send + 'static,
+{
+    fn reveal_shares(&mut self, share: (usize, T)) -> Pin<Box<dyn Future<Output = T> + Send>> {
+        let tag = self.new_session_tag();
+        self.reveal_shares_tagged(tag, share)
+    }
+
+    fn distribute_secret(
+        &mut self,
+        secret: T,
+    ) -> Pin<Box<dyn Future<Output = Vec<(usize, T)>> + Send>> {
+        let tag = self.new_session_tag();
+        self.distribute_secret_tagged(tag, secret)
+    }
+
+    fn new_session_tag(&mut self) -> SessionTag {
+        self.protocol.new_session_tag()
+    }
+
+    fn reveal_shares_tagged(
+        &mut self,
+        tag: SessionTag,
+        share: (usize, T),
+    ) -> Pin<Box<dyn Future<Output = T> + Send>> {
+        let revealed = self.protocol.reveal_shares_tagged(tag, share);
+        let observer = self.observer.clone();
+        let label = self.label;
+
+        Box::pin(async move {
+            let value = revealed.await;
+            observer.on_reveal(label, &value);
+            value
+        })
+    }
+
+    fn distribute_secret_tagged(
+        &mut self,
+        tag: SessionTag,
+        secret: T,
+    ) -> Pin<Box<dyn Future<Output = Vec<(usize, T)>> + Send>> {
+        let distributed = self.protocol.distribute_secret_tagged(tag, secret);
+        let observer = self.observer.clone();
+        let label = self.label;
+
+        Box::pin(async move {
+            let shares = distributed.await;
+            observer.on_distribute(label, true);
+            shares
+        })
+    }
+}
+
+impl<P, T> RawCommunicationScheme for ObservedProtocol<P, T>
+where
+    P: RawCommunicationScheme,
+{
+    fn broadcast_bytes(
+        &mut self,
+        tag: SessionTag,
+        message: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        self.protocol.broadcast_bytes(tag, message)
+    }
+
+    fn send_bytes(
+        &mut self,
+        tag: SessionTag,
+        participant: usize,
+        message: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        self.protocol.send_bytes(tag, participant, message)
+    }
+
+    fn collect_bytes(
+        &mut self,
+        tag: SessionTag,
+    ) -> Pin<Box<dyn Future<Output = Vec<(usize, Vec<u8>)>> + Send>> {
+        self.protocol.collect_bytes(tag)
+    }
+}
+
+impl<P, T> BeaverCommunicationScheme<(usize, T)> for ObservedProtocol<P, T>
+where
+    P: BeaverCommunicationScheme<(usize, T)>,
+{
+    fn get_reconstruction_threshold(&self) -> usize {
+        self.protocol.get_reconstruction_threshold()
+    }
+
+    fn obtain_beaver_triples<'a>(
+        &'a mut self,
+        count: usize,
+    ) -> Pin<Box<dyn Future<Output = Vec<((usize, T), (usize, T), (usize, T))>> + Send + 'a>> {
+        self.protocol.obtain_beaver_triples(count)
+    }
+}
+
+/// An unbounded multiplication that reports each completed multiplication round of the delegate `D` to the observer
+/// of the protocol.
+pub struct ObservedUnboundedMultiplication<D>(PhantomData<D>);
+
+impl<D, P, T> UnboundedMultiplicationScheme<T, (usize, T), ObservedProtocol<P, T>>
+    for ObservedUnboundedMultiplication<D>
+where
+    D: UnboundedMultiplicationScheme<T, (usize, T), ObservedProtocol<P, T>>,
+    ObservedProtocol<P, T>: Send + Sync,
+    T: Send + Sync + 'static,
+{
+    fn unbounded_multiply<'a>(
+        protocol: &'a mut ObservedProtocol<P, T>,
+        pairs: &[((usize, T), (usize, T))],
+    ) -> Pin<Box<dyn Future<Output = Vec<(usize, T)>> + Send + 'a>> {
+        let observer = protocol.observer.clone();
+        let products = D::unbounded_multiply(protocol, pairs);
+
+        Box::pin(async move {
+            let products = products.await;
+            observer.on_round_complete("UnboundedMultiplicationScheme");
+            products
+        })
+    }
+}
+
+impl<P, T> RandomNumberGenerationSchemeMarker for ObservedProtocol<P, T> {
+    type Marker = Delegate;
+}
+
+impl<P, T> RandomNumberGenerationSchemeDelegate<T, (usize, T), ObservedProtocol<P, T>>
+    for ObservedProtocol<P, T>
+where
+    P: RandomNumberGenerationSchemeDelegate<T, (usize, T), ObservedProtocol<P, T>>,
+    Self: ThresholdSecretSharingScheme<T, (usize, T)>
+        + LinearSharingScheme<T, (usize, T)>
+        + CliqueCommunicationScheme<T, (usize, T)>,
+    T: PrimeField + 'static,
+{
+    type Delegate = P::Delegate;
+}
+
+impl<P, T> RandomBitGenerationSchemeMarker for ObservedProtocol<P, T> {
+    type Marker = Delegate;
+}
+
+impl<P, T> RandomBitGenerationSchemeDelegate<T, (usize, T), ObservedProtocol<P, T>>
+    for ObservedProtocol<P, T>
+where
+    P: RandomBitGenerationSchemeDelegate<T, (usize, T), ObservedProtocol<P, T>>,
+    T: PrimeField,
+{
+    type Delegate = P::Delegate;
+}
+
+impl<P, T> MultiplicationSchemeMarker for ObservedProtocol<P, T> {
+    type Marker = Delegate;
+}
+
+impl<P, T> MultiplicationSchemeDelegate<T, (usize, T), ObservedProtocol<P, T>>
+    for ObservedProtocol<P, T>
+where
+    P: MultiplicationSchemeDelegate<T, (usize, T), ObservedProtocol<P, T>>,
+    Self: Send + Sync,
+    T: Send + Sync,
+{
+    type Delegate = P::Delegate;
+}
+
+impl<P, T> UnboundedMultiplicationSchemeMarker for ObservedProtocol<P, T> {
+    type Marker = Delegate;
+}
+
+impl<P, T> UnboundedMultiplicationSchemeDelegate<T, (usize, T), ObservedProtocol<P, T>>
+    for ObservedProtocol<P, T>
+where
+    P: UnboundedMultiplicationSchemeDelegate<T, (usize, T), ObservedProtocol<P, T>>,
+    Self: Send + Sync,
+    T: Send + Sync + 'static,
+{
+    type Delegate = ObservedUnboundedMultiplication<P::Delegate>;
+}
+
+impl<P, T> InversionSchemeMarker for ObservedProtocol<P, T> {
+    type Marker = Delegate;
+}
+
+impl<P, T> InversionSchemeDelegate<T, (usize, T), ObservedProtocol<P, T>> for ObservedProtocol<P, T>
+where
+    P: InversionSchemeDelegate<T, (usize, T), ObservedProtocol<P, T>>,
+    Self: ThresholdSecretSharingScheme<T, (usize, T)>
+        + LinearSharingScheme<T, (usize, T)>
+        + Send
+        + Sync,
+    T: PrimeField + Send + Sync + 'static,
+{
+    type Delegate = P::Delegate;
+}
+
+impl<P, T> UnboundedInversionSchemeMarker for ObservedProtocol<P, T> {
+    type Marker = Delegate;
+}
+
+impl<P, T> UnboundedInversionSchemeDelegate<T, (usize, T), ObservedProtocol<P, T>>
+    for ObservedProtocol<P, T>
+where
+    P: UnboundedInversionSchemeDelegate<T, (usize, T), ObservedProtocol<P, T>>,
+    Self: ThresholdSecretSharingScheme<T, (usize, T)>
+        + LinearSharingScheme<T, (usize, T)>
+        + Send
+        + Sync,
+    T: PrimeField + Send + Sync + 'static,
+{
+    type Delegate = P::Delegate;
+}
+
+impl<P, T> ConditionalSelectionSchemeMarker for ObservedProtocol<P, T> {
+    type Marker = Delegate;
+}
+
+impl<P, T> ConditionalSelectionSchemeDelegate<T, (usize, T), ObservedProtocol<P, T>>
+    for ObservedProtocol<P, T>
+where
+    P: ConditionalSelectionSchemeDelegate<T, (usize, T), ObservedProtocol<P, T>>,
+    Self: MultiplicationScheme<T, (usize, T), Self> + Send + Sync,
+    T: Send + Sync,
+{
+    type Delegate = P::Delegate;
+}
+
+impl<P, T> UnboundedConditionalSelectionSchemeMarker for ObservedProtocol<P, T> {
+    type Marker = Delegate;
+}
+
+impl<P, T> UnboundedConditionalSelectionSchemeDelegate<T, (usize, T), ObservedProtocol<P, T>>
+    for ObservedProtocol<P, T>
+where
+    P: UnboundedConditionalSelectionSchemeDelegate<T, (usize, T), ObservedProtocol<P, T>>,
+    Self: UnboundedMultiplicationScheme<T, (usize, T), Self> + Send + Sync,
+    T: Send + Sync,
+{
+    type Delegate = P::Delegate;
+}
+
+impl<P, T> OrFunctionSchemeMarker for ObservedProtocol<P, T> {
+    type Marker = Delegate;
+}
+
+impl<P, T> OrFunctionSchemeDelegate<T, (usize, T), ObservedProtocol<P, T>>
+    for ObservedProtocol<P, T>
+where
+    P: OrFunctionSchemeDelegate<T, (usize, T), ObservedProtocol<P, T>>,
+    Self: ThresholdSecretSharingScheme<T, (usize, T)>
+        + LinearSharingScheme<T, (usize, T)>
+        + CliqueCommunicationScheme<T, (usize, T)>
+        + UnboundedMultiplicationScheme<T, (usize, T), Self>
+        + RandomNumberGenerationScheme<T, (usize, T), Self>
+        + UnboundedInversionScheme<T, (usize, T), Self>
+        + Send
+        + Sync,
+    T: PrimeField + Send + Sync + 'static,
+{
+    type Delegate = P::Delegate;
+}
+
+impl<P, T> UnboundedOrFunctionSchemeMarker for ObservedProtocol<P, T> {
+    type Marker = Delegate;
+}
+
+impl<P, T> UnboundedOrFunctionSchemeDelegate<T, (usize, T), ObservedProtocol<P, T>>
+    for ObservedProtocol<P, T>
+where
+    P: UnboundedOrFunctionSchemeDelegate<T, (usize, T), ObservedProtocol<P, T>>,
+    Self: ThresholdSecretSharingScheme<T, (usize, T)>
+        + LinearSharingScheme<T, (usize, T)>
+        + CliqueCommunicationScheme<T, (usize, T)>
+        + UnboundedMultiplicationScheme<T, (usize, T), Self>
+        + RandomNumberGenerationScheme<T, (usize, T), Self>
+        + UnboundedInversionScheme<T, (usize, T), Self>
+        + Send
+        + Sync,
+    T: PrimeField + Send + Sync + 'static,
+{
+    type Delegate = P::Delegate;
+}
+
+/// An event reported to a `ProtocolObserver`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProtocolEvent<T> {
+    /// A secret was revealed
+    Reveal { label: &'static str, value: T },
+
+    /// A secret was distributed
+    Distribute {
+        label: &'static str,
+        secret_known: bool,
+    },
+
+    /// A round of communication of a scheme completed
+    RoundComplete { scheme_name: &'static str },
+}
+
+/// An observer that records all events in the order they are reported.
+pub struct RecordingObserver<T> {
+    events: Mutex<Vec<ProtocolEvent<T>>>,
+}
+
+impl<T> RecordingObserver<T>
+where
+    T: Clone,
+{
+    pub fn new() -> Self {
+        RecordingObserver {
+            events: Mutex::new(vec![]),
+        }
+    }
+
+    /// All events recorded so far
+    pub fn events(&self) -> Vec<ProtocolEvent<T>> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+impl<T> Default for RecordingObserver<T>
+where
+    T: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, S> ProtocolObserver<T, S> for RecordingObserver<T>
+where
+    T: Clone,
+{
+    fn on_reveal(&self, label: &'static str, value: &T) {
+        self.events.lock().unwrap().push(ProtocolEvent::Reveal {
+            label,
+            value: value.clone(),
+        });
+    }
+
+    fn on_distribute(&self, label: &'static str, secret_known: bool) {
+        self.events.lock().unwrap().push(ProtocolEvent::Distribute {
+            label,
+            secret_known,
+        });
+    }
+
+    fn on_round_complete(&self, scheme_name: &'static str) {
+        self.events
+            .lock()
+            .unwrap()
+            .push(ProtocolEvent::RoundComplete { scheme_name });
+    }
+}
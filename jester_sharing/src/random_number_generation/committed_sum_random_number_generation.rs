@@ -0,0 +1,198 @@
+//! A random number generation that commits all participants to their contributions before they are revealed. Every
+//! participant shares a random contribution, hides each share behind a salted hash commitment and broadcasts the
+//! commitments. Only after all commitments were received, each share and its salt are sent to the participant the
+//! share belongs to, who verifies them against the commitment. The random number is the sum of all contributions.
+
+use crate::rounds::{decode_field_elements, encode_field_elements};
+use crate::{
+    CliqueCommunicationScheme, CommittedRandomNumberGenerationScheme, FieldRng, IndexedShare,
+    LinearSharingScheme, PrimeField, ProtocolException, RawCommunicationScheme, RoundParticipants,
+    SessionTag, ThresholdSecretSharingScheme,
+};
+use futures::Future;
+use jester_hashes::sm3::SM3Hash;
+use jester_hashes::{constant_time_eq, BlockHashFunction, DefaultContextHash, HashValue};
+use std::marker::PhantomData;
+use std::pin::Pin;
+
+/// Separates the commitments of this scheme from other hashes of the same data
+const COMMITMENT_DOMAIN: &[u8] = b"jester_sharing committed random number";
+
+/// The number of random bits the salt of a commitment contains at least
+const SALT_BITS: usize = 128;
+
+/// A committed random number generation scheme, that sums the contributions of all participants like
+/// `SumRandomNumberGeneration`. The commitments are hashes of the hash function `H`.
+/// # Usage
+/// ```
+/// use jester_sharing::{CommittedRandomNumberGenerationSchemeDelegate,
+/// CommittedRandomNumberGenerationSchemeMarker, Delegate, ThresholdSecretSharingScheme, LinearSharingScheme,
+/// CliqueCommunicationScheme, RawCommunicationScheme, IndexedShare, PrimeField};
+/// use jester_sharing::random_number_generation::committed_sum_random_number_generation::
+/// CommittedSumRandomNumberGeneration;
+///
+/// struct ExampleProtocol;
+///
+/// // snip: implementations for ThresholdSecretSharingScheme, LinearSharingScheme, CliqueCommunicationScheme and
+/// // RawCommunicationScheme for ExampleProtocol
+///
+/// impl CommittedRandomNumberGenerationSchemeMarker for ExampleProtocol {
+///     type Marker = Delegate;
+/// }
+///
+/// impl<T, S, P> CommittedRandomNumberGenerationSchemeDelegate<T, S, P> for ExampleProtocol
+/// where
+///     P: ThresholdSecretSharingScheme<T, S>
+///         + LinearSharingScheme<T, S>
+///         + CliqueCommunicationScheme<T, S>
+///         + RawCommunicationScheme
+///         + Send,
+///     T: PrimeField + Send + Sync + 'static,
+///     S: IndexedShare<T> + Send + 'static, {
+///     type Delegate = CommittedSumRandomNumberGeneration<T, S, P>;
+/// }
+/// ```
+pub struct CommittedSumRandomNumberGeneration<T, S, P, H = SM3Hash> {
+    data: PhantomData<T>,
+    share: PhantomData<S>,
+    protocol: PhantomData<P>,
+    hash: PhantomData<H>,
+}
+
+impl<T, S, P, H> CommittedSumRandomNumberGeneration<T, S, P, H>
+where
+    T: PrimeField,
+    H: BlockHashFunction,
+    H::Context: Default,
+{
+    /// The number of field elements of a salt, such that it contains at least `SALT_BITS` random bits
+    fn salt_length() -> usize {
+        let element_bits = (T::field_prime().as_uint().bits() - 1).max(1);
+        SALT_BITS.div_ceil(element_bits)
+    }
+
+    /// The size of a commitment in bytes
+    fn commitment_size() -> usize {
+        H::output_size(&H::Context::default())
+    }
+
+    /// Commit to the `opening` that the participant `sender` sends to the participant `recipient` in the
+    /// operation `tag`. The opening is a salt followed by the committed share value.
+    fn commit(tag: SessionTag, sender: usize, recipient: usize, opening: &[T]) -> Vec<u8> {
+        H::digest(
+            &[
+                COMMITMENT_DOMAIN,
+                &tag.to_u64().to_le_bytes(),
+                &(sender as u64).to_le_bytes(),
+                &(recipient as u64).to_le_bytes(),
+                &encode_field_elements(opening),
+            ]
+            .concat(),
+        )
+        .raw()
+    }
+}
+
+impl<T, S, P, H> CommittedRandomNumberGenerationScheme<T, S, P>
+    for CommittedSumRandomNumberGeneration<T, S, P, H>
+where
+    P: ThresholdSecretSharingScheme<T, S>
+        + LinearSharingScheme<T, S>
+        + CliqueCommunicationScheme<T, S>
+        + RawCommunicationScheme
+        + Send,
+    T: PrimeField + Send + Sync + 'static,
+    S: IndexedShare<T> + Send + 'static,
+    H: BlockHashFunction,
+    H::Context: Default,
+{
+    fn generate_committed_random_number_sharing<'a, R>(
+        rng: &mut R,
+        protocol: &'a mut P,
+        participants: RoundParticipants,
+    ) -> Pin<Box<dyn Future<Output = Result<S, ProtocolException>> + Send + 'a>>
+    where
+        R: FieldRng,
+    {
+        let contribution = rng.next_field_element("CommittedSumRandomNumberGeneration");
+        let shares = P::generate_shares(
+            rng,
+            &contribution,
+            participants.participant_count(),
+            participants.threshold(),
+        );
+
+        // the opening of participant i's share is a fresh salt followed by the value of the share
+        let salt_length = Self::salt_length();
+        let openings = shares
+            .iter()
+            .map(|share| {
+                let mut opening = (0..salt_length)
+                    .map(|_| rng.next_field_element("CommittedSumRandomNumberGeneration"))
+                    .collect::<Vec<T>>();
+                opening.push(share.to_indexed().1);
+                opening
+            })
+            .collect::<Vec<_>>();
+
+        let commitment_tag = protocol.new_session_tag();
+        let opening_tag = protocol.new_session_tag();
+        let participant_id = participants.participant_id();
+        let commitments = openings
+            .iter()
+            .enumerate()
+            .flat_map(|(index, opening)| {
+                Self::commit(commitment_tag, participant_id, index + 1, opening)
+            })
+            .collect::<Vec<_>>();
+
+        Box::pin(async move {
+            protocol.broadcast_bytes(commitment_tag, commitments).await;
+            let received_commitments = protocol.collect_bytes(commitment_tag).await;
+
+            // no opening is sent before the commitments of all participants were received
+            for recipient in participants.others() {
+                protocol
+                    .send_bytes(
+                        opening_tag,
+                        recipient,
+                        encode_field_elements(&openings[recipient - 1]),
+                    )
+                    .await;
+            }
+            let received_openings = protocol.collect_bytes(opening_tag).await;
+
+            let commitment_size = Self::commitment_size();
+            let mut summands = vec![S::from_indexed(
+                participant_id,
+                openings[participant_id - 1][salt_length].clone(),
+            )];
+            for ((sender, commitments), (_, opening)) in
+                received_commitments.iter().zip(&received_openings)
+            {
+                let malformed = ProtocolException::MalformedMessage {
+                    participant: *sender,
+                };
+                if commitments.len() != commitment_size * participants.participant_count() {
+                    return Err(malformed);
+                }
+                let mut opening = decode_field_elements::<T>(opening)
+                    .filter(|opening| opening.len() == salt_length + 1)
+                    .ok_or(malformed)?;
+
+                let commitment =
+                    &commitments[commitment_size * (participant_id - 1)..][..commitment_size];
+                let expected = Self::commit(commitment_tag, *sender, participant_id, &opening);
+                if !constant_time_eq(&expected, commitment) {
+                    return Err(ProtocolException::CommitmentMismatch {
+                        participant: *sender,
+                    });
+                }
+
+                summands.push(S::from_indexed(participant_id, opening.pop().unwrap()));
+            }
+
+            Ok(P::sum_shares(&summands).unwrap())
+        })
+    }
+}
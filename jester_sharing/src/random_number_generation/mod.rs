@@ -1,16 +1,55 @@
 use crate::{
-    CliqueCommunicationScheme, CryptoRng, Delegate, LinearSharingScheme, PrimeField, RngCore,
+    CliqueCommunicationScheme, CryptoRng, Delegate, IndexedShare, LinearSharingScheme, PrimeField,
+    ProtocolException, RawCommunicationScheme, RngCore, RoundParticipants,
     ThresholdSecretSharingScheme,
 };
 use futures::Future;
 use jester_sharing_proc::delegatable_protocol;
 use std::pin::Pin;
 
+#[cfg(feature = "commitments")]
+pub mod committed_sum_random_number_generation;
 pub mod root_random_bit_generation;
 pub mod sum_random_number_generation;
 
 pub(crate) mod sum_non_zero_random_number_generation;
 
+/// A source of random field elements. All protocols draw their randomness through this trait, so a caller can observe
+/// or replay the random choices of a protocol run (see `testing::DeterministicProtocolRng`).
+pub trait FieldRng {
+    /// Draw a uniformly random member of the field `T`.
+    ///
+    /// # Parameters
+    /// - `label` names the scheme drawing the element. It carries no meaning for the drawn value.
+    fn next_field_element<T: PrimeField>(&mut self, label: &'static str) -> T;
+
+    /// Draw a uniformly random member of the field `T`, that is not zero.
+    ///
+    /// # Parameters
+    /// - `label` names the scheme drawing the element. It carries no meaning for the drawn value.
+    fn next_nonzero_field_element<T: PrimeField>(&mut self, label: &'static str) -> T {
+        loop {
+            let element: T = self.next_field_element(label);
+            if !element.is_zero() {
+                return element;
+            }
+        }
+    }
+}
+
+impl<R> FieldRng for R
+where
+    R: RngCore + CryptoRng,
+{
+    fn next_field_element<T: PrimeField>(&mut self, _label: &'static str) -> T {
+        T::generate_random_member(self)
+    }
+
+    fn next_nonzero_field_element<T: PrimeField>(&mut self, _label: &'static str) -> T {
+        T::generate_random_nonzero_member(self)
+    }
+}
+
 /// A scheme that can be used to randomly generate a share of a number that is unknown to all participants.
 /// # Type Parameters
 /// - `T` the secret type
@@ -35,7 +74,45 @@ where
         protocol: &mut P,
     ) -> Pin<Box<dyn Future<Output = S> + Send>>
     where
-        R: RngCore + CryptoRng;
+        R: FieldRng;
+}
+
+/// A scheme that randomly generates a share of a number that is unknown to all participants, like a
+/// `RandomNumberGenerationScheme`, but commits all participants to their contributions before any contribution is
+/// revealed. Thus a rushing participant, that waits for the contributions of all others, cannot bias the number.
+/// # Type Parameters
+/// - `T` the secret type
+/// - `S` the share type
+/// - `P` the protocol this scheme requires. It must support exchanging raw messages (see
+///   `RawCommunicationScheme`).
+#[delegatable_protocol]
+pub trait CommittedRandomNumberGenerationScheme<T, S, P>
+where
+    P: ThresholdSecretSharingScheme<T, S>
+        + LinearSharingScheme<T, S>
+        + CliqueCommunicationScheme<T, S>
+        + RawCommunicationScheme
+        + Send,
+    T: PrimeField + Send + Sync + 'static,
+    S: IndexedShare<T> + Send + 'static,
+{
+    /// Generate a random number and obtain a share of it, without learning the random number.
+    ///
+    /// # Parameters
+    /// - `rng` a cryptographically secure random number generator
+    /// - `protocol` the protocol instance this scheme is used within.
+    /// - `participants` the participants of the protocol, whose shares are generated by this scheme
+    ///
+    /// # Returns
+    /// Returns a future on the share, or a `ProtocolException` identifying a participant that did not open its
+    /// contribution consistently with its commitment
+    fn generate_committed_random_number_sharing<'a, R>(
+        rng: &mut R,
+        protocol: &'a mut P,
+        participants: RoundParticipants,
+    ) -> Pin<Box<dyn Future<Output = Result<S, ProtocolException>> + Send + 'a>>
+    where
+        R: FieldRng;
 }
 
 /// A scheme that can be used to randomly generate a share of a bit that is unknown to all participants.
@@ -54,5 +131,5 @@ where
         protocol: &'a mut P,
     ) -> Pin<Box<dyn Future<Output = S> + Send + 'a>>
     where
-        R: RngCore + CryptoRng;
+        R: FieldRng;
 }
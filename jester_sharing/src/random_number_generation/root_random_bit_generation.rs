@@ -1,6 +1,6 @@
 use crate::{
-    CliqueCommunicationScheme, CryptoRng, LinearSharingScheme, MultiplicationScheme, PrimeField,
-    RandomBitGenerationScheme, RandomNumberGenerationScheme, RngCore, ThresholdSecretSharingScheme,
+    CliqueCommunicationScheme, FieldRng, LinearSharingScheme, MultiplicationScheme, PrimeField,
+    RandomBitGenerationScheme, RandomNumberGenerationScheme, ThresholdSecretSharingScheme,
 };
 use futures::Future;
 use std::marker::PhantomData;
@@ -66,14 +66,15 @@ where
         protocol: &'a mut P,
     ) -> Pin<Box<dyn Future<Output = S> + Send + 'a>>
     where
-        R: RngCore + CryptoRng,
+        R: FieldRng,
     {
         let r = P::generate_random_number_sharing(rng, protocol);
 
         Box::pin(async move {
             let r = r.await;
             let square = P::multiply(protocol, &r, &r).await;
-            let square_revealed = protocol.reveal_shares(square).await;
+            let tag = protocol.new_session_tag();
+            let square_revealed = protocol.reveal_shares_tagged(tag, square).await;
             let square_root: T = unimplemented!(); // calculate the root of the revealed number
             P::multiply_scalar(
                 &P::add_scalar(&P::multiply_scalar(&r, &square_root.inverse()), &T::one()),
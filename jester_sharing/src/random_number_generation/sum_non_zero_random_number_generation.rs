@@ -5,8 +5,8 @@
 #![cfg(test)]
 
 use crate::{
-    CliqueCommunicationScheme, CryptoRng, LinearSharingScheme, PrimeField,
-    RandomNumberGenerationScheme, RngCore, ThresholdSecretSharingScheme,
+    CliqueCommunicationScheme, FieldRng, LinearSharingScheme, PrimeField,
+    RandomNumberGenerationScheme, ThresholdSecretSharingScheme,
 };
 use futures::Future;
 use std::marker::PhantomData;
@@ -41,14 +41,12 @@ where
         protocol: &mut P,
     ) -> Pin<Box<dyn Future<Output = S> + Send>>
     where
-        R: RngCore + CryptoRng,
+        R: FieldRng,
     {
-        let mut rand_partial = T::generate_random_member(rng);
-        while rand_partial.is_zero() {
-            rand_partial = T::generate_random_member(rng);
-        }
+        let rand_partial: T = rng.next_nonzero_field_element("SumNonZeroRandomNumberGeneration");
 
-        let all_shares_future = protocol.distribute_secret(rand_partial);
+        let tag = protocol.new_session_tag();
+        let all_shares_future = protocol.distribute_secret_tagged(tag, rand_partial);
 
         Box::pin(async move { P::sum_shares(&all_shares_future.await).unwrap() })
     }
@@ -1,6 +1,6 @@
 use crate::{
-    CliqueCommunicationScheme, CryptoRng, LinearSharingScheme, PrimeField,
-    RandomNumberGenerationScheme, RngCore, ThresholdSecretSharingScheme,
+    CliqueCommunicationScheme, FieldRng, LinearSharingScheme, PrimeField,
+    RandomNumberGenerationScheme, ThresholdSecretSharingScheme,
 };
 use futures::Future;
 use std::marker::PhantomData;
@@ -58,10 +58,11 @@ where
         protocol: &mut P,
     ) -> Pin<Box<dyn Future<Output = S> + Send>>
     where
-        R: RngCore + CryptoRng,
+        R: FieldRng,
     {
-        let rand_partial = T::generate_random_member(rng);
-        let all_shares_future = protocol.distribute_secret(rand_partial);
+        let rand_partial = rng.next_field_element("SumRandomNumberGeneration");
+        let tag = protocol.new_session_tag();
+        let all_shares_future = protocol.distribute_secret_tagged(tag, rand_partial);
 
         Box::pin(async move { P::sum_shares(&all_shares_future.await).unwrap() })
     }
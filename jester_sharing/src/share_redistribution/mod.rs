@@ -0,0 +1,324 @@
+//! Redistribution of shared secrets to a new set of participants, possibly with a different threshold, without
+//! reconstructing them. Every old participant shares its own share among the new participants, and each new
+//! participant interpolates the sub-shares it received at zero, using the Lagrange coefficients of the old shares'
+//! indices. The result is a share of the original secret under a fresh polynomial, so old shares cannot be combined
+//! with new ones.
+
+use crate::shamir_secret_sharing::{LagrangeBasis, ShamirSecretSharing};
+use crate::{
+    CliqueCommunicationScheme, Delegate, FieldRng, IndexedShare, PrimeField, ProtocolException,
+    RawCommunicationScheme, ReconstructionException, ThresholdSecretSharingScheme,
+};
+use futures::Future;
+use jester_sharing_proc::delegatable_protocol;
+use std::pin::Pin;
+
+pub mod shamir_share_redistribution;
+
+/// The participants of a redistribution. The old and the new participants are connected by a single transition
+/// clique of `old_count + new_count` participants: the old participant holding the share with index `i` has the id
+/// `i`, and the new participant receiving the share with index `j` has the id `old_count + j`. A party that is part
+/// of both sets takes part with two protocol instances.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedistributionParticipants {
+    participant_id: usize,
+    old_count: usize,
+    old_indices: Vec<usize>,
+    new_count: usize,
+    new_threshold: usize,
+}
+
+impl RedistributionParticipants {
+    /// Describe the participant `participant_id` of a transition clique.
+    ///
+    /// # Parameters
+    /// - `participant_id` the id of this participant in the transition clique
+    /// - `old_count` the number of old participants
+    /// - `old_indices` the indices of the old shares that are redistributed. There must be at least as many as the
+    ///   old threshold, all other old participants stay idle.
+    /// - `new_count` the number of new participants
+    /// - `new_threshold` how many new shares are required to reconstruct the secret
+    ///
+    /// # Panics
+    /// If `participant_id` is not within the transition clique, an old index is not within `1..=old_count` or
+    /// used twice, or `new_threshold` is not within `2..=new_count`.
+    pub fn new(
+        participant_id: usize,
+        old_count: usize,
+        old_indices: &[usize],
+        new_count: usize,
+        new_threshold: usize,
+    ) -> Self {
+        assert!(
+            (1..=old_count + new_count).contains(&participant_id),
+            "participant id {} is not within 1..={}",
+            participant_id,
+            old_count + new_count
+        );
+        assert!(
+            (2..=new_count).contains(&new_threshold),
+            "new threshold {} is not within 2..={}",
+            new_threshold,
+            new_count
+        );
+
+        let mut sorted_indices = old_indices.to_vec();
+        sorted_indices.sort_unstable();
+        sorted_indices.dedup();
+        assert!(
+            sorted_indices.len() == old_indices.len()
+                && sorted_indices
+                    .iter()
+                    .all(|index| (1..=old_count).contains(index)),
+            "old indices {:?} are not distinct indices within 1..={}",
+            old_indices,
+            old_count
+        );
+
+        RedistributionParticipants {
+            participant_id,
+            old_count,
+            old_indices: sorted_indices,
+            new_count,
+            new_threshold,
+        }
+    }
+
+    /// The id of this participant in the transition clique
+    pub fn participant_id(&self) -> usize {
+        self.participant_id
+    }
+
+    /// The number of participants of the transition clique
+    pub fn participant_count(&self) -> usize {
+        self.old_count + self.new_count
+    }
+
+    /// The indices of the redistributed old shares in ascending order
+    pub fn old_indices(&self) -> &[usize] {
+        &self.old_indices
+    }
+
+    /// The number of new participants
+    pub fn new_count(&self) -> usize {
+        self.new_count
+    }
+
+    /// The reconstruction threshold of the new shares
+    pub fn new_threshold(&self) -> usize {
+        self.new_threshold
+    }
+
+    /// Whether this participant redistributes its old share
+    pub fn is_dealer(&self) -> bool {
+        self.old_indices.contains(&self.participant_id)
+    }
+
+    /// The index of the new share this participant receives, or `None` if it is an old participant
+    pub fn new_index(&self) -> Option<usize> {
+        self.participant_id
+            .checked_sub(self.old_count)
+            .filter(|index| *index > 0)
+    }
+
+    /// The id of the new participant receiving the share with the given index
+    pub fn new_participant_id(&self, new_index: usize) -> usize {
+        self.old_count + new_index
+    }
+
+    /// The ids of all participants of the transition clique except this one
+    pub fn others(&self) -> impl Iterator<Item = usize> {
+        let participant_id = self.participant_id;
+        (1..=self.participant_count()).filter(move |id| *id != participant_id)
+    }
+}
+
+/// Share the value of an old `share` among `new_count` new participants. The sub-share with index `j` is sent to
+/// the new participant with index `j`.
+///
+/// # Parameters
+/// - `rng` a cryptographically secure random number generator
+/// - `share` the old share that is redistributed
+/// - `new_count` the number of new participants
+/// - `new_threshold` how many new shares are required to reconstruct the secret
+pub fn deal_sub_shares<T, R>(
+    rng: &mut R,
+    share: &(usize, T),
+    new_count: usize,
+    new_threshold: usize,
+) -> Vec<(usize, T)>
+where
+    T: PrimeField,
+    R: FieldRng,
+{
+    ShamirSecretSharing::generate_shares(rng, &share.1, new_count, new_threshold)
+}
+
+/// Combine the sub-shares a new participant received into its share of the redistributed secret.
+///
+/// # Parameters
+/// - `new_index` the index of the new share
+/// - `sub_shares` the values of the received sub-shares, each paired with the index of the old share it was dealt
+///   from
+///
+/// # Returns
+/// The new share or a `ReconstructionException` if the old indices are empty or contain duplicates
+pub fn combine_sub_shares<T>(
+    new_index: usize,
+    sub_shares: &[(usize, T)],
+) -> Result<(usize, T), ReconstructionException>
+where
+    T: PrimeField,
+{
+    let old_indices = sub_shares
+        .iter()
+        .map(|(index, _)| *index)
+        .collect::<Vec<_>>();
+    let value = LagrangeBasis::new(&old_indices)?.interpolate(sub_shares)?;
+    Ok((new_index, value))
+}
+
+/// A scheme that moves shares of a secret from one set of participants to another, without reconstructing the
+/// secret. Afterwards, the old shares must be deleted, as enough of them still reconstruct the secret.
+/// # Type Parameters
+/// - `T` the secret type
+/// - `S` the share type
+/// - `P` the protocol of the transition clique (see `RedistributionParticipants`). It must support exchanging raw
+///   messages (see `RawCommunicationScheme`).
+#[delegatable_protocol]
+pub trait ShareRedistributionScheme<T, S, P>
+where
+    P: CliqueCommunicationScheme<T, S> + RawCommunicationScheme + Send,
+    T: PrimeField + Send + Sync + 'static,
+    S: IndexedShare<T> + Send + 'static,
+{
+    /// Redistribute the old shares to the new participants.
+    ///
+    /// # Parameters
+    /// - `rng` a cryptographically secure random number generator
+    /// - `protocol` the protocol instance of this participant in the transition clique
+    /// - `participants` the participants of the redistribution
+    /// - `share` the old share of this participant, if it is one of the redistributed shares
+    ///
+    /// # Returns
+    /// Returns a future on the new share of this participant, or `None` for old participants. The future fails with
+    /// `ProtocolException::InvalidInput` if a redistributed share is missing and with
+    /// `ProtocolException::MalformedMessage` if an old participant sent a malformed sub-share.
+    #[allow(clippy::type_complexity)]
+    fn redistribute<'a, R>(
+        rng: &mut R,
+        protocol: &'a mut P,
+        participants: RedistributionParticipants,
+        share: Option<S>,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<S>, ProtocolException>> + Send + 'a>>
+    where
+        R: FieldRng;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jester_maths::prime::Mersenne61;
+    use num::FromPrimitive;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// Redistribute the shares with the given old indices to `new_count` new participants
+    fn redistribute_locally(
+        rng: &mut StdRng,
+        old_shares: &[(usize, Mersenne61)],
+        new_count: usize,
+        new_threshold: usize,
+    ) -> Vec<(usize, Mersenne61)> {
+        let dealt = old_shares
+            .iter()
+            .map(|share| deal_sub_shares(rng, share, new_count, new_threshold))
+            .collect::<Vec<_>>();
+
+        (1..=new_count)
+            .map(|new_index| {
+                let sub_shares = old_shares
+                    .iter()
+                    .zip(&dealt)
+                    .map(|((old_index, _), sub_shares)| {
+                        (*old_index, sub_shares[new_index - 1].1.clone())
+                    })
+                    .collect::<Vec<_>>();
+                combine_sub_shares(new_index, &sub_shares).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_redistribution() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let secret = Mersenne61::from_u64(0xC0FFEE).unwrap();
+        let old_shares = ShamirSecretSharing::generate_shares(&mut rng, &secret, 5, 3);
+
+        // any three old participants can redistribute the secret
+        for old_indices in &[[1, 2, 3], [2, 4, 5], [1, 3, 5]] {
+            let selected = old_indices
+                .iter()
+                .map(|index| old_shares[index - 1].clone())
+                .collect::<Vec<_>>();
+            let new_shares = redistribute_locally(&mut rng, &selected, 3, 2);
+
+            for pair in &[[0, 1], [1, 2], [2, 0]] {
+                let shares = [new_shares[pair[0]].clone(), new_shares[pair[1]].clone()];
+                assert_eq!(
+                    ShamirSecretSharing::reconstruct_secret(&shares, 2).unwrap(),
+                    secret
+                );
+            }
+
+            // the new polynomial is unrelated to the old one
+            let mixed = [
+                old_shares[3].clone(),
+                new_shares[0].clone(),
+                new_shares[1].clone(),
+            ];
+            assert_ne!(
+                ShamirSecretSharing::reconstruct_secret(&mixed, 3).unwrap(),
+                secret
+            );
+            let mixed = [new_shares[0].clone(), old_shares[4].clone()];
+            assert_ne!(
+                ShamirSecretSharing::reconstruct_secret(&mixed, 2).unwrap(),
+                secret
+            );
+        }
+    }
+
+    #[test]
+    fn test_combine_duplicate_dealers() {
+        let sub_shares = [
+            (1, Mersenne61::from_u64(1).unwrap()),
+            (1, Mersenne61::from_u64(2).unwrap()),
+        ];
+        assert_eq!(
+            combine_sub_shares(1, &sub_shares),
+            Err(ReconstructionException::DuplicateShareIndex { index: 1 })
+        );
+    }
+
+    #[test]
+    fn test_participants() {
+        let old = RedistributionParticipants::new(2, 5, &[5, 2, 1], 3, 2);
+        assert_eq!(old.participant_count(), 8);
+        assert_eq!(old.old_indices(), &[1, 2, 5]);
+        assert!(old.is_dealer());
+        assert_eq!(old.new_index(), None);
+
+        let new = RedistributionParticipants::new(7, 5, &[1, 2, 5], 3, 2);
+        assert!(!new.is_dealer());
+        assert_eq!(new.new_index(), Some(2));
+        assert_eq!(new.new_participant_id(2), 7);
+        assert_eq!(new.others().count(), 7);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_participants_duplicate_old_index() {
+        RedistributionParticipants::new(1, 5, &[1, 2, 2], 3, 2);
+    }
+}
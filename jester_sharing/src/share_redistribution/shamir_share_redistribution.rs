@@ -0,0 +1,95 @@
+//! Redistribution of Shamir shares within a transition clique. Every old participant deals sub-shares of its share
+//! in a single round, and every new participant combines the sub-shares it received locally.
+
+use crate::rounds::{decode_field_elements, encode_field_elements};
+use crate::share_redistribution::{combine_sub_shares, deal_sub_shares};
+use crate::{
+    CliqueCommunicationScheme, FieldRng, IndexedShare, PrimeField, ProtocolException,
+    RawCommunicationScheme, RedistributionParticipants, ShareRedistributionScheme,
+};
+use futures::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+
+/// A share redistribution scheme for Shamir shares. Every participant of the transition clique sends a message to
+/// every other participant, which is empty unless it carries a sub-share, because raw messages are collected from
+/// all participants.
+pub struct ShamirShareRedistribution<T, S, P> {
+    data: PhantomData<T>,
+    share: PhantomData<S>,
+    protocol: PhantomData<P>,
+}
+
+impl<T, S, P> ShareRedistributionScheme<T, S, P> for ShamirShareRedistribution<T, S, P>
+where
+    P: CliqueCommunicationScheme<T, S> + RawCommunicationScheme + Send,
+    T: PrimeField + Send + Sync + 'static,
+    S: IndexedShare<T> + Send + 'static,
+{
+    fn redistribute<'a, R>(
+        rng: &mut R,
+        protocol: &'a mut P,
+        participants: RedistributionParticipants,
+        share: Option<S>,
+    ) -> Pin<Box<dyn Future<Output = Result<Option<S>, ProtocolException>> + Send + 'a>>
+    where
+        R: FieldRng,
+    {
+        let sub_shares = if participants.is_dealer() {
+            match share.map(|share| share.to_indexed()) {
+                Some(share) if share.0 == participants.participant_id() => deal_sub_shares(
+                    rng,
+                    &share,
+                    participants.new_count(),
+                    participants.new_threshold(),
+                ),
+                _ => {
+                    return Box::pin(async {
+                        Err(ProtocolException::InvalidInput {
+                            reason: "the share of a redistributing participant is missing",
+                        })
+                    })
+                }
+            }
+        } else {
+            vec![]
+        };
+
+        let tag = protocol.new_session_tag();
+
+        Box::pin(async move {
+            for recipient in participants.others() {
+                let payload = sub_shares
+                    .iter()
+                    .find(|(index, _)| participants.new_participant_id(*index) == recipient)
+                    .map(|(_, sub_share)| encode_field_elements(std::slice::from_ref(sub_share)))
+                    .unwrap_or_default();
+                protocol.send_bytes(tag, recipient, payload).await;
+            }
+            let messages = protocol.collect_bytes(tag).await;
+
+            let new_index = match participants.new_index() {
+                Some(new_index) => new_index,
+                None => return Ok(None),
+            };
+
+            let mut received = Vec::with_capacity(participants.old_indices().len());
+            for dealer in participants.old_indices() {
+                let sub_share = messages
+                    .iter()
+                    .find(|(sender, _)| sender == dealer)
+                    .and_then(|(_, payload)| decode_field_elements::<T>(payload))
+                    .filter(|sub_share| sub_share.len() == 1)
+                    .map(|mut sub_share| sub_share.pop().unwrap())
+                    .ok_or(ProtocolException::MalformedMessage {
+                        participant: *dealer,
+                    })?;
+                received.push((*dealer, sub_share));
+            }
+
+            let (index, value) = combine_sub_shares(new_index, &received)
+                .expect("the old indices are distinct and nonzero");
+            Ok(Some(S::from_indexed(index, value)))
+        })
+    }
+}
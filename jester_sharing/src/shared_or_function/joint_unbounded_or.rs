@@ -1,7 +1,8 @@
 use crate::{
-    BigUint, CliqueCommunicationScheme, CryptoRng, LinearSharingScheme, OrFunctionScheme,
-    PrimeField, RandomNumberGenerationScheme, RngCore, ThresholdSecretSharingScheme,
-    UnboundedInversionScheme, UnboundedMultiplicationScheme, UnboundedOrFunctionScheme,
+    join_limited, BigUint, CliqueCommunicationScheme, FieldRng, LinearSharingScheme,
+    OrCoefficientCache, OrFunctionScheme, PrimeField, ProtocolException,
+    RandomNumberGenerationScheme, ThresholdSecretSharingScheme, UnboundedInversionScheme,
+    UnboundedMultiplicationScheme, UnboundedOrFunctionScheme,
 };
 
 use futures::Future;
@@ -12,7 +13,6 @@ use std::pin::Pin;
 use futures::lock::Mutex;
 use futures::{future::join_all, join};
 use lazy_static::*;
-use num::FromPrimitive;
 
 pub struct JointUnboundedOrFunction<T, S, P>(PhantomData<T>, PhantomData<S>, PhantomData<P>)
 where
@@ -40,16 +40,17 @@ where
     T: Send + Sync + PrimeField + 'static,
     S: Send + Sync + Clone + 'static,
 {
+    /// The OR of a single bit is the bit itself, so its share is returned without any communication.
     fn shared_or<'a, R>(
-        rng: &'a mut R,
-        protocol: &'a mut P,
+        _rng: &'a mut R,
+        _protocol: &'a mut P,
         bits: &S,
-    ) -> Pin<Box<dyn Future<Output = S> + 'a>>
+    ) -> Pin<Box<dyn Future<Output = S> + Send + 'a>>
     where
-        R: RngCore + CryptoRng,
+        R: FieldRng + Send,
     {
-        let bits_vec = vec![bits.clone()];
-        Self::unbounded_shared_or(rng, protocol, &bits_vec)
+        let bit = bits.clone();
+        Box::pin(async move { bit })
     }
 }
 
@@ -66,15 +67,54 @@ where
     T: Send + Sync + PrimeField + 'static,
     S: Send + Sync + Clone + 'static,
 {
+    /// Evaluate the OR function with a fresh `OrCoefficientCache`.
     fn unbounded_shared_or<'a, R>(
         rng: &'a mut R,
         protocol: &'a mut P,
         bits: &[S],
-    ) -> Pin<Box<dyn Future<Output = S> + 'a>>
+    ) -> Pin<Box<dyn Future<Output = Result<S, ProtocolException>> + Send + 'a>>
+    where
+        R: FieldRng + Send,
+    {
+        let bits = bits.to_vec();
+
+        Box::pin(async move {
+            let mut cache = OrCoefficientCache::new();
+            Self::unbounded_shared_or_with_cache(rng, protocol, &bits, &mut cache).await
+        })
+    }
+
+    fn unbounded_shared_or_with_cache<'a, R>(
+        rng: &'a mut R,
+        protocol: &'a mut P,
+        bits: &[S],
+        cache: &'a mut OrCoefficientCache<T>,
+    ) -> Pin<Box<dyn Future<Output = Result<S, ProtocolException>> + Send + 'a>>
     where
-        R: RngCore + CryptoRng,
+        R: FieldRng + Send,
     {
-        assert!(!bits.is_empty());
+        if bits.is_empty() {
+            return Box::pin(async {
+                Err(ProtocolException::InvalidInput {
+                    reason: "the OR of zero bits has no share",
+                })
+            });
+        }
+
+        // the OR of a single bit is the bit itself
+        if bits.len() == 1 {
+            let bit = bits[0].clone();
+            return Box::pin(async move { Ok(bit) });
+        }
+
+        // the polynomial interpolates the `l + 1` possible values of the sum, which must be distinct in the field
+        if BigUint::from(bits.len()) >= T::field_prime().as_uint() {
+            return Box::pin(async {
+                Err(ProtocolException::InvalidInput {
+                    reason: "the field must have more elements than the number of bits",
+                })
+            });
+        }
 
         // compute a polynomial share of the sum of all `l` bits plus one.
         let sum = P::add_scalar(&P::sum_shares(bits).unwrap(), &T::one());
@@ -82,30 +122,14 @@ where
         let degree = bits.len(); // `l`
 
         Box::pin(async move {
-            // now define an `l`-degree polynomial f(x) such that `f(1) = 0, f(2) = f(3) = ... = f(l + 1) = 1`. Note that
-            // f(sum) = bits[0] | bits[1] | ... | bits[l]. Choose `l + 1` samples from the polynomial. Conveniently, the
-            // samples at points `(1..l+1)` are chosen. Those samples are the lagrange-coefficients of the polynomial and can
-            // be transformed to monomial-coefficients by multiplication with the inverse vandermonde-matrix
-            let lagrange_coefficients: Vec<_> = (1..=degree + 1)
-                .map(|a| if a == 1 { 0_usize } else { 1_usize })
-                .collect();
-
-            let monomial_coefficients: Vec<T> = join_all((0..=degree).map(|i| {
-                let iter_clone = lagrange_coefficients.iter();
-                async move {
-                    join_all(iter_clone.enumerate().map(|(j, c)| {
-                        async move {
-                            get_inverted_vandermonde_entry::<T>(i as isize, j as isize, degree + 1)
-                                .await
-                                * BigUint::from(*c).into()
-                        }
-                    }))
-                    .await
-                    .into_iter()
-                    .sum()
+            let monomial_coefficients = match cache.coefficients(degree) {
+                Some(coefficients) => coefficients.to_vec(),
+                None => {
+                    let coefficients = or_polynomial_coefficients::<T>(degree).await;
+                    cache.insert(degree, coefficients.clone(), (degree + 1) * (degree + 1));
+                    coefficients
                 }
-            }))
-            .await;
+            };
 
             // generate `l` helper used for an unbounded multiplication. Those helpers will be inverted using an
             // unbounded inversion and then multiplied with the elements that are used in the unbounded multiplication such
@@ -114,27 +138,22 @@ where
             // other out and the last (inverse) helper remaining will be cancelled by all parties independently by
             // multiplying their share of that helper. This way, all parties obtain a share of the unbounded multiplication
             // result, but cannot learn the reconstructed result without learning the reconstructed last helper.
+            let concurrency = protocol.concurrency();
             let helpers: Vec<_> = (1..=degree)
                 .map(|_| P::generate_random_number_sharing(rng, protocol))
                 .collect();
-            let helpers = join_all(helpers).await;
-
-            let inverted_helpers = P::unbounded_inverse(rng, protocol, &helpers).await;
-
-            // multiply the `i`'th inverted helper (except the first one) with the `(i - 1)'th` helper
-            let mut cancellation_factors = vec![];
-            cancellation_factors.push(inverted_helpers[0].clone());
-            cancellation_factors.append(
-                &mut P::unbounded_multiply(
-                    protocol,
-                    &helpers[..degree - 1]
-                        .iter()
-                        .cloned()
-                        .zip(inverted_helpers[1..].iter().cloned())
-                        .collect::<Vec<_>>(),
-                )
-                .await,
-            );
+            let helpers = join_limited(concurrency, helpers).await;
+
+            // multiply the `i`'th inverted helper with the `(i - 1)'th` helper, and the first inverted helper with a
+            // share of one. The inversion and the multiplication are fused, so they require one round less than an
+            // inversion followed by a multiplication, at the cost of one more beaver triple for the share of one
+            let one = P::add_scalar(&P::sub_shares(&sum, &sum), &T::one());
+            let partners = Some(one)
+                .into_iter()
+                .chain(helpers[..degree - 1].iter().cloned())
+                .collect::<Vec<_>>();
+            let cancellation_factors =
+                P::unbounded_inverse_then_multiply(rng, protocol, &helpers, &partners).await;
 
             // unbounded multiplication keeping all factors
             let factors = P::unbounded_multiply(
@@ -149,32 +168,65 @@ where
             // reveal factors
             let revealed_factors: Vec<_> = factors
                 .iter()
-                .map(|c| protocol.reveal_shares(c.clone()))
+                .map(|c| {
+                    let tag = protocol.new_session_tag();
+                    protocol.reveal_shares_tagged(tag, c.clone())
+                })
                 .collect();
-            let revealed_factors = join_all(revealed_factors).await;
+            let revealed_factors = join_limited(concurrency, revealed_factors).await;
 
-            // calculate all powers of `sum` between `1` and `degree` and add their respective monomials
-            let powers_for_polynomial: Vec<_> = (1..=degree)
+            // the `power`'th power of `sum` is the `power - 1`'th helper multiplied by the first `power` revealed factors.
+            // Evaluate the polynomial as the linear combination of those helpers with the revealed factors and monomial
+            // coefficients, plus the constant monomial coefficient
+            let power_coefficients: Vec<T> = (1..=degree)
                 .map(|power| {
-                    P::multiply_scalar(
-                        &P::multiply_scalar(
-                            &helpers[power - 1],
-                            &revealed_factors[..power].iter().cloned().product(),
-                        ),
-                        &monomial_coefficients[power],
-                    )
+                    revealed_factors[..power].iter().cloned().product::<T>()
+                        * monomial_coefficients[power].clone()
                 })
                 .collect();
 
-            // add the constant monomial coefficient to the polynomial and sum it up
-            powers_for_polynomial[1..].iter().fold(
-                P::add_scalar(&powers_for_polynomial[0], &monomial_coefficients[0]),
-                |acc, monomial| P::add_shares(&acc, monomial),
+            Ok(P::weighted_sum_with_constant(
+                &helpers[..degree],
+                &power_coefficients,
+                &monomial_coefficients[0],
             )
+            .unwrap())
         })
     }
 }
 
+/// Compute the monomial coefficients of the `l`-degree polynomial used to evaluate the OR of `l` bits. This requires
+/// `(l + 1)^2` entries of the inverted Vandermonde matrix.
+///
+/// # Parameters
+/// - `degree` the number of bits `l`
+async fn or_polynomial_coefficients<T>(degree: usize) -> Vec<T>
+where
+    T: PrimeField + Send + Sync + 'static,
+{
+    // define an `l`-degree polynomial f(x) such that `f(1) = 0, f(2) = f(3) = ... = f(l + 1) = 1`. Note that
+    // f(sum) = bits[0] | bits[1] | ... | bits[l]. Choose `l + 1` samples from the polynomial. Conveniently, the
+    // samples at points `(1..l+1)` are chosen. Those samples are the lagrange-coefficients of the polynomial and can
+    // be transformed to monomial-coefficients by multiplication with the inverse vandermonde-matrix
+    let lagrange_coefficients: Vec<_> = (1..=degree + 1)
+        .map(|a| if a == 1 { 0_usize } else { 1_usize })
+        .collect();
+
+    join_all((0..=degree).map(|i| {
+        let iter_clone = lagrange_coefficients.iter();
+        async move {
+            join_all(iter_clone.enumerate().map(|(j, c)| async move {
+                get_inverted_vandermonde_entry::<T>(i as isize, j as isize, degree + 1).await
+                    * BigUint::from(*c).into()
+            }))
+            .await
+            .into_iter()
+            .sum()
+        }
+    }))
+    .await
+}
+
 /// A function generating the upper triangular matrix U that is defined by V = U * L, where V is the inverted
 /// Vandermonde matrix. The function generates the matrix recursively and caches results to be used later on.
 /// Asynchronicity is used to wait on a lock onto the global cache it uses for pre-calculated entries.
@@ -219,7 +271,7 @@ where
                 assert!(column >= 0);
                 assert!(row >= 0);
 
-                let x = BigUint::from_isize(column).unwrap().into();
+                let x = T::from_signed(column as i64);
 
                 let (a, b) = join!(
                     get_inverted_vandermonde_upper::<T>(row - 1, column - 1),
@@ -274,11 +326,15 @@ where
         } else if row == 0 && column == 0 {
             T::one()
         } else {
-            (0..=row)
-                .filter(|k| *k != column)
-                .map(|k| T::from_isize(column).unwrap() - T::from_isize(k).unwrap())
-                .product::<T>()
-                .inverse()
+            // the product of `column - k` for all `k` in `0..=row` except `column` is
+            // `column! * (-1)^(row - column) * (row - column)!`
+            let inverse = T::small_factorial_inverse(column as u64)
+                * T::small_factorial_inverse((row - column) as u64);
+            if (row - column) % 2 == 0 {
+                inverse
+            } else {
+                inverse.neg()
+            }
         };
 
         mutex_guard
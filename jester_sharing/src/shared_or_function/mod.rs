@@ -1,13 +1,57 @@
 use crate::{
-    CliqueCommunicationScheme, CryptoRng, Delegate, LinearSharingScheme, PrimeField,
-    RandomNumberGenerationScheme, RngCore, ThresholdSecretSharingScheme, UnboundedInversionScheme,
-    UnboundedMultiplicationScheme,
+    CliqueCommunicationScheme, Delegate, FieldRng, LinearSharingScheme, PrimeField,
+    ProtocolException, RandomNumberGenerationScheme, ThresholdSecretSharingScheme,
+    UnboundedInversionScheme, UnboundedMultiplicationScheme,
 };
 use futures::Future;
 use jester_sharing_proc::delegatable_protocol;
+use std::collections::HashMap;
 use std::pin::Pin;
 
 pub mod joint_unbounded_or;
+pub mod or_accumulator;
+
+/// A cache of the monomial coefficients of the polynomials used to evaluate an unbounded OR. The coefficients only
+/// depend on the number of bits and the field, so protocols that evaluate many OR functions of the same width should
+/// keep one cache and pass it to `unbounded_shared_or_with_cache`.
+#[derive(Debug, Clone)]
+pub struct OrCoefficientCache<T> {
+    coefficients: HashMap<usize, Vec<T>>,
+    computed_entries: usize,
+}
+
+impl<T> OrCoefficientCache<T> {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        OrCoefficientCache {
+            coefficients: HashMap::new(),
+            computed_entries: 0,
+        }
+    }
+
+    /// The monomial coefficients of the polynomial for `degree` bits, if they were computed before.
+    pub fn coefficients(&self, degree: usize) -> Option<&[T]> {
+        self.coefficients.get(&degree).map(Vec::as_slice)
+    }
+
+    /// The number of inverted Vandermonde matrix entries that were computed to fill this cache.
+    pub fn computed_entries(&self) -> usize {
+        self.computed_entries
+    }
+
+    /// Store the coefficients of the polynomial for `degree` bits, that required `computed_entries` inverted
+    /// Vandermonde matrix entries to be computed.
+    pub(crate) fn insert(&mut self, degree: usize, coefficients: Vec<T>, computed_entries: usize) {
+        self.coefficients.insert(degree, coefficients);
+        self.computed_entries += computed_entries;
+    }
+}
+
+impl<T> Default for OrCoefficientCache<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 #[delegatable_protocol]
 pub trait OrFunctionScheme<T, S, P>
@@ -27,9 +71,9 @@ where
         rng: &'a mut R,
         protocol: &'a mut P,
         bits: &S,
-    ) -> Pin<Box<dyn Future<Output = S> + 'a>>
+    ) -> Pin<Box<dyn Future<Output = S> + Send + 'a>>
     where
-        R: RngCore + CryptoRng;
+        R: FieldRng + Send;
 }
 
 #[delegatable_protocol]
@@ -46,11 +90,32 @@ where
     T: PrimeField + Send + Sync + 'static,
     S: Send + Sync + Clone + 'static,
 {
+    /// Compute a share of the OR of all `bits`, without any participant learning the bits or the result.
+    ///
+    /// # Parameters
+    /// - `rng` a cryptographically secure random number generator
+    /// - `protocol` the primitives required for this scheme
+    /// - `bits` shares of values that are either `0` or `1`
+    ///
+    /// # Returns
+    /// Returns a future on a share of the OR, or `ProtocolException::InvalidInput` if `bits` is empty or the field
+    /// is too small to evaluate the OR of that many bits
     fn unbounded_shared_or<'a, R>(
         rng: &'a mut R,
         protocol: &'a mut P,
         bits: &[S],
-    ) -> Pin<Box<dyn Future<Output = S> + 'a>>
+    ) -> Pin<Box<dyn Future<Output = Result<S, ProtocolException>> + Send + 'a>>
+    where
+        R: FieldRng + Send;
+
+    /// Like `unbounded_shared_or`, but the polynomial coefficients are taken from `cache` if they were computed
+    /// before, and stored in it otherwise.
+    fn unbounded_shared_or_with_cache<'a, R>(
+        rng: &'a mut R,
+        protocol: &'a mut P,
+        bits: &[S],
+        cache: &'a mut OrCoefficientCache<T>,
+    ) -> Pin<Box<dyn Future<Output = Result<S, ProtocolException>> + Send + 'a>>
     where
-        R: RngCore + CryptoRng;
+        R: FieldRng + Send;
 }
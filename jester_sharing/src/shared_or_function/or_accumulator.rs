@@ -0,0 +1,105 @@
+//! A running OR over bits that become available over time, like the votes of an anonymous veto or the alarms of a
+//! streaming anomaly detection. Instead of evaluating the OR polynomial of `UnboundedOrFunctionScheme` over all bits
+//! again whenever a bit arrives, the accumulator folds every bit into its current share using the identity
+//! `or(acc, b) = acc + b - acc * b`, which requires a single multiplication.
+
+use crate::{LinearSharingScheme, UnboundedMultiplicationScheme};
+use futures::Future;
+use std::marker::PhantomData;
+use std::pin::Pin;
+
+/// A share of the OR of all bits absorbed so far.
+///
+/// # Type Parameters
+/// - `T` the field the bits are shared in
+/// - `S` the share type of the bits
+/// - `P` the protocol multiplying shares
+pub struct OrAccumulator<T, S, P> {
+    current: Option<S>,
+    data: PhantomData<T>,
+    protocol: PhantomData<P>,
+}
+
+impl<T, S, P> OrAccumulator<T, S, P>
+where
+    P: LinearSharingScheme<T, S> + UnboundedMultiplicationScheme<T, S, P> + Send + Sync,
+    T: Send + Sync,
+    S: Clone + Send + Sync,
+{
+    /// Create an accumulator that did not absorb any bits yet.
+    pub fn new() -> Self {
+        OrAccumulator {
+            current: None,
+            data: PhantomData,
+            protocol: PhantomData,
+        }
+    }
+
+    /// A share of the OR of all absorbed bits, or `None` if no bit was absorbed yet, because the OR of zero bits has
+    /// no share.
+    pub fn current(&self) -> Option<&S> {
+        self.current.as_ref()
+    }
+
+    /// Fold the share of a single bit into the accumulator. Except for the first bit, which is taken as it is, this
+    /// requires one multiplication.
+    ///
+    /// # Parameters
+    /// - `protocol` the protocol multiplying shares
+    /// - `bit` a share of a value that is either `0` or `1`
+    pub fn absorb<'a>(
+        &'a mut self,
+        protocol: &'a mut P,
+        bit: &S,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+        self.absorb_many(protocol, std::slice::from_ref(bit))
+    }
+
+    /// Fold the shares of multiple bits into the accumulator. The bits and the current share are combined pairwise
+    /// in a balanced tree, and all pairs of a level of the tree are multiplied with a single `unbounded_multiply`, so
+    /// `n` bits require `⌈log2(n + 1)⌉` rounds of multiplications instead of `n`.
+    ///
+    /// # Parameters
+    /// - `protocol` the protocol multiplying shares
+    /// - `bits` shares of values that are either `0` or `1`
+    pub fn absorb_many<'a>(
+        &'a mut self,
+        protocol: &'a mut P,
+        bits: &[S],
+    ) -> Pin<Box<dyn Future<Output = ()> + Send + 'a>> {
+        let mut level = self.current.iter().chain(bits).cloned().collect::<Vec<_>>();
+
+        Box::pin(async move {
+            while level.len() > 1 {
+                let pairs = level
+                    .chunks_exact(2)
+                    .map(|pair| (pair[0].clone(), pair[1].clone()))
+                    .collect::<Vec<_>>();
+                let products = P::unbounded_multiply(protocol, &pairs).await;
+
+                let unpaired = level.chunks_exact(2).remainder().first().cloned();
+                level = pairs
+                    .iter()
+                    .zip(products)
+                    .map(|((lhs, rhs), product)| P::sub_shares(&P::add_shares(lhs, rhs), &product))
+                    .chain(unpaired)
+                    .collect();
+            }
+
+            if let Some(or) = level.pop() {
+                self.current = Some(or);
+            }
+        })
+    }
+}
+
+impl<T, S, P> Default for OrAccumulator<T, S, P>
+where
+    P: LinearSharingScheme<T, S> + UnboundedMultiplicationScheme<T, S, P> + Send + Sync,
+    T: Send + Sync,
+    S: Clone + Send + Sync,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
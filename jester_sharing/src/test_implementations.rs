@@ -2,7 +2,7 @@
 
 #![cfg(test)]
 
-use crate::CliqueCommunicationScheme;
+use crate::{CliqueCommunicationScheme, ProtocolConfig};
 use futures::Future;
 use jester_maths::prime::PrimeField;
 use jester_maths::prime_fields;
@@ -17,6 +17,8 @@ pub(crate) struct TestProtocol {
 // define a prime field for testing
 prime_fields!(pub(crate) TestPrimeField("7", 10));
 
+impl ProtocolConfig for TestProtocol {}
+
 /// All shares are considered to be carried out on polynomials where all coefficients are zero. Thus
 /// communication is unnecessary and the secret is always the share. This is obviously stupid to do, but useful for
 /// testing whether protocols calculate their stuff correctly.
@@ -0,0 +1,408 @@
+//! Utilities for reproducible protocol runs. Protocols draw all their randomness through `FieldRng`, so running them
+//! with a seeded `DeterministicProtocolRng` makes every drawn field element reproducible and inspectable. The
+//! communication of a protocol can be counted by wrapping it into a `MeteredProtocol`, which allows tests to pin the
+//! round and bandwidth complexity of schemes.
+
+use crate::beaver_randomization_multiplication::BeaverCommunicationScheme;
+use crate::shamir_secret_sharing::ShamirSecretSharingScheme;
+use crate::{
+    BigUint, CliqueCommunicationScheme, ConditionalSelectionSchemeDelegate,
+    ConditionalSelectionSchemeMarker, Delegate, FieldRng, InversionSchemeDelegate,
+    InversionSchemeMarker, LinearSharingScheme, MultiplicationScheme, MultiplicationSchemeDelegate,
+    MultiplicationSchemeMarker, OrFunctionSchemeDelegate, OrFunctionSchemeMarker, PrimeField,
+    ProtocolConcurrency, ProtocolConfig, RandomBitGenerationSchemeDelegate,
+    RandomBitGenerationSchemeMarker, RandomNumberGenerationScheme,
+    RandomNumberGenerationSchemeDelegate, RandomNumberGenerationSchemeMarker,
+    RawCommunicationScheme, SessionTag, ThresholdSecretSharingScheme,
+    UnboundedConditionalSelectionSchemeDelegate, UnboundedConditionalSelectionSchemeMarker,
+    UnboundedInversionScheme, UnboundedInversionSchemeDelegate, UnboundedInversionSchemeMarker,
+    UnboundedMultiplicationScheme, UnboundedMultiplicationSchemeDelegate,
+    UnboundedMultiplicationSchemeMarker, UnboundedOrFunctionSchemeDelegate,
+    UnboundedOrFunctionSchemeMarker,
+};
+use futures::Future;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::marker::PhantomData;
+use std::pin::Pin;
+
+/// A deterministic random field element source, that records every field element it hands out, together with the
+/// label of the scheme that requested it. Two instances created from the same seed produce identical transcripts, if
+/// they are used by the same sequence of protocol calls.
+///
+/// This generator is meant for tests and debugging only. Its output is fully determined by the seed.
+pub struct DeterministicProtocolRng {
+    rng: StdRng,
+    transcript: Vec<(&'static str, BigUint)>,
+}
+
+impl DeterministicProtocolRng {
+    /// Create a new generator from a 64 bit seed.
+    pub fn seed_from_u64(seed: u64) -> Self {
+        DeterministicProtocolRng {
+            rng: StdRng::seed_from_u64(seed),
+            transcript: vec![],
+        }
+    }
+
+    /// All field elements drawn so far, in order, each with the label of the scheme that requested it.
+    pub fn transcript(&self) -> &[(&'static str, BigUint)] {
+        &self.transcript
+    }
+}
+
+impl FieldRng for DeterministicProtocolRng {
+    fn next_field_element<T: PrimeField>(&mut self, label: &'static str) -> T {
+        let element = T::generate_random_member(&mut self.rng);
+        self.transcript.push((label, element.as_uint()));
+        element
+    }
+}
+
+/// The communication a participant performed through a `MeteredProtocol`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProtocolMetrics {
+    /// The number of shares revealed to all participants
+    pub reveals: usize,
+
+    /// The number of secrets distributed among all participants
+    pub distributions: usize,
+
+    /// The number of field elements this participant sent to other participants. A reveal sends the participant's
+    /// share and a distribution sends one share of the secret to each other participant.
+    pub field_elements_sent: usize,
+
+    /// The number of beaver triples obtained from the triple source
+    pub beaver_triples: usize,
+
+    /// The number of unbounded multiplications, each of which requires one round of communication
+    pub multiplication_rounds: usize,
+}
+
+/// A protocol wrapper that counts all communication of the wrapped protocol `P`. It implements all schemes the
+/// wrapped protocol delegates, by using the same delegates, so the communication of nested schemes is counted as
+/// well. Communication is counted when it is started, regardless of whether its future is ever polled.
+///
+/// The wrapper supports protocols with Shamir shares.
+pub struct MeteredProtocol<P> {
+    protocol: P,
+    participant_count: usize,
+    metrics: ProtocolMetrics,
+}
+
+impl<P> MeteredProtocol<P> {
+    /// Wrap the `protocol` of one of `participant_count` participants. The participant count is required to count
+    /// the field elements sent to the other participants.
+    pub fn new(protocol: P, participant_count: usize) -> Self {
+        MeteredProtocol {
+            protocol,
+            participant_count,
+            metrics: ProtocolMetrics::default(),
+        }
+    }
+
+    /// The communication counted since the protocol was wrapped or the metrics were reset
+    pub fn metrics(&self) -> ProtocolMetrics {
+        self.metrics
+    }
+
+    /// Reset all counters to zero, to count the communication of the next computation only.
+    pub fn reset_metrics(&mut self) {
+        self.metrics = ProtocolMetrics::default();
+    }
+
+    /// The wrapped protocol
+    pub fn protocol(&self) -> &P {
+        &self.protocol
+    }
+
+    /// Unwrap the metered protocol
+    pub fn into_inner(self) -> P {
+        self.protocol
+    }
+}
+
+impl<P, T> ShamirSecretSharingScheme<T> for MeteredProtocol<P>
+where
+    P: ShamirSecretSharingScheme<T>,
+    T: PrimeField,
+{
+}
+
+impl<P> ProtocolConfig for MeteredProtocol<P>
+where
+    P: ProtocolConfig,
+{
+    fn concurrency(&self) -> ProtocolConcurrency {
+        self.protocol.concurrency()
+    }
+}
+
+impl<P, T> CliqueCommunicationScheme<T, (usize, T)> for MeteredProtocol<P>
+where
+    P: CliqueCommunicationScheme<T, (usize, T)> + ShamirSecretSharingScheme<T>,
+    T: PrimeField + Send + 'static,
+{
+    fn reveal_shares(&mut self, share: (usize, T)) -> Pin<Box<dyn Future<Output = T> + Send>> {
+        let tag = self.new_session_tag();
+        self.reveal_shares_tagged(tag, share)
+    }
+
+    fn distribute_secret(
+        &mut self,
+        secret: T,
+    ) -> Pin<Box<dyn Future<Output = Vec<(usize, T)>> + Send>> {
+        let tag = self.new_session_tag();
+        self.distribute_secret_tagged(tag, secret)
+    }
+
+    fn new_session_tag(&mut self) -> SessionTag {
+        self.protocol.new_session_tag()
+    }
+
+    fn reveal_shares_tagged(
+        &mut self,
+        tag: SessionTag,
+        share: (usize, T),
+    ) -> Pin<Box<dyn Future<Output = T> + Send>> {
+        self.metrics.reveals += 1;
+        self.metrics.field_elements_sent += self.participant_count - 1;
+        self.protocol.reveal_shares_tagged(tag, share)
+    }
+
+    fn distribute_secret_tagged(
+        &mut self,
+        tag: SessionTag,
+        secret: T,
+    ) -> Pin<Box<dyn Future<Output = Vec<(usize, T)>> + Send>> {
+        self.metrics.distributions += 1;
+        self.metrics.field_elements_sent += self.participant_count - 1;
+        self.protocol.distribute_secret_tagged(tag, secret)
+    }
+}
+
+impl<P> RawCommunicationScheme for MeteredProtocol<P>
+where
+    P: RawCommunicationScheme,
+{
+    fn broadcast_bytes(
+        &mut self,
+        tag: SessionTag,
+        message: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        self.protocol.broadcast_bytes(tag, message)
+    }
+
+    fn send_bytes(
+        &mut self,
+        tag: SessionTag,
+        participant: usize,
+        message: Vec<u8>,
+    ) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        self.protocol.send_bytes(tag, participant, message)
+    }
+
+    fn collect_bytes(
+        &mut self,
+        tag: SessionTag,
+    ) -> Pin<Box<dyn Future<Output = Vec<(usize, Vec<u8>)>> + Send>> {
+        self.protocol.collect_bytes(tag)
+    }
+}
+
+impl<P, T> BeaverCommunicationScheme<(usize, T)> for MeteredProtocol<P>
+where
+    P: BeaverCommunicationScheme<(usize, T)>,
+{
+    fn get_reconstruction_threshold(&self) -> usize {
+        self.protocol.get_reconstruction_threshold()
+    }
+
+    fn obtain_beaver_triples<'a>(
+        &'a mut self,
+        count: usize,
+    ) -> Pin<Box<dyn Future<Output = Vec<((usize, T), (usize, T), (usize, T))>> + Send + 'a>> {
+        self.metrics.beaver_triples += count;
+        self.protocol.obtain_beaver_triples(count)
+    }
+}
+
+impl<P> RandomNumberGenerationSchemeMarker for MeteredProtocol<P> {
+    type Marker = Delegate;
+}
+
+impl<P, T> RandomNumberGenerationSchemeDelegate<T, (usize, T), MeteredProtocol<P>>
+    for MeteredProtocol<P>
+where
+    P: RandomNumberGenerationSchemeDelegate<T, (usize, T), MeteredProtocol<P>>,
+    Self: ThresholdSecretSharingScheme<T, (usize, T)>
+        + LinearSharingScheme<T, (usize, T)>
+        + CliqueCommunicationScheme<T, (usize, T)>,
+    T: PrimeField + 'static,
+{
+    type Delegate = P::Delegate;
+}
+
+impl<P> RandomBitGenerationSchemeMarker for MeteredProtocol<P> {
+    type Marker = Delegate;
+}
+
+impl<P, T> RandomBitGenerationSchemeDelegate<T, (usize, T), MeteredProtocol<P>>
+    for MeteredProtocol<P>
+where
+    P: RandomBitGenerationSchemeDelegate<T, (usize, T), MeteredProtocol<P>>,
+    T: PrimeField,
+{
+    type Delegate = P::Delegate;
+}
+
+impl<P> MultiplicationSchemeMarker for MeteredProtocol<P> {
+    type Marker = Delegate;
+}
+
+impl<P, T> MultiplicationSchemeDelegate<T, (usize, T), MeteredProtocol<P>> for MeteredProtocol<P>
+where
+    P: MultiplicationSchemeDelegate<T, (usize, T), MeteredProtocol<P>>,
+    Self: Send + Sync,
+    T: Send + Sync,
+{
+    type Delegate = P::Delegate;
+}
+
+impl<P> UnboundedMultiplicationSchemeMarker for MeteredProtocol<P> {
+    type Marker = Delegate;
+}
+
+impl<P, T> UnboundedMultiplicationSchemeDelegate<T, (usize, T), MeteredProtocol<P>>
+    for MeteredProtocol<P>
+where
+    P: UnboundedMultiplicationSchemeDelegate<T, (usize, T), MeteredProtocol<P>>,
+    Self: Send + Sync,
+    T: Send + Sync + 'static,
+{
+    type Delegate = MeteredUnboundedMultiplication<P::Delegate>;
+}
+
+/// An unbounded multiplication that counts each multiplication round of the delegate `D` in the metrics of the
+/// protocol.
+pub struct MeteredUnboundedMultiplication<D>(PhantomData<D>);
+
+impl<D, P, T> UnboundedMultiplicationScheme<T, (usize, T), MeteredProtocol<P>>
+    for MeteredUnboundedMultiplication<D>
+where
+    D: UnboundedMultiplicationScheme<T, (usize, T), MeteredProtocol<P>>,
+    MeteredProtocol<P>: Send + Sync,
+    T: Send + Sync + 'static,
+{
+    fn unbounded_multiply<'a>(
+        protocol: &'a mut MeteredProtocol<P>,
+        pairs: &[((usize, T), (usize, T))],
+    ) -> Pin<Box<dyn Future<Output = Vec<(usize, T)>> + Send + 'a>> {
+        if !pairs.is_empty() {
+            protocol.metrics.multiplication_rounds += 1;
+        }
+        D::unbounded_multiply(protocol, pairs)
+    }
+}
+
+impl<P> InversionSchemeMarker for MeteredProtocol<P> {
+    type Marker = Delegate;
+}
+
+impl<P, T> InversionSchemeDelegate<T, (usize, T), MeteredProtocol<P>> for MeteredProtocol<P>
+where
+    P: InversionSchemeDelegate<T, (usize, T), MeteredProtocol<P>>,
+    Self: ThresholdSecretSharingScheme<T, (usize, T)>
+        + LinearSharingScheme<T, (usize, T)>
+        + Send
+        + Sync,
+    T: PrimeField + Send + Sync + 'static,
+{
+    type Delegate = P::Delegate;
+}
+
+impl<P> UnboundedInversionSchemeMarker for MeteredProtocol<P> {
+    type Marker = Delegate;
+}
+
+impl<P, T> UnboundedInversionSchemeDelegate<T, (usize, T), MeteredProtocol<P>>
+    for MeteredProtocol<P>
+where
+    P: UnboundedInversionSchemeDelegate<T, (usize, T), MeteredProtocol<P>>,
+    Self: ThresholdSecretSharingScheme<T, (usize, T)>
+        + LinearSharingScheme<T, (usize, T)>
+        + Send
+        + Sync,
+    T: PrimeField + Send + Sync + 'static,
+{
+    type Delegate = P::Delegate;
+}
+
+impl<P> ConditionalSelectionSchemeMarker for MeteredProtocol<P> {
+    type Marker = Delegate;
+}
+
+impl<P, T> ConditionalSelectionSchemeDelegate<T, (usize, T), MeteredProtocol<P>>
+    for MeteredProtocol<P>
+where
+    P: ConditionalSelectionSchemeDelegate<T, (usize, T), MeteredProtocol<P>>,
+    Self: MultiplicationScheme<T, (usize, T), Self> + Send + Sync,
+    T: Send + Sync,
+{
+    type Delegate = P::Delegate;
+}
+
+impl<P> UnboundedConditionalSelectionSchemeMarker for MeteredProtocol<P> {
+    type Marker = Delegate;
+}
+
+impl<P, T> UnboundedConditionalSelectionSchemeDelegate<T, (usize, T), MeteredProtocol<P>>
+    for MeteredProtocol<P>
+where
+    P: UnboundedConditionalSelectionSchemeDelegate<T, (usize, T), MeteredProtocol<P>>,
+    Self: UnboundedMultiplicationScheme<T, (usize, T), Self> + Send + Sync,
+    T: Send + Sync,
+{
+    type Delegate = P::Delegate;
+}
+
+impl<P> OrFunctionSchemeMarker for MeteredProtocol<P> {
+    type Marker = Delegate;
+}
+
+impl<P, T> OrFunctionSchemeDelegate<T, (usize, T), MeteredProtocol<P>> for MeteredProtocol<P>
+where
+    P: OrFunctionSchemeDelegate<T, (usize, T), MeteredProtocol<P>>,
+    Self: ThresholdSecretSharingScheme<T, (usize, T)>
+        + LinearSharingScheme<T, (usize, T)>
+        + CliqueCommunicationScheme<T, (usize, T)>
+        + UnboundedMultiplicationScheme<T, (usize, T), Self>
+        + RandomNumberGenerationScheme<T, (usize, T), Self>
+        + UnboundedInversionScheme<T, (usize, T), Self>
+        + Send
+        + Sync,
+    T: PrimeField + Send + Sync + 'static,
+{
+    type Delegate = P::Delegate;
+}
+
+impl<P> UnboundedOrFunctionSchemeMarker for MeteredProtocol<P> {
+    type Marker = Delegate;
+}
+
+impl<P, T> UnboundedOrFunctionSchemeDelegate<T, (usize, T), MeteredProtocol<P>>
+    for MeteredProtocol<P>
+where
+    P: UnboundedOrFunctionSchemeDelegate<T, (usize, T), MeteredProtocol<P>>,
+    Self: ThresholdSecretSharingScheme<T, (usize, T)>
+        + LinearSharingScheme<T, (usize, T)>
+        + CliqueCommunicationScheme<T, (usize, T)>
+        + UnboundedMultiplicationScheme<T, (usize, T), Self>
+        + RandomNumberGenerationScheme<T, (usize, T), Self>
+        + UnboundedInversionScheme<T, (usize, T), Self>
+        + Send
+        + Sync,
+    T: PrimeField + Send + Sync + 'static,
+{
+    type Delegate = P::Delegate;
+}
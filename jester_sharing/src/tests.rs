@@ -3,27 +3,44 @@
 use crate::beaver_randomization_multiplication::BeaverCommunicationScheme;
 use crate::shamir_secret_sharing::ShamirSecretSharingScheme;
 use crate::{
-    BigUint, CliqueCommunicationScheme, Delegate, LinearSharingScheme, PrimeField,
-    RandomNumberGenerationScheme, RandomNumberGenerationSchemeDelegate,
-    RandomNumberGenerationSchemeMarker, ThresholdSecretSharingScheme, UnboundedInversionScheme,
-    UnboundedInversionSchemeDelegate, UnboundedInversionSchemeMarker,
+    BigUint, CliqueCommunicationScheme, ConditionalSelectionScheme,
+    ConditionalSelectionSchemeDelegate, ConditionalSelectionSchemeMarker, Delegate,
+    InversionScheme, InversionSchemeDelegate, InversionSchemeMarker, LinearSharingScheme,
+    MultiplicationScheme, MultiplicationSchemeDelegate, MultiplicationSchemeMarker,
+    OrCoefficientCache, OrFunctionScheme, OrFunctionSchemeDelegate, OrFunctionSchemeMarker,
+    PrimeField, ProtocolConfig, ProtocolException, RandomBitGenerationScheme, ReconstructionException,
+    RandomBitGenerationSchemeDelegate,
+    RandomBitGenerationSchemeMarker, RandomNumberGenerationScheme,
+    RandomNumberGenerationSchemeDelegate, RandomNumberGenerationSchemeMarker,
+    ThresholdSecretSharingScheme, UnboundedConditionalSelectionScheme,
+    UnboundedConditionalSelectionSchemeDelegate, UnboundedConditionalSelectionSchemeMarker,
+    UnboundedInversionScheme, UnboundedInversionSchemeDelegate, UnboundedInversionSchemeMarker,
     UnboundedMultiplicationScheme, UnboundedMultiplicationSchemeDelegate,
     UnboundedMultiplicationSchemeMarker, UnboundedOrFunctionScheme,
     UnboundedOrFunctionSchemeDelegate, UnboundedOrFunctionSchemeMarker,
+    sharing_scheme_by_name, capabilities, Capabilities,
 };
 
 use futures::executor::block_on;
 use num::traits::{One, Zero};
-use rand::thread_rng;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 
 use jester_maths::prime_fields;
+use jester_maths::testing::assert_field_axioms;
 
 use mashup::*;
 use std::iter::repeat;
 use std::pin::Pin;
+use std::sync::Arc;
 
+use crate::observer::{ObservedProtocol, ProtocolEvent, RecordingObserver};
+use crate::testing::{MeteredProtocol, ProtocolMetrics};
+
+use crate::conditional_selection::joint_conditional_selection::JointConditionalSelection;
 use crate::inversion::unbounded_inversion::JointUnboundedInversion;
 use crate::multiplication::beaver_randomization_multiplication::BeaverRerandomizationMultiplication;
+use crate::random_number_generation::root_random_bit_generation::RootRandomBitGeneration;
 use crate::random_number_generation::sum_non_zero_random_number_generation::SumNonZeroRandomNumberGeneration;
 use crate::shared_or_function::joint_unbounded_or::JointUnboundedOrFunction;
 use futures::Future;
@@ -31,6 +48,12 @@ use futures::Future;
 // Define a prime field for testing with p = 7
 prime_fields!(pub(super) TestPrimeField("7", 10));
 
+#[test]
+fn test_field_axioms() {
+    let mut rng = StdRng::seed_from_u64(7);
+    assert_field_axioms::<TestPrimeField, _>(&mut rng, 100);
+}
+
 /// A testing protocol that is carried out between two participants that do not randomize their inputs and do no
 /// communicate as all values are deterministic anyways.
 pub(super) struct TestProtocol {
@@ -93,6 +116,8 @@ where
     type Delegate = JointUnboundedOrFunction<T, S, P>;
 }
 
+impl ProtocolConfig for TestProtocol {}
+
 /// All shares are considered to be carried out on polynomials where all coefficients are zero. Thus
 /// communication is unnecessary and the secret is always the share
 impl CliqueCommunicationScheme<TestPrimeField, (usize, TestPrimeField)> for TestProtocol
@@ -165,6 +190,180 @@ impl UnboundedMultiplicationSchemeMarker for TestProtocol {
     type Marker = Delegate;
 }
 
+impl<T, S, P> MultiplicationSchemeDelegate<T, S, P> for TestProtocol
+where
+    P: ThresholdSecretSharingScheme<T, S>
+        + LinearSharingScheme<T, S>
+        + CliqueCommunicationScheme<T, S>
+        + BeaverCommunicationScheme<S>
+        + Send
+        + Sync,
+    T: PrimeField + Send + Sync,
+    S: Send + Sync + Clone + 'static,
+{
+    type Delegate = BeaverRerandomizationMultiplication<T, S, P>;
+}
+
+impl MultiplicationSchemeMarker for TestProtocol {
+    type Marker = Delegate;
+}
+
+impl<T, S, P> InversionSchemeDelegate<T, S, P> for TestProtocol
+where
+    P: ThresholdSecretSharingScheme<T, S>
+        + LinearSharingScheme<T, S>
+        + CliqueCommunicationScheme<T, S>
+        + UnboundedMultiplicationScheme<T, S, P>
+        + RandomNumberGenerationScheme<T, S, P>
+        + Send
+        + Sync,
+    T: Send + Sync + PrimeField,
+    S: Send + Sync + Clone + 'static,
+{
+    type Delegate = JointUnboundedInversion<T, S, P>;
+}
+
+impl InversionSchemeMarker for TestProtocol {
+    type Marker = Delegate;
+}
+
+impl<T, S, P> ConditionalSelectionSchemeDelegate<T, S, P> for TestProtocol
+where
+    P: ThresholdSecretSharingScheme<T, S>
+        + LinearSharingScheme<T, S>
+        + CliqueCommunicationScheme<T, S>
+        + MultiplicationScheme<T, S, P>
+        + Send
+        + Sync,
+    T: Send + Sync + PrimeField,
+    S: Send + Sync + Clone + 'static,
+{
+    type Delegate = JointConditionalSelection<T, S, P>;
+}
+
+impl ConditionalSelectionSchemeMarker for TestProtocol {
+    type Marker = Delegate;
+}
+
+impl<T, S, P> UnboundedConditionalSelectionSchemeDelegate<T, S, P> for TestProtocol
+where
+    P: ThresholdSecretSharingScheme<T, S>
+        + LinearSharingScheme<T, S>
+        + CliqueCommunicationScheme<T, S>
+        + MultiplicationScheme<T, S, P>
+        + UnboundedMultiplicationScheme<T, S, P>
+        + Send
+        + Sync,
+    T: Send + Sync + PrimeField,
+    S: Send + Sync + Clone + 'static,
+{
+    type Delegate = JointConditionalSelection<T, S, P>;
+}
+
+impl UnboundedConditionalSelectionSchemeMarker for TestProtocol {
+    type Marker = Delegate;
+}
+
+impl<T, S, P> OrFunctionSchemeDelegate<T, S, P> for TestProtocol
+where
+    P: ThresholdSecretSharingScheme<T, S>
+        + LinearSharingScheme<T, S>
+        + CliqueCommunicationScheme<T, S>
+        + UnboundedMultiplicationScheme<T, S, P>
+        + RandomNumberGenerationScheme<T, S, P>
+        + UnboundedInversionScheme<T, S, P>
+        + Send
+        + Sync,
+    T: Send + Sync + PrimeField + 'static,
+    S: Send + Sync + Clone + 'static,
+{
+    type Delegate = JointUnboundedOrFunction<T, S, P>;
+}
+
+impl OrFunctionSchemeMarker for TestProtocol {
+    type Marker = Delegate;
+}
+
+impl<T, S, P> RandomBitGenerationSchemeDelegate<T, S, P> for TestProtocol
+where
+    P: ThresholdSecretSharingScheme<T, S>
+        + LinearSharingScheme<T, S>
+        + CliqueCommunicationScheme<T, S>
+        + MultiplicationScheme<T, S, P>
+        + RandomNumberGenerationScheme<T, S, P>
+        + Send
+        + Sync,
+    T: PrimeField + Sync + Send,
+    S: Sync + Send + 'static,
+{
+    type Delegate = RootRandomBitGeneration<T, S, P>;
+}
+
+impl RandomBitGenerationSchemeMarker for TestProtocol {
+    type Marker = Delegate;
+}
+
+/// Statically assert that a value can be sent between threads
+fn assert_send<T: Send>(_: &T) {}
+
+/// Assert that the futures of all schemes are `Send`, so they can be spawned on multithreaded executors. This test
+/// fails to compile, if any scheme future is not `Send`.
+#[test]
+fn test_scheme_futures_send() {
+    let mut protocol = TestProtocol { participant_id: 1 };
+    let mut rng = StdRng::seed_from_u64(1);
+    let share = (1, TestPrimeField::one());
+    let shares = vec![share.clone(), share.clone()];
+
+    assert_send(&protocol.reveal_shares(share.clone()));
+    assert_send(&protocol.distribute_secret(TestPrimeField::one()));
+    assert_send(&TestProtocol::generate_random_number_sharing(
+        &mut rng,
+        &mut protocol,
+    ));
+    assert_send(&TestProtocol::generate_random_bit(&mut rng, &mut protocol));
+    assert_send(&TestProtocol::multiply(&mut protocol, &share, &share));
+    assert_send(&TestProtocol::unbounded_multiply(
+        &mut protocol,
+        &[(share.clone(), share.clone())],
+    ));
+    assert_send(&TestProtocol::inverse(&mut rng, &mut protocol, &share));
+    assert_send(&TestProtocol::unbounded_inverse(
+        &mut rng,
+        &mut protocol,
+        &shares,
+    ));
+    assert_send(&TestProtocol::unbounded_inverse_then_multiply(
+        &mut rng,
+        &mut protocol,
+        &shares,
+        &shares,
+    ));
+    assert_send(&TestProtocol::joint_conditional_selection(
+        &mut protocol,
+        &share,
+        &share,
+        &share,
+    ));
+    assert_send(&TestProtocol::unbounded_conditional_selection(
+        &mut protocol,
+        &[(share.clone(), share.clone(), share.clone())],
+    ));
+    assert_send(&TestProtocol::shared_or(&mut rng, &mut protocol, &share));
+    assert_send(&TestProtocol::unbounded_shared_or(
+        &mut rng,
+        &mut protocol,
+        &shares,
+    ));
+    let mut cache = OrCoefficientCache::new();
+    assert_send(&TestProtocol::unbounded_shared_or_with_cache(
+        &mut rng,
+        &mut protocol,
+        &shares,
+        &mut cache,
+    ));
+}
+
 #[test]
 fn test_unbounded_or_one() {
     let mut protocol = TestProtocol { participant_id: 1 };
@@ -176,7 +375,10 @@ fn test_unbounded_or_one() {
             (1, TestPrimeField::one()),
         ];
 
-        let or = TestProtocol::unbounded_shared_or(&mut thread_rng(), &mut protocol, &bits).await;
+        let or =
+            TestProtocol::unbounded_shared_or(&mut StdRng::seed_from_u64(2), &mut protocol, &bits)
+                .await
+                .unwrap();
         let revealed = protocol.reveal_shares(or).await;
         assert_eq!(revealed, TestPrimeField::one());
     })
@@ -193,16 +395,164 @@ fn test_unbounded_or_zero() {
             (1, TestPrimeField::zero()),
         ];
 
-        let or = TestProtocol::unbounded_shared_or(&mut thread_rng(), &mut protocol, &bits).await;
+        let or =
+            TestProtocol::unbounded_shared_or(&mut StdRng::seed_from_u64(3), &mut protocol, &bits)
+                .await
+                .unwrap();
         let revealed = protocol.reveal_shares(or).await;
         assert_eq!(revealed, TestPrimeField::zero());
     })
 }
 
+/// The largest OR that can be evaluated in a field with 7 elements has 6 bits, where the sum of all bits plus one
+/// wraps around to zero
+#[test]
+fn test_unbounded_or_field_size() {
+    let mut protocol = TestProtocol { participant_id: 1 };
+
+    block_on(async {
+        let bits = vec![(1, TestPrimeField::one()); 6];
+
+        let or =
+            TestProtocol::unbounded_shared_or(&mut StdRng::seed_from_u64(6), &mut protocol, &bits)
+                .await
+                .unwrap();
+        let revealed = protocol.reveal_shares(or).await;
+        assert_eq!(revealed, TestPrimeField::one());
+    })
+}
+
+#[test]
+fn test_unbounded_or_exceeds_field() {
+    let mut protocol = TestProtocol { participant_id: 1 };
+    let bits = vec![(1, TestPrimeField::zero()); 7];
+
+    let or = block_on(TestProtocol::unbounded_shared_or(
+        &mut StdRng::seed_from_u64(7),
+        &mut protocol,
+        &bits,
+    ));
+    assert_eq!(
+        or,
+        Err(ProtocolException::InvalidInput {
+            reason: "the field must have more elements than the number of bits"
+        })
+    );
+}
+
+#[test]
+fn test_unbounded_or_degenerate_inputs() {
+    let mut protocol = metered_test_protocol();
+    let bit = (1, TestPrimeField::one());
+
+    let or = block_on(MeteredProtocol::unbounded_shared_or(
+        &mut StdRng::seed_from_u64(8),
+        &mut protocol,
+        &[],
+    ));
+    assert_eq!(
+        or,
+        Err(ProtocolException::InvalidInput {
+            reason: "the OR of zero bits has no share"
+        })
+    );
+
+    // the OR of a single bit is the bit itself, so no participant needs to communicate
+    let or = block_on(MeteredProtocol::unbounded_shared_or(
+        &mut StdRng::seed_from_u64(8),
+        &mut protocol,
+        std::slice::from_ref(&bit),
+    ));
+    assert_eq!(or, Ok(bit.clone()));
+    let or = block_on(MeteredProtocol::shared_or(
+        &mut StdRng::seed_from_u64(8),
+        &mut protocol,
+        &bit,
+    ));
+    assert_eq!(or, bit);
+    assert_eq!(protocol.metrics(), ProtocolMetrics::default());
+}
+
+#[test]
+fn test_unbounded_empty_inputs() {
+    let mut protocol = metered_test_protocol();
+
+    assert!(block_on(MeteredProtocol::unbounded_multiply(&mut protocol, &[])).is_empty());
+    assert!(block_on(MeteredProtocol::unbounded_inverse(
+        &mut StdRng::seed_from_u64(9),
+        &mut protocol,
+        &[],
+    ))
+    .is_empty());
+    assert!(block_on(MeteredProtocol::unbounded_inverse_then_multiply(
+        &mut StdRng::seed_from_u64(9),
+        &mut protocol,
+        &[],
+        &[],
+    ))
+    .is_empty());
+    assert!(
+        block_on(MeteredProtocol::unbounded_conditional_selection(&mut protocol, &[])).is_empty()
+    );
+    assert_eq!(protocol.metrics(), ProtocolMetrics::default());
+}
+
+#[test]
+fn test_unbounded_or_cached() {
+    let mut protocol = TestProtocol { participant_id: 1 };
+    let mut cache = OrCoefficientCache::new();
+
+    block_on(async {
+        let bits = vec![
+            (1, TestPrimeField::zero()),
+            (1, TestPrimeField::one()),
+            (1, TestPrimeField::zero()),
+        ];
+
+        let uncached =
+            TestProtocol::unbounded_shared_or(&mut StdRng::seed_from_u64(5), &mut protocol, &bits)
+                .await
+                .unwrap();
+        let cached = TestProtocol::unbounded_shared_or_with_cache(
+            &mut StdRng::seed_from_u64(5),
+            &mut protocol,
+            &bits,
+            &mut cache,
+        )
+        .await
+        .unwrap();
+        assert_eq!(cached, uncached);
+        assert_eq!(cache.computed_entries(), 16);
+
+        // the second evaluation of the same width does not compute any matrix entries
+        let cached_again = TestProtocol::unbounded_shared_or_with_cache(
+            &mut StdRng::seed_from_u64(5),
+            &mut protocol,
+            &bits,
+            &mut cache,
+        )
+        .await
+        .unwrap();
+        assert_eq!(cached_again, uncached);
+        assert_eq!(cache.computed_entries(), 16);
+
+        TestProtocol::unbounded_shared_or_with_cache(
+            &mut StdRng::seed_from_u64(5),
+            &mut protocol,
+            &bits[..2],
+            &mut cache,
+        )
+        .await
+        .unwrap();
+        assert_eq!(cache.computed_entries(), 25);
+        assert!(cache.coefficients(2).is_some());
+    })
+}
+
 #[test]
 fn test_unbounded_inversion() {
     let mut protocol = TestProtocol { participant_id: 1 };
-    let mut rng = thread_rng();
+    let mut rng = StdRng::seed_from_u64(4);
 
     block_on(async {
         let elements: Vec<(usize, TestPrimeField)> = vec![
@@ -222,7 +572,7 @@ fn test_unbounded_inversion() {
 #[test]
 fn test_double_inversion() {
     let mut protocol = TestProtocol { participant_id: 1 };
-    let mut rng = thread_rng();
+    let mut rng = StdRng::seed_from_u64(5);
 
     block_on(async {
         let shares = protocol.distribute_secret(BigUint::from(2u32).into()).await;
@@ -234,3 +584,336 @@ fn test_double_inversion() {
         assert_eq!(revealed, BigUint::from(2u32).into());
     })
 }
+
+/// A linear sharing scheme where each share is the secret itself. It only implements the required methods, so the
+/// default implementations of `LinearSharingScheme` can be tested.
+struct PlainSharingScheme;
+
+impl LinearSharingScheme<TestPrimeField, TestPrimeField> for PlainSharingScheme {
+    fn add_shares(lhs: &TestPrimeField, rhs: &TestPrimeField) -> TestPrimeField {
+        lhs.clone() + rhs.clone()
+    }
+
+    fn sub_shares(lhs: &TestPrimeField, rhs: &TestPrimeField) -> TestPrimeField {
+        lhs.clone() - rhs.clone()
+    }
+
+    fn add_scalar(share: &TestPrimeField, scalar: &TestPrimeField) -> TestPrimeField {
+        share.clone() + scalar.clone()
+    }
+
+    fn sub_scalar(share: &TestPrimeField, scalar: &TestPrimeField) -> TestPrimeField {
+        share.clone() - scalar.clone()
+    }
+
+    fn multiply_scalar(share: &TestPrimeField, scalar: &TestPrimeField) -> TestPrimeField {
+        share.clone() * scalar.clone()
+    }
+
+    fn sum_shares(shares: &[TestPrimeField]) -> Option<TestPrimeField> {
+        if shares.is_empty() {
+            None
+        } else {
+            Some(shares.iter().cloned().sum())
+        }
+    }
+}
+
+#[test]
+fn test_default_linear_combination() {
+    let field = |value: u32| TestPrimeField::from(BigUint::from(value));
+    let shares = vec![field(2), field(3), field(5)];
+    let coefficients = vec![field(3), field(4), field(6)];
+
+    // 3 * 2 + 4 * 3 + 6 * 5 = 48 = 6 mod 7
+    assert_eq!(
+        PlainSharingScheme::linear_combination(&shares, &coefficients),
+        Some(field(6))
+    );
+    assert_eq!(
+        PlainSharingScheme::weighted_sum_with_constant(&shares, &coefficients, &field(2)),
+        Some(field(1))
+    );
+    assert_eq!(PlainSharingScheme::neg_share(&field(3)), field(4));
+    assert_eq!(
+        PlainSharingScheme::linear_combination(&shares[1..], &coefficients),
+        None
+    );
+}
+
+#[test]
+fn test_observed_unbounded_or() {
+    let observer = Arc::new(RecordingObserver::new());
+    let mut protocol = ObservedProtocol::new(TestProtocol { participant_id: 1 }, observer.clone());
+
+    block_on(async {
+        let bits = vec![
+            (1, TestPrimeField::zero()),
+            (1, TestPrimeField::one()),
+            (1, TestPrimeField::zero()),
+        ];
+
+        protocol.set_label("or");
+        let or = ObservedProtocol::unbounded_shared_or(
+            &mut StdRng::seed_from_u64(9),
+            &mut protocol,
+            &bits,
+        )
+        .await
+        .unwrap();
+        protocol.set_label("result");
+        let revealed = protocol.reveal_shares(or).await;
+        assert_eq!(revealed, TestPrimeField::one());
+    });
+
+    // reduce the events to their kinds, as revealed values are randomized
+    let events: Vec<_> = observer
+        .events()
+        .into_iter()
+        .map(|event| match event {
+            ProtocolEvent::Reveal { label, .. } => ("reveal", label),
+            ProtocolEvent::Distribute { label, .. } => ("distribute", label),
+            ProtocolEvent::RoundComplete { scheme_name } => ("round", scheme_name),
+        })
+        .collect();
+
+    let reveals = |count: usize| repeat(("reveal", "or")).take(count);
+    let multiplication_round =
+        |pairs: usize| reveals(2 * pairs).chain(Some(("round", "UnboundedMultiplicationScheme")));
+
+    // three random helpers and three random numbers for their inversion are generated
+    let expected: Vec<_> = repeat(("distribute", "or"))
+        .take(6)
+        // the inversion rerandomizes the helpers and their predecessors with one multiplication round and reveals
+        // the rerandomized helpers, which chains the helpers with their successors' inverses
+        .chain(multiplication_round(6))
+        .chain(reveals(3))
+        // the sum of all bits is multiplied with the cancellation factors and the products are revealed
+        .chain(multiplication_round(3))
+        .chain(reveals(3))
+        .chain(Some(("reveal", "result")))
+        .collect();
+
+    assert_eq!(events, expected);
+}
+
+#[test]
+fn test_observed_unbounded_conditional_selection() {
+    let observer = Arc::new(RecordingObserver::new());
+    let mut protocol = ObservedProtocol::new(TestProtocol { participant_id: 1 }, observer.clone());
+    let share = |value: u32| (1, TestPrimeField::from(BigUint::from(value)));
+
+    let selections = vec![
+        (share(1), share(2), share(3)),
+        (share(0), share(2), share(3)),
+        (share(1), share(5), share(6)),
+        (share(0), share(5), share(6)),
+    ];
+
+    let selected = block_on(ObservedProtocol::unbounded_conditional_selection(
+        &mut protocol,
+        &selections,
+    ));
+    assert_eq!(selected, vec![share(2), share(3), share(5), share(6)]);
+
+    let rounds = observer
+        .events()
+        .into_iter()
+        .filter(|event| matches!(event, ProtocolEvent::RoundComplete { .. }))
+        .count();
+    assert_eq!(rounds, 1);
+}
+
+/// Two participants, as `TestProtocol` distributes secrets among two participants
+fn metered_test_protocol() -> MeteredProtocol<TestProtocol> {
+    MeteredProtocol::new(TestProtocol { participant_id: 1 }, 2)
+}
+
+#[test]
+fn test_metered_unbounded_multiply() {
+    let mut protocol = metered_test_protocol();
+    let share = |value: u32| (1, TestPrimeField::from(BigUint::from(value)));
+    let pairs = vec![
+        (share(2), share(3)),
+        (share(4), share(5)),
+        (share(6), share(6)),
+    ];
+
+    let products = block_on(MeteredProtocol::unbounded_multiply(&mut protocol, &pairs));
+    assert_eq!(products, vec![share(6), share(6), share(1)]);
+
+    // one triple per pair, whose masked factors are revealed
+    assert_eq!(
+        protocol.metrics(),
+        ProtocolMetrics {
+            reveals: 6,
+            distributions: 0,
+            field_elements_sent: 6,
+            beaver_triples: 3,
+            multiplication_rounds: 1,
+        }
+    );
+
+    protocol.reset_metrics();
+    assert_eq!(protocol.metrics(), ProtocolMetrics::default());
+}
+
+#[test]
+fn test_metered_unbounded_inverse() {
+    let mut protocol = metered_test_protocol();
+    let share = |value: u32| (1, TestPrimeField::from(BigUint::from(value)));
+
+    let inverses = block_on(MeteredProtocol::unbounded_inverse(
+        &mut StdRng::seed_from_u64(4),
+        &mut protocol,
+        &[share(2), share(3), share(6)],
+    ));
+    assert_eq!(inverses, vec![share(4), share(5), share(6)]);
+
+    // one random helper and one multiplication per element, whose products are revealed
+    assert_eq!(
+        protocol.metrics(),
+        ProtocolMetrics {
+            reveals: 9,
+            distributions: 3,
+            field_elements_sent: 12,
+            beaver_triples: 3,
+            multiplication_rounds: 1,
+        }
+    );
+}
+
+#[test]
+fn test_metered_unbounded_inverse_then_multiply() {
+    let share = |value: u32| (1, TestPrimeField::from(BigUint::from(value)));
+    let elements = vec![share(2), share(3), share(6)];
+    let partners = vec![share(5), share(1), share(4)];
+
+    let mut fused_protocol = metered_test_protocol();
+    let fused = block_on(MeteredProtocol::unbounded_inverse_then_multiply(
+        &mut StdRng::seed_from_u64(4),
+        &mut fused_protocol,
+        &elements,
+        &partners,
+    ));
+    // 5 * 4 = 6, 1 * 5 = 5 and 4 * 6 = 3 mod 7
+    assert_eq!(fused, vec![share(6), share(5), share(3)]);
+
+    let mut unfused_protocol = metered_test_protocol();
+    let unfused = block_on(async {
+        let inverses = MeteredProtocol::unbounded_inverse(
+            &mut StdRng::seed_from_u64(4),
+            &mut unfused_protocol,
+            &elements,
+        )
+        .await;
+        MeteredProtocol::unbounded_multiply(
+            &mut unfused_protocol,
+            &partners.iter().cloned().zip(inverses).collect::<Vec<_>>(),
+        )
+        .await
+    });
+    assert_eq!(fused, unfused);
+
+    // the partners are multiplied in the same round as the elements, which saves one multiplication round at the
+    // same number of triples and reveals
+    assert_eq!(
+        fused_protocol.metrics(),
+        ProtocolMetrics {
+            multiplication_rounds: 1,
+            ..unfused_protocol.metrics()
+        }
+    );
+    assert_eq!(unfused_protocol.metrics().multiplication_rounds, 2);
+}
+
+#[test]
+fn test_metered_unbounded_or() {
+    let mut protocol = metered_test_protocol();
+    let bits = vec![
+        (1, TestPrimeField::zero()),
+        (1, TestPrimeField::one()),
+        (1, TestPrimeField::zero()),
+    ];
+
+    block_on(MeteredProtocol::unbounded_shared_or(
+        &mut StdRng::seed_from_u64(9),
+        &mut protocol,
+        &bits,
+    ))
+    .unwrap();
+
+    // the fused inversion and chaining of three helpers (6 triples, 15 reveals) and the cancellation of the bit sum
+    // (3 triples, 9 reveals) as in `test_observed_unbounded_or`
+    assert_eq!(
+        protocol.metrics(),
+        ProtocolMetrics {
+            reveals: 24,
+            distributions: 6,
+            field_elements_sent: 30,
+            beaver_triples: 9,
+            multiplication_rounds: 2,
+        }
+    );
+}
+
+/// Share and reconstruct `secret` with a scheme selected at runtime, and check that shares can be added.
+fn assert_dyn_scheme_round_trip(name: &str, count: usize, threshold: usize) {
+    let scheme = sharing_scheme_by_name::<TestPrimeField>(name).unwrap();
+    assert_eq!(scheme.name(), name);
+
+    let mut rng = StdRng::seed_from_u64(17);
+    let field = |value: u32| TestPrimeField::from(BigUint::from(value));
+
+    let shares = scheme.generate_shares(&mut rng, &field(5), count, threshold);
+    assert_eq!(shares.len(), count);
+    assert_eq!(scheme.reconstruct_secret(&shares, threshold), Ok(field(5)));
+    assert_eq!(
+        scheme.reconstruct_secret(&shares[1..threshold], threshold),
+        Err(ReconstructionException::NotEnoughShares {
+            required: threshold,
+            given: threshold - 1,
+        })
+    );
+
+    // 5 + 4 = 2 mod 7, and (2 + 3) * 2 = 3 mod 7
+    let other_shares = scheme.generate_shares(&mut rng, &field(4), count, threshold);
+    let combined = shares
+        .iter()
+        .zip(&other_shares)
+        .map(|(lhs, rhs)| scheme.add_shares(lhs, rhs))
+        .map(|share| scheme.add_scalar(&share, &field(3)))
+        .map(|share| scheme.multiply_scalar(&share, &field(2)))
+        .collect::<Vec<_>>();
+    assert_eq!(scheme.reconstruct_secret(&combined, threshold), Ok(field(3)));
+}
+
+#[test]
+fn test_dyn_sharing_scheme_by_name() {
+    assert_dyn_scheme_round_trip("shamir", 4, 3);
+    assert_dyn_scheme_round_trip("additive", 4, 4);
+    assert!(sharing_scheme_by_name::<TestPrimeField>("replicated").is_none());
+}
+
+#[test]
+fn test_capabilities() {
+    assert!(capabilities().contains(Capabilities::SHAMIR | Capabilities::ADDITIVE));
+    assert_eq!(
+        capabilities().contains(Capabilities::PERSISTENCE),
+        cfg!(feature = "persistence")
+    );
+}
+
+#[test]
+#[cfg(feature = "commitments")]
+fn test_capabilities_commitments() {
+    assert!(capabilities().contains(Capabilities::COMMITMENTS));
+    assert!(capabilities().names().contains(&"commitments"));
+}
+
+#[test]
+#[cfg(not(feature = "commitments"))]
+fn test_capabilities_without_commitments() {
+    assert!(!capabilities().contains(Capabilities::COMMITMENTS));
+    assert!(!capabilities().names().contains(&"commitments"));
+}
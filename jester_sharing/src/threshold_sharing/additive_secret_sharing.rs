@@ -0,0 +1,165 @@
+use std::collections::HashSet;
+
+use crate::{FieldRng, PrimeField};
+
+use crate::{LinearSharingScheme, ReconstructionException, ThresholdSecretSharingScheme};
+
+/// Additive secret sharing, where the shares are random field elements that sum up to the secret. All shares are
+/// required to reconstruct the secret, so the threshold must equal the number of shares. It is the cheapest linear
+/// sharing scheme for protocols where all participants are online.
+pub struct AdditiveSecretSharing;
+
+/// A share of the additive secret sharing scheme. The index identifies the participant holding the share, so public
+/// scalars are only added by the participant with index `1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdditiveShare<T> {
+    pub index: usize,
+    pub value: T,
+}
+
+impl<T> ThresholdSecretSharingScheme<T, AdditiveShare<T>> for AdditiveSecretSharing
+where
+    T: PrimeField,
+{
+    /// Generate `count - 1` random shares and a last share that is the difference of the secret and their sum. The
+    /// shares have the indices `1..=count`.
+    /// # Parameters
+    /// - `rng` a cryptographically secure random number generator.
+    /// - `secret` an instance of `T`
+    /// - `count` how many shares to generate
+    /// - `threshold` how many shares are required to reconstruct the secret. It must be equal to `count`.
+    ///
+    /// # Returns
+    /// Returns a vector of `count` shares
+    ///
+    /// # Panics
+    /// If `count` is zero or `threshold` differs from `count`.
+    fn generate_shares<R>(
+        rng: &mut R,
+        secret: &T,
+        count: usize,
+        threshold: usize,
+    ) -> Vec<AdditiveShare<T>>
+    where
+        R: FieldRng,
+    {
+        assert!(count > 0, "cannot share a secret among zero participants");
+        assert_eq!(
+            threshold, count,
+            "additive sharing requires all shares for reconstruction"
+        );
+
+        let mut shares: Vec<AdditiveShare<T>> = (1..count)
+            .map(|index| AdditiveShare {
+                index,
+                value: rng.next_field_element("AdditiveSecretSharing"),
+            })
+            .collect();
+
+        let sum: T = shares.iter().map(|share| share.value.clone()).sum();
+        shares.push(AdditiveShare {
+            index: count,
+            value: secret.clone() - sum,
+        });
+        shares
+    }
+
+    /// Sum the first `threshold` shares.
+    /// # Parameters
+    /// - `shares` a collection of at least `threshold` shares with pairwise distinct indices
+    /// - `threshold` the number of shares generated
+    ///
+    /// # Returns
+    /// The secret, or a `ReconstructionException` if `threshold` is zero, less than `threshold` shares are given or
+    /// two of them have the same index.
+    fn reconstruct_secret(
+        shares: &[AdditiveShare<T>],
+        threshold: usize,
+    ) -> Result<T, ReconstructionException> {
+        if threshold == 0 {
+            return Err(ReconstructionException::ZeroThreshold {});
+        }
+
+        if shares.len() < threshold {
+            return Err(ReconstructionException::NotEnoughShares {
+                required: threshold,
+                given: shares.len(),
+            });
+        }
+
+        let mut indices = HashSet::new();
+        if let Some(share) = shares[..threshold]
+            .iter()
+            .find(|share| !indices.insert(share.index))
+        {
+            return Err(ReconstructionException::DuplicateShareIndex { index: share.index });
+        }
+
+        Ok(shares[..threshold]
+            .iter()
+            .map(|share| share.value.clone())
+            .sum())
+    }
+}
+
+/// Additive sharing is linear. Public scalars are added to the share with index `1` only, so they are added to the
+/// secret exactly once.
+impl<T> LinearSharingScheme<T, AdditiveShare<T>> for AdditiveSecretSharing
+where
+    T: PrimeField,
+{
+    fn add_shares(lhs: &AdditiveShare<T>, rhs: &AdditiveShare<T>) -> AdditiveShare<T> {
+        assert_eq!(lhs.index, rhs.index);
+        AdditiveShare {
+            index: lhs.index,
+            value: lhs.value.clone() + rhs.value.clone(),
+        }
+    }
+
+    fn sub_shares(lhs: &AdditiveShare<T>, rhs: &AdditiveShare<T>) -> AdditiveShare<T> {
+        assert_eq!(lhs.index, rhs.index);
+        AdditiveShare {
+            index: lhs.index,
+            value: lhs.value.clone() - rhs.value.clone(),
+        }
+    }
+
+    fn add_scalar(share: &AdditiveShare<T>, scalar: &T) -> AdditiveShare<T> {
+        if share.index == 1 {
+            AdditiveShare {
+                index: share.index,
+                value: share.value.clone() + scalar.clone(),
+            }
+        } else {
+            share.clone()
+        }
+    }
+
+    fn sub_scalar(share: &AdditiveShare<T>, scalar: &T) -> AdditiveShare<T> {
+        if share.index == 1 {
+            AdditiveShare {
+                index: share.index,
+                value: share.value.clone() - scalar.clone(),
+            }
+        } else {
+            share.clone()
+        }
+    }
+
+    fn multiply_scalar(share: &AdditiveShare<T>, scalar: &T) -> AdditiveShare<T> {
+        AdditiveShare {
+            index: share.index,
+            value: share.value.clone() * scalar.clone(),
+        }
+    }
+
+    fn sum_shares(shares: &[AdditiveShare<T>]) -> Option<AdditiveShare<T>> {
+        let first = shares.first()?;
+        assert!(shares.iter().all(|share| share.index == first.index));
+
+        Some(AdditiveShare {
+            index: first.index,
+            value: shares.iter().map(|share| share.value.clone()).sum(),
+        })
+    }
+}
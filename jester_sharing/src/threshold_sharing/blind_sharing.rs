@@ -0,0 +1,229 @@
+//! Blinding of Shamir shares for the transport over untrusted relays. Each share is masked by adding a random
+//! blinding to its value. The relay only ever sees blinded shares, while the blindings are delivered out of band, so
+//! neither the relay nor an observer of the out-of-band channel learns anything about the shares, even across
+//! repeated sharings of related secrets.
+
+use crate::shamir_secret_sharing::ShamirSecretSharing;
+use crate::{FieldRng, PrimeField, ReconstructionException, ThresholdSecretSharingScheme};
+
+/// The additive mask of the share with index `index`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Blinding<T> {
+    pub index: usize,
+    pub mask: T,
+}
+
+/// Exceptions that can arise when reconstructing a secret from blinded shares. The blindings do not belong to the
+/// shares, thus the shares cannot be unblinded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlindingException {
+    /// A different number of blindings than shares was given, so a blinding is missing or superfluous
+    BlindingCountMismatch { shares: usize, blindings: usize },
+
+    /// The blinding at the position of a share belongs to a share with another index
+    MismatchedBlinding {
+        share_index: usize,
+        blinding_index: usize,
+    },
+
+    /// The unblinded shares cannot be reconstructed
+    Reconstruction { exception: ReconstructionException },
+}
+
+/// Blinding and unblinding of Shamir shares with additive masks.
+pub struct BlindSharing;
+
+impl BlindSharing {
+    /// Mask the value of `share` by adding `blinding`.
+    pub fn blind_share<T>(share: &(usize, T), blinding: &T) -> (usize, T)
+    where
+        T: PrimeField,
+    {
+        (share.0, share.1.clone() + blinding.clone())
+    }
+
+    /// Remove the mask `blinding` from the value of a share blinded by `blind_share`.
+    pub fn unblind_share<T>(share: &(usize, T), blinding: &T) -> (usize, T)
+    where
+        T: PrimeField,
+    {
+        (share.0, share.1.clone() - blinding.clone())
+    }
+
+    /// Generate Shamir shares of `secret` and blind each share with a fresh random mask.
+    ///
+    /// # Parameters
+    /// - `rng` a cryptographically secure random number generator
+    /// - `secret` the secret to share
+    /// - `count` how many shares to generate
+    /// - `threshold` how many shares are required to reconstruct the secret
+    ///
+    /// # Returns
+    /// The blinded shares, which may be sent through the relay, and the blindings of the shares in the same order,
+    /// which must be delivered out of band.
+    ///
+    /// # Panics
+    /// If Shamir shares cannot be generated with the given `count` and `threshold` (see
+    /// `ThresholdSecretSharingScheme::generate_shares`).
+    pub fn generate_blinded_shares<T, R>(
+        rng: &mut R,
+        secret: &T,
+        count: usize,
+        threshold: usize,
+    ) -> (Vec<(usize, T)>, Vec<Blinding<T>>)
+    where
+        T: PrimeField,
+        R: FieldRng,
+    {
+        ShamirSecretSharing::generate_shares(rng, secret, count, threshold)
+            .into_iter()
+            .map(|share| {
+                let mask: T = rng.next_field_element("BlindSharing");
+                (
+                    Self::blind_share(&share, &mask),
+                    Blinding {
+                        index: share.0,
+                        mask,
+                    },
+                )
+            })
+            .unzip()
+    }
+
+    /// Unblind the `shares` and reconstruct their secret. The blinding of each share must be at the same position as
+    /// the share, so a subset of the shares requires the same subset of the blindings.
+    ///
+    /// # Parameters
+    /// - `shares` blinded shares generated by `generate_blinded_shares`
+    /// - `blindings` the blindings of the shares, in the order of the shares
+    /// - `threshold` the threshold the shares were generated with
+    ///
+    /// # Returns
+    /// The reconstructed secret, or a `BlindingException` if a blinding is missing, does not belong to the share at
+    /// its position, or the unblinded shares cannot be reconstructed.
+    pub fn reconstruct_blinded_secret<T>(
+        shares: &[(usize, T)],
+        blindings: &[Blinding<T>],
+        threshold: usize,
+    ) -> Result<T, BlindingException>
+    where
+        T: PrimeField,
+    {
+        if shares.len() != blindings.len() {
+            return Err(BlindingException::BlindingCountMismatch {
+                shares: shares.len(),
+                blindings: blindings.len(),
+            });
+        }
+
+        let unblinded = shares
+            .iter()
+            .zip(blindings)
+            .map(|(share, blinding)| {
+                if share.0 != blinding.index {
+                    return Err(BlindingException::MismatchedBlinding {
+                        share_index: share.0,
+                        blinding_index: blinding.index,
+                    });
+                }
+
+                Ok(Self::unblind_share(share, &blinding.mask))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        ShamirSecretSharing::reconstruct_secret(&unblinded, threshold)
+            .map_err(|exception| BlindingException::Reconstruction { exception })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jester_maths::prime::Mersenne61;
+    use num::FromPrimitive;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn element(value: usize) -> Mersenne61 {
+        Mersenne61::from_usize(value).unwrap()
+    }
+
+    #[test]
+    fn test_blind_share() {
+        let share = (3, element(10));
+        let blinded = BlindSharing::blind_share(&share, &element(7));
+        assert_eq!(blinded, (3, element(17)));
+        assert_eq!(BlindSharing::unblind_share(&blinded, &element(7)), share);
+    }
+
+    #[test]
+    fn test_blinded_reconstruction() {
+        let mut rng = StdRng::seed_from_u64(16);
+        let (shares, blindings) =
+            BlindSharing::generate_blinded_shares(&mut rng, &element(42), 5, 3);
+        assert_eq!(shares.len(), 5);
+        assert_eq!(
+            blindings
+                .iter()
+                .map(|blinding| blinding.index)
+                .collect::<Vec<_>>(),
+            shares.iter().map(|share| share.0).collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            BlindSharing::reconstruct_blinded_secret(&shares, &blindings, 3),
+            Ok(element(42))
+        );
+        assert_eq!(
+            BlindSharing::reconstruct_blinded_secret(&shares[2..], &blindings[2..], 3),
+            Ok(element(42))
+        );
+
+        // the blinded shares alone are shares of garbage
+        assert_ne!(
+            ShamirSecretSharing::reconstruct_secret(&shares, 3),
+            Ok(element(42))
+        );
+    }
+
+    #[test]
+    fn test_blinded_reconstruction_mismatched_blindings() {
+        let mut rng = StdRng::seed_from_u64(17);
+        let (shares, mut blindings) =
+            BlindSharing::generate_blinded_shares(&mut rng, &element(42), 4, 2);
+
+        assert_eq!(
+            BlindSharing::reconstruct_blinded_secret(&shares, &blindings[..3], 2),
+            Err(BlindingException::BlindingCountMismatch {
+                shares: 4,
+                blindings: 3,
+            })
+        );
+        assert_eq!(
+            BlindSharing::reconstruct_blinded_secret(&shares[..2], &blindings[1..3], 2),
+            Err(BlindingException::MismatchedBlinding {
+                share_index: 1,
+                blinding_index: 2,
+            })
+        );
+
+        blindings.swap(1, 3);
+        assert_eq!(
+            BlindSharing::reconstruct_blinded_secret(&shares, &blindings, 2),
+            Err(BlindingException::MismatchedBlinding {
+                share_index: 2,
+                blinding_index: 4,
+            })
+        );
+
+        assert_eq!(
+            BlindSharing::reconstruct_blinded_secret(&shares[..1], &blindings[..1], 2),
+            Err(BlindingException::Reconstruction {
+                exception: ReconstructionException::NotEnoughShares {
+                    required: 2,
+                    given: 1,
+                },
+            })
+        );
+    }
+}
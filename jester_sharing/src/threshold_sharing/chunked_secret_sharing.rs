@@ -0,0 +1,415 @@
+//! Sharing of byte strings that exceed the capacity of a single field element. The data is split into chunks that
+//! each fit into a field element, and every chunk is shared on its own with a `ThresholdSecretSharingScheme`. A hash
+//! of the data is appended before it is split, so the hash is shared along with the data and a reconstruction from
+//! tampered shares is detected without revealing the hash to any shareholder.
+
+use crate::rounds::{decode_field_elements, encode_field_elements};
+use crate::{FieldRng, PrimeField, ReconstructionException, ThresholdSecretSharingScheme};
+use jester_hashes::hasher::Hasher;
+use jester_hashes::sm3::SM3Hash;
+use jester_hashes::{constant_time_eq, BlockHashFunction, HashValue};
+use std::convert::TryInto;
+use std::io::{self, Write};
+use std::marker::PhantomData;
+
+/// Separates the integrity hashes of this scheme from other hashes of the same data
+const INTEGRITY_DOMAIN: &[u8] = b"jester_sharing chunked secret";
+
+/// A participant's share of a byte string shared with `ChunkedSecretSharing`.
+///
+/// # Type Parameters
+/// - `S` the share type of the underlying sharing scheme
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkedShare<S> {
+    /// The participant's share of every chunk, in the order of the chunks
+    pub chunks: Vec<S>,
+
+    /// The length of the shared data in bytes
+    pub length: usize,
+}
+
+impl<T> ChunkedShare<(usize, T)>
+where
+    T: PrimeField,
+{
+    /// Encode a share of an indexed sharing scheme like `ShamirSecretSharing`, e.g. to hand it to its holder. The
+    /// encoding consists of the index of the chunk shares and the length of the data as little endian `u64`s,
+    /// followed by the values of the chunk shares encoded with `encode_field_elements`.
+    ///
+    /// # Panics
+    /// If the chunk shares have different indices, which never happens for the shares of `share_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let index = self.chunks.first().map_or(0, |(index, _)| *index);
+        assert!(
+            self.chunks
+                .iter()
+                .all(|(chunk_index, _)| *chunk_index == index),
+            "the chunk shares have different indices"
+        );
+
+        let values = self
+            .chunks
+            .iter()
+            .map(|(_, value)| value.clone())
+            .collect::<Vec<_>>();
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(index as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.length as u64).to_le_bytes());
+        bytes.extend(encode_field_elements(&values));
+        bytes
+    }
+
+    /// Decode a share encoded by `to_bytes`.
+    ///
+    /// # Returns
+    /// The share, or `None` if `bytes` is truncated or a chunk share is not a member of the field
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 16 {
+            return None;
+        }
+
+        let index = u64::from_le_bytes(bytes[..8].try_into().unwrap()) as usize;
+        let length = u64::from_le_bytes(bytes[8..16].try_into().unwrap()) as usize;
+        let chunks = decode_field_elements::<T>(&bytes[16..])?
+            .into_iter()
+            .map(|value| (index, value))
+            .collect();
+
+        Some(ChunkedShare { chunks, length })
+    }
+}
+
+/// Exceptions that can arise when reconstructing a byte string from chunked shares. Except for write failures, they
+/// all denote a misuse of the scheme or tampered shares, thus the data cannot be recovered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkedReconstructionException {
+    /// The chunk at index `chunk` could not be reconstructed from its shares
+    Reconstruction {
+        chunk: usize,
+        exception: ReconstructionException,
+    },
+
+    /// Two shares state different lengths of the shared data
+    MismatchedLength { expected: usize, given: usize },
+
+    /// A share does not contain the number of chunks its stated length requires
+    MismatchedChunkCount { expected: usize, given: usize },
+
+    /// The reconstructed data does not match its hash, so at least one share was tampered with
+    IntegrityCheckFailed {},
+
+    /// The reconstructed data could not be written to the output
+    WriteFailed { kind: io::ErrorKind },
+}
+
+/// Sharing of arbitrary byte strings with the threshold secret sharing scheme `P` over the field `T`. The data and
+/// its hash of the hash function `H` are split into chunks of one byte less than the byte length of the field's
+/// prime, so every chunk is a member of the field.
+///
+/// # Type Parameters
+/// - `T` the field the chunks are shared in
+/// - `P` the threshold secret sharing scheme sharing every chunk
+/// - `H` the hash function of the integrity check
+pub struct ChunkedSecretSharing<T, P, H = SM3Hash> {
+    data: PhantomData<T>,
+    scheme: PhantomData<P>,
+    hash: PhantomData<H>,
+}
+
+impl<T, P, H> ChunkedSecretSharing<T, P, H>
+where
+    T: PrimeField,
+    H: BlockHashFunction,
+    H::Context: Default,
+{
+    /// The number of bytes of data every chunk holds
+    ///
+    /// # Panics
+    /// If the field's prime has less than nine bits, so a chunk could not hold a single byte.
+    pub fn chunk_size() -> usize {
+        let chunk_size = T::field_prime().as_uint().bits().div_ceil(8) - 1;
+        assert!(
+            chunk_size > 0,
+            "the field is too small to hold a chunk of data"
+        );
+        chunk_size
+    }
+
+    /// The number of chunks the shares of `length` bytes of data consist of
+    fn chunk_count(length: usize) -> usize {
+        (length + H::output_size(&H::Context::default())).div_ceil(Self::chunk_size())
+    }
+
+    /// A hasher for the integrity hash of `length` bytes of data. The data is yet to be compressed into it.
+    fn integrity_hasher(length: usize) -> Hasher<H> {
+        let mut hasher = Hasher::new(H::Context::default());
+        hasher.update(INTEGRITY_DOMAIN);
+        hasher.update(&(length as u64).to_le_bytes());
+        hasher
+    }
+
+    /// Share `data` among `count` participants, such that `threshold` of them are required to reconstruct it.
+    ///
+    /// # Parameters
+    /// - `rng` a cryptographically secure random number generator.
+    /// - `data` the byte string to share
+    /// - `count` how many shares to generate
+    /// - `threshold` how many shares are required to reconstruct the data
+    ///
+    /// # Returns
+    /// Returns a vector of `count` shares, each holding one share of every chunk
+    pub fn share_bytes<R, S>(
+        rng: &mut R,
+        data: &[u8],
+        count: usize,
+        threshold: usize,
+    ) -> Vec<ChunkedShare<S>>
+    where
+        R: FieldRng,
+        P: ThresholdSecretSharingScheme<T, S>,
+    {
+        let mut hasher = Self::integrity_hasher(data.len());
+        hasher.update(data);
+        let payload = [data, &hasher.finalize().raw()].concat();
+
+        let chunk_count = Self::chunk_count(data.len());
+        let mut shares = (0..count)
+            .map(|_| ChunkedShare {
+                chunks: Vec::with_capacity(chunk_count),
+                length: data.len(),
+            })
+            .collect::<Vec<_>>();
+
+        for chunk in payload.chunks(Self::chunk_size()) {
+            let chunk_shares =
+                P::generate_shares(rng, &T::from_bytes_le(chunk).unwrap(), count, threshold);
+            for (share, chunk_share) in shares.iter_mut().zip(chunk_shares) {
+                share.chunks.push(chunk_share);
+            }
+        }
+
+        shares
+    }
+
+    /// Reconstruct the data of `shares` and write it to `output` chunk by chunk, so only a single chunk of the data
+    /// is held in memory at once. The integrity of the data can only be verified once all of it was reconstructed,
+    /// so data written to `output` must not be used, unless this function succeeds.
+    ///
+    /// # Parameters
+    /// - `shares` a collection of at least `threshold` shares
+    /// - `threshold` the original threshold the shares were generated upon
+    /// - `output` the writer receiving the reconstructed data
+    ///
+    /// # Returns
+    /// `Ok` if the data was reconstructed and its hash verified, or a `ChunkedReconstructionException` otherwise.
+    pub fn reconstruct_to<S, W>(
+        shares: &[ChunkedShare<S>],
+        threshold: usize,
+        output: &mut W,
+    ) -> Result<(), ChunkedReconstructionException>
+    where
+        P: ThresholdSecretSharingScheme<T, S>,
+        S: Clone,
+        W: Write,
+    {
+        let length = match shares.first() {
+            Some(share) => share.length,
+            None => {
+                return Err(ChunkedReconstructionException::Reconstruction {
+                    chunk: 0,
+                    exception: ReconstructionException::NotEnoughShares {
+                        required: threshold,
+                        given: 0,
+                    },
+                })
+            }
+        };
+
+        let chunk_count = Self::chunk_count(length);
+        for share in shares {
+            if share.length != length {
+                return Err(ChunkedReconstructionException::MismatchedLength {
+                    expected: length,
+                    given: share.length,
+                });
+            }
+
+            if share.chunks.len() != chunk_count {
+                return Err(ChunkedReconstructionException::MismatchedChunkCount {
+                    expected: chunk_count,
+                    given: share.chunks.len(),
+                });
+            }
+        }
+
+        let chunk_size = Self::chunk_size();
+        let mut hasher = Self::integrity_hasher(length);
+        let digest_size = H::output_size(&H::Context::default());
+        let mut digest = Vec::with_capacity(digest_size);
+        let mut chunk_shares = Vec::with_capacity(shares.len());
+        for chunk in 0..chunk_count {
+            chunk_shares.clear();
+            chunk_shares.extend(shares.iter().map(|share| share.chunks[chunk].clone()));
+            let value = P::reconstruct_secret(&chunk_shares, threshold).map_err(|exception| {
+                ChunkedReconstructionException::Reconstruction { chunk, exception }
+            })?;
+
+            // a tampered chunk may not fit into the chunk size, which the integrity check alone would not notice
+            let mut bytes = value.as_bytes_le();
+            if bytes.len() > chunk_size {
+                return Err(ChunkedReconstructionException::IntegrityCheckFailed {});
+            }
+            let chunk_length = chunk_size.min(length + digest_size - chunk * chunk_size);
+            bytes.resize(chunk_length, 0);
+
+            // the data ends within this chunk, and the rest of the chunk is part of the hash
+            let data_length = chunk_length.min(length.saturating_sub(chunk * chunk_size));
+            hasher.update(&bytes[..data_length]);
+            digest.extend_from_slice(&bytes[data_length..]);
+            output.write_all(&bytes[..data_length]).map_err(|error| {
+                ChunkedReconstructionException::WriteFailed { kind: error.kind() }
+            })?;
+        }
+
+        if constant_time_eq(&hasher.finalize().raw(), &digest) {
+            Ok(())
+        } else {
+            Err(ChunkedReconstructionException::IntegrityCheckFailed {})
+        }
+    }
+
+    /// Reconstruct the data of `shares`.
+    ///
+    /// # Parameters
+    /// - `shares` a collection of at least `threshold` shares
+    /// - `threshold` the original threshold the shares were generated upon
+    ///
+    /// # Returns
+    /// The reconstructed data, or a `ChunkedReconstructionException` if the shares are inconsistent or the data does
+    /// not match its hash.
+    pub fn reconstruct_bytes<S>(
+        shares: &[ChunkedShare<S>],
+        threshold: usize,
+    ) -> Result<Vec<u8>, ChunkedReconstructionException>
+    where
+        P: ThresholdSecretSharingScheme<T, S>,
+        S: Clone,
+    {
+        let mut data = Vec::with_capacity(shares.first().map_or(0, |share| share.length));
+        Self::reconstruct_to(shares, threshold, &mut data)?;
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shamir_secret_sharing::ShamirSecretSharing;
+    use jester_maths::prime::{Mersenne127, Mersenne61};
+    use num::FromPrimitive;
+    use rand::rngs::StdRng;
+    use rand::{RngCore, SeedableRng};
+
+    type Chunked = ChunkedSecretSharing<Mersenne127, ShamirSecretSharing>;
+
+    #[test]
+    fn test_chunked_round_trip() {
+        let mut rng = StdRng::seed_from_u64(424);
+        let mut data = vec![0_u8; 1 << 20];
+        rng.fill_bytes(&mut data);
+
+        let shares = Chunked::share_bytes(&mut rng, &data, 5, 3);
+        assert_eq!(shares.len(), 5);
+        assert_eq!(Chunked::reconstruct_bytes(&shares[1..4], 3).unwrap(), data);
+    }
+
+    #[test]
+    fn test_chunked_short_data() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for length in 0..40 {
+            let data = (0..length).map(|byte| byte as u8).collect::<Vec<_>>();
+            let shares = ChunkedSecretSharing::<Mersenne61, ShamirSecretSharing>::share_bytes(
+                &mut rng, &data, 3, 2,
+            );
+            assert_eq!(
+                ChunkedSecretSharing::<Mersenne61, ShamirSecretSharing>::reconstruct_bytes(
+                    &shares[..2],
+                    2
+                )
+                .unwrap(),
+                data
+            );
+        }
+    }
+
+    #[test]
+    fn test_chunked_mismatched_chunk_count() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut shares = Chunked::share_bytes(&mut rng, &[7_u8; 100], 5, 3);
+        let expected = shares[0].chunks.len();
+        shares[1].chunks.pop();
+
+        assert_eq!(
+            Chunked::reconstruct_bytes(&shares, 3),
+            Err(ChunkedReconstructionException::MismatchedChunkCount {
+                expected,
+                given: expected - 1
+            })
+        );
+
+        shares[1].length = 50;
+        assert_eq!(
+            Chunked::reconstruct_bytes(&shares, 3),
+            Err(ChunkedReconstructionException::MismatchedLength {
+                expected: 100,
+                given: 50
+            })
+        );
+    }
+
+    #[test]
+    fn test_chunked_tampered_chunk() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut shares = Chunked::share_bytes(&mut rng, b"the secret file key bundle", 5, 3);
+        let (index, value) = shares[2].chunks[0].clone();
+        shares[2].chunks[0] = (index, value + Mersenne127::from_usize(1).unwrap());
+
+        assert_eq!(
+            Chunked::reconstruct_bytes(&shares[..3], 3),
+            Err(ChunkedReconstructionException::IntegrityCheckFailed {})
+        );
+        assert_eq!(
+            Chunked::reconstruct_bytes(
+                &[shares[0].clone(), shares[1].clone(), shares[3].clone()],
+                3
+            )
+            .unwrap(),
+            b"the secret file key bundle".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_share_encoding() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let shares = Chunked::share_bytes(&mut rng, b"correct horse battery staple", 5, 3);
+
+        let decoded = shares
+            .iter()
+            .map(|share| {
+                ChunkedShare::<(usize, Mersenne127)>::from_bytes(&share.to_bytes()).unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(decoded, shares);
+
+        let encoded = shares[0].to_bytes();
+        assert_eq!(&encoded[..8], &1_u64.to_le_bytes());
+        assert_eq!(
+            ChunkedShare::<(usize, Mersenne127)>::from_bytes(&encoded[..15]),
+            None
+        );
+        assert_eq!(
+            ChunkedShare::<(usize, Mersenne127)>::from_bytes(&encoded[..encoded.len() - 1]),
+            None
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! Feldman's verifiable secret sharing. The dealer generates Shamir shares of a secret in the field `T` and commits
+//! to every coefficient `a_k` of the polynomial by publishing `g^(a_k)`, where `g` generates a subgroup of order
+//! `|T|` in the multiplicative group of the prime field `G`. Every participant can verify its share against the
+//! commitments, without learning anything about the secret beyond `g^secret`, which is the first commitment.
+
+use std::marker::PhantomData;
+
+use num::{One, Zero};
+
+use crate::{FieldRng, PrimeField};
+
+/// The subgroup of prime order `q = |T|` of the multiplicative group of the prime field `G` (a Schnorr group),
+/// generated by a fixed generator. Exponents are members of the field `T`.
+///
+/// # Type Parameters
+/// - `G` the prime field the group elements are members of
+/// - `T` the prime field of exponents, whose order is the order of the group
+#[derive(Debug, Clone)]
+pub struct SchnorrGroup<G, T> {
+    generator: G,
+    exponent: PhantomData<T>,
+}
+
+/// Exceptions that can arise when creating a `SchnorrGroup`. The parameters do not describe a group of the order of
+/// the exponent field, so commitments in the group would not bind the exponents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchnorrGroupException {
+    /// The order of the exponent field does not divide the order of the multiplicative group of `G`
+    InvalidOrder {},
+
+    /// The generator is one, or its order is not the order of the exponent field
+    InvalidGenerator {},
+}
+
+impl<G, T> SchnorrGroup<G, T>
+where
+    G: PrimeField,
+    T: PrimeField,
+{
+    /// Create the group generated by `generator`.
+    ///
+    /// # Returns
+    /// The group, or a `SchnorrGroupException` if the order of `T` does not divide `|G| - 1` or `generator` does not
+    /// generate a subgroup of that order.
+    pub fn new(generator: G) -> Result<Self, SchnorrGroupException> {
+        let order = T::field_prime().as_uint();
+        if !((G::field_prime().as_uint() - 1_u32) % &order).is_zero() {
+            return Err(SchnorrGroupException::InvalidOrder {});
+        }
+
+        let group = SchnorrGroup {
+            generator,
+            exponent: PhantomData,
+        };
+        if group.generator.is_zero()
+            || group.generator.is_one()
+            || !group.contains(&group.generator)
+        {
+            return Err(SchnorrGroupException::InvalidGenerator {});
+        }
+
+        Ok(group)
+    }
+
+    /// The generator of the group
+    pub fn generator(&self) -> &G {
+        &self.generator
+    }
+
+    /// The power of the generator with the given `exponent`
+    pub fn exp(&self, exponent: &T) -> G {
+        Self::pow(&self.generator, exponent)
+    }
+
+    /// The power of `element` with the given `exponent`
+    pub fn pow(element: &G, exponent: &T) -> G {
+        G::from(
+            element
+                .as_uint()
+                .modpow(&exponent.as_uint(), &G::field_prime().as_uint()),
+        )
+    }
+
+    /// Whether `element` is a member of the group, i.e. it is nonzero and its order divides the group order
+    pub fn contains(&self, element: &G) -> bool {
+        !element.is_zero()
+            && element
+                .as_uint()
+                .modpow(&T::field_prime().as_uint(), &G::field_prime().as_uint())
+                .is_one()
+    }
+}
+
+/// Feldman's verifiable secret sharing on top of Shamir's secret sharing. The shares can be reconstructed with
+/// `ShamirSecretSharing`.
+pub struct FeldmanVss;
+
+impl FeldmanVss {
+    /// Generate Shamir shares of `secret` at the indices `1..=count` and the commitments to the coefficients of the
+    /// sharing polynomial.
+    ///
+    /// # Parameters
+    /// - `rng` a cryptographically secure random number generator
+    /// - `group` the group the commitments are made in
+    /// - `secret` the secret to share
+    /// - `count` how many shares to generate
+    /// - `threshold` how many shares are required to reconstruct the secret
+    ///
+    /// # Returns
+    /// The shares, and the `threshold` commitments `g^(a_k)` to the coefficients `a_k` of the polynomial, starting
+    /// with the commitment `g^secret` to the constant coefficient.
+    ///
+    /// # Panics
+    /// If `threshold` is zero.
+    pub fn generate_shares<T, G, R>(
+        rng: &mut R,
+        group: &SchnorrGroup<G, T>,
+        secret: &T,
+        count: usize,
+        threshold: usize,
+    ) -> (Vec<(usize, T)>, Vec<G>)
+    where
+        T: PrimeField,
+        G: PrimeField,
+        R: FieldRng,
+    {
+        assert!(threshold > 0, "the threshold must be at least one");
+
+        let mut coefficients = vec![secret.clone()];
+        coefficients.extend((1..threshold).map(|_| rng.next_field_element("FeldmanVss")));
+
+        let shares = (1..=count)
+            .map(|index| {
+                // evaluate the polynomial using Horner's method
+                let x = T::from_usize(index).unwrap();
+                let value = coefficients
+                    .iter()
+                    .rev()
+                    .fold(T::zero(), |acc, coefficient| {
+                        acc * x.clone() + coefficient.clone()
+                    });
+                (index, value)
+            })
+            .collect();
+        let commitments = coefficients
+            .iter()
+            .map(|coefficient| group.exp(coefficient))
+            .collect();
+
+        (shares, commitments)
+    }
+
+    /// Verify that `share` is the evaluation of the polynomial committed to by `commitments` at the share's index,
+    /// i.e. `g^share = prod_k commitments[k]^(index^k)`.
+    pub fn verify_share<T, G>(
+        group: &SchnorrGroup<G, T>,
+        share: &(usize, T),
+        commitments: &[G],
+    ) -> bool
+    where
+        T: PrimeField,
+        G: PrimeField,
+    {
+        let x = match T::from_usize(share.0) {
+            Some(x) if !x.is_zero() => x,
+            _ => return false,
+        };
+
+        // evaluate the polynomial in the exponent using Horner's method
+        let expected = commitments.iter().rev().fold(G::one(), |acc, commitment| {
+            SchnorrGroup::<G, T>::pow(&acc, &x) * commitment.clone()
+        });
+        group.exp(&share.1) == expected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shamir_secret_sharing::ShamirSecretSharing;
+    use crate::ThresholdSecretSharingScheme;
+    use jester_maths::prime::{Mersenne61, Mersenne89};
+    use jester_maths::prime_fields;
+    use mashup::*;
+    use num::{BigUint, FromPrimitive};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    // 2039 = 2 * 1019 + 1 is a safe prime, and 4 generates its subgroup of order 1019
+    prime_fields!(SafePrime("2039", 10), SubgroupOrder("1019", 10));
+
+    fn group() -> SchnorrGroup<SafePrime, SubgroupOrder> {
+        SchnorrGroup::new(SafePrime::from_usize(4).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_schnorr_group_parameters() {
+        assert!(group().contains(&SafePrime::from_usize(16).unwrap()));
+        assert!(!group().contains(&SafePrime::from_usize(2038).unwrap()));
+        assert_eq!(
+            group().exp(&SubgroupOrder::from_usize(1019 + 3).unwrap()),
+            SafePrime::from_usize(64).unwrap()
+        );
+
+        // -1 has order two
+        assert_eq!(
+            SchnorrGroup::<SafePrime, SubgroupOrder>::new(SafePrime::from_usize(2038).unwrap())
+                .unwrap_err(),
+            SchnorrGroupException::InvalidGenerator {}
+        );
+        assert_eq!(
+            SchnorrGroup::<SafePrime, SubgroupOrder>::new(SafePrime::one()).unwrap_err(),
+            SchnorrGroupException::InvalidGenerator {}
+        );
+        assert_eq!(
+            SchnorrGroup::<Mersenne89, Mersenne61>::new(Mersenne89::from(BigUint::from(3_u32)))
+                .unwrap_err(),
+            SchnorrGroupException::InvalidOrder {}
+        );
+    }
+
+    #[test]
+    fn test_verifiable_shares() {
+        let mut rng = StdRng::seed_from_u64(21);
+        let secret = SubgroupOrder::from_usize(421).unwrap();
+        let (shares, commitments) = FeldmanVss::generate_shares(&mut rng, &group(), &secret, 5, 3);
+
+        assert_eq!(commitments.len(), 3);
+        assert_eq!(commitments[0], group().exp(&secret));
+        assert!(shares
+            .iter()
+            .all(|share| FeldmanVss::verify_share(&group(), share, &commitments)));
+        assert_eq!(
+            ShamirSecretSharing::reconstruct_secret(&shares[1..4], 3),
+            Ok(secret)
+        );
+
+        let (index, value) = shares[2].clone();
+        assert!(!FeldmanVss::verify_share(
+            &group(),
+            &(index, value + SubgroupOrder::one()),
+            &commitments
+        ));
+        assert!(!FeldmanVss::verify_share(
+            &group(),
+            &(index + 1, shares[2].1.clone()),
+            &commitments
+        ));
+    }
+}
@@ -1,7 +1,15 @@
 //! This module defines trait for different sharing schemes and provides some default implementations for those.
 
-use crate::{CryptoRng, RngCore};
+use crate::{FieldRng, PrimeField};
 
+pub mod additive_secret_sharing;
+pub mod blind_sharing;
+#[cfg(feature = "commitments")]
+pub mod chunked_secret_sharing;
+pub mod feldman_vss;
+pub mod participant_index_map;
+#[cfg(feature = "dealer")]
+pub mod shamir_dealer;
 pub mod shamir_secret_sharing;
 
 /// A threshold secret sharing scheme that generates n shares of a given secret and requires t <= n of those shares
@@ -24,7 +32,7 @@ pub trait ThresholdSecretSharingScheme<T, S> {
     /// Returns a vector of `count` shares if the `secret`
     fn generate_shares<R>(rng: &mut R, secret: &T, count: usize, threshold: usize) -> Vec<S>
     where
-        R: RngCore + CryptoRng;
+        R: FieldRng;
 
     /// Take a vector of shares and reconstruct the secret from them. At least `threshold` shares must be present,
     /// otherwise the secret cannot be reconstructed
@@ -35,8 +43,41 @@ pub trait ThresholdSecretSharingScheme<T, S> {
     ///
     /// # Returns
     /// Given that `threshold` matches the threshold at generation and enough shares are present, it will return an
-    /// instance of `T` that is the secret reconstructed from the shares
-    fn reconstruct_secret(shares: &[S], threshold: usize) -> T;
+    /// instance of `T` that is the secret reconstructed from the shares. If the shares cannot be used for
+    /// reconstruction, a `ReconstructionException` is returned instead.
+    fn reconstruct_secret(shares: &[S], threshold: usize) -> Result<T, ReconstructionException>;
+}
+
+/// Exceptions that can arise when reconstructing a secret from a set of shares. They all denote a misuse of the
+/// scheme, thus the secret cannot be recovered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReconstructionException {
+    /// The reconstruction threshold was zero, which cannot be a valid threshold of any sharing.
+    ZeroThreshold {},
+
+    /// Less shares than the reconstruction threshold were given.
+    NotEnoughShares { required: usize, given: usize },
+
+    /// Two shares have the same index, so they cannot originate from different participants.
+    DuplicateShareIndex { index: usize },
+
+    /// The indices of the given shares do not match the indices a precomputed interpolation basis was built for.
+    MismatchedShareIndices {},
+
+    /// The index of a share is a multiple of the field's prime, so the share is an evaluation of the polynomial at
+    /// zero. Such shares are never generated, as they are the secret itself.
+    InvalidShareIndex { index: usize },
+}
+
+/// Exceptions that can arise when generating shares at explicitly given points. They denote points that cannot be
+/// the indices of shares, thus no shares are generated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShareGenerationException {
+    /// The point is a multiple of the field's prime, so the share would be the secret itself.
+    InvalidSharePoint { point: usize },
+
+    /// The point is congruent to an earlier point modulo the field's prime, so both shares would be equal.
+    DuplicateSharePoint { point: usize },
 }
 
 /// A trait for sharing schemes whose shares addition is linear thus enabling the addition of shares of this
@@ -60,4 +101,38 @@ pub trait LinearSharingScheme<T, S> {
     /// Sum a slice of shares resulting in a `Some` with a new share of their secrets' sum or `None` if the slice was
     /// empty.
     fn sum_shares(shares: &[S]) -> Option<S>;
+
+    /// Negate a `share` resulting in a new share of its secret's additive inverse.
+    fn neg_share(share: &S) -> S
+    where
+        T: PrimeField,
+    {
+        Self::multiply_scalar(share, &(T::zero() - T::one()))
+    }
+
+    /// Compute a share of the sum of all secrets of `shares`, each multiplied with the public coefficient of the
+    /// same index.
+    ///
+    /// # Returns
+    /// `Some` share of the linear combination or `None` if `shares` is empty or `shares` and `coefficients` differ
+    /// in length.
+    fn linear_combination(shares: &[S], coefficients: &[T]) -> Option<S> {
+        if shares.len() != coefficients.len() {
+            return None;
+        }
+
+        Self::sum_shares(
+            &shares
+                .iter()
+                .zip(coefficients)
+                .map(|(share, coefficient)| Self::multiply_scalar(share, coefficient))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Compute a share of the linear combination of `shares` and `coefficients` (see `linear_combination`) with a
+    /// public `constant` added.
+    fn weighted_sum_with_constant(shares: &[S], coefficients: &[T], constant: &T) -> Option<S> {
+        Self::linear_combination(shares, coefficients).map(|sum| Self::add_scalar(&sum, constant))
+    }
 }
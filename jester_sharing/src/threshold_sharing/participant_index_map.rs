@@ -0,0 +1,331 @@
+//! Stable share indices for participants that are known by an identity rather than by their position in a list.
+//! Shares generated with `generate_shares` are indexed by position, so two runs that enumerate the same participants
+//! in different orders hand different shares to the same party. A `ParticipantIndexMap` assigns every identity a
+//! fixed index independently of the order the identities are given in, and those indices are used as the points of
+//! `ShamirSecretSharingScheme::generate_shares_at`.
+
+use std::convert::TryInto;
+
+/// Exceptions that can arise when assigning indices to participants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParticipantIndexException {
+    /// The same identity was given twice, so both participants would hold the same shares.
+    DuplicateParticipant { id: Vec<u8> },
+
+    /// The range of hash derived indices is smaller than the number of participants, so not every participant can
+    /// be assigned an index.
+    IndexRangeExhausted { range: usize, participants: usize },
+}
+
+/// An assignment of distinct, nonzero share indices to participant identities. Identities are opaque byte strings,
+/// like public keys or names, and the assignment only depends on the set of identities, never on their order.
+///
+/// Indices are compared as field elements by the sharing schemes, so they must be smaller than the prime of the
+/// field the map is used with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParticipantIndexMap {
+    /// The identities and their indices, sorted by identity
+    entries: Vec<(Vec<u8>, usize)>,
+}
+
+impl ParticipantIndexMap {
+    /// Assign every participant its position among the sorted identities, starting at one. The indices are as small
+    /// as possible, but they change for all following identities if a participant joins or leaves.
+    ///
+    /// # Returns
+    /// The map, or a `ParticipantIndexException` if an identity is given twice.
+    pub fn new<I>(ids: I) -> Result<Self, ParticipantIndexException>
+    where
+        I: IntoIterator<Item = Vec<u8>>,
+    {
+        let ids = sorted_ids(ids)?;
+        Ok(ParticipantIndexMap {
+            entries: ids
+                .into_iter()
+                .enumerate()
+                .map(|(position, id)| (id, position + 1))
+                .collect(),
+        })
+    }
+
+    /// Derive the index of every participant from a hash of its identity, so the index of a participant only
+    /// changes if its preferred index collides with another participant. Indices are taken from `1..=range`. The
+    /// identities are processed in sorted order, and an identity whose preferred index is taken gets the next free
+    /// index, wrapping around to one after `range`.
+    ///
+    /// # Returns
+    /// The map, or a `ParticipantIndexException` if an identity is given twice or `range` is smaller than the number
+    /// of participants.
+    pub fn hashed<I>(ids: I, range: usize) -> Result<Self, ParticipantIndexException>
+    where
+        I: IntoIterator<Item = Vec<u8>>,
+    {
+        let ids = sorted_ids(ids)?;
+        if range < ids.len() {
+            return Err(ParticipantIndexException::IndexRangeExhausted {
+                range,
+                participants: ids.len(),
+            });
+        }
+
+        let mut taken = vec![false; range];
+        let entries = ids
+            .into_iter()
+            .map(|id| {
+                let mut slot = (fnv1a(&id) % range as u64) as usize;
+                while taken[slot] {
+                    slot = (slot + 1) % range;
+                }
+                taken[slot] = true;
+                (id, slot + 1)
+            })
+            .collect();
+
+        Ok(ParticipantIndexMap { entries })
+    }
+
+    /// The share index of the participant with identity `id`, or `None` if it is not part of the map.
+    pub fn index_of(&self, id: &[u8]) -> Option<usize> {
+        self.entries
+            .binary_search_by(|(other, _)| other.as_slice().cmp(id))
+            .ok()
+            .map(|position| self.entries[position].1)
+    }
+
+    /// All identities of the map in sorted order.
+    pub fn ids(&self) -> impl Iterator<Item = &[u8]> {
+        self.entries.iter().map(|(id, _)| id.as_slice())
+    }
+
+    /// The share indices of all participants, in the sorted order of their identities. These are the points to
+    /// generate shares at with `generate_shares_at`.
+    pub fn indices(&self) -> Vec<usize> {
+        self.entries.iter().map(|(_, index)| *index).collect()
+    }
+
+    /// Encode the map, e.g. to store it along with long-lived shares. The encoding consists of the number of
+    /// participants as a little endian `u64`, followed by the index, the length of the identity as little endian
+    /// `u64`s and the identity of every participant.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(self.entries.len() as u64).to_le_bytes());
+        for (id, index) in &self.entries {
+            bytes.extend_from_slice(&(*index as u64).to_le_bytes());
+            bytes.extend_from_slice(&(id.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(id);
+        }
+        bytes
+    }
+
+    /// Decode a map encoded by `to_bytes`.
+    ///
+    /// # Returns
+    /// The map, or `None` if `bytes` is truncated, has trailing data, or does not describe a valid assignment, i.e.
+    /// identities are unsorted or duplicated, or indices are zero or duplicated.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut remaining = bytes;
+        let count = read_u64(&mut remaining)?;
+        let mut entries: Vec<(Vec<u8>, usize)> = Vec::new();
+        for _ in 0..count {
+            let index = read_u64(&mut remaining)?;
+            let length = read_u64(&mut remaining)?;
+            if remaining.len() < length {
+                return None;
+            }
+            let (id, rest) = remaining.split_at(length);
+            remaining = rest;
+
+            if index == 0
+                || entries
+                    .last()
+                    .is_some_and(|(previous, _)| previous.as_slice() >= id)
+                || entries.iter().any(|(_, other)| *other == index)
+            {
+                return None;
+            }
+            entries.push((id.to_vec(), index));
+        }
+
+        if remaining.is_empty() {
+            Some(ParticipantIndexMap { entries })
+        } else {
+            None
+        }
+    }
+}
+
+/// Read a little endian `u64` from the front of `remaining` and advance it
+fn read_u64(remaining: &mut &[u8]) -> Option<usize> {
+    if remaining.len() < 8 {
+        return None;
+    }
+    let (value, rest) = remaining.split_at(8);
+    *remaining = rest;
+    Some(u64::from_le_bytes(value.try_into().unwrap()) as usize)
+}
+
+/// Sort the identities and reject duplicates
+fn sorted_ids<I>(ids: I) -> Result<Vec<Vec<u8>>, ParticipantIndexException>
+where
+    I: IntoIterator<Item = Vec<u8>>,
+{
+    let mut ids = ids.into_iter().collect::<Vec<_>>();
+    ids.sort();
+    if let Some(pair) = ids.windows(2).find(|pair| pair[0] == pair[1]) {
+        return Err(ParticipantIndexException::DuplicateParticipant {
+            id: pair[0].clone(),
+        });
+    }
+    Ok(ids)
+}
+
+/// The 64 bit FNV-1a hash of `data`. Hash derived indices must be identical on every platform and in every release,
+/// so a fixed function is used instead of the standard library's hasher.
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shamir_secret_sharing::{ShamirSecretSharing, ShamirSecretSharingScheme};
+    use crate::ThresholdSecretSharingScheme;
+    use jester_maths::prime::Mersenne61;
+    use num::FromPrimitive;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn ids(names: &[&str]) -> Vec<Vec<u8>> {
+        names.iter().map(|name| name.as_bytes().to_vec()).collect()
+    }
+
+    #[test]
+    fn test_permuted_participants() {
+        let names = ["carol", "alice", "dave", "bob"];
+        let permuted = ["bob", "dave", "alice", "carol"];
+
+        let map = ParticipantIndexMap::new(ids(&names)).unwrap();
+        assert_eq!(map, ParticipantIndexMap::new(ids(&permuted)).unwrap());
+        assert_eq!(
+            map.ids().collect::<Vec<_>>(),
+            vec![&b"alice"[..], b"bob", b"carol", b"dave"]
+        );
+        assert_eq!(map.index_of(b"carol"), Some(3));
+        assert_eq!(map.index_of(b"eve"), None);
+
+        let hashed = ParticipantIndexMap::hashed(ids(&names), 1000).unwrap();
+        assert_eq!(
+            hashed,
+            ParticipantIndexMap::hashed(ids(&permuted), 1000).unwrap()
+        );
+        assert!(hashed
+            .indices()
+            .iter()
+            .all(|index| (1..=1000).contains(index)));
+    }
+
+    #[test]
+    fn test_hashed_collisions() {
+        // alice and dave both prefer the last of the four indices. alice is sorted first, so dave wraps around to the
+        // first index.
+        let names = ["dave", "carol", "alice"];
+        let map = ParticipantIndexMap::hashed(ids(&names), 4).unwrap();
+        assert_eq!(map.indices(), vec![4, 3, 1]);
+
+        for permutation in &[["alice", "carol", "dave"], ["carol", "alice", "dave"]] {
+            assert_eq!(
+                ParticipantIndexMap::hashed(ids(permutation), 4).unwrap(),
+                map
+            );
+        }
+
+        // without the collision, dave keeps the preferred index
+        let without_alice = ParticipantIndexMap::hashed(ids(&["dave", "carol"]), 4).unwrap();
+        assert_eq!(without_alice.index_of(b"dave"), Some(4));
+    }
+
+    #[test]
+    fn test_invalid_participants() {
+        assert_eq!(
+            ParticipantIndexMap::new(ids(&["alice", "bob", "alice"])),
+            Err(ParticipantIndexException::DuplicateParticipant {
+                id: b"alice".to_vec()
+            })
+        );
+        assert_eq!(
+            ParticipantIndexMap::hashed(ids(&["alice", "bob", "carol"]), 2),
+            Err(ParticipantIndexException::IndexRangeExhausted {
+                range: 2,
+                participants: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_serialization() {
+        let map = ParticipantIndexMap::hashed(ids(&["alice", "bob", "", "carol"]), 50).unwrap();
+        let bytes = map.to_bytes();
+        assert_eq!(ParticipantIndexMap::from_bytes(&bytes), Some(map));
+
+        assert_eq!(
+            ParticipantIndexMap::from_bytes(&bytes[..bytes.len() - 1]),
+            None
+        );
+        assert_eq!(
+            ParticipantIndexMap::from_bytes(&[bytes.as_slice(), &[0]].concat()),
+            None
+        );
+
+        // two participants with the same index, and unsorted identities
+        let entry =
+            |index: u64, id: u8| [&index.to_le_bytes()[..], &1u64.to_le_bytes(), &[id]].concat();
+        let valid = [2u64.to_le_bytes().to_vec(), entry(1, b'a'), entry(2, b'b')].concat();
+        assert!(ParticipantIndexMap::from_bytes(&valid).is_some());
+        let duplicate = [2u64.to_le_bytes().to_vec(), entry(1, b'a'), entry(1, b'b')].concat();
+        assert_eq!(ParticipantIndexMap::from_bytes(&duplicate), None);
+        let unsorted = [2u64.to_le_bytes().to_vec(), entry(1, b'b'), entry(2, b'a')].concat();
+        assert_eq!(ParticipantIndexMap::from_bytes(&unsorted), None);
+    }
+
+    #[test]
+    fn test_reconstruction_by_identity() {
+        let names = ["carol", "alice", "dave", "bob", "erin"];
+        let map = ParticipantIndexMap::hashed(ids(&names), 1 << 20).unwrap();
+        let secret = Mersenne61::from_u64(4242).unwrap();
+
+        let shares = ShamirSecretSharing::generate_shares_at(
+            &mut StdRng::seed_from_u64(5),
+            &secret,
+            &map.indices(),
+            3,
+        )
+        .unwrap();
+
+        // shares are handed out and stored by identity only
+        let labeled = map
+            .ids()
+            .map(|id| id.to_vec())
+            .zip(shares.into_iter().map(|(_, value)| value))
+            .collect::<Vec<_>>();
+
+        // a later run enumerates the participants differently, but finds the same indices
+        let later_map =
+            ParticipantIndexMap::hashed(ids(&["erin", "bob", "alice", "dave", "carol"]), 1 << 20)
+                .unwrap();
+        let collected = labeled
+            .iter()
+            .rev()
+            .filter(|(id, _)| id.as_slice() != b"alice" && id.as_slice() != b"dave")
+            .map(|(id, value)| (later_map.index_of(id).unwrap(), value.clone()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            <ShamirSecretSharing as ThresholdSecretSharingScheme<Mersenne61, _>>::reconstruct_secret(
+                &collected, 3
+            ),
+            Ok(secret)
+        );
+    }
+}
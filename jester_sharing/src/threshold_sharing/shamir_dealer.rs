@@ -0,0 +1,392 @@
+//! A dealer of Shamir sharings for environments where random bytes are scarce, like embedded devices that share many
+//! secrets. `ShamirSecretSharingScheme::generate_shares` draws `threshold - 1` random field elements per secret from
+//! the random number generator. A `ShamirDealer` instead draws its entropy once, as the seed material it is created
+//! with, and derives the coefficients of every polynomial from it with an HKDF-like construction over HMAC-SM3: the
+//! seed material is extracted into a dealer secret, and the coefficients of a sharing are expanded from the dealer
+//! secret and a per-secret nonce.
+//!
+//! # Security
+//! The dealer secret determines every polynomial the dealer ever creates. Anyone who learns it can compute the
+//! secrets of all sharings from a single share each, so the dealer is only suited for sealed-dealer scenarios, where
+//! the dealer secret never leaves the device. Likewise, a nonce must never be used for two different secrets, since
+//! the shares of two secrets on the same polynomial reveal the difference of the secrets.
+
+use jester_hashes::hmac::hmac_array;
+use jester_hashes::sm3::SM3Hash;
+use jester_hashes::HashFunction;
+use jester_util::Redacted;
+use num::ToPrimitive;
+
+use crate::{BigUint, PrimeField, ShareGenerationException};
+
+/// The length of the dealer secret and of every expanded block in bytes, which is the output size of SM3
+const BLOCK_LENGTH: usize = SM3Hash::MAX_OUTPUT_SIZE;
+
+/// The salt of the extraction of the dealer secret from the seed material
+const EXTRACT_SALT: &[u8] = b"jester_sharing shamir dealer";
+
+/// A dealer that shares secrets with Shamir's secret sharing scheme, deriving the coefficients of each polynomial
+/// deterministically from its dealer secret and the nonce of the sharing. The nonce starts at zero and is incremented
+/// by every sharing. Secrets dealt by a `ShamirDealer` are reconstructed like any other Shamir sharing, e.g. with
+/// `ShamirSecretSharing`.
+///
+/// The dealer keeps the buffers for the coefficients and for the random bytes between sharings, so dealing allocates
+/// no memory besides the output, once the buffers have grown to their size in the first sharing. The field arithmetic
+/// of `T` may allocate on its own, like any arithmetic on `BigUint`.
+///
+/// # Type Parameters
+/// - `T` the prime field of the secrets
+pub struct ShamirDealer<T>
+where
+    T: PrimeField,
+{
+    secret: Redacted<[u8; BLOCK_LENGTH]>,
+    threshold: usize,
+    nonce: u64,
+
+    /// The prime of `T`, which bounds the sampled coefficients
+    prime: BigUint,
+
+    /// The prime of `T`, if it fits into a `usize`. Share indices are only reduced if it does.
+    small_prime: Option<usize>,
+
+    /// The mask of the most significant byte of a sampled coefficient, which cuts it to the bit length of the prime
+    top_byte_mask: u8,
+
+    /// Buffer of the big endian bytes of a sampled coefficient
+    bytes: Vec<u8>,
+
+    /// Buffer of the coefficients of the monomials `x^1` to `x^(threshold - 1)` of the current polynomial
+    coefficients: Vec<T>,
+}
+
+impl<T> ShamirDealer<T>
+where
+    T: PrimeField,
+{
+    /// Create a dealer whose dealer secret is extracted from `seed_material`.
+    ///
+    /// # Parameters
+    /// - `seed_material` secret random bytes. They need not be uniformly distributed, but must contain enough
+    ///   entropy, e.g. 32 bytes of a hardware random number generator.
+    /// - `threshold` how many shares are required to reconstruct a secret
+    ///
+    /// # Panics
+    /// If `threshold` is less than two.
+    pub fn new(seed_material: &[u8], threshold: usize) -> Self {
+        assert!(threshold > 1);
+
+        let prime = T::field_prime().as_uint();
+        let bits = prime.bits();
+        let length = bits.div_ceil(8);
+
+        ShamirDealer {
+            secret: Redacted::new(hmac_array::<SM3Hash, BLOCK_LENGTH>(
+                &(),
+                EXTRACT_SALT,
+                seed_material,
+            )),
+            threshold,
+            nonce: 0,
+            small_prime: prime.to_usize(),
+            prime,
+            top_byte_mask: 0xFF_u8 >> (length * 8 - bits),
+            bytes: vec![0; length],
+            coefficients: Vec::with_capacity(threshold - 1),
+        }
+    }
+
+    /// How many shares are required to reconstruct a secret
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// The nonce of the next sharing
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    /// Set the nonce of the next sharing, e.g. to resume dealing with a dealer secret after a restart. The nonce must
+    /// not have been used for a different secret before.
+    pub fn set_nonce(&mut self, nonce: u64) {
+        self.nonce = nonce;
+    }
+
+    /// Share `secret` with the shares at the indices `1..=count`.
+    ///
+    /// # Returns
+    /// The `count` shares in the order of their indices
+    ///
+    /// # Panics
+    /// If `count` is not less than the prime of `T`, since two shares would then have the same index.
+    pub fn deal(&mut self, secret: &T, count: usize) -> Vec<(usize, T)> {
+        assert!(
+            self.small_prime.is_none_or(|prime| count < prime),
+            "more shares than elements of the field"
+        );
+
+        let mut shares = Vec::with_capacity(count);
+        self.deal_at(secret, 1..=count, &mut shares);
+        shares
+    }
+
+    /// Share `secret` with the shares at the given `indices` and append them to `out`, so a caller that deals many
+    /// secrets can reuse its output buffer as well.
+    ///
+    /// # Parameters
+    /// - `secret` the secret to share
+    /// - `indices` the indices of the shares. They are compared as field elements, so they must be pairwise distinct
+    ///   and nonzero modulo the prime of `T`.
+    /// - `out` the vector the shares are appended to, in the order of `indices`
+    ///
+    /// # Returns
+    /// A `ShareGenerationException` if an index is zero or used twice. Then neither `out` nor the nonce is changed.
+    pub fn deal_into(
+        &mut self,
+        secret: &T,
+        indices: &[usize],
+        out: &mut Vec<(usize, T)>,
+    ) -> Result<(), ShareGenerationException> {
+        let small_prime = self.small_prime;
+        let reduce = |index: usize| small_prime.map_or(index, |prime| index % prime);
+
+        // compare the indices pairwise, so no set of them must be allocated
+        for (position, index) in indices.iter().enumerate() {
+            if reduce(*index) == 0 {
+                return Err(ShareGenerationException::InvalidSharePoint { point: *index });
+            }
+
+            if indices[..position]
+                .iter()
+                .any(|earlier| reduce(*earlier) == reduce(*index))
+            {
+                return Err(ShareGenerationException::DuplicateSharePoint { point: *index });
+            }
+        }
+
+        out.reserve(indices.len());
+        self.deal_at(secret, indices.iter().copied(), out);
+        Ok(())
+    }
+
+    /// Derive the polynomial of the current nonce, append its evaluations at `indices` to `out` and advance the
+    /// nonce. The indices must be valid share points.
+    fn deal_at<I>(&mut self, secret: &T, indices: I, out: &mut Vec<(usize, T)>)
+    where
+        I: Iterator<Item = usize>,
+    {
+        self.derive_coefficients();
+
+        out.extend(indices.map(|index| {
+            let x = T::from(BigUint::from(index));
+
+            // evaluate the polynomial using Horner's method, so no powers of `x` must be computed
+            let y = self
+                .coefficients
+                .iter()
+                .rev()
+                .fold(T::zero(), |acc, coefficient| {
+                    (acc + coefficient.clone()) * x.clone()
+                })
+                + secret.clone();
+            (index, y)
+        }));
+
+        self.nonce = self.nonce.wrapping_add(1);
+    }
+
+    /// Replace the coefficients in the buffer with those of the current nonce. Each coefficient is sampled by
+    /// rejection like `PrimeField::generate_random_member`, from a stream of blocks that is expanded from the dealer
+    /// secret, the nonce, the index of the coefficient and a block counter.
+    fn derive_coefficients(&mut self) {
+        self.coefficients.clear();
+
+        for coefficient in 1..self.threshold {
+            let mut stream = ExpandStream {
+                secret: &self.secret,
+                info: [0; 16],
+                block: [0; BLOCK_LENGTH],
+                position: BLOCK_LENGTH,
+            };
+            stream.info[..8].copy_from_slice(&self.nonce.to_be_bytes());
+            stream.info[8..12].copy_from_slice(&(coefficient as u32).to_be_bytes());
+
+            loop {
+                stream.fill(&mut self.bytes);
+                self.bytes[0] &= self.top_byte_mask;
+
+                let candidate = BigUint::from_bytes_be(&self.bytes);
+                if candidate < self.prime {
+                    self.coefficients.push(T::from(candidate));
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// The blocks `HMAC(secret, nonce || coefficient || counter)` for the counters `0, 1, ...` as a stream of bytes
+struct ExpandStream<'a> {
+    secret: &'a [u8; BLOCK_LENGTH],
+
+    /// The nonce and the index of the coefficient, followed by the counter of the next block, all big endian
+    info: [u8; 16],
+
+    /// The current block
+    block: [u8; BLOCK_LENGTH],
+
+    /// The position of the next unused byte of `block`
+    position: usize,
+}
+
+impl ExpandStream<'_> {
+    /// Fill `output` with the next bytes of the stream.
+    fn fill(&mut self, output: &mut [u8]) {
+        for byte in output {
+            if self.position == BLOCK_LENGTH {
+                self.block = hmac_array::<SM3Hash, BLOCK_LENGTH>(&(), self.secret, &self.info);
+
+                let counter = u32::from_be_bytes([
+                    self.info[12],
+                    self.info[13],
+                    self.info[14],
+                    self.info[15],
+                ]);
+                self.info[12..].copy_from_slice(&(counter + 1).to_be_bytes());
+                self.position = 0;
+            }
+
+            *byte = self.block[self.position];
+            self.position += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jester_maths::prime::{IetfGroup3, Mersenne89};
+    use num::FromPrimitive;
+
+    use super::*;
+    use crate::shamir_secret_sharing::ShamirSecretSharing;
+    use crate::test_implementations::TestPrimeField;
+    use crate::ThresholdSecretSharingScheme;
+
+    const SEED: &[u8] = b"seed material of a sealed dealer";
+
+    #[test]
+    fn test_deterministic() {
+        let secret = Mersenne89::from_u64(4711).unwrap();
+        let mut dealer = ShamirDealer::<Mersenne89>::new(SEED, 3);
+        let mut other_dealer = ShamirDealer::<Mersenne89>::new(SEED, 3);
+
+        let shares = dealer.deal(&secret, 5);
+        assert_eq!(other_dealer.deal(&secret, 5), shares);
+        assert_eq!(dealer.nonce(), 1);
+
+        dealer.set_nonce(0);
+        assert_eq!(dealer.deal(&secret, 5), shares);
+
+        // the shares at explicit indices lie on the same polynomial
+        let mut out = vec![];
+        dealer.set_nonce(0);
+        dealer.deal_into(&secret, &[4, 2], &mut out).unwrap();
+        assert_eq!(out, vec![shares[3].clone(), shares[1].clone()]);
+    }
+
+    #[test]
+    fn test_independent_nonces() {
+        let secret = Mersenne89::from_u64(4711).unwrap();
+        let mut dealer = ShamirDealer::<Mersenne89>::new(SEED, 3);
+
+        let first = dealer.deal(&secret, 5);
+        let second = dealer.deal(&secret, 5);
+        for (lhs, rhs) in first.iter().zip(&second) {
+            assert_eq!(lhs.0, rhs.0);
+            assert_ne!(lhs.1, rhs.1);
+        }
+
+        let mut other_dealer = ShamirDealer::<Mersenne89>::new(b"other seed material", 3);
+        assert_ne!(other_dealer.deal(&secret, 5), first);
+    }
+
+    #[test]
+    fn test_reconstruction() {
+        let mut dealer = ShamirDealer::<Mersenne89>::new(SEED, 4);
+        for value in 0..10 {
+            let secret = Mersenne89::from_u64(value * 1_000_003).unwrap();
+            let shares = dealer.deal(&secret, 7);
+            assert_eq!(
+                ShamirSecretSharing::reconstruct_secret(&shares, 4),
+                Ok(secret.clone())
+            );
+            assert_eq!(
+                ShamirSecretSharing::reconstruct_secret(&shares[3..], 4),
+                Ok(secret)
+            );
+        }
+
+        // the coefficients of a 2048 bit prime span several expanded blocks
+        let secret = IetfGroup3::from_u64(42).unwrap();
+        let shares = ShamirDealer::<IetfGroup3>::new(SEED, 3).deal(&secret, 3);
+        assert_eq!(
+            ShamirSecretSharing::reconstruct_secret(&shares, 3),
+            Ok(secret)
+        );
+
+        let secret = TestPrimeField::from_u64(5).unwrap();
+        let shares = ShamirDealer::<TestPrimeField>::new(SEED, 2).deal(&secret, 6);
+        assert_eq!(
+            ShamirSecretSharing::reconstruct_secret(&shares[4..], 2),
+            Ok(secret)
+        );
+    }
+
+    #[test]
+    fn test_buffers_reused() {
+        let secret = Mersenne89::from_u64(4711).unwrap();
+        let mut dealer = ShamirDealer::<Mersenne89>::new(SEED, 5);
+        let mut out = Vec::with_capacity(12);
+
+        dealer.deal_into(&secret, &[1, 2, 3], &mut out).unwrap();
+        let coefficients = (dealer.coefficients.as_ptr(), dealer.coefficients.capacity());
+        let bytes = (dealer.bytes.as_ptr(), dealer.bytes.capacity());
+        let output = out.as_ptr();
+
+        for _ in 0..3 {
+            dealer.deal_into(&secret, &[4, 5, 6], &mut out).unwrap();
+            assert_eq!(
+                (dealer.coefficients.as_ptr(), dealer.coefficients.capacity()),
+                coefficients
+            );
+            assert_eq!((dealer.bytes.as_ptr(), dealer.bytes.capacity()), bytes);
+        }
+        assert_eq!(out.as_ptr(), output);
+        assert_eq!(out.len(), 12);
+        assert_eq!(dealer.coefficients.len(), 4);
+    }
+
+    #[test]
+    fn test_invalid_indices() {
+        let secret = TestPrimeField::from_u64(5).unwrap();
+        let mut dealer = ShamirDealer::<TestPrimeField>::new(SEED, 2);
+        let mut out = vec![];
+
+        assert_eq!(
+            dealer.deal_into(&secret, &[1, 7], &mut out),
+            Err(ShareGenerationException::InvalidSharePoint { point: 7 })
+        );
+        assert_eq!(
+            dealer.deal_into(&secret, &[1, 2, 8], &mut out),
+            Err(ShareGenerationException::DuplicateSharePoint { point: 8 })
+        );
+        assert!(out.is_empty());
+        assert_eq!(dealer.nonce(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_too_many_shares() {
+        ShamirDealer::<TestPrimeField>::new(SEED, 2).deal(&TestPrimeField::from_u64(5).unwrap(), 7);
+    }
+}
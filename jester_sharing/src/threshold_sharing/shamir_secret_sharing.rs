@@ -1,11 +1,12 @@
-use crate::{CryptoRng, RngCore};
-use num::pow::pow;
-use num::{BigUint, FromPrimitive};
-use num_bigint::RandBigInt;
+use crate::FieldRng;
+use std::collections::HashSet;
 
-use crate::PrimeField;
+use crate::{BigUint, PrimeField};
 
-use crate::{LinearSharingScheme, ThresholdSecretSharingScheme};
+use crate::{
+    LinearSharingScheme, ReconstructionException, ShareGenerationException,
+    ThresholdSecretSharingScheme,
+};
 
 /// A trait marking a special instance of a additive linear threshold secret sharing scheme invented by Adi Shamir. A
 /// protocol implementing this trait does not have to provide implementations for `ThresholdSecretSharingScheme` nor
@@ -13,9 +14,83 @@ use crate::{LinearSharingScheme, ThresholdSecretSharingScheme};
 pub trait ShamirSecretSharingScheme<T>:
     ThresholdSecretSharingScheme<T, (usize, T)> + LinearSharingScheme<T, (usize, T)>
 {
-    // this is a marker trait
+    /// Generate a random polynomial `f` with `f(0) = secret` and evaluate it at the given `points`, so participants
+    /// can hold shares at fixed indices, like long-term party ids, instead of the indices `1..=count` used by
+    /// `generate_shares`.
+    /// # Parameters
+    /// - `rng` a cryptographically secure random number generator.
+    /// - `secret` an instance of `T`
+    /// - `points` the indices of the shares. They are compared as field elements, so they must be pairwise distinct
+    ///   and nonzero modulo the field's prime.
+    /// - `threshold` how many shares are required to reconstruct the secret
+    ///
+    /// # Returns
+    /// One share per point in the order of `points`, or a `ShareGenerationException` if a point is zero or used
+    /// twice.
+    ///
+    /// # Panics
+    /// If `threshold` is less than two.
+    fn generate_shares_at<R>(
+        rng: &mut R,
+        secret: &T,
+        points: &[usize],
+        threshold: usize,
+    ) -> Result<Vec<(usize, T)>, ShareGenerationException>
+    where
+        R: FieldRng,
+        T: PrimeField,
+    {
+        assert!(threshold > 1);
+
+        let x_values = points
+            .iter()
+            .map(|point| T::from(BigUint::from(*point)))
+            .collect::<Vec<_>>();
+
+        if let Some((point, _)) = points.iter().zip(&x_values).find(|(_, x)| x.is_zero()) {
+            return Err(ShareGenerationException::InvalidSharePoint { point: *point });
+        }
+
+        let mut distinct_x_values = HashSet::new();
+        if let Some((point, _)) = points
+            .iter()
+            .zip(&x_values)
+            .find(|(_, x)| !distinct_x_values.insert(x.as_uint()))
+        {
+            return Err(ShareGenerationException::DuplicateSharePoint { point: *point });
+        }
+
+        // coefficients of the monomials x^1 to x^(threshold - 1). The constant coefficient is the secret itself.
+        let coefficients: Vec<T> = (1..threshold)
+            .map(|_| rng.next_field_element("ShamirSecretSharingScheme"))
+            .collect();
+
+        Ok(points
+            .iter()
+            .zip(x_values)
+            .map(|(point, x_value)| {
+                // evaluate the polynomial using Horner's method, so no powers of `x` must be computed
+                (
+                    *point,
+                    coefficients
+                        .iter()
+                        .rev()
+                        .fold(T::zero(), |acc, coefficient| {
+                            (acc + coefficient.clone()) * x_value.clone()
+                        })
+                        + secret.clone(),
+                )
+            })
+            .collect())
+    }
 }
 
+/// Shamir's secret sharing scheme on its own, for uses that do not require a protocol, like selecting the scheme at
+/// runtime as a `DynSharingScheme`.
+pub struct ShamirSecretSharing;
+
+impl<T> ShamirSecretSharingScheme<T> for ShamirSecretSharing where T: PrimeField {}
+
 /// Shamir's secret sharing scheme is linear for addition. Addition implemented by simply delegating the calls to `T`
 impl<T, P> LinearSharingScheme<T, (usize, T)> for P
 where
@@ -66,6 +141,160 @@ where
             ))
         }
     }
+
+    fn neg_share(share: &(usize, T)) -> (usize, T) {
+        (share.0, share.1.neg())
+    }
+
+    /// Evaluates the combination on the share values directly, without creating an intermediate share per summand.
+    /// Returns `None` as well, if the shares have different indices, as they cannot be combined then.
+    fn linear_combination(shares: &[(usize, T)], coefficients: &[T]) -> Option<(usize, T)> {
+        if shares.is_empty() || shares.len() != coefficients.len() {
+            return None;
+        }
+
+        let x = shares[0].0;
+        if shares.iter().any(|(index, _)| *index != x) {
+            return None;
+        }
+
+        Some((
+            x,
+            shares
+                .iter()
+                .zip(coefficients)
+                .fold(T::zero(), |acc, ((_, y), coefficient)| {
+                    acc + y.clone() * coefficient.clone()
+                }),
+        ))
+    }
+}
+
+/// Precomputed Lagrange coefficients for the interpolation of a secret at zero from a fixed set of share indices.
+/// Computing the coefficients requires one inversion per index, which is expensive, so services that repeatedly
+/// reconstruct secrets from the same set of participants should build the basis only once and reuse it.
+#[derive(Clone, Debug)]
+pub struct LagrangeBasis<T>
+where
+    T: PrimeField,
+{
+    coefficients: Vec<(usize, T)>,
+}
+
+impl<T> LagrangeBasis<T>
+where
+    T: PrimeField,
+{
+    /// Compute the Lagrange coefficients for the given share indices.
+    /// # Parameters
+    /// - `indices` the indices of all shares that will be used for interpolation. They must be pairwise distinct and
+    ///   there must be exactly as many indices as the reconstruction threshold of the shared secrets.
+    ///
+    /// # Returns
+    /// The basis for the given indices or a `ReconstructionException` if `indices` is empty or contains duplicates.
+    /// Indices are compared as field elements, so two indices that are congruent modulo the field's prime are
+    /// duplicates, and an index that is a multiple of the prime is invalid.
+    pub fn new(indices: &[usize]) -> Result<Self, ReconstructionException> {
+        Self::with_evaluation_point(indices, T::zero())
+    }
+
+    /// Compute the Lagrange coefficients for evaluating the polynomial at `point` instead of zero. Interpolating
+    /// shares with such a basis yields the share a participant with index `point` would hold, which can be used to
+    /// verify additional shares.
+    /// # Parameters
+    /// - `indices` the indices of all shares that will be used for interpolation, see `new`
+    /// - `point` the index at which the polynomial is evaluated
+    pub fn new_at(indices: &[usize], point: usize) -> Result<Self, ReconstructionException> {
+        Self::with_evaluation_point(indices, T::from(BigUint::from(point)))
+    }
+
+    fn with_evaluation_point(indices: &[usize], point: T) -> Result<Self, ReconstructionException> {
+        if indices.is_empty() {
+            return Err(ReconstructionException::ZeroThreshold {});
+        }
+
+        let x_values = indices
+            .iter()
+            .map(|index| T::from(BigUint::from(*index)))
+            .collect::<Vec<_>>();
+
+        if let Some((index, _)) = indices.iter().zip(&x_values).find(|(_, x)| x.is_zero()) {
+            return Err(ReconstructionException::InvalidShareIndex { index: *index });
+        }
+
+        let mut distinct_x_values = HashSet::new();
+        if let Some((index, _)) = indices
+            .iter()
+            .zip(&x_values)
+            .find(|(_, x)| !distinct_x_values.insert(x.as_uint()))
+        {
+            return Err(ReconstructionException::DuplicateShareIndex { index: *index });
+        }
+
+        let coefficients = indices
+            .iter()
+            .zip(&x_values)
+            .map(|(i, x_i)| {
+                (
+                    *i,
+                    x_values
+                        .iter()
+                        .filter(|x_j| x_i != *x_j)
+                        .map(|x_j| {
+                            // the numerator `point - x_j` intentionally wraps around the prime. The denominator
+                            // `x_i - x_j` is built from the absolute difference, so it wraps only if `x_i < x_j`.
+                            let numerator = point.clone() - x_j.clone();
+                            let denominator = x_i
+                                .checked_sub(x_j)
+                                .unwrap_or_else(|| x_j.abs_diff(x_i).neg());
+                            numerator.mul(denominator.inverse())
+                        })
+                        .product(),
+                )
+            })
+            .collect();
+
+        Ok(LagrangeBasis { coefficients })
+    }
+
+    /// Returns the Lagrange coefficient of the share with the given `index` or `None` if the index is not part of
+    /// this basis.
+    pub fn coefficient(&self, index: usize) -> Option<&T> {
+        self.coefficients
+            .iter()
+            .find(|(i, _)| *i == index)
+            .map(|(_, coefficient)| coefficient)
+    }
+
+    /// Interpolate the secret from a set of shares. The shares may be given in any order, but their indices must be
+    /// exactly the indices this basis was built for.
+    ///
+    /// # Returns
+    /// The interpolated secret or `ReconstructionException::MismatchedShareIndices` if the share indices do not match
+    /// the basis
+    pub fn interpolate(&self, shares: &[(usize, T)]) -> Result<T, ReconstructionException> {
+        if shares.len() != self.coefficients.len() {
+            return Err(ReconstructionException::MismatchedShareIndices {});
+        }
+
+        // keep track of used coefficients, so duplicate share indices are detected
+        let mut used_coefficients = vec![false; self.coefficients.len()];
+        let mut secret = T::zero();
+
+        for (index, share) in shares {
+            let position = self
+                .coefficients
+                .iter()
+                .position(|(i, _)| i == index)
+                .filter(|position| !used_coefficients[*position])
+                .ok_or(ReconstructionException::MismatchedShareIndices {})?;
+
+            used_coefficients[position] = true;
+            secret = secret + share.clone() * self.coefficients[position].1.clone();
+        }
+
+        Ok(secret)
+    }
 }
 
 impl<T, P> ThresholdSecretSharingScheme<T, (usize, T)> for P
@@ -75,7 +304,7 @@ where
 {
     /// Generate a random polynomial `f` and `count` solutions `sn = f(n)` where `n != 0` as shares. The secret is
     /// the solution `secret = f(0)` of the polynomial and each share is the solution of `f(i)` where `i - 1` is the
-    /// index within the returned vector.
+    /// index within the returned vector, so this is `generate_shares_at` with the points `1..=count`.
     /// # Parameters
     /// - `rng` a cryptographically secure random number generator.
     /// - `secret` an instance of `T`
@@ -84,6 +313,10 @@ where
     ///
     /// # Returns
     /// Returns a vector of `count` shares
+    ///
+    /// # Panics
+    /// If `count` is not less than the field's prime, because share indices would repeat modulo the prime and the
+    /// share at the prime's index would be the secret itself.
     fn generate_shares<R>(
         rng: &mut R,
         secret: &T,
@@ -91,99 +324,380 @@ where
         threshold: usize,
     ) -> Vec<(usize, T)>
     where
-        R: RngCore + CryptoRng + RandBigInt,
+        R: FieldRng,
     {
-        assert!(threshold > 1);
-
-        let polynomial = (1..threshold)
-            .map(|i| (i, T::generate_random_member(rng)))
-            .collect::<Vec<_>>();
+        assert!(
+            T::from_usize(count).is_some_and(|c| c.as_uint() == BigUint::from(count)),
+            "cannot generate {} shares over the prime field of order {}, as the share indices would collide",
+            count,
+            T::field_prime().as_uint()
+        );
 
-        (1..=count)
-            .map(|x| {
-                (
-                    x,
-                    polynomial
-                        .clone()
-                        .iter()
-                        .fold(secret.clone(), |akk, (index, val)| {
-                            akk + val.clone() * BigUint::from_usize(pow(x, *index)).unwrap().into()
-                        }),
-                )
-            })
-            .collect()
+        P::generate_shares_at(rng, secret, &(1..=count).collect::<Vec<_>>(), threshold)
+            .expect("consecutive share indices below the prime are distinct and nonzero")
     }
 
-    /// Interpolates the secret using the Lagrange interpolation method.
+    /// Interpolates the secret using the Lagrange interpolation method. Only the first `threshold` shares are used
+    /// for interpolation, all other shares are ignored.
     /// # Parameters
-    /// - `shares` a collection of at least `threshold` shares
+    /// - `shares` a collection of at least `threshold` shares with pairwise distinct indices
     /// - `threshold` the original threshold the shares were generated upon. This may be less than the actual number
     /// of shares given, but it must be the same value as during generation
     ///
     /// # Returns
     /// Given that `threshold` matches the threshold at generation and enough shares are present, it will return an
-    /// instance of `T` that is reconstructed from the shares
-    fn reconstruct_secret(shares: &[(usize, T)], threshold: usize) -> T {
-        shares
+    /// instance of `T` that is reconstructed from the shares. If `threshold` is zero, less than `threshold` shares
+    /// are given or two shares have the same index, a `ReconstructionException` is returned.
+    fn reconstruct_secret(
+        shares: &[(usize, T)],
+        threshold: usize,
+    ) -> Result<T, ReconstructionException> {
+        if threshold == 0 {
+            return Err(ReconstructionException::ZeroThreshold {});
+        }
+
+        if shares.len() < threshold {
+            return Err(ReconstructionException::NotEnoughShares {
+                required: threshold,
+                given: shares.len(),
+            });
+        }
+
+        let mut indices = HashSet::new();
+        if let Some((index, _)) = shares.iter().find(|(index, _)| !indices.insert(*index)) {
+            return Err(ReconstructionException::DuplicateShareIndex { index: *index });
+        }
+
+        // the lagrange basis polynomials must only be built from the shares used for interpolation, otherwise
+        // they would belong to a polynomial of higher degree
+        let selected_shares = &shares[..threshold];
+        let indices = selected_shares
             .iter()
-            .take(threshold)
-            .map(|(i, share)| {
-                share.clone().mul(
-                    shares
-                        .iter()
-                        .filter(|(j, _)| *i != *j)
-                        .map(|(j, _)| {
-                            T::from_isize(-(*j as isize))
-                                .unwrap()
-                                .mul(T::from_isize(*i as isize - *j as isize).unwrap().inverse())
-                        })
-                        .product(),
-                )
-            })
-            .sum()
+            .map(|(index, _)| *index)
+            .collect::<Vec<_>>();
+
+        LagrangeBasis::new(&indices)?.interpolate(selected_shares)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use jester_maths::prime::{Mersenne127Fast, Mersenne3, Mersenne89};
     use num::{FromPrimitive, One};
-    use rand::thread_rng;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
 
     use super::*;
     use crate::test_implementations::*;
 
     impl ShamirSecretSharingScheme<TestPrimeField> for TestProtocol {}
 
+    impl ShamirSecretSharingScheme<Mersenne3> for TestProtocol {}
+
+    impl ShamirSecretSharingScheme<Mersenne89> for TestProtocol {}
+
+    impl ShamirSecretSharingScheme<Mersenne127Fast> for TestProtocol {}
+
     #[test]
     fn test_generator() {
-        let shares = TestProtocol::generate_shares(&mut thread_rng(), &TestPrimeField::one(), 5, 5);
+        let shares = TestProtocol::generate_shares(
+            &mut StdRng::seed_from_u64(1),
+            &TestPrimeField::one(),
+            5,
+            5,
+        );
         assert_eq!(shares.len(), 5)
     }
 
     #[test]
     fn test_reconstruction() {
         let shares = TestProtocol::generate_shares(
-            &mut thread_rng(),
+            &mut StdRng::seed_from_u64(2),
             &TestPrimeField::from_usize(3).unwrap(),
             5,
             5,
         );
         assert_eq!(
-            TestProtocol::reconstruct_secret(&shares, 5),
+            TestProtocol::reconstruct_secret(&shares, 5).unwrap(),
             TestPrimeField::from_usize(3).unwrap()
         );
     }
 
+    #[test]
+    fn test_reconstruction_excess_shares() {
+        let secret = Mersenne89::from_usize(1337).unwrap();
+        let shares = TestProtocol::generate_shares(&mut StdRng::seed_from_u64(3), &secret, 5, 3);
+
+        assert_eq!(
+            TestProtocol::reconstruct_secret(&shares, 3).unwrap(),
+            secret
+        );
+        assert_eq!(
+            TestProtocol::reconstruct_secret(&shares[2..], 3).unwrap(),
+            secret
+        );
+    }
+
+    #[test]
+    fn test_reconstruction_invalid_input() {
+        let shares = TestProtocol::generate_shares(
+            &mut StdRng::seed_from_u64(4),
+            &Mersenne89::from_usize(1337).unwrap(),
+            5,
+            3,
+        );
+
+        assert_eq!(
+            TestProtocol::reconstruct_secret(&shares, 0),
+            Err(ReconstructionException::ZeroThreshold {})
+        );
+        assert_eq!(
+            TestProtocol::reconstruct_secret(&shares[..2], 3),
+            Err(ReconstructionException::NotEnoughShares {
+                required: 3,
+                given: 2
+            })
+        );
+
+        let duplicate_shares = vec![shares[0].clone(), shares[1].clone(), shares[1].clone()];
+        assert_eq!(
+            TestProtocol::reconstruct_secret(&duplicate_shares, 3),
+            Err(ReconstructionException::DuplicateShareIndex { index: 2 })
+        );
+    }
+
+    /// Shares with indices larger than the prime are evaluations at the index modulo the prime.
+    #[test]
+    fn test_reconstruction_indices_exceeding_prime() {
+        // f(x) = 4 + 3x over the field with p = 7
+        let f = |x: usize| {
+            TestPrimeField::from_usize(4).unwrap()
+                + TestPrimeField::from_usize(3).unwrap() * TestPrimeField::from_usize(x).unwrap()
+        };
+        let share = |x: usize| (x, f(x));
+
+        assert_eq!(
+            TestProtocol::reconstruct_secret(&[share(1), share(10)], 2),
+            Ok(TestPrimeField::from_usize(4).unwrap())
+        );
+        assert_eq!(
+            TestProtocol::reconstruct_secret(&[share(12), share(10)], 2),
+            Ok(TestPrimeField::from_usize(4).unwrap())
+        );
+
+        // 10 and 3 are the same index modulo 7
+        assert_eq!(
+            TestProtocol::reconstruct_secret(&[share(3), share(10)], 2),
+            Err(ReconstructionException::DuplicateShareIndex { index: 10 })
+        );
+        assert_eq!(
+            TestProtocol::reconstruct_secret(&[share(1), share(14)], 2),
+            Err(ReconstructionException::InvalidShareIndex { index: 14 })
+        );
+    }
+
+    #[test]
+    fn test_generator_at_points() {
+        let secret = Mersenne89::from_usize(1337).unwrap();
+        let shares = TestProtocol::generate_shares_at(
+            &mut StdRng::seed_from_u64(9),
+            &secret,
+            &[3, 17, 40],
+            3,
+        )
+        .unwrap();
+
+        assert_eq!(
+            shares.iter().map(|(index, _)| *index).collect::<Vec<_>>(),
+            vec![3, 17, 40]
+        );
+        assert_eq!(TestProtocol::reconstruct_secret(&shares, 3), Ok(secret));
+    }
+
+    #[test]
+    fn test_generator_invalid_points() {
+        let mut rng = StdRng::seed_from_u64(10);
+        let secret = TestPrimeField::one();
+
+        assert_eq!(
+            TestProtocol::generate_shares_at(&mut rng, &secret, &[3, 0, 40], 2),
+            Err(ShareGenerationException::InvalidSharePoint { point: 0 })
+        );
+        assert_eq!(
+            TestProtocol::generate_shares_at(&mut rng, &secret, &[3, 5, 3], 2),
+            Err(ShareGenerationException::DuplicateSharePoint { point: 3 })
+        );
+
+        // 10 and 3 are the same point modulo 7, and 14 is zero modulo 7
+        assert_eq!(
+            TestProtocol::generate_shares_at(&mut rng, &secret, &[3, 10], 2),
+            Err(ShareGenerationException::DuplicateSharePoint { point: 10 })
+        );
+        assert_eq!(
+            TestProtocol::generate_shares_at(&mut rng, &secret, &[14], 2),
+            Err(ShareGenerationException::InvalidSharePoint { point: 14 })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot generate 8 shares over the prime field of order 7")]
+    fn test_generator_too_many_shares() {
+        TestProtocol::generate_shares(&mut StdRng::seed_from_u64(7), &Mersenne3::one(), 8, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot generate 7 shares")]
+    fn test_generator_share_at_prime() {
+        TestProtocol::generate_shares(&mut StdRng::seed_from_u64(7), &Mersenne3::one(), 7, 3);
+    }
+
+    /// The largest possible number of shares in a small field can still be reconstructed from any subset.
+    #[test]
+    fn test_reconstruction_small_field() {
+        let secret = Mersenne3::from_usize(5).unwrap();
+        let shares = TestProtocol::generate_shares(&mut StdRng::seed_from_u64(8), &secret, 6, 3);
+        assert_eq!(shares.len(), 6);
+
+        for offset in 0..4 {
+            assert_eq!(
+                TestProtocol::reconstruct_secret(&shares[offset..offset + 3], 3),
+                Ok(secret.clone())
+            );
+        }
+        assert_eq!(TestProtocol::reconstruct_secret(&shares, 3), Ok(secret));
+    }
+
+    /// Test, whether shares are still correct if `count ^ threshold` exceeds the range of `usize`
+    #[test]
+    fn test_reconstruction_many_shares() {
+        let secret = Mersenne89::from_usize(424_242).unwrap();
+        let shares = TestProtocol::generate_shares(&mut StdRng::seed_from_u64(5), &secret, 200, 12);
+        assert_eq!(shares.len(), 200);
+
+        assert_eq!(
+            TestProtocol::reconstruct_secret(&shares[188..], 12).unwrap(),
+            secret
+        );
+    }
+
+    #[test]
+    fn test_lagrange_basis() {
+        let secret = Mersenne89::from_usize(4711).unwrap();
+        let shares = TestProtocol::generate_shares(&mut StdRng::seed_from_u64(6), &secret, 6, 4);
+
+        let basis = LagrangeBasis::<Mersenne89>::new(&[6, 2, 3, 5]).unwrap();
+        let selected_shares = vec![
+            shares[4].clone(),
+            shares[5].clone(),
+            shares[1].clone(),
+            shares[2].clone(),
+        ];
+
+        // the coefficient of share `i` is the product of `-j / (i - j)` for all other indices `j`
+        let expected_coefficient = Mersenne89::from_signed(-2)
+            * Mersenne89::from_signed(-3)
+            * Mersenne89::from_signed(-5)
+            * (Mersenne89::from_signed(4)
+                * Mersenne89::from_signed(3)
+                * Mersenne89::from_signed(1))
+            .inverse();
+        assert_eq!(basis.coefficient(6), Some(&expected_coefficient));
+        assert_eq!(basis.coefficient(1), None);
+
+        assert_eq!(basis.interpolate(&selected_shares).unwrap(), secret);
+        assert_eq!(
+            basis.interpolate(&selected_shares).unwrap(),
+            TestProtocol::reconstruct_secret(&selected_shares, 4).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_lagrange_basis_at_point() {
+        let shares = TestProtocol::generate_shares(
+            &mut StdRng::seed_from_u64(9),
+            &Mersenne89::from_usize(1234).unwrap(),
+            6,
+            3,
+        );
+
+        // interpolating the first three shares yields the remaining shares
+        let indices = [1, 2, 3];
+        for (index, share) in &shares[3..] {
+            let basis = LagrangeBasis::<Mersenne89>::new_at(&indices, *index).unwrap();
+            assert_eq!(basis.interpolate(&shares[..3]), Ok(share.clone()));
+        }
+    }
+
+    /// Test, whether indices that are not representable as `i64` are lifted into the field correctly
+    #[test]
+    fn test_reconstruction_large_indices() {
+        let secret = Mersenne89::from_usize(2020).unwrap();
+        let points = [usize::MAX, usize::MAX - 1, 1 << 63, 7];
+        let shares =
+            TestProtocol::generate_shares_at(&mut StdRng::seed_from_u64(12), &secret, &points, 3)
+                .unwrap();
+
+        assert_eq!(
+            TestProtocol::reconstruct_secret(&shares, 3),
+            Ok(secret.clone())
+        );
+        assert_eq!(
+            TestProtocol::reconstruct_secret(&shares[1..], 3),
+            Ok(secret)
+        );
+        let basis = LagrangeBasis::<Mersenne89>::new_at(&points[..3], usize::MAX - 2).unwrap();
+        assert!(basis.interpolate(&shares[..3]).is_ok());
+    }
+
+    #[test]
+    fn test_reconstruction_mersenne127_fast() {
+        let secret = Mersenne127Fast::from_usize(2021).unwrap();
+        let shares = TestProtocol::generate_shares(&mut StdRng::seed_from_u64(13), &secret, 5, 3);
+
+        assert_eq!(
+            TestProtocol::reconstruct_secret(&shares[2..], 3),
+            Ok(secret)
+        );
+    }
+
+    #[test]
+    fn test_lagrange_basis_mismatch() {
+        let shares = TestProtocol::generate_shares(
+            &mut StdRng::seed_from_u64(7),
+            &Mersenne89::from_usize(4711).unwrap(),
+            4,
+            3,
+        );
+        let basis = LagrangeBasis::<Mersenne89>::new(&[1, 2, 3]).unwrap();
+
+        assert_eq!(
+            basis.interpolate(&shares[1..]),
+            Err(ReconstructionException::MismatchedShareIndices {})
+        );
+        assert_eq!(
+            basis.interpolate(&shares[..2]),
+            Err(ReconstructionException::MismatchedShareIndices {})
+        );
+        assert_eq!(
+            basis.interpolate(&[shares[0].clone(), shares[1].clone(), shares[1].clone()]),
+            Err(ReconstructionException::MismatchedShareIndices {})
+        );
+        assert_eq!(
+            LagrangeBasis::<Mersenne89>::new(&[1, 2, 1]).unwrap_err(),
+            ReconstructionException::DuplicateShareIndex { index: 1 }
+        );
+    }
+
     #[test]
     fn test_linearity() {
         let shares = TestProtocol::generate_shares(
-            &mut thread_rng(),
+            &mut StdRng::seed_from_u64(8),
             &TestPrimeField::from_usize(2).unwrap(),
             2,
             2,
         );
         let shares_2 = TestProtocol::generate_shares(
-            &mut thread_rng(),
+            &mut StdRng::seed_from_u64(9),
             &TestPrimeField::from_usize(3).unwrap(),
             2,
             2,
@@ -196,8 +710,51 @@ mod tests {
             .collect();
 
         assert_eq!(
-            TestProtocol::reconstruct_secret(&addition, 2),
+            TestProtocol::reconstruct_secret(&addition, 2).unwrap(),
             TestPrimeField::from_usize(5).unwrap()
         );
     }
+
+    #[test]
+    fn test_linear_combination() {
+        let field = |value| TestPrimeField::from_usize(value).unwrap();
+        let shares = vec![(1, field(2)), (1, field(3)), (1, field(5))];
+        let coefficients = vec![field(3), field(4), field(6)];
+
+        // 3 * 2 + 4 * 3 + 6 * 5 = 48 = 6 mod 7
+        assert_eq!(
+            TestProtocol::linear_combination(&shares, &coefficients),
+            Some((1, field(6)))
+        );
+        assert_eq!(
+            TestProtocol::weighted_sum_with_constant(&shares, &coefficients, &field(2)),
+            Some((1, field(1)))
+        );
+        assert_eq!(TestProtocol::neg_share(&(1, field(3))), (1, field(4)));
+
+        assert_eq!(
+            TestProtocol::linear_combination(&shares, &coefficients[..2]),
+            None
+        );
+        assert_eq!(
+            TestProtocol::linear_combination(&[], &coefficients[..0]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_linear_combination_mixed_indices() {
+        let field = |value| TestPrimeField::from_usize(value).unwrap();
+        let shares = vec![(1, field(2)), (2, field(3)), (1, field(5))];
+        let coefficients = vec![field(3), field(4), field(6)];
+
+        assert_eq!(
+            TestProtocol::linear_combination(&shares, &coefficients),
+            None
+        );
+        assert_eq!(
+            TestProtocol::weighted_sum_with_constant(&shares, &coefficients, &field(2)),
+            None
+        );
+    }
 }
@@ -0,0 +1,226 @@
+//! Small utilities shared by the crates of the workspace, which do not belong to any of the cryptographic primitives.
+
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+/// A wrapper around secret values like keys, whose `Debug` and `Display` output is `<redacted>` instead of the
+/// value. It dereferences to the wrapped value, so it can be used in place of it, but a `{:?}` of a struct containing
+/// it does not leak the secret into logs or panic messages.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Redacted<T>(T);
+
+impl<T> Redacted<T> {
+    /// Wrap the secret `value`.
+    pub const fn new(value: T) -> Self {
+        Redacted(value)
+    }
+
+    /// Unwrap the secret value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Redacted<T> {
+    fn from(value: T) -> Self {
+        Redacted(value)
+    }
+}
+
+impl<T> Deref for Redacted<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Redacted<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> fmt::Debug for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+impl<T> fmt::Display for Redacted<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<redacted>")
+    }
+}
+
+/// Declare a set of flags that describes which algorithms and optional features a crate was compiled with. Every
+/// flag is an associated constant of a single bit, and a set of flags is their union:
+///
+/// ```
+/// jester_util::capabilities! {
+///     /// The ciphers of a build
+///     pub struct Ciphers {
+///         const AES = 0, "aes";
+///         const RSA = 1, "rsa";
+///     }
+/// }
+///
+/// let ciphers = Ciphers::AES.with(Ciphers::RSA, cfg!(feature = "rsa"));
+/// assert!(ciphers.contains(Ciphers::AES));
+/// assert_eq!(ciphers.names(), vec!["aes"]);
+/// ```
+///
+/// The name of a flag is the name of the algorithm or the feature it stands for, as it is reported by `names` and
+/// the `Debug` output.
+#[macro_export]
+macro_rules! capabilities {
+    (
+        $(#[$attribute:meta])*
+        $visibility:vis struct $name:ident {
+            $(
+                $(#[$flag_attribute:meta])*
+                const $flag:ident = $bit:expr, $flag_name:expr;
+            )*
+        }
+    ) => {
+        $(#[$attribute])*
+        #[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+        $visibility struct $name(u32);
+
+        impl $name {
+            $(
+                $(#[$flag_attribute])*
+                pub const $flag: $name = $name(1 << $bit);
+            )*
+
+            /// All flags together with their names, in the order of their declaration
+            const FLAGS: &'static [($name, &'static str)] = &[$(($name::$flag, $flag_name)),*];
+
+            /// The set without any flags
+            pub const fn empty() -> Self {
+                $name(0)
+            }
+
+            /// The set of all declared flags
+            pub const fn all() -> Self {
+                $name(0 $(| 1 << $bit)*)
+            }
+
+            /// The flags as a bit mask
+            pub const fn bits(self) -> u32 {
+                self.0
+            }
+
+            /// Whether all flags of `other` are contained in this set
+            pub const fn contains(self, other: Self) -> bool {
+                self.0 & other.0 == other.0
+            }
+
+            /// The union of this set and `other`
+            pub const fn union(self, other: Self) -> Self {
+                $name(self.0 | other.0)
+            }
+
+            /// The union of this set and `flags`, if `enabled` is true. Meant to be called with
+            /// `cfg!(feature = ...)`.
+            pub const fn with(self, flags: Self, enabled: bool) -> Self {
+                if enabled {
+                    self.union(flags)
+                } else {
+                    self
+                }
+            }
+
+            /// The names of the flags in this set, in the order of their declaration
+            pub fn names(self) -> ::std::vec::Vec<&'static str> {
+                $name::FLAGS.iter().filter(|(flag, _)| self.contains(*flag)).map(|(_, name)| *name).collect()
+            }
+        }
+
+        impl ::std::ops::BitOr for $name {
+            type Output = $name;
+
+            fn bitor(self, rhs: $name) -> $name {
+                self.union(rhs)
+            }
+        }
+
+        impl ::std::ops::BitOrAssign for $name {
+            fn bitor_assign(&mut self, rhs: $name) {
+                *self = self.union(rhs);
+            }
+        }
+
+        impl ::std::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                write!(f, "{}({})", stringify!($name), self.names().join(" | "))
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Keyed {
+        key: Redacted<Vec<u8>>,
+    }
+
+    #[test]
+    fn test_formatting_redacted() {
+        let secret = Redacted::new(vec![0xAB_u8, 0xCD, 0xEF]);
+
+        assert_eq!(format!("{:?}", secret), "<redacted>");
+        assert_eq!(format!("{:#?}", secret), "<redacted>");
+        assert_eq!(format!("{}", secret), "<redacted>");
+        assert_eq!(format!("{}", Redacted::new(4711)), "<redacted>");
+
+        let keyed = Keyed { key: secret };
+        let debug = format!("{:?}", keyed);
+        assert_eq!(debug, "Keyed { key: <redacted> }");
+        for byte in keyed.key.iter() {
+            assert!(!debug.contains(&format!("{:x}", byte)));
+            assert!(!debug.contains(&format!("{:X}", byte)));
+            assert!(!debug.contains(&format!("{}", byte)));
+        }
+    }
+
+    #[test]
+    fn test_deref() {
+        let mut secret = Redacted::from(vec![1u8, 2, 3]);
+        assert_eq!(secret.len(), 3);
+
+        secret.push(4);
+        assert_eq!(&secret[..], &[1, 2, 3, 4]);
+        assert_eq!(secret.into_inner(), vec![1, 2, 3, 4]);
+    }
+    capabilities! {
+        struct TestCapabilities {
+            const FIRST = 0, "first";
+            const SECOND = 1, "second";
+            const THIRD = 4, "third";
+        }
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let capabilities = TestCapabilities::FIRST | TestCapabilities::THIRD;
+        assert_eq!(capabilities.bits(), 0b10001);
+        assert!(capabilities.contains(TestCapabilities::FIRST));
+        assert!(!capabilities.contains(TestCapabilities::SECOND));
+        assert!(!capabilities.contains(TestCapabilities::FIRST | TestCapabilities::SECOND));
+        assert!(capabilities.contains(TestCapabilities::empty()));
+        assert!(TestCapabilities::all().contains(capabilities));
+
+        assert_eq!(capabilities.names(), vec!["first", "third"]);
+        assert_eq!(format!("{:?}", capabilities), "TestCapabilities(first | third)");
+        assert_eq!(format!("{:?}", TestCapabilities::empty()), "TestCapabilities()");
+
+        let mut built = TestCapabilities::default().with(TestCapabilities::FIRST, true);
+        built = built.with(TestCapabilities::SECOND, false);
+        built |= TestCapabilities::THIRD;
+        assert_eq!(built, capabilities);
+    }
+}